@@ -0,0 +1,243 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A complete little chess program made only from the engine's library APIs.
+//!
+//! Moves come from the legal move generator, the engine's moves from [`Searcher`] over
+//! [`evaluate`], and the game is kept in a [`GameData`] so [`GameData::status`] can tell the end of
+//! the game from its history. Draws by the fifty-move rule or threefold repetition are claimed as
+//! soon as they can be.
+//!
+//! Usage:
+//! - `cargo run --example simple_engine`: you play White against the engine
+//! - `cargo run --example simple_engine -- --engine-vs-engine`
+//! - `cargo run --example simple_engine -- --depth 6`
+
+use std::{env, io};
+
+use lumifox::{
+  eval::evaluate,
+  search::{MATE_SCORE, MAX_PLY, SearchConfig, Searcher},
+};
+use lumifox_chess::model::{
+  gameboard::GameBoard,
+  gamedata::GameData,
+  piecemove::PieceMove,
+  status::{DrawReason, GameStatus},
+};
+
+/// Depth searched when `--depth` is not given.
+const DEFAULT_DEPTH: u32 = 4;
+
+/// How the game ended, or `None` while it goes on. Draws that could be claimed end the game
+/// too, as if the player to move had claimed them.
+fn describe_ending(status: GameStatus) -> Option<&'static str> {
+  let reason = match status {
+    GameStatus::Ongoing => return None,
+    GameStatus::Checkmate { white_wins: true } => return Some("Checkmate, White wins."),
+    GameStatus::Checkmate { white_wins: false } => return Some("Checkmate, Black wins."),
+    GameStatus::VariantWin { white_wins: true } => return Some("White wins."),
+    GameStatus::VariantWin { white_wins: false } => return Some("Black wins."),
+    GameStatus::ForcedDraw(reason) | GameStatus::ClaimableDraw(reason) => reason,
+  };
+  Some(match reason {
+    DrawReason::Stalemate => "Stalemate, a draw.",
+    DrawReason::InsufficientMaterial => "Insufficient material, a draw.",
+    DrawReason::FiftyMoves => "Fifty moves without progress, a draw is claimed.",
+    DrawReason::ThreefoldRepetition => "Threefold repetition, a draw is claimed.",
+    DrawReason::SeventyFiveMoves => "Seventy-five moves without progress, a draw.",
+    DrawReason::FivefoldRepetition => "Fivefold repetition, a draw.",
+  })
+}
+
+/// Searches a fixed depth and plays what it finds.
+struct SimpleEngine {
+  searcher: Searcher,
+  depth: u32,
+}
+
+impl SimpleEngine {
+  fn new(depth: u32) -> Self {
+    Self {
+      searcher: Searcher::new(SearchConfig {
+        deterministic: true,
+        ..Default::default()
+      }),
+      depth,
+    }
+  }
+
+  /// The move to play in `board` and its score for the side to move, or `None` if there is no
+  /// legal move.
+  fn best_move(&mut self, board: &GameBoard) -> Option<(PieceMove, i32)> {
+    let result = self.searcher.search(board, self.depth);
+    result.best_move.map(|best_move| (best_move, result.score))
+  }
+}
+
+/// The legal move of `game` written as `text` in UCI notation.
+fn parse_move(game: &GameData, text: &str) -> Option<PieceMove> {
+  let typed = PieceMove::from_uci(text, &game.board).ok()?;
+  game.board.legal_moves_iter().find(|candidate| {
+    candidate.from_square() == typed.from_square()
+      && candidate.to_square() == typed.to_square()
+      && candidate.promotion_type() == typed.promotion_type()
+  })
+}
+
+/// A score as the engine would report it: pawns, or the moves to a forced mate.
+fn describe_score(score: i32) -> String {
+  let plies = MATE_SCORE - score.abs();
+  if plies <= MAX_PLY as i32 {
+    let moves = (plies + 1) / 2;
+    return if score > 0 {
+      format!("mate in {moves}")
+    } else {
+      format!("mated in {moves}")
+    };
+  }
+  format!("{:+.2}", score as f64 / 100.0)
+}
+
+fn main() {
+  let args: Vec<String> = env::args().collect();
+  let engine_vs_engine = args.iter().any(|arg| arg == "--engine-vs-engine");
+  let depth = match args.iter().position(|arg| arg == "--depth") {
+    Some(index) => match args.get(index + 1).and_then(|value| value.parse().ok()) {
+      Some(depth) => depth,
+      None => {
+        eprintln!("--depth needs a positive number");
+        return;
+      }
+    },
+    None => DEFAULT_DEPTH,
+  };
+
+  let mut game = GameData::START_POS;
+  let mut engine = SimpleEngine::new(depth);
+  println!("Simple engine, searching {depth} plies.");
+  if !engine_vs_engine {
+    println!("You play White. Enter moves in UCI notation, such as e2e4 or e7e8q.");
+  }
+
+  loop {
+    game.print_board();
+    println!(
+      "Static evaluation: {}",
+      describe_score(evaluate(&game.board))
+    );
+    if let Some(ending) = describe_ending(game.status()) {
+      println!("{ending}");
+      break;
+    }
+
+    let piece_move = if engine_vs_engine || !game.board.playing {
+      let (piece_move, score) = engine
+        .best_move(&game.board)
+        .expect("a game that has not ended has a legal move");
+      println!("Engine plays {piece_move} ({})", describe_score(score));
+      piece_move
+    } else {
+      let mut input = String::new();
+      if io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+        break;
+      }
+      match parse_move(&game, input.trim()) {
+        Some(piece_move) => piece_move,
+        None => {
+          println!("{:?} is not a legal move here.", input.trim());
+          continue;
+        }
+      }
+    };
+    game
+      .play_move(&piece_move)
+      .expect("searched and parsed moves are legal");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lumifox_chess::constants::*;
+
+  fn game(fen: &str) -> GameData {
+    GameData::from_fen(fen).unwrap()
+  }
+
+  fn assert_finds(fen: &str, expected: PieceMove, mate_in: i32) {
+    let (best_move, score) = SimpleEngine::new(4).best_move(&game(fen).board).unwrap();
+    assert_eq!(best_move, expected, "{fen}");
+    assert_eq!(score, MATE_SCORE - (2 * mate_in - 1), "{fen}");
+  }
+
+  #[test]
+  fn test_mates_in_one() {
+    let suite = [
+      ("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1", E1, E8),
+      (
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        H5,
+        F7,
+      ),
+      ("r6k/8/8/8/8/8/5PPP/6K1 b - - 0 1", A8, A1),
+    ];
+    for (fen, from, to) in suite {
+      let capture = game(fen).board.get_piece(to).is_some();
+      assert_finds(fen, PieceMove::new(from, to, capture, None), 1);
+    }
+  }
+
+  #[test]
+  fn test_mates_in_two() {
+    // Morphy's mate and its mirror image with Black to move
+    assert_finds(
+      "kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1",
+      PieceMove::new(A1, A6, false, None),
+      2,
+    );
+    assert_finds(
+      "r7/8/8/8/8/1p6/PP6/KBk5 b - - 0 1",
+      PieceMove::new(A8, A3, false, None),
+      2,
+    );
+  }
+
+  #[test]
+  fn test_endings() {
+    assert_eq!(describe_ending(GameData::START_POS.status()), None);
+    assert_eq!(
+      describe_ending(game("R5k1/5ppp/8/8/8/8/8/K7 b - - 0 1").status()),
+      Some("Checkmate, White wins.")
+    );
+
+    // The claimable draw ends the game on the first threefold repetition
+    let mut shuffled = GameData::START_POS;
+    for _ in 0..2 {
+      for text in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+        assert_eq!(describe_ending(shuffled.status()), None);
+        let piece_move = parse_move(&shuffled, text).unwrap();
+        shuffled.play_move(&piece_move).unwrap();
+      }
+    }
+    assert_eq!(
+      describe_ending(shuffled.status()),
+      Some("Threefold repetition, a draw is claimed.")
+    );
+  }
+}
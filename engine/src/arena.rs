@@ -0,0 +1,618 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Engine-vs-engine matches, played concurrently.
+//!
+//! [`run_match`] plays two [`Player`]s, each a [`SearchConfig`] and a search depth, against each
+//! other on a pool of threads. Games come in pairs: both games of a pair start from the same
+//! opening of the book, with colours swapped, so neither player gains from a lopsided opening.
+//! Game `n` uses opening `(n / 2) % book.len()`, and the first player has white in the even games.
+//!
+//! Each worker runs its own [`Searcher`]s and fixed-depth searches do not depend on timing, so the
+//! games, and the PGN written for them, are the same however many threads play them. With a
+//! [`TimeControl`] the players search against a [`ClockState`] instead, budgeting each move with
+//! the [`TimeManager`], and a player whose flag falls loses on time. The PGN of
+//! each game is written whole, in game order, as soon as the games before it are done; the
+//! [`MatchResults`] are tallied as games finish and end with an Elo estimate and its error bars.
+//!
+//! Books are lists of starting positions: [`book_from_epd`] reads one position per line, and
+//! [`book_from_pgn`] takes the final position of each game, whose moves then start the PGN of
+//! every game played from it.
+
+use std::{
+  collections::BTreeMap,
+  fmt, io,
+  sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+  },
+  thread,
+  time::Instant,
+};
+
+use lumifox_chess::{
+  errors::{FenParseError, PgnError},
+  model::{
+    clock::{ClockState, TimeControl},
+    gamedata::GameData,
+    piecemove::PieceMove,
+    status::GameStatus,
+  },
+  pgn::{Eval, PgnGame, read_games},
+};
+
+use crate::search::{
+  MATE_SCORE, MAX_PLY, PvLine, SearchConfig, SearchListener, SearchResult, Searcher, TimeManager,
+};
+
+/// One side of a match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Player {
+  /// Name written to the `White` and `Black` tags.
+  pub name: String,
+  pub config: SearchConfig,
+  /// Depth of every search, or the deepest a search may go under a time control.
+  pub depth: u32,
+}
+
+/// How [`run_match`] plays a match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArenaConfig {
+  /// Pairs of games to play; each opening is played once with each colour.
+  pub pairs: usize,
+  /// Games played at the same time, one per thread. `0` is treated as `1`.
+  pub threads: usize,
+  /// A game still going after this many moves from its opening is adjudicated a draw.
+  pub max_plies: usize,
+  /// Written to the `Event` tag.
+  pub event: String,
+  /// Clock both players move against, or `None` for untimed games.
+  pub time_control: Option<TimeControl>,
+}
+
+impl Default for ArenaConfig {
+  fn default() -> Self {
+    Self {
+      pairs: 50,
+      threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+      max_plies: 400,
+      event: "Lumifox arena".to_string(),
+      time_control: None,
+    }
+  }
+}
+
+/// Wins, draws and losses of the first player of a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchResults {
+  pub wins: u32,
+  pub draws: u32,
+  pub losses: u32,
+}
+
+/// Standard normal quantile for a two-sided 95% interval.
+const Z_95: f64 = 1.959_964;
+
+impl MatchResults {
+  pub fn games(&self) -> u32 {
+    self.wins + self.draws + self.losses
+  }
+
+  /// Points per game, from 0 to 1, or 0.5 before any game is played.
+  pub fn score(&self) -> f64 {
+    match self.games() {
+      0 => 0.5,
+      games => (f64::from(self.wins) + f64::from(self.draws) / 2.0) / f64::from(games),
+    }
+  }
+
+  /// Elo difference between the first player and the second that the score implies. Infinite
+  /// once one side has every point.
+  pub fn elo(&self) -> f64 {
+    elo_difference(self.score())
+  }
+
+  /// Half the width of the 95% confidence interval of [`elo`](Self::elo), from the spread of the
+  /// individual game results. Infinite when the interval reaches a score of 0 or 1, and `0.0`
+  /// before any game is played.
+  pub fn elo_error(&self) -> f64 {
+    let games = self.games();
+    if games == 0 {
+      return 0.0;
+    }
+    let n = f64::from(games);
+    let score = self.score();
+    let variance = (f64::from(self.wins) * (1.0 - score).powi(2)
+      + f64::from(self.draws) * (0.5 - score).powi(2)
+      + f64::from(self.losses) * score.powi(2))
+      / n;
+    let margin = Z_95 * (variance / n).sqrt();
+    (elo_difference(score + margin) - elo_difference(score - margin)) / 2.0
+  }
+
+  fn record(&mut self, first_player_score: Option<bool>) {
+    match first_player_score {
+      Some(true) => self.wins += 1,
+      Some(false) => self.losses += 1,
+      None => self.draws += 1,
+    }
+  }
+}
+
+impl fmt::Display for MatchResults {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "+{} ={} -{} ({:.1}%), Elo {:+.1} +/- {:.1}",
+      self.wins,
+      self.draws,
+      self.losses,
+      self.score() * 100.0,
+      self.elo(),
+      self.elo_error()
+    )
+  }
+}
+
+fn elo_difference(score: f64) -> f64 {
+  if score <= 0.0 {
+    f64::NEG_INFINITY
+  } else if score >= 1.0 {
+    f64::INFINITY
+  } else {
+    -400.0 * (1.0 / score - 1.0).log10()
+  }
+}
+
+/// Read an EPD or FEN book, one position per line, in the formats
+/// [`GameData::from_fen_batch`] accepts. Fails with the line number, counting from 1, and error
+/// of the first bad line.
+pub fn book_from_epd(text: &str) -> Result<Vec<GameData>, (usize, FenParseError)> {
+  GameData::from_fen_batch(text.lines()).collect()
+}
+
+/// Read a PGN book: each game's final position becomes an opening, with its moves kept as the
+/// start of every game played from it.
+pub fn book_from_pgn(text: &str) -> Result<Vec<GameData>, PgnError> {
+  read_games(text)
+    .map(|game| game.map(|game| game.game))
+    .collect()
+}
+
+/// Play `config.pairs` pairs of games between `first` and `second` from the openings of `book`,
+/// or from the standard starting position if it is empty, and return the first player's results.
+///
+/// Every game is written to `pgn` as it becomes the next in order, separated by blank lines. A
+/// write error stops the match: the games already being played are finished, no new ones start,
+/// and the error is returned.
+pub fn run_match<W: io::Write + Send>(
+  first: &Player,
+  second: &Player,
+  book: &[GameData],
+  config: &ArenaConfig,
+  pgn: W,
+) -> io::Result<MatchResults> {
+  let start = [GameData::START_POS];
+  let book = if book.is_empty() { &start[..] } else { book };
+  let games = config.pairs.saturating_mul(2);
+  let next_game = AtomicUsize::new(0);
+  let failed = AtomicBool::new(false);
+  let results = Mutex::new(MatchResults::default());
+  let writer = Mutex::new(PgnWriter {
+    out: pgn,
+    next: 0,
+    pending: BTreeMap::new(),
+    error: None,
+  });
+
+  thread::scope(|scope| {
+    for _ in 0..config.threads.clamp(1, games.max(1)) {
+      scope.spawn(|| {
+        let mut searchers = [Searcher::new(first.config), Searcher::new(second.config)];
+        loop {
+          let index = next_game.fetch_add(1, Ordering::Relaxed);
+          if index >= games || failed.load(Ordering::Relaxed) {
+            break;
+          }
+          let first_white = index.is_multiple_of(2);
+          let opening = &book[(index / 2) % book.len()];
+          let (game, winner) = play_game(
+            [first, second],
+            &mut searchers,
+            opening,
+            first_white,
+            config,
+            index,
+          );
+          results
+            .lock()
+            .expect("no thread panics holding the results")
+            .record(winner.map(|white_wins| white_wins == first_white));
+
+          let mut writer = writer.lock().expect("no thread panics holding the writer");
+          writer.push(index, game.to_string());
+          if writer.error.is_some() {
+            failed.store(true, Ordering::Relaxed);
+          }
+        }
+      });
+    }
+  });
+
+  let writer = writer
+    .into_inner()
+    .expect("no thread panics holding the writer");
+  match writer.error {
+    Some(error) => Err(error),
+    None => Ok(
+      results
+        .into_inner()
+        .expect("no thread panics holding the results"),
+    ),
+  }
+}
+
+/// Writes games in index order, holding back those that finish before an earlier one.
+struct PgnWriter<W> {
+  out: W,
+  /// Index of the next game to write.
+  next: usize,
+  pending: BTreeMap<usize, String>,
+  error: Option<io::Error>,
+}
+
+impl<W: io::Write> PgnWriter<W> {
+  fn push(&mut self, index: usize, text: String) {
+    self.pending.insert(index, text);
+    while self.error.is_none()
+      && let Some(text) = self.pending.remove(&self.next)
+    {
+      let separator = if self.next > 0 { "\n" } else { "" };
+      let written = write!(self.out, "{separator}{text}").and_then(|()| self.out.flush());
+      self.error = written.err();
+      self.next += 1;
+    }
+  }
+}
+
+/// Play one game of a match and return it with the winner, `Some(true)` for white, or `None` for
+/// a draw.
+fn play_game(
+  players: [&Player; 2],
+  searchers: &mut [Searcher; 2],
+  opening: &GameData,
+  first_white: bool,
+  config: &ArenaConfig,
+  index: usize,
+) -> (PgnGame, Option<bool>) {
+  let mut pgn = PgnGame::new(*opening);
+  let book_moves = opening.history_len();
+  pgn.evals = vec![None; book_moves];
+  let mut clock = config.time_control.map(ClockState::new);
+  if clock.is_some() {
+    pgn.clocks = vec![None; book_moves];
+  }
+  let (white, black) = if first_white { (0, 1) } else { (1, 0) };
+
+  let (winner, termination) = loop {
+    let game = &pgn.game;
+    match game.status() {
+      GameStatus::Checkmate { white_wins } | GameStatus::VariantWin { white_wins } => {
+        break (Some(white_wins), "normal");
+      }
+      // Engines claim every draw they can
+      GameStatus::ForcedDraw(_) | GameStatus::ClaimableDraw(_) => break (None, "normal"),
+      GameStatus::Ongoing => {}
+    }
+    if game.history_len() - book_moves >= config.max_plies {
+      break (None, "adjudication");
+    }
+
+    let mover = if game.board.playing { white } else { black };
+    let white_to_move = game.board.playing;
+    let start = Instant::now();
+    let depth = players[mover].depth;
+    let result = match clock.as_ref() {
+      Some(clock) => {
+        let manager = TimeManager::new(&clock.to_clock(white_to_move), white_to_move, None)
+          .expect("a simulated clock always has time for the mover");
+        let mut budget = Budget {
+          start,
+          manager,
+          stop: searchers[mover].stop_handle(),
+        };
+        searchers[mover].search_with(&game.board, depth, None, &mut budget)
+      }
+      None => searchers[mover].search(&game.board, depth),
+    };
+    let remaining = match clock.as_mut() {
+      Some(clock) => {
+        let elapsed = start.elapsed().as_millis() as u64;
+        let Some(remaining) = clock.record_move(white_to_move, elapsed) else {
+          // Running out of time only loses to a side that could still mate
+          let opponent_wins = game.board.has_mating_material(!white_to_move);
+          break (opponent_wins.then_some(!white_to_move), "time forfeit");
+        };
+        Some(remaining)
+      }
+      None => None,
+    };
+    let Some(best_move) = result.best_move else {
+      break (None, "adjudication");
+    };
+    if pgn.game.play_move(&best_move).is_none() {
+      // The move history is full
+      break (None, "adjudication");
+    }
+    pgn.evals.push(Some(eval(if white_to_move {
+      result.score
+    } else {
+      -result.score
+    })));
+    if clock.is_some() {
+      pgn.clocks.push(remaining);
+    }
+  };
+
+  let tags = &mut pgn.tags;
+  tags.event = config.event.clone();
+  tags.round = (index + 1).to_string();
+  tags.white = players[white].name.clone();
+  tags.black = players[black].name.clone();
+  tags.result = match winner {
+    Some(true) => "1-0",
+    Some(false) => "0-1",
+    None => "1/2-1/2",
+  }
+  .to_string();
+  tags.set("Termination", termination);
+  (pgn, winner)
+}
+
+/// Stops a search once the [`TimeManager`] says the move has taken long enough: after an
+/// iteration when the best move is settled, or between root moves past the maximum.
+struct Budget {
+  start: Instant,
+  manager: TimeManager,
+  stop: Arc<AtomicBool>,
+}
+
+impl SearchListener for Budget {
+  fn root_move(&mut self, _piece_move: PieceMove, _move_number: u32) {
+    if self.start.elapsed() >= self.manager.maximum() {
+      self.stop.store(true, Ordering::Relaxed);
+    }
+  }
+
+  fn iteration_complete(&mut self, result: &SearchResult, _lines: &[PvLine]) {
+    if self
+      .manager
+      .stop_after_iteration(self.start.elapsed(), result.stability)
+    {
+      self.stop.store(true, Ordering::Relaxed);
+    }
+  }
+}
+
+/// A search score, already from white's point of view, as a PGN evaluation.
+fn eval(score: i32) -> Eval {
+  if score.abs() >= MATE_SCORE - MAX_PLY as i32 {
+    let moves = (MATE_SCORE - score.abs() + 1) / 2;
+    Eval::Mate(if score > 0 { moves } else { -moves })
+  } else {
+    Eval::Centipawns(score)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use lumifox_chess::model::clock::TimeBonus;
+
+  use super::*;
+
+  fn players() -> (Player, Player) {
+    let player = |name: &str, depth| Player {
+      name: name.to_string(),
+      config: SearchConfig::default(),
+      depth,
+    };
+    (player("deep", 2), player("shallow", 1))
+  }
+
+  fn config(threads: usize) -> ArenaConfig {
+    ArenaConfig {
+      pairs: 3,
+      threads,
+      max_plies: 12,
+      event: "Test".to_string(),
+      time_control: None,
+    }
+  }
+
+  const BOOK: &str = "\
+rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq -
+rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq - 0 1
+";
+
+  #[test]
+  fn test_same_games_on_any_thread_count() {
+    let (first, second) = players();
+    let book = book_from_epd(BOOK).unwrap();
+    let mut one = Vec::new();
+    let results = run_match(&first, &second, &book, &config(1), &mut one).unwrap();
+    assert_eq!(results.games(), 6);
+    let mut four = Vec::new();
+    assert_eq!(
+      run_match(&first, &second, &book, &config(4), &mut four).unwrap(),
+      results
+    );
+    assert_eq!(
+      String::from_utf8(one.clone()).unwrap(),
+      String::from_utf8(four).unwrap()
+    );
+
+    // Written in order, and the colours swap within each pair
+    let games: Vec<_> = read_games(std::str::from_utf8(&one).unwrap())
+      .collect::<Result<_, _>>()
+      .unwrap();
+    assert_eq!(games.len(), 6);
+    for (index, game) in games.iter().enumerate() {
+      assert_eq!(game.tags.round, (index + 1).to_string());
+      let white = if index % 2 == 0 { "deep" } else { "shallow" };
+      assert_eq!(game.tags.white, white);
+      assert_eq!(game.game.initial_board, book[(index / 2) % 2].board);
+      assert!(game.game.history_len() <= 12);
+    }
+  }
+
+  #[test]
+  fn test_decisive_games_are_scored() {
+    // White mates with Re8 whoever plays it, so each player wins once
+    let book = book_from_epd("7k/5ppp/8/8/8/8/8/K3R3 w - -").unwrap();
+    let (first, second) = players();
+    let config = ArenaConfig {
+      pairs: 1,
+      ..config(2)
+    };
+    let mut pgn = Vec::new();
+    let results = run_match(&first, &second, &book, &config, &mut pgn).unwrap();
+    assert_eq!(
+      results,
+      MatchResults {
+        wins: 1,
+        draws: 0,
+        losses: 1
+      }
+    );
+    let text = String::from_utf8(pgn).unwrap();
+    assert_eq!(text.matches("1-0").count(), 4, "{text}");
+    assert!(text.contains("[%eval #1]"), "{text}");
+  }
+
+  #[test]
+  fn test_pgn_book_keeps_moves() {
+    let book =
+      book_from_pgn("[Event \"a\"]\n\n1. e4 e5 2. Nf3 *\n\n[Event \"b\"]\n\n1. d4 *\n").unwrap();
+    assert_eq!(book.len(), 2);
+    assert_eq!(book[0].history_len(), 3);
+    let (first, second) = players();
+    let config = ArenaConfig {
+      pairs: 1,
+      max_plies: 2,
+      ..config(1)
+    };
+    let mut pgn = Vec::new();
+    run_match(&first, &second, &book, &config, &mut pgn).unwrap();
+    let text = String::from_utf8(pgn).unwrap();
+    assert_eq!(text.matches("1. e4 e5 2. Nf3").count(), 2, "{text}");
+    assert!(text.contains("[Termination \"adjudication\"]"));
+  }
+
+  #[test]
+  fn test_time_control() {
+    let (first, second) = players();
+    let config = ArenaConfig {
+      pairs: 1,
+      time_control: Some(TimeControl::sudden_death(
+        60_000,
+        TimeBonus::Increment(1_000),
+      )),
+      ..config(2)
+    };
+    let mut pgn = Vec::new();
+    run_match(&first, &second, &[], &config, &mut pgn).unwrap();
+    let text = String::from_utf8(pgn).unwrap();
+    assert!(text.contains("[%clk 0:01:"), "{text}");
+    assert!(!text.contains("time forfeit"), "{text}");
+
+    // A deterministic search keeps to its budget too, however deep it is asked to go
+    let player = |name: &str| Player {
+      name: name.to_string(),
+      config: SearchConfig {
+        deterministic: true,
+        ..SearchConfig::default()
+      },
+      depth: 64,
+    };
+    let config = ArenaConfig {
+      time_control: Some(TimeControl::sudden_death(1_000, TimeBonus::None)),
+      ..config
+    };
+    let mut pgn = Vec::new();
+    run_match(&player("a"), &player("b"), &[], &config, &mut pgn).unwrap();
+    let text = String::from_utf8(pgn).unwrap();
+    assert!(!text.contains("time forfeit"), "{text}");
+  }
+
+  #[test]
+  fn test_write_error_stops_match() {
+    struct Full;
+    impl io::Write for Full {
+      fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::WriteZero.into())
+      }
+      fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+      }
+    }
+    let (first, second) = players();
+    let error = run_match(&first, &second, &[], &config(2), Full).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+  }
+
+  #[test]
+  fn test_elo_estimate() {
+    let even = MatchResults {
+      wins: 10,
+      draws: 20,
+      losses: 10,
+    };
+    assert_eq!(even.score(), 0.5);
+    assert_eq!(even.elo(), 0.0);
+    assert!(even.elo_error() > 0.0);
+
+    // 75% is about +191 Elo
+    let ahead = MatchResults {
+      wins: 60,
+      draws: 30,
+      losses: 10,
+    };
+    assert!((ahead.elo() - 190.85).abs() < 0.01);
+    // More games narrow the interval
+    let more = MatchResults {
+      wins: 600,
+      draws: 300,
+      losses: 100,
+    };
+    assert!(more.elo_error() < ahead.elo_error() / 3.0);
+    assert_eq!(MatchResults::default().elo_error(), 0.0);
+    assert_eq!(
+      MatchResults {
+        wins: 3,
+        ..Default::default()
+      }
+      .elo(),
+      f64::INFINITY
+    );
+    assert!(
+      ahead
+        .to_string()
+        .starts_with("+60 =30 -10 (75.0%), Elo +190.8 +/- ")
+    );
+  }
+}
@@ -0,0 +1,367 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Training data from self-play.
+//!
+//! [`play_game`] lets the alpha-beta [`Searcher`] play a game against itself, starting with a few
+//! random moves so that games differ, and labels every quiet position it searched with the search
+//! score and, once the game is over, its result. [`write_records`] stores the labelled positions
+//! as fixed-size [`Record`]s for NNUE-style trainers, and [`read_records`] reads them back.
+//!
+//! A record is 32 bytes, little-endian:
+//!
+//! | Bytes  | Field                                                                        |
+//! |--------|------------------------------------------------------------------------------|
+//! | 0..8   | occupancy bitboard                                                           |
+//! | 8..24  | a nibble per occupied square from a1 up, low nibble first: piece type 0..=5  |
+//! |        | (pawn to king), plus 8 for black                                             |
+//! | 24     | bit 0 white to move, bits 1..=4 castling rights in `KQkq` order              |
+//! | 25     | en passant target square, or 255                                             |
+//! | 26     | halfmove clock, saturated at 255                                             |
+//! | 27..29 | search score in centipawns for the side to move, `i16`                       |
+//! | 29     | game result for the side to move: 1 win, 0 draw, -1 loss, `i8`              |
+//! | 30..32 | fullmove number, `u16`                                                       |
+//!
+//! Castling rights are stored as the four classic flags, so Chess960 rook squares are not kept.
+
+use std::io::{self, Read, Write};
+
+use lumifox_chess::model::{
+  castling::CastlingRights,
+  gameboard::{GameBoard, PieceType},
+  gamedata::GameData,
+  piecemove::PieceMove,
+};
+
+use crate::search::{MATE_SCORE, MAX_PLY, Searcher, in_check, mix};
+
+/// Size of one encoded [`Record`].
+pub const RECORD_SIZE: usize = 32;
+
+const PIECES: [PieceType; 6] = [
+  PieceType::Pawn,
+  PieceType::Knight,
+  PieceType::Bishop,
+  PieceType::Rook,
+  PieceType::Queen,
+  PieceType::King,
+];
+
+/// One labelled training position.
+#[derive(Debug, Clone, Copy)]
+pub struct Record {
+  pub board: GameBoard,
+  pub halfmove_clock: u8,
+  pub fullmove: u16,
+  /// Search score in centipawns for the side to move.
+  pub score: i16,
+  /// Game result for the side to move: 1 win, 0 draw, -1 loss.
+  pub result: i8,
+}
+
+impl Record {
+  /// The 32-byte encoding described in the [module docs](self), or `None` if the board has more
+  /// than 32 pieces.
+  pub fn encode(&self) -> Option<[u8; RECORD_SIZE]> {
+    let mut bytes = [0u8; RECORD_SIZE];
+    bytes[..26].copy_from_slice(&pack_position(&self.board)?);
+    bytes[26] = self.halfmove_clock;
+    bytes[27..29].copy_from_slice(&self.score.to_le_bytes());
+    bytes[29] = self.result as u8;
+    bytes[30..32].copy_from_slice(&self.fullmove.to_le_bytes());
+    Some(bytes)
+  }
+
+  /// Read a record written by [`encode`](Self::encode). Returns `None` for bytes that do not
+  /// describe a board.
+  pub fn decode(bytes: &[u8; RECORD_SIZE]) -> Option<Self> {
+    let occupancy = u64::from_le_bytes(bytes[..8].try_into().ok()?);
+    let mut board = GameBoard::default();
+    for (index, square) in (0..64u8)
+      .filter(|&square| occupancy & (1 << square) != 0)
+      .enumerate()
+    {
+      let nibble = (bytes[8 + index / 2] >> (4 * (index % 2))) & 0xF;
+      let piece = *PIECES.get(usize::from(nibble & 7))?;
+      board.set_square(square, piece, nibble & 8 == 0)?;
+    }
+    board.playing = bytes[24] & 1 != 0;
+    board.castling = CastlingRights::from_bits((bytes[24] >> 1) & 0xF);
+    board.en_passant = match bytes[25] {
+      255 => None,
+      // Stored the way a double pawn push leaves it: from the pawn to the square it skipped
+      target @ 0..64 => Some(PieceMove::new(target ^ 8, target, false, None)),
+      _ => return None,
+    };
+    Some(Self {
+      board,
+      halfmove_clock: bytes[26],
+      score: i16::from_le_bytes([bytes[27], bytes[28]]),
+      result: bytes[29] as i8,
+      fullmove: u16::from_le_bytes([bytes[30], bytes[31]]),
+    })
+  }
+}
+
+/// Bytes 0..26 of a record: everything about the position except the clocks.
+fn pack_position(board: &GameBoard) -> Option<[u8; 26]> {
+  let mut bytes = [0u8; 26];
+  let occupancy = board.combined();
+  if occupancy.count() > 32 {
+    return None;
+  }
+  bytes[..8].copy_from_slice(&occupancy.raw().to_le_bytes());
+  for (index, square) in occupancy.into_iter().enumerate() {
+    let (piece, white) = board.piece_at(square)?;
+    let nibble = piece as u8 | if white { 0 } else { 8 };
+    bytes[8 + index / 2] |= nibble << (4 * (index % 2));
+  }
+  bytes[24] = u8::from(board.playing) | board.castling.bits() << 1;
+  bytes[25] = board
+    .en_passant
+    .map_or(255, |en_passant| en_passant.to_square());
+  Some(bytes)
+}
+
+/// How [`play_game`] plays and labels a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatagenConfig {
+  /// Depth of every search.
+  pub depth: u32,
+  /// Random moves played at the start of each game, not recorded.
+  pub random_plies: usize,
+  /// The game is scored a draw after this many plies.
+  pub max_plies: usize,
+  /// A search score at least this far from zero ends the game as a win for the side ahead.
+  pub adjudicate_score: i32,
+}
+
+impl Default for DatagenConfig {
+  fn default() -> Self {
+    Self {
+      depth: 4,
+      random_plies: 8,
+      max_plies: 400,
+      adjudicate_score: MATE_SCORE - MAX_PLY as i32,
+    }
+  }
+}
+
+/// Play one self-play game from `start` and return its labelled positions. `seed` picks the
+/// random opening moves. Positions in check and positions whose best move captures are searched
+/// but not recorded, since their static value says little about the search score.
+pub fn play_game(
+  searcher: &mut Searcher,
+  start: &GameData,
+  config: &DatagenConfig,
+  seed: u64,
+) -> Vec<Record> {
+  let mut game = *start;
+  let mut state = seed;
+  let mut seen: Vec<GameBoard> = Vec::new();
+  let mut records = Vec::new();
+  // Result for white, once known
+  let mut white_result = 0;
+
+  for ply in 0..config.max_plies {
+    let repetitions = seen.iter().filter(|&seen| *seen == game.board).count();
+    if game.halfmove_clock >= 100 || repetitions >= 2 {
+      break;
+    }
+    seen.push(game.board);
+
+    let board = game.board;
+    let piece_move = if ply < config.random_plies {
+      let moves = legal_moves(&board);
+      state = mix(state);
+      moves
+        .get((state % moves.len().max(1) as u64) as usize)
+        .copied()
+    } else {
+      let result = searcher.search(&board, config.depth);
+      if result.score.abs() >= config.adjudicate_score {
+        let winner = (result.score > 0) == board.playing;
+        white_result = if winner { 1 } else { -1 };
+        break;
+      }
+      if let Some(best_move) = result.best_move
+        && !best_move.is_capture()
+        && !in_check(&board)
+      {
+        records.push(Record {
+          board,
+          halfmove_clock: game.halfmove_clock.min(255) as u8,
+          fullmove: (game.plies / 2 + 1).min(u16::MAX as usize) as u16,
+          score: result.score.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+          result: 0,
+        });
+      }
+      result.best_move
+    };
+
+    let Some(piece_move) = piece_move else {
+      // No legal moves: mate or stalemate
+      if in_check(&board) {
+        white_result = if board.playing { -1 } else { 1 };
+      }
+      break;
+    };
+    if game.play_move(&piece_move).is_none() {
+      break;
+    }
+  }
+
+  for record in &mut records {
+    record.result = if record.board.playing {
+      white_result
+    } else {
+      -white_result
+    };
+  }
+  records
+}
+
+/// Write `records` one after another. Records of boards that cannot be encoded are skipped.
+/// Returns how many were written.
+pub fn write_records<W: Write>(out: &mut W, records: &[Record]) -> io::Result<usize> {
+  let mut written = 0;
+  for bytes in records.iter().filter_map(Record::encode) {
+    out.write_all(&bytes)?;
+    written += 1;
+  }
+  Ok(written)
+}
+
+/// Read every record from `input` until it ends. A trailing partial record or bytes that do not
+/// describe a board are an [`io::ErrorKind::InvalidData`] error.
+pub fn read_records<R: Read>(input: &mut R) -> io::Result<Vec<Record>> {
+  let mut data = Vec::new();
+  input.read_to_end(&mut data)?;
+  let chunks = data.chunks_exact(RECORD_SIZE);
+  if !chunks.remainder().is_empty() {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidData,
+      "trailing partial record",
+    ));
+  }
+  chunks
+    .map(|chunk| {
+      let bytes = chunk.try_into().expect("chunks are RECORD_SIZE long");
+      Record::decode(bytes)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid record"))
+    })
+    .collect()
+}
+
+fn legal_moves(board: &GameBoard) -> Vec<PieceMove> {
+  let (moves, count) = lumifox_chess::movegen::generate_moves(board);
+  moves[..count]
+    .iter()
+    .copied()
+    .filter(|piece_move| {
+      let mut child = *board;
+      child.move_piece(piece_move).is_some()
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fen(record: &Record) -> String {
+    let mut fen = String::new();
+    record.board.write_fen(&mut fen).unwrap();
+    fen
+  }
+
+  #[test]
+  fn test_record_round_trip() {
+    for text in [
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b Kq - 3 17",
+      "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+      "8/8/8/8/8/8/8/K6k b - - 99 200",
+    ] {
+      let game = GameData::from_fen(text).unwrap();
+      let record = Record {
+        board: game.board,
+        halfmove_clock: game.halfmove_clock as u8,
+        fullmove: (game.plies / 2 + 1) as u16,
+        score: -1234,
+        result: -1,
+      };
+      let bytes = record.encode().unwrap();
+      let decoded = Record::decode(&bytes).unwrap();
+      assert_eq!(fen(&decoded), fen(&record), "{text}");
+      assert_eq!(decoded.encode(), Some(bytes));
+      assert_eq!(
+        (decoded.score, decoded.result, decoded.fullmove),
+        (-1234, -1, record.fullmove)
+      );
+      assert_eq!(decoded.board.en_passant, record.board.en_passant);
+    }
+  }
+
+  #[test]
+  fn test_write_and_read() {
+    let mut searcher = Searcher::default();
+    let config = DatagenConfig {
+      depth: 1,
+      random_plies: 4,
+      max_plies: 24,
+      ..Default::default()
+    };
+    let records = play_game(&mut searcher, &GameData::START_POS, &config, 42);
+    assert!(!records.is_empty());
+    assert!(records.len() <= 20);
+
+    let mut bytes = Vec::new();
+    assert_eq!(write_records(&mut bytes, &records).unwrap(), records.len());
+    assert_eq!(bytes.len(), records.len() * RECORD_SIZE);
+    let read = read_records(&mut bytes.as_slice()).unwrap();
+    assert_eq!(read.len(), records.len());
+    assert!(read.iter().zip(&records).all(|(a, b)| fen(a) == fen(b)));
+
+    bytes.pop();
+    assert!(read_records(&mut bytes.as_slice()).is_err());
+  }
+
+  #[test]
+  fn test_results_label_each_side() {
+    // White mates with Re8 after black's only move; white's position is labelled a win
+    let start = GameData::from_fen("7k/5ppp/8/8/8/8/8/K3R3 w - - 0 1").unwrap();
+    let config = DatagenConfig {
+      depth: 3,
+      random_plies: 0,
+      ..Default::default()
+    };
+    let records = play_game(&mut Searcher::default(), &start, &config, 0);
+    assert!(records.is_empty(), "the mate score adjudicates at once");
+
+    let config = DatagenConfig {
+      adjudicate_score: i32::MAX,
+      ..config
+    };
+    let records = play_game(&mut Searcher::default(), &start, &config, 0);
+    assert_eq!(records.len(), 1);
+    assert!(records[0].board.playing);
+    assert_eq!(records[0].result, 1);
+    assert!(records[0].score > 0);
+  }
+}
@@ -0,0 +1,108 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Static evaluation.
+//!
+//! Scores are in centipawns from the point of view of the side to move, which is what the negamax
+//! search expects.
+
+use lumifox_chess::model::gameboard::{GameBoard, PieceType};
+
+use crate::{
+  search::MAX_PLY,
+  tablebase::{TB_WIN_SCORE, Tablebase, Wdl},
+};
+
+/// Material value of each piece type in centipawns, shared with the rest of the workspace.
+pub use lumifox_chess::constants::piece_value;
+
+/// Evaluate `board` from the side to move's perspective.
+pub fn evaluate(board: &GameBoard) -> i32 {
+  let material = board.material_signature();
+
+  let mut score = 0;
+  for piece in [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+  ] {
+    let white_count = i32::from(material.count(piece, true));
+    let black_count = i32::from(material.count(piece, false));
+    score += (white_count - black_count) * piece_value(piece);
+  }
+
+  // With only pawns besides the bishops, opposite-coloured bishops hold most extra pawns
+  if board.has_opposite_colored_bishops() && (board.knights | board.rooks | board.queens).is_empty()
+  {
+    score /= 2;
+  }
+
+  if board.playing { score } else { -score }
+}
+
+/// Evaluate `board`, taking the result from `tablebase` when it covers the position. Won
+/// positions score below any tablebase score the search gives (see
+/// [`wdl_score`](crate::tablebase::wdl_score)) but far above any material count, and higher the
+/// nearer the tables say the win is, so that a search guided by this keeps making progress.
+pub fn evaluate_with<T: Tablebase + ?Sized>(board: &GameBoard, tablebase: &T) -> i32 {
+  if !tablebase.covers(board) {
+    return evaluate(board);
+  }
+  let won = || {
+    let distance = tablebase.probe_dtz(board).unwrap_or(0).abs();
+    TB_WIN_SCORE - MAX_PLY as i32 - distance.min(MAX_TB_DISTANCE)
+  };
+  match tablebase.probe_wdl(board) {
+    Some(Wdl::Win) => won(),
+    Some(Wdl::Loss) => -won(),
+    Some(_) => 0,
+    None => evaluate(board),
+  }
+}
+
+/// Longest tablebase distance [`evaluate_with`] tells apart.
+const MAX_TB_DISTANCE: i32 = 1000;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lumifox_chess::model::gamedata::GameData;
+
+  #[test]
+  fn test_start_position_is_balanced() {
+    assert_eq!(evaluate(&GameBoard::START_POS), 0);
+  }
+
+  #[test]
+  fn test_opposite_coloured_bishops_scale_down() {
+    let opposite = GameData::from_fen("4k3/5b2/8/3p4/3P4/8/P7/2B1K3 w - - 0 1").unwrap();
+    let same = GameData::from_fen("4k3/4b3/8/3p4/3P4/8/P7/2B1K3 w - - 0 1").unwrap();
+    assert_eq!(evaluate(&same.board), 100);
+    assert_eq!(evaluate(&opposite.board), 50);
+  }
+
+  #[test]
+  fn test_score_is_relative_to_side_to_move() {
+    let white = GameData::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+    let black = GameData::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+    assert_eq!(evaluate(&white.board), 900);
+    assert_eq!(evaluate(&black.board), -900);
+  }
+}
@@ -0,0 +1,40 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Lumifox engine internals
+//!
+//! The `lumifox` binary is a thin frontend; the pieces that make up the engine live here so they
+//! can be tested and reused independently of the frontend.
+//!
+//! Key modules
+//! - `arena` — engine-vs-engine matches on a pool of threads, with PGN output and an Elo estimate
+//! - `datagen` — self-play training data, written as packed labelled positions
+//! - `eval` — static evaluation of a position
+//! - `mcts` — Monte Carlo tree search, an alternative to the alpha-beta search
+//! - `search` — alpha-beta search and its tunables
+//! - `tablebase` — endgame tablebase interface, DTZ root move filtering and table generation; no
+//!   Syzygy file reader
+//! - `uci` — adapter that drives the search from UCI commands
+
+pub mod arena;
+pub mod datagen;
+pub mod eval;
+pub mod mcts;
+pub mod search;
+pub mod tablebase;
+pub mod uci;
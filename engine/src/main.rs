@@ -16,4 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-fn main() {}
+use std::io;
+
+use lumifox::uci::UciSession;
+
+fn main() -> io::Result<()> {
+  UciSession::new(io::stdout()).run(io::stdin().lock())
+}
@@ -0,0 +1,522 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Monte Carlo tree search.
+//!
+//! An alternative to the alpha-beta [`Searcher`](crate::search::Searcher) for experimenting
+//! with search paradigms on the same board code. Each playout walks down the tree from the root,
+//! picking children by UCT or PUCT, expands the first unvisited position it reaches, asks an
+//! [`Evaluator`] how good that position is and backs the value up the path. The move played is
+//! the most visited root move.
+//!
+//! [`Mcts`] takes the same inputs as the alpha-beta searcher and returns a [`SearchResult`]: a
+//! playout budget stands in for the depth, root moves can be restricted, a
+//! [`SearchListener`] hears about progress and a stop handle ends the search from another thread.
+//!
+//! Evaluators supply a value for a position and, optionally, prior probabilities for its moves:
+//! [`RandomPlayout`] plays random games out, [`EvalGuided`] squashes the static evaluation, and a
+//! neural network with policy and value heads fits the same trait.
+
+use core::cmp::Reverse;
+use std::{
+  collections::hash_map::RandomState,
+  hash::BuildHasher,
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+};
+
+use lumifox_chess::{
+  model::{gameboard::GameBoard, piecemove::PieceMove},
+  movegen::generate_moves,
+};
+
+use crate::{
+  eval::evaluate,
+  search::{MATE_SCORE, PvLine, SearchListener, SearchResult, in_check, mix},
+};
+
+/// Judges leaf positions for [`Mcts`].
+pub trait Evaluator {
+  /// How good `board` is for the side to move, from -1 (lost) to 1 (won). Only called for
+  /// positions that have legal moves.
+  fn value(&mut self, board: &GameBoard) -> f32;
+
+  /// Fill `priors` with the probability of playing each of `moves`, in the same order. Used by
+  /// [`Selection::Puct`] to weigh exploration and by both rules to order unvisited moves. The
+  /// default is uniform.
+  fn priors(&mut self, _board: &GameBoard, moves: &[PieceMove], priors: &mut [f32]) {
+    priors.fill(1.0 / moves.len().max(1) as f32);
+  }
+}
+
+/// Values a position by playing random legal moves until the game ends or `max_plies` have been
+/// played. Mates count as wins or losses, anything unfinished as a draw.
+#[derive(Debug, Clone)]
+pub struct RandomPlayout {
+  pub max_plies: usize,
+  state: u64,
+}
+
+impl RandomPlayout {
+  pub fn new(max_plies: usize) -> Self {
+    Self::with_seed(max_plies, RandomState::new().hash_one(0u64))
+  }
+
+  /// Like [`new`](Self::new), with the move choices fixed by `seed`.
+  pub fn with_seed(max_plies: usize, seed: u64) -> Self {
+    Self {
+      max_plies,
+      state: seed,
+    }
+  }
+}
+
+impl Evaluator for RandomPlayout {
+  fn value(&mut self, board: &GameBoard) -> f32 {
+    let mut board = *board;
+    for ply in 0..self.max_plies {
+      let moves = legal_moves(&board);
+      if moves.is_empty() {
+        // The side to move at the start won if the mated side is its opponent
+        let outcome = if in_check(&board) { -1.0 } else { 0.0 };
+        return if ply % 2 == 0 { outcome } else { -outcome };
+      }
+      self.state = mix(self.state);
+      let piece_move = moves[(self.state % moves.len() as u64) as usize];
+      board.move_piece(&piece_move);
+    }
+    0.0
+  }
+}
+
+/// Values a position by its static evaluation, squashed into -1..1 so that `scale` centipawns
+/// is about three quarters of a win.
+#[derive(Debug, Clone, Copy)]
+pub struct EvalGuided {
+  pub scale: f32,
+}
+
+impl Default for EvalGuided {
+  fn default() -> Self {
+    Self { scale: 400.0 }
+  }
+}
+
+impl Evaluator for EvalGuided {
+  fn value(&mut self, board: &GameBoard) -> f32 {
+    (evaluate(board) as f32 / self.scale).tanh()
+  }
+}
+
+/// How a playout picks the child to descend into.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Selection {
+  /// UCB1 applied to trees: the child's average value plus
+  /// `exploration * sqrt(ln(parent visits) / visits)`. Unvisited children go first, highest
+  /// prior first.
+  #[default]
+  Uct,
+  /// The AlphaZero rule: the child's average value plus
+  /// `exploration * prior * sqrt(parent visits) / (1 + visits)`.
+  Puct,
+}
+
+/// Configuration for [`Mcts`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsConfig {
+  pub selection: Selection,
+  /// Weight of exploration against the average value in the selection rule.
+  pub exploration: f32,
+  /// Root moves reported to [`SearchListener::iteration_complete`], most visited first.
+  pub multi_pv: usize,
+}
+
+impl Default for MctsConfig {
+  fn default() -> Self {
+    Self {
+      selection: Selection::Uct,
+      exploration: core::f32::consts::SQRT_2,
+      multi_pv: 1,
+    }
+  }
+}
+
+/// A position in the tree, reached by `piece_move` from its parent.
+#[derive(Debug, Clone)]
+struct Node {
+  piece_move: PieceMove,
+  prior: f32,
+  visits: u32,
+  /// Sum of the values backed up through this node, from the point of view of the side that
+  /// played `piece_move`.
+  value_sum: f32,
+  /// Indices of the children in `Mcts::nodes`; empty until expanded.
+  children: Vec<usize>,
+  expanded: bool,
+  /// The value of a position without legal moves, for the side to move.
+  terminal: Option<f32>,
+}
+
+impl Node {
+  fn new(piece_move: PieceMove, prior: f32) -> Self {
+    Self {
+      piece_move,
+      prior,
+      visits: 0,
+      value_sum: 0.0,
+      children: Vec::new(),
+      expanded: false,
+      terminal: None,
+    }
+  }
+
+  fn mean(&self) -> f32 {
+    if self.visits == 0 {
+      0.0
+    } else {
+      self.value_sum / self.visits as f32
+    }
+  }
+}
+
+/// Monte Carlo tree search over an [`Evaluator`]. See the [module docs](self).
+pub struct Mcts<E: Evaluator> {
+  pub config: MctsConfig,
+  pub evaluator: E,
+  nodes: Vec<Node>,
+  stop: Arc<AtomicBool>,
+}
+
+impl<E: Evaluator> Mcts<E> {
+  pub fn new(config: MctsConfig, evaluator: E) -> Self {
+    Self {
+      config,
+      evaluator,
+      nodes: Vec::new(),
+      stop: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  /// Flag that stops the search after the current playout when set, from any thread. It is
+  /// cleared whenever a search returns.
+  pub fn stop_handle(&self) -> Arc<AtomicBool> {
+    Arc::clone(&self.stop)
+  }
+
+  /// Run `playouts` playouts from `board` and return the most visited move.
+  pub fn search(&mut self, board: &GameBoard, playouts: u64) -> SearchResult {
+    self.search_with(board, playouts, None, &mut ())
+  }
+
+  /// Like [`Mcts::search`], reporting progress to `listener` and, if `root_moves` is given,
+  /// considering only those moves at the root.
+  ///
+  /// The listener hears from [`SearchListener::iteration_complete`] after every power of two
+  /// playouts and once more at the end. The result's `depth` is the deepest the tree reached,
+  /// `nodes` counts playouts and `score` converts the best move's average value back to
+  /// centipawns.
+  pub fn search_with<L: SearchListener>(
+    &mut self,
+    board: &GameBoard,
+    playouts: u64,
+    root_moves: Option<&[PieceMove]>,
+    listener: &mut L,
+  ) -> SearchResult {
+    self.nodes.clear();
+    self.nodes.push(Node::new(PieceMove::NULL, 1.0));
+    self.expand(0, board, root_moves);
+
+    let mut result = SearchResult {
+      best_move: None,
+      score: match self.nodes[0].terminal {
+        Some(value) => value_to_score(value),
+        None => 0,
+      },
+      depth: 0,
+      stability: 0,
+      nodes: 0,
+      tb_hits: 0,
+    };
+    if self.nodes[0].terminal.is_none() {
+      let mut next_report = 1;
+      for playout in 1..=playouts {
+        if self.stop.load(Ordering::Relaxed) {
+          break;
+        }
+        let depth = self.playout(board);
+        result.depth = result.depth.max(depth);
+        result.nodes = playout;
+        if playout == next_report || playout == playouts {
+          next_report *= 2;
+          self.report(&mut result, listener);
+        }
+      }
+      if result.best_move.is_none() {
+        // Stopped before the first playout
+        result.best_move = self.nodes[0]
+          .children
+          .first()
+          .map(|&child| self.nodes[child].piece_move);
+      }
+    }
+
+    self.stop.store(false, Ordering::Relaxed);
+    result
+  }
+
+  /// One playout: select down to a leaf, expand and evaluate it, and back the value up. Returns
+  /// the depth of the leaf.
+  fn playout(&mut self, root: &GameBoard) -> u32 {
+    let mut board = *root;
+    let mut path = vec![0];
+    let mut node = 0;
+    while self.nodes[node].expanded && !self.nodes[node].children.is_empty() {
+      node = self.select(node);
+      board.move_piece(&self.nodes[node].piece_move);
+      path.push(node);
+    }
+
+    // `value` is for the side to move at `node`
+    let value = if let Some(value) = self.nodes[node].terminal {
+      value
+    } else {
+      self.expand(node, &board, None);
+      match self.nodes[node].terminal {
+        Some(value) => value,
+        None => self.evaluator.value(&board).clamp(-1.0, 1.0),
+      }
+    };
+
+    // Each node stores the value for the side that moved into it
+    let mut value = -value;
+    for &node in path.iter().rev() {
+      let node = &mut self.nodes[node];
+      node.visits += 1;
+      node.value_sum += value;
+      value = -value;
+    }
+    path.len() as u32 - 1
+  }
+
+  fn select(&self, parent: usize) -> usize {
+    let parent_visits = self.nodes[parent].visits.max(1) as f32;
+    let c = self.config.exploration;
+    let score = |child: &Node| match self.config.selection {
+      Selection::Uct if child.visits == 0 => f32::INFINITY,
+      Selection::Uct => child.mean() + c * (parent_visits.ln() / child.visits as f32).sqrt(),
+      Selection::Puct => {
+        child.mean() + c * child.prior * parent_visits.sqrt() / (1.0 + child.visits as f32)
+      }
+    };
+    // Children are stored by falling prior, so ties go to the likelier move
+    let mut best = self.nodes[parent].children[0];
+    let mut best_score = f32::NEG_INFINITY;
+    for &child in &self.nodes[parent].children {
+      let child_score = score(&self.nodes[child]);
+      if child_score > best_score {
+        best = child;
+        best_score = child_score;
+      }
+    }
+    best
+  }
+
+  /// Add the children of `node`, the position `board`, or mark it terminal.
+  fn expand(&mut self, node: usize, board: &GameBoard, root_moves: Option<&[PieceMove]>) {
+    self.nodes[node].expanded = true;
+    let mut moves = legal_moves(board);
+    if let Some(root_moves) = root_moves {
+      moves.retain(|piece_move| root_moves.contains(piece_move));
+    }
+    if moves.is_empty() {
+      let lost = root_moves.is_none() && in_check(board);
+      self.nodes[node].terminal = Some(if lost { -1.0 } else { 0.0 });
+      return;
+    }
+
+    let mut priors = vec![0.0; moves.len()];
+    self.evaluator.priors(board, &moves, &mut priors);
+    let mut children: Vec<(PieceMove, f32)> = moves.into_iter().zip(priors).collect();
+    children.sort_by(|a, b| b.1.total_cmp(&a.1));
+    for (piece_move, prior) in children {
+      let child = self.nodes.len();
+      self.nodes[node].children.push(child);
+      self.nodes.push(Node::new(piece_move, prior));
+    }
+  }
+
+  /// Update `result` from the root's children and tell `listener`.
+  fn report<L: SearchListener>(&self, result: &mut SearchResult, listener: &mut L) {
+    let mut children: Vec<&Node> = self.nodes[0]
+      .children
+      .iter()
+      .map(|&child| &self.nodes[child])
+      .collect();
+    children.sort_by_key(|child| Reverse(child.visits));
+    let Some(best) = children.first() else {
+      return;
+    };
+
+    let kept = result.best_move == Some(best.piece_move);
+    result.stability = if kept { result.stability + 1 } else { 0 };
+    result.best_move = Some(best.piece_move);
+    result.score = value_to_score(best.mean());
+    let lines: Vec<PvLine> = children
+      .iter()
+      .take(self.config.multi_pv.max(1))
+      .map(|child| PvLine {
+        best_move: child.piece_move,
+        score: value_to_score(child.mean()),
+      })
+      .collect();
+    listener.iteration_complete(result, &lines);
+  }
+}
+
+/// Every legal move of `board`.
+fn legal_moves(board: &GameBoard) -> Vec<PieceMove> {
+  let (moves, count) = generate_moves(board);
+  moves[..count]
+    .iter()
+    .copied()
+    .filter(|piece_move| {
+      let mut child = *board;
+      child.move_piece(piece_move).is_some()
+    })
+    .collect()
+}
+
+/// Centipawns for a value between -1 and 1, the inverse of [`EvalGuided`] at its default scale.
+/// Certain wins and losses become mate scores.
+fn value_to_score(value: f32) -> i32 {
+  if value >= 1.0 {
+    MATE_SCORE
+  } else if value <= -1.0 {
+    -MATE_SCORE
+  } else {
+    (value.atanh() * EvalGuided::default().scale) as i32
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lumifox_chess::{constants::*, model::gamedata::GameData};
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  const BACK_RANK_MATE: &str = "6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1";
+
+  #[test]
+  fn test_finds_mate_in_one() {
+    let board = board_from_fen(BACK_RANK_MATE);
+    for selection in [Selection::Uct, Selection::Puct] {
+      let config = MctsConfig {
+        selection,
+        ..Default::default()
+      };
+      let result = Mcts::new(config, EvalGuided::default()).search(&board, 400);
+      assert_eq!(result.best_move, Some(PieceMove::new(E1, E8, false, None)));
+      assert_eq!(result.nodes, 400);
+      assert!(result.score > 0);
+    }
+
+    let result =
+      Mcts::new(MctsConfig::default(), RandomPlayout::with_seed(40, 7)).search(&board, 400);
+    assert_eq!(result.best_move, Some(PieceMove::new(E1, E8, false, None)));
+  }
+
+  #[test]
+  fn test_terminal_root() {
+    // Black is mated
+    let board = board_from_fen("4R1k1/5ppp/8/8/8/8/8/K7 b - - 0 1");
+    let result = Mcts::new(MctsConfig::default(), EvalGuided::default()).search(&board, 10);
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, -MATE_SCORE);
+    assert_eq!(result.nodes, 0);
+  }
+
+  #[test]
+  fn test_root_moves_and_stop() {
+    let board = GameBoard::START_POS;
+    let only = [PieceMove::new(A2, A3, false, None)];
+    let mut mcts = Mcts::new(MctsConfig::default(), EvalGuided::default());
+    let result = mcts.search_with(&board, 50, Some(&only), &mut ());
+    assert_eq!(result.best_move, Some(only[0]));
+
+    mcts.stop_handle().store(true, Ordering::Relaxed);
+    let result = mcts.search(&board, 50);
+    assert_eq!(result.nodes, 0);
+    assert!(result.best_move.is_some());
+    assert!(!mcts.stop_handle().load(Ordering::Relaxed));
+  }
+
+  #[test]
+  fn test_policy_priors_steer_search() {
+    // A policy that is sure about one move, as a network might be
+    struct Policy(PieceMove);
+    impl Evaluator for Policy {
+      fn value(&mut self, _board: &GameBoard) -> f32 {
+        0.0
+      }
+
+      fn priors(&mut self, _board: &GameBoard, moves: &[PieceMove], priors: &mut [f32]) {
+        for (prior, piece_move) in priors.iter_mut().zip(moves) {
+          *prior = if *piece_move == self.0 { 0.9 } else { 0.005 };
+        }
+      }
+    }
+
+    let favourite = PieceMove::new(G1, F3, false, None);
+    let config = MctsConfig {
+      selection: Selection::Puct,
+      ..Default::default()
+    };
+    let result = Mcts::new(config, Policy(favourite)).search(&GameBoard::START_POS, 200);
+    assert_eq!(result.best_move, Some(favourite));
+  }
+
+  #[test]
+  fn test_reports_progress() {
+    struct Reports(Vec<(u64, usize)>);
+    impl SearchListener for Reports {
+      fn iteration_complete(&mut self, result: &SearchResult, lines: &[PvLine]) {
+        self.0.push((result.nodes, lines.len()));
+      }
+    }
+
+    let config = MctsConfig {
+      multi_pv: 3,
+      ..Default::default()
+    };
+    let mut reports = Reports(Vec::new());
+    Mcts::new(config, EvalGuided::default()).search_with(
+      &GameBoard::START_POS,
+      100,
+      None,
+      &mut reports,
+    );
+    let nodes: Vec<u64> = reports.0.iter().map(|&(nodes, _)| nodes).collect();
+    assert_eq!(nodes, [1, 2, 4, 8, 16, 32, 64, 100]);
+    assert!(reports.0.iter().all(|&(_, lines)| lines == 3));
+  }
+}
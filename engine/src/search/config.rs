@@ -0,0 +1,212 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Search tunables.
+//!
+//! Everything that changes which moves the search looks at, or in which order, is configured here
+//! so it can be adjusted (or tuned automatically) without touching the search itself.
+
+/// How underpromotions (to rook, bishop or knight) are treated by the search.
+///
+/// The move generator always produces all four promotions in a fixed order (queen, rook, bishop,
+/// knight). Underpromotions are almost never best, so searching them early wastes nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnderpromotionPolicy {
+  /// Search every promotion, in the order produced by the move generator.
+  SearchAll,
+  /// Search queen promotions first and underpromotions after every other move. Quiescence only
+  /// looks at queen promotions and knight promotions that give check.
+  #[default]
+  Delay,
+  /// Never search rook or bishop promotions, and only search knight promotions that give check.
+  Prune,
+}
+
+/// Configuration for [`Searcher`](super::Searcher).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchConfig {
+  pub underpromotions: UnderpromotionPolicy,
+  /// Search one ply deeper when the side to move is in check.
+  pub check_extensions: bool,
+  /// Most check extensions applied along a single line, so long series of checks cannot blow up
+  /// the tree.
+  pub max_check_extensions: u32,
+  /// Number of checking moves quiescence may play along a line besides captures and promotions.
+  /// Once any are allowed, quiescence also answers checks with every evasion instead of standing
+  /// pat, so it can see mates. `0` disables both.
+  pub quiescence_checks: u32,
+  /// Null-move pruning: give the opponent a free move and cut the node if a reduced search still
+  /// fails high.
+  pub null_move: bool,
+  /// Depth reduction of the null-move search, on top of the move itself.
+  pub null_move_reduction: u32,
+  /// Confirm null-move cutoffs at this depth or more with a reduced search of the real position,
+  /// which protects against zugzwang. `None` trusts every cutoff.
+  pub null_move_verification: Option<u32>,
+  /// Late-move reductions: search quiet moves ordered late at reduced depth, re-searching at full
+  /// depth if they turn out better than expected. Reductions grow with the logarithm of both the
+  /// depth and the move number.
+  pub late_move_reductions: bool,
+  /// The reduction is `lmr_base + ln(depth) * ln(move number) / lmr_divisor`, both in hundredths
+  /// so they can be tuned as integers.
+  pub lmr_base: u32,
+  pub lmr_divisor: u32,
+  /// Futility pruning: near the leaves, skip quiet moves when the static evaluation plus
+  /// `futility_margin` per ply of depth left cannot reach alpha.
+  pub futility_pruning: bool,
+  pub futility_margin: i32,
+  /// Reverse futility pruning: near the leaves, return the static evaluation when it beats beta
+  /// by more than `reverse_futility_margin` per ply of depth left.
+  pub reverse_futility_pruning: bool,
+  pub reverse_futility_margin: i32,
+  /// Aspiration windows: from [`ASPIRATION_MIN_DEPTH`] on, search the root with a window this
+  /// many centipawns either side of the previous iteration's score, widening it on a fail. `0`
+  /// searches every iteration with the full window.
+  pub aspiration_window: i32,
+  /// Make every search a pure function of the position, depth, node limit and this config, so
+  /// node counts and best moves are identical from run to run. Anything that would add randomness
+  /// or more threads to the search must stay off while this is set. Stop requests and time limits
+  /// still end a search, which then depends on when they came: limit it by depth or nodes alone
+  /// for reproducible results. Meant for test suites and debugging.
+  pub deterministic: bool,
+  /// Play at roughly `elo` instead of full strength, by capping depth and nodes and adding noise
+  /// to the evaluation as [`StrengthLimits::for_elo`] describes.
+  pub limit_strength: bool,
+  /// Target rating while `limit_strength` is set, clamped to [`MIN_ELO`]..=[`MAX_ELO`].
+  pub elo: u32,
+  /// Number of best lines to search and report, each with a different first move. `1` is a
+  /// normal search; `0` is treated as `1`.
+  pub multi_pv: u32,
+  /// The GUI is analysing rather than playing (`UCI_AnalyseMode`). Every root move is searched
+  /// with the full window, so each gets an exact score instead of a bound, and time management
+  /// must not cut a search short because the best move looks settled. An opening book or
+  /// contempt, once added, must stay off while this is set.
+  pub analyse_mode: bool,
+}
+
+/// Most lines [`SearchConfig::multi_pv`] is offered with over UCI.
+pub const MAX_MULTI_PV: u32 = 256;
+
+/// Weakest rating [`SearchConfig::limit_strength`] can play at.
+pub const MIN_ELO: u32 = 800;
+/// Strongest rating [`SearchConfig::limit_strength`] can play at. The caps at this rating are
+/// still well below full strength.
+pub const MAX_ELO: u32 = 2400;
+
+/// How a strength-limited search is held back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrengthLimits {
+  /// Deepest iteration searched.
+  pub max_depth: u32,
+  /// Positions visited before the search stops and plays its best move so far.
+  pub max_nodes: u64,
+  /// Most centipawns added to or taken from each evaluation, so weaker play also misjudges
+  /// positions instead of only seeing less.
+  pub eval_noise: i32,
+}
+
+impl StrengthLimits {
+  /// Limits for a rating of `elo`, clamped to [`MIN_ELO`]..=[`MAX_ELO`]. Every 200 points add a
+  /// ply of depth, double the node budget and take 25 centipawns off the noise, from 1 ply, 500
+  /// nodes and 200 centipawns at `MIN_ELO`.
+  pub fn for_elo(elo: u32) -> Self {
+    let steps = (elo.clamp(MIN_ELO, MAX_ELO) - MIN_ELO) / 200;
+    Self {
+      max_depth: 1 + steps,
+      max_nodes: 500 << steps,
+      eval_noise: 200 - 25 * steps as i32,
+    }
+  }
+}
+
+impl SearchConfig {
+  /// The limits to search with, or `None` at full strength.
+  pub fn strength_limits(&self) -> Option<StrengthLimits> {
+    self
+      .limit_strength
+      .then(|| StrengthLimits::for_elo(self.elo))
+  }
+}
+
+/// Futility and reverse futility pruning only apply this close to the leaves.
+pub const FUTILITY_MAX_DEPTH: u32 = 3;
+
+/// Shallower iterations are cheap and their scores too rough to aim a window with.
+pub const ASPIRATION_MIN_DEPTH: u32 = 4;
+
+impl Default for SearchConfig {
+  fn default() -> Self {
+    Self {
+      underpromotions: UnderpromotionPolicy::default(),
+      check_extensions: true,
+      max_check_extensions: 16,
+      quiescence_checks: 0,
+      null_move: true,
+      null_move_reduction: 2,
+      null_move_verification: Some(6),
+      late_move_reductions: true,
+      lmr_base: 75,
+      lmr_divisor: 225,
+      futility_pruning: true,
+      futility_margin: 150,
+      reverse_futility_pruning: true,
+      reverse_futility_margin: 120,
+      aspiration_window: 50,
+      deterministic: false,
+      limit_strength: false,
+      elo: 1500,
+      multi_pv: 1,
+      analyse_mode: false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_strength_limits_grow_with_elo() {
+    let weakest = StrengthLimits::for_elo(MIN_ELO);
+    assert_eq!(weakest.max_depth, 1);
+    assert_eq!(weakest.max_nodes, 500);
+    assert_eq!(weakest.eval_noise, 200);
+
+    let strongest = StrengthLimits::for_elo(MAX_ELO);
+    assert_eq!(strongest.max_depth, 9);
+    assert_eq!(strongest.eval_noise, 0);
+    assert!(strongest.max_nodes > StrengthLimits::for_elo(1500).max_nodes);
+
+    // Ratings outside the range are clamped
+    assert_eq!(StrengthLimits::for_elo(0), weakest);
+    assert_eq!(StrengthLimits::for_elo(3500), strongest);
+  }
+
+  #[test]
+  fn test_full_strength_by_default() {
+    assert_eq!(SearchConfig::default().strength_limits(), None);
+    let config = SearchConfig {
+      limit_strength: true,
+      ..Default::default()
+    };
+    assert_eq!(
+      config.strength_limits(),
+      Some(StrengthLimits::for_elo(1500))
+    );
+  }
+}
@@ -0,0 +1,40 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use lumifox_chess::model::piecemove::PieceMove;
+
+use super::{PvLine, SearchResult};
+
+/// Receives progress reports from [`super::Searcher::search_with`].
+///
+/// Every method has an empty default, so implementors only override the events they care about.
+/// Callbacks run on the search thread and should return quickly.
+pub trait SearchListener {
+  /// Called once at the start of every search, before anything is searched.
+  fn search_started(&mut self) {}
+
+  /// Called before each root move is searched. `move_number` starts at 1.
+  fn root_move(&mut self, _piece_move: PieceMove, _move_number: u32) {}
+
+  /// Called after each iteration that searched every line to the end. `result` is the search's
+  /// answer so far and `lines` holds the best move of each MultiPV line in order, best first.
+  fn iteration_complete(&mut self, _result: &SearchResult, _lines: &[PvLine]) {}
+}
+
+/// Ignores every event.
+impl SearchListener for () {}
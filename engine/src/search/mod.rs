@@ -0,0 +1,1378 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Alpha-beta search.
+//!
+//! An iteratively deepened negamax search with alpha-beta pruning and a quiescence search over
+//! captures and promotions. Behaviour that trades accuracy for speed is controlled through
+//! [`SearchConfig`]. Interior nodes order their moves with a [`MovePicker`], fed by killer moves
+//! and a [`History`] table that the search updates on every quiet cutoff.
+
+pub mod config;
+pub mod listener;
+pub mod picker;
+pub mod see;
+pub mod stats;
+pub mod timeman;
+pub mod tunables;
+
+use core::cmp::Reverse;
+use std::{
+  collections::hash_map::RandomState,
+  hash::BuildHasher,
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+};
+
+use lumifox_chess::{
+  legal::attack::is_square_attacked,
+  model::{
+    gameboard::{GameBoard, PieceType},
+    piecemove::{PieceMove, PromotionType},
+  },
+  movegen::{MAX_MOVES, MoveGenFlags, generate_moves_with},
+};
+
+use crate::{
+  eval::{evaluate, evaluate_with, piece_value},
+  tablebase::{Tablebase, wdl_score},
+};
+
+pub use config::{
+  ASPIRATION_MIN_DEPTH, FUTILITY_MAX_DEPTH, MAX_ELO, MAX_MULTI_PV, MIN_ELO, SearchConfig,
+  StrengthLimits, UnderpromotionPolicy,
+};
+pub use listener::SearchListener;
+pub use picker::{History, MovePicker};
+pub use stats::SearchStats;
+pub use timeman::TimeManager;
+pub use tunables::{TUNABLES, Tunable};
+
+/// Larger than any score the search can return.
+pub const INFINITY: i32 = 32_000;
+/// Score of delivering mate at the root; mates further away score `MATE_SCORE - ply`.
+pub const MATE_SCORE: i32 = 31_000;
+/// Hard limit on search depth, including quiescence.
+pub const MAX_PLY: usize = 128;
+
+/// Outcome of a call to [`Searcher::search`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+  /// Best move found, or `None` if the side to move has no legal moves. A stopped search still
+  /// returns a legal move whenever one exists.
+  pub best_move: Option<PieceMove>,
+  /// Score of `best_move` in centipawns from the side to move's perspective.
+  pub score: i32,
+  /// Depth of the deepest iteration that contributed `best_move`; `0` if the search was stopped
+  /// before any root move was searched.
+  pub depth: u32,
+  /// Completed iterations in a row, not counting the first, that kept the same best move. Time
+  /// management stops earlier the longer the best move has been stable.
+  pub stability: u32,
+  /// Number of positions visited.
+  pub nodes: u64,
+  /// Number of positions scored from the tablebase.
+  pub tb_hits: u64,
+}
+
+/// One line of a MultiPV search: a root move and its score, from the side to move's
+/// perspective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PvLine {
+  pub best_move: PieceMove,
+  pub score: i32,
+}
+
+/// Per-ply search state.
+#[derive(Debug, Clone, Copy, Default)]
+struct Frame {
+  /// The move leading to this ply was a null move.
+  null_move: bool,
+  /// Don't try a null move at this ply; set while verifying a null-move cutoff.
+  skip_null: bool,
+  /// The last two quiet moves that caused a cutoff at this ply, most recent first.
+  killers: [PieceMove; 2],
+}
+
+pub struct Searcher {
+  pub config: SearchConfig,
+  nodes: u64,
+  tb_hits: u64,
+  tablebase: Option<Arc<dyn Tablebase + Send + Sync>>,
+  stack: [Frame; MAX_PLY + 1],
+  history: History,
+  /// Late-move reductions, indexed by depth and move number.
+  reductions: [[u8; 64]; 64],
+  /// The `lmr_base` and `lmr_divisor` that `reductions` was built from.
+  reductions_for: (u32, u32),
+  stop: Arc<AtomicBool>,
+  /// The current iteration was cut short by `stop`, so its scores can't be trusted.
+  stopped: bool,
+  /// The search stops once it has visited this many positions.
+  node_limit: u64,
+  /// Node limit set by [`Searcher::set_node_limit`], applied to every search.
+  max_nodes: Option<u64>,
+  /// Most centipawns of noise added to each evaluation in the current search.
+  eval_noise: i32,
+  /// Varies the noise from one search to the next.
+  noise_seed: u64,
+}
+
+impl Default for Searcher {
+  fn default() -> Self {
+    Searcher::new(SearchConfig::default())
+  }
+}
+
+impl Searcher {
+  pub fn new(config: SearchConfig) -> Self {
+    Self {
+      config,
+      nodes: 0,
+      tb_hits: 0,
+      tablebase: None,
+      stack: [Frame::default(); MAX_PLY + 1],
+      history: History::default(),
+      reductions: reduction_table(config.lmr_base, config.lmr_divisor),
+      reductions_for: (config.lmr_base, config.lmr_divisor),
+      stop: Arc::new(AtomicBool::new(false)),
+      stopped: false,
+      node_limit: u64::MAX,
+      max_nodes: None,
+      eval_noise: 0,
+      noise_seed: RandomState::new().hash_one(0u64),
+    }
+  }
+
+  /// Flag that stops the search as soon as possible when set, from any thread. The search then
+  /// returns the best move of the iterations it did finish. The flag is cleared whenever a search
+  /// returns, so a stop requested before a search starts applies to that search only.
+  pub fn stop_handle(&self) -> Arc<AtomicBool> {
+    Arc::clone(&self.stop)
+  }
+
+  /// Stop every following search once it has visited `nodes` positions, as `go nodes` asks, or
+  /// lift the limit with `None`. A strength limit with fewer nodes still applies.
+  pub fn set_node_limit(&mut self, nodes: Option<u64>) {
+    self.max_nodes = nodes;
+  }
+
+  /// Score positions covered by `tablebase` from it instead of searching them.
+  pub fn set_tablebase(&mut self, tablebase: Option<Arc<dyn Tablebase + Send + Sync>>) {
+    self.tablebase = tablebase;
+  }
+
+  pub fn tablebase(&self) -> Option<&Arc<dyn Tablebase + Send + Sync>> {
+    self.tablebase.as_ref()
+  }
+
+  /// Search `board` to `depth` and return the best move found.
+  pub fn search(&mut self, board: &GameBoard, depth: u32) -> SearchResult {
+    self.search_with(board, depth, None, &mut ())
+  }
+
+  /// Like [`Searcher::search`], reporting progress to `listener` as the search runs. If
+  /// `root_moves` is given, only those moves are considered at the root.
+  ///
+  /// The search deepens one ply at a time up to `depth`, trying the previous iteration's best move
+  /// first. Before anything is searched the first legal root move is taken as the answer, so even
+  /// a search stopped straight away has a legal move to play.
+  ///
+  /// With [`SearchConfig::multi_pv`] above 1 each iteration searches the root again for every
+  /// further line, leaving out the moves already reported, and hands the lines to
+  /// [`SearchListener::iteration_complete`]. The first line is the one returned.
+  ///
+  /// With [`SearchConfig::limit_strength`] set, `depth` and the number of nodes are capped and the
+  /// evaluation is made noisy, as [`StrengthLimits`] describes. A deterministic search keeps the
+  /// caps but not the noise.
+  pub fn search_with<L: SearchListener>(
+    &mut self,
+    board: &GameBoard,
+    mut depth: u32,
+    root_moves: Option<&[PieceMove]>,
+    listener: &mut L,
+  ) -> SearchResult {
+    let limits = self.config.strength_limits();
+    if let Some(limits) = limits {
+      depth = depth.min(limits.max_depth);
+    }
+    self.node_limit = limits
+      .map_or(u64::MAX, |limits| limits.max_nodes)
+      .min(self.max_nodes.unwrap_or(u64::MAX));
+    self.eval_noise = match limits {
+      Some(limits) if !self.config.deterministic => limits.eval_noise,
+      _ => 0,
+    };
+    self.noise_seed = mix(self.noise_seed);
+
+    self.nodes = 0;
+    self.tb_hits = 0;
+    self.stopped = false;
+    listener.search_started();
+    self.stack = [Frame::default(); MAX_PLY + 1];
+    self.history.clear();
+    // The config is public, so the tunables may have changed since the last search
+    let lmr = (self.config.lmr_base, self.config.lmr_divisor);
+    if lmr != self.reductions_for {
+      self.reductions = reduction_table(lmr.0, lmr.1);
+      self.reductions_for = lmr;
+    }
+
+    let (moves, count) = self.ordered_moves(board);
+    let legal: Vec<(PieceMove, GameBoard)> = moves[..count]
+      .iter()
+      .filter(|piece_move| root_moves.is_none_or(|root_moves| root_moves.contains(piece_move)))
+      .filter_map(|&piece_move| {
+        let mut child = *board;
+        child.move_piece(&piece_move).map(|_| (piece_move, child))
+      })
+      .collect();
+    // Pruning underpromotions must not leave the root without a move, or it would be scored as
+    // mate or stalemate
+    let pruned = legal
+      .iter()
+      .any(|(piece_move, child)| self.is_searched(piece_move, child));
+    let mut legal: Vec<PieceMove> = legal
+      .into_iter()
+      .filter(|(piece_move, child)| !pruned || self.is_searched(piece_move, child))
+      .map(|(piece_move, _)| piece_move)
+      .collect();
+
+    let mut result = SearchResult {
+      best_move: legal.first().copied(),
+      score: if legal.is_empty() && in_check(board) {
+        -MATE_SCORE
+      } else if legal.is_empty() {
+        0
+      } else {
+        evaluate(board)
+      },
+      depth: 0,
+      stability: 0,
+      nodes: 0,
+      tb_hits: 0,
+    };
+
+    let line_count = (self.config.multi_pv.max(1) as usize).min(legal.len());
+    let iterations = if legal.is_empty() { 0 } else { depth.max(1) };
+    'deepening: for iteration in 1..=iterations {
+      let mut lines: Vec<PvLine> = Vec::with_capacity(line_count);
+      for pv in 0..line_count {
+        // Each line after the first leaves out the moves already reported in this iteration
+        let candidates: Vec<PieceMove> = legal
+          .iter()
+          .copied()
+          .filter(|&piece_move| lines.iter().all(|line| line.best_move != piece_move))
+          .collect();
+        let (best_move, score, complete) = if pv == 0 && result.depth > 0 {
+          self.search_aspirated(board, iteration, &candidates, result.score, listener)
+        } else {
+          self.search_root(board, iteration, &candidates, -INFINITY, INFINITY, listener)
+        };
+        if pv == 0
+          && let Some(best_move) = best_move
+        {
+          if complete {
+            let kept = result.depth > 0 && result.best_move == Some(best_move);
+            result.stability = if kept { result.stability + 1 } else { 0 };
+          }
+          result.best_move = Some(best_move);
+          result.score = score;
+          result.depth = iteration;
+        }
+        let (true, Some(best_move)) = (complete, best_move) else {
+          break 'deepening;
+        };
+        lines.push(PvLine { best_move, score });
+      }
+
+      // Search the best moves first next time, so a stopped iteration starts from them
+      let rest: Vec<PieceMove> = legal
+        .iter()
+        .copied()
+        .filter(|&piece_move| lines.iter().all(|line| line.best_move != piece_move))
+        .collect();
+      legal = lines
+        .iter()
+        .map(|line| line.best_move)
+        .chain(rest)
+        .collect();
+
+      result.nodes = self.nodes;
+      result.tb_hits = self.tb_hits;
+      listener.iteration_complete(&result, &lines);
+    }
+
+    self.stop.store(false, Ordering::Relaxed);
+    result.nodes = self.nodes;
+    result.tb_hits = self.tb_hits;
+    result
+  }
+
+  /// Like [`search_root`](Self::search_root) with the full window, but first tries a window of
+  /// [`SearchConfig::aspiration_window`] around `guess`, the previous iteration's score, doubling
+  /// it on the side that failed until the score lands inside.
+  fn search_aspirated<L: SearchListener>(
+    &mut self,
+    board: &GameBoard,
+    depth: u32,
+    moves: &[PieceMove],
+    guess: i32,
+    listener: &mut L,
+  ) -> (Option<PieceMove>, i32, bool) {
+    let mut window = self.config.aspiration_window;
+    if window <= 0
+      || depth < ASPIRATION_MIN_DEPTH
+      || self.config.analyse_mode
+      || guess.abs() >= MATE_SCORE - MAX_PLY as i32
+    {
+      return self.search_root(board, depth, moves, -INFINITY, INFINITY, listener);
+    }
+    let (mut alpha, mut beta) = (guess - window, guess + window);
+    loop {
+      let (best_move, score, complete) =
+        self.search_root(board, depth, moves, alpha, beta, listener);
+      if !complete || (alpha < score && score < beta) {
+        return (best_move, score, complete);
+      }
+      window = window.saturating_mul(2);
+      if score <= alpha {
+        alpha = guess.saturating_sub(window).max(-INFINITY);
+      } else {
+        beta = guess.saturating_add(window).min(INFINITY);
+      }
+    }
+  }
+
+  /// Search each of `moves` to `depth` within the window `alpha..beta`. Returns the best move
+  /// among those searched to the end, its score, and whether every move was searched before a
+  /// stop. A move scoring `beta` or more ends the search early with that score.
+  fn search_root<L: SearchListener>(
+    &mut self,
+    board: &GameBoard,
+    depth: u32,
+    moves: &[PieceMove],
+    mut alpha: i32,
+    beta: i32,
+    listener: &mut L,
+  ) -> (Option<PieceMove>, i32, bool) {
+    let mut best_move = None;
+    let mut best_score = -INFINITY;
+
+    for (&piece_move, move_number) in moves.iter().zip(1..) {
+      if self.should_stop() {
+        return (best_move, best_score, false);
+      }
+      let mut child = *board;
+      child.move_piece(&piece_move);
+      self.nodes += 1;
+      listener.root_move(piece_move, move_number);
+
+      let score = -self.negamax(&child, depth - 1, -beta, -alpha, 1, 0);
+      if self.stopped {
+        return (best_move, best_score, false);
+      }
+      if best_move.is_none() || score > best_score {
+        best_move = Some(piece_move);
+        best_score = score;
+      }
+      if score >= beta {
+        break;
+      }
+      // In analysis mode every root move keeps the full window
+      if !self.config.analyse_mode {
+        alpha = alpha.max(score);
+      }
+    }
+
+    (best_move, best_score, true)
+  }
+
+  /// Whether the search has been asked to stop or has used up its nodes. Once it has, every node
+  /// returns at once and the current iteration is thrown away. A deterministic search obeys both:
+  /// a stop is how `stop`, `quit` and the clock end a search.
+  fn should_stop(&mut self) -> bool {
+    if !self.stopped && (self.nodes >= self.node_limit || self.stop.load(Ordering::Relaxed)) {
+      self.stopped = true;
+    }
+    self.stopped
+  }
+
+  /// Static evaluation of `board`, exact where the tablebase covers it, plus this search's noise
+  /// when playing at reduced strength. The noise depends only on the position and the search, so
+  /// a position is judged the same way each time the search meets it.
+  fn evaluate(&self, board: &GameBoard) -> i32 {
+    let score = match &self.tablebase {
+      Some(tablebase) => evaluate_with(board, tablebase.as_ref()),
+      None => evaluate(board),
+    };
+    if self.eval_noise == 0 {
+      return score;
+    }
+    let key = [
+      board.pawns,
+      board.knights,
+      board.bishops,
+      board.rooks,
+      board.queens,
+      board.kings,
+      board.colour,
+    ]
+    .into_iter()
+    .fold(self.noise_seed ^ board.playing as u64, |key, bitboard| {
+      mix(key ^ bitboard.raw())
+    });
+    let span = 2 * self.eval_noise as u64 + 1;
+    score + (key % span) as i32 - self.eval_noise
+  }
+
+  /// `extensions` counts the check extensions already applied on the way to `board`.
+  fn negamax(
+    &mut self,
+    board: &GameBoard,
+    mut depth: u32,
+    mut alpha: i32,
+    beta: i32,
+    ply: usize,
+    mut extensions: u32,
+  ) -> i32 {
+    if self.should_stop() {
+      return 0;
+    }
+    let skip_null = core::mem::take(&mut self.stack[ply.min(MAX_PLY)].skip_null);
+    let checked = in_check(board);
+    if self.config.check_extensions
+      && checked
+      && extensions < self.config.max_check_extensions
+      && ply < MAX_PLY
+    {
+      depth += 1;
+      extensions += 1;
+    }
+
+    if let Some(tablebase) = &self.tablebase
+      && tablebase.covers(board)
+      && let Some(wdl) = tablebase.probe_wdl(board)
+    {
+      self.tb_hits += 1;
+      return wdl_score(wdl, ply);
+    }
+
+    if depth == 0 || ply >= MAX_PLY {
+      return self.quiescence(board, alpha, beta, ply, self.config.quiescence_checks);
+    }
+
+    let static_eval = self.evaluate(board);
+    let mate_bound = MATE_SCORE - MAX_PLY as i32;
+
+    // Reverse futility: far enough above beta that a quiet move will not bring us back down
+    if self.config.reverse_futility_pruning
+      && !checked
+      && depth <= FUTILITY_MAX_DEPTH
+      && beta.abs() < mate_bound
+      && static_eval - self.config.reverse_futility_margin * depth as i32 >= beta
+    {
+      return static_eval;
+    }
+
+    if self.config.null_move
+      && !checked
+      && !skip_null
+      && !self.stack[ply].null_move
+      && depth > self.config.null_move_reduction
+      && static_eval >= beta
+      && has_non_pawn_material(board)
+    {
+      let mut child = *board;
+      child.playing = !child.playing;
+      child.en_passant = None;
+      self.stack[ply + 1].null_move = true;
+      let reduced = depth - 1 - self.config.null_move_reduction;
+      let score = -self.negamax(&child, reduced, -beta, -beta + 1, ply + 1, extensions);
+      self.stack[ply + 1].null_move = false;
+
+      if score >= beta {
+        let verified = match self.config.null_move_verification {
+          Some(min_depth) if depth >= min_depth => {
+            self.stack[ply].skip_null = true;
+            let depth = depth - self.config.null_move_reduction;
+            self.negamax(board, depth, beta - 1, beta, ply, extensions) >= beta
+          }
+          _ => true,
+        };
+        if verified {
+          // Don't trust a mate found after passing
+          return if score >= mate_bound { beta } else { score };
+        }
+      }
+    }
+
+    let futile = self.config.futility_pruning
+      && !checked
+      && depth <= FUTILITY_MAX_DEPTH
+      && alpha.abs() < mate_bound
+      && static_eval + self.config.futility_margin * depth as i32 <= alpha;
+
+    let mut has_legal_move = false;
+    let mut searched = 0;
+    let mut best = -INFINITY;
+    let mut pruned = None;
+
+    let picker = MovePicker::new(
+      board,
+      None,
+      &self.stack[ply].killers,
+      &self.history,
+      self.config.underpromotions,
+    );
+    for piece_move in picker {
+      let mut child = *board;
+      if child.move_piece(&piece_move).is_none() {
+        continue;
+      }
+      has_legal_move = true;
+      if !self.is_searched(&piece_move, &child) {
+        pruned.get_or_insert(child);
+        continue;
+      }
+
+      let quiet = !piece_move.is_capture() && !piece_move.is_promotion();
+      let gives_check = quiet && in_check(&child);
+      if futile && quiet && !gives_check && searched > 0 {
+        continue;
+      }
+      self.nodes += 1;
+      searched += 1;
+
+      let reduction = if self.config.late_move_reductions
+        && quiet
+        && !checked
+        && !gives_check
+        && depth >= 3
+        && searched > 3
+      {
+        self.reduction(depth, searched).min(depth - 1)
+      } else {
+        0
+      };
+
+      let mut score = -self.negamax(
+        &child,
+        depth - 1 - reduction,
+        -beta,
+        -alpha,
+        ply + 1,
+        extensions,
+      );
+      if reduction > 0 && score > alpha {
+        score = -self.negamax(&child, depth - 1, -beta, -alpha, ply + 1, extensions);
+      }
+
+      best = best.max(score);
+      alpha = alpha.max(score);
+      if alpha >= beta {
+        if quiet {
+          let killers = &mut self.stack[ply].killers;
+          if killers[0] != piece_move {
+            killers[1] = killers[0];
+            killers[0] = piece_move;
+          }
+          self.history.reward(board.playing, &piece_move, depth);
+        }
+        break;
+      }
+    }
+
+    if !has_legal_move {
+      return if checked { -MATE_SCORE + ply as i32 } else { 0 };
+    }
+
+    // Every legal move was a pruned underpromotion; search one rather than score the position
+    // by its evaluation
+    if searched == 0
+      && let Some(child) = pruned
+    {
+      self.nodes += 1;
+      return -self.negamax(&child, depth - 1, -beta, -alpha, ply + 1, extensions);
+    }
+
+    // Every move was pruned as futile; the position is no better than its evaluation
+    if searched == 0 {
+      return static_eval;
+    }
+
+    best
+  }
+
+  fn reduction(&self, depth: u32, move_number: usize) -> u32 {
+    self.reductions[(depth as usize).min(63)][move_number.min(63)] as u32
+  }
+
+  /// `checks_left` is how many more quiet checking moves this line may play.
+  fn quiescence(
+    &mut self,
+    board: &GameBoard,
+    mut alpha: i32,
+    beta: i32,
+    ply: usize,
+    checks_left: u32,
+  ) -> i32 {
+    if self.should_stop() {
+      return 0;
+    }
+    if ply >= MAX_PLY {
+      return self.evaluate(board);
+    }
+
+    // With quiet checks in play a check must be answered properly, or quiescence would stand
+    // pat in positions that are actually mate
+    let evading = self.config.quiescence_checks > 0 && in_check(board);
+    if !evading {
+      let stand_pat = self.evaluate(board);
+      if stand_pat >= beta {
+        return stand_pat;
+      }
+      alpha = alpha.max(stand_pat);
+    }
+
+    let mut has_legal_move = false;
+    let flags = if evading || checks_left > 0 {
+      MoveGenFlags::ALL
+    } else {
+      MoveGenFlags::CAPTURES_ONLY
+    };
+    let (moves, count) = self.ordered_moves_with(board, flags);
+    for &piece_move in moves.iter().take(count) {
+      let tactical = piece_move.is_capture() || piece_move.is_promotion();
+      if !evading && !tactical && checks_left == 0 {
+        continue;
+      }
+      let mut child = *board;
+      if child.move_piece(&piece_move).is_none() {
+        continue;
+      }
+      has_legal_move = true;
+
+      let (searched, child_checks) = if evading {
+        (self.is_searched(&piece_move, &child), checks_left)
+      } else if tactical {
+        (
+          self.is_searched_in_quiescence(&piece_move, &child),
+          checks_left,
+        )
+      } else {
+        (in_check(&child), checks_left - 1)
+      };
+      if !searched {
+        continue;
+      }
+      self.nodes += 1;
+
+      let score = -self.quiescence(&child, -beta, -alpha, ply + 1, child_checks);
+      if score >= beta {
+        return score;
+      }
+      alpha = alpha.max(score);
+    }
+
+    if evading && !has_legal_move {
+      return -MATE_SCORE + ply as i32;
+    }
+
+    alpha
+  }
+
+  /// Whether the main search looks at `piece_move`. `child` is the position after the move.
+  fn is_searched(&self, piece_move: &PieceMove, child: &GameBoard) -> bool {
+    match (self.config.underpromotions, piece_move.promotion_type()) {
+      (_, None | Some(PromotionType::Queen)) => true,
+      (UnderpromotionPolicy::Prune, Some(PromotionType::Knight)) => in_check(child),
+      (UnderpromotionPolicy::Prune, Some(_)) => false,
+      _ => true,
+    }
+  }
+
+  /// Whether quiescence looks at `piece_move`. `child` is the position after the move.
+  fn is_searched_in_quiescence(&self, piece_move: &PieceMove, child: &GameBoard) -> bool {
+    match (self.config.underpromotions, piece_move.promotion_type()) {
+      (_, None | Some(PromotionType::Queen)) => true,
+      (UnderpromotionPolicy::SearchAll, Some(_)) => true,
+      (_, Some(PromotionType::Knight)) => in_check(child),
+      (_, Some(_)) => false,
+    }
+  }
+
+  /// Generate pseudo-legal moves for `board`, best candidates first.
+  fn ordered_moves(&self, board: &GameBoard) -> ([PieceMove; MAX_MOVES], usize) {
+    self.ordered_moves_with(board, MoveGenFlags::ALL)
+  }
+
+  /// Like [`ordered_moves`](Self::ordered_moves), only the moves `flags` allows.
+  fn ordered_moves_with(
+    &self,
+    board: &GameBoard,
+    flags: MoveGenFlags,
+  ) -> ([PieceMove; MAX_MOVES], usize) {
+    let (mut moves, count) = generate_moves_with(board, flags);
+    // Stable sort, so moves with equal scores keep the generator's order
+    moves[..count].sort_by_key(|piece_move| Reverse(self.order_score(board, piece_move)));
+    (moves, count)
+  }
+
+  fn order_score(&self, board: &GameBoard, piece_move: &PieceMove) -> i32 {
+    let mut score = 0;
+
+    if piece_move.is_capture() {
+      // MVV-LVA
+      let victim = board.captured_piece(piece_move).unwrap_or(PieceType::Pawn);
+      let attacker = board
+        .get_piece(piece_move.from_square())
+        .unwrap_or(PieceType::Pawn);
+      score += 10_000 + 10 * piece_value(victim) - piece_value(attacker);
+    }
+
+    match (self.config.underpromotions, piece_move.promotion_type()) {
+      (_, None) => {}
+      (_, Some(PromotionType::Queen)) | (UnderpromotionPolicy::SearchAll, Some(_)) => {
+        score += 9_000
+      }
+      (_, Some(_)) => score -= 100_000,
+    }
+
+    score
+  }
+}
+
+/// `reductions[depth][move_number]` for late-move reductions, from [`SearchConfig::lmr_base`] and
+/// [`SearchConfig::lmr_divisor`].
+fn reduction_table(base: u32, divisor: u32) -> [[u8; 64]; 64] {
+  let base = base as f64 / 100.0;
+  let divisor = divisor.max(1) as f64 / 100.0;
+  let mut table = [[0; 64]; 64];
+  for (depth, row) in table.iter_mut().enumerate().skip(1) {
+    for (move_number, reduction) in row.iter_mut().enumerate().skip(1) {
+      let r = base + (depth as f64).ln() * (move_number as f64).ln() / divisor;
+      *reduction = r as u8;
+    }
+  }
+  table
+}
+
+/// SplitMix64's output function: a cheap, well-spread 64-bit hash.
+pub(crate) fn mix(mut x: u64) -> u64 {
+  x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+  x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+  x ^ (x >> 31)
+}
+
+/// Whether the side to move has anything besides pawns and its king. Null-move pruning is unsafe
+/// without it, since pawn endings are full of zugzwang.
+fn has_non_pawn_material(board: &GameBoard) -> bool {
+  board
+    .material_signature()
+    .has_non_pawn_material(board.playing)
+}
+
+/// Whether the side to move in `board` is in check.
+pub(crate) fn in_check(board: &GameBoard) -> bool {
+  board
+    .king_square(board.playing)
+    .is_some_and(|king| is_square_attacked(board, king))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::tablebase::{TB_WIN_SCORE, tests::QueenEnding};
+  use lumifox_chess::{constants::*, model::gamedata::GameData, movegen::generate_moves};
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  fn searcher(underpromotions: UnderpromotionPolicy) -> Searcher {
+    Searcher::new(SearchConfig {
+      underpromotions,
+      ..Default::default()
+    })
+  }
+
+  fn promotions(searcher: &Searcher, board: &GameBoard) -> Vec<PromotionType> {
+    let (moves, count) = searcher.ordered_moves(board);
+    moves[..count]
+      .iter()
+      .filter_map(|piece_move| piece_move.promotion_type())
+      .collect()
+  }
+
+  #[test]
+  fn test_finds_mate_in_one() {
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let result = Searcher::default().search(&board, 2);
+    assert_eq!(result.best_move, Some(PieceMove::new(E1, E8, false, None)));
+    assert_eq!(result.score, MATE_SCORE - 1);
+  }
+
+  #[test]
+  fn test_kingless_side_searches_normally() {
+    // Composed fragments may leave out a king; the side without one is never in check
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/4R3 w - - 0 1");
+    let result = Searcher::default().search(&board, 2);
+    assert_eq!(result.best_move, Some(PieceMove::new(E1, E8, false, None)));
+    assert_eq!(result.score, MATE_SCORE - 1);
+  }
+
+  #[test]
+  fn test_check_extension_sees_mate_at_horizon() {
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let result = Searcher::default().search(&board, 1);
+    assert_eq!(result.best_move, Some(PieceMove::new(E1, E8, false, None)));
+    assert_eq!(result.score, MATE_SCORE - 1);
+
+    let mut searcher = Searcher::new(SearchConfig {
+      check_extensions: false,
+      ..Default::default()
+    });
+    assert!(searcher.search(&board, 1).score < MATE_SCORE - MAX_PLY as i32);
+  }
+
+  #[test]
+  fn test_check_extensions_are_limited() {
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let mut searcher = Searcher::new(SearchConfig {
+      max_check_extensions: 0,
+      ..Default::default()
+    });
+    assert!(searcher.search(&board, 1).score < MATE_SCORE - MAX_PLY as i32);
+  }
+
+  #[test]
+  fn test_quiescence_checks_find_mate() {
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let mut searcher = Searcher::new(SearchConfig {
+      quiescence_checks: 1,
+      ..Default::default()
+    });
+    assert_eq!(
+      searcher.quiescence(&board, -INFINITY, INFINITY, 0, 1),
+      MATE_SCORE - 1
+    );
+
+    // Without quiet checks quiescence only sees the material
+    let mut searcher = Searcher::default();
+    assert_eq!(
+      searcher.quiescence(&board, -INFINITY, INFINITY, 0, 0),
+      evaluate(&board)
+    );
+  }
+
+  fn without_pruning() -> SearchConfig {
+    SearchConfig {
+      null_move: false,
+      late_move_reductions: false,
+      futility_pruning: false,
+      reverse_futility_pruning: false,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_pruning_keeps_tactics() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1");
+    let mate = PieceMove::new(H5, F7, true, None);
+    let configs = [
+      SearchConfig::default(),
+      without_pruning(),
+      SearchConfig {
+        null_move: true,
+        ..without_pruning()
+      },
+      SearchConfig {
+        late_move_reductions: true,
+        ..without_pruning()
+      },
+      SearchConfig {
+        futility_pruning: true,
+        ..without_pruning()
+      },
+      SearchConfig {
+        reverse_futility_pruning: true,
+        ..without_pruning()
+      },
+    ];
+    for config in configs {
+      let result = Searcher::new(config).search(&board, 4);
+      assert_eq!(result.best_move, Some(mate), "{config:?}");
+      assert_eq!(result.score, MATE_SCORE - 1, "{config:?}");
+    }
+  }
+
+  #[test]
+  fn test_pruning_saves_nodes() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1");
+    let pruned = Searcher::default().search(&board, 4).nodes;
+    let full = Searcher::new(without_pruning()).search(&board, 4).nodes;
+    assert!(pruned < full, "{pruned} >= {full}");
+  }
+
+  #[test]
+  fn test_reduction_table() {
+    let searcher = Searcher::default();
+    assert_eq!(searcher.reduction(1, 1), 0);
+    assert_eq!(searcher.reduction(3, 4), 1);
+    assert!(searcher.reduction(10, 30) > searcher.reduction(3, 4));
+    assert!(searcher.reduction(200, 200) <= 63);
+
+    // Tuned terms take effect from the next search
+    let mut tuned = Searcher::default();
+    tuned.config.lmr_base = 200;
+    assert_eq!(tuned.reduction(3, 4), 1);
+    tuned.search(&board_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), 1);
+    assert_eq!(tuned.reduction(3, 4), 2);
+  }
+
+  #[test]
+  fn test_aspiration_windows_agree() {
+    // Winning the queen is clear at any window
+    let board = board_from_fen("4k3/8/8/3q4/8/8/3R4/3RK3 w - - 0 1");
+    let deterministic = SearchConfig {
+      deterministic: true,
+      ..Default::default()
+    };
+    let aspirated = Searcher::new(deterministic).search(&board, 6);
+    let full = Searcher::new(SearchConfig {
+      aspiration_window: 0,
+      ..deterministic
+    })
+    .search(&board, 6);
+    assert_eq!(aspirated.best_move, full.best_move);
+    assert_eq!(aspirated.best_move.map(|m| m.to_square()), Some(D5));
+  }
+
+  #[test]
+  fn test_null_move_needs_pieces() {
+    assert!(!has_non_pawn_material(&board_from_fen(
+      "4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1"
+    )));
+    assert!(has_non_pawn_material(&board_from_fen(
+      "4k3/4p3/8/8/8/8/4P3/4KN2 w - - 0 1"
+    )));
+    // Only the side to move counts
+    assert!(!has_non_pawn_material(&board_from_fen(
+      "4kn2/4p3/8/8/8/8/4P3/4K3 w - - 0 1"
+    )));
+  }
+
+  #[test]
+  fn test_listener_sees_each_root_move() {
+    struct Recorder(Vec<(PieceMove, u32)>);
+    impl SearchListener for Recorder {
+      fn root_move(&mut self, piece_move: PieceMove, move_number: u32) {
+        self.0.push((piece_move, move_number));
+      }
+    }
+
+    let mut recorder = Recorder(Vec::new());
+    Searcher::default().search_with(&GameBoard::START_POS, 1, None, &mut recorder);
+    assert_eq!(recorder.0.len(), 20);
+    assert!(recorder.0.iter().zip(1..).all(|(&(_, n), i)| n == i));
+  }
+
+  /// Sets the stop flag when root move `at` is about to be searched.
+  struct StopAt {
+    stop: Arc<AtomicBool>,
+    at: u32,
+  }
+
+  impl SearchListener for StopAt {
+    fn root_move(&mut self, _piece_move: PieceMove, move_number: u32) {
+      if move_number == self.at {
+        self.stop.store(true, Ordering::Relaxed);
+      }
+    }
+  }
+
+  fn is_legal(board: &GameBoard, piece_move: PieceMove) -> bool {
+    let (moves, count) = generate_moves(board);
+    let mut child = *board;
+    moves[..count].contains(&piece_move) && child.move_piece(&piece_move).is_some()
+  }
+
+  #[test]
+  fn test_stop_before_search_still_moves() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1");
+    let mut searcher = Searcher::default();
+    searcher.stop_handle().store(true, Ordering::Relaxed);
+    let result = searcher.search(&board, 6);
+    assert!(is_legal(&board, result.best_move.unwrap()));
+    assert_eq!(result.depth, 0);
+    assert_eq!(result.nodes, 0);
+
+    // The stop only applied to that search
+    assert!(!searcher.stop_handle().load(Ordering::Relaxed));
+    assert_eq!(searcher.search(&board, 2).depth, 2);
+  }
+
+  #[test]
+  fn test_stop_mid_iteration_keeps_a_legal_move() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1");
+    for at in 1..=3 {
+      let mut searcher = Searcher::default();
+      let mut listener = StopAt {
+        stop: searcher.stop_handle(),
+        at,
+      };
+      let result = searcher.search_with(&board, 6, None, &mut listener);
+      assert!(
+        is_legal(&board, result.best_move.unwrap()),
+        "stopped at {at}"
+      );
+      // The first iteration never gets to finish
+      assert!(result.depth <= 1, "stopped at {at}");
+    }
+  }
+
+  #[test]
+  fn test_stop_keeps_mate_from_finished_iteration() {
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let mut searcher = Searcher::default();
+    // Stop on the second root move of the second iteration; the mate found at depth 1 is
+    // searched first and survives
+    struct StopLater(Arc<AtomicBool>, u32);
+    impl SearchListener for StopLater {
+      fn root_move(&mut self, _piece_move: PieceMove, _move_number: u32) {
+        self.1 += 1;
+        if self.1 == 18 {
+          self.0.store(true, Ordering::Relaxed);
+        }
+      }
+    }
+    let mut listener = StopLater(searcher.stop_handle(), 0);
+    let result = searcher.search_with(&board, 5, None, &mut listener);
+    assert_eq!(result.best_move, Some(PieceMove::new(E1, E8, false, None)));
+    assert_eq!(result.score, MATE_SCORE - 1);
+    assert!(result.depth >= 1);
+  }
+
+  #[test]
+  fn test_deterministic_search_repeats_exactly() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1");
+    let config = SearchConfig {
+      deterministic: true,
+      ..Default::default()
+    };
+    let mut searcher = Searcher::new(config);
+    let first = searcher.search(&board, 4);
+    // Searching again with the same searcher must not be influenced by the first search
+    assert_eq!(searcher.search(&board, 4), first);
+    assert_eq!(Searcher::new(config).search(&board, 4), first);
+  }
+
+  #[test]
+  fn test_deterministic_search_still_stops() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1");
+    let config = SearchConfig {
+      deterministic: true,
+      ..Default::default()
+    };
+    let expected = Searcher::new(config).search(&board, 3);
+
+    assert_eq!(Searcher::new(config).search(&board, 3), expected);
+
+    let mut searcher = Searcher::new(config);
+    let mut listener = StopAt {
+      stop: searcher.stop_handle(),
+      at: 2,
+    };
+    let stopped = searcher.search_with(&board, 3, None, &mut listener);
+    assert!(stopped.nodes < expected.nodes);
+    assert!(is_legal(&board, stopped.best_move.unwrap()));
+  }
+
+  fn limited(elo: u32, deterministic: bool) -> Searcher {
+    Searcher::new(SearchConfig {
+      limit_strength: true,
+      elo,
+      deterministic,
+      ..Default::default()
+    })
+  }
+
+  #[test]
+  fn test_strength_limits_cap_depth_and_nodes() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1");
+    let result = limited(MIN_ELO, false).search(&board, 8);
+    assert!(is_legal(&board, result.best_move.unwrap()));
+    assert!(result.depth <= 1);
+    // The stop is noticed at the next node after the budget runs out
+    assert!(result.nodes <= StrengthLimits::for_elo(MIN_ELO).max_nodes + 1);
+
+    let result = limited(1600, true).search(&board, 8);
+    assert!(result.depth <= StrengthLimits::for_elo(1600).max_depth);
+  }
+
+  #[test]
+  fn test_node_limit() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1");
+    let mut searcher = Searcher::default();
+    searcher.set_node_limit(Some(1_000));
+    let result = searcher.search(&board, 8);
+    assert!(is_legal(&board, result.best_move.unwrap()));
+    // Moves already being looped over when the limit is hit are still counted
+    assert!((1_000..1_100).contains(&result.nodes));
+
+    // The smaller of the node and strength limits applies
+    let mut searcher = limited(MIN_ELO, false);
+    searcher.set_node_limit(Some(1_000_000));
+    let result = searcher.search(&board, 8);
+    assert!(result.nodes <= StrengthLimits::for_elo(MIN_ELO).max_nodes + 1);
+
+    searcher.set_node_limit(None);
+    searcher.config.limit_strength = false;
+    assert!(searcher.search(&board, 3).nodes > 1_100);
+  }
+
+  #[test]
+  fn test_strength_noise_is_consistent_and_bounded() {
+    let board =
+      board_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1");
+    let mut searcher = limited(MIN_ELO, false);
+    searcher.search(&board, 1);
+    let noise = StrengthLimits::for_elo(MIN_ELO).eval_noise;
+    let noisy = searcher.evaluate(&board);
+    assert_eq!(searcher.evaluate(&board), noisy);
+    assert!((noisy - evaluate(&board)).abs() <= noise);
+
+    // Deterministic mode and full strength evaluate exactly
+    let mut searcher = limited(MIN_ELO, true);
+    searcher.search(&board, 1);
+    assert_eq!(searcher.evaluate(&board), evaluate(&board));
+    let mut searcher = Searcher::default();
+    searcher.search(&board, 1);
+    assert_eq!(searcher.evaluate(&board), evaluate(&board));
+  }
+
+  #[test]
+  fn test_multi_pv_lines() {
+    struct Lines(Vec<(u32, Vec<PvLine>)>);
+    impl SearchListener for Lines {
+      fn iteration_complete(&mut self, result: &SearchResult, lines: &[PvLine]) {
+        self.0.push((result.depth, lines.to_vec()));
+      }
+    }
+
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let mut searcher = Searcher::new(SearchConfig {
+      multi_pv: 3,
+      ..Default::default()
+    });
+    let mut lines = Lines(Vec::new());
+    let result = searcher.search_with(&board, 3, None, &mut lines);
+    assert_eq!(lines.0.len(), 3);
+    for (depth, iteration) in lines.0.iter().zip(1..) {
+      assert_eq!(depth.0, iteration);
+      let pv = &depth.1;
+      assert_eq!(pv.len(), 3);
+      assert_eq!(pv[0].best_move, PieceMove::new(E1, E8, false, None));
+      assert!(pv[0].best_move != pv[1].best_move && pv[1].best_move != pv[2].best_move);
+      assert!(pv[2].best_move != pv[0].best_move);
+      assert!(pv[0].score >= pv[1].score && pv[1].score >= pv[2].score);
+    }
+    assert_eq!(result.best_move, Some(PieceMove::new(E1, E8, false, None)));
+    assert_eq!(result.score, MATE_SCORE - 1);
+
+    // Never more lines than legal moves
+    let mut lines = Lines(Vec::new());
+    let only = PieceMove::new(A1, B1, false, None);
+    searcher.search_with(&board, 1, Some(&[only]), &mut lines);
+    assert_eq!(
+      lines.0[0].1,
+      [PvLine {
+        best_move: only,
+        score: lines.0[0].1[0].score
+      }]
+    );
+  }
+
+  #[test]
+  fn test_analyse_mode_searches_root_moves_fully() {
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let playing = Searcher::default().search(&board, 3);
+    let analysing = Searcher::new(SearchConfig {
+      analyse_mode: true,
+      ..Default::default()
+    })
+    .search(&board, 3);
+    assert_eq!(analysing.best_move, playing.best_move);
+    assert_eq!(analysing.score, playing.score);
+    assert!(analysing.nodes > playing.nodes);
+  }
+
+  #[test]
+  fn test_stability_counts_unchanged_iterations() {
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let result = Searcher::default().search(&board, 4);
+    assert_eq!(result.depth, 4);
+    assert_eq!(result.stability, 3);
+    assert_eq!(Searcher::default().search(&board, 1).stability, 0);
+  }
+
+  #[test]
+  fn test_root_moves_restrict_search() {
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+    let quiet = PieceMove::new(A1, B1, false, None);
+    let result = Searcher::default().search_with(&board, 2, Some(&[quiet]), &mut ());
+    assert_eq!(result.best_move, Some(quiet));
+  }
+
+  #[test]
+  fn test_tablebase_scores_interior_nodes() {
+    // Qxd5 leaves KQ vs K, which the tables know to be won
+    let board = board_from_fen("7k/8/8/3r4/8/1Q6/8/K7 w - - 0 1");
+    let mut searcher = Searcher::default();
+    searcher.set_tablebase(Some(Arc::new(QueenEnding)));
+    let result = searcher.search(&board, 2);
+    assert_eq!(result.best_move, Some(PieceMove::new(B3, D5, true, None)));
+    assert_eq!(result.score, TB_WIN_SCORE - 1);
+    assert!(result.tb_hits > 0);
+  }
+
+  #[test]
+  fn test_no_legal_moves() {
+    // Checkmated
+    let board = board_from_fen("R5k1/5ppp/8/8/8/8/8/K7 b - - 0 1");
+    let result = Searcher::default().search(&board, 2);
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, -MATE_SCORE);
+
+    // Stalemated
+    let board = board_from_fen("7k/5Q2/8/8/8/8/8/K7 b - - 0 1");
+    let result = Searcher::default().search(&board, 2);
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, 0);
+  }
+
+  #[test]
+  fn test_search_all_keeps_generator_order() {
+    let board = board_from_fen("8/4P3/8/8/8/k7/8/K7 w - - 0 1");
+    let searcher = searcher(UnderpromotionPolicy::SearchAll);
+    assert_eq!(
+      promotions(&searcher, &board),
+      [
+        PromotionType::Queen,
+        PromotionType::Rook,
+        PromotionType::Bishop,
+        PromotionType::Knight
+      ]
+    );
+    let (moves, _) = searcher.ordered_moves(&board);
+    assert!(
+      moves[..4]
+        .iter()
+        .all(|piece_move| piece_move.is_promotion())
+    );
+  }
+
+  #[test]
+  fn test_delay_orders_underpromotions_last() {
+    let board = board_from_fen("8/4P3/8/8/8/k7/8/K7 w - - 0 1");
+    let searcher = searcher(UnderpromotionPolicy::Delay);
+    let (moves, count) = searcher.ordered_moves(&board);
+    assert_eq!(moves[0].promotion_type(), Some(PromotionType::Queen));
+    assert!(
+      moves[count - 3..count]
+        .iter()
+        .all(|piece_move| piece_move.is_promotion())
+    );
+    assert!(
+      moves[1..count - 3]
+        .iter()
+        .all(|piece_move| !piece_move.is_promotion())
+    );
+  }
+
+  #[test]
+  fn test_prune_only_keeps_checking_knight_promotions() {
+    let searcher = searcher(UnderpromotionPolicy::Prune);
+    let promote = |board: &GameBoard, promotion| {
+      let piece_move = PieceMove::new(E7, E8, false, Some(promotion));
+      let mut child = *board;
+      child.move_piece(&piece_move).unwrap();
+      (piece_move, child)
+    };
+
+    // e8=N gives check to the king on d6
+    let board = board_from_fen("8/4P3/3k4/8/8/8/8/K7 w - - 0 1");
+    let (piece_move, child) = promote(&board, PromotionType::Knight);
+    assert!(searcher.is_searched(&piece_move, &child));
+    assert!(searcher.is_searched_in_quiescence(&piece_move, &child));
+    for promotion in [PromotionType::Rook, PromotionType::Bishop] {
+      let (piece_move, child) = promote(&board, promotion);
+      assert!(!searcher.is_searched(&piece_move, &child));
+      assert!(!searcher.is_searched_in_quiescence(&piece_move, &child));
+    }
+
+    // Quiet knight promotion
+    let board = board_from_fen("8/4P3/8/8/8/k7/8/K7 w - - 0 1");
+    let (piece_move, child) = promote(&board, PromotionType::Knight);
+    assert!(!searcher.is_searched(&piece_move, &child));
+
+    let (piece_move, child) = promote(&board, PromotionType::Queen);
+    assert!(searcher.is_searched(&piece_move, &child));
+  }
+
+  #[test]
+  fn test_prune_keeps_the_last_root_move() {
+    // searchmoves leaves only underpromotions, which Prune would otherwise drop
+    let board = board_from_fen("8/4P3/8/8/8/k7/8/K7 w - - 0 1");
+    let rook = PieceMove::new(E7, E8, false, Some(PromotionType::Rook));
+    let bishop = PieceMove::new(E7, E8, false, Some(PromotionType::Bishop));
+    let mut searcher = searcher(UnderpromotionPolicy::Prune);
+    let result = searcher.search_with(&board, 3, Some(&[rook, bishop]), &mut ());
+    assert!(result.best_move == Some(rook) || result.best_move == Some(bishop));
+    assert!(result.score > 0, "{}", result.score);
+  }
+
+  #[test]
+  fn test_policies_agree_on_simple_promotion() {
+    let board = board_from_fen("8/4P3/8/8/8/k7/8/K7 w - - 0 1");
+    for policy in [
+      UnderpromotionPolicy::SearchAll,
+      UnderpromotionPolicy::Delay,
+      UnderpromotionPolicy::Prune,
+    ] {
+      let result = searcher(policy).search(&board, 2);
+      assert_eq!(
+        result.best_move,
+        Some(PieceMove::new(E7, E8, false, Some(PromotionType::Queen))),
+        "{policy:?}"
+      );
+    }
+  }
+}
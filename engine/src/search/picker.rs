@@ -0,0 +1,333 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Staged move ordering.
+//!
+//! A [`MovePicker`] hands out the moves of a position best-first, one at a time: the
+//! transposition table move, captures that win or break even by [`see`], killer moves, the
+//! remaining quiet moves by [`History`] score, captures that lose material and finally
+//! underpromotions the search wants to delay. Each call to `next` selects the best remaining move
+//! instead of sorting the whole list up front, so a node that cuts off after the first move or
+//! two does not pay for ordering the rest.
+
+use lumifox_chess::{
+  model::{
+    gameboard::{GameBoard, PieceType},
+    piecemove::{PieceMove, PromotionType},
+  },
+  movegen::{MAX_MOVES, generate_moves},
+};
+
+use super::{UnderpromotionPolicy, see::see};
+use crate::eval::piece_value;
+
+/// Largest magnitude a [`History`] score reaches.
+pub const MAX_HISTORY: i32 = 16_384;
+
+/// Groups of moves a [`MovePicker`] yields, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+  TtMove,
+  /// Captures that don't lose material, most valuable victim first, and promotions.
+  GoodCaptures,
+  /// Quiet moves that caused a cutoff at the same ply elsewhere in the tree.
+  Killers,
+  /// Other quiet moves, by history score.
+  Quiets,
+  /// Captures that lose material by static exchange, least bad first.
+  BadCaptures,
+  /// Underpromotions, unless the search treats them like queen promotions.
+  Underpromotions,
+}
+
+/// How often quiet moves caused a cutoff, indexed by side, from and to square. Deeper cutoffs
+/// count for more, and scores decay as they approach [`MAX_HISTORY`] so old results fade.
+#[derive(Clone)]
+pub struct History {
+  scores: [[[i32; 64]; 64]; 2],
+}
+
+impl Default for History {
+  fn default() -> Self {
+    Self {
+      scores: [[[0; 64]; 64]; 2],
+    }
+  }
+}
+
+impl History {
+  pub fn score(&self, white: bool, piece_move: &PieceMove) -> i32 {
+    self.scores[white as usize][piece_move.from_square() as usize][piece_move.to_square() as usize]
+  }
+
+  /// Record that `piece_move` caused a cutoff `depth` plies from the leaves.
+  pub fn reward(&mut self, white: bool, piece_move: &PieceMove, depth: u32) {
+    let entry = &mut self.scores[white as usize][piece_move.from_square() as usize]
+      [piece_move.to_square() as usize];
+    let bonus = (depth * depth).min(MAX_HISTORY as u32) as i32;
+    *entry += bonus - *entry * bonus / MAX_HISTORY;
+  }
+
+  pub fn clear(&mut self) {
+    *self = Self::default();
+  }
+}
+
+/// Yields the pseudo-legal moves of a position in [`Stage`] order; see the module docs.
+pub struct MovePicker {
+  moves: [PieceMove; MAX_MOVES],
+  keys: [(Stage, i32); MAX_MOVES],
+  len: usize,
+  next: usize,
+}
+
+impl MovePicker {
+  /// `tt_move` and `killers` are only yielded if they are pseudo-legal in `board`, so stale
+  /// entries are harmless. Use [`PieceMove::NULL`] for an empty killer slot.
+  pub fn new(
+    board: &GameBoard,
+    tt_move: Option<PieceMove>,
+    killers: &[PieceMove; 2],
+    history: &History,
+    underpromotions: UnderpromotionPolicy,
+  ) -> Self {
+    let (moves, len) = generate_moves(board);
+    let mut keys = [(Stage::Quiets, 0); MAX_MOVES];
+    for (key, piece_move) in keys.iter_mut().zip(&moves[..len]) {
+      *key = classify(
+        board,
+        piece_move,
+        tt_move,
+        killers,
+        history,
+        underpromotions,
+      );
+    }
+    Self {
+      moves,
+      keys,
+      len,
+      next: 0,
+    }
+  }
+}
+
+impl Iterator for MovePicker {
+  type Item = PieceMove;
+
+  fn next(&mut self) -> Option<PieceMove> {
+    if self.next >= self.len {
+      return None;
+    }
+    // Earliest of the best, so moves that score the same keep the generator's order
+    let mut best = self.next;
+    for i in self.next + 1..self.len {
+      let (stage, score) = self.keys[i];
+      let (best_stage, best_score) = self.keys[best];
+      if stage < best_stage || (stage == best_stage && score > best_score) {
+        best = i;
+      }
+    }
+    self.moves[self.next..=best].rotate_right(1);
+    self.keys[self.next..=best].rotate_right(1);
+    self.next += 1;
+    Some(self.moves[self.next - 1])
+  }
+}
+
+fn classify(
+  board: &GameBoard,
+  piece_move: &PieceMove,
+  tt_move: Option<PieceMove>,
+  killers: &[PieceMove; 2],
+  history: &History,
+  underpromotions: UnderpromotionPolicy,
+) -> (Stage, i32) {
+  if tt_move == Some(*piece_move) {
+    return (Stage::TtMove, 0);
+  }
+
+  // MVV-LVA
+  let mvv_lva = || {
+    let victim = board.captured_piece(piece_move);
+    let attacker = board.get_piece(piece_move.from_square());
+    10 * victim.map_or(piece_value(PieceType::Pawn), piece_value) - attacker.map_or(0, piece_value)
+  };
+
+  match piece_move.promotion_type() {
+    Some(promotion)
+      if promotion != PromotionType::Queen
+        && underpromotions != UnderpromotionPolicy::SearchAll =>
+    {
+      return (Stage::Underpromotions, 0);
+    }
+    Some(_) => {
+      let capture = if piece_move.is_capture() {
+        mvv_lva()
+      } else {
+        0
+      };
+      return (Stage::GoodCaptures, 10_000 + capture);
+    }
+    None => {}
+  }
+
+  if piece_move.is_capture() {
+    let exchange = see(board, piece_move);
+    return if exchange >= 0 {
+      (Stage::GoodCaptures, mvv_lva())
+    } else {
+      (Stage::BadCaptures, exchange)
+    };
+  }
+
+  if *piece_move != PieceMove::NULL
+    && let Some(slot) = killers.iter().position(|killer| killer == piece_move)
+  {
+    return (Stage::Killers, -(slot as i32));
+  }
+  (Stage::Quiets, history.score(board.playing, piece_move))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lumifox_chess::{constants::*, model::gamedata::GameData};
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  fn stages(board: &GameBoard, moves: &[PieceMove], killers: &[PieceMove; 2]) -> Vec<Stage> {
+    moves
+      .iter()
+      .map(|piece_move| {
+        classify(
+          board,
+          piece_move,
+          None,
+          killers,
+          &History::default(),
+          UnderpromotionPolicy::Delay,
+        )
+        .0
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_yields_every_move_once() {
+    let board =
+      board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let (moves, count) = generate_moves(&board);
+    let mut picked: Vec<_> = MovePicker::new(
+      &board,
+      None,
+      &[PieceMove::NULL; 2],
+      &History::default(),
+      UnderpromotionPolicy::Delay,
+    )
+    .collect();
+    let mut generated = moves[..count].to_vec();
+    picked.sort();
+    generated.sort();
+    assert_eq!(picked, generated);
+  }
+
+  #[test]
+  fn test_stage_order() {
+    // Rook takes the free knight on a5, queen takes the defended pawn on d6, b7 promotes
+    let board = board_from_fen("4k3/1P2p3/3p4/n7/8/8/8/R2QK3 w - - 0 1");
+    let tt_move = PieceMove::new(E1, F2, false, None);
+    let killer = PieceMove::new(A1, B1, false, None);
+    let killers = [killer, PieceMove::NULL];
+    let order: Vec<_> = MovePicker::new(
+      &board,
+      Some(tt_move),
+      &killers,
+      &History::default(),
+      UnderpromotionPolicy::Delay,
+    )
+    .collect();
+
+    assert_eq!(order[0], tt_move);
+    assert_eq!(order[1].promotion_type(), Some(PromotionType::Queen));
+    assert_eq!(order[2], PieceMove::new(A1, A5, true, None));
+    assert_eq!(order[3], killer);
+    assert_eq!(order[order.len() - 4], PieceMove::new(D1, D6, true, None));
+
+    let stages = stages(&board, &order, &killers);
+    assert!(stages[1..].is_sorted());
+    assert_eq!(stages.last(), Some(&Stage::Underpromotions));
+  }
+
+  #[test]
+  fn test_quiets_follow_history() {
+    let board = board_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+    let favourite = PieceMove::new(A1, A7, false, None);
+    let mut history = History::default();
+    history.reward(true, &favourite, 5);
+    // Black's history does not affect white's moves
+    history.reward(false, &PieceMove::new(A1, A2, false, None), 10);
+
+    let first = MovePicker::new(
+      &board,
+      None,
+      &[PieceMove::NULL; 2],
+      &history,
+      UnderpromotionPolicy::Delay,
+    )
+    .next();
+    assert_eq!(first, Some(favourite));
+  }
+
+  #[test]
+  fn test_history_saturates() {
+    let mut history = History::default();
+    let piece_move = PieceMove::new(A1, A2, false, None);
+    for _ in 0..10_000 {
+      history.reward(true, &piece_move, 60);
+    }
+    let score = history.score(true, &piece_move);
+    assert!(score > MAX_HISTORY / 2 && score <= MAX_HISTORY, "{score}");
+  }
+
+  #[test]
+  fn test_search_all_keeps_promotions_together() {
+    let board = board_from_fen("8/4P3/8/8/8/k7/8/K7 w - - 0 1");
+    let order: Vec<_> = MovePicker::new(
+      &board,
+      None,
+      &[PieceMove::NULL; 2],
+      &History::default(),
+      UnderpromotionPolicy::SearchAll,
+    )
+    .take(4)
+    .filter_map(|piece_move| piece_move.promotion_type())
+    .collect();
+    assert_eq!(
+      order,
+      [
+        PromotionType::Queen,
+        PromotionType::Rook,
+        PromotionType::Bishop,
+        PromotionType::Knight
+      ]
+    );
+  }
+}
@@ -0,0 +1,193 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Static exchange evaluation.
+//!
+//! [`see`] works out what a capture wins once every piece that can join the fight on the target
+//! square has had its say, without searching. Move ordering uses it to tell winning captures from
+//! losing ones.
+
+use lumifox_chess::{
+  legal::attack::attacks_of,
+  model::{
+    bitboard::BitBoard,
+    gameboard::{GameBoard, PieceType},
+    piecemove::{PieceMove, PromotionType},
+  },
+};
+
+use crate::eval::piece_value;
+
+/// Least valuable first, the order in which pieces join an exchange.
+const EXCHANGE_ORDER: [PieceType; 6] = [
+  PieceType::Pawn,
+  PieceType::Knight,
+  PieceType::Bishop,
+  PieceType::Rook,
+  PieceType::Queen,
+  PieceType::King,
+];
+
+/// Material the side to move gains with `piece_move` in centipawns, assuming both sides keep
+/// recapturing on the target square with their least valuable piece for as long as it pays.
+/// Sliders lined up behind each other join in turn. Quiet moves score what the opponent can win
+/// by capturing the moved piece, so `0` means the move is safe.
+pub fn see(board: &GameBoard, piece_move: &PieceMove) -> i32 {
+  let from = piece_move.from_square();
+  let to = piece_move.to_square();
+  let Some(mut on_square) = board.get_piece(from) else {
+    return 0;
+  };
+
+  let mut occupancy = board.combined().raw() & !(1u64 << from);
+  let mut gains = [0; 32];
+  gains[0] = board.captured_piece(piece_move).map_or(0, piece_value);
+  if gains[0] != 0 && board.get_piece(to).is_none() {
+    // En passant: the captured pawn is beside the target square
+    occupancy &= !(1u64 << ((from & !7) | (to & 7)));
+  }
+  if let Some(promotion) = piece_move.promotion_type() {
+    on_square = promoted_piece(promotion);
+    gains[0] += piece_value(on_square) - piece_value(PieceType::Pawn);
+  }
+
+  let mut white = !board.playing;
+  let mut depth = 0;
+  while depth + 1 < gains.len() {
+    let attackers = attackers_to(board, to, occupancy);
+    let side = board.pieces_of(white).raw();
+    let Some((piece, square)) = least_valuable(board, attackers & side) else {
+      break;
+    };
+    // The king can only take last
+    if piece == PieceType::King && attackers & !side & occupancy != 0 {
+      break;
+    }
+
+    depth += 1;
+    gains[depth] = piece_value(on_square) - gains[depth - 1];
+    occupancy &= !(1u64 << square);
+    on_square = piece;
+    white = !white;
+  }
+
+  // Either side may stop capturing when carrying on would lose material
+  while depth > 0 {
+    gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+    depth -= 1;
+  }
+  gains[0]
+}
+
+/// Pieces of both sides in `occupancy` that attack `square`.
+fn attackers_to(board: &GameBoard, square: u8, occupancy: u64) -> u64 {
+  let occ = BitBoard::new(occupancy);
+  let attacks = |piece| attacks_of(piece, square, occ, true).raw();
+  // A pawn attacks `square` from where a pawn of the other colour on `square` would attack
+  let pawns = (attacks_of(PieceType::Pawn, square, occ, true).raw() & board.black_pieces().raw())
+    | (attacks_of(PieceType::Pawn, square, occ, false).raw() & board.white_pieces().raw());
+  let rooks = (board.rooks | board.queens).raw() & attacks(PieceType::Rook);
+  let bishops = (board.bishops | board.queens).raw() & attacks(PieceType::Bishop);
+
+  occupancy
+    & ((pawns & board.pawns.raw())
+      | (board.knights.raw() & attacks(PieceType::Knight))
+      | (board.kings.raw() & attacks(PieceType::King))
+      | rooks
+      | bishops)
+}
+
+fn least_valuable(board: &GameBoard, attackers: u64) -> Option<(PieceType, u8)> {
+  EXCHANGE_ORDER.iter().find_map(|&piece| {
+    let pieces = match piece {
+      PieceType::Pawn => board.pawns,
+      PieceType::Knight => board.knights,
+      PieceType::Bishop => board.bishops,
+      PieceType::Rook => board.rooks,
+      PieceType::Queen => board.queens,
+      PieceType::King => board.kings,
+    };
+    let candidates = pieces.raw() & attackers;
+    (candidates != 0).then(|| (piece, candidates.trailing_zeros() as u8))
+  })
+}
+
+fn promoted_piece(promotion: PromotionType) -> PieceType {
+  match promotion {
+    PromotionType::Queen => PieceType::Queen,
+    PromotionType::Rook => PieceType::Rook,
+    PromotionType::Bishop => PieceType::Bishop,
+    PromotionType::Knight => PieceType::Knight,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lumifox_chess::{constants::*, model::gamedata::GameData};
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  #[test]
+  fn test_undefended_capture_wins_the_piece() {
+    let board = board_from_fen("4k3/8/8/3r4/8/8/8/3RK3 w - - 0 1");
+    assert_eq!(see(&board, &PieceMove::new(D1, D5, true, None)), 500);
+  }
+
+  #[test]
+  fn test_defended_capture() {
+    // Rook takes a pawn defended by a pawn
+    let board = board_from_fen("4k3/2p5/3p4/8/8/8/8/3RK3 w - - 0 1");
+    assert_eq!(see(&board, &PieceMove::new(D1, D6, true, None)), 100 - 500);
+
+    // Pawn takes a knight defended by a pawn
+    let board = board_from_fen("4k3/2p5/3n4/4P3/8/8/8/4K3 w - - 0 1");
+    assert_eq!(see(&board, &PieceMove::new(E5, D6, true, None)), 320 - 100);
+  }
+
+  #[test]
+  fn test_xray_recaptures() {
+    // Doubled rooks take a pawn defended once by a rook: R x p, r x R, R x r
+    let board = board_from_fen("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1");
+    assert_eq!(see(&board, &PieceMove::new(D2, D5, true, None)), 100);
+
+    // Without the second rook the exchange loses the rook for a pawn
+    let board = board_from_fen("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1");
+    assert_eq!(see(&board, &PieceMove::new(D2, D5, true, None)), 100 - 500);
+  }
+
+  #[test]
+  fn test_king_cannot_capture_into_defence() {
+    // Kxd3 would walk into the second rook, so the pawn is simply won
+    let board = board_from_fen("3rk3/3r4/8/8/8/3P4/4K3/8 b - - 0 1");
+    assert_eq!(see(&board, &PieceMove::new(D7, D3, true, None)), 100);
+
+    // With only one rook the king takes it back
+    let board = board_from_fen("4k3/3r4/8/8/8/3P4/4K3/8 b - - 0 1");
+    assert_eq!(see(&board, &PieceMove::new(D7, D3, true, None)), 100 - 500);
+  }
+
+  #[test]
+  fn test_quiet_move_onto_attacked_square() {
+    let board = board_from_fen("4k3/8/2p5/8/8/8/8/3QK3 w - - 0 1");
+    assert_eq!(see(&board, &PieceMove::new(D1, D5, false, None)), -900);
+    assert_eq!(see(&board, &PieceMove::new(D1, D2, false, None)), 0);
+  }
+}
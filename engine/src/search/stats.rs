@@ -0,0 +1,249 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Time-to-depth and branching factor statistics.
+//!
+//! [`SearchStats`] is a [`SearchListener`] that records how long each iteration of a search
+//! took to complete and how many nodes it visited. One instance can listen to many searches, as
+//! in a bench run over a set of positions, and then totals every search per depth. Its
+//! [`Display`](fmt::Display) impl prints those totals as a table, which is the quickest way to
+//! tell whether a change to move ordering or pruning made the search cheaper:
+//!
+//! - *Time to depth* is the time from the start of a search until the iteration at that depth
+//!   completed, averaged over the searches that completed it.
+//! - The *effective branching factor* (EBF) at a depth is the ratio of the nodes its iteration
+//!   visited to the nodes the iteration before visited. Better ordering means more cutoffs and
+//!   a lower EBF.
+
+use core::fmt;
+use std::time::{Duration, Instant};
+
+use super::{PvLine, SearchListener, SearchResult};
+
+/// Totals for the iterations at one depth, over every search that completed them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DepthStats {
+  /// Searches that completed an iteration at this depth.
+  pub searches: u32,
+  /// Nodes visited by those iterations alone, not counting earlier iterations.
+  pub nodes: u64,
+  /// Time from the start of each search until this iteration completed, summed.
+  pub time_to_depth: Duration,
+}
+
+impl DepthStats {
+  /// Average nodes of one iteration at this depth.
+  pub fn average_nodes(&self) -> f64 {
+    self.nodes as f64 / self.searches.max(1) as f64
+  }
+
+  /// Average time from the start of a search until the iteration at this depth completed.
+  pub fn average_time_to_depth(&self) -> Duration {
+    self.time_to_depth / self.searches.max(1)
+  }
+}
+
+/// Records per-iteration timing and node counts of every search it listens to.
+#[derive(Debug, Clone, Default)]
+pub struct SearchStats {
+  /// Indexed by depth minus one.
+  depths: Vec<DepthStats>,
+  searches: u32,
+  /// Start of the current search.
+  started: Option<Instant>,
+  /// Nodes the current search had visited when its last iteration completed.
+  nodes_so_far: u64,
+}
+
+impl SearchStats {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Searches listened to.
+  pub fn searches(&self) -> u32 {
+    self.searches
+  }
+
+  /// Deepest depth any search completed, or 0 before any iteration completed.
+  pub fn max_depth(&self) -> u32 {
+    self.depths.len() as u32
+  }
+
+  /// Totals at `depth`, if any search completed it.
+  pub fn depth(&self, depth: u32) -> Option<&DepthStats> {
+    self.depths.get((depth as usize).checked_sub(1)?)
+  }
+
+  /// Average time for a search to complete `depth`.
+  pub fn time_to_depth(&self, depth: u32) -> Option<Duration> {
+    self.depth(depth).map(DepthStats::average_time_to_depth)
+  }
+
+  /// Effective branching factor at `depth`: the average nodes of its iteration over the average
+  /// nodes of the one before. `None` at depth 1 and at depths no search completed.
+  pub fn branching_factor(&self, depth: u32) -> Option<f64> {
+    let previous = self.depth(depth.checked_sub(1)?)?.average_nodes();
+    let current = self.depth(depth)?.average_nodes();
+    (previous > 0.0).then(|| current / previous)
+  }
+
+  /// Effective branching factor over every depth: the geometric mean of the per-depth factors,
+  /// so a single noisy iteration weighs no more than the others.
+  pub fn effective_branching_factor(&self) -> Option<f64> {
+    let first = self.depth(1)?.average_nodes();
+    let last = self.depth(self.max_depth())?.average_nodes();
+    let steps = self.max_depth().checked_sub(1).filter(|&steps| steps > 0)?;
+    (first > 0.0).then(|| (last / first).powf(1.0 / steps as f64))
+  }
+
+  /// Add every total of `other` to these, as if this had listened to its searches as well.
+  pub fn merge(&mut self, other: &SearchStats) {
+    if self.depths.len() < other.depths.len() {
+      self
+        .depths
+        .resize(other.depths.len(), DepthStats::default());
+    }
+    for (total, depth) in self.depths.iter_mut().zip(&other.depths) {
+      total.searches += depth.searches;
+      total.nodes += depth.nodes;
+      total.time_to_depth += depth.time_to_depth;
+    }
+    self.searches += other.searches;
+  }
+}
+
+impl SearchListener for SearchStats {
+  fn search_started(&mut self) {
+    self.searches += 1;
+    self.started = Some(Instant::now());
+    self.nodes_so_far = 0;
+  }
+
+  fn iteration_complete(&mut self, result: &SearchResult, _lines: &[PvLine]) {
+    let elapsed = self
+      .started
+      .map_or(Duration::ZERO, |started| started.elapsed());
+    let index = result.depth.saturating_sub(1) as usize;
+    if self.depths.len() <= index {
+      self.depths.resize(index + 1, DepthStats::default());
+    }
+    let depth = &mut self.depths[index];
+    depth.searches += 1;
+    depth.nodes += result.nodes - self.nodes_so_far;
+    depth.time_to_depth += elapsed;
+    self.nodes_so_far = result.nodes;
+  }
+}
+
+impl fmt::Display for SearchStats {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      "{:>5} {:>8} {:>14} {:>6} {:>12}",
+      "depth", "searches", "avg nodes", "ebf", "avg ttd (ms)"
+    )?;
+    for (stats, depth) in self.depths.iter().zip(1..) {
+      let ebf = match self.branching_factor(depth) {
+        Some(ebf) => format!("{ebf:.2}"),
+        None => "-".to_string(),
+      };
+      writeln!(
+        f,
+        "{depth:>5} {:>8} {:>14.0} {ebf:>6} {:>12.1}",
+        stats.searches,
+        stats.average_nodes(),
+        stats.average_time_to_depth().as_secs_f64() * 1000.0
+      )?;
+    }
+    match self.effective_branching_factor() {
+      Some(ebf) => write!(
+        f,
+        "{} searches, effective branching factor {ebf:.2}",
+        self.searches
+      ),
+      None => write!(f, "{} searches", self.searches),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::search::Searcher;
+  use lumifox_chess::model::gamedata::GameData;
+
+  #[test]
+  fn test_records_each_iteration() {
+    let board = GameData::START_POS.board;
+    let mut searcher = Searcher::default();
+    let mut stats = SearchStats::new();
+    let result = searcher.search_with(&board, 4, None, &mut stats);
+
+    assert_eq!(stats.searches(), 1);
+    assert_eq!(stats.max_depth(), 4);
+    let total: u64 = (1..=4).map(|depth| stats.depth(depth).unwrap().nodes).sum();
+    assert_eq!(total, result.nodes);
+    for depth in 2..=4 {
+      assert!(stats.time_to_depth(depth) >= stats.time_to_depth(depth - 1));
+      assert!(stats.branching_factor(depth).unwrap() > 0.0);
+    }
+    assert_eq!(stats.branching_factor(1), None);
+    assert_eq!(stats.depth(0), None);
+    assert_eq!(stats.depth(5), None);
+
+    let ebf = stats.effective_branching_factor().unwrap();
+    let nodes = |depth| stats.depth(depth).unwrap().average_nodes();
+    assert!((ebf.powi(3) - nodes(4) / nodes(1)).abs() < 1e-6 * nodes(4));
+  }
+
+  #[test]
+  fn test_aggregates_searches() {
+    let board = GameData::START_POS.board;
+    let mut searcher = Searcher::default();
+    let mut stats = SearchStats::new();
+    searcher.search_with(&board, 3, None, &mut stats);
+    let single = stats.clone();
+    searcher.search_with(&board, 2, None, &mut stats);
+
+    assert_eq!(stats.searches(), 2);
+    assert_eq!(stats.depth(2).unwrap().searches, 2);
+    assert_eq!(stats.depth(3).unwrap().searches, 1);
+    // The search is deterministic from the same position, so the averages don't move
+    assert_eq!(
+      stats.depth(2).unwrap().nodes,
+      2 * single.depth(2).unwrap().nodes
+    );
+
+    let mut merged = single.clone();
+    merged.merge(&single);
+    assert_eq!(merged.searches(), 2);
+    assert_eq!(
+      merged.depth(3).unwrap().nodes,
+      2 * single.depth(3).unwrap().nodes
+    );
+    assert_eq!(merged.branching_factor(3), single.branching_factor(3));
+
+    let summary = stats.to_string();
+    assert_eq!(summary.lines().count(), 5);
+    assert!(summary.ends_with(&format!(
+      "2 searches, effective branching factor {:.2}",
+      stats.effective_branching_factor().unwrap()
+    )));
+  }
+}
@@ -0,0 +1,199 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Time management: how long to think about a move.
+//!
+//! [`TimeManager`] turns a [`Clock`] from the GUI into two limits. The optimum is the time a move
+//! should take on average, so a search should not start another iteration once it has passed.
+//! The maximum is a hard limit the search must stop at, even mid-iteration.
+
+use std::time::Duration;
+
+use lumifox_chess::model::clock::Clock;
+
+/// Time kept back on every move for the GUI and the operating system, in milliseconds.
+pub const MOVE_OVERHEAD: u64 = 30;
+/// Moves assumed to be left in the game when the GUI does not send `movestogo`.
+pub const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// Thinking time for one move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeManager {
+  optimum: Duration,
+  maximum: Duration,
+  analysing: bool,
+}
+
+impl TimeManager {
+  /// Budget a move for the side given by `white`. A `movetime` from the GUI is used as is, less
+  /// the overhead; otherwise the side's remaining time is shared out over the moves to go, plus
+  /// most of the increment. Returns `None` when the side is not on a clock.
+  pub fn new(clock: &Clock, white: bool, movetime: Option<u64>) -> Option<Self> {
+    if let Some(movetime) = movetime {
+      let limit = Duration::from_millis(movetime.saturating_sub(MOVE_OVERHEAD));
+      return Some(Self {
+        optimum: limit,
+        maximum: limit,
+        analysing: false,
+      });
+    }
+
+    let usable = clock.time(white)?.saturating_sub(MOVE_OVERHEAD);
+    let moves_to_go = clock.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    let optimum = (usable / u64::from(moves_to_go) + clock.increment(white) * 3 / 4).min(usable);
+    let maximum = (optimum * 3).min(usable);
+    Some(Self {
+      optimum: Duration::from_millis(optimum),
+      maximum: Duration::from_millis(maximum),
+      analysing: false,
+    })
+  }
+
+  /// The same budget for analysis mode, where a stable best move is no reason to stop early.
+  pub fn analysing(self) -> Self {
+    Self {
+      analysing: true,
+      ..self
+    }
+  }
+
+  /// Time a move should take; don't start a new iteration after this.
+  pub fn optimum(&self) -> Duration {
+    self.optimum
+  }
+
+  /// Time a move must not exceed; stop searching at once after this.
+  pub fn maximum(&self) -> Duration {
+    self.maximum
+  }
+
+  /// Whether to stop after an iteration that finished `elapsed` into the move, where `stability`
+  /// is [`SearchResult::stability`](super::SearchResult::stability). A best move that keeps
+  /// changing may use up to twice the optimum, one that has held for several iterations stops
+  /// well before it, and the maximum is never passed. When analysing, every iteration may run
+  /// until the optimum.
+  pub fn stop_after_iteration(&self, elapsed: Duration, stability: u32) -> bool {
+    let percent = match stability {
+      _ if self.analysing => 100,
+      0 => 200,
+      1 => 130,
+      2 => 100,
+      3 => 80,
+      _ => 60,
+    };
+    elapsed >= self.maximum.min(self.optimum * percent / 100)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use lumifox_chess::model::clock::{ClockState, TimeBonus, TimeControl};
+
+  use super::*;
+
+  fn clock(time: u64, increment: u64, moves_to_go: Option<u32>) -> Clock {
+    Clock {
+      white_time: Some(time),
+      black_time: Some(time / 2),
+      white_increment: increment,
+      black_increment: 0,
+      moves_to_go,
+    }
+  }
+
+  #[test]
+  fn test_sudden_death() {
+    let manager = TimeManager::new(&clock(60_030, 0, None), true, None).unwrap();
+    assert_eq!(manager.optimum(), Duration::from_millis(2_000));
+    assert_eq!(manager.maximum(), Duration::from_millis(6_000));
+
+    // Black has half the time
+    let manager = TimeManager::new(&clock(60_030, 0, None), false, None).unwrap();
+    assert_eq!(manager.optimum(), Duration::from_millis(999));
+  }
+
+  #[test]
+  fn test_increment_and_moves_to_go() {
+    let manager = TimeManager::new(&clock(10_030, 2_000, Some(5)), true, None).unwrap();
+    assert_eq!(manager.optimum(), Duration::from_millis(3_500));
+    assert_eq!(manager.maximum(), Duration::from_millis(10_000));
+
+    // The last move before the time control may use everything left
+    let manager = TimeManager::new(&clock(10_030, 0, Some(1)), true, None).unwrap();
+    assert_eq!(manager.optimum(), Duration::from_millis(10_000));
+    assert_eq!(manager.maximum(), Duration::from_millis(10_000));
+  }
+
+  #[test]
+  fn test_stability_shortens_the_move() {
+    let manager = TimeManager::new(&clock(60_030, 0, None), true, None).unwrap();
+    let at = Duration::from_millis;
+    // Optimum 2s, maximum 6s
+    assert!(!manager.stop_after_iteration(at(3_000), 0));
+    assert!(manager.stop_after_iteration(at(4_000), 0));
+    assert!(!manager.stop_after_iteration(at(1_900), 2));
+    assert!(manager.stop_after_iteration(at(2_000), 2));
+    assert!(manager.stop_after_iteration(at(1_200), 10));
+
+    let analysing = manager.analysing();
+    assert!(!analysing.stop_after_iteration(at(1_900), 10));
+    assert!(analysing.stop_after_iteration(at(2_000), 0));
+
+    let movetime = TimeManager::new(&Clock::default(), true, Some(1_030)).unwrap();
+    assert!(!movetime.stop_after_iteration(at(999), 0));
+    assert!(movetime.stop_after_iteration(at(1_000), 0));
+  }
+
+  #[test]
+  fn test_movetime_and_untimed() {
+    let manager = TimeManager::new(&Clock::default(), true, Some(1_000)).unwrap();
+    assert_eq!(manager.optimum(), Duration::from_millis(970));
+    assert_eq!(manager.maximum(), Duration::from_millis(970));
+
+    assert_eq!(TimeManager::new(&Clock::default(), true, None), None);
+    // Almost out of time: never budget more than is left
+    let manager = TimeManager::new(&clock(10, 500, None), true, None).unwrap();
+    assert_eq!(manager.maximum(), Duration::ZERO);
+  }
+
+  #[test]
+  fn test_budgets_never_flag() {
+    let controls = [
+      TimeControl::sudden_death(60_000, TimeBonus::None),
+      TimeControl::sudden_death(1_000, TimeBonus::Increment(100)),
+      TimeControl::sudden_death(5_000, TimeBonus::Delay(200)),
+      TimeControl::parse("40/10:5").unwrap(),
+      TimeControl::parse("5/1").unwrap(),
+    ];
+    for control in controls {
+      let mut state = ClockState::new(control);
+      // Spend the whole maximum on every move: the clock must still hold out
+      for ply in 0..300u32 {
+        let white = ply.is_multiple_of(2);
+        let manager = TimeManager::new(&state.to_clock(white), white, None).unwrap();
+        assert!(manager.optimum() <= manager.maximum());
+        let spent = manager.maximum().as_millis() as u64;
+        assert!(
+          state.record_move(white, spent).is_some(),
+          "{control:?} at ply {ply}"
+        );
+      }
+      assert_eq!(state.flagged(), None);
+    }
+  }
+}
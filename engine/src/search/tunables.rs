@@ -0,0 +1,178 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Named search parameters for automated tuning.
+//!
+//! [`TUNABLES`] lists every numeric [`SearchConfig`] field worth tuning, with the range it may
+//! take and the step an SPSA tuner should start from. The UCI session accepts each one as a
+//! `setoption` without announcing it in reply to `uci`, so GUIs don't show them but a tuning
+//! framework such as OpenBench can set them between games. [`spsa_inputs`] prints the list in
+//! the format OpenBench takes.
+
+use std::fmt::Write;
+
+use super::SearchConfig;
+
+/// One tunable search parameter.
+#[derive(Debug, Clone, Copy)]
+pub struct Tunable {
+  /// The `setoption` name.
+  pub name: &'static str,
+  pub min: i32,
+  pub max: i32,
+  /// Perturbation an SPSA run should end with, the `c_end` of OpenBench.
+  pub step: f64,
+  get: fn(&SearchConfig) -> i32,
+  set: fn(&mut SearchConfig, i32),
+}
+
+impl Tunable {
+  /// The parameter's value in `config`.
+  pub fn get(&self, config: &SearchConfig) -> i32 {
+    (self.get)(config)
+  }
+
+  /// Set the parameter in `config` to `value`. Returns `false`, leaving `config` alone, if `value`
+  /// is outside `min..=max`.
+  pub fn set(&self, config: &mut SearchConfig, value: i32) -> bool {
+    if !(self.min..=self.max).contains(&value) {
+      return false;
+    }
+    (self.set)(config, value);
+    true
+  }
+}
+
+/// Every tunable search parameter. Depths and reductions are whole plies, margins and windows
+/// centipawns, and the late-move reduction terms hundredths.
+pub const TUNABLES: [Tunable; 6] = [
+  Tunable {
+    name: "LmrBase",
+    min: 0,
+    max: 300,
+    step: 10.0,
+    get: |config| config.lmr_base as i32,
+    set: |config, value| config.lmr_base = value as u32,
+  },
+  Tunable {
+    name: "LmrDivisor",
+    min: 100,
+    max: 500,
+    step: 20.0,
+    get: |config| config.lmr_divisor as i32,
+    set: |config, value| config.lmr_divisor = value as u32,
+  },
+  Tunable {
+    name: "FutilityMargin",
+    min: 0,
+    max: 500,
+    step: 15.0,
+    get: |config| config.futility_margin,
+    set: |config, value| config.futility_margin = value,
+  },
+  Tunable {
+    name: "ReverseFutilityMargin",
+    min: 0,
+    max: 500,
+    step: 15.0,
+    get: |config| config.reverse_futility_margin,
+    set: |config, value| config.reverse_futility_margin = value,
+  },
+  Tunable {
+    name: "AspirationWindow",
+    min: 0,
+    max: 300,
+    step: 5.0,
+    get: |config| config.aspiration_window,
+    set: |config, value| config.aspiration_window = value,
+  },
+  Tunable {
+    name: "NullMoveReduction",
+    min: 1,
+    max: 5,
+    step: 0.5,
+    get: |config| config.null_move_reduction as i32,
+    set: |config, value| config.null_move_reduction = value as u32,
+  },
+];
+
+/// The tunable called `name`, matched without regard to case.
+pub fn find_tunable(name: &str) -> Option<&'static Tunable> {
+  TUNABLES
+    .iter()
+    .find(|tunable| tunable.name.eq_ignore_ascii_case(name))
+}
+
+/// [`TUNABLES`] as OpenBench's SPSA input, one `name, int, default, min, max, c_end, r_end` line
+/// each, with defaults taken from `config`.
+pub fn spsa_inputs(config: &SearchConfig) -> String {
+  let mut out = String::new();
+  for tunable in &TUNABLES {
+    let _ = writeln!(
+      out,
+      "{}, int, {}, {}, {}, {}, 0.002",
+      tunable.name,
+      tunable.get(config),
+      tunable.min,
+      tunable.max,
+      tunable.step
+    );
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_defaults_in_range() {
+    let config = SearchConfig::default();
+    for tunable in &TUNABLES {
+      let value = tunable.get(&config);
+      assert!(
+        (tunable.min..=tunable.max).contains(&value),
+        "{}",
+        tunable.name
+      );
+    }
+  }
+
+  #[test]
+  fn test_set_and_get() {
+    let mut config = SearchConfig::default();
+    let window = find_tunable("aspirationwindow").unwrap();
+    assert!(window.set(&mut config, 80));
+    assert_eq!(config.aspiration_window, 80);
+    assert!(!window.set(&mut config, -1));
+    assert_eq!(window.get(&config), 80);
+
+    let reduction = find_tunable("NullMoveReduction").unwrap();
+    assert!(reduction.set(&mut config, 3));
+    assert_eq!(config.null_move_reduction, 3);
+    assert!(find_tunable("Hash").is_none());
+  }
+
+  #[test]
+  fn test_spsa_inputs() {
+    let inputs = spsa_inputs(&SearchConfig::default());
+    assert_eq!(inputs.lines().count(), TUNABLES.len());
+    assert!(inputs.contains("FutilityMargin, int, 150, 0, 500, 15, 0.002\n"));
+    assert!(inputs.contains("NullMoveReduction, int, 2, 1, 5, 0.5, 0.002\n"));
+  }
+}
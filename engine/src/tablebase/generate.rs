@@ -0,0 +1,721 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tablebases generated in memory by retrograde analysis.
+//!
+//! [`generate_tb`] solves every position of a small ending (KQvK, KRvK, KPvK, KBNvK and the
+//! like, up to [`MAX_MEN`] pieces) by working backwards from the mates: a position is won if some
+//! move reaches a lost one, and lost if every move reaches a won one. Endings reached by a capture
+//! or a promotion are generated first and looked up where the main table leaves off, so KPvK
+//! knows which promotions win. Positions are found by distance to mate, so the tables play exact
+//! endgames and give the search and evaluation a ground truth to be checked against.
+//!
+//! Tables hold neither castling rights nor en passant squares: en passant only matters with pawns
+//! on both sides, and a position with such a capture available is scored as if it were not.
+
+use std::{collections::HashMap, fmt};
+
+use lumifox_chess::{
+  legal::attack::attacks_of,
+  model::{
+    bitboard::BitBoard,
+    gameboard::{GameBoard, PieceType},
+    material::MaterialSig,
+  },
+};
+
+use super::{Tablebase, Wdl};
+
+/// Largest ending, kings included, that [`generate_tb`] will solve.
+pub const MAX_MEN: u8 = 4;
+
+/// Why [`generate_tb`] refused an ending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerateError {
+  /// The ending has more than [`MAX_MEN`] pieces.
+  TooManyPieces,
+  /// One of the sides does not have exactly one king.
+  Kings,
+}
+
+impl fmt::Display for GenerateError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GenerateError::TooManyPieces => {
+        write!(f, "endings of at most {MAX_MEN} pieces can be generated")
+      }
+      GenerateError::Kings => f.write_str("each side needs exactly one king"),
+    }
+  }
+}
+
+impl std::error::Error for GenerateError {}
+
+/// Order of the pieces after the two kings, for each side in turn.
+const PIECES: [PieceType; 5] = [
+  PieceType::Queen,
+  PieceType::Rook,
+  PieceType::Bishop,
+  PieceType::Knight,
+  PieceType::Pawn,
+];
+
+/// `counters` entry for an index that is not a legal position.
+const INVALID: u8 = u8::MAX;
+/// `counters` entry for a position with a move to a draw or win outside the table.
+const NEVER_LOSES: u8 = u8::MAX - 1;
+
+/// One solved ending. Entries are indexed by the squares of `pieces`, six bits each with the
+/// first piece lowest, above a bit for the side to move. An entry is 0 for a draw (or an index
+/// that is not a position), or the distance to mate in plies plus one: odd distances are wins
+/// for the side to move, even ones losses.
+struct Table {
+  pieces: Vec<(PieceType, bool)>,
+  entries: Vec<u8>,
+}
+
+impl Table {
+  fn entry(&self, board: &GameBoard) -> Option<u8> {
+    let mut squares = Vec::with_capacity(self.pieces.len());
+    let mut slot = 0;
+    while slot < self.pieces.len() {
+      let (piece, white) = self.pieces[slot];
+      let run = self.pieces[slot..]
+        .iter()
+        .take_while(|&&other| other == (piece, white))
+        .count();
+      let placed = pieces_of_type(board, piece) & board.pieces_of(white);
+      if placed.count() as usize != run {
+        return None;
+      }
+      squares.extend(placed);
+      slot += run;
+    }
+    self.entries.get(index(&squares, board.playing)).copied()
+  }
+}
+
+/// In-memory tables made by [`generate_tb`], probed through [`Tablebase`]. Each ending answers
+/// for its colour-swapped twin as well, and endings without mating material are always draws.
+#[derive(Default)]
+pub struct GeneratedTablebase {
+  tables: HashMap<MaterialSig, Table>,
+}
+
+/// Solve the ending `sig`, for example `"KPvK".parse()`, together with every ending it can turn
+/// into. Each piece multiplies the work by 64, so four-piece endings take a while.
+pub fn generate_tb(sig: MaterialSig) -> Result<GeneratedTablebase, GenerateError> {
+  let mut tablebase = GeneratedTablebase::default();
+  tablebase.generate(sig)?;
+  Ok(tablebase)
+}
+
+impl GeneratedTablebase {
+  /// Add the ending `sig`, and the endings it can turn into, to the tables.
+  pub fn generate(&mut self, sig: MaterialSig) -> Result<(), GenerateError> {
+    if sig.men() > MAX_MEN {
+      return Err(GenerateError::TooManyPieces);
+    }
+    if sig.count(PieceType::King, true) != 1 || sig.count(PieceType::King, false) != 1 {
+      return Err(GenerateError::Kings);
+    }
+    if is_dead_draw(&sig)
+      || self.tables.contains_key(&sig)
+      || self.tables.contains_key(&sig.mirrored())
+    {
+      return Ok(());
+    }
+    for next in successors(&sig) {
+      self.generate(next)?;
+    }
+    let pieces = pieces(&sig);
+    let entries = self.solve(&pieces);
+    self.tables.insert(sig, Table { pieces, entries });
+    Ok(())
+  }
+
+  /// Endings currently in the tables, without their colour-swapped twins.
+  pub fn endings(&self) -> impl Iterator<Item = MaterialSig> + '_ {
+    self.tables.keys().copied()
+  }
+
+  /// Result of `board` and its distance to mate in plies (0 for draws and for a side already
+  /// mated). `None` if the position is not in the tables.
+  pub fn probe(&self, board: &GameBoard) -> Option<(Wdl, u32)> {
+    Some(match self.entry(board)? {
+      0 => (Wdl::Draw, 0),
+      entry if entry % 2 == 0 => (Wdl::Win, u32::from(entry - 1)),
+      entry => (Wdl::Loss, u32::from(entry - 1)),
+    })
+  }
+
+  fn entry(&self, board: &GameBoard) -> Option<u8> {
+    if !board.castling.is_empty() {
+      return None;
+    }
+    let sig = board.material_signature();
+    if is_dead_draw(&sig) {
+      return Some(0);
+    }
+    if let Some(table) = self.tables.get(&sig) {
+      table.entry(board)
+    } else {
+      self.tables.get(&sig.mirrored())?.entry(&mirror(board))
+    }
+  }
+
+  /// Retrograde analysis of every placement of `pieces`.
+  fn solve(&self, pieces: &[(PieceType, bool)]) -> Vec<u8> {
+    let size = 2 << (6 * pieces.len());
+    let mut entries = vec![0u8; size];
+    // Moves inside the table not yet known to lose, for positions that could still be lost
+    let mut counters = vec![INVALID; size];
+    // Longest mate among the moves leaving the table, for positions whose moves all lose
+    let mut floors = HashMap::new();
+    // Positions found to be decided, by distance to mate
+    let mut pending: Vec<Vec<usize>> = Vec::new();
+
+    let mut squares = Vec::with_capacity(pieces.len());
+    for (position, counter) in counters.iter_mut().enumerate() {
+      if !decode(pieces, position, &mut squares) {
+        continue;
+      }
+      let white = position & 1 == 1;
+      let mut inside = 0;
+      let mut legal = 0;
+      let mut escapes = false;
+      let mut win: Option<usize> = None;
+      let mut floor = 0;
+      for_each_move(pieces, &mut squares, white, |squares, moved| {
+        legal += 1;
+        let Some((slot, captured, promotion)) = moved else {
+          inside += 1;
+          return;
+        };
+        let child = child_board(pieces, squares, !white, slot, captured, promotion);
+        match self
+          .entry(&child)
+          .expect("smaller endings are generated first")
+        {
+          0 => escapes = true,
+          entry if entry % 2 == 1 => {
+            let distance = usize::from(entry);
+            win = Some(win.map_or(distance, |win| win.min(distance)));
+          }
+          entry => floor = floor.max(usize::from(entry)),
+        }
+      });
+
+      *counter = NEVER_LOSES;
+      if legal == 0 {
+        if king_attacked(pieces, &squares, white, None) {
+          schedule(&mut pending, 0, position);
+        }
+      } else if let Some(distance) = win {
+        schedule(&mut pending, distance, position);
+      } else if !escapes {
+        // Without a move to a drawn ending, the side loses once every move inside is lost
+        if inside == 0 {
+          schedule(&mut pending, floor, position);
+        } else {
+          *counter = inside;
+          if floor > 0 {
+            floors.insert(position, floor);
+          }
+        }
+      }
+    }
+
+    let mut distance = 0;
+    let mut predecessors = Vec::new();
+    while distance < pending.len() {
+      for position in std::mem::take(&mut pending[distance]) {
+        if entries[position] != 0 {
+          continue;
+        }
+        entries[position] = (distance + 1).min(usize::from(u8::MAX)) as u8;
+        let won = distance % 2 == 1;
+        unmoves(pieces, position, &mut predecessors);
+        for &previous in &predecessors {
+          if entries[previous] != 0 || counters[previous] == INVALID {
+            continue;
+          }
+          if !won {
+            schedule(&mut pending, distance + 1, previous);
+          } else if counters[previous] != NEVER_LOSES {
+            counters[previous] -= 1;
+            if counters[previous] == 0 {
+              let floor = floors.get(&previous).copied().unwrap_or(0);
+              schedule(&mut pending, floor.max(distance + 1), previous);
+            }
+          }
+        }
+      }
+      distance += 1;
+    }
+    entries
+  }
+}
+
+fn schedule(pending: &mut Vec<Vec<usize>>, distance: usize, position: usize) {
+  if pending.len() <= distance {
+    pending.resize_with(distance + 1, Vec::new);
+  }
+  pending[distance].push(position);
+}
+
+impl Tablebase for GeneratedTablebase {
+  fn max_pieces(&self) -> u32 {
+    u32::from(MAX_MEN)
+  }
+
+  fn probe_wdl(&self, board: &GameBoard) -> Option<Wdl> {
+    self.probe(board).map(|(wdl, _)| wdl)
+  }
+
+  /// The distance to mate, which is never shorter than the distance to zeroing: the winner can
+  /// always mate that soon, and a mate ends the count as surely as a capture or pawn move. A
+  /// side already mated gets -1, as in Syzygy tables.
+  fn probe_dtz(&self, board: &GameBoard) -> Option<i32> {
+    Some(match self.probe(board)? {
+      (Wdl::Win, distance) => distance as i32,
+      (Wdl::Loss, distance) => -(distance.max(1) as i32),
+      _ => 0,
+    })
+  }
+
+  fn covers(&self, board: &GameBoard) -> bool {
+    self.entry(board).is_some()
+  }
+}
+
+fn pieces_of_type(board: &GameBoard, piece: PieceType) -> BitBoard {
+  match piece {
+    PieceType::Pawn => board.pawns,
+    PieceType::Knight => board.knights,
+    PieceType::Bishop => board.bishops,
+    PieceType::Rook => board.rooks,
+    PieceType::Queen => board.queens,
+    PieceType::King => board.kings,
+  }
+}
+
+/// Endings where neither side can mate: bare kings, or a lone minor piece.
+fn is_dead_draw(sig: &MaterialSig) -> bool {
+  let count = |piece| sig.count(piece, true) + sig.count(piece, false);
+  count(PieceType::Pawn) + count(PieceType::Rook) + count(PieceType::Queen) == 0
+    && count(PieceType::Knight) + count(PieceType::Bishop) <= 1
+}
+
+/// The endings one capture or promotion away from `sig`.
+fn successors(sig: &MaterialSig) -> Vec<MaterialSig> {
+  let base = sig.to_string();
+  let (white, black) = base.split_once('v').expect("signatures have two sides");
+  let mut next = Vec::new();
+  for (side, white_side) in [(white, true), (black, false)] {
+    for (at, letter) in side.char_indices().filter(|&(_, letter)| letter != 'K') {
+      let rest = format!("{}{}", &side[..at], &side[at + 1..]);
+      let mut replacements = vec![rest.clone()];
+      if letter == 'P' {
+        replacements.extend(["Q", "R", "B", "N"].map(|piece| format!("{rest}{piece}")));
+      }
+      for replaced in replacements {
+        let text = if white_side {
+          format!("{replaced}v{black}")
+        } else {
+          format!("{white}v{replaced}")
+        };
+        next.push(text.parse().expect("built from a valid signature"));
+      }
+    }
+  }
+  next
+}
+
+/// The pieces of `sig` in table order: the two kings, then the white and the black pieces.
+fn pieces(sig: &MaterialSig) -> Vec<(PieceType, bool)> {
+  let mut pieces = vec![(PieceType::King, true), (PieceType::King, false)];
+  for white in [true, false] {
+    for piece in PIECES {
+      for _ in 0..sig.count(piece, white) {
+        pieces.push((piece, white));
+      }
+    }
+  }
+  pieces
+}
+
+/// Table index of the pieces standing on `squares`. Pieces of the same type and colour are
+/// sorted first, so each position has one index.
+fn index(squares: &[u8], white_to_move: bool) -> usize {
+  squares
+    .iter()
+    .rev()
+    .fold(0, |index, &square| index << 6 | usize::from(square))
+    << 1
+    | usize::from(white_to_move)
+}
+
+/// Read the squares of `position` into `squares`, and say whether they make a legal position.
+fn decode(pieces: &[(PieceType, bool)], position: usize, squares: &mut Vec<u8>) -> bool {
+  squares.clear();
+  squares.extend((0..pieces.len()).map(|slot| (position >> (1 + 6 * slot) & 63) as u8));
+  for slot in 1..pieces.len() {
+    // Identical pieces must be in ascending order, and no two pieces can share a square
+    if pieces[slot] == pieces[slot - 1] && squares[slot] <= squares[slot - 1] {
+      return false;
+    }
+    if squares[..slot].contains(&squares[slot]) {
+      return false;
+    }
+  }
+  let pawns_on_board = pieces
+    .iter()
+    .zip(squares.iter())
+    .all(|(&(piece, _), square)| piece != PieceType::Pawn || (8..56).contains(square));
+  // The side that just moved cannot be left in check
+  pawns_on_board && !king_attacked(pieces, squares, position & 1 == 0, None)
+}
+
+fn occupancy(squares: &[u8], skip: Option<usize>) -> BitBoard {
+  squares
+    .iter()
+    .enumerate()
+    .filter(|&(slot, _)| Some(slot) != skip)
+    .fold(BitBoard::EMPTY, |occupancy, (_, &square)| {
+      occupancy | BitBoard::new(1 << square)
+    })
+}
+
+/// Whether the king of the side given by `white` is attacked, with the piece in slot `captured`
+/// taken off the board.
+fn king_attacked(
+  pieces: &[(PieceType, bool)],
+  squares: &[u8],
+  white: bool,
+  captured: Option<usize>,
+) -> bool {
+  let king = squares[usize::from(!white)];
+  let occupancy = occupancy(squares, captured);
+  pieces
+    .iter()
+    .zip(squares)
+    .enumerate()
+    .any(|(slot, (&(piece, colour), &square))| {
+      colour != white
+        && Some(slot) != captured
+        && attacks_of(piece, square, occupancy, colour).get_bit(king) == Some(true)
+    })
+}
+
+/// Call `visit` with `squares` updated for each legal move of the side given by `white`. Moves
+/// that stay inside the table come with `None`; captures and promotions come with the slot that
+/// moved, the slot captured and the piece promoted to, since they lead to another ending.
+/// Castling and en passant never happen in tables.
+fn for_each_move(
+  pieces: &[(PieceType, bool)],
+  squares: &mut [u8],
+  white: bool,
+  mut visit: impl FnMut(&[u8], Option<(usize, Option<usize>, Option<PieceType>)>),
+) {
+  let occupied = occupancy(squares, None);
+  let own = pieces
+    .iter()
+    .zip(squares.iter())
+    .filter(|&(&(_, colour), _)| colour == white)
+    .fold(BitBoard::EMPTY, |own, (_, &square)| {
+      own | BitBoard::new(1 << square)
+    });
+
+  for slot in 0..pieces.len() {
+    let (piece, colour) = pieces[slot];
+    if colour != white {
+      continue;
+    }
+    let from = squares[slot];
+    let targets = if piece == PieceType::Pawn {
+      let forward = if white { from + 8 } else { from - 8 };
+      let mut targets = attacks_of(piece, from, occupied, white) & occupied & !own;
+      if occupied.get_bit(forward) == Some(false) {
+        targets |= BitBoard::new(1 << forward);
+        let double = if white {
+          forward + 8
+        } else {
+          forward.wrapping_sub(8)
+        };
+        let start_rank = if white { 1 } else { 6 };
+        if from / 8 == start_rank && occupied.get_bit(double) == Some(false) {
+          targets |= BitBoard::new(1 << double);
+        }
+      }
+      targets
+    } else {
+      attacks_of(piece, from, occupied, white) & !own
+    };
+
+    for to in targets {
+      let captured = squares.iter().position(|&square| square == to);
+      squares[slot] = to;
+      if !king_attacked(pieces, squares, white, captured) {
+        if piece == PieceType::Pawn && !(8..56).contains(&to) {
+          for promotion in [
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+          ] {
+            visit(squares, Some((slot, captured, Some(promotion))));
+          }
+        } else if captured.is_some() {
+          visit(squares, Some((slot, captured, None)));
+        } else {
+          visit(squares, None);
+        }
+      }
+      squares[slot] = from;
+    }
+  }
+}
+
+/// The board after a capture or promotion, with `white` to move.
+fn child_board(
+  pieces: &[(PieceType, bool)],
+  squares: &[u8],
+  white: bool,
+  moved: usize,
+  captured: Option<usize>,
+  promotion: Option<PieceType>,
+) -> GameBoard {
+  let mut board = GameBoard::default();
+  for (slot, (&(piece, colour), &square)) in pieces.iter().zip(squares).enumerate() {
+    if Some(slot) == captured {
+      continue;
+    }
+    let piece = match promotion {
+      Some(promotion) if slot == moved => promotion,
+      _ => piece,
+    };
+    board.set_square(square, piece, colour);
+  }
+  board.playing = white;
+  board
+}
+
+/// Indexes of the positions from which one move inside the table leads to `position`, written
+/// to `out`.
+fn unmoves(pieces: &[(PieceType, bool)], position: usize, out: &mut Vec<usize>) {
+  out.clear();
+  let mut squares: Vec<u8> = (0..pieces.len())
+    .map(|slot| (position >> (1 + 6 * slot) & 63) as u8)
+    .collect();
+  let mover = position & 1 == 0;
+  let occupancy = squares.iter().fold(BitBoard::EMPTY, |occupancy, &square| {
+    occupancy | BitBoard::new(1 << square)
+  });
+
+  for slot in 0..pieces.len() {
+    let (piece, white) = pieces[slot];
+    if white != mover {
+      continue;
+    }
+    let to = squares[slot];
+    let origins = if piece == PieceType::Pawn {
+      // Pawns only step back towards their own side, and never come from the first rank
+      let (single, double_rank) = if white {
+        (to.wrapping_sub(8), 3)
+      } else {
+        (to + 8, 4)
+      };
+      let mut origins = BitBoard::EMPTY;
+      if (8..56).contains(&single) && occupancy.get_bit(single) == Some(false) {
+        origins |= BitBoard::new(1 << single);
+        let double = if white { single - 8 } else { single + 8 };
+        if to / 8 == double_rank && occupancy.get_bit(double) == Some(false) {
+          origins |= BitBoard::new(1 << double);
+        }
+      }
+      origins
+    } else {
+      attacks_of(piece, to, occupancy, white) & !occupancy
+    };
+
+    for from in origins {
+      squares[slot] = from;
+      let mut sorted = squares.clone();
+      sort_runs(pieces, &mut sorted);
+      out.push(index(&sorted, mover));
+    }
+    squares[slot] = to;
+  }
+}
+
+/// Sort the squares of each run of identical pieces.
+fn sort_runs(pieces: &[(PieceType, bool)], squares: &mut [u8]) {
+  let mut start = 0;
+  while start < pieces.len() {
+    let end = start
+      + pieces[start..]
+        .iter()
+        .take_while(|&&piece| piece == pieces[start])
+        .count();
+    squares[start..end].sort_unstable();
+    start = end;
+  }
+}
+
+/// `board` with the colours swapped and the ranks flipped, so that it is the same position with
+/// white and black exchanged.
+fn mirror(board: &GameBoard) -> GameBoard {
+  let mut mirrored = GameBoard::default();
+  for square in board.combined() {
+    if let Some((piece, white)) = board.piece_at(square) {
+      mirrored.set_square(square ^ 56, piece, !white);
+    }
+  }
+  mirrored.playing = !board.playing;
+  mirrored
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::eval::{evaluate, evaluate_with};
+  use lumifox_chess::model::gamedata::GameData;
+  use std::sync::OnceLock;
+
+  /// KPvK, which brings in KQvK and KRvK through the promotions. Shared, since generating it is
+  /// the slow part.
+  fn tables() -> &'static GeneratedTablebase {
+    static TABLES: OnceLock<GeneratedTablebase> = OnceLock::new();
+    TABLES.get_or_init(|| generate_tb("KPvK".parse().unwrap()).unwrap())
+  }
+
+  fn probe(fen: &str) -> Option<(Wdl, u32)> {
+    tables().probe(&GameData::from_fen(fen).unwrap().board)
+  }
+
+  #[test]
+  fn test_generates_successors() {
+    let mut endings: Vec<String> = tables().endings().map(|sig| sig.to_string()).collect();
+    endings.sort();
+    // KBvK and KNvK are dead draws, which need no table
+    assert_eq!(endings, ["KPvK", "KQvK", "KRvK"].map(String::from));
+  }
+
+  #[test]
+  fn test_queen_ending() {
+    assert_eq!(probe("7k/8/6K1/8/8/8/Q7/8 w - - 0 1"), Some((Wdl::Win, 1)));
+    assert_eq!(probe("Q6k/8/6K1/8/8/8/8/8 b - - 0 1"), Some((Wdl::Loss, 0)));
+    // Stalemate, and a queen left hanging next to the defending king
+    assert_eq!(
+      probe("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"),
+      Some((Wdl::Draw, 0))
+    );
+    assert_eq!(probe("8/8/8/8/8/8/1q6/K6k w - - 0 1"), Some((Wdl::Draw, 0)));
+    // The colour-swapped twin answers the same
+    assert_eq!(probe("8/q7/8/8/8/6k1/8/7K b - - 0 1"), Some((Wdl::Win, 1)));
+  }
+
+  #[test]
+  fn test_longest_wins() {
+    let longest = |table: &Table| {
+      table
+        .entries
+        .iter()
+        .filter(|&&entry| entry % 2 == 0)
+        .max()
+        .map(|&entry| entry - 1)
+    };
+    let table = |name: &str| &tables().tables[&name.parse().unwrap()];
+    // The known maximum distances to mate: 10 moves with a queen, 16 with a rook
+    assert_eq!(longest(table("KQvK")), Some(19));
+    assert_eq!(longest(table("KRvK")), Some(31));
+  }
+
+  #[test]
+  fn test_pawn_ending() {
+    // A king on the sixth in front of its pawn wins whoever is to move
+    assert_eq!(
+      probe("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").map(|(wdl, _)| wdl),
+      Some(Wdl::Win)
+    );
+    assert_eq!(
+      probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1").map(|(wdl, _)| wdl),
+      Some(Wdl::Loss)
+    );
+    // Stalemate, and a pawn lost to the defending king
+    assert_eq!(
+      probe("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"),
+      Some((Wdl::Draw, 0))
+    );
+    assert_eq!(probe("8/8/8/8/8/8/3kP3/7K b - - 0 1"), Some((Wdl::Draw, 0)));
+    // A pawn the defender cannot catch, and the same for black
+    assert_eq!(
+      probe("8/8/8/8/P7/8/8/K6k w - - 0 1").map(|(wdl, _)| wdl),
+      Some(Wdl::Win)
+    );
+    assert_eq!(
+      probe("k6K/8/8/p7/8/8/8/8 b - - 0 1").map(|(wdl, _)| wdl),
+      Some(Wdl::Win)
+    );
+    assert!(probe("k7/8/8/8/8/8/8/K6Q w - - 0 1").is_some());
+    assert!(probe("k7/8/8/8/8/8/8/KR4BN w - - 0 1").is_none());
+  }
+
+  #[test]
+  fn test_errors() {
+    assert_eq!(
+      generate_tb("KQRvKR".parse().unwrap()).err(),
+      Some(GenerateError::TooManyPieces)
+    );
+    assert_eq!(
+      generate_tb("KQvQ".parse().unwrap()).err(),
+      Some(GenerateError::Kings)
+    );
+    // Nothing to solve without mating material
+    assert_eq!(
+      generate_tb("KNvK".parse().unwrap())
+        .unwrap()
+        .endings()
+        .count(),
+      0
+    );
+  }
+
+  #[test]
+  fn test_tablebase_interface() {
+    let tables = tables();
+    let board = GameData::from_fen("8/8/8/8/8/2k5/8/K6Q w - - 0 1")
+      .unwrap()
+      .board;
+    assert!(tables.covers(&board));
+    assert_eq!(tables.probe_wdl(&board), Some(Wdl::Win));
+    let mated = GameData::from_fen("Q6k/8/6K1/8/8/8/8/8 b - - 0 1")
+      .unwrap()
+      .board;
+    assert_eq!(tables.probe_dtz(&mated), Some(-1));
+
+    // The evaluation knows the result and prefers the faster win
+    let near = GameData::from_fen("7k/8/6K1/8/8/8/Q7/8 w - - 0 1")
+      .unwrap()
+      .board;
+    assert!(evaluate_with(&near, tables) > evaluate_with(&board, tables));
+    assert!(evaluate_with(&board, tables) > evaluate(&board) + 10_000);
+    assert_eq!(evaluate_with(&GameBoard::START_POS, tables), 0);
+  }
+}
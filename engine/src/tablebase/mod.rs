@@ -0,0 +1,274 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Endgame tablebase integration.
+//!
+//! The search talks to tablebases through the [`Tablebase`] trait, so any backend can be plugged
+//! in. Two things use it:
+//! - inside the tree, positions with few enough pieces are scored from their WDL value instead
+//!   of being searched;
+//! - at the root, [`rank_root_moves`] uses DTZ to keep only the moves that preserve the best
+//!   result reachable under the 50-move rule.
+//!
+//! The one backend here is [`GeneratedTablebase`], built in memory by [`generate_tb`] for endings
+//! of up to [`MAX_MEN`] pieces. There is no reader for Syzygy files, whose compressed format is
+//! a project of its own; [`Wdl`] and DTZ follow Syzygy's conventions, so a reader can implement
+//! [`Tablebase`] without converting its results. The `lumifox` binary loads no tables and offers
+//! no `SyzygyPath` option: embedders hand a backend to
+//! [`UciSession::set_tablebase`](crate::uci::session::UciSession::set_tablebase).
+
+use lumifox_chess::{
+  model::{gameboard::GameBoard, piecemove::PieceMove},
+  movegen::generate_moves,
+};
+
+use crate::search::{MATE_SCORE, MAX_PLY, in_check};
+
+mod generate;
+
+pub use generate::{GenerateError, GeneratedTablebase, MAX_MEN, generate_tb};
+
+/// Win/draw/loss from the point of view of the side to move, as stored in Syzygy WDL tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+  Loss,
+  /// A loss that the 50-move rule turns into a draw.
+  BlessedLoss,
+  Draw,
+  /// A win that the 50-move rule turns into a draw.
+  CursedWin,
+  Win,
+}
+
+impl Wdl {
+  pub fn flip(self) -> Wdl {
+    match self {
+      Wdl::Loss => Wdl::Win,
+      Wdl::BlessedLoss => Wdl::CursedWin,
+      Wdl::Draw => Wdl::Draw,
+      Wdl::CursedWin => Wdl::BlessedLoss,
+      Wdl::Win => Wdl::Loss,
+    }
+  }
+}
+
+/// A source of endgame tablebase results.
+pub trait Tablebase {
+  /// Largest number of pieces, kings included, the tables cover.
+  fn max_pieces(&self) -> u32;
+
+  /// Result of `board` with best play, or `None` if it is not in the tables.
+  fn probe_wdl(&self, board: &GameBoard) -> Option<Wdl>;
+
+  /// Distance to zeroing in plies, Syzygy style: positive if the side to move wins, negative if
+  /// it loses, and 0 for a draw. `None` if the position is not in the tables.
+  fn probe_dtz(&self, board: &GameBoard) -> Option<i32>;
+
+  /// Whether `board` is small enough to probe. Positions with castling rights never are.
+  fn covers(&self, board: &GameBoard) -> bool {
+    let men = u8::try_from(self.max_pieces()).unwrap_or(u8::MAX);
+    board.castling.is_empty() && board.material_signature().is_tablebase_position(men)
+  }
+}
+
+/// Scores below a mate but above any evaluation, for positions known to be won by the tables.
+pub const TB_WIN_SCORE: i32 = MATE_SCORE - MAX_PLY as i32 - 1;
+
+/// Search score for a tablebase result at `ply` from the root. Cursed wins and blessed losses are
+/// draws, since the 50-move rule applies.
+pub fn wdl_score(wdl: Wdl, ply: usize) -> i32 {
+  match wdl {
+    Wdl::Win => TB_WIN_SCORE - ply as i32,
+    Wdl::Loss => -TB_WIN_SCORE + ply as i32,
+    _ => 0,
+  }
+}
+
+/// Rank for a move whose DTZ guarantees the win inside the 50-move rule.
+const MAX_RANK: i32 = 1000;
+
+/// Rank the legal root moves of `board` by DTZ and return the ones that keep the best result,
+/// where `halfmove_clock` counts the plies since the last capture or pawn move.
+///
+/// A win that can be converted before the 50-move rule strikes ranks above any cursed win, and
+/// among cursed wins (or losses) the moves closest to zeroing are preferred. Returns `None` if
+/// the position, or any position after a legal move, is not covered by `tablebase`.
+pub fn rank_root_moves<T: Tablebase + ?Sized>(
+  tablebase: &T,
+  board: &GameBoard,
+  halfmove_clock: usize,
+) -> Option<Vec<PieceMove>> {
+  if !tablebase.covers(board) {
+    return None;
+  }
+
+  let (moves, count) = generate_moves(board);
+  let mut ranked = Vec::with_capacity(count);
+  for &piece_move in &moves[..count] {
+    let mut child = *board;
+    if child.move_piece(&piece_move).is_none() {
+      continue;
+    }
+
+    let zeroing = piece_move.is_capture()
+      || board.get_piece(piece_move.to_square()).is_some()
+      || board
+        .pawns
+        .get_bit(piece_move.from_square())
+        .unwrap_or(false);
+    let dtz = if zeroing {
+      // The clock resets, so only the result after the move matters
+      match tablebase.probe_wdl(&child)?.flip() {
+        Wdl::Win => 1,
+        Wdl::Loss => -1,
+        _ => 0,
+      }
+    } else {
+      match -tablebase.probe_dtz(&child)? {
+        dtz if dtz > 0 => dtz + 1,
+        dtz if dtz < 0 => dtz - 1,
+        _ => 0,
+      }
+    };
+    // Mating is as good as zeroing
+    let dtz = if dtz == 2 && in_check(&child) && !child.has_legal_move() {
+      1
+    } else {
+      dtz
+    };
+
+    let clock = halfmove_clock as i32;
+    let rank = if dtz > 0 {
+      if dtz + clock <= 99 {
+        MAX_RANK
+      } else {
+        MAX_RANK - (dtz + clock)
+      }
+    } else if dtz < 0 {
+      if -dtz * 2 + clock < 100 {
+        -MAX_RANK
+      } else {
+        -MAX_RANK + (-dtz + clock)
+      }
+    } else {
+      0
+    };
+    ranked.push((piece_move, rank));
+  }
+
+  let best = ranked.iter().map(|&(_, rank)| rank).max()?;
+  Some(
+    ranked
+      .into_iter()
+      .filter(|&(_, rank)| rank == best)
+      .map(|(piece_move, _)| piece_move)
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+  use lumifox_chess::{constants::*, model::gamedata::GameData};
+
+  /// Knows only KQ vs K: every position is a win for the side with the queen, with a made-up DTZ
+  /// that shrinks as the queen closes in on the defending king.
+  pub(crate) struct QueenEnding;
+
+  impl QueenEnding {
+    fn queen_side(board: &GameBoard) -> Option<bool> {
+      let queens = board.queens.raw();
+      if queens.count_ones() != 1 || board.combined().raw().count_ones() != 3 {
+        return None;
+      }
+      Some(board.colour.raw() & queens != 0)
+    }
+  }
+
+  impl Tablebase for QueenEnding {
+    fn max_pieces(&self) -> u32 {
+      3
+    }
+
+    fn probe_wdl(&self, board: &GameBoard) -> Option<Wdl> {
+      if board.combined().raw().count_ones() == 2 {
+        return Some(Wdl::Draw);
+      }
+      let white_has_queen = Self::queen_side(board)?;
+      Some(if white_has_queen == board.playing {
+        Wdl::Win
+      } else {
+        Wdl::Loss
+      })
+    }
+
+    fn probe_dtz(&self, board: &GameBoard) -> Option<i32> {
+      let dtz = match self.probe_wdl(board)? {
+        Wdl::Draw => return Some(0),
+        wdl => {
+          let king = board.king_square(!Self::queen_side(board)?)? as i32;
+          let queen = board.queens.raw().trailing_zeros() as i32;
+          let distance = (king % 8 - queen % 8).abs() + (king / 8 - queen / 8).abs();
+          (distance * 4 + 1) * if wdl == Wdl::Win { 1 } else { -1 }
+        }
+      };
+      Some(dtz)
+    }
+  }
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  #[test]
+  fn test_covers() {
+    let board = board_from_fen("7k/8/8/8/8/8/8/KQ6 w - - 0 1");
+    assert!(QueenEnding.covers(&board));
+    assert!(!QueenEnding.covers(&GameBoard::START_POS));
+  }
+
+  #[test]
+  fn test_root_moves_respect_fifty_move_rule() {
+    let board = board_from_fen("8/8/8/8/8/6k1/8/K6Q w - - 0 1");
+
+    // Every move converts in time with a fresh clock
+    let early = rank_root_moves(&QueenEnding, &board, 0).unwrap();
+    assert!(early.contains(&PieceMove::new(A1, B1, false, None)));
+
+    // Late in the count only the quickest progress keeps the win
+    let late = rank_root_moves(&QueenEnding, &board, 90).unwrap();
+    assert!(late.len() < early.len());
+    assert!(late.iter().all(|piece_move| early.contains(piece_move)));
+    assert!(late.contains(&PieceMove::new(H1, G2, false, None)));
+    assert!(!late.contains(&PieceMove::new(A1, B1, false, None)));
+  }
+
+  #[test]
+  fn test_root_moves_need_coverage() {
+    let board = board_from_fen("7k/8/8/3r4/8/1Q6/8/K7 w - - 0 1");
+    assert!(rank_root_moves(&QueenEnding, &board, 0).is_none());
+  }
+
+  #[test]
+  fn test_wdl_score() {
+    assert_eq!(wdl_score(Wdl::Win, 3), TB_WIN_SCORE - 3);
+    assert_eq!(wdl_score(Wdl::Loss, 3), -TB_WIN_SCORE + 3);
+    assert_eq!(wdl_score(Wdl::CursedWin, 3), 0);
+    assert!(TB_WIN_SCORE < MATE_SCORE - MAX_PLY as i32);
+  }
+}
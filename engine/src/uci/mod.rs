@@ -0,0 +1,29 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! UCI adapter
+//!
+//! Translates [`GuiToEngineCommand`](lumifox_uci::GuiToEngineCommand)s into calls on the search
+//! and writes the engine's replies back to the GUI.
+
+pub mod options;
+pub mod rate_limit;
+pub mod session;
+
+pub use rate_limit::{InfoRateLimit, RateLimiter};
+pub use session::UciSession;
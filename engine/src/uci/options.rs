@@ -0,0 +1,337 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! UCI options exposed by the engine and how they map onto the search configuration.
+
+use lumifox_uci::OptionType;
+
+use crate::search::{MAX_ELO, MAX_MULTI_PV, MIN_ELO, SearchConfig, tunables::find_tunable};
+
+/// Accessor for a boolean field of [`SearchConfig`].
+type Toggle = fn(&mut SearchConfig) -> &mut bool;
+
+/// On/off switches for the search's pruning and reduction techniques, deterministic mode,
+/// strength limiting and analysis mode, so their effect can be studied from a GUI. Each entry is
+/// the option name and the config field it controls.
+const SEARCH_TOGGLES: [(&str, Toggle); 8] = [
+  ("NullMovePruning", |config| &mut config.null_move),
+  ("LateMoveReductions", |config| {
+    &mut config.late_move_reductions
+  }),
+  ("FutilityPruning", |config| &mut config.futility_pruning),
+  ("ReverseFutilityPruning", |config| {
+    &mut config.reverse_futility_pruning
+  }),
+  ("CheckExtensions", |config| &mut config.check_extensions),
+  ("Deterministic", |config| &mut config.deterministic),
+  ("UCI_LimitStrength", |config| &mut config.limit_strength),
+  ("UCI_AnalyseMode", |config| &mut config.analyse_mode),
+];
+
+/// Name of the option that switches null-move verification on and off.
+const NULL_MOVE_VERIFICATION: &str = "NullMoveVerification";
+
+/// Accessor for a numeric field of [`SearchConfig`].
+type Number = fn(&mut SearchConfig) -> &mut u32;
+
+/// Numeric options: the option name, the config field it sets and the values it accepts.
+/// `UCI_Elo` is the rating played at while `UCI_LimitStrength` is on.
+const SEARCH_SPINS: [(&str, Number, u32, u32); 2] = [
+  ("MultiPV", |config| &mut config.multi_pv, 1, MAX_MULTI_PV),
+  ("UCI_Elo", |config| &mut config.elo, MIN_ELO, MAX_ELO),
+];
+
+/// The options to announce in reply to `uci`, with defaults taken from `config`.
+pub fn search_options(config: &SearchConfig) -> Vec<OptionType> {
+  let mut config = *config;
+  let mut options: Vec<_> = SEARCH_TOGGLES
+    .iter()
+    .map(|(name, field)| OptionType::Check {
+      name: name.to_string(),
+      default: *field(&mut config),
+    })
+    .collect();
+  options.push(OptionType::Check {
+    name: NULL_MOVE_VERIFICATION.to_string(),
+    default: config.null_move_verification.is_some(),
+  });
+  options.extend(
+    SEARCH_SPINS
+      .iter()
+      .map(|(name, field, min, max)| OptionType::Spin {
+        name: name.to_string(),
+        default: *field(&mut config) as i32,
+        min: *min as i32,
+        max: *max as i32,
+      }),
+  );
+  options
+}
+
+/// Apply `setoption name <name> value <value>` to `config`. Option names are matched without
+/// regard to case. Returns `false` if the option is unknown or the value is not valid for it.
+///
+/// The [tunables](crate::search::tunables) are accepted too, though [`search_options`] does not
+/// announce them.
+pub fn apply_search_option(config: &mut SearchConfig, name: &str, value: Option<&str>) -> bool {
+  if let Some(tunable) = find_tunable(name) {
+    return value
+      .and_then(|value| value.parse().ok())
+      .is_some_and(|number| tunable.set(config, number));
+  }
+  if let Some((_, field, min, max)) = SEARCH_SPINS
+    .iter()
+    .find(|(option, ..)| option.eq_ignore_ascii_case(name))
+  {
+    return match value.and_then(|value| value.parse().ok()) {
+      Some(number) if (*min..=*max).contains(&number) => {
+        *field(config) = number;
+        true
+      }
+      _ => false,
+    };
+  }
+
+  let Some(on) = value.and_then(parse_check) else {
+    return false;
+  };
+
+  if name.eq_ignore_ascii_case(NULL_MOVE_VERIFICATION) {
+    let default = SearchConfig::default().null_move_verification;
+    config.null_move_verification = if on { default } else { None };
+    return true;
+  }
+
+  match SEARCH_TOGGLES
+    .iter()
+    .find(|(option, _)| option.eq_ignore_ascii_case(name))
+  {
+    Some((_, field)) => {
+      *field(config) = on;
+      true
+    }
+    None => false,
+  }
+}
+
+/// Default of the `Hash` option, in megabytes.
+pub const DEFAULT_HASH_MB: u32 = 16;
+/// Largest `Hash` the engine announces, in megabytes.
+pub const MAX_HASH_MB: u32 = 65536;
+/// Largest `Threads` the engine announces.
+pub const MAX_THREADS: u32 = 256;
+
+/// Name of the button that empties the hash table.
+const CLEAR_HASH: &str = "Clear Hash";
+
+/// A change to an option that acts on the engine's resources rather than on [`SearchConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineOption {
+  /// `Hash`: size the transposition table to this many megabytes.
+  Hash(u32),
+  /// `Threads`: search with this many threads.
+  Threads(u32),
+  /// The `Clear Hash` button: forget what earlier searches stored.
+  ClearHash,
+}
+
+/// Where [`EngineOption`] changes go. The session calls it between searches only: a `setoption`
+/// that arrives while a search runs is refused without reaching the hooks.
+pub trait EngineHooks {
+  /// Apply `option`. Returns `false` to reject it, which the session reports to the GUI.
+  fn apply(&mut self, option: EngineOption) -> bool;
+}
+
+/// Accepts and ignores every change, for a searcher with nothing to resize. A session without
+/// hooks does not announce the engine options at all, so a GUI is never offered one that does
+/// nothing.
+impl EngineHooks for () {
+  fn apply(&mut self, _option: EngineOption) -> bool {
+    true
+  }
+}
+
+/// The engine options to announce in reply to `uci`: `Hash`, `Threads` and `Clear Hash`.
+pub fn engine_options() -> Vec<OptionType> {
+  vec![
+    OptionType::Spin {
+      name: "Hash".to_string(),
+      default: DEFAULT_HASH_MB as i32,
+      min: 1,
+      max: MAX_HASH_MB as i32,
+    },
+    OptionType::Spin {
+      name: "Threads".to_string(),
+      default: 1,
+      min: 1,
+      max: MAX_THREADS as i32,
+    },
+    OptionType::Button {
+      name: CLEAR_HASH.to_string(),
+    },
+  ]
+}
+
+/// The engine option `setoption name <name> value <value>` asks for, or `None` if the name is not
+/// an engine option or the value is out of range. Names are matched without regard to case.
+pub fn parse_engine_option(name: &str, value: Option<&str>) -> Option<EngineOption> {
+  if name.eq_ignore_ascii_case(CLEAR_HASH) {
+    return Some(EngineOption::ClearHash);
+  }
+  let number = value?.parse::<u32>().ok()?;
+  if name.eq_ignore_ascii_case("Hash") {
+    (1..=MAX_HASH_MB)
+      .contains(&number)
+      .then_some(EngineOption::Hash(number))
+  } else if name.eq_ignore_ascii_case("Threads") {
+    (1..=MAX_THREADS)
+      .contains(&number)
+      .then_some(EngineOption::Threads(number))
+  } else {
+    None
+  }
+}
+
+fn parse_check(value: &str) -> Option<bool> {
+  match value {
+    v if v.eq_ignore_ascii_case("true") => Some(true),
+    v if v.eq_ignore_ascii_case("false") => Some(false),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_options_reflect_config() {
+    let config = SearchConfig {
+      futility_pruning: false,
+      ..Default::default()
+    };
+    let options = search_options(&config);
+    assert_eq!(options.len(), SEARCH_TOGGLES.len() + 1 + SEARCH_SPINS.len());
+    assert!(options.contains(&OptionType::Spin {
+      name: "UCI_Elo".to_string(),
+      default: 1500,
+      min: 800,
+      max: 2400,
+    }));
+    assert!(options.contains(&OptionType::Check {
+      name: "FutilityPruning".to_string(),
+      default: false,
+    }));
+    assert!(options.contains(&OptionType::Check {
+      name: "NullMovePruning".to_string(),
+      default: true,
+    }));
+  }
+
+  #[test]
+  fn test_parse_engine_option() {
+    assert_eq!(
+      parse_engine_option("hash", Some("64")),
+      Some(EngineOption::Hash(64))
+    );
+    assert_eq!(
+      parse_engine_option("Threads", Some("4")),
+      Some(EngineOption::Threads(4))
+    );
+    assert_eq!(
+      parse_engine_option("clear hash", None),
+      Some(EngineOption::ClearHash)
+    );
+    assert_eq!(parse_engine_option("Hash", Some("0")), None);
+    assert_eq!(parse_engine_option("Threads", Some("many")), None);
+    assert_eq!(parse_engine_option("Threads", None), None);
+    assert_eq!(parse_engine_option("MultiPV", Some("2")), None);
+    assert!(engine_options().contains(&OptionType::Button {
+      name: "Clear Hash".to_string()
+    }));
+  }
+
+  #[test]
+  fn test_apply_option() {
+    let mut config = SearchConfig::default();
+    assert!(apply_search_option(
+      &mut config,
+      "latemovereductions",
+      Some("false")
+    ));
+    assert!(!config.late_move_reductions);
+
+    assert!(apply_search_option(
+      &mut config,
+      "NullMoveVerification",
+      Some("false")
+    ));
+    assert_eq!(config.null_move_verification, None);
+    assert!(apply_search_option(
+      &mut config,
+      "NullMoveVerification",
+      Some("true")
+    ));
+    assert_eq!(
+      config.null_move_verification,
+      SearchConfig::default().null_move_verification
+    );
+
+    assert!(apply_search_option(
+      &mut config,
+      "UCI_LimitStrength",
+      Some("true")
+    ));
+    assert!(apply_search_option(&mut config, "uci_elo", Some("1200")));
+    assert_eq!(config.elo, 1200);
+    assert!(config.limit_strength);
+    assert!(!apply_search_option(&mut config, "UCI_Elo", Some("100")));
+    assert!(!apply_search_option(&mut config, "UCI_Elo", Some("strong")));
+    assert_eq!(config.elo, 1200);
+
+    assert!(apply_search_option(
+      &mut config,
+      "UCI_AnalyseMode",
+      Some("true")
+    ));
+    assert!(config.analyse_mode);
+
+    assert!(apply_search_option(&mut config, "MultiPV", Some("3")));
+    assert_eq!(config.multi_pv, 3);
+    assert!(!apply_search_option(&mut config, "MultiPV", Some("0")));
+
+    // Tunables are hidden from the option list but can still be set
+    assert!(apply_search_option(&mut config, "LmrBase", Some("90")));
+    assert_eq!(config.lmr_base, 90);
+    assert!(!apply_search_option(&mut config, "LmrBase", Some("-5")));
+    assert!(
+      !search_options(&config)
+        .iter()
+        .any(|option| format!("{option:?}").contains("LmrBase"))
+    );
+
+    assert!(!apply_search_option(&mut config, "Hash", Some("16")));
+    assert!(!apply_search_option(
+      &mut config,
+      "FutilityPruning",
+      Some("maybe")
+    ));
+    assert!(!apply_search_option(&mut config, "FutilityPruning", None));
+    assert!(config.futility_pruning);
+  }
+}
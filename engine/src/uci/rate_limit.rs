@@ -0,0 +1,112 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::{Duration, Instant};
+
+/// Limits how often a kind of `info` line is sent to the GUI.
+///
+/// Nothing is sent during the first `delay` of a search, so short searches stay quiet, and after
+/// that at most one line is sent per `min_interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InfoRateLimit {
+  pub delay: Duration,
+  pub min_interval: Duration,
+}
+
+impl InfoRateLimit {
+  /// Never hold back a line.
+  pub const UNLIMITED: InfoRateLimit = InfoRateLimit {
+    delay: Duration::ZERO,
+    min_interval: Duration::ZERO,
+  };
+}
+
+impl Default for InfoRateLimit {
+  /// Wait 3 seconds, then send at most 10 lines per second.
+  fn default() -> Self {
+    Self {
+      delay: Duration::from_secs(3),
+      min_interval: Duration::from_millis(100),
+    }
+  }
+}
+
+/// Applies an [`InfoRateLimit`] to a single search.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimiter {
+  limit: InfoRateLimit,
+  start: Instant,
+  last: Option<Instant>,
+}
+
+impl RateLimiter {
+  pub fn new(limit: InfoRateLimit, start: Instant) -> Self {
+    Self {
+      limit,
+      start,
+      last: None,
+    }
+  }
+
+  /// Whether a line may be sent at `now`. Returning `true` counts as sending one.
+  pub fn allow(&mut self, now: Instant) -> bool {
+    if now.saturating_duration_since(self.start) < self.limit.delay {
+      return false;
+    }
+    if let Some(last) = self.last
+      && now.saturating_duration_since(last) < self.limit.min_interval
+    {
+      return false;
+    }
+    self.last = Some(now);
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_quiet_before_delay() {
+    let start = Instant::now();
+    let mut limiter = RateLimiter::new(InfoRateLimit::default(), start);
+    assert!(!limiter.allow(start));
+    assert!(!limiter.allow(start + Duration::from_millis(2999)));
+    assert!(limiter.allow(start + Duration::from_secs(3)));
+  }
+
+  #[test]
+  fn test_at_most_one_line_per_interval() {
+    let start = Instant::now();
+    let mut limiter = RateLimiter::new(InfoRateLimit::default(), start);
+    let t = start + Duration::from_secs(5);
+    assert!(limiter.allow(t));
+    assert!(!limiter.allow(t + Duration::from_millis(50)));
+    assert!(!limiter.allow(t + Duration::from_millis(99)));
+    assert!(limiter.allow(t + Duration::from_millis(100)));
+  }
+
+  #[test]
+  fn test_unlimited() {
+    let start = Instant::now();
+    let mut limiter = RateLimiter::new(InfoRateLimit::UNLIMITED, start);
+    assert!(limiter.allow(start));
+    assert!(limiter.allow(start));
+  }
+}
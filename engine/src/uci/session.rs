@@ -0,0 +1,863 @@
+/*
+ * A simple chess engine in Rust, with ambitious goals.
+ * Copyright (C) 2025  Clifton Toaster Reid
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+  io::{self, BufRead, Write},
+  sync::{
+    Arc, Mutex, MutexGuard,
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Sender},
+  },
+  thread::{self, JoinHandle},
+  time::Instant,
+};
+
+use lumifox_chess::{
+  model::{gamedata::GameData, piecemove::PieceMove},
+  movegen::generate_moves,
+};
+use lumifox_uci::{
+  EngineToGuiCommand, GoParams, GuiToEngineCommand, InfoType, PositionType, ScoreType,
+};
+
+use crate::{
+  search::{MATE_SCORE, MAX_PLY, PvLine, SearchListener, SearchResult, Searcher, TimeManager},
+  tablebase::{Tablebase, rank_root_moves},
+  uci::{
+    options::{
+      EngineHooks, apply_search_option, engine_options, parse_engine_option, search_options,
+    },
+    rate_limit::{InfoRateLimit, RateLimiter},
+  },
+};
+
+/// Depth searched when `go` sets no limit at all.
+pub const DEFAULT_DEPTH: u32 = 4;
+
+/// Stack of the search thread. Every ply of the search keeps a list of
+/// [`MAX_MOVES`](lumifox_chess::movegen::MAX_MOVES) moves on the stack, up to [`MAX_PLY`] deep,
+/// which can outgrow the default stack of a spawned thread in debug builds.
+const SEARCH_STACK_SIZE: usize = 16 << 20;
+
+/// State of one conversation with a GUI: the current game, the searcher and the output stream.
+///
+/// `go` searches on a separate thread, so `stop`, `quit` and `isready` are answered while it runs.
+/// Every other command waits for the search to finish first.
+pub struct UciSession<W: Write> {
+  /// Shared with the search thread, which sends the `info` and `bestmove` lines.
+  out: Arc<Mutex<W>>,
+  game: GameData,
+  /// Held by the search thread for as long as it searches.
+  searcher: Arc<Mutex<Searcher>>,
+  /// The searcher's stop flag, which can be set while the search thread holds the searcher.
+  stop: Arc<AtomicBool>,
+  /// The thread of the last search, until it has been waited for.
+  search: Option<JoinHandle<io::Result<()>>>,
+  /// Held while a `go infinite` search runs; dropping it lets the search send its best move.
+  infinite: Option<Sender<()>>,
+  /// Where `Hash`, `Threads` and `Clear Hash` changes go. Those options are only announced once
+  /// hooks are set.
+  hooks: Option<Box<dyn EngineHooks + Send>>,
+  /// How often `info currmove ... currmovenumber ...` lines are sent while searching.
+  pub currmove_limit: InfoRateLimit,
+}
+
+impl<W: Write + Send + 'static> UciSession<W> {
+  pub fn new(out: W) -> Self {
+    let searcher = Searcher::default();
+    Self {
+      out: Arc::new(Mutex::new(out)),
+      game: GameData::START_POS,
+      stop: searcher.stop_handle(),
+      searcher: Arc::new(Mutex::new(searcher)),
+      search: None,
+      infinite: None,
+      hooks: None,
+      currmove_limit: InfoRateLimit::default(),
+    }
+  }
+
+  /// Read commands from `input` until `quit` or end of input. Lines that fail to parse are
+  /// ignored, as the UCI protocol asks. A search still running at the end of input is allowed to
+  /// finish, unless it is infinite.
+  pub fn run(&mut self, input: impl BufRead) -> io::Result<()> {
+    for line in input.lines() {
+      let Ok(command) = line?.parse::<GuiToEngineCommand>() else {
+        continue;
+      };
+      if !self.handle(command)? {
+        return Ok(());
+      }
+    }
+    if self.infinite.is_some() {
+      self.stop_search();
+    }
+    self.wait()
+  }
+
+  /// Handle a single command. Returns `false` once the session should end. `go` returns as soon
+  /// as the search has started. A `setoption` sent while a search runs is refused, as the UCI
+  /// protocol asks, rather than waiting for the search.
+  pub fn handle(&mut self, command: GuiToEngineCommand) -> io::Result<bool> {
+    if !matches!(
+      command,
+      GuiToEngineCommand::IsReady
+        | GuiToEngineCommand::SetOption { .. }
+        | GuiToEngineCommand::Stop
+        | GuiToEngineCommand::PonderHit
+        | GuiToEngineCommand::Quit
+    ) {
+      self.wait()?;
+    }
+    match command {
+      GuiToEngineCommand::Uci => {
+        self.send(EngineToGuiCommand::Id {
+          name: Some(format!("Lumifox {}", env!("CARGO_PKG_VERSION"))),
+          author: Some("Clifton Toaster Reid".to_string()),
+        })?;
+        let engine = self
+          .hooks
+          .is_some()
+          .then(engine_options)
+          .unwrap_or_default();
+        let options = search_options(&self.searcher().config);
+        for option in engine.into_iter().chain(options) {
+          self.send(EngineToGuiCommand::Option { option })?;
+        }
+        self.send(EngineToGuiCommand::UciOk)?;
+      }
+      GuiToEngineCommand::SetOption { name, value } => {
+        if self
+          .search
+          .as_ref()
+          .is_some_and(|search| !search.is_finished())
+        {
+          writeln!(
+            self.out(),
+            "info string option not changed while searching: {name}"
+          )?;
+        } else {
+          self.wait()?;
+          self.set_option(&name, value.as_deref())?;
+        }
+      }
+      GuiToEngineCommand::IsReady => self.send(EngineToGuiCommand::ReadyOk)?,
+      GuiToEngineCommand::UciNewGame => self.game = GameData::START_POS,
+      GuiToEngineCommand::Position { position, .. } => self.set_position(*position),
+      GuiToEngineCommand::Go(params) => self.go(params)?,
+      GuiToEngineCommand::Stop => self.stop_search(),
+      GuiToEngineCommand::Quit => {
+        self.stop_search();
+        self.wait()?;
+        return Ok(false);
+      }
+      _ => {}
+    }
+    self.out().flush()?;
+    Ok(true)
+  }
+
+  /// Probe `tablebase` during searches and use it to filter root moves. Waits for the search in
+  /// progress, if any, to finish.
+  pub fn set_tablebase(&mut self, tablebase: Option<Arc<dyn Tablebase + Send + Sync>>) {
+    self.searcher().set_tablebase(tablebase);
+  }
+
+  /// Announce `Hash`, `Threads` and `Clear Hash` in reply to `uci`, and send changes to them to
+  /// `hooks`. Without hooks the session has nothing to apply them to and does not offer them.
+  pub fn set_engine_hooks(&mut self, hooks: Box<dyn EngineHooks + Send>) {
+    self.hooks = Some(hooks);
+  }
+
+  /// The game the next search starts from.
+  pub fn game(&self) -> &GameData {
+    &self.game
+  }
+
+  /// Consume the session and return its output stream, once the search in progress, if any, has
+  /// finished. An error the search hit while writing is dropped.
+  pub fn into_output(mut self) -> W {
+    let _ = self.wait();
+    let out =
+      Arc::try_unwrap(self.out).unwrap_or_else(|_| unreachable!("the search thread has ended"));
+    out
+      .into_inner()
+      .expect("no thread panics holding the output")
+  }
+
+  /// Stop the search in progress as soon as possible. It still sends its best move.
+  fn stop_search(&mut self) {
+    self.infinite = None;
+    // With no search running the flag would stop the next one instead
+    if self.search.is_some() {
+      self.stop.store(true, Ordering::Relaxed);
+    }
+  }
+
+  /// Wait for the search in progress, if any, to send its best move.
+  fn wait(&mut self) -> io::Result<()> {
+    match self.search.take().map(JoinHandle::join) {
+      Some(Ok(result)) => result,
+      Some(Err(panic)) => std::panic::resume_unwind(panic),
+      None => Ok(()),
+    }
+  }
+
+  fn searcher(&self) -> MutexGuard<'_, Searcher> {
+    self
+      .searcher
+      .lock()
+      .expect("no thread panics holding the searcher")
+  }
+
+  fn out(&self) -> MutexGuard<'_, W> {
+    self
+      .out
+      .lock()
+      .expect("no thread panics holding the output")
+  }
+
+  fn send(&mut self, command: EngineToGuiCommand) -> io::Result<()> {
+    write!(self.out(), "{command}")
+  }
+
+  fn set_option(&mut self, name: &str, value: Option<&str>) -> io::Result<()> {
+    if apply_search_option(&mut self.searcher().config, name, value) {
+      return Ok(());
+    }
+    let applied = match (parse_engine_option(name, value), self.hooks.as_mut()) {
+      (Some(option), Some(hooks)) => hooks.apply(option),
+      _ => return writeln!(self.out(), "info string unknown option or value: {name}"),
+    };
+    if !applied {
+      writeln!(
+        self.out(),
+        "info string option rejected by the engine: {name}"
+      )?;
+    }
+    Ok(())
+  }
+
+  fn set_position(&mut self, position: PositionType) {
+    let (mut game, moves) = match position {
+      PositionType::StartPos { moves } => (GameData::START_POS, moves),
+      PositionType::Fen { gamedata, moves } => (*gamedata, moves),
+    };
+    for piece_move in moves {
+      // Moves from the GUI carry no capture or castling flags, so use the generated move
+      let Some(piece_move) = resolve_move(&game, piece_move) else {
+        break;
+      };
+      if game.play_move(&piece_move).is_none() {
+        break;
+      }
+    }
+    self.game = game;
+  }
+
+  /// Thinking time for a search with `params`, or `None` if it is infinite or has neither a clock
+  /// nor a `movetime`.
+  fn time_manager(&self, params: &GoParams) -> Option<TimeManager> {
+    // An infinite search ignores the clock and runs until `stop`
+    if params.infinite {
+      return None;
+    }
+    let manager = TimeManager::new(&params.clock, self.game.board.playing, params.movetime)?;
+    // While the GUI analyses, a stable best move is no reason to stop early
+    Some(if self.searcher().config.analyse_mode {
+      manager.analysing()
+    } else {
+      manager
+    })
+  }
+
+  /// Start searching the current position on a new thread, within the limits `params` sets.
+  fn go(&mut self, params: GoParams) -> io::Result<()> {
+    let start = Instant::now();
+    let manager = self.time_manager(&params);
+    let limited = manager.is_some() || params.nodes.is_some() || params.mate.is_some();
+    let mut depth = match params.depth {
+      Some(depth) => depth,
+      // Leave it to the other limits, or to `stop`, to end the search
+      None if limited || params.infinite => MAX_PLY as u32,
+      None => DEFAULT_DEPTH,
+    };
+    // A mate in `n` moves is `2n - 1` plies deep
+    let mate_plies = params.mate.map(|moves| moves.saturating_mul(2) - 1);
+    if let Some(plies) = mate_plies {
+      depth = depth.min(plies);
+    }
+    depth = depth.min(MAX_PLY as u32);
+    self.searcher().set_node_limit(params.nodes);
+
+    // A list without a single legal move restricts nothing, so search every move instead
+    let mut root_moves = params
+      .searchmoves
+      .map(|moves| {
+        moves
+          .into_iter()
+          .filter_map(|piece_move| resolve_move(&self.game, piece_move))
+          .collect::<Vec<_>>()
+      })
+      .filter(|moves| !moves.is_empty());
+
+    // Let DTZ pick the moves that keep the best result under the 50-move rule, and leave the
+    // search to choose among them
+    let tablebase = self.searcher().tablebase().cloned();
+    let ranked = tablebase.as_ref().and_then(|tablebase| {
+      rank_root_moves(
+        tablebase.as_ref(),
+        &self.game.board,
+        self.game.halfmove_clock,
+      )
+    });
+    if let Some(ranked) = ranked {
+      let kept: Vec<_> = match &root_moves {
+        Some(moves) => ranked
+          .into_iter()
+          .filter(|piece_move| moves.contains(piece_move))
+          .collect(),
+        None => ranked,
+      };
+      if !kept.is_empty() {
+        let list: Vec<_> = kept.iter().map(PieceMove::to_string).collect();
+        writeln!(
+          self.out(),
+          "info string tablebase root moves {}",
+          list.join(" ")
+        )?;
+        root_moves = Some(kept);
+      }
+    }
+
+    // A stop that came after the last search had already ended is not meant for this one
+    self.stop.store(false, Ordering::Relaxed);
+    let (infinite, released) = mpsc::channel();
+    self.infinite = params.infinite.then_some(infinite);
+    let stop = Arc::clone(&self.stop);
+    let out = Arc::clone(&self.out);
+    let searcher = Arc::clone(&self.searcher);
+    let board = self.game.board;
+    let currmove_limit = self.currmove_limit;
+    let search = thread::Builder::new().stack_size(SEARCH_STACK_SIZE);
+    self.search = Some(search.spawn(move || {
+      let mut searcher = searcher
+        .lock()
+        .expect("no thread panics holding the searcher");
+      let mut reporter = SearchReporter {
+        out: &out,
+        limiter: RateLimiter::new(currmove_limit, start),
+        multi_pv: searcher.config.multi_pv > 1,
+        error: None,
+        start,
+        manager,
+        mate_plies,
+        stop,
+      };
+      let result = searcher.search_with(&board, depth, root_moves.as_deref(), &mut reporter);
+      if let Some(error) = reporter.error {
+        return Err(error);
+      }
+      // The best move of an infinite search waits for `stop`, which drops the sender
+      let _ = released.recv();
+
+      let mut info = vec![
+        InfoType::Depth(result.depth),
+        InfoType::Score(score_type(result.score)),
+        InfoType::Nodes(result.nodes),
+      ];
+      if tablebase.is_some() {
+        info.push(InfoType::TbHits(result.tb_hits));
+      }
+      let mut out = out.lock().expect("no thread panics holding the output");
+      write!(out, "{}", EngineToGuiCommand::Info { info })?;
+      match result.best_move {
+        Some(bestmove) => write!(
+          out,
+          "{}",
+          EngineToGuiCommand::BestMove {
+            bestmove,
+            ponder: None,
+          }
+        )?,
+        // UCI spells "no move" as 0000
+        None => writeln!(out, "bestmove 0000")?,
+      }
+      out.flush()
+    })?);
+    Ok(())
+  }
+}
+
+/// Find the generated move matching the squares and promotion of `piece_move`.
+fn resolve_move(game: &GameData, piece_move: PieceMove) -> Option<PieceMove> {
+  let (moves, count) = generate_moves(&game.board);
+  moves[..count].iter().copied().find(|candidate| {
+    candidate.from_square() == piece_move.from_square()
+      && candidate.to_square() == piece_move.to_square()
+      && candidate.promotion_type() == piece_move.promotion_type()
+  })
+}
+
+fn score_type(score: i32) -> ScoreType {
+  if score.abs() >= MATE_SCORE - MAX_PLY as i32 {
+    let plies = MATE_SCORE - score.abs();
+    let moves = (plies + 1) / 2;
+    ScoreType::Mate {
+      moves: if score > 0 { moves } else { -moves },
+      bound: None,
+    }
+  } else {
+    ScoreType::Cp {
+      value: score,
+      bound: None,
+    }
+  }
+}
+
+/// Sends `info currmove` lines as the root moves are searched, and an `info multipv` line for
+/// each line after every iteration in MultiPV mode. Also stops the search once it reaches the
+/// time or mate limit of its `go` command.
+struct SearchReporter<'a, W: Write> {
+  out: &'a Mutex<W>,
+  limiter: RateLimiter,
+  multi_pv: bool,
+  error: Option<io::Error>,
+  /// When `go` was received.
+  start: Instant,
+  manager: Option<TimeManager>,
+  /// A mate within this many plies ends the search.
+  mate_plies: Option<u32>,
+  stop: Arc<AtomicBool>,
+}
+
+impl<W: Write> SearchReporter<'_, W> {
+  fn send(&mut self, info: Vec<InfoType>) {
+    let info = EngineToGuiCommand::Info { info };
+    let mut out = self
+      .out
+      .lock()
+      .expect("no thread panics holding the output");
+    if let Err(error) = write!(out, "{info}").and_then(|()| out.flush()) {
+      self.error = Some(error);
+    }
+  }
+}
+
+impl<W: Write> SearchListener for SearchReporter<'_, W> {
+  fn root_move(&mut self, piece_move: PieceMove, move_number: u32) {
+    if let Some(manager) = &self.manager
+      && self.start.elapsed() >= manager.maximum()
+    {
+      self.stop.store(true, Ordering::Relaxed);
+    }
+    if self.error.is_some() || !self.limiter.allow(Instant::now()) {
+      return;
+    }
+    self.send(vec![
+      InfoType::CurrMove(piece_move),
+      InfoType::CurrMoveNumber(move_number),
+    ]);
+  }
+
+  fn iteration_complete(&mut self, result: &SearchResult, lines: &[PvLine]) {
+    let out_of_time = self
+      .manager
+      .is_some_and(|manager| manager.stop_after_iteration(self.start.elapsed(), result.stability));
+    let mated = self
+      .mate_plies
+      .is_some_and(|plies| MATE_SCORE - result.score <= plies as i32);
+    if out_of_time || mated {
+      self.stop.store(true, Ordering::Relaxed);
+    }
+
+    if !self.multi_pv || self.error.is_some() {
+      return;
+    }
+    for (line, number) in lines.iter().zip(1..) {
+      self.send(vec![
+        InfoType::Depth(result.depth),
+        InfoType::MultiPv(number),
+        InfoType::Score(score_type(line.score)),
+        InfoType::Nodes(result.nodes),
+        InfoType::Pv(vec![line.best_move]),
+      ]);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::tablebase::tests::QueenEnding;
+
+  fn run(session: &mut UciSession<Vec<u8>>, input: &str) -> String {
+    session.run(input.as_bytes()).unwrap();
+    String::from_utf8(std::mem::take(&mut *session.out())).unwrap()
+  }
+
+  #[test]
+  fn test_handshake() {
+    let mut session = UciSession::new(Vec::new());
+    let output = run(&mut session, "uci\nisready\n");
+    assert!(output.starts_with("id name Lumifox"));
+    assert!(output.ends_with("uciok\nreadyok\n"));
+  }
+
+  #[test]
+  fn test_setoption() {
+    let mut session = UciSession::new(Vec::new());
+    let output = run(&mut session, "uci\n");
+    assert!(output.contains("option name NullMovePruning type check default true\n"));
+
+    let output = run(
+      &mut session,
+      "setoption name NullMovePruning value false\nsetoption name Bogus value 1\n",
+    );
+    assert!(!session.searcher().config.null_move);
+    assert_eq!(output, "info string unknown option or value: Bogus\n");
+  }
+
+  #[test]
+  fn test_engine_options_reach_hooks() {
+    use std::sync::Mutex;
+
+    use crate::uci::options::EngineOption;
+
+    /// Records every change and refuses more than 8 threads.
+    struct Recorder(Arc<Mutex<Vec<EngineOption>>>);
+
+    impl EngineHooks for Recorder {
+      fn apply(&mut self, option: EngineOption) -> bool {
+        self.0.lock().unwrap().push(option);
+        !matches!(option, EngineOption::Threads(threads) if threads > 8)
+      }
+    }
+
+    // Without hooks there is nothing to apply the options to, so they are not offered
+    let mut session = UciSession::new(Vec::new());
+    let output = run(&mut session, "uci\n");
+    assert!(!output.contains("option name Hash "));
+    let output = run(&mut session, "setoption name Hash value 64\n");
+    assert_eq!(output, "info string unknown option or value: Hash\n");
+
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    session.set_engine_hooks(Box::new(Recorder(Arc::clone(&changes))));
+    let output = run(&mut session, "uci\n");
+    assert!(output.contains("option name Hash type spin default 16 min 1 max 65536\n"));
+    assert!(output.contains("option name Threads type spin default 1 min 1 max 256\n"));
+    assert!(output.contains("option name Clear Hash type button\n"));
+
+    let output = run(
+      &mut session,
+      "setoption name Hash value 64\nsetoption name Clear Hash\nsetoption name Threads value 16\nsetoption name Threads value 0\n",
+    );
+    assert_eq!(
+      *changes.lock().unwrap(),
+      [
+        EngineOption::Hash(64),
+        EngineOption::ClearHash,
+        EngineOption::Threads(16)
+      ]
+    );
+    assert_eq!(
+      output,
+      "info string option rejected by the engine: Threads\ninfo string unknown option or value: Threads\n"
+    );
+  }
+
+  #[test]
+  fn test_setoption_while_searching() {
+    let mut session = UciSession::new(Vec::new());
+    let output = run(
+      &mut session,
+      "position startpos\ngo infinite\nsetoption name MultiPV value 3\nstop\n",
+    );
+    assert!(output.contains("info string option not changed while searching: MultiPV\n"));
+    assert!(output.contains("\nbestmove "));
+    assert_eq!(session.searcher().config.multi_pv, 1);
+
+    // Once the search is over the change goes through
+    run(&mut session, "setoption name MultiPV value 3\n");
+    assert_eq!(session.searcher().config.multi_pv, 3);
+  }
+
+  #[test]
+  fn test_position_applies_moves() {
+    let mut session = UciSession::new(Vec::new());
+    run(&mut session, "position startpos moves e2e4 e7e5 g1f3\n");
+    assert_eq!(
+      session.game().to_fen(),
+      "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+  }
+
+  #[test]
+  fn test_currmove_is_rate_limited_by_default() {
+    let mut session = UciSession::new(Vec::new());
+    let output = run(&mut session, "position startpos\ngo depth 1\n");
+    assert!(!output.contains("currmove"));
+    assert!(output.contains("bestmove "));
+  }
+
+  #[test]
+  fn test_currmove_without_limit() {
+    let mut session = UciSession::new(Vec::new());
+    session.currmove_limit = InfoRateLimit::UNLIMITED;
+    let output = run(&mut session, "position startpos\ngo depth 1\n");
+    let currmoves: Vec<_> = output
+      .lines()
+      .filter(|line| line.starts_with("info currmove"))
+      .collect();
+    assert_eq!(currmoves.len(), 20);
+    assert!(currmoves[0].ends_with("currmovenumber 1"));
+    assert!(currmoves[19].ends_with("currmovenumber 20"));
+  }
+
+  #[test]
+  fn test_stop_and_quit_end_the_search() {
+    // Without the stop these would search for far longer than any test runs
+    let mut session = UciSession::new(Vec::new());
+    let output = run(
+      &mut session,
+      "position startpos\ngo depth 60\nisready\nstop\n",
+    );
+    let ready = output.find("readyok\n").unwrap();
+    let bestmove = output.find("bestmove ").unwrap();
+    assert!(ready < bestmove);
+    assert!(output.ends_with('\n'));
+
+    let output = run(&mut session, "go depth 60\nquit\nisready\n");
+    assert!(output.contains("bestmove "));
+    assert!(!output.contains("readyok"));
+
+    // A stop with nothing to stop does not cut the next search short
+    let output = run(
+      &mut session,
+      "position fen 6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1\nstop\ngo depth 2\n",
+    );
+    assert!(output.contains("info depth 2 score mate 1 "));
+    assert!(output.ends_with("bestmove e1e8\n"));
+  }
+
+  /// Depth and nodes of the summary sent before `bestmove`.
+  fn summary(output: &str) -> (u32, u64) {
+    let line = output
+      .lines()
+      .rfind(|line| line.starts_with("info depth "))
+      .unwrap();
+    let words: Vec<_> = line.split(' ').collect();
+    let value = |name| words[words.iter().position(|&word| word == name).unwrap() + 1];
+    (
+      value("depth").parse().unwrap(),
+      value("nodes").parse().unwrap(),
+    )
+  }
+
+  #[test]
+  fn test_go_limits() {
+    use std::time::Duration;
+
+    // On the clock the search goes past the default depth, and stops well within the budget
+    let mut session = UciSession::new(Vec::new());
+    let started = Instant::now();
+    let output = run(
+      &mut session,
+      "position startpos\ngo wtime 3000 btime 3000\n",
+    );
+    assert!(started.elapsed() < Duration::from_millis(1_000));
+    assert!(summary(&output).0 > DEFAULT_DEPTH);
+
+    let started = Instant::now();
+    let output = run(&mut session, "go movetime 100\n");
+    assert!(started.elapsed() < Duration::from_millis(1_000));
+    assert!(output.contains("bestmove "));
+
+    let output = run(&mut session, "go nodes 500\n");
+    assert!((500..600).contains(&summary(&output).1));
+    // The node limit only applies to the search that asked for it
+    let output = run(&mut session, "go depth 3\n");
+    assert!(summary(&output).1 > 600);
+
+    // Deep enough for a mate in 3, but the mate in 1 ends the search
+    let output = run(
+      &mut session,
+      "position fen 6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1\ngo mate 3\n",
+    );
+    assert!(output.contains("info depth 1 score mate 1 "));
+    assert!(output.ends_with("bestmove e1e8\n"));
+  }
+
+  #[test]
+  fn test_deterministic_search_keeps_to_the_clock() {
+    use std::time::Duration;
+
+    let mut session = UciSession::new(Vec::new());
+    let started = Instant::now();
+    let output = run(
+      &mut session,
+      "setoption name Deterministic value true\nposition startpos\ngo wtime 1000 btime 1000\n",
+    );
+    assert!(started.elapsed() < Duration::from_millis(1_000));
+    assert!(output.contains("bestmove "));
+
+    let output = run(&mut session, "go infinite\nstop\n");
+    assert!(output.contains("bestmove "));
+  }
+
+  #[test]
+  fn test_analyse_mode_time_manager() {
+    let params = |args: &str| GoParams::parse(&args.split(' ').collect::<Vec<_>>()).unwrap();
+    let timed = params("wtime 3000 btime 3000");
+
+    let mut session = UciSession::new(Vec::new());
+    let playing = session.time_manager(&timed).unwrap();
+    assert_eq!(playing, TimeManager::new(&timed.clock, true, None).unwrap());
+    run(&mut session, "setoption name UCI_AnalyseMode value true\n");
+    assert_eq!(session.time_manager(&timed), Some(playing.analysing()));
+
+    // Black's clock is used when black is to move, and an infinite search has no budget
+    run(&mut session, "position startpos moves e2e4\n");
+    let black = TimeManager::new(&timed.clock, false, None).unwrap();
+    assert_eq!(session.time_manager(&timed), Some(black.analysing()));
+    assert_eq!(
+      session.time_manager(&params("infinite wtime 3000 btime 3000")),
+      None
+    );
+  }
+
+  #[test]
+  fn test_go_infinite_waits_for_stop() {
+    use std::time::Duration;
+
+    let mut session = UciSession::new(Vec::new());
+    run(
+      &mut session,
+      "position fen 6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1\n",
+    );
+    session.handle("go infinite".parse().unwrap()).unwrap();
+    // The mate is found at once, but the best move is held back until `stop`
+    thread::sleep(Duration::from_millis(100));
+    assert!(!String::from_utf8_lossy(&session.out()).contains("bestmove"));
+    let output = run(&mut session, "isready\nstop\n");
+    assert!(output.contains("readyok\n"));
+    assert!(output.ends_with("bestmove e1e8\n"));
+
+    // The end of input stops an infinite search too
+    let output = run(&mut session, "go infinite\n");
+    assert!(output.contains("\nbestmove "));
+  }
+
+  #[test]
+  fn test_searchmoves() {
+    let mut session = UciSession::new(Vec::new());
+    let output = run(
+      &mut session,
+      "position fen 6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1\ngo depth 2 searchmoves a1b1\n",
+    );
+    assert!(output.ends_with("bestmove a1b1\n"));
+  }
+
+  #[test]
+  fn test_searchmoves_without_legal_moves() {
+    let mut session = UciSession::new(Vec::new());
+    let output = run(
+      &mut session,
+      "position fen 6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1\ngo depth 2 searchmoves a1a3 e1f2\n",
+    );
+    assert!(output.ends_with("bestmove e1e8\n"));
+
+    // The tablebase still ranks the moves when none of the given ones is legal
+    let mut session = UciSession::new(Vec::new());
+    session.set_tablebase(Some(Arc::new(QueenEnding)));
+    let output = run(
+      &mut session,
+      "position fen 8/8/8/8/8/6k1/8/K6Q w - - 90 80\ngo depth 1 searchmoves a1c3 h1b4\n",
+    );
+    assert!(output.contains("info string tablebase root moves "));
+    assert!(!output.ends_with("bestmove 0000\n"));
+  }
+
+  #[test]
+  fn test_multipv_reports_distinct_lines() {
+    let mut session = UciSession::new(Vec::new());
+    let output = run(
+      &mut session,
+      "setoption name MultiPV value 3\nposition fen 6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1\ngo depth 2\n",
+    );
+    let last: Vec<_> = output
+      .lines()
+      .filter(|line| line.starts_with("info depth 2 multipv "))
+      .collect();
+    assert_eq!(last.len(), 3);
+    assert!(last[0].starts_with("info depth 2 multipv 1 score mate 1 "));
+    assert!(last[0].ends_with(" pv e1e8"));
+    assert!(last[1].contains(" multipv 2 score cp "));
+    let moves: Vec<_> = last.iter().map(|line| line.rsplit(' ').next()).collect();
+    assert!(moves[1] != moves[0] && moves[2] != moves[0] && moves[2] != moves[1]);
+    assert!(output.ends_with("bestmove e1e8\n"));
+
+    // Without MultiPV only the summary is sent
+    let mut session = UciSession::new(Vec::new());
+    let output = run(&mut session, "position startpos\ngo depth 2\n");
+    assert!(!output.contains("multipv"));
+  }
+
+  #[test]
+  fn test_tablebase_root_filter() {
+    let mut session = UciSession::new(Vec::new());
+    session.set_tablebase(Some(Arc::new(QueenEnding)));
+    let output = run(
+      &mut session,
+      "position fen 8/8/8/8/8/6k1/8/K6Q w - - 90 80\ngo depth 1\n",
+    );
+    // Only moves that still convert before the 50-move rule survive
+    let filter = output
+      .lines()
+      .find(|line| line.starts_with("info string tablebase root moves "))
+      .unwrap();
+    assert!(filter.contains("h1g2"));
+    assert!(!filter.contains("a1b1"));
+    assert!(output.contains(" tbhits "));
+    assert!(!output.ends_with("bestmove a1b1\n"));
+
+    // Without tables nothing is reported
+    let mut session = UciSession::new(Vec::new());
+    let output = run(&mut session, "position startpos\ngo depth 1\n");
+    assert!(!output.contains("tablebase"));
+    assert!(!output.contains("tbhits"));
+  }
+
+  #[test]
+  fn test_mate_score_and_no_move() {
+    let mut session = UciSession::new(Vec::new());
+    let output = run(
+      &mut session,
+      "position fen 6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1\ngo depth 2\n",
+    );
+    assert!(output.contains("score mate 1"));
+    assert!(output.ends_with("bestmove e1e8\n"));
+
+    let output = run(
+      &mut session,
+      "position fen R5k1/5ppp/8/8/8/8/8/K7 b - - 0 1\ngo depth 2\n",
+    );
+    assert!(output.contains("score mate 0"));
+    assert!(output.ends_with("bestmove 0000\n"));
+  }
+}
@@ -18,7 +18,7 @@
 use lumifox_chess::{
   model::{
     gamedata::GameData,
-    piecemove::{PieceMove, PromotionType},
+    piecemove::{PieceMove, PromotionType, parse_square},
   },
   movegen::generate_moves,
 };
@@ -61,26 +61,6 @@ fn print_move(piece_move: &PieceMove) {
   }
 }
 
-fn alg_to_square(alg: &str) -> Option<u8> {
-  if alg.len() != 2 {
-    return None;
-  }
-  let mut chars = alg.chars();
-  let file_char = chars.next()?;
-  let rank_char = chars.next()?;
-
-  let file = match file_char {
-    'a'..='h' => file_char as u8 - b'a',
-    _ => return None,
-  };
-  let rank = match rank_char {
-    '1'..='8' => rank_char as u8 - b'1',
-    _ => return None,
-  };
-
-  Some(rank * 8 + file)
-}
-
 fn main() {
   use std::env;
   let args: Vec<String> = env::args().collect();
@@ -184,14 +164,14 @@ fn main() {
         None
       };
 
-      let from = match alg_to_square(from_str) {
+      let from = match parse_square(from_str) {
         Some(sq) => sq,
         None => {
           println!("❌ Invalid from square '{}'. Use a1-h8 format.", from_str);
           continue;
         }
       };
-      let to = match alg_to_square(to_str) {
+      let to = match parse_square(to_str) {
         Some(sq) => sq,
         None => {
           println!("❌ Invalid to square '{}'. Use a1-h8 format.", to_str);
@@ -287,19 +267,19 @@ fn main() {
           // legality checks and returns None if the move is illegal.
           let mut found_safe_move = false;
           for &test_mv in moves.iter().take(count) {
-            if test_mv != mv {
-              if let Some(()) = game.board.move_piece(&test_mv) {
-                print!("🤖 AI plays (retry): ");
-                print_move(&test_mv);
-                game.plies += 1;
-                if test_mv.is_capture() {
-                  game.halfmove_clock = 0;
-                } else {
-                  game.halfmove_clock += 1;
-                }
-                found_safe_move = true;
-                break;
+            if test_mv != mv
+              && let Some(()) = game.board.move_piece(&test_mv)
+            {
+              print!("🤖 AI plays (retry): ");
+              print_move(&test_mv);
+              game.plies += 1;
+              if test_mv.is_capture() {
+                game.halfmove_clock = 0;
+              } else {
+                game.halfmove_clock += 1;
               }
+              found_safe_move = true;
+              break;
             }
           }
 
@@ -1,4 +1,5 @@
 use lumifox_chess::{
+  constants::START_FEN,
   model::{gamedata::GameData, piecemove::PieceMove},
   movegen::generate_moves,
 };
@@ -24,11 +25,9 @@ fn print_move(piece_move: &PieceMove) {
 }
 
 fn main() {
-  let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+  let mut game = GameData::from_fen(START_FEN).expect("Failed to parse FEN string");
 
-  let mut game = GameData::from_fen(fen).expect("Failed to parse FEN string");
-
-  println!("Initial board state from FEN: {fen}\n");
+  println!("Initial board state from FEN: {START_FEN}\n");
   game.print_board();
 
   println!(
@@ -0,0 +1,88 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Deprecated forms of APIs that have since changed shape, kept for one release cycle.
+//!
+//! Where a function can stand in for an old form, it is kept as a `#[deprecated]` wrapper over
+//! the new one, here or next to its replacement, and the warning names the replacement. Each is
+//! removed in the release after the one that deprecated it.
+//!
+//! | Deprecated                               | Replacement                        |
+//! |------------------------------------------|------------------------------------|
+//! | [`castling_bits`], [`set_castling_bits`] | [`CastlingRights`] and its `bits`  |
+//! | `NOT_A_FILE`, `NOT_AB_FILE`, ...         | `NOT_FILE_A`, `NOT_FILE_AB`, ...   |
+//! | `GameBoard::combined_coloured`           | [`GameBoard::pieces_of`]           |
+//! | `GameBoard::refresh_mailbox`             | [`GameBoard::refresh_derived`]     |
+//!
+//! Changes to public fields, statics and struct layouts can't be wrapped, so code that used
+//! them must be updated. These came in 0.2.0:
+//!
+//! - `GameBoard::castling` is a [`CastlingRights`] rather than a 4-bit `u8`.
+//! - `GameBoard::en_passant` is an `Option<PieceMove>`, `None` where it used to hold the null
+//!   move.
+//! - `GameBoard` has a private field, the Zobrist key of its pieces, so it can no longer be built
+//!   with a struct literal. Start from `GameBoard::START_POS` or a FEN, and call
+//!   [`GameBoard::refresh_derived`] after editing its fields.
+//! - `RAYS` and `KNIGHT_MOVES` are `static` [`CacheAligned`](crate::model::rays::CacheAligned)
+//!   tables rather than `const` arrays: index them through `.0`, and they can't be used in
+//!   `const` items.
+//!
+//! CI runs `cargo-semver-checks` against the commit each change builds on, so a break shows up
+//! there and needs a version bump, as these did.
+
+#[allow(deprecated)]
+pub use crate::constants::{NOT_A_FILE, NOT_AB_FILE, NOT_GH_FILE, NOT_H_FILE};
+use crate::model::{castling::CastlingRights, gameboard::GameBoard};
+
+/// The castling rights of `board` in the old 4-bit encoding: bit 0 white kingside, bit 1 white
+/// queenside, bit 2 black kingside, bit 3 black queenside.
+///
+/// The encoding only says which rights are left, not which rook each castles with, so a
+/// Chess960 right is reported the same as a standard one.
+#[deprecated(note = "read `board.castling`, a `CastlingRights`, and call `bits` if needed")]
+pub const fn castling_bits(board: &GameBoard) -> u8 {
+  board.castling.bits()
+}
+
+/// Set the castling rights of `board` from the old 4-bit encoding, with every rook on its
+/// standard corner square.
+#[deprecated(note = "assign `board.castling` a `CastlingRights`, such as `from_bits(bits)`")]
+pub fn set_castling_bits(board: &mut GameBoard, bits: u8) {
+  board.castling = CastlingRights::from_bits(bits);
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+  use super::*;
+  use crate::constants::{NOT_FILE_A, NOT_FILE_H};
+
+  #[test]
+  fn test_wrappers_match_replacements() {
+    let mut board = GameBoard::START_POS;
+    assert_eq!(castling_bits(&board), 0b1111);
+    set_castling_bits(&mut board, 0b0101);
+    assert!(board.castling.has(true, true) && board.castling.has(false, true));
+    assert!(!board.castling.has(true, false) && !board.castling.has(false, false));
+    assert_eq!(castling_bits(&board), 0b0101);
+
+    assert_eq!(board.combined_coloured(true), board.pieces_of(false));
+    assert_eq!(NOT_A_FILE, NOT_FILE_A);
+    assert_eq!(NOT_H_FILE, NOT_FILE_H);
+  }
+}
@@ -16,6 +16,35 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
+//! Shared constants: the starting FEN, piece values, file, rank and square-colour masks,
+//! move-encoding masks and square indices. Downstream crates take them from here rather than
+//! keeping copies.
+//!
+//! # Square numbering
+//!
+//! Every square in this crate is a `u8` from 0 to 63 counted from a1: a1 = 0, b1 = 1, ...,
+//! h1 = 7, a2 = 8, ..., h8 = 63, so `rank * 8 + file` with both counted from 0. Bit `n` of a
+//! bitboard is square `n`. UCI, FEN squares and Polyglot book moves read the same way.
+//!
+//! Some GUIs, JavaScript libraries and board arrays printed top-down count from a8 instead
+//! (a8 = 0, h8 = 7, a1 = 56). Convert at the boundary with [`to_a8_index`] and
+//! [`from_a8_index`] rather than flipping ranks by hand, which is where off-by-rank bugs creep
+//! into FEN and book code.
+
+use crate::model::gameboard::PieceType;
+
+/// The standard starting position as FEN, the position `GameData::START_POS` holds.
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Material value of each piece type in centipawns, indexed by `PieceType as usize`: pawn,
+/// knight, bishop, rook, queen and king. The king is never traded so it is worth 0.
+pub const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+/// The value of `piece` in [`PIECE_VALUES`].
+pub const fn piece_value(piece: PieceType) -> i32 {
+  PIECE_VALUES[piece as usize]
+}
+
 pub static NUMBER_OF_PIECES: usize = 6;
 pub static BOARD_SIZE: usize = 64;
 
@@ -42,10 +71,55 @@ pub const RANK_6: u64 = 0x0000_FF00_0000_0000;
 pub const RANK_7: u64 = 0x00FF_0000_0000_0000;
 pub const RANK_8: u64 = 0xFF00_0000_0000_0000;
 
-pub const NOT_A_FILE: u64 = !FILE_A;
-pub const NOT_AB_FILE: u64 = !(FILE_A | FILE_B);
-pub const NOT_H_FILE: u64 = !FILE_H;
-pub const NOT_GH_FILE: u64 = !(FILE_G | FILE_H);
+/// Light squares: h1, a2 and every square of their colour.
+pub const LIGHT_SQUARES: u64 = 0x55AA_55AA_55AA_55AA;
+/// Dark squares: a1, h8 and every square of their colour.
+pub const DARK_SQUARES: u64 = !LIGHT_SQUARES;
+
+/// Whether `square` is a light square. Squares off the board are neither light nor dark.
+pub const fn is_light(square: u8) -> bool {
+  square < 64 && LIGHT_SQUARES & (1 << square) != 0
+}
+
+/// Whether `square` is a dark square.
+pub const fn is_dark(square: u8) -> bool {
+  square < 64 && DARK_SQUARES & (1 << square) != 0
+}
+
+/// The files from a to h, so `FILES[square as usize % 8]` is the file of a square.
+pub const FILES: [u64; 8] = [
+  FILE_A, FILE_B, FILE_C, FILE_D, FILE_E, FILE_F, FILE_G, FILE_H,
+];
+/// The ranks from 1 to 8, so `RANKS[square as usize / 8]` is the rank of a square.
+pub const RANKS: [u64; 8] = [
+  RANK_1, RANK_2, RANK_3, RANK_4, RANK_5, RANK_6, RANK_7, RANK_8,
+];
+
+// Everything but the edge files, for masking off shifts that would wrap around the board
+pub const NOT_FILE_A: u64 = !FILE_A;
+pub const NOT_FILE_AB: u64 = !(FILE_A | FILE_B);
+pub const NOT_FILE_H: u64 = !FILE_H;
+pub const NOT_FILE_GH: u64 = !(FILE_G | FILE_H);
+
+#[deprecated(note = "renamed to `NOT_FILE_A`")]
+pub const NOT_A_FILE: u64 = NOT_FILE_A;
+#[deprecated(note = "renamed to `NOT_FILE_AB`")]
+pub const NOT_AB_FILE: u64 = NOT_FILE_AB;
+#[deprecated(note = "renamed to `NOT_FILE_H`")]
+pub const NOT_H_FILE: u64 = NOT_FILE_H;
+#[deprecated(note = "renamed to `NOT_FILE_GH`")]
+pub const NOT_GH_FILE: u64 = NOT_FILE_GH;
+
+/// The rank the pawns of the side given by `white` promote on.
+pub const fn promotion_rank(white: bool) -> u64 {
+  if white { RANK_8 } else { RANK_1 }
+}
+
+/// The rank the pawns of the side given by `white` start on, the only one they can push two
+/// squares from.
+pub const fn double_push_rank(white: bool) -> u64 {
+  if white { RANK_2 } else { RANK_7 }
+}
 
 pub const FROM_MASK: u16 = 0b0000_0000_0011_1111;
 pub const DEST_MASK: u16 = 0b0000_1111_1100_0000;
@@ -54,7 +128,7 @@ pub const CAPTURE_MASK: u16 = 0b0010_0000_0000_0000;
 pub const EN_PASSANT_MASK: u16 = 0b0100_0000_0000_0000;
 pub const CASTLING_MASK: u16 = 0b1000_0000_0000_0000;
 
-// Individual square definitions
+// Individual square definitions, numbered from a1 (see the module docs)
 pub const A1: u8 = 0;
 pub const B1: u8 = 1;
 pub const C1: u8 = 2;
@@ -126,3 +200,78 @@ pub const E8: u8 = 60;
 pub const F8: u8 = 61;
 pub const G8: u8 = 62;
 pub const H8: u8 = 63;
+
+/// The square on `file` and `rank`, both counted from 0 (the a-file and the first rank), or
+/// `None` when either is off the board.
+pub const fn square_at(file: u8, rank: u8) -> Option<u8> {
+  if file < 8 && rank < 8 {
+    Some(rank * 8 + file)
+  } else {
+    None
+  }
+}
+
+/// The index of `square` when squares are counted from a8 (a8 = 0, h8 = 7, a1 = 56), or `None`
+/// when `square` is off the board. The file stays the same and the rank is flipped.
+pub const fn to_a8_index(square: u8) -> Option<u8> {
+  if square < 64 { Some(square ^ 56) } else { None }
+}
+
+/// The square an index counted from a8 (a8 = 0, h8 = 7, a1 = 56) stands for, or `None` when the
+/// index is off the board. Inverse of [`to_a8_index`].
+pub const fn from_a8_index(index: u8) -> Option<u8> {
+  to_a8_index(index)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::gamedata::GameData;
+
+  #[test]
+  fn test_start_fen_and_values() {
+    let start = GameData::from_fen(START_FEN).unwrap();
+    assert_eq!(start.board, GameData::START_POS.board);
+    assert_eq!(piece_value(PieceType::Pawn), 100);
+    assert_eq!(piece_value(PieceType::Queen), 900);
+    assert_eq!(piece_value(PieceType::King), 0);
+  }
+
+  #[test]
+  fn test_files_and_ranks() {
+    for square in 0..64u8 {
+      let bit = 1u64 << square;
+      assert_eq!(FILES[square as usize % 8] & bit, bit);
+      assert_eq!(RANKS[square as usize / 8] & bit, bit);
+      assert_eq!(FILES.iter().filter(|&&file| file & bit != 0).count(), 1);
+      assert_eq!(RANKS.iter().filter(|&&rank| rank & bit != 0).count(), 1);
+    }
+    assert_eq!(NOT_FILE_A & FILE_A, 0);
+    assert_eq!(NOT_FILE_GH | FILE_G | FILE_H, u64::MAX);
+    assert_eq!(promotion_rank(true), 0xFF << (8 * 7));
+    assert_eq!(double_push_rank(false), 0xFF << (8 * 6));
+  }
+
+  #[test]
+  fn test_a8_index() {
+    assert_eq!(to_a8_index(A8), Some(0));
+    assert_eq!(to_a8_index(H8), Some(7));
+    assert_eq!(to_a8_index(A1), Some(56));
+    assert_eq!(to_a8_index(H1), Some(63));
+    assert_eq!(to_a8_index(E4), Some(36));
+    assert_eq!(from_a8_index(36), Some(E4));
+    assert_eq!(to_a8_index(64), None);
+    assert_eq!(from_a8_index(64), None);
+
+    for square in 0..64u8 {
+      let index = to_a8_index(square).unwrap();
+      assert_eq!(from_a8_index(index), Some(square));
+      // Same file, rank counted from the top
+      assert_eq!(index % 8, square % 8);
+      assert_eq!(index / 8, 7 - square / 8);
+      assert_eq!(square_at(square % 8, square / 8), Some(square));
+    }
+    assert_eq!(square_at(8, 0), None);
+    assert_eq!(square_at(0, 8), None);
+  }
+}
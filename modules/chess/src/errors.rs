@@ -15,6 +15,7 @@
  * You should have received a copy of the GNU Lesser General Public License
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
+use crate::model::gameboard::PieceType;
 
 pub enum InvalidMove {
   OutOfBounds,
@@ -44,6 +45,8 @@ pub enum FenParseError {
   InvalidCastling,
   /// An invalid character was found in the castling availability field.
   InvalidCastlingChar,
+  /// A castling right has no king on its back rank or no rook on the right's square.
+  InvalidCastlingContext,
   /// Error parsing the en passant target square field.
   InvalidEnPassant,
   /// The en passant square is not a valid algebraic notation.
@@ -54,6 +57,8 @@ pub enum FenParseError {
   InvalidHalfmoveClock,
   /// Error parsing the full-move number (not a valid number).
   InvalidFullmoveNumber,
+  /// The Three-check field is not `+W+B` (or `W+B` before the clocks) with counts up to 3.
+  InvalidCheckCount,
   /// A numeric value was expected but not found or was unparseable.
   ExpectedNumber,
   /// An unexpected character was encountered during parsing.
@@ -76,4 +81,358 @@ pub enum MoveParseError {
   OutOfBounds,
   /// Invalid character for the promotion piece.
   InvalidPromotionPiece,
+  /// A promotion that doesn't go from the seventh rank to the eighth (or second to first).
+  InvalidPromotionRank,
+  /// A promotion by a piece other than a pawn.
+  PromotionByNonPawn,
+  /// A pawn move to the last rank without a promotion piece.
+  MissingPromotion,
+  /// There is no piece of the side to move on the from square.
+  NoPieceOnSquare,
+}
+
+impl core::fmt::Display for MoveParseError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let message = match self {
+      MoveParseError::TooShort => "move is shorter than 4 characters",
+      MoveParseError::InvalidFromFile => "invalid from file",
+      MoveParseError::InvalidFromRank => "invalid from rank",
+      MoveParseError::InvalidToFile => "invalid to file",
+      MoveParseError::InvalidToRank => "invalid to rank",
+      MoveParseError::OutOfBounds => "square is off the board",
+      MoveParseError::InvalidPromotionPiece => "promotion piece must be one of q, r, b or n",
+      MoveParseError::InvalidPromotionRank => "promotions must move onto the last rank",
+      MoveParseError::PromotionByNonPawn => "only pawns can promote",
+      MoveParseError::MissingPromotion => "pawn reaches the last rank without promoting",
+      MoveParseError::NoPieceOnSquare => "no piece of the side to move on the from square",
+    };
+    f.write_str(message)
+  }
+}
+
+/// Why a move is illegal in a given position, from
+/// [`GameBoard::explain_illegal`](crate::model::gameboard::GameBoard::explain_illegal).
+///
+/// [`Display`](core::fmt::Display) gives a sentence meant for players, naming the pieces and
+/// squares involved ("your king would be in check from the rook on h1");
+/// [`message`](IllegalMoveReason::message) gives the same without the details.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IllegalMoveReason {
+  /// The move is [`PieceMove::NULL`](crate::model::piecemove::PieceMove::NULL), which stands
+  /// for no move at all.
+  NullMove,
+  /// There is no piece on the from square.
+  NoPiece { square: u8 },
+  /// The piece on the from square belongs to the side not to move.
+  OpponentsPiece { square: u8 },
+  /// The piece can't reach the destination even on an empty board.
+  WrongMovement { piece: PieceType },
+  /// The piece moves that way, but `blocker` is in the way.
+  Blocked { piece: PieceType, blocker: u8 },
+  /// The destination holds one of the mover's own pieces.
+  OwnPieceOnTarget { square: u8 },
+  /// The destination holds the enemy king.
+  CapturesKing,
+  /// A pawn moves diagonally onto an empty square.
+  PawnCaptureWithoutTarget,
+  /// A pawn reaches the last rank without a promotion piece.
+  MissingPromotion,
+  /// A promotion piece is given for a move that doesn't reach the last rank.
+  UnexpectedPromotion,
+  /// An en passant capture when there is no pawn to take that way.
+  EnPassantUnavailable,
+  /// The king moves two squares along its home rank without the right to castle that way.
+  NoCastlingRight,
+  /// The king or rook would pass over `blocker` when castling.
+  CastlingBlocked { blocker: u8 },
+  /// Castling while the king is in check.
+  CastlingOutOfCheck,
+  /// The king would cross or land on the attacked `square` when castling.
+  CastlingThroughCheck { square: u8 },
+  /// The king is in check from the piece on `square` and the move doesn't deal with it.
+  StillInCheck { attacker: PieceType, square: u8 },
+  /// The move would expose the king to the piece on `square`.
+  ExposesKing { attacker: PieceType, square: u8 },
+}
+
+impl IllegalMoveReason {
+  /// A short explanation that doesn't name squares, for when a `&'static str` is needed.
+  pub fn message(&self) -> &'static str {
+    match self {
+      IllegalMoveReason::NullMove => "that is not a move",
+      IllegalMoveReason::NoPiece { .. } => "there is no piece on that square",
+      IllegalMoveReason::OpponentsPiece { .. } => "that piece belongs to your opponent",
+      IllegalMoveReason::WrongMovement { .. } => "that piece doesn't move like that",
+      IllegalMoveReason::Blocked { .. } => "another piece is in the way",
+      IllegalMoveReason::OwnPieceOnTarget { .. } => "you can't capture your own piece",
+      IllegalMoveReason::CapturesKing => "the king can never be captured",
+      IllegalMoveReason::PawnCaptureWithoutTarget => "pawns only move diagonally when capturing",
+      IllegalMoveReason::MissingPromotion => "a pawn reaching the last rank must promote",
+      IllegalMoveReason::UnexpectedPromotion => "pawns only promote on the last rank",
+      IllegalMoveReason::EnPassantUnavailable => "en passant isn't possible here",
+      IllegalMoveReason::NoCastlingRight => "you can no longer castle on that side",
+      IllegalMoveReason::CastlingBlocked { .. } => "there are pieces between your king and rook",
+      IllegalMoveReason::CastlingOutOfCheck => "you can't castle while in check",
+      IllegalMoveReason::CastlingThroughCheck { .. } => {
+        "your king can't castle through an attacked square"
+      }
+      IllegalMoveReason::StillInCheck { .. } => "your king is in check",
+      IllegalMoveReason::ExposesKing { .. } => "your king would be in check",
+    }
+  }
+}
+
+impl core::fmt::Display for IllegalMoveReason {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let square = |sq: &u8| Square(*sq);
+    match self {
+      IllegalMoveReason::NoPiece { square: sq } => write!(f, "there is no piece on {}", square(sq)),
+      IllegalMoveReason::OpponentsPiece { square: sq } => {
+        write!(f, "the piece on {} belongs to your opponent", square(sq))
+      }
+      IllegalMoveReason::WrongMovement { piece } => {
+        write!(f, "a {} doesn't move like that", piece_name(*piece))
+      }
+      IllegalMoveReason::Blocked { piece, blocker } => write!(
+        f,
+        "the {} is blocked by the piece on {}",
+        piece_name(*piece),
+        square(blocker)
+      ),
+      IllegalMoveReason::OwnPieceOnTarget { square: sq } => {
+        write!(f, "you can't capture your own piece on {}", square(sq))
+      }
+      IllegalMoveReason::CastlingBlocked { blocker } => {
+        write!(f, "you can't castle past the piece on {}", square(blocker))
+      }
+      IllegalMoveReason::CastlingThroughCheck { square: sq } => write!(
+        f,
+        "your king can't castle through {}, which is attacked",
+        square(sq)
+      ),
+      IllegalMoveReason::StillInCheck {
+        attacker,
+        square: sq,
+      } => write!(
+        f,
+        "your king is in check from the {} on {}",
+        piece_name(*attacker),
+        square(sq)
+      ),
+      IllegalMoveReason::ExposesKing {
+        attacker,
+        square: sq,
+      } => write!(
+        f,
+        "your king would be in check from the {} on {}",
+        piece_name(*attacker),
+        square(sq)
+      ),
+      _ => f.write_str(self.message()),
+    }
+  }
+}
+
+/// Algebraic name of a square, e.g. `e4`.
+struct Square(u8);
+
+impl core::fmt::Display for Square {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let file = (b'a' + self.0 % 8) as char;
+    let rank = (b'1' + self.0 / 8 % 8) as char;
+    write!(f, "{file}{rank}")
+  }
+}
+
+fn piece_name(piece: PieceType) -> &'static str {
+  match piece {
+    PieceType::Pawn => "pawn",
+    PieceType::Knight => "knight",
+    PieceType::Bishop => "bishop",
+    PieceType::Rook => "rook",
+    PieceType::Queen => "queen",
+    PieceType::King => "king",
+  }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SanError {
+  /// The text is not a move in standard algebraic notation.
+  InvalidSyntax,
+  /// No legal move fits the description.
+  NoMatchingMove,
+  /// More than one legal move fits; the move needs a file or rank to tell them apart.
+  AmbiguousMove,
+  /// The game's move history is full.
+  HistoryFull,
+}
+
+impl core::fmt::Display for SanError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let message = match self {
+      SanError::InvalidSyntax => "not a move in standard algebraic notation",
+      SanError::NoMatchingMove => "no legal move matches",
+      SanError::AmbiguousMove => "more than one legal move matches",
+      SanError::HistoryFull => "the game history is full",
+    };
+    f.write_str(message)
+  }
+}
+
+/// Why one of the convenience methods that combine several steps failed, such as
+/// [`GameData::play_san`](crate::model::gamedata::GameData::play_san), which parses a move,
+/// finds it among the legal moves and plays it. The error of each step converts into it, so
+/// scripts can use `?` on all of them and report any with [`Display`](core::fmt::Display).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChessError {
+  /// A FEN string could not be read.
+  Fen(FenParseError),
+  /// A move in UCI notation could not be read.
+  Uci(MoveParseError),
+  /// A move in standard algebraic notation could not be read, or matched no single legal move.
+  San(SanError),
+  /// The move can't be played in this position.
+  IllegalMove(IllegalMoveReason),
+  /// The game's move history is full.
+  HistoryFull,
+}
+
+impl From<FenParseError> for ChessError {
+  fn from(error: FenParseError) -> Self {
+    ChessError::Fen(error)
+  }
+}
+
+impl From<MoveParseError> for ChessError {
+  fn from(error: MoveParseError) -> Self {
+    ChessError::Uci(error)
+  }
+}
+
+impl From<SanError> for ChessError {
+  fn from(error: SanError) -> Self {
+    match error {
+      SanError::HistoryFull => ChessError::HistoryFull,
+      error => ChessError::San(error),
+    }
+  }
+}
+
+impl From<IllegalMoveReason> for ChessError {
+  fn from(reason: IllegalMoveReason) -> Self {
+    ChessError::IllegalMove(reason)
+  }
+}
+
+impl core::fmt::Display for ChessError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      ChessError::Fen(error) => write!(f, "invalid FEN: {error:?}"),
+      ChessError::Uci(error) => write!(f, "invalid move: {error}"),
+      ChessError::San(error) => write!(f, "invalid move: {error}"),
+      ChessError::IllegalMove(reason) => write!(f, "illegal move: {reason}"),
+      ChessError::HistoryFull => f.write_str("the game history is full"),
+    }
+  }
+}
+
+/// A move of some movetext that could not be read.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MovetextError {
+  /// Position of the move in the movetext, counting from 0 and skipping move numbers.
+  pub index: usize,
+  pub error: SanError,
+}
+
+impl core::fmt::Display for MovetextError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "move {}: {}", self.index + 1, self.error)
+  }
+}
+
+/// A PGN game that could not be read.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PgnError {
+  /// A tag pair line is not of the form `[Name "value"]`. Lines count from 0.
+  InvalidTag { line: usize },
+  /// The `FEN` tag does not hold a valid position.
+  InvalidFen(FenParseError),
+  /// A move of the movetext is not legal.
+  Movetext(MovetextError),
+}
+
+impl core::fmt::Display for PgnError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      PgnError::InvalidTag { line } => write!(f, "line {}: malformed tag pair", line + 1),
+      PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {error:?}"),
+      PgnError::Movetext(error) => write!(f, "{error}"),
+    }
+  }
+}
+
+/// A [`GameSnapshot`](crate::model::snapshot::GameSnapshot) that does not restore to a game.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SnapshotError {
+  /// The initial position is not a valid FEN.
+  InvalidFen(FenParseError),
+  /// The move at `index` of the history, counting from 0, is not legal where it is played.
+  IllegalMove { index: usize },
+  /// The history replays to a different position than the one saved.
+  PositionMismatch,
+}
+
+impl core::fmt::Display for SnapshotError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      SnapshotError::InvalidFen(error) => write!(f, "invalid initial position: {error:?}"),
+      SnapshotError::IllegalMove { index } => write!(f, "move {}: illegal", index + 1),
+      SnapshotError::PositionMismatch => {
+        f.write_str("the history does not lead to the saved position")
+      }
+    }
+  }
+}
+
+/// Why a string is not a material signature such as `KQvK`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MaterialParseError {
+  /// There is no `v` between the two sides, or more than one.
+  MissingSeparator,
+  /// A character other than `K`, `Q`, `R`, `B`, `N` or `P`.
+  InvalidPiece(char),
+}
+
+impl core::fmt::Display for MaterialParseError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      MaterialParseError::MissingSeparator => f.write_str("expected the two sides split by 'v'"),
+      MaterialParseError::InvalidPiece(c) => write!(f, "invalid piece letter '{c}'"),
+    }
+  }
+}
+
+/// Why text is not a board diagram.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DiagramParseError {
+  /// A character that is neither a square nor part of a border or label.
+  InvalidChar(char),
+  /// The diagram does not have eight rows of squares.
+  InvalidRankCount(usize),
+  /// A row, counted from the top from 0, does not have eight squares.
+  InvalidRankLength { row: usize, squares: usize },
+}
+
+impl core::fmt::Display for DiagramParseError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      DiagramParseError::InvalidChar(c) => write!(f, "'{c}' is not a piece or an empty square"),
+      DiagramParseError::InvalidRankCount(rows) => {
+        write!(f, "expected 8 rows of squares, found {rows}")
+      }
+      DiagramParseError::InvalidRankLength { row, squares } => {
+        write!(f, "expected 8 squares in row {}, found {squares}", row + 1)
+      }
+    }
+  }
 }
@@ -0,0 +1,229 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Input features for training neural networks on positions.
+//!
+//! [`extract`] turns a board into a [`FeatureVector`] of [`PLANES`] planes of 8x8 `f32`s, in the
+//! style of AlphaZero's input planes, for a single position without history. The position is
+//! seen from the side to move: "own" means the side to move, and for black the board is
+//! mirrored top to bottom so that own pawns always advance up the planes.
+//!
+//! | Plane  | Contents                                                                 |
+//! |--------|--------------------------------------------------------------------------|
+//! | 0..6   | own pawns, knights, bishops, rooks, queens and king: 1 on their squares  |
+//! | 6..12  | the opponent's pieces, in the same order                                 |
+//! | 12     | all 1 if white is to move, all 0 if black is                             |
+//! | 13, 14 | all 1 if the side to move may still castle kingside, queenside           |
+//! | 15, 16 | the same for the opponent                                                |
+//! | 17     | 1 on the en passant target square, if a capture onto it is legal         |
+//! | 18     | the halfmove clock divided by 100, on every square                       |
+//!
+//! Within a plane, square `i` is file `i % 8` and rank `i / 8` counted from the side to move's
+//! back rank, the a1 = 0 numbering of the rest of the crate for white. The whole vector is the
+//! planes one after another, `plane * 64 + square`, which is the row-major layout of an array of
+//! shape `(PLANES, 8, 8)` indexed by plane, rank and file.
+//!
+//! The layout is part of the API: a network trained on these features keeps working with later
+//! versions of the crate. With the `ndarray` feature, [`FeatureVector::to_array`] and [`stack`]
+//! return the planes as `ndarray` arrays.
+
+use crate::model::{gameboard::GameBoard, gamedata::GameData};
+
+/// Number of 8x8 planes in a [`FeatureVector`].
+pub const PLANES: usize = 19;
+/// Number of values in a [`FeatureVector`].
+pub const LEN: usize = PLANES * 64;
+
+/// First of the six planes of the side to move's pieces, indexed by
+/// [`PieceType`](crate::model::gameboard::PieceType).
+pub const OWN_PIECES: usize = 0;
+/// First of the six planes of the opponent's pieces, indexed by
+/// [`PieceType`](crate::model::gameboard::PieceType).
+pub const OPPONENT_PIECES: usize = 6;
+/// Plane that is all 1 when white is to move.
+pub const COLOUR: usize = 12;
+/// First of the four castling planes: own kingside, own queenside, then the opponent's.
+pub const CASTLING: usize = 13;
+/// Plane of the en passant target square.
+pub const EN_PASSANT: usize = 17;
+/// Plane of the halfmove clock, divided by 100.
+pub const HALFMOVE_CLOCK: usize = 18;
+
+/// The input planes of one position, laid out as the [module docs](self) describe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureVector {
+  values: [f32; LEN],
+}
+
+impl FeatureVector {
+  /// Every value, plane after plane.
+  pub fn as_slice(&self) -> &[f32] {
+    &self.values
+  }
+
+  /// The 64 values of `plane`, or `None` past the last one.
+  pub fn plane(&self, plane: usize) -> Option<&[f32]> {
+    self.values.get(plane * 64..(plane + 1) * 64)
+  }
+
+  /// The value of `plane` on `square`, in the side to move's numbering.
+  pub fn get(&self, plane: usize, square: u8) -> Option<f32> {
+    self
+      .plane(plane)
+      .and_then(|plane| plane.get(square as usize))
+      .copied()
+  }
+
+  fn fill(&mut self, plane: usize, value: f32) {
+    self.values[plane * 64..(plane + 1) * 64].fill(value);
+  }
+
+  /// The planes as an array of shape `(PLANES, 8, 8)`, indexed by plane, rank and file.
+  #[cfg(feature = "ndarray")]
+  pub fn to_array(&self) -> ndarray::Array3<f32> {
+    ndarray::Array3::from_shape_vec((PLANES, 8, 8), self.values.to_vec())
+      .expect("LEN is PLANES * 8 * 8")
+  }
+}
+
+/// The features of `board`. Boards do not know their halfmove clock, so its plane is 0; use
+/// [`extract_game`] to fill it in.
+pub fn extract(board: &GameBoard) -> FeatureVector {
+  let mut features = FeatureVector { values: [0.0; LEN] };
+  let own = board.playing;
+  // Mirror the ranks for black, so the side to move always plays up the board
+  let orient = |square: u8| if own { square } else { square ^ 56 };
+
+  for square in board.combined() {
+    if let Some((piece, white)) = board.piece_at(square) {
+      let base = if white == own {
+        OWN_PIECES
+      } else {
+        OPPONENT_PIECES
+      };
+      features.values[(base + piece as usize) * 64 + orient(square) as usize] = 1.0;
+    }
+  }
+  if own {
+    features.fill(COLOUR, 1.0);
+  }
+  for (index, (white, kingside)) in [(own, true), (own, false), (!own, true), (!own, false)]
+    .into_iter()
+    .enumerate()
+  {
+    if board.castling.has(white, kingside) {
+      features.fill(CASTLING + index, 1.0);
+    }
+  }
+  if let Some(target) = board.en_passant_capture_square() {
+    features.values[EN_PASSANT * 64 + orient(target) as usize] = 1.0;
+  }
+  features
+}
+
+/// The features of the current position of `game`, with its halfmove clock.
+pub fn extract_game(game: &GameData) -> FeatureVector {
+  let mut features = extract(&game.board);
+  features.fill(HALFMOVE_CLOCK, game.halfmove_clock as f32 / 100.0);
+  features
+}
+
+/// The features of several positions as one array of shape `(positions, PLANES, 8, 8)`, the
+/// usual input of a training batch.
+#[cfg(feature = "ndarray")]
+pub fn stack<'a>(features: impl IntoIterator<Item = &'a FeatureVector>) -> ndarray::Array4<f32> {
+  let mut values = alloc::vec::Vec::new();
+  let mut positions = 0;
+  for vector in features {
+    values.extend_from_slice(&vector.values);
+    positions += 1;
+  }
+  ndarray::Array4::from_shape_vec((positions, PLANES, 8, 8), values)
+    .expect("each position adds PLANES * 8 * 8 values")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::gameboard::PieceType};
+
+  fn ones(features: &FeatureVector, plane: usize) -> u64 {
+    (0..64u8)
+      .filter(|&square| features.get(plane, square) == Some(1.0))
+      .fold(0, |mask, square| mask | 1 << square)
+  }
+
+  #[test]
+  fn test_start_position() {
+    let features = extract(&GameBoard::START_POS);
+    assert_eq!(features.as_slice().len(), LEN);
+    assert_eq!(ones(&features, OWN_PIECES), 0xFF00);
+    assert_eq!(
+      ones(&features, OWN_PIECES + PieceType::King as usize),
+      1 << E1
+    );
+    assert_eq!(ones(&features, OPPONENT_PIECES), 0x00FF << 48);
+    assert_eq!(ones(&features, COLOUR), u64::MAX);
+    for plane in CASTLING..CASTLING + 4 {
+      assert_eq!(ones(&features, plane), u64::MAX);
+    }
+    assert_eq!(ones(&features, EN_PASSANT), 0);
+    assert_eq!(features.plane(PLANES), None);
+    assert_eq!(features.get(HALFMOVE_CLOCK, 0), Some(0.0));
+  }
+
+  #[test]
+  fn test_black_sees_the_board_mirrored() {
+    let game =
+      GameData::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQK2R b Kkq e3 0 3").unwrap();
+    let features = extract_game(&game);
+    // Black's pawns start on its own second rank, its d4 pawn is on its fifth
+    assert_eq!(ones(&features, OWN_PIECES), 0xF700 | 1 << D5);
+    assert_eq!(
+      ones(&features, OWN_PIECES + PieceType::King as usize),
+      1 << E1
+    );
+    assert_eq!(ones(&features, OPPONENT_PIECES) & 1 << E5, 1 << E5);
+    assert_eq!(ones(&features, COLOUR), 0);
+    // Own rights are black's kingside and queenside, the opponent's white's kingside only
+    let rights: [bool; 4] =
+      core::array::from_fn(|index| ones(&features, CASTLING + index) == u64::MAX);
+    assert_eq!(rights, [true, true, true, false]);
+    // dxe3 is legal, so e3 counts; mirrored it is e6
+    assert_eq!(ones(&features, EN_PASSANT), 1 << E6);
+    assert_eq!(features.get(HALFMOVE_CLOCK, A1), Some(0.0));
+
+    let quiet = GameData::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 42 80").unwrap();
+    assert_eq!(extract_game(&quiet).get(HALFMOVE_CLOCK, H8), Some(0.42));
+  }
+
+  #[cfg(feature = "ndarray")]
+  #[test]
+  fn test_arrays() {
+    let features = extract(&GameBoard::START_POS);
+    let array = features.to_array();
+    assert_eq!(array.shape(), &[PLANES, 8, 8]);
+    // Plane, rank, file: the white king on e1
+    assert_eq!(array[[OWN_PIECES + PieceType::King as usize, 0, 4]], 1.0);
+    assert_eq!(array.as_slice(), Some(features.as_slice()));
+
+    let batch = stack([&features, &features, &features]);
+    assert_eq!(batch.shape(), &[3, PLANES, 8, 8]);
+    assert_eq!(batch[[2, OPPONENT_PIECES, 6, 3]], 1.0);
+  }
+}
@@ -16,10 +16,13 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
-use crate::constants::{FILE_A, FILE_H, NOT_A_FILE, NOT_AB_FILE, NOT_GH_FILE, NOT_H_FILE};
+use crate::constants::{NOT_FILE_A, NOT_FILE_AB, NOT_FILE_GH, NOT_FILE_H};
 use crate::model::bitboard::BitBoard;
-use crate::model::gameboard::GameBoard;
-use crate::model::rays::{DIR_OFFSETS, RAYS};
+use crate::model::gameboard::{GameBoard, PieceType};
+#[cfg(not(feature = "magics"))]
+use crate::model::rays::DIR_OFFSETS;
+use crate::model::rays::RAYS;
+use crate::stats::{self, Event};
 
 fn is_square_attacked_pawn(board: &GameBoard, square: u8) -> bool {
   if square >= 64 {
@@ -27,16 +30,15 @@ fn is_square_attacked_pawn(board: &GameBoard, square: u8) -> bool {
   }
 
   let opponent_white = !board.playing;
-  let desired_for_opponent = !opponent_white;
-  let opponent_pawns = board.pawns & board.combined_coloured(desired_for_opponent);
+  let opponent_pawns = board.pawns & board.pieces_of(opponent_white);
 
   let attacks = if opponent_white {
-    let left_attacks = (opponent_pawns & BitBoard::new(!FILE_A)) << 7;
-    let right_attacks = (opponent_pawns & BitBoard::new(!FILE_H)) << 9;
+    let left_attacks = (opponent_pawns & BitBoard::new(NOT_FILE_A)) << 7;
+    let right_attacks = (opponent_pawns & BitBoard::new(NOT_FILE_H)) << 9;
     left_attacks | right_attacks
   } else {
-    let left_attacks = (opponent_pawns & BitBoard::new(!FILE_A)) >> 9;
-    let right_attacks = (opponent_pawns & BitBoard::new(!FILE_H)) >> 7;
+    let left_attacks = (opponent_pawns & BitBoard::new(NOT_FILE_A)) >> 9;
+    let right_attacks = (opponent_pawns & BitBoard::new(NOT_FILE_H)) >> 7;
     left_attacks | right_attacks
   };
 
@@ -45,14 +47,13 @@ fn is_square_attacked_pawn(board: &GameBoard, square: u8) -> bool {
 
 fn is_square_attacked_knight(board: &GameBoard, square: u8) -> bool {
   let opponent_white = !board.playing;
-  let desired = !opponent_white;
-  let opponent_knights = board.knights & board.combined_coloured(desired);
+  let opponent_knights = board.knights & board.pieces_of(opponent_white);
   let knights = opponent_knights.raw();
 
-  let l1 = (knights >> 1) & NOT_H_FILE;
-  let l2 = (knights >> 2) & NOT_GH_FILE;
-  let r1 = (knights << 1) & NOT_A_FILE;
-  let r2 = (knights << 2) & NOT_AB_FILE;
+  let l1 = (knights >> 1) & NOT_FILE_H;
+  let l2 = (knights >> 2) & NOT_FILE_GH;
+  let r1 = (knights << 1) & NOT_FILE_A;
+  let r2 = (knights << 2) & NOT_FILE_AB;
   let h1 = l1 | r1;
   let h2 = l2 | r2;
   let attacks = (h1 << 16) | (h1 >> 16) | (h2 << 8) | (h2 >> 8);
@@ -62,12 +63,11 @@ fn is_square_attacked_knight(board: &GameBoard, square: u8) -> bool {
 
 fn is_square_attacked_king(board: &GameBoard, square: u8) -> bool {
   let opponent_white = !board.playing;
-  let desired = !opponent_white;
-  let opponent_kings = board.kings & board.combined_coloured(desired);
+  let opponent_kings = board.kings & board.pieces_of(opponent_white);
   let kings = opponent_kings.raw();
 
-  let east = (kings << 1) & NOT_A_FILE;
-  let west = (kings >> 1) & NOT_H_FILE;
+  let east = (kings << 1) & NOT_FILE_A;
+  let west = (kings >> 1) & NOT_FILE_H;
   let attacks = east | west;
   let king_set = kings | attacks;
   let north = king_set << 8;
@@ -77,6 +77,7 @@ fn is_square_attacked_king(board: &GameBoard, square: u8) -> bool {
   (all_attacks & (1u64 << square)) != 0
 }
 
+#[cfg(not(feature = "magics"))]
 fn is_square_attacked_sliding(
   board: &GameBoard,
   square: u8,
@@ -127,27 +128,206 @@ fn is_square_attacked_sliding(
   false
 }
 
+#[cfg(not(feature = "magics"))]
 fn is_square_attacked_rook(board: &GameBoard, square: u8) -> bool {
   let opponent_white = !board.playing;
-  let desired = !opponent_white;
-  let opponent_rooks = board.rooks & board.combined_coloured(desired);
-  let opponent_queens = board.queens & board.combined_coloured(desired);
+  let opponent_rooks = board.rooks & board.pieces_of(opponent_white);
+  let opponent_queens = board.queens & board.pieces_of(opponent_white);
   let piece_bb = opponent_rooks | opponent_queens;
   let dirs: [i8; 4] = [1, -1, 8, -8];
   is_square_attacked_sliding(board, square, &dirs, piece_bb, opponent_white)
 }
 
+#[cfg(feature = "magics")]
+fn is_square_attacked_rook(board: &GameBoard, square: u8) -> bool {
+  let attackers = (board.rooks | board.queens) & board.pieces_of(!board.playing);
+  rook_attacks(square, board.combined().raw()) & attackers.raw() != 0
+}
+
+#[cfg(not(feature = "magics"))]
 fn is_square_attacked_bishop(board: &GameBoard, square: u8) -> bool {
   let opponent_white = !board.playing;
-  let desired = !opponent_white;
-  let opponent_bishops = board.bishops & board.combined_coloured(desired);
-  let opponent_queens = board.queens & board.combined_coloured(desired);
+  let opponent_bishops = board.bishops & board.pieces_of(opponent_white);
+  let opponent_queens = board.queens & board.pieces_of(opponent_white);
   let piece_bb = opponent_bishops | opponent_queens;
   let dirs: [i8; 4] = [9, -9, 7, -7];
   is_square_attacked_sliding(board, square, &dirs, piece_bb, opponent_white)
 }
 
+#[cfg(feature = "magics")]
+fn is_square_attacked_bishop(board: &GameBoard, square: u8) -> bool {
+  let attackers = (board.bishops | board.queens) & board.pieces_of(!board.playing);
+  bishop_attacks(square, board.combined().raw()) & attackers.raw() != 0
+}
+
+/// Squares attacked by a `piece` of the given colour standing on `square`, with `occupancy` as
+/// the occupied squares. Sliding attacks stop at (and include) the first occupied square in each
+/// direction; pawns attack diagonally forward. Returns an empty board if `square` is off the
+/// board.
+///
+/// This needs no `GameBoard`, so evaluation, exchange evaluation and tools can ask about
+/// hypothetical placements. With the `precomputed_rays` feature the leaper attacks come from the
+/// lookup tables in [`crate::model::rays`].
+pub fn attacks_of(piece: PieceType, square: u8, occupancy: BitBoard, white: bool) -> BitBoard {
+  if square >= 64 {
+    return BitBoard::EMPTY;
+  }
+  let attacks = match piece {
+    PieceType::Pawn => pawn_attacks(square, white),
+    PieceType::Knight => knight_attacks(square),
+    PieceType::King => king_attacks(square),
+    PieceType::Rook => rook_attacks(square, occupancy.raw()),
+    PieceType::Bishop => bishop_attacks(square, occupancy.raw()),
+    PieceType::Queen => {
+      rook_attacks(square, occupancy.raw()) | bishop_attacks(square, occupancy.raw())
+    }
+  };
+  BitBoard::new(attacks)
+}
+
+/// Rook attacks from `square` that pass through the first layer of `blockers`: the squares a rook
+/// would newly attack if those blockers were removed. The pieces found this way are the ones
+/// pinning or skewering a blocker against `square`.
+pub fn xray_rook_attacks(occupancy: BitBoard, blockers: BitBoard, square: u8) -> BitBoard {
+  xray_attacks(PieceType::Rook, occupancy, blockers, square)
+}
+
+/// Like [`xray_rook_attacks`], along the diagonals.
+pub fn xray_bishop_attacks(occupancy: BitBoard, blockers: BitBoard, square: u8) -> BitBoard {
+  xray_attacks(PieceType::Bishop, occupancy, blockers, square)
+}
+
+fn xray_attacks(piece: PieceType, occupancy: BitBoard, blockers: BitBoard, square: u8) -> BitBoard {
+  let attacks = attacks_of(piece, square, occupancy, true).raw();
+  let first_layer = blockers.raw() & attacks;
+  let through = attacks_of(
+    piece,
+    square,
+    BitBoard::new(occupancy.raw() & !first_layer),
+    true,
+  )
+  .raw();
+  BitBoard::new(attacks ^ through)
+}
+
+/// Squares strictly between `a` and `b` when they share a rank, file or diagonal, else 0.
+pub(crate) fn between(a: u8, b: u8) -> u64 {
+  if a >= 64 || b >= 64 {
+    return 0;
+  }
+  let target = 1u64 << b;
+  for (idx, ray) in RAYS[a as usize].iter().enumerate() {
+    if ray & target != 0 {
+      return ray & !RAYS[b as usize][idx] & !target;
+    }
+  }
+  0
+}
+
+/// Squares attacked by the piece on `square` of `board`, or 0 if the square is empty.
+pub(crate) fn attacks_from(board: &GameBoard, square: u8) -> u64 {
+  match board.get_piece(square) {
+    Some(piece) => attacks_of(
+      piece,
+      square,
+      board.combined(),
+      board.colour.get_bit_unchecked(square),
+    )
+    .raw(),
+    None => 0,
+  }
+}
+
+#[cfg(feature = "precomputed_rays")]
+fn pawn_attacks(square: u8, white: bool) -> u64 {
+  use crate::model::rays::{PAWN_ATTACK_BLACK, PAWN_ATTACK_WHITE};
+  if white {
+    PAWN_ATTACK_WHITE[square as usize]
+  } else {
+    PAWN_ATTACK_BLACK[square as usize]
+  }
+}
+
+#[cfg(not(feature = "precomputed_rays"))]
+fn pawn_attacks(square: u8, white: bool) -> u64 {
+  let bit = 1u64 << square;
+  if white {
+    ((bit & NOT_FILE_A) << 7) | ((bit & NOT_FILE_H) << 9)
+  } else {
+    ((bit & NOT_FILE_A) >> 9) | ((bit & NOT_FILE_H) >> 7)
+  }
+}
+
+#[cfg(feature = "precomputed_rays")]
+fn knight_attacks(square: u8) -> u64 {
+  crate::model::rays::KNIGHT_MOVES[square as usize]
+}
+
+#[cfg(not(feature = "precomputed_rays"))]
+fn knight_attacks(square: u8) -> u64 {
+  let bit = 1u64 << square;
+  let l1 = (bit >> 1) & NOT_FILE_H;
+  let l2 = (bit >> 2) & NOT_FILE_GH;
+  let r1 = (bit << 1) & NOT_FILE_A;
+  let r2 = (bit << 2) & NOT_FILE_AB;
+  let h1 = l1 | r1;
+  let h2 = l2 | r2;
+  (h1 << 16) | (h1 >> 16) | (h2 << 8) | (h2 >> 8)
+}
+
+#[cfg(feature = "precomputed_rays")]
+fn king_attacks(square: u8) -> u64 {
+  crate::model::rays::KING_MOVES[square as usize]
+}
+
+#[cfg(not(feature = "precomputed_rays"))]
+fn king_attacks(square: u8) -> u64 {
+  let bit = 1u64 << square;
+  let row = bit | ((bit << 1) & NOT_FILE_A) | ((bit >> 1) & NOT_FILE_H);
+  (row | (row << 8) | (row >> 8)) & !bit
+}
+
+#[cfg(feature = "magics")]
+use crate::movegen::magics::{bishop_attacks, rook_attacks};
+
+#[cfg(not(feature = "magics"))]
+fn rook_attacks(square: u8, occupancy: u64) -> u64 {
+  sliding_attacks(square, occupancy, 0..4)
+}
+
+#[cfg(not(feature = "magics"))]
+fn bishop_attacks(square: u8, occupancy: u64) -> u64 {
+  sliding_attacks(square, occupancy, 4..8)
+}
+
+/// Union of the rays `directions` (indices into [`DIR_OFFSETS`]), each cut at its first blocker.
+#[cfg(not(feature = "magics"))]
+fn sliding_attacks(square: u8, occupancy: u64, directions: core::ops::Range<usize>) -> u64 {
+  let mut attacks = 0;
+  for idx in directions {
+    let ray = RAYS[square as usize][idx];
+    let blockers = occupancy & ray;
+    attacks |= if blockers == 0 {
+      ray
+    } else {
+      let blocker = if DIR_OFFSETS[idx] > 0 {
+        blockers.trailing_zeros()
+      } else {
+        63 - blockers.leading_zeros()
+      };
+      ray & !RAYS[blocker as usize][idx]
+    };
+  }
+  attacks
+}
+
+/// Whether any piece of the side not to move attacks `square`. Squares off the board are never
+/// attacked.
 pub fn is_square_attacked(board: &GameBoard, square: u8) -> bool {
+  stats::record(Event::AttackQuery);
+  if square >= 64 {
+    return false;
+  }
   is_square_attacked_pawn(board, square)
     || is_square_attacked_knight(board, square)
     || is_square_attacked_king(board, square)
@@ -155,6 +335,31 @@ pub fn is_square_attacked(board: &GameBoard, square: u8) -> bool {
     || is_square_attacked_bishop(board, square)
 }
 
+/// Like [`is_square_attacked`], as if the squares of `excluded` were empty: sliding attacks run
+/// through them and the pieces on them attack nothing.
+///
+/// A king stepping along the line of a slider that checks it stays on that line, yet asked with
+/// the king still on the board, the king itself shields the square behind it. Excluding the king
+/// gives the right answer for its destination without playing the move out. Castling in Chess960
+/// likewise excludes the king and the rook, since both leave their squares.
+pub fn is_square_attacked_excluding(board: &GameBoard, square: u8, excluded: BitBoard) -> bool {
+  stats::record(Event::AttackQuery);
+  if square >= 64 {
+    return false;
+  }
+  let occupancy = board.combined() & !excluded;
+  let enemies = board.pieces_of(!board.playing) & !excluded;
+  let attackers = |piece: PieceType, pieces: BitBoard| {
+    attacks_of(piece, square, occupancy, board.playing) & pieces & enemies
+  };
+  !(attackers(PieceType::Rook, board.rooks | board.queens)
+    | attackers(PieceType::Bishop, board.bishops | board.queens)
+    | attackers(PieceType::Knight, board.knights)
+    | attackers(PieceType::Pawn, board.pawns)
+    | attackers(PieceType::King, board.kings))
+  .is_empty()
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -165,6 +370,101 @@ mod tests {
     GameData::from_fen(fen).unwrap().board
   }
 
+  fn squares(list: &[u8]) -> u64 {
+    list.iter().fold(0, |acc, &sq| acc | (1u64 << sq))
+  }
+
+  fn attacks(piece: PieceType, square: u8, occupancy: u64, white: bool) -> u64 {
+    attacks_of(piece, square, BitBoard::new(occupancy), white).raw()
+  }
+
+  #[test]
+  fn test_xray_attacks() {
+    // Rook on a1, own knight on a3, enemy queen on a6 behind it
+    let occupancy = squares(&[A1, A3, A6, C1]);
+    let blockers = squares(&[A3]);
+    assert_eq!(
+      xray_rook_attacks(BitBoard::new(occupancy), BitBoard::new(blockers), A1).raw(),
+      squares(&[A4, A5, A6])
+    );
+    // Blockers that are not hit directly are not seen through
+    assert_eq!(
+      xray_rook_attacks(BitBoard::new(occupancy), BitBoard::new(squares(&[A6])), A1).raw(),
+      0
+    );
+
+    let occupancy = squares(&[C1, E3, G5]);
+    assert_eq!(
+      xray_bishop_attacks(BitBoard::new(occupancy), BitBoard::new(squares(&[E3])), C1).raw(),
+      squares(&[F4, G5])
+    );
+  }
+
+  #[test]
+  fn test_is_square_attacked_matches_attacks_from() {
+    for fen in [
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "4k3/8/8/3n4/8/8/8/4K3 w - - 0 1",
+    ] {
+      let board = get_board(fen);
+      let enemies = board.pieces_of(!board.playing).raw();
+      for square in 0..64 {
+        let attacked = (0..64)
+          .filter(|&from| enemies & (1u64 << from) != 0)
+          .any(|from| attacks_from(&board, from) & (1u64 << square) != 0);
+        assert_eq!(
+          is_square_attacked(&board, square),
+          attacked,
+          "{fen} square {square}"
+        );
+      }
+    }
+    assert!(!is_square_attacked(&GameBoard::START_POS, 64));
+  }
+
+  #[test]
+  fn test_between() {
+    assert_eq!(between(A1, A4), squares(&[A2, A3]));
+    assert_eq!(between(H8, C3), squares(&[G7, F6, E5, D4]));
+    assert_eq!(between(E1, E2), 0);
+    assert_eq!(between(A1, B3), 0);
+  }
+
+  #[test]
+  fn test_attacks_of_leapers() {
+    assert_eq!(attacks(PieceType::Knight, A1, 0, true), squares(&[B3, C2]));
+    assert_eq!(attacks(PieceType::Knight, E4, 0, false).count_ones(), 8);
+    assert_eq!(
+      attacks(PieceType::King, H8, 0, true),
+      squares(&[G8, G7, H7])
+    );
+    assert_eq!(attacks(PieceType::Pawn, E4, 0, true), squares(&[D5, F5]));
+    assert_eq!(attacks(PieceType::Pawn, E4, 0, false), squares(&[D3, F3]));
+    assert_eq!(attacks(PieceType::Pawn, A2, 0, true), squares(&[B3]));
+    assert_eq!(attacks(PieceType::Pawn, H7, 0, false), squares(&[G6]));
+  }
+
+  #[test]
+  fn test_attacks_of_sliders_stop_at_blockers() {
+    let occupancy = squares(&[D4, D6, F4, B2]);
+    assert_eq!(
+      attacks(PieceType::Rook, D4, occupancy, true),
+      squares(&[D5, D6, D3, D2, D1, E4, F4, C4, B4, A4])
+    );
+    assert_eq!(
+      attacks(PieceType::Bishop, A1, occupancy, false),
+      squares(&[B2])
+    );
+    assert_eq!(
+      attacks(PieceType::Queen, D4, occupancy, true),
+      attacks(PieceType::Rook, D4, occupancy, true)
+        | attacks(PieceType::Bishop, D4, occupancy, true)
+    );
+    assert_eq!(attacks(PieceType::Queen, 64, occupancy, true), 0);
+  }
+
   // Pawn Tests
   #[test]
   fn test_pawn_attacks() {
@@ -437,4 +737,39 @@ mod tests {
     assert!(!is_square_attacked(&board, A1));
     assert!(!is_square_attacked(&board, C2));
   }
+
+  #[test]
+  fn test_square_attacked_excluding() {
+    // The rook on a1 checks the king on d1, which shields e1 from it
+    let board = get_board("7K/8/8/8/8/8/8/R2k4 b - - 0 1");
+    let king = BitBoard::new(1 << D1);
+    assert!(!is_square_attacked(&board, E1));
+    assert!(is_square_attacked_excluding(&board, E1, king));
+    assert!(is_square_attacked_excluding(&board, C1, king));
+    assert!(!is_square_attacked_excluding(&board, E2, king));
+    // Excluded pieces attack nothing
+    assert!(is_square_attacked(&board, A8));
+    assert!(!is_square_attacked_excluding(
+      &board,
+      A8,
+      BitBoard::new(1 << A1)
+    ));
+    assert!(!is_square_attacked_excluding(&board, 64, king));
+  }
+
+  #[test]
+  fn test_king_cannot_walk_along_check_ray() {
+    let king_moves = |fen: &str| {
+      let board = get_board(fen);
+      let mut targets: Vec<u8> = board.legal_moves_iter().map(|m| m.to_square()).collect();
+      targets.sort_unstable();
+      targets
+    };
+    // Away from a rook along the rank, and from a queen along the diagonal
+    assert_eq!(king_moves("7K/8/8/8/8/8/8/R2k4 b - - 0 1"), [C2, D2, E2]);
+    assert!(!king_moves("7K/8/8/8/8/2k5/8/Q7 b - - 0 1").contains(&D4));
+    assert_eq!(king_moves("7K/8/8/8/8/2k5/8/Q7 b - - 0 1").len(), 6);
+    // The same with the rook further away
+    assert_eq!(king_moves("K7/8/8/8/8/8/8/R3k3 b - - 0 1"), [D2, E2, F2]);
+  }
 }
@@ -76,12 +76,12 @@ impl BatchLegalContext {
     {
       if opponent_white {
         // white pawns attack to NW and NE -> compute via shifts
-        let left = (opponent_pawns & crate::constants::NOT_A_FILE) << 7;
-        let right = (opponent_pawns & crate::constants::NOT_H_FILE) << 9;
+        let left = (opponent_pawns & crate::constants::NOT_FILE_A) << 7;
+        let right = (opponent_pawns & crate::constants::NOT_FILE_H) << 9;
         attacks |= left | right;
       } else {
-        let left = (opponent_pawns & crate::constants::NOT_A_FILE) >> 9;
-        let right = (opponent_pawns & crate::constants::NOT_H_FILE) >> 7;
+        let left = (opponent_pawns & crate::constants::NOT_FILE_A) >> 9;
+        let right = (opponent_pawns & crate::constants::NOT_FILE_H) >> 7;
         attacks |= left | right;
       }
     }
@@ -107,18 +107,18 @@ impl BatchLegalContext {
     {
       // Parallel-shift approach from attack.rs
       let knights = opponent_knights;
-      let l1 = (knights >> 1) & crate::constants::NOT_H_FILE;
-      let l2 = (knights >> 2) & crate::constants::NOT_GH_FILE;
-      let r1 = (knights << 1) & crate::constants::NOT_A_FILE;
-      let r2 = (knights << 2) & crate::constants::NOT_AB_FILE;
+      let l1 = (knights >> 1) & crate::constants::NOT_FILE_H;
+      let l2 = (knights >> 2) & crate::constants::NOT_FILE_GH;
+      let r1 = (knights << 1) & crate::constants::NOT_FILE_A;
+      let r2 = (knights << 2) & crate::constants::NOT_FILE_AB;
       let h1 = l1 | r1;
       let h2 = l2 | r2;
       attacks |= (h1 << 16) | (h1 >> 16) | (h2 << 8) | (h2 >> 8);
 
       // King attacks via shifts
       let kings = opponent_kings;
-      let east = (kings << 1) & crate::constants::NOT_A_FILE;
-      let west = (kings >> 1) & crate::constants::NOT_H_FILE;
+      let east = (kings << 1) & crate::constants::NOT_FILE_A;
+      let west = (kings >> 1) & crate::constants::NOT_FILE_H;
       let king_adj = east | west;
       let king_set = kings | king_adj;
       attacks |= king_adj | (king_set << 8) | (king_set >> 8);
@@ -0,0 +1,197 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Per-position attack tables for workloads that ask many attack questions about one position.
+//!
+//! [`is_square_attacked`](super::attack::is_square_attacked) recomputes what it needs on every
+//! call, which is the right trade for move generation. Tactics scanners and analysis tools that
+//! query every square of the same position many times can build an [`AttackCache`] once instead
+//! and read attacker sets from it. The cache remembers the piece placement it was built from, so
+//! after a move (or taking one back) [`AttackCache::refresh`] rebuilds it only if the placement
+//! actually changed.
+
+use crate::legal::attack::attacks_from;
+use crate::model::bitboard::BitBoard;
+use crate::model::gameboard::GameBoard;
+
+/// The attackers of every square of one position, for both sides.
+#[derive(Debug, Clone)]
+pub struct AttackCache {
+  /// Piece bitboards and the colour bitboard of the position the tables were built from.
+  placement: [u64; 7],
+  /// Bit `s` of `attackers[t]` is set when the piece on `s` attacks `t`.
+  attackers: [u64; 64],
+}
+
+impl AttackCache {
+  /// Build the tables for `board`.
+  pub fn new(board: &GameBoard) -> Self {
+    let mut cache = Self {
+      placement: placement(board),
+      attackers: [0; 64],
+    };
+    cache.rebuild(board);
+    cache
+  }
+
+  /// Whether the tables still describe `board`. Only piece placement matters; the side to move,
+  /// castling rights and en passant square do not change what attacks what.
+  pub fn is_current(&self, board: &GameBoard) -> bool {
+    self.placement == placement(board)
+  }
+
+  /// Rebuild the tables if `board` has a different piece placement than they were built from.
+  /// Returns whether anything was rebuilt.
+  pub fn refresh(&mut self, board: &GameBoard) -> bool {
+    if self.is_current(board) {
+      return false;
+    }
+    self.placement = placement(board);
+    self.rebuild(board);
+    true
+  }
+
+  /// Every piece, of either colour, attacking `square`. Empty for squares off the board.
+  pub fn attackers(&self, square: u8) -> BitBoard {
+    match self.attackers.get(square as usize) {
+      Some(&attackers) => BitBoard::new(attackers),
+      None => BitBoard::EMPTY,
+    }
+  }
+
+  /// The pieces of the side given by `white` attacking `square`.
+  pub fn attackers_by(&self, square: u8, white: bool) -> BitBoard {
+    self.attackers(square) & self.side(white)
+  }
+
+  /// Whether the side given by `white` attacks `square`.
+  pub fn is_attacked_by(&self, square: u8, white: bool) -> bool {
+    !self.attackers_by(square, white).is_empty()
+  }
+
+  /// Every square the side given by `white` attacks.
+  pub fn attacked_by(&self, white: bool) -> BitBoard {
+    let side = self.side(white);
+    let attacked = (0..64u8)
+      .filter(|&square| self.attackers[square as usize] & side != 0)
+      .fold(0, |acc, square| acc | (1u64 << square));
+    BitBoard::new(attacked)
+  }
+
+  /// Squares holding a piece of the side given by `white`.
+  fn side(&self, white: bool) -> u64 {
+    let occupied = self.placement[..6].iter().fold(0, |acc, bb| acc | bb);
+    let colour = self.placement[6];
+    if white {
+      occupied & colour
+    } else {
+      occupied & !colour
+    }
+  }
+
+  fn rebuild(&mut self, board: &GameBoard) {
+    self.attackers = [0; 64];
+    for from in board.combined() {
+      let bit = 1u64 << from;
+      for to in BitBoard::new(attacks_from(board, from)) {
+        self.attackers[to as usize] |= bit;
+      }
+    }
+  }
+}
+
+fn placement(board: &GameBoard) -> [u64; 7] {
+  [
+    board.pawns.raw(),
+    board.knights.raw(),
+    board.bishops.raw(),
+    board.rooks.raw(),
+    board.queens.raw(),
+    board.kings.raw(),
+    board.colour.raw(),
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constants::*;
+  use crate::legal::attack::is_square_attacked;
+  use crate::model::gamedata::GameData;
+  use crate::model::piecemove::PieceMove;
+
+  fn get_board(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  #[test]
+  fn test_agrees_with_is_square_attacked() {
+    for fen in [
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1",
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+      "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ] {
+      let board = get_board(fen);
+      let cache = AttackCache::new(&board);
+      for square in 0..64 {
+        // is_square_attacked asks about the side not to move
+        assert_eq!(
+          cache.is_attacked_by(square, !board.playing),
+          is_square_attacked(&board, square),
+          "{fen} square {square}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn test_attackers() {
+    // White rook d1 and knight c3 both hit the pawn on d5, which the pawn on e6 defends
+    let board = get_board("4k3/8/4p3/3p4/8/2N5/8/3RK3 w - - 0 1");
+    let cache = AttackCache::new(&board);
+    let bits = |squares: &[u8]| squares.iter().fold(0u64, |acc, &sq| acc | (1u64 << sq));
+    assert_eq!(cache.attackers_by(D5, true).raw(), bits(&[C3, D1]));
+    assert_eq!(cache.attackers_by(D5, false).raw(), bits(&[E6]));
+    assert_eq!(cache.attackers(D5).raw(), bits(&[C3, D1, E6]));
+    // The pawn on d5 blocks the rook
+    assert!(!cache.attackers_by(D6, true).contains(D1));
+    assert!(cache.attacked_by(true).contains(D4));
+    assert!(cache.attackers(64).is_empty());
+  }
+
+  #[test]
+  fn test_refresh_after_move() {
+    let mut board = GameBoard::START_POS;
+    let mut cache = AttackCache::new(&board);
+    assert!(!cache.refresh(&board));
+
+    let before = board;
+    board
+      .move_piece(&PieceMove::new(E2, E4, false, None))
+      .unwrap();
+    assert!(!cache.is_current(&board));
+    assert!(cache.refresh(&board));
+    assert!(cache.is_attacked_by(H5, true));
+    assert_eq!(cache.attackers(H5), AttackCache::new(&board).attackers(H5));
+
+    // Taking the move back invalidates it again
+    assert!(cache.refresh(&before));
+    assert!(!cache.is_attacked_by(H5, true));
+  }
+}
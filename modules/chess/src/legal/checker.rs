@@ -16,15 +16,23 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
-use crate::legal::attack::is_square_attacked;
+use crate::errors::IllegalMoveReason;
+use crate::legal::attack::{
+  attacks_from, between, is_square_attacked, is_square_attacked_excluding,
+};
+use crate::model::bitboard::BitBoard;
+use crate::model::castling::{castling_clearance, castling_king_path, castling_square_attacked};
 use crate::model::gameboard::GameBoard;
 use crate::model::gameboard::PieceType;
 use crate::model::piecemove::PieceMove;
+use crate::model::rays::RAYS;
 #[cfg(feature = "precomputed_rays")]
 use crate::model::rays::{
   KING_MOVES, KNIGHT_MOVES, PAWN_ATTACK_BLACK, PAWN_ATTACK_WHITE, PAWN_PUSH_BLACK, PAWN_PUSH_WHITE,
 };
+use crate::stats::{self, Event};
 
+#[derive(Clone, Copy)]
 pub struct LegalChecker<'a> {
   pub board: &'a GameBoard,
 }
@@ -35,10 +43,22 @@ impl<'a> LegalChecker<'a> {
   }
 
   pub fn is_move_legal(&self, piece_move: &PieceMove) -> bool {
+    let legal = self.passes_checks(piece_move);
+    if !legal {
+      stats::record(Event::IllegalMove);
+    }
+    legal
+  }
+
+  fn passes_checks(&self, piece_move: &PieceMove) -> bool {
     // replicate the original checks from GameBoard::is_move_legal
-    if !self.is_correct_turn_piece(piece_move) {
+    if *piece_move == PieceMove::NULL || !self.is_correct_turn_piece(piece_move) {
       return false;
     }
+    if let Some(rook) = self.board.castling_rook(piece_move) {
+      return self.is_castling_valid(piece_move, rook)
+        && self.does_not_leave_king_in_check(piece_move);
+    }
     if !self.is_piece_move_valid(piece_move) {
       return false;
     }
@@ -54,6 +74,172 @@ impl<'a> LegalChecker<'a> {
     true
   }
 
+  /// Why `piece_move` is illegal, or `None` if it is legal. Runs the same checks as
+  /// [`is_move_legal`](Self::is_move_legal) in the same order, then looks closer at the one that
+  /// failed.
+  pub fn illegal_reason(&self, piece_move: &PieceMove) -> Option<IllegalMoveReason> {
+    if *piece_move == PieceMove::NULL {
+      return Some(IllegalMoveReason::NullMove);
+    }
+    let from = piece_move.from_square();
+    let to = piece_move.to_square();
+    let Some(piece) = self.board.get_piece(from) else {
+      return Some(IllegalMoveReason::NoPiece { square: from });
+    };
+    if !self.is_correct_turn_piece(piece_move) {
+      return Some(IllegalMoveReason::OpponentsPiece { square: from });
+    }
+    if let Some(rook) = self.board.castling_rook(piece_move) {
+      if !self.is_castling_valid(piece_move, rook) {
+        return Some(self.castling_reason(piece_move, rook));
+      }
+      return self.check_reason(piece_move);
+    }
+    if !self.is_piece_move_valid(piece_move) {
+      return Some(self.movement_reason(piece_move, piece));
+    }
+    if !self.is_destination_valid(piece_move) {
+      if self.board.get_piece(to) == Some(PieceType::King) {
+        return Some(IllegalMoveReason::CapturesKing);
+      }
+      return Some(IllegalMoveReason::OwnPieceOnTarget { square: to });
+    }
+    if !self.are_special_moves_valid(piece_move) {
+      // The only special move that can fail here is en passant, and when the capture itself is
+      // fine the rank check found a rook or queen behind the two pawns
+      if self.is_en_passant_capture_valid(piece_move) {
+        return self.check_reason(piece_move);
+      }
+      return Some(IllegalMoveReason::EnPassantUnavailable);
+    }
+    // A king "promoting" would leave the side without a king, which the simulation rejects
+    if piece == PieceType::King && piece_move.is_promotion() {
+      return Some(IllegalMoveReason::UnexpectedPromotion);
+    }
+    self.check_reason(piece_move)
+  }
+
+  fn movement_reason(&self, piece_move: &PieceMove, piece: PieceType) -> IllegalMoveReason {
+    let from = piece_move.from_square();
+    let to = piece_move.to_square();
+    let dr = (to / 8) as i8 - (from / 8) as i8;
+    let df = (to % 8) as i8 - (from % 8) as i8;
+    let wrong = IllegalMoveReason::WrongMovement { piece };
+    let occupied = self.board.combined().raw();
+
+    match piece {
+      PieceType::Pawn => {
+        let forward = if self.board.playing { dr } else { -dr };
+        let start_rank = if self.board.playing { 1 } else { 6 };
+        let push = df == 0 && (forward == 1 || (forward == 2 && from / 8 == start_rank));
+        if push {
+          let path = (between(from, to) | (1u64 << to)) & occupied;
+          if path != 0 {
+            return IllegalMoveReason::Blocked {
+              piece,
+              blocker: nearest(path, from < to),
+            };
+          }
+        } else if df.abs() == 1 && forward == 1 {
+          let capture = self
+            .board
+            .colour
+            .get_bit(to)
+            .is_some_and(|f| f != self.board.playing)
+            && self.board.get_piece(to).is_some();
+          if !capture && !piece_move.is_en_passant() {
+            return IllegalMoveReason::PawnCaptureWithoutTarget;
+          }
+        } else {
+          return wrong;
+        }
+        let last_rank = if self.board.playing { 7 } else { 0 };
+        match (to / 8 == last_rank, piece_move.is_promotion()) {
+          (true, false) => IllegalMoveReason::MissingPromotion,
+          (false, true) => IllegalMoveReason::UnexpectedPromotion,
+          _ => wrong,
+        }
+      }
+      PieceType::King if dr == 0 && df.abs() == 2 => IllegalMoveReason::NoCastlingRight,
+      PieceType::Bishop | PieceType::Rook | PieceType::Queen => {
+        let diagonal = dr.abs() == df.abs();
+        let straight = dr == 0 || df == 0;
+        let shape = match piece {
+          PieceType::Bishop => diagonal,
+          PieceType::Rook => straight,
+          _ => diagonal || straight,
+        };
+        let path = between(from, to) & occupied;
+        if shape && from != to && path != 0 {
+          IllegalMoveReason::Blocked {
+            piece,
+            blocker: nearest(path, from < to),
+          }
+        } else {
+          wrong
+        }
+      }
+      _ => wrong,
+    }
+  }
+
+  fn castling_reason(&self, piece_move: &PieceMove, rook: u8) -> IllegalMoveReason {
+    let white = self.board.playing;
+    let king = piece_move.from_square();
+    if !(self.board.rooks & self.board.pieces_of(white))
+      .get_bit(rook)
+      .unwrap_or(false)
+    {
+      return IllegalMoveReason::NoCastlingRight;
+    }
+    let blockers = self.board.combined().raw() & castling_clearance(white, king, rook);
+    if blockers != 0 {
+      return IllegalMoveReason::CastlingBlocked {
+        blocker: nearest(blockers, king < rook),
+      };
+    }
+    if is_square_attacked(self.board, king) {
+      return IllegalMoveReason::CastlingOutOfCheck;
+    }
+    let path = castling_king_path(white, king, rook);
+    let mut attacked = path;
+    while attacked != 0 {
+      let square = attacked.trailing_zeros() as u8;
+      if castling_square_attacked(self.board, square, king, rook) {
+        return IllegalMoveReason::CastlingThroughCheck { square };
+      }
+      attacked &= attacked - 1;
+    }
+    IllegalMoveReason::CastlingOutOfCheck
+  }
+
+  /// `None` if `piece_move` keeps the king safe, else the piece that would be giving check.
+  fn check_reason(&self, piece_move: &PieceMove) -> Option<IllegalMoveReason> {
+    let white = self.board.playing;
+    let mut new_board = *self.board;
+    new_board.apply_move_unchecked(piece_move);
+    let king = new_board.king_square(white)?;
+    let mut attackers = new_board.pieces_of(!white).raw();
+    while attackers != 0 {
+      let square = attackers.trailing_zeros() as u8;
+      attackers &= attackers - 1;
+      if attacks_from(&new_board, square) & (1u64 << king) == 0 {
+        continue;
+      }
+      let attacker = new_board.get_piece(square)?;
+      let in_check = self
+        .board
+        .king_square(white)
+        .is_some_and(|sq| is_square_attacked(self.board, sq));
+      return Some(if in_check {
+        IllegalMoveReason::StillInCheck { attacker, square }
+      } else {
+        IllegalMoveReason::ExposesKing { attacker, square }
+      });
+    }
+    None
+  }
+
   fn is_correct_turn_piece(&self, piece_move: &PieceMove) -> bool {
     self
       .board
@@ -276,77 +462,38 @@ impl<'a> LegalChecker<'a> {
     let to = piece_move.to_square();
     #[cfg(feature = "precomputed_rays")]
     {
-      // Quick adjacency test with precomputed king moves
-      if (KING_MOVES[from as usize] & (1u64 << to)) != 0 {
-        return true;
-      }
-      // Castling remains a special-case two-square horizontal move
-      let dr = (from / 8) as i8 - (to / 8) as i8;
-      let df = (from % 8) as i8 - (to % 8) as i8;
-      if dr == 0 && df.abs() == 2 {
-        return self.is_castling_valid(piece_move);
-      }
-      false
+      // Castling is handled before we get here, so only single steps remain
+      (KING_MOVES[from as usize] & (1u64 << to)) != 0
     }
     #[cfg(not(feature = "precomputed_rays"))]
     {
       let dr = (from / 8) as i8 - (to / 8) as i8;
       let df = (from % 8) as i8 - (to % 8) as i8;
-      if dr.abs() <= 1 && df.abs() <= 1 {
-        return true;
-      }
-      if dr == 0 && df.abs() == 2 {
-        return self.is_castling_valid(piece_move);
-      }
-      false
+      dr.abs() <= 1 && df.abs() <= 1
     }
   }
 
-  fn is_castling_valid(&self, piece_move: &PieceMove) -> bool {
-    let from = piece_move.from_square();
-    let to = piece_move.to_square();
-    let is_kingside = to == from + 2;
-    let (can_k, can_q) = if self.board.playing {
-      self.board.casling_right_white()
-    } else {
-      self.board.casling_right_black()
-    };
-    if (is_kingside && !can_k) || (!is_kingside && !can_q) {
+  fn is_castling_valid(&self, piece_move: &PieceMove, rook: u8) -> bool {
+    let white = self.board.playing;
+    let king = piece_move.from_square();
+    if !(self.board.rooks & self.board.pieces_of(white))
+      .get_bit(rook)
+      .unwrap_or(false)
+    {
       return false;
     }
-    if !self.are_castling_squares_clear(from, is_kingside) {
+    if self.board.combined().raw() & castling_clearance(white, king, rook) != 0 {
       return false;
     }
-    self.is_castling_path_safe(from, is_kingside)
-  }
-
-  fn are_castling_squares_clear(&self, from: u8, is_kingside: bool) -> bool {
-    if is_kingside {
-      for sq in [from + 1, from + 2] {
-        if self.board.combined().get_bit(sq).unwrap_or(false) {
-          return false;
-        }
-      }
-    } else {
-      for sq in [from - 1, from - 2, from - 3] {
-        if self.board.combined().get_bit(sq).unwrap_or(false) {
-          return false;
-        }
-      }
-    }
-    true
-  }
 
-  fn is_castling_path_safe(&self, from: u8, is_kingside: bool) -> bool {
-    let path = if is_kingside {
-      [from, from + 1, from + 2]
-    } else {
-      [from, from - 1, from - 2]
-    };
-    for &sq in &path {
-      if is_square_attacked(self.board, sq) {
+    // The king may not castle out of, through or into check
+    let mut path = castling_king_path(white, king, rook);
+    while path != 0 {
+      let sq = path.trailing_zeros() as u8;
+      if castling_square_attacked(self.board, sq, king, rook) {
         return false;
       }
+      path &= path - 1;
     }
     true
   }
@@ -359,10 +506,26 @@ impl<'a> LegalChecker<'a> {
   }
 
   fn is_en_passant_valid(&self, piece_move: &PieceMove) -> bool {
+    let from = piece_move.from_square();
+    let captured_pawn_square = if self.board.playing {
+      piece_move.to_square().wrapping_sub(8)
+    } else {
+      piece_move.to_square() + 8
+    };
+    self.is_en_passant_capture_valid(piece_move)
+      && self.is_en_passant_rank_clear(from, captured_pawn_square)
+  }
+
+  /// Everything about an en passant capture except whether it exposes the king along the rank.
+  fn is_en_passant_capture_valid(&self, piece_move: &PieceMove) -> bool {
     let from = piece_move.from_square();
     let to = piece_move.to_square();
-    let ep_square = self.board.en_passant.to_square();
-    if ep_square != to {
+    if self
+      .board
+      .en_passant
+      .map(|en_passant| en_passant.to_square())
+      != Some(to)
+    {
       return false;
     }
     let from_file = from % 8;
@@ -393,13 +556,69 @@ impl<'a> LegalChecker<'a> {
     true
   }
 
+  /// En passant is the only move that lifts two pawns off the same rank at once, which can expose
+  /// the king to a rook or queen along that rank (e.g. `8/8/8/K2pP2r/8/8/8/7k w - d6`). Neither
+  /// pawn is pinned on its own, so this is checked explicitly rather than left to the simulation.
+  fn is_en_passant_rank_clear(&self, from: u8, captured_pawn_square: u8) -> bool {
+    let king_square = match self.board.king_square(self.board.playing) {
+      Some(sq) => sq,
+      None => return true,
+    };
+    if king_square / 8 != from / 8 {
+      return true;
+    }
+
+    // Occupancy as it will be after the capture, minus the capturing pawn's destination which is
+    // off the rank anyway.
+    let occ = self.board.combined().raw() & !(1u64 << from) & !(1u64 << captured_pawn_square);
+    let opponent = self.board.pieces_of(!self.board.playing).raw();
+    let sliders = (self.board.rooks | self.board.queens).raw() & opponent;
+
+    // RAYS index 0 is east, 1 is west (see DIR_OFFSETS)
+    let towards_east = from > king_square;
+    let blockers = occ & RAYS[king_square as usize][if towards_east { 0 } else { 1 }];
+    if blockers == 0 {
+      return true;
+    }
+    let nearest = if towards_east {
+      blockers.trailing_zeros()
+    } else {
+      63 - blockers.leading_zeros()
+    };
+
+    (sliders & (1u64 << nearest)) == 0
+  }
+
+  /// A side without a king has no king to keep safe, so every move passes. A side with one must
+  /// still have it afterwards, out of check.
   fn does_not_leave_king_in_check(&self, piece_move: &PieceMove) -> bool {
+    let Some(king) = self.board.king_square(self.board.playing) else {
+      return true;
+    };
+    // A king step only needs its destination safe once the king no longer shields it
+    if piece_move.from_square() == king
+      && !piece_move.is_promotion()
+      && self.board.castling_rook(piece_move).is_none()
+    {
+      let king = BitBoard::new(1 << king);
+      return !is_square_attacked_excluding(self.board, piece_move.to_square(), king);
+    }
     let mut new_board = *self.board;
     new_board.apply_move_unchecked(piece_move);
-    if let Some(king_square) = new_board.find_king(self.board.playing) {
+    if let Some(king_square) = new_board.king_square(self.board.playing) {
       !is_square_attacked(&new_board, king_square)
     } else {
       false
     }
   }
 }
+
+/// The set square of `squares` closest to the start of a line running up the board (`ascending`)
+/// or down it.
+fn nearest(squares: u64, ascending: bool) -> u8 {
+  if ascending {
+    squares.trailing_zeros() as u8
+  } else {
+    63 - squares.leading_zeros() as u8
+  }
+}
@@ -18,4 +18,6 @@
 
 pub mod attack;
 pub mod batch;
+pub mod cache;
 pub mod checker;
+pub mod pins;
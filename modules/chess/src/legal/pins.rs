@@ -0,0 +1,294 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Pins and checks against the king of the side to move, for telling generated moves legal
+//! without playing them.
+//!
+//! [`Pins`] looks along the eight lines from the king once per position. A piece standing alone
+//! between the king and an enemy slider may only move along that line, towards the king or onto
+//! the pinner; while in check, every other piece must capture the checker or step between it and
+//! the king. With both masks a pseudo-legal move by anything but the king is legal exactly when
+//! its destination is in them.
+//!
+//! En passant is the exception: it lifts two pawns off the board at once, so it can expose the
+//! king along a rank where neither pawn is pinned on its own (`8/8/8/K2pP2r/8/8/8/7k w - d6`), or
+//! along a diagonal through the captured pawn. [`Pins::allows`] looks along the lines from the
+//! king again with both pawns gone for those.
+
+use crate::{
+  legal::attack::{attacks_of, between},
+  model::{
+    bitboard::BitBoard,
+    gameboard::{GameBoard, PieceType},
+    piecemove::PieceMove,
+    rays::{DIR_OFFSETS, RAYS},
+  },
+};
+
+/// The pins and checks against the king of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pins {
+  king: Option<u8>,
+  /// Pieces of the side to move pinned to their king.
+  pinned: u64,
+  /// Per direction from the king (indexed as [`DIR_OFFSETS`]), the squares a piece pinned along
+  /// it may move to: those up to and including the pinner. Empty where nothing is pinned.
+  pin_rays: [u64; 8],
+  /// Enemy pieces giving check.
+  checkers: u64,
+  /// The squares that answer the check: the checker and the squares between it and the king. Every
+  /// square when not in check, none in double check.
+  check_mask: u64,
+}
+
+impl Pins {
+  /// Finds the pins and checks in `board`. A side without a king has neither.
+  pub fn new(board: &GameBoard) -> Self {
+    let mut pins = Pins {
+      king: board.king_square(board.playing),
+      pinned: 0,
+      pin_rays: [0; 8],
+      checkers: 0,
+      check_mask: !0,
+    };
+    let Some(king) = pins.king else {
+      return pins;
+    };
+
+    let white = board.playing;
+    let occupied = board.combined().raw();
+    let own = board.pieces_of(white).raw();
+    let enemy = board.pieces_of(!white).raw();
+    let straight = (board.rooks | board.queens).raw() & enemy;
+    let diagonal = (board.bishops | board.queens).raw() & enemy;
+
+    for (direction, &offset) in DIR_OFFSETS.iter().enumerate() {
+      let ray = RAYS[king as usize][direction];
+      let sliders = if direction < 4 { straight } else { diagonal };
+      if ray & sliders == 0 {
+        continue;
+      }
+      let Some(first) = nearest(ray & occupied, offset) else {
+        continue;
+      };
+      if sliders & (1u64 << first) != 0 {
+        pins.checkers |= 1u64 << first;
+        continue;
+      }
+      if own & (1u64 << first) == 0 {
+        continue;
+      }
+      let beyond = RAYS[first as usize][direction] & occupied;
+      if let Some(second) = nearest(beyond, offset)
+        && sliders & (1u64 << second) != 0
+      {
+        pins.pinned |= 1u64 << first;
+        pins.pin_rays[direction] = ray & !RAYS[second as usize][direction];
+      }
+    }
+
+    let empty = BitBoard::EMPTY;
+    pins.checkers |=
+      attacks_of(PieceType::Knight, king, empty, white).raw() & board.knights.raw() & enemy;
+    pins.checkers |=
+      attacks_of(PieceType::Pawn, king, empty, white).raw() & board.pawns.raw() & enemy;
+
+    pins.check_mask = match pins.checkers.count_ones() {
+      0 => !0,
+      1 => pins.checkers | between(king, pins.checkers.trailing_zeros() as u8),
+      _ => 0,
+    };
+    pins
+  }
+
+  /// The king these pins and checks are against.
+  pub fn king(&self) -> Option<u8> {
+    self.king
+  }
+
+  /// Pieces of the side to move pinned to their king.
+  pub fn pinned(&self) -> BitBoard {
+    BitBoard::new(self.pinned)
+  }
+
+  /// Enemy pieces giving check.
+  pub fn checkers(&self) -> BitBoard {
+    BitBoard::new(self.checkers)
+  }
+
+  /// The squares that answer the check: the checker and the squares between it and the king.
+  /// Every square when not in check, none in double check.
+  pub(crate) fn check_mask(&self) -> u64 {
+    self.check_mask
+  }
+
+  /// The squares the piece on `square` may move to without exposing its king: the pin line when
+  /// it is pinned, else every square. Checks are not taken into account.
+  pub fn pin_mask(&self, square: u8) -> BitBoard {
+    if self.pinned & (1u64 << square) == 0 {
+      return BitBoard::new(!0);
+    }
+    let line = self
+      .pin_rays
+      .iter()
+      .find(|&&ray| ray & (1u64 << square) != 0)
+      .copied()
+      .unwrap_or(0);
+    BitBoard::new(line)
+  }
+
+  /// Whether `piece_move`, a pseudo-legal move of `board` by anything but the king, keeps the
+  /// king safe. King moves and castling need the king's destination checked for attacks, which
+  /// these masks know nothing about.
+  pub fn allows(&self, board: &GameBoard, piece_move: &PieceMove) -> bool {
+    let from = piece_move.from_square();
+    let to = piece_move.to_square();
+    // Capturing the king is never a move, whatever the position
+    if board.kings.raw() & (1u64 << to) != 0 {
+      return false;
+    }
+    let mut targets = self.check_mask & self.pin_mask(from).raw();
+    // The move flags only say "diagonal capture"; en passant is the one landing on an empty square
+    let en_passant = piece_move.is_en_passant() && board.get_piece(to).is_none();
+    if !en_passant {
+      return targets & (1u64 << to) != 0;
+    }
+
+    // Taking the pawn that gives check answers it too, though the capture lands behind it
+    let captured = to ^ 8;
+    if self.check_mask & (1u64 << captured) != 0 {
+      targets |= 1u64 << to;
+    }
+    targets & (1u64 << to) != 0 && !self.en_passant_exposes_king(board, from, to, captured)
+  }
+
+  /// Whether an enemy slider sees the king once the pawns on `from` and `captured` are gone and
+  /// one stands on `to`.
+  fn en_passant_exposes_king(&self, board: &GameBoard, from: u8, to: u8, captured: u8) -> bool {
+    let Some(king) = self.king else {
+      return false;
+    };
+    let enemy = board.pieces_of(!board.playing).raw();
+    let occupied = (board.combined().raw() & !(1u64 << from) & !(1u64 << captured)) | (1u64 << to);
+    let occupancy = BitBoard::new(occupied);
+    let straight = (board.rooks | board.queens).raw() & enemy;
+    let diagonal = (board.bishops | board.queens).raw() & enemy;
+    attacks_of(PieceType::Rook, king, occupancy, board.playing).raw() & straight != 0
+      || attacks_of(PieceType::Bishop, king, occupancy, board.playing).raw() & diagonal != 0
+  }
+}
+
+/// The occupied square of `squares` nearest the start of a ray stepping by `offset`.
+fn nearest(squares: u64, offset: i8) -> Option<u8> {
+  match (squares, offset > 0) {
+    (0, _) => None,
+    (_, true) => Some(squares.trailing_zeros() as u8),
+    (_, false) => Some(63 - squares.leading_zeros() as u8),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::gamedata::GameData, movegen::generate_moves};
+
+  fn board(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  fn squares(list: &[u8]) -> u64 {
+    list.iter().fold(0, |bits, &square| bits | (1u64 << square))
+  }
+
+  #[test]
+  fn test_pin_masks() {
+    // The rook on e4 is pinned along the file and the bishop on d2 along the diagonal; the knight
+    // on b3 is off the king's lines and the rook on a4 pins nothing
+    let pins = Pins::new(&board("4r2k/8/8/b7/r3R3/1N6/3B4/4K3 w - - 0 1"));
+    assert_eq!(pins.king(), Some(E1));
+    assert_eq!(pins.checkers(), BitBoard::EMPTY);
+    assert_eq!(pins.pinned().raw(), squares(&[E4, D2]));
+    assert_eq!(
+      pins.pin_mask(E4).raw(),
+      squares(&[E2, E3, E4, E5, E6, E7, E8])
+    );
+    assert_eq!(pins.pin_mask(D2).raw(), squares(&[D2, C3, B4, A5]));
+    assert_eq!(pins.pin_mask(B3).raw(), !0);
+  }
+
+  #[test]
+  fn test_check_masks() {
+    // Single check by a rook: capture it or block on the file
+    let pins = Pins::new(&board("4r2k/8/8/8/8/8/8/4K3 w - - 0 1"));
+    assert_eq!(pins.checkers().raw(), 1u64 << E8);
+    assert_eq!(pins.check_mask, squares(&[E2, E3, E4, E5, E6, E7, E8]));
+
+    // Double check by a rook and a knight leaves only king moves
+    let pins = Pins::new(&board("4r2k/8/8/8/8/3n4/8/4K3 w - - 0 1"));
+    assert_eq!(pins.checkers().raw(), squares(&[E8, D3]));
+    assert_eq!(pins.check_mask, 0);
+  }
+
+  #[test]
+  fn test_en_passant_discovered_check() {
+    // Along the rank, with neither pawn pinned on its own
+    let rank = board("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1");
+    let pins = Pins::new(&rank);
+    assert_eq!(pins.pinned(), BitBoard::EMPTY);
+    assert!(!pins.allows(&rank, &PieceMove::new_en_passant(E5, D6)));
+
+    // Along the diagonal through the captured pawn
+    let diagonal = board("6b1/8/8/3pP3/8/8/K7/7k w - d6 0 1");
+    assert!(!Pins::new(&diagonal).allows(&diagonal, &PieceMove::new_en_passant(E5, D6)));
+
+    // Taking the pawn that gives check answers it
+    let check = board("8/8/8/3k4/4Pp2/8/8/4K3 b - e3 0 1");
+    let pins = Pins::new(&check);
+    assert_eq!(pins.checkers().raw(), 1u64 << E4);
+    assert!(pins.allows(&check, &PieceMove::new_en_passant(F4, E3)));
+  }
+
+  #[test]
+  fn test_agrees_with_simulation() {
+    for fen in [
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+      "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+      "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+      "4r2k/8/8/b7/r3R3/1N6/3B4/4K3 w - - 0 1",
+      "4r2k/8/8/8/8/3n4/8/4K3 w - - 0 1",
+      "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+      "6b1/8/8/3pP3/8/8/K7/7k w - d6 0 1",
+      "8/8/8/3k4/4Pp2/8/8/4K3 b - e3 0 1",
+    ] {
+      let board = board(fen);
+      let pins = Pins::new(&board);
+      let (moves, count) = generate_moves(&board);
+      for piece_move in &moves[..count] {
+        if Some(piece_move.from_square()) == pins.king() {
+          continue;
+        }
+        assert_eq!(
+          pins.allows(&board, piece_move),
+          board.is_move_legal(piece_move),
+          "{fen} {piece_move}"
+        );
+      }
+    }
+  }
+}
@@ -27,27 +27,123 @@
 //!
 //! Key modules
 //! - `model` — board and piece representations (bitboards, moves, game state)
-//! - `movegen` — move generation for all piece types (fast, allocation-free)
-//! - `legal` — move legality checks and attack detection
-//! - `constants` — shared constants such as square indices and masks
+//! - `movegen` — move generation for all piece types (fast, allocation-free), of every move or
+//!   only captures, quiet moves or check evasions
+//! - `features` — positions as 8x8 input planes for training neural networks
+//! - `legal` — move legality checks, attack detection and cached per-position attack tables
+//! - `pgn` — PGN games with their tag pairs, read and written one or many at a time (requires
+//!   `alloc`)
+//! - `repertoire` — opening repertoires built from PGN games, for lookup and practice (requires
+//!   `alloc`)
+//! - `perft` — move tree node counts, divided by root move or broken down by category for
+//!   debugging movegen, and a rules-complete count that stops at ended games
+//! - `prelude` — the common types and functions, for a single glob import
+//! - `san` — standard algebraic notation for moves and whole games
+//! - `stats` — movegen and attack query counters for profiling (requires `stats`)
+//! - `svg` — board diagrams as SVG images (requires `svg`)
+//! - `tactics` — detection of forks, pins, skewers and hanging pieces, and a forced mate solver
+//! - `zobrist` — position hashing with fixed keys that are stable across versions
+//! - `constants` — shared constants such as square indices (a1 = 0, h8 = 63), masks, the
+//!   starting FEN and piece values, and conversions to the a8 = 0 numbering some other libraries
+//!   use
 //! - `errors` — crate-specific error types
+//! - `compat` — deprecated forms of changed APIs, kept for one release cycle
+//!
+//! Feature tiers
+//!
+//! Each tier adds to the one before it, so embedded users can stop at the first that has what
+//! they need:
+//!
+//! - no features: `core` only and allocation-free. Bitboards, move generation, legality, FEN
+//!   parsing, perft, tactics, and FEN and SAN writing into any `core::fmt::Write`.
+//! - `alloc`: needs a global allocator. Adds the helpers that return `String` or `Vec` (`to_fen`,
+//!   `to_san`, `to_movetext`, `motifs`) and the `pgn` and `repertoire` modules.
+//! - `std`: needs the standard library and implies `alloc`. Adds printing boards to the terminal
+//!   and [`GameData::from_fen_batch`](model::gamedata::GameData::from_fen_batch) for ingesting
+//!   datasets.
+//!
+//! `precomputed_rays`, `magics` and `mailbox` trade memory for speed, `stats` counts calls of the
+//! move generators and attack queries, `iterative` adds a perft and an alpha-beta search that do
+//! not recurse, for targets with little stack, and `svg` draws boards as SVG; all six work in
+//! every tier. `magics` looks slider attacks up in about 840 KiB of magic bitboard tables,
+//! indexed with `pext` on x86-64 targets built with BMI2, instead of walking rays. CI builds the
+//! crate for a bare-metal target without features and with `alloc`, and tests every tier.
+//!
+//! `serde` implies `alloc` and derives `Serialize` and `Deserialize` for
+//! `model::snapshot::GameSnapshot` and the types it holds, so adjourned games can be stored in
+//! any serde format.
+//!
+//! `ndarray` implies `alloc` and converts the input planes of `features` to `ndarray` arrays.
+//!
+//! The engine's search recurses and needs `std`. On a microcontroller, `search` has a fixed-depth
+//! alpha-beta over a caller-supplied evaluation that keeps its plies in a caller-owned stack, as
+//! `perft_iterative` does.
+//!
+//! API stability
+//!
+//! Until 1.0, a function whose signature changes keeps its old form as a deprecated wrapper for
+//! one release where one can stand in for it. Changes to fields and statics can't be wrapped;
+//! `compat` lists them, and they bump the minor version. CI checks every change with
+//! `cargo-semver-checks`.
+//!
+//! The most used types are also re-exported at the crate root, so `lumifox_chess::GameBoard`
+//! works as well as the full path.
 //!
 //! Example
 //! ```rust
-//! use lumifox_chess::model::gameboard::GameBoard;
-//! use lumifox_chess::movegen::generate_moves;
+//! use lumifox_chess::{GameBoard, generate_legal_moves, generate_moves};
 //!
 //! // Create a starting position and generate moves (API is intentionally low-level)
 //! let board = GameBoard::START_POS;
 //! let (moves, count) = generate_moves(&board);
 //! assert!(count > 0);
+//! // Pseudo-legal moves may leave the king in check; these never do
+//! let (_, legal_count) = generate_legal_moves(&board);
+//! assert_eq!(legal_count, 20);
 //! ```
 //!
+//! Panics
+//!
+//! Library code does not panic on caller input, in debug or release builds: malformed FENs and
+//! move strings come back as errors, and illegal or nonsensical moves are rejected by
+//! `GameBoard::move_piece`. The remaining `debug_assert!`s guard internal invariants, such as
+//! the move buffers being large enough, and the `*_unchecked` bitboard accessors whose contract
+//! is documented. Tests feed arbitrary moves and strings through the public surface, including
+//! boards with promoted pieces up to the known 218-move positions, and CI runs the suite in
+//! release mode as well, so debug-only behaviour cannot hide a difference.
+//!
 //! For higher-level documentation and usage examples see the crate README at
 //! <https://github.com/ArchProtogens/lumifox/tree/main/modules/chess>
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod compat;
 pub mod constants;
 pub mod errors;
+pub mod features;
 pub mod legal;
 pub mod model;
 pub mod movegen;
+pub mod perft;
+#[cfg(feature = "alloc")]
+pub mod pgn;
+pub mod prelude;
+#[cfg(feature = "alloc")]
+pub mod repertoire;
+pub mod san;
+#[cfg(feature = "iterative")]
+pub mod search;
+pub mod stats;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod tactics;
+pub mod zobrist;
+
+pub use model::{
+  bitboard::BitBoard,
+  gameboard::{GameBoard, PieceType},
+  gamedata::GameData,
+  piecemove::{PieceMove, PromotionType},
+};
+pub use movegen::{generate_legal_moves, generate_moves};
@@ -16,9 +16,18 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
-use core::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+use core::ops::{
+  BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, ShlAssign, Shr,
+  ShrAssign,
+};
 
-#[derive(Clone, Copy, Debug)]
+/// A set of squares, one bit per square with a1 as bit 0 and h8 as bit 63.
+///
+/// The bitwise operators combine bitboards with each other and with raw `u64` masks on either
+/// side, always producing a `BitBoard`, so code mixing the two does not need [`raw`](Self::raw).
+/// Shifts move every square by a number of bit positions; squares shifted past either end are
+/// dropped, so a shift of 64 or more leaves the board empty rather than overflowing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub struct BitBoard {
   data: u64,
 }
@@ -29,12 +38,49 @@ impl BitBoard {
     Self { data }
   }
 
+  /// The bitboard holding only `square`, or an empty one if `square` is off the board.
+  #[inline]
+  pub const fn from_square(square: u8) -> Self {
+    if square < 64 {
+      Self::new(1 << square)
+    } else {
+      Self::EMPTY
+    }
+  }
+
   /// Get the raw bits value
   #[inline]
   pub const fn raw(&self) -> u64 {
     self.data
   }
 
+  /// Whether `square` is in the set. Squares off the board never are.
+  #[inline]
+  pub const fn contains(&self, square: u8) -> bool {
+    square < 64 && self.data & (1 << square) != 0
+  }
+
+  #[inline]
+  pub const fn is_empty(&self) -> bool {
+    self.data == 0
+  }
+
+  /// Number of squares in the set.
+  #[inline]
+  pub const fn count(&self) -> u32 {
+    self.data.count_ones()
+  }
+
+  /// The lowest square in the set, or `None` if it is empty.
+  #[inline]
+  pub const fn first_square(&self) -> Option<u8> {
+    if self.data == 0 {
+      None
+    } else {
+      Some(self.data.trailing_zeros() as u8)
+    }
+  }
+
   #[inline(always)]
   pub fn set_bit(&mut self, index: u8) -> Option<u64> {
     if index < 64 {
@@ -45,7 +91,9 @@ impl BitBoard {
     }
   }
 
+  /// `index` must be below 64. Debug builds check this; release builds wrap it.
   #[inline(always)]
+  #[track_caller]
   pub fn set_bit_unchecked(&mut self, index: u8) -> u64 {
     debug_assert!(index < 64, "Index out of bounds: {index}");
     self.data |= 1 << index;
@@ -62,7 +110,9 @@ impl BitBoard {
     }
   }
 
+  /// `index` must be below 64. Debug builds check this; release builds wrap it.
   #[inline(always)]
+  #[track_caller]
   pub fn unset_bit_unchecked(&mut self, index: u8) -> u64 {
     debug_assert!(index < 64, "Index out of bounds: {index}");
     self.data &= !(1 << index);
@@ -87,7 +137,9 @@ impl BitBoard {
     }
   }
 
+  /// `index` must be below 64. Debug builds check this; release builds wrap it.
   #[inline(always)]
+  #[track_caller]
   pub fn get_bit_unchecked(&self, index: u8) -> bool {
     debug_assert!(index < 64, "Index out of bounds: {index}");
     (self.data & (1 << index)) != 0
@@ -97,29 +149,58 @@ impl BitBoard {
   pub const ALL_SQUARES: Self = Self { data: u64::MAX };
 }
 
-impl BitOr for BitBoard {
-  type Output = Self;
+/// `BitBoard op BitBoard`, `BitBoard op u64` and `u64 op BitBoard`, plus the assigning forms,
+/// for one bitwise operator.
+macro_rules! bitwise_ops {
+  ($op:ident, $method:ident, $assign:ident, $assign_method:ident) => {
+    impl $op for BitBoard {
+      type Output = Self;
 
-  fn bitor(self, rhs: Self) -> Self::Output {
-    Self::new(self.data | rhs.data)
-  }
-}
+      #[inline]
+      fn $method(self, rhs: Self) -> Self::Output {
+        Self::new(self.data.$method(rhs.data))
+      }
+    }
 
-impl BitAnd for BitBoard {
-  type Output = Self;
+    impl $op<u64> for BitBoard {
+      type Output = Self;
 
-  fn bitand(self, rhs: Self) -> Self::Output {
-    Self::new(self.data & rhs.data)
-  }
-}
+      #[inline]
+      fn $method(self, rhs: u64) -> Self::Output {
+        Self::new(self.data.$method(rhs))
+      }
+    }
 
-impl BitAnd<u64> for BitBoard {
-  type Output = Self;
-  fn bitand(self, rhs: u64) -> Self::Output {
-    Self::new(self.data & rhs)
-  }
+    impl $op<BitBoard> for u64 {
+      type Output = BitBoard;
+
+      #[inline]
+      fn $method(self, rhs: BitBoard) -> Self::Output {
+        BitBoard::new(self.$method(rhs.data))
+      }
+    }
+
+    impl $assign for BitBoard {
+      #[inline]
+      fn $assign_method(&mut self, rhs: Self) {
+        self.data.$assign_method(rhs.data);
+      }
+    }
+
+    impl $assign<u64> for BitBoard {
+      #[inline]
+      fn $assign_method(&mut self, rhs: u64) {
+        self.data.$assign_method(rhs);
+      }
+    }
+  };
 }
 
+bitwise_ops!(BitOr, bitor, BitOrAssign, bitor_assign);
+bitwise_ops!(BitAnd, bitand, BitAndAssign, bitand_assign);
+bitwise_ops!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+/// XOR with `true` inverts every square; XOR with `false` changes nothing.
 impl BitXor<bool> for BitBoard {
   type Output = Self;
   fn bitxor(self, rhs: bool) -> Self::Output {
@@ -143,7 +224,7 @@ impl Not for BitBoard {
 impl Shl<u8> for BitBoard {
   type Output = Self;
   fn shl(self, rhs: u8) -> Self::Output {
-    Self::new(self.data << rhs)
+    Self::new(self.data.checked_shl(rhs as u32).unwrap_or(0))
   }
 }
 
@@ -151,7 +232,25 @@ impl Shr<u8> for BitBoard {
   type Output = Self;
 
   fn shr(self, rhs: u8) -> Self::Output {
-    Self::new(self.data >> rhs)
+    Self::new(self.data.checked_shr(rhs as u32).unwrap_or(0))
+  }
+}
+
+impl ShlAssign<u8> for BitBoard {
+  fn shl_assign(&mut self, rhs: u8) {
+    *self = *self << rhs;
+  }
+}
+
+impl ShrAssign<u8> for BitBoard {
+  fn shr_assign(&mut self, rhs: u8) {
+    *self = *self >> rhs;
+  }
+}
+
+impl From<u64> for BitBoard {
+  fn from(data: u64) -> Self {
+    Self::new(data)
   }
 }
 
@@ -215,3 +314,64 @@ impl From<Direction> for i8 {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constants::*;
+
+  #[test]
+  fn test_squares() {
+    let board = BitBoard::from_square(E4) | BitBoard::from_square(A1);
+    assert!(board.contains(E4));
+    assert!(!board.contains(E5));
+    assert!(!board.contains(64));
+    assert_eq!(board.count(), 2);
+    assert_eq!(board.first_square(), Some(A1));
+    assert!(BitBoard::from_square(64).is_empty());
+    assert_eq!(BitBoard::EMPTY.first_square(), None);
+  }
+
+  #[test]
+  fn test_mixed_operands() {
+    let e4 = BitBoard::from_square(E4);
+    let mask = 1u64 << E4 | 1u64 << D4;
+    assert_eq!(e4 & mask, e4);
+    assert_eq!(mask & e4, e4);
+    assert_eq!((mask ^ e4).raw(), 1u64 << D4);
+    assert_eq!((e4 | mask).count(), 2);
+    assert_eq!(BitBoard::from(mask), BitBoard::new(mask));
+  }
+
+  #[test]
+  fn test_assigning_operators() {
+    let mut board = BitBoard::from_square(A1);
+    board |= BitBoard::from_square(B1);
+    board |= 1u64 << C1;
+    assert_eq!(board.count(), 3);
+    board &= !BitBoard::from_square(B1);
+    assert!(!board.contains(B1));
+    board ^= 1u64 << A1;
+    assert_eq!(board, BitBoard::from_square(C1));
+    board <<= 8;
+    assert_eq!(board, BitBoard::from_square(C2));
+    board >>= 16;
+    assert!(board.is_empty());
+  }
+
+  #[test]
+  fn test_shifts_off_the_board() {
+    let full = BitBoard::new(!0);
+    assert_eq!(full << 63, BitBoard::from_square(H8));
+    for amount in [64, 65, u8::MAX] {
+      assert!((full << amount).is_empty());
+      assert!((full >> amount).is_empty());
+      let mut board = full;
+      board <<= amount;
+      assert!(board.is_empty());
+      let mut board = full;
+      board >>= amount;
+      assert!(board.is_empty());
+    }
+  }
+}
@@ -0,0 +1,547 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Castling rights.
+//!
+//! Each right is stored as the square of the rook it castles with rather than as a flag, so the
+//! same representation covers standard chess and Chess960, and a right is dropped as soon as its
+//! rook moves or is captured.
+//!
+//! A right alone does not mean castling is possible now: [`GameBoard::can_castle`] also looks at
+//! the squares between king and rook and at the squares the king crosses, and says which of them
+//! stands in the way.
+
+use crate::{
+  errors::FenParseError,
+  legal::attack::{is_square_attacked, is_square_attacked_excluding},
+  model::{bitboard::BitBoard, gameboard::GameBoard},
+};
+
+/// Castling rights for both sides, stored as the squares of the castling rooks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct CastlingRights {
+  // Indexed by `index`: white kingside, white queenside, black kingside, black queenside
+  rooks: [Option<u8>; 4],
+}
+
+/// Index of a right in `CastlingRights::rooks`; matches the bit order of [`CastlingRights::bits`].
+const fn index(white: bool, kingside: bool) -> usize {
+  (!white as usize) * 2 + (!kingside as usize)
+}
+
+/// Back rank of the given side, as a square offset.
+const fn back_rank(white: bool) -> u8 {
+  if white { 0 } else { 56 }
+}
+
+impl CastlingRights {
+  /// No castling rights.
+  pub const NONE: CastlingRights = CastlingRights { rooks: [None; 4] };
+
+  /// All four rights with the rooks on their standard squares (KQkq).
+  pub const STANDARD: CastlingRights = CastlingRights::from_bits(0b1111);
+
+  /// Build rights from the classic 4-bit encoding (bit 0 K, bit 1 Q, bit 2 k, bit 3 q), with the
+  /// rooks on their standard corner squares.
+  pub const fn from_bits(bits: u8) -> Self {
+    const CORNERS: [u8; 4] = [7, 0, 63, 56];
+    let mut rooks = [None; 4];
+    let mut i = 0;
+    while i < 4 {
+      if bits & (1 << i) != 0 {
+        rooks[i] = Some(CORNERS[i]);
+      }
+      i += 1;
+    }
+    Self { rooks }
+  }
+
+  /// The classic 4-bit encoding of these rights (bit 0 K, bit 1 Q, bit 2 k, bit 3 q), regardless
+  /// of where the rooks stand.
+  pub const fn bits(&self) -> u8 {
+    let mut bits = 0;
+    let mut i = 0;
+    while i < 4 {
+      if self.rooks[i].is_some() {
+        bits |= 1 << i;
+      }
+      i += 1;
+    }
+    bits
+  }
+
+  pub const fn is_empty(&self) -> bool {
+    self.bits() == 0
+  }
+
+  /// Square of the rook `white` may castle with on the given side, if that right remains.
+  pub const fn rook(&self, white: bool, kingside: bool) -> Option<u8> {
+    self.rooks[index(white, kingside)]
+  }
+
+  pub const fn has(&self, white: bool, kingside: bool) -> bool {
+    self.rook(white, kingside).is_some()
+  }
+
+  /// Set or clear a single right.
+  pub fn set(&mut self, white: bool, kingside: bool, rook: Option<u8>) {
+    self.rooks[index(white, kingside)] = rook;
+  }
+
+  /// Drop both rights of one side, e.g. after its king moves.
+  pub fn clear_side(&mut self, white: bool) {
+    self.set(white, true, None);
+    self.set(white, false, None);
+  }
+
+  /// Drop any right whose rook stands on `square`, e.g. after that rook moves or is captured.
+  pub fn clear_square(&mut self, square: u8) {
+    for rook in self.rooks.iter_mut() {
+      if *rook == Some(square) {
+        *rook = None;
+      }
+    }
+  }
+
+  /// Parse the castling field of a FEN string against an already placed `board`.
+  ///
+  /// Accepts the classic `KQkq` letters, which refer to the outermost rook on that side of the
+  /// king, as well as Shredder-FEN and X-FEN file letters (`A`-`H`, `a`-`h`) naming the rook's
+  /// file directly, so Chess960 positions round-trip.
+  pub fn from_fen(field: &str, board: &GameBoard) -> Result<Self, FenParseError> {
+    let mut rights = CastlingRights::NONE;
+    if field == "-" {
+      return Ok(rights);
+    }
+    for c in field.chars() {
+      let white = c.is_ascii_uppercase();
+      let (kingside, rook) = match c.to_ascii_lowercase() {
+        'k' => (true, outermost_rook(board, white, true)),
+        'q' => (false, outermost_rook(board, white, false)),
+        file @ 'a'..='h' => {
+          let square = back_rank(white) + (file as u8 - b'a');
+          let kingside = match board.king_square(white) {
+            Some(king) if king / 8 == square / 8 => square > king,
+            _ => square % 8 >= 4,
+          };
+          (kingside, square)
+        }
+        _ => return Err(FenParseError::InvalidCastlingChar),
+      };
+      if rights.has(white, kingside) {
+        return Err(FenParseError::InvalidCastling);
+      }
+      rights.set(white, kingside, Some(rook));
+    }
+    if rights.restrict_to(board) != rights {
+      return Err(FenParseError::InvalidCastlingContext);
+    }
+    Ok(rights)
+  }
+
+  /// These rights without the ones `board` cannot back up: a right needs the king on its back
+  /// rank and a rook of the same colour on the right's square, on the right side of the king.
+  pub fn restrict_to(&self, board: &GameBoard) -> Self {
+    let mut rights = *self;
+    for white in [true, false] {
+      let king = board
+        .king_square(white)
+        .filter(|king| king / 8 == back_rank(white) / 8);
+      let rooks = board.rooks & board.pieces_of(white);
+      for kingside in [true, false] {
+        let backed = match (king, self.rook(white, kingside)) {
+          (Some(king), Some(rook)) => {
+            rooks.get_bit(rook).unwrap_or(false)
+              && rook / 8 == king / 8
+              && (rook > king) == kingside
+          }
+          _ => false,
+        };
+        if !backed {
+          rights.set(white, kingside, None);
+        }
+      }
+    }
+    rights
+  }
+
+  /// Write the castling field of a FEN string for `board`.
+  ///
+  /// Rights whose rook is the outermost on its side are written as `KQkq`; any other rook is
+  /// written as its file letter (X-FEN), so the output is plain FEN for standard positions.
+  pub fn write_fen<W: core::fmt::Write>(
+    &self,
+    board: &GameBoard,
+    out: &mut W,
+  ) -> core::fmt::Result {
+    if self.is_empty() {
+      return out.write_char('-');
+    }
+    for (white, kingside) in [(true, true), (true, false), (false, true), (false, false)] {
+      let Some(rook) = self.rook(white, kingside) else {
+        continue;
+      };
+      let c = if rook == outermost_rook(board, white, kingside) {
+        if kingside { 'k' } else { 'q' }
+      } else {
+        (b'a' + rook % 8) as char
+      };
+      out.write_char(if white { c.to_ascii_uppercase() } else { c })?;
+    }
+    Ok(())
+  }
+}
+
+/// The outermost rook of `white` on its back rank on the given side of its king, falling back to
+/// the standard corner when there is none.
+fn outermost_rook(board: &GameBoard, white: bool, kingside: bool) -> u8 {
+  let rank = back_rank(white);
+  let corner = if kingside { rank + 7 } else { rank };
+  let Some(king) = board.king_square(white).filter(|king| king / 8 == rank / 8) else {
+    return corner;
+  };
+
+  let back_rank_rooks = (board.rooks & board.pieces_of(white) & BitBoard::new(0xFF << rank)).raw();
+  let candidates = if kingside {
+    back_rank_rooks & !((2u64 << king) - 1)
+  } else {
+    back_rank_rooks & ((1u64 << king) - 1)
+  };
+  match (candidates, kingside) {
+    (0, _) => corner,
+    (rooks, true) => 63 - rooks.leading_zeros() as u8,
+    (rooks, false) => rooks.trailing_zeros() as u8,
+  }
+}
+
+/// Where the king and rook end up after castling: the king on the g- or c-file and the rook next
+/// to it on the f- or d-file, whatever squares they started from.
+pub const fn castling_destinations(white: bool, kingside: bool) -> (u8, u8) {
+  let rank = back_rank(white);
+  if kingside {
+    (rank + 6, rank + 5)
+  } else {
+    (rank + 2, rank + 3)
+  }
+}
+
+/// Squares from `a` to `b` inclusive, for two squares on the same rank.
+const fn rank_span(a: u8, b: u8) -> u64 {
+  let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+  (u64::MAX >> (63 - hi)) & (u64::MAX << lo)
+}
+
+/// Squares that must be empty, apart from the king and rook themselves, for a king on `king` to
+/// castle with the rook on `rook`.
+pub const fn castling_clearance(white: bool, king: u8, rook: u8) -> u64 {
+  let (king_to, rook_to) = castling_destinations(white, rook > king);
+  (rank_span(king, king_to) | rank_span(rook, rook_to)) & !(1u64 << king) & !(1u64 << rook)
+}
+
+/// Squares the king stands on or crosses while castling; none of them may be attacked.
+pub const fn castling_king_path(white: bool, king: u8, rook: u8) -> u64 {
+  let (king_to, _) = castling_destinations(white, rook > king);
+  rank_span(king, king_to)
+}
+
+/// Whether `square` of a castling path is attacked once the king and the rook have left their
+/// squares: in Chess960 the rook can shield the square its king lands on from an attacker behind
+/// it.
+pub(crate) fn castling_square_attacked(board: &GameBoard, square: u8, king: u8, rook: u8) -> bool {
+  let movers = BitBoard::new((1 << king) | (1 << rook));
+  is_square_attacked_excluding(board, square, movers)
+}
+
+/// Whether one side can castle on one wing right now, and if not, what stops it.
+///
+/// The checks run in this order, and the first that fails is reported: the right, the squares
+/// between king and rook, the king being in check, and the squares the king crosses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CastleStatus {
+  /// The right is gone, or its king or rook is no longer in place.
+  NoRights,
+  /// A piece stands between king and rook or on their destinations; `blocker` is the one
+  /// nearest the king.
+  Blocked { blocker: u8 },
+  /// The king is in check.
+  InCheck,
+  /// The king would cross or land on `square`, which an enemy piece attacks.
+  ThroughCheck { square: u8 },
+  /// Castling is legal as soon as it is this side's turn.
+  Available,
+}
+
+impl CastleStatus {
+  pub const fn is_available(&self) -> bool {
+    matches!(self, CastleStatus::Available)
+  }
+
+  /// Whether the right itself remains, even if castling is not possible right now. Evaluation
+  /// terms rewarding a king that can still castle usually want this rather than
+  /// [`is_available`](Self::is_available).
+  pub const fn has_right(&self) -> bool {
+    !matches!(self, CastleStatus::NoRights)
+  }
+}
+
+impl GameBoard {
+  /// Whether the side given by `white` can castle on the given wing, combining its rights with
+  /// the occupancy of the squares in between and the enemy attacks on the king's path.
+  ///
+  /// Works for either side, whoever is to move, so a GUI can grey out the castling buttons of
+  /// both players. Only the position is considered: a side that is not to move may still find
+  /// its path attacked differently once the opponent has replied.
+  pub fn can_castle(&self, white: bool, kingside: bool) -> CastleStatus {
+    let Some(rook) = self.castling.rook(white, kingside) else {
+      return CastleStatus::NoRights;
+    };
+    let Some(king) = self.king_square(white) else {
+      return CastleStatus::NoRights;
+    };
+    let own_rook = (self.rooks & self.pieces_of(white))
+      .get_bit(rook)
+      .unwrap_or(false);
+    if !own_rook || king / 8 != back_rank(white) / 8 || rook / 8 != king / 8 {
+      return CastleStatus::NoRights;
+    }
+
+    // Of several squares, the one the king meets first on its way to the rook
+    let nearest = |squares: u64| {
+      if king < rook {
+        squares.trailing_zeros() as u8
+      } else {
+        63 - squares.leading_zeros() as u8
+      }
+    };
+    let blockers = self.combined().raw() & castling_clearance(white, king, rook);
+    if blockers != 0 {
+      return CastleStatus::Blocked {
+        blocker: nearest(blockers),
+      };
+    }
+
+    // Attacks are those of the side not to move, so look from `white`'s turn
+    let mut turn = *self;
+    turn.playing = white;
+    if is_square_attacked(&turn, king) {
+      return CastleStatus::InCheck;
+    }
+    let mut path = castling_king_path(white, king, rook) & !(1u64 << king);
+    while path != 0 {
+      let square = nearest(path);
+      if castling_square_attacked(&turn, square, king, rook) {
+        return CastleStatus::ThroughCheck { square };
+      }
+      path &= !(1u64 << square);
+    }
+    CastleStatus::Available
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::gamedata::GameData};
+
+  fn board(placement: &str) -> GameBoard {
+    GameData::from_fen(&format!("{placement} w - - 0 1"))
+      .unwrap()
+      .board
+  }
+
+  #[test]
+  fn test_bits_roundtrip() {
+    for bits in 0..16 {
+      assert_eq!(CastlingRights::from_bits(bits).bits(), bits);
+    }
+    assert_eq!(CastlingRights::STANDARD.rook(true, true), Some(H1));
+    assert_eq!(CastlingRights::STANDARD.rook(true, false), Some(A1));
+    assert_eq!(CastlingRights::STANDARD.rook(false, true), Some(H8));
+    assert_eq!(CastlingRights::STANDARD.rook(false, false), Some(A8));
+  }
+
+  #[test]
+  fn test_classic_letters_find_rooks() {
+    // Chess960 start: rooks on b and g files
+    let board = board("nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN");
+    let rights = CastlingRights::from_fen("KQkq", &board).unwrap();
+    assert_eq!(rights.rook(true, true), Some(G1));
+    assert_eq!(rights.rook(true, false), Some(B1));
+    assert_eq!(rights.rook(false, true), Some(G8));
+    assert_eq!(rights.rook(false, false), Some(B8));
+  }
+
+  #[test]
+  fn test_shredder_letters() {
+    let board = board("1r2k1r1/8/8/8/8/8/8/1RR1K1R1");
+    let rights = CastlingRights::from_fen("CGbg", &board).unwrap();
+    assert_eq!(rights.rook(true, false), Some(C1));
+    assert_eq!(rights.rook(true, true), Some(G1));
+    assert_eq!(rights.rook(false, false), Some(B8));
+    assert_eq!(rights.rook(false, true), Some(G8));
+
+    // The inner rook needs its file; the others are outermost
+    let mut fen = String::new();
+    rights.write_fen(&board, &mut fen).unwrap();
+    assert_eq!(fen, "KCkq");
+  }
+
+  #[test]
+  fn test_invalid_fields() {
+    let board = board("r3k2r/8/8/8/8/8/8/R3K2R");
+    assert_eq!(
+      CastlingRights::from_fen("KX", &board),
+      Err(FenParseError::InvalidCastlingChar)
+    );
+    assert_eq!(
+      CastlingRights::from_fen("KH", &board),
+      Err(FenParseError::InvalidCastling)
+    );
+  }
+
+  #[test]
+  fn test_rights_need_king_and_rook() {
+    for (fen, field) in [
+      // Rook gone from the back rank
+      ("r3k2r/8/8/8/8/8/7R/R3K3 w KQkq - 0 1", "KQkq"),
+      // King has left the back rank
+      ("r3k2r/8/8/8/8/8/4K3/R6R w KQkq - 0 1", "KQkq"),
+      // Black rook on a white castling square
+      ("4k3/8/8/8/8/8/8/r3K2R w KQ - 0 1", "KQ"),
+      // File letter naming an empty square
+      ("4k3/8/8/8/8/8/8/R3K2R w KC - 0 1", "KC"),
+      // No rooks at all
+      ("4k3/8/8/8/8/8/8/4K3 w Kq - 0 1", "Kq"),
+    ] {
+      assert_eq!(
+        GameData::from_fen(fen).err(),
+        Some(FenParseError::InvalidCastlingContext),
+        "{fen}"
+      );
+      let board = GameData::from_fen(&fen.replace(field, "-")).unwrap().board;
+      assert_eq!(
+        CastlingRights::from_fen(field, &board),
+        Err(FenParseError::InvalidCastlingContext)
+      );
+    }
+
+    let board = board("r3k2r/8/8/8/8/8/7R/R3K3");
+    let rights = CastlingRights::STANDARD.restrict_to(&board);
+    assert_eq!(rights.bits(), 0b1110);
+    assert_eq!(rights.restrict_to(&board), rights);
+    // The remaining rights parse as they are
+    assert_eq!(CastlingRights::from_fen("Qkq", &board), Ok(rights));
+  }
+
+  #[test]
+  fn test_castling_squares() {
+    assert_eq!(castling_clearance(true, E1, H1), (1 << F1) | (1 << G1));
+    assert_eq!(
+      castling_clearance(true, E1, A1),
+      (1 << B1) | (1 << C1) | (1 << D1)
+    );
+    assert_eq!(
+      castling_king_path(true, E1, A1),
+      (1 << C1) | (1 << D1) | (1 << E1)
+    );
+    assert_eq!(
+      castling_king_path(false, E8, H8),
+      (1 << E8) | (1 << F8) | (1 << G8)
+    );
+
+    // Chess960: king on b1 castles queenside with the rook on a1 to c1/d1
+    assert_eq!(castling_clearance(true, B1, A1), (1 << C1) | (1 << D1));
+    // King already on g1 with the rook on h1: only f1 has to be empty
+    assert_eq!(castling_clearance(true, G1, H1), 1 << F1);
+    assert_eq!(castling_king_path(true, G1, H1), 1 << G1);
+  }
+
+  #[test]
+  fn test_clearing() {
+    let mut rights = CastlingRights::STANDARD;
+    rights.clear_square(H1);
+    assert_eq!(rights.bits(), 0b1110);
+    rights.clear_side(false);
+    assert_eq!(rights.bits(), 0b0010);
+    rights.clear_square(E1);
+    assert_eq!(rights.bits(), 0b0010);
+  }
+
+  #[test]
+  fn test_can_castle() {
+    let status = |fen: &str, white: bool, kingside: bool| {
+      GameData::from_fen(fen)
+        .unwrap()
+        .board
+        .can_castle(white, kingside)
+    };
+    let open = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    for (white, kingside) in [(true, true), (true, false), (false, true), (false, false)] {
+      assert_eq!(status(open, white, kingside), CastleStatus::Available);
+    }
+    assert_eq!(
+      status("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1", true, false),
+      CastleStatus::NoRights
+    );
+    assert_eq!(
+      status(crate::constants::START_FEN, true, true),
+      CastleStatus::Blocked { blocker: F1 }
+    );
+    assert_eq!(
+      status("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1", true, false),
+      CastleStatus::Blocked { blocker: B1 }
+    );
+    // Black rook on e8 checks the white king
+    assert_eq!(
+      status("4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1", true, true),
+      CastleStatus::InCheck
+    );
+    // Bishop on a6 covers f1, rook on d8 covers d1
+    let fen = "3rk3/8/b7/8/8/8/8/R3K2R w KQ - 0 1";
+    assert_eq!(
+      status(fen, true, true),
+      CastleStatus::ThroughCheck { square: F1 }
+    );
+    assert_eq!(
+      status(fen, true, false),
+      CastleStatus::ThroughCheck { square: D1 }
+    );
+    // b1 under attack does not matter, the king never crosses it; black, not to move, is judged
+    // the same way
+    assert_eq!(
+      status("r3k2r/8/8/8/8/8/8/4K2R w Kq - 0 1", false, false),
+      CastleStatus::Available
+    );
+    assert!(status("1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1", true, false).is_available());
+    assert!(status("4k3/8/8/8/8/8/8/RN2K3 w Q - 0 1", true, false).has_right());
+
+    // Chess960: the rook on b1 shields the path from the rook on a1 until it castles itself
+    let shielded = "4k3/8/8/8/8/8/8/rR4K1 w B - 0 1";
+    assert_eq!(
+      status(shielded, true, false),
+      CastleStatus::ThroughCheck { square: F1 }
+    );
+    let board = GameData::from_fen(shielded).unwrap().board;
+    let (moves, count) = crate::movegen::generate_moves(&board);
+    assert!(
+      moves[..count]
+        .iter()
+        .all(|m| m.from_square() != G1 || m.to_square() != B1)
+    );
+  }
+}
@@ -0,0 +1,497 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Chess clocks, in the shape UCI's `go` command describes them.
+//!
+//! A [`Clock`] holds each side's remaining time and increment plus the moves left until the next
+//! time control. The UCI parser fills one from `wtime`/`btime`/`winc`/`binc`/`movestogo`, the
+//! engine's time manager budgets from it, and PGN writers record it as `[%clk]` comments via
+//! [`write_clk`] (read back with [`parse_clk`]), so all three agree on units (milliseconds) and
+//! meaning.
+//!
+//! A [`ClockState`] runs a whole [`TimeControl`] instead: Fischer increments, Bronstein delays
+//! and classical controls of several periods, flagging a side that overstays its time. Match
+//! runners use it to adjudicate time losses, and it hands engines the [`Clock`] they would be
+//! sent over UCI.
+
+use core::fmt::{self, Write};
+
+/// Remaining time and increments for both sides, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clock {
+  /// White's remaining time, or `None` when white is not on a clock.
+  pub white_time: Option<u64>,
+  /// Black's remaining time, or `None` when black is not on a clock.
+  pub black_time: Option<u64>,
+  /// Time added to white's clock after each of white's moves.
+  pub white_increment: u64,
+  /// Time added to black's clock after each of black's moves.
+  pub black_increment: u64,
+  /// Moves until the next time control adds time, or `None` for sudden death.
+  pub moves_to_go: Option<u32>,
+}
+
+impl Clock {
+  /// The remaining time of the side given by `white`.
+  pub fn time(&self, white: bool) -> Option<u64> {
+    if white {
+      self.white_time
+    } else {
+      self.black_time
+    }
+  }
+
+  /// The increment of the side given by `white`.
+  pub fn increment(&self, white: bool) -> u64 {
+    if white {
+      self.white_increment
+    } else {
+      self.black_increment
+    }
+  }
+
+  /// Whether either side is on a clock.
+  pub fn is_timed(&self) -> bool {
+    self.white_time.is_some() || self.black_time.is_some()
+  }
+
+  /// Charge `elapsed` milliseconds to the side given by `white` and add its increment, as after
+  /// that side moves. A black move also counts down `moves_to_go`. Returns the mover's remaining
+  /// time, which is what a PGN `[%clk]` comment records.
+  pub fn record_move(&mut self, white: bool, elapsed: u64) -> Option<u64> {
+    let increment = self.increment(white);
+    let time = if white {
+      &mut self.white_time
+    } else {
+      &mut self.black_time
+    };
+    *time = time.map(|time| time.saturating_sub(elapsed) + increment);
+    if !white {
+      self.moves_to_go = self.moves_to_go.map(|moves| moves.saturating_sub(1));
+    }
+    *time
+  }
+}
+
+/// What a side gets back for each move it makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeBonus {
+  /// Nothing: the time of the period is all there is.
+  #[default]
+  None,
+  /// A Fischer increment, added after every move.
+  Increment(u64),
+  /// A Bronstein delay: the time a move took is given back, up to this much.
+  Delay(u64),
+}
+
+/// One period of a time control, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimePeriod {
+  /// Moves to make within the period, or `None` when it lasts the rest of the game.
+  pub moves: Option<u32>,
+  /// Time added to the clock when the period starts.
+  pub time: u64,
+  /// What each move of the period gets back.
+  pub bonus: TimeBonus,
+}
+
+/// The most periods a [`TimeControl`] holds.
+pub const MAX_PERIODS: usize = 4;
+
+/// A sequence of up to [`MAX_PERIODS`] periods. When the last one has a move count it repeats,
+/// so `40/5400` is 90 minutes for every 40 moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeControl {
+  periods: [TimePeriod; MAX_PERIODS],
+  len: usize,
+}
+
+impl TimeControl {
+  /// A single period lasting the whole game.
+  pub const fn sudden_death(time: u64, bonus: TimeBonus) -> Self {
+    let period = TimePeriod {
+      moves: None,
+      time,
+      bonus,
+    };
+    Self {
+      periods: [period; MAX_PERIODS],
+      len: 1,
+    }
+  }
+
+  /// A control made of `periods`, or `None` when there are none, more than [`MAX_PERIODS`], or
+  /// one of zero moves, which could never end.
+  pub fn new(periods: &[TimePeriod]) -> Option<Self> {
+    let first = *periods.first()?;
+    if periods.len() > MAX_PERIODS || periods.iter().any(|period| period.moves == Some(0)) {
+      return None;
+    }
+    let mut control = Self::sudden_death(first.time, first.bonus);
+    control.periods[..periods.len()].copy_from_slice(periods);
+    control.len = periods.len();
+    Some(control)
+  }
+
+  /// Read a PGN `TimeControl` tag such as `300+2` or `40/5400+30:1800+30`, where times and
+  /// increments are in seconds. Delays have no PGN form, and `?`, `-` and `*` give `None`, as
+  /// does anything malformed.
+  pub fn parse(text: &str) -> Option<Self> {
+    let mut periods = [TimePeriod {
+      moves: None,
+      time: 0,
+      bonus: TimeBonus::None,
+    }; MAX_PERIODS];
+    let mut len = 0;
+    for field in text.split(':') {
+      let (moves, rest) = match field.split_once('/') {
+        Some((moves, rest)) => (Some(parse_number(moves)?), rest),
+        None => (None, field),
+      };
+      let (time, bonus) = match rest.split_once('+') {
+        Some((time, increment)) => (
+          time,
+          TimeBonus::Increment(parse_number(increment)?.checked_mul(1000)?),
+        ),
+        None => (rest, TimeBonus::None),
+      };
+      *periods.get_mut(len)? = TimePeriod {
+        moves: moves.map(u32::try_from).transpose().ok()?,
+        time: parse_number(time)?.checked_mul(1000)?,
+        bonus,
+      };
+      len += 1;
+    }
+    Self::new(&periods[..len])
+  }
+
+  /// The periods in the order they are played.
+  pub fn periods(&self) -> &[TimePeriod] {
+    &self.periods[..self.len]
+  }
+
+  /// The period played after `index` periods are done, repeating the last one.
+  fn period(&self, index: usize) -> &TimePeriod {
+    &self.periods[index.min(self.len - 1)]
+  }
+}
+
+/// A plain decimal number with no sign.
+fn parse_number(text: &str) -> Option<u64> {
+  if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  text.parse().ok()
+}
+
+/// Both sides' clocks running through a [`TimeControl`], move by move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClockState {
+  control: TimeControl,
+  /// Remaining time, indexed by `usize::from(!white)`.
+  remaining: [u64; 2],
+  /// Periods completed by each side.
+  period: [usize; 2],
+  /// Moves each side has made in its current period.
+  period_moves: [u32; 2],
+  flagged: Option<bool>,
+}
+
+impl ClockState {
+  /// Both clocks at the start of `control`.
+  pub fn new(control: TimeControl) -> Self {
+    let time = control.period(0).time;
+    Self {
+      control,
+      remaining: [time; 2],
+      period: [0; 2],
+      period_moves: [0; 2],
+      flagged: None,
+    }
+  }
+
+  /// The time control being played.
+  pub fn control(&self) -> &TimeControl {
+    &self.control
+  }
+
+  /// The remaining time of the side given by `white`.
+  pub fn remaining(&self, white: bool) -> u64 {
+    self.remaining[usize::from(!white)]
+  }
+
+  /// Moves the side given by `white` must make before its next period starts, or `None` in a
+  /// period lasting the rest of the game.
+  pub fn moves_to_go(&self, white: bool) -> Option<u32> {
+    let side = usize::from(!white);
+    let moves = self.control.period(self.period[side]).moves?;
+    Some(moves - self.period_moves[side])
+  }
+
+  /// The side that ran out of time (`true` for white), if either has.
+  pub fn flagged(&self) -> Option<bool> {
+    self.flagged
+  }
+
+  /// Charge `elapsed` milliseconds to the side given by `white`, as after that side moves, then
+  /// give back its increment or delay and start its next period when this move completes one.
+  /// Returns the mover's remaining time, or `None` once a side has flagged: overstaying the
+  /// clock flags the mover, and no later move is recorded.
+  pub fn record_move(&mut self, white: bool, elapsed: u64) -> Option<u64> {
+    if self.flagged.is_some() {
+      return None;
+    }
+    let side = usize::from(!white);
+    let Some(mut left) = self.remaining[side].checked_sub(elapsed) else {
+      self.remaining[side] = 0;
+      self.flagged = Some(white);
+      return None;
+    };
+    let period = *self.control.period(self.period[side]);
+    left += match period.bonus {
+      TimeBonus::None => 0,
+      TimeBonus::Increment(increment) => increment,
+      TimeBonus::Delay(delay) => elapsed.min(delay),
+    };
+    self.period_moves[side] += 1;
+    if period.moves == Some(self.period_moves[side]) {
+      self.period[side] += 1;
+      self.period_moves[side] = 0;
+      left += self.control.period(self.period[side]).time;
+    }
+    self.remaining[side] = left;
+    Some(left)
+  }
+
+  /// The clock UCI would send the side given by `white` to move. A delay is not an increment,
+  /// so it is left out, leaving the engine to budget on the safe side.
+  pub fn to_clock(&self, white: bool) -> Clock {
+    let increment = |white: bool| {
+      let side = usize::from(!white);
+      match self.control.period(self.period[side]).bonus {
+        TimeBonus::Increment(increment) => increment,
+        TimeBonus::None | TimeBonus::Delay(_) => 0,
+      }
+    };
+    Clock {
+      white_time: Some(self.remaining(true)),
+      black_time: Some(self.remaining(false)),
+      white_increment: increment(true),
+      black_increment: increment(false),
+      moves_to_go: self.moves_to_go(white),
+    }
+  }
+}
+
+/// Write `millis` as the `H:MM:SS` time of a PGN `[%clk]` comment, adding tenths of a second
+/// when there are any.
+pub fn write_clk<W: Write>(millis: u64, out: &mut W) -> fmt::Result {
+  let seconds = millis / 1000;
+  write!(
+    out,
+    "{}:{:02}:{:02}",
+    seconds / 3600,
+    seconds / 60 % 60,
+    seconds % 60
+  )?;
+  let tenths = millis % 1000 / 100;
+  if tenths > 0 {
+    write!(out, ".{tenths}")?;
+  }
+  Ok(())
+}
+
+/// Read the `H:MM:SS` time of a PGN `[%clk]` comment as milliseconds. Fractions of a second
+/// are kept to the millisecond, and the hours may be left out. Returns `None` for anything else.
+pub fn parse_clk(text: &str) -> Option<u64> {
+  let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+  let mut seconds = 0u64;
+  for (index, part) in whole.split(':').enumerate() {
+    let digits_only = !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if index > 2 || !digits_only {
+      return None;
+    }
+    seconds = seconds.checked_mul(60)?.checked_add(part.parse().ok()?)?;
+  }
+  if !whole.contains(':') || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  let millis = fraction
+    .bytes()
+    .chain(core::iter::repeat(b'0'))
+    .take(3)
+    .fold(0, |acc, digit| acc * 10 + u64::from(digit - b'0'));
+  seconds.checked_mul(1000)?.checked_add(millis)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn clk(millis: u64) -> String {
+    let mut text = String::new();
+    write_clk(millis, &mut text).unwrap();
+    text
+  }
+
+  #[test]
+  fn test_write_clk() {
+    assert_eq!(clk(0), "0:00:00");
+    assert_eq!(clk(90_000), "0:01:30");
+    assert_eq!(clk(3_725_000), "1:02:05");
+    assert_eq!(clk(5_450), "0:00:05.4");
+  }
+
+  #[test]
+  fn test_parse_clk() {
+    assert_eq!(parse_clk("0:01:30"), Some(90_000));
+    assert_eq!(parse_clk("1:02:05"), Some(3_725_000));
+    assert_eq!(parse_clk("0:00:05.4"), Some(5_400));
+    assert_eq!(parse_clk("0:00:05.123"), Some(5_123));
+    assert_eq!(parse_clk("01:30"), Some(90_000));
+    for millis in [0, 90_000, 3_725_000, 5_400] {
+      assert_eq!(parse_clk(&clk(millis)), Some(millis));
+    }
+
+    for bad in [
+      "",
+      "90",
+      "1:2:3:4",
+      "0:-1:30",
+      "0:01:3x",
+      "0::30",
+      "0:01:30.x",
+    ] {
+      assert_eq!(parse_clk(bad), None, "{bad}");
+    }
+  }
+
+  #[test]
+  fn test_record_move() {
+    let mut clock = Clock {
+      white_time: Some(60_000),
+      black_time: Some(30_000),
+      white_increment: 2_000,
+      black_increment: 0,
+      moves_to_go: Some(2),
+    };
+    assert_eq!(clock.record_move(true, 5_000), Some(57_000));
+    assert_eq!(clock.moves_to_go, Some(2));
+    assert_eq!(clock.record_move(false, 40_000), Some(0));
+    assert_eq!(clock.moves_to_go, Some(1));
+    assert_eq!(clock.time(true), Some(57_000));
+
+    let mut untimed = Clock::default();
+    assert!(!untimed.is_timed());
+    assert_eq!(untimed.record_move(true, 1_000), None);
+  }
+
+  #[test]
+  fn test_parse_time_control() {
+    let blitz = TimeControl::parse("300+2").unwrap();
+    assert_eq!(
+      blitz.periods(),
+      [TimePeriod {
+        moves: None,
+        time: 300_000,
+        bonus: TimeBonus::Increment(2_000),
+      }]
+    );
+    let classical = TimeControl::parse("40/5400+30:1800+30").unwrap();
+    assert_eq!(classical.periods().len(), 2);
+    assert_eq!(classical.periods()[0].moves, Some(40));
+    assert_eq!(classical.periods()[1].time, 1_800_000);
+    assert_eq!(
+      TimeControl::parse("40/7200").unwrap().periods()[0].bonus,
+      TimeBonus::None
+    );
+
+    for bad in [
+      "",
+      "?",
+      "-",
+      "*",
+      "300+",
+      "+2",
+      "40/",
+      "1/1:2/2:3/3:4/4:5",
+      "-5",
+      "0/60",
+      "40/5400:0/60",
+    ] {
+      assert_eq!(TimeControl::parse(bad), None, "{bad}");
+    }
+    let endless = TimePeriod {
+      moves: Some(0),
+      time: 60_000,
+      bonus: TimeBonus::None,
+    };
+    assert_eq!(TimeControl::new(&[endless]), None);
+  }
+
+  #[test]
+  fn test_fischer_and_bronstein() {
+    let mut fischer = ClockState::new(TimeControl::sudden_death(
+      10_000,
+      TimeBonus::Increment(1_000),
+    ));
+    assert_eq!(fischer.record_move(true, 500), Some(10_500));
+    assert_eq!(fischer.record_move(false, 4_000), Some(7_000));
+    assert_eq!(fischer.moves_to_go(true), None);
+    let clock = fischer.to_clock(true);
+    assert_eq!(clock.time(true), Some(10_500));
+    assert_eq!(clock.increment(false), 1_000);
+
+    let mut bronstein = ClockState::new(TimeControl::sudden_death(10_000, TimeBonus::Delay(2_000)));
+    assert_eq!(bronstein.record_move(true, 1_500), Some(10_000));
+    assert_eq!(bronstein.record_move(true, 5_000), Some(7_000));
+    assert_eq!(bronstein.to_clock(true).increment(true), 0);
+  }
+
+  #[test]
+  fn test_periods_and_flags() {
+    let control = TimeControl::parse("2/60:30").unwrap();
+    let mut state = ClockState::new(control);
+    assert_eq!(state.moves_to_go(true), Some(2));
+    assert_eq!(state.record_move(true, 20_000), Some(40_000));
+    assert_eq!(state.moves_to_go(true), Some(1));
+    assert_eq!(state.to_clock(true).moves_to_go, Some(1));
+    assert_eq!(state.record_move(true, 20_000), Some(50_000));
+    assert_eq!(state.moves_to_go(true), None);
+    assert_eq!(state.remaining(false), 60_000);
+
+    let mut repeating = ClockState::new(TimeControl::parse("1/10").unwrap());
+    for expected in [11_000, 12_000, 13_000] {
+      assert_eq!(repeating.record_move(false, 9_000), Some(expected));
+      assert_eq!(repeating.moves_to_go(false), Some(1));
+    }
+
+    assert_eq!(state.flagged(), None);
+    assert_eq!(state.record_move(false, 60_000), Some(0));
+    assert_eq!(state.record_move(false, 1), None);
+    assert_eq!(state.flagged(), Some(false));
+    assert_eq!(state.remaining(false), 0);
+    assert_eq!(state.record_move(true, 0), None);
+  }
+}
@@ -0,0 +1,270 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Boards read from text diagrams, for pasting a position from a chat, forum or terminal
+//! without converting it to FEN by hand.
+//!
+//! [`GameBoard::from_diagram`] takes eight rows of eight squares, rank 8 at the top: the layout
+//! of the `position!` macro and of this crate's own [`Display`](core::fmt::Display) output.
+//! Squares are FEN piece letters or the Unicode chess glyphs (`♔` to `♟`), and an empty square
+//! is any of `.`, `·`, `_`, `⭘`, `□` or `■`. Whatever else a pasted board tends to carry is
+//! skipped: spaces between squares, rank numbers, a file legend line (`a b c d e f g h`), quotes,
+//! and borders drawn with `|`, `+`, `-`, `=` or box-drawing characters.
+//!
+//! A diagram shows only the pieces, so the side to move and the castling rights come from
+//! [`DiagramOptions`], and there is never an en passant square.
+
+use crate::{
+  errors::DiagramParseError,
+  model::{
+    castling::CastlingRights,
+    gameboard::{GameBoard, PieceType},
+  },
+};
+
+/// What a diagram does not show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiagramOptions {
+  /// White is to move.
+  pub white_to_move: bool,
+  /// The diagram is drawn from black's side: rank 1 at the top and the h-file on the left.
+  pub flipped: bool,
+  /// Grant every standard castling right the kings and rooks still allow, as `position!` does
+  /// without a castling field. Without it, nobody may castle.
+  pub castling: bool,
+}
+
+impl Default for DiagramOptions {
+  fn default() -> Self {
+    Self {
+      white_to_move: true,
+      flipped: false,
+      castling: true,
+    }
+  }
+}
+
+impl GameBoard {
+  /// Reads a board from a text diagram; see the [module docs](crate::model::diagram) for what
+  /// counts as a square. Lines without squares, such as borders and the file legend, are
+  /// skipped, so exactly eight lines must be left with exactly eight squares each.
+  pub fn from_diagram(diagram: &str, options: DiagramOptions) -> Result<Self, DiagramParseError> {
+    let mut board = GameBoard::new();
+    board.playing = options.white_to_move;
+    let mut row: u8 = 0;
+    for line in diagram.lines() {
+      let mut marks = line.chars().filter(|&c| !is_decoration(c));
+      if is_file_legend(marks.clone()) {
+        continue;
+      }
+      let mut column: usize = 0;
+      for mark in &mut marks {
+        let square = parse_square(mark).ok_or(DiagramParseError::InvalidChar(mark))?;
+        if let Some((piece, white)) = square
+          && row < 8
+          && column < 8
+        {
+          let (rank, file) = if options.flipped {
+            (row, 7 - column as u8)
+          } else {
+            (7 - row, column as u8)
+          };
+          board.set_square(rank * 8 + file, piece, white);
+        }
+        column += 1;
+      }
+      match column {
+        0 => continue,
+        8 => row += 1,
+        squares => {
+          return Err(DiagramParseError::InvalidRankLength {
+            row: row as usize,
+            squares,
+          });
+        }
+      }
+    }
+    if row != 8 {
+      return Err(DiagramParseError::InvalidRankCount(row as usize));
+    }
+
+    if options.castling {
+      board.castling = CastlingRights::STANDARD.restrict_to(&board);
+    }
+    Ok(board)
+  }
+}
+
+/// Characters around the squares: spacing, rank numbers, quotes and borders.
+fn is_decoration(c: char) -> bool {
+  c.is_whitespace()
+    || matches!(c, '1'..='8' | '|' | '+' | '-' | '=' | '"')
+    || ('\u{2500}'..='\u{257f}').contains(&c)
+}
+
+/// Whether a line is the `a b c d e f g h` legend under or over a board, either way round.
+fn is_file_legend(marks: impl Iterator<Item = char> + Clone) -> bool {
+  let lower = marks.map(|c| c.to_ascii_lowercase());
+  lower.clone().eq("abcdefgh".chars()) || lower.eq("hgfedcba".chars())
+}
+
+/// The contents of the square shown as `mark`: `Some(None)` for an empty square, `None` if
+/// `mark` is not a square at all.
+fn parse_square(mark: char) -> Option<Option<(PieceType, bool)>> {
+  let piece = match mark {
+    '.' | '·' | '_' | '⭘' | '□' | '■' => return Some(None),
+    '♔' => (PieceType::King, true),
+    '♕' => (PieceType::Queen, true),
+    '♖' => (PieceType::Rook, true),
+    '♗' => (PieceType::Bishop, true),
+    '♘' => (PieceType::Knight, true),
+    '♙' => (PieceType::Pawn, true),
+    '♚' => (PieceType::King, false),
+    '♛' => (PieceType::Queen, false),
+    '♜' => (PieceType::Rook, false),
+    '♝' => (PieceType::Bishop, false),
+    '♞' => (PieceType::Knight, false),
+    '♟' => (PieceType::Pawn, false),
+    letter => {
+      let piece = match letter.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+      };
+      (piece, letter.is_ascii_uppercase())
+    }
+  };
+  Some(Some(piece))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::gamedata::GameData;
+
+  fn fen_board(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  #[test]
+  fn test_layouts() {
+    let options = DiagramOptions::default();
+
+    // The position! layout
+    let plain = "rnbqkbnr\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nRNBQKBNR";
+    assert_eq!(
+      GameBoard::from_diagram(plain, options),
+      Ok(GameBoard::START_POS)
+    );
+
+    // Display output, with rank numbers and the file legend, round trips
+    let board = fen_board("r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4");
+    let shown = "\
+8 r . b q k b . r
+7 p p p p . p p p
+6 . . n . . n . .
+5 . . . . p . . .
+4 . . . . P . . .
+3 . . N . . N . .
+2 P P P P . P P P
+1 R . B Q K B . R
+  a b c d e f g h";
+    #[cfg(feature = "alloc")]
+    assert_eq!(alloc::format!("{board}"), shown);
+    assert_eq!(GameBoard::from_diagram(shown, options), Ok(board));
+
+    // Unicode glyphs inside a box-drawn border, as terminals print them
+    let boxed = "\
+┌────────┐
+│♜♞♝♛♚♝♞♜│
+│♟♟♟♟⭘♟♟♟│
+│⭘⭘⭘⭘⭘⭘⭘⭘│
+│⭘⭘⭘⭘♟⭘⭘⭘│
+│⭘⭘⭘⭘♙⭘⭘⭘│
+│⭘⭘⭘⭘⭘⭘⭘⭘│
+│♙♙♙♙⭘♙♙♙│
+│♖♘♗♕♔♗♘♖│
+└────────┘";
+    assert_eq!(
+      GameBoard::from_diagram(boxed, options),
+      Ok(fen_board(
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+      ))
+    );
+  }
+
+  #[test]
+  fn test_options() {
+    // From black's side, black to move, with the rook on h8 gone so black keeps only the
+    // queenside right
+    let diagram = "\
+R . . K . . . R
+P P P . . . . .
+. . . . . . . .
+. . . . . . . .
+. . . . . . . .
+. . . . . . . .
+. . . . . p p p
+. . . k . . . r
+h g f e d c b a";
+    let options = DiagramOptions {
+      white_to_move: false,
+      flipped: true,
+      castling: true,
+    };
+    assert_eq!(
+      GameBoard::from_diagram(diagram, options),
+      Ok(fen_board("r3k3/ppp5/8/8/8/8/5PPP/R3K2R b KQq - 0 1"))
+    );
+
+    let none = DiagramOptions {
+      castling: false,
+      ..options
+    };
+    let board = GameBoard::from_diagram(diagram, none).unwrap();
+    assert_eq!(board.castling, CastlingRights::NONE);
+  }
+
+  #[test]
+  fn test_errors() {
+    let options = DiagramOptions::default();
+    assert_eq!(
+      GameBoard::from_diagram("rnbqkbnr\npppppppp", options),
+      Err(DiagramParseError::InvalidRankCount(2))
+    );
+    let short = "rnbqkbnr\npppppppp\n........\n.......\n........\n........\nPPPPPPPP\nRNBQKBNR";
+    assert_eq!(
+      GameBoard::from_diagram(short, options),
+      Err(DiagramParseError::InvalidRankLength { row: 3, squares: 7 })
+    );
+    let extra =
+      "rnbqkbnr\npppppppp\n........\n........\n........\n........\nPPPPPPPP\nRNBQKBNR\n........";
+    assert_eq!(
+      GameBoard::from_diagram(extra, options),
+      Err(DiagramParseError::InvalidRankCount(9))
+    );
+    assert_eq!(
+      GameBoard::from_diagram("rnbqkbnr\nppppxppp", options),
+      Err(DiagramParseError::InvalidChar('x'))
+    );
+  }
+}
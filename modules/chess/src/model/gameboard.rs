@@ -16,14 +16,28 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
+use core::{
+  fmt::{self, Write},
+  hash::{Hash, Hasher},
+};
+
 use crate::{
-  constants::{A1, A8, D1, D8, F1, F8, H1, H8},
-  legal::checker::LegalChecker,
-  model::piecemove::{PieceMove, PromotionType},
+  constants::{DARK_SQUARES, LIGHT_SQUARES},
+  errors::IllegalMoveReason,
+  legal::{
+    attack::{attacks_from, attacks_of, between, xray_bishop_attacks, xray_rook_attacks},
+    checker::LegalChecker,
+  },
+  model::{
+    castling::{CastlingRights, castling_destinations},
+    material::MaterialSig,
+    piecemove::{PieceMove, PromotionType},
+  },
+  movegen::{LegalMoves, king::generate_king_moves},
+  zobrist,
 };
 
 use super::bitboard::BitBoard;
-#[cfg(feature = "precomputed_rays")]
 use super::rays::BETWEEN;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -36,7 +50,61 @@ pub enum PieceType {
   King,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// The piece on each square and whether it is white, kept by [`GameBoard`] with the `mailbox`
+/// feature.
+#[cfg(feature = "mailbox")]
+pub type Mailbox = [Option<(PieceType, bool)>; 64];
+
+/// When a FEN writer puts a square in the en passant field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnPassantFen {
+  /// Only when the side to move can legally capture en passant, as X-FEN and most engines write
+  /// it. Positions that are equal for repetition then have the same FEN, and the parser accepts
+  /// every FEN written this way.
+  #[default]
+  Legal,
+  /// After every double pawn push, as the original FEN standard asks.
+  Always,
+}
+
+/// What [`GameBoard::make_move`] changed, for [`GameBoard::unmake_move`] to put back.
+///
+/// Only valid for the board and move that produced it, and only while the moves made after it
+/// have been unmade in reverse order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoInfo {
+  piece_move: PieceMove,
+  moved: PieceType,
+  /// The captured piece and the square it stood on, which differs from the destination for en
+  /// passant.
+  captured: Option<(PieceType, u8)>,
+  /// The rook castled with, for castling moves.
+  castling_rook: Option<u8>,
+  castling: CastlingRights,
+  en_passant: Option<PieceMove>,
+}
+
+impl UndoInfo {
+  /// The move that was made.
+  pub fn piece_move(&self) -> PieceMove {
+    self.piece_move
+  }
+
+  /// The piece the move captured, if any.
+  pub fn captured(&self) -> Option<PieceType> {
+    self.captured.map(|(piece, _)| piece)
+  }
+}
+
+/// A position: one bitboard per piece type plus the side, castling and en passant state.
+///
+/// The board keeps the Zobrist key of its pieces, and with the `mailbox` feature also a
+/// [`Mailbox`], which makes [`get_piece`](GameBoard::get_piece) and
+/// [`piece_at`](GameBoard::piece_at) a single lookup instead of a scan over the bitboards. Both
+/// are kept up to date by [`set_square`](GameBoard::set_square),
+/// [`clear_square`](GameBoard::clear_square) and everything built on them; code that edits the
+/// bitboard fields directly must call [`refresh_derived`](GameBoard::refresh_derived) afterwards.
+#[derive(Clone, Copy)]
 pub struct GameBoard {
   // Boards for each piece type
   pub pawns: BitBoard,
@@ -47,10 +115,21 @@ pub struct GameBoard {
   pub kings: BitBoard,
 
   // Now for additional metadata
-  pub colour: BitBoard, // BitBoard indicating which pieces are white (1) or black (0)
-  pub castling: u8,
-  pub en_passant: PieceMove,
+  /// Set for squares holding a white piece. A clear bit means black only on an occupied square, so
+  /// mask with the piece bitboards or use [`pieces_of`](GameBoard::pieces_of) rather than negating
+  /// it on its own.
+  pub colour: BitBoard,
+  pub castling: CastlingRights,
+  /// The last move if it was a double pawn push, stored as a move from the pushed pawn to the
+  /// square it skipped, which is the en passant target. `None` when no capture en passant can
+  /// follow.
+  pub en_passant: Option<PieceMove>,
   pub playing: bool, // true if it's white's turn to play
+
+  /// Zobrist key of the pieces alone; [`zobrist_key`](GameBoard::zobrist_key) adds the rest.
+  piece_key: u64,
+  #[cfg(feature = "mailbox")]
+  mailbox: Mailbox,
 }
 
 impl Default for GameBoard {
@@ -63,13 +142,101 @@ impl Default for GameBoard {
       queens: BitBoard::EMPTY,
       kings: BitBoard::EMPTY,
       colour: BitBoard::EMPTY,
-      castling: 0,
-      en_passant: PieceMove::NULL,
+      castling: CastlingRights::NONE,
+      en_passant: None,
       playing: true,
+      piece_key: 0,
+      #[cfg(feature = "mailbox")]
+      mailbox: [None; 64],
+    }
+  }
+}
+
+/// Two boards are equal when they are the same position in the sense of the repetition rules:
+/// the same pieces on the same squares, the same side to move, the same castling rights and the
+/// same en passant capture. An en passant square only counts when the side to move has a legal
+/// capture onto it, so a double push nobody can take does not make a position new. The mailbox
+/// and colour bits of empty squares are ignored, and there are no move counters to compare.
+impl PartialEq for GameBoard {
+  fn eq(&self, other: &Self) -> bool {
+    self.pawns == other.pawns
+      && self.knights == other.knights
+      && self.bishops == other.bishops
+      && self.rooks == other.rooks
+      && self.queens == other.queens
+      && self.kings == other.kings
+      && self.colour & self.combined() == other.colour & other.combined()
+      && self.playing == other.playing
+      && self.castling == other.castling
+      && self.en_passant_capture_square() == other.en_passant_capture_square()
+  }
+}
+
+impl Eq for GameBoard {}
+
+/// Hashes exactly what [`PartialEq`] compares, so equal positions hash alike.
+impl Hash for GameBoard {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.pawns.hash(state);
+    self.knights.hash(state);
+    self.bishops.hash(state);
+    self.rooks.hash(state);
+    self.queens.hash(state);
+    self.kings.hash(state);
+    (self.colour & self.combined()).hash(state);
+    self.playing.hash(state);
+    self.castling.hash(state);
+    self.en_passant_capture_square().hash(state);
+  }
+}
+
+/// An ASCII diagram from white's side, rank 8 at the top, with `.` for empty squares.
+impl fmt::Display for GameBoard {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for rank in (0..8).rev() {
+      write!(f, "{}", rank + 1)?;
+      for file in 0..8 {
+        write!(f, " {}", self.piece_char(rank * 8 + file).unwrap_or('.'))?;
+      }
+      writeln!(f)?;
     }
+    write!(f, "  a b c d e f g h")
   }
 }
 
+/// One line: the position as FEN, then the castling rook squares and the pawn that can be taken
+/// en passant, which FEN only shows indirectly.
+impl fmt::Debug for GameBoard {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("GameBoard(")?;
+    self.write_fen(f)?;
+    f.write_str("; castling rooks:")?;
+    let mut any = false;
+    for (white, kingside) in [(true, true), (true, false), (false, true), (false, false)] {
+      if let Some(rook) = self.castling.rook(white, kingside) {
+        f.write_char(' ')?;
+        write_square(f, rook)?;
+        any = true;
+      }
+    }
+    if !any {
+      f.write_str(" -")?;
+    }
+    if let Some(en_passant) = self.en_passant {
+      f.write_str("; en passant pawn: ")?;
+      // The pawn stands one rank past the target: rank 5 for a target on rank 6, rank 4 for one
+      // on rank 3
+      write_square(f, en_passant.to_square() ^ 8)?;
+    }
+    f.write_char(')')
+  }
+}
+
+fn write_square<W: fmt::Write>(out: &mut W, square: u8) -> fmt::Result {
+  out.write_char((b'a' + square % 8) as char)?;
+  out.write_char((b'1' + square / 8) as char)
+}
+
 impl GameBoard {
   pub fn new() -> Self {
     GameBoard::default()
@@ -83,162 +250,305 @@ impl GameBoard {
     self.pawns | self.knights | self.bishops | self.rooks | self.queens | self.kings
   }
 
+  /// Pieces of white, whose squares are the set bits of `colour`.
+  #[inline]
+  pub fn white_pieces(&self) -> BitBoard {
+    self.combined() & self.colour
+  }
+
+  /// Pieces of black: occupied squares whose `colour` bit is clear.
+  #[inline]
+  pub fn black_pieces(&self) -> BitBoard {
+    self.combined() & !self.colour
+  }
+
+  /// Pieces of white if `white`, else of black.
+  #[inline]
+  pub fn pieces_of(&self, white: bool) -> BitBoard {
+    if white {
+      self.white_pieces()
+    } else {
+      self.black_pieces()
+    }
+  }
+
+  /// The pieces of the side *opposite* to `desired`: black's for `true`, white's for `false`.
+  #[deprecated(note = "use `pieces_of(!desired)`, which names the side it returns")]
   pub fn combined_coloured(&self, desired: bool) -> BitBoard {
-    self.combined() & (self.colour ^ desired)
+    self.pieces_of(!desired)
   }
 
   pub fn casling_right_white(&self) -> (bool, bool) {
     (
-      (self.castling & 0b0001) != 0, // White kingside
-      (self.castling & 0b0010) != 0, // White queenside
+      self.castling.has(true, true),  // White kingside
+      self.castling.has(true, false), // White queenside
     )
   }
 
   pub fn casling_right_black(&self) -> (bool, bool) {
     (
-      (self.castling & 0b0100) != 0, // Black kingside
-      (self.castling & 0b1000) != 0, // Black queenside
+      self.castling.has(false, true),  // Black kingside
+      self.castling.has(false, false), // Black queenside
     )
   }
 
-  pub(crate) fn find_king(&self, is_white: bool) -> Option<u8> {
-    let king_board = if is_white {
-      self.kings & self.colour
-    } else {
-      self.kings & !self.colour
-    };
+  /// If `piece_move` is a castling move for the side to move, returns the square of the rook it
+  /// castles with.
+  ///
+  /// Castling is written either as the king moving two squares from the e-file towards a corner
+  /// rook (`e1g1`), or as the king moving onto its own castling rook (`e1h1`, the Chess960 form).
+  pub fn castling_rook(&self, piece_move: &PieceMove) -> Option<u8> {
+    let white = self.playing;
+    let from = piece_move.from_square();
+    let to = piece_move.to_square();
+    if !(self.kings & self.pieces_of(white))
+      .get_bit(from)
+      .unwrap_or(false)
+      || from / 8 != to / 8
+    {
+      return None;
+    }
 
-    if king_board.raw() != BitBoard::EMPTY.raw() {
-      Some(king_board.raw().trailing_zeros() as u8)
-    } else {
-      None
+    for kingside in [true, false] {
+      let Some(rook) = self.castling.rook(white, kingside) else {
+        continue;
+      };
+      if rook / 8 != from / 8 {
+        continue;
+      }
+      let corner_file = if kingside { 7 } else { 0 };
+      let (king_to, _) = castling_destinations(white, kingside);
+      if to == rook || (from % 8 == 4 && rook % 8 == corner_file && to == king_to) {
+        return Some(rook);
+      }
     }
+    None
   }
 
-  /// Check that all squares between `from` and `to` are empty (exclusive).
-  #[cfg(not(feature = "precomputed_rays"))]
-  pub(crate) fn is_path_clear(&self, from: u8, to: u8) -> bool {
-    let from_rank = (from / 8) as i8;
-    let from_file = (from % 8) as i8;
-    let to_rank = (to / 8) as i8;
-    let to_file = (to % 8) as i8;
-    let dr = (to_rank - from_rank).signum();
-    let df = (to_file - from_file).signum();
-    let mut r = from_rank + dr;
-    let mut f = from_file + df;
-    while r != to_rank || f != to_file {
-      let sq = (r * 8 + f) as u8;
-      if self.combined().get_bit(sq).unwrap_or(false) {
-        return false;
-      }
-      r += dr;
-      f += df;
+  /// The square of the king of the side given by `white`, or `None` if it has none. With more
+  /// than one king, the lowest square.
+  pub fn king_square(&self, white: bool) -> Option<u8> {
+    (self.kings & self.pieces_of(white)).first_square()
+  }
+
+  /// Like [`king_square`](Self::king_square) for positions known to have that king, such as any
+  /// loaded from a FEN or reached by legal moves from one. Without the king the result is 64,
+  /// which is off the board.
+  #[inline]
+  pub fn king_square_unchecked(&self, white: bool) -> u8 {
+    let kings = (self.kings & self.pieces_of(white)).raw();
+    debug_assert!(kings != 0, "no king for the side given by white = {white}");
+    kings.trailing_zeros() as u8
+  }
+
+  /// Pieces of the given colour pinned to their own king: each is the only piece between the
+  /// king and an enemy rook, bishop or queen on the same line.
+  pub fn pinned_pieces(&self, white: bool) -> BitBoard {
+    let own = self.pieces_of(white);
+    BitBoard::new(self.line_blockers(white, own.raw(), (self.combined() & !own).raw()))
+  }
+
+  /// Pieces of the given colour that would give a discovered check by moving away: each is the
+  /// only piece between the enemy king and one of our rooks, bishops or queens.
+  pub fn discovered_check_candidates(&self, white: bool) -> BitBoard {
+    let own = self.pieces_of(white);
+    BitBoard::new(self.line_blockers(!white, own.raw(), own.raw()))
+  }
+
+  /// Members of `candidates` that stand alone between the king of `king_white` and a slider in
+  /// `sliders` that would otherwise attack it.
+  fn line_blockers(&self, king_white: bool, candidates: u64, sliders: u64) -> u64 {
+    let Some(king) = self.king_square(king_white) else {
+      return 0;
+    };
+    let occupancy = self.combined();
+    let candidates = BitBoard::new(candidates);
+    let straight = (self.rooks | self.queens).raw() & sliders;
+    let diagonal = (self.bishops | self.queens).raw() & sliders;
+    let mut pinners = (xray_rook_attacks(occupancy, candidates, king).raw() & straight)
+      | (xray_bishop_attacks(occupancy, candidates, king).raw() & diagonal);
+
+    let mut blockers = 0;
+    while pinners != 0 {
+      let pinner = pinners.trailing_zeros() as u8;
+      pinners &= pinners - 1;
+      blockers |= between(king, pinner) & candidates.raw();
     }
-    true
+    blockers
   }
 
   /// Check that all squares between `from` and `to` are empty (exclusive).
-  #[cfg(feature = "precomputed_rays")]
+  ///
+  /// Squares that share no rank, file or diagonal have nothing between them, so this is `true`
+  /// for them; callers check the geometry of the move first.
   pub(crate) fn is_path_clear(&self, from: u8, to: u8) -> bool {
-    let between_mask = BETWEEN[from as usize][to as usize];
-    if between_mask == 0 {
-      return true;
-    }
-
-    // If any occupied square intersects the BETWEEN mask, path is blocked.
-    (self.combined().raw() & between_mask) == 0
+    (self.combined() & BETWEEN[from as usize][to as usize]).is_empty()
   }
 
+  /// Whether `piece_move` is legal for the side to move.
+  ///
+  /// Positions need not have kings, so fragments such as `8/8/8/8/3R4/8/8/8 w` can be analysed:
+  /// a side without a king is never in check, and its moves are judged only on how the pieces
+  /// move. The opponent's king, if there is one, still cannot be captured.
   pub fn is_move_legal(&self, piece_move: &PieceMove) -> bool {
     // Delegate to the unoptimised LegalChecker implementation
     let checker = LegalChecker::new(self);
     checker.is_move_legal(piece_move)
   }
 
+  /// Whether `piece_move` could be a legal move for the side given by `white` by the time it is
+  /// their turn, for validating premoves. Whose turn it is now is ignored.
+  ///
+  /// Until the premove is played the opponent's pieces may move and the side's own pieces stay
+  /// where they are. So the move must start on one of the side's pieces, must not land on
+  /// another, and must follow how the piece moves with only the side's own pieces in the way.
+  /// Pawns may premove diagonally onto any square, since a piece may arrive there to be
+  /// captured, and must name a promotion exactly when they reach the last rank. Castling needs
+  /// the castling right and no own piece in the way. Checks are not considered, as the
+  /// opponent's reply decides them.
+  pub fn is_plausible_premove(&self, piece_move: &PieceMove, white: bool) -> bool {
+    let from = piece_move.from_square();
+    let to = piece_move.to_square();
+    let own = self.pieces_of(white).raw();
+    if *piece_move == PieceMove::NULL || own & (1u64 << from) == 0 {
+      return false;
+    }
+    let Some(piece) = self.get_piece(from) else {
+      return false;
+    };
+
+    let mut turn = *self;
+    turn.playing = white;
+    if let Some(rook) = turn.castling_rook(piece_move) {
+      let king_to = castling_destinations(white, rook > from).0;
+      let in_the_way = (between(from, king_to) | (1u64 << king_to) | between(from, rook))
+        & own
+        & !(1u64 << from)
+        & !(1u64 << rook);
+      return !piece_move.is_promotion() && in_the_way == 0;
+    }
+    if own & (1u64 << to) != 0 {
+      return false;
+    }
+
+    let last_rank = if white { 7 } else { 0 };
+    if (piece == PieceType::Pawn && to / 8 == last_rank) != piece_move.is_promotion() {
+      return false;
+    }
+    let reachable = if piece == PieceType::Pawn {
+      let push = if white { 8 } else { -8 };
+      let start_rank = if white { 1 } else { 6 };
+      let one = from as i8 + push;
+      let two = one + push;
+      let pushes = if (0..64).contains(&one) && own & (1u64 << one) == 0 {
+        let double = from / 8 == start_rank && own & (1u64 << two) == 0;
+        (1u64 << one) | if double { 1u64 << two } else { 0 }
+      } else {
+        0
+      };
+      pushes | attacks_of(PieceType::Pawn, from, BitBoard::EMPTY, white).raw()
+    } else {
+      attacks_of(piece, from, BitBoard::new(own), white).raw()
+    };
+    reachable & (1u64 << to) != 0
+  }
+
+  /// The legal moves of this board, generated and checked lazily in the order of
+  /// [`generate_moves`](crate::movegen::generate_moves). Cheaper than filtering the full list when
+  /// only the first few moves or whether there is one at all matter.
+  pub fn legal_moves_iter(&self) -> LegalMoves<'_> {
+    LegalMoves::new(self)
+  }
+
+  /// The en passant target square, if the side to move can legally capture onto it.
+  pub fn en_passant_capture_square(&self) -> Option<u8> {
+    let target = self.en_passant?.to_square();
+    // The capturing pawns stand beside the pawn that double pushed, on the rank it landed on
+    let pushed = target ^ 8;
+    let beside = [pushed.checked_sub(1), Some(pushed + 1)];
+    beside
+      .into_iter()
+      .flatten()
+      .filter(|&from| from / 8 == pushed / 8)
+      .any(|from| self.is_move_legal(&PieceMove::new_en_passant(from, target)))
+      .then_some(target)
+  }
+
+  /// Whether the side to move has a legal move, so the game is not over by checkmate or
+  /// stalemate. Stops at the first legal move, trying the king's moves first since they are the
+  /// likeliest way out of check.
+  pub fn has_legal_move(&self) -> bool {
+    let checker = LegalChecker::new(self);
+    let (king_moves, count) = generate_king_moves(self);
+    king_moves[..count]
+      .iter()
+      .any(|piece_move| checker.is_move_legal(piece_move))
+      || LegalMoves::without_king(self).next().is_some()
+  }
+
+  /// The piece `piece_move` takes: the enemy piece on its to square, or a pawn when it captures
+  /// en passant. `None` for quiet moves and castling, whatever the capture flag says. Legality is
+  /// not checked, so ask this before playing the move.
+  pub fn captured_piece(&self, piece_move: &PieceMove) -> Option<PieceType> {
+    let (from, to) = (piece_move.from_square(), piece_move.to_square());
+    if self.pieces_of(!self.playing).get_bit(to).unwrap_or(false) {
+      return self.get_piece(to);
+    }
+    let en_passant = self.get_piece(from) == Some(PieceType::Pawn)
+      && from % 8 != to % 8
+      && self.en_passant.map(|en_passant| en_passant.to_square()) == Some(to);
+    en_passant.then_some(PieceType::Pawn)
+  }
+
+  /// Why `piece_move` can't be played here, or `None` if it can. The reason's `Display` is a
+  /// sentence for players, e.g. "your king would be in check from the rook on h1".
+  pub fn explain_illegal(&self, piece_move: &PieceMove) -> Option<IllegalMoveReason> {
+    LegalChecker::new(self).illegal_reason(piece_move)
+  }
+
   /// Apply a move to the board without any legality checks.
   /// Intended for internal use (e.g., simulation inside `is_move_legal`).
   /// NOTE: This does NOT switch turns - the caller is responsible for that.
   pub(crate) fn apply_move_unchecked(&mut self, piece_move: &PieceMove) {
+    // The legality checks turn NULL away, so reaching here with it is a bug in the caller
+    debug_assert_ne!(*piece_move, PieceMove::NULL, "applying the null move");
     let from_square = piece_move.from_square();
     let to_square = piece_move.to_square();
     let mover_white = self.playing;
 
-    // Remove the piece from the from_square
-    let piece = self
-      .get_piece(from_square)
-      .expect("No piece at from_square");
-    self.clear_square(from_square);
-
-    // Update castling rights for the moving piece
-    if piece == PieceType::King {
-      if mover_white {
-        self.castling &= !0b0011; // Clear white kingside and queenside
-      } else {
-        self.castling &= !0b1100; // Clear black kingside and queenside
-      }
-    } else if piece == PieceType::Rook {
-      let home_ks = if mover_white { H1 } else { H8 }; // h1 or h8
-      let home_qs = if mover_white { A1 } else { A8 }; // a1 or a8
-      if from_square == home_ks {
-        if mover_white {
-          self.castling &= !0b0001; // Clear white kingside
-        } else {
-          self.castling &= !0b0100; // Clear black kingside
-        }
-      } else if from_square == home_qs {
-        if mover_white {
-          self.castling &= !0b0010; // Clear white queenside
-        } else {
-          self.castling &= !0b1000; // Clear black queenside
-        }
-      }
-    }
+    // Nothing to move; callers check legality first, so this only happens on bad input
+    let Some(piece) = self.get_piece(from_square) else {
+      return;
+    };
 
-    // Handle castling: move the rook if this is a castling move
-    if piece == PieceType::King && (to_square as i32 - from_square as i32).abs() == 2 {
-      let is_kingside = to_square > from_square;
-      let rook_from = if mover_white {
-        if is_kingside { H1 } else { A1 }
-      } else if is_kingside {
-        H8
-      } else {
-        A8
-      };
-      let rook_to = if mover_white {
-        if is_kingside { F1 } else { D1 }
-      } else if is_kingside {
-        F8
-      } else {
-        D8
-      };
-      // Move the rook (clear old position, set new)
+    // Castling: lift the king and the rook first, since the king may land where the rook stood
+    if piece == PieceType::King
+      && let Some(rook_from) = self.castling_rook(piece_move)
+    {
+      let (king_to, rook_to) = castling_destinations(mover_white, rook_from > from_square);
+      self.clear_square(from_square);
       self.clear_square(rook_from);
+      self.set_square(king_to, PieceType::King, mover_white);
       self.set_square(rook_to, PieceType::Rook, mover_white);
+      self.castling.clear_side(mover_white);
+      self.en_passant = None;
+      return;
     }
 
+    self.clear_square(from_square);
+
+    // Update castling rights: a king move gives up both, and a rook leaving or being captured on
+    // its castling square gives up that one
+    if piece == PieceType::King {
+      self.castling.clear_side(mover_white);
+    }
+    self.castling.clear_square(from_square);
+    self.castling.clear_square(to_square);
+
     // Clear the destination square and handle capture
     let captured_opt = self.get_piece(to_square);
-    if let Some(captured) = captured_opt {
+    if captured_opt.is_some() {
       self.clear_square(to_square);
-      // If captured an opponent's rook on its home square, update their castling rights
-      if captured == PieceType::Rook {
-        let opp_white = !mover_white;
-        let opp_home_ks = if opp_white { H1 } else { H8 };
-        let opp_home_qs = if opp_white { A1 } else { A8 };
-        if to_square == opp_home_ks {
-          if opp_white {
-            self.castling &= !0b0001; // Clear white kingside
-          } else {
-            self.castling &= !0b0100; // Clear black kingside
-          }
-        } else if to_square == opp_home_qs {
-          if opp_white {
-            self.castling &= !0b0010; // Clear white queenside
-          } else {
-            self.castling &= !0b1000; // Clear black queenside
-          }
-        }
-      }
     }
 
     // Handle special cases like en passant, promotion
@@ -265,8 +575,7 @@ impl GameBoard {
     }
 
     // Place the piece on the to_square, handling promotion
-    if piece_move.is_promotion() {
-      let promotion_type = piece_move.promotion_type().expect("Promotion type not set");
+    if let Some(promotion_type) = piece_move.promotion_type() {
       self.set_square(
         to_square,
         match promotion_type {
@@ -282,7 +591,7 @@ impl GameBoard {
     }
 
     // Reset en passant target
-    self.en_passant = PieceMove::NULL;
+    self.en_passant = None;
 
     // Set new en passant target if this was a double pawn push
     if piece == PieceType::Pawn
@@ -294,11 +603,103 @@ impl GameBoard {
       } else {
         to_square + 8
       };
-      self.en_passant = PieceMove::new(to_square, skipped_square, false, None);
+      self.en_passant = Some(PieceMove::new(to_square, skipped_square, false, None));
+    }
+  }
+
+  /// FEN letter of the piece on `square`: upper case for white, lower case for black.
+  pub fn piece_char(&self, square: u8) -> Option<char> {
+    let c = match self.get_piece(square)? {
+      PieceType::Pawn => 'p',
+      PieceType::Knight => 'n',
+      PieceType::Bishop => 'b',
+      PieceType::Rook => 'r',
+      PieceType::Queen => 'q',
+      PieceType::King => 'k',
+    };
+    Some(if self.colour.get_bit_unchecked(square) {
+      c.to_ascii_uppercase()
+    } else {
+      c
+    })
+  }
+
+  /// Write the first four FEN fields: placement, side to move, castling and en passant. The
+  /// clocks live in `GameData`, so they are left out. The en passant square is written only when
+  /// the capture is legal; see [`EnPassantFen`].
+  pub fn write_fen<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+    self.write_fen_with(out, EnPassantFen::default())
+  }
+
+  /// Like [`write_fen`](Self::write_fen), with `en_passant` choosing when the en passant square
+  /// is written.
+  pub fn write_fen_with<W: fmt::Write>(
+    &self,
+    out: &mut W,
+    en_passant: EnPassantFen,
+  ) -> fmt::Result {
+    for rank in (0..8).rev() {
+      let mut empty = 0;
+      for file in 0..8 {
+        match self.piece_char(rank * 8 + file) {
+          Some(c) => {
+            if empty > 0 {
+              write!(out, "{empty}")?;
+              empty = 0;
+            }
+            out.write_char(c)?;
+          }
+          None => empty += 1,
+        }
+      }
+      if empty > 0 {
+        write!(out, "{empty}")?;
+      }
+      if rank > 0 {
+        out.write_char('/')?;
+      }
+    }
+
+    out.write_str(if self.playing { " w " } else { " b " })?;
+    self.castling.write_fen(self, out)?;
+    out.write_char(' ')?;
+    let target = match en_passant {
+      EnPassantFen::Legal => self.en_passant_capture_square(),
+      EnPassantFen::Always => self.en_passant.map(|en_passant| en_passant.to_square()),
+    };
+    match target {
+      Some(target) => write_square(out, target),
+      None => out.write_char('-'),
+    }
+  }
+
+  /// The piece on `square` and whether it is white.
+  pub fn piece_at(&self, square: u8) -> Option<(PieceType, bool)> {
+    #[cfg(feature = "mailbox")]
+    {
+      *self.mailbox.get(square as usize)?
+    }
+    #[cfg(not(feature = "mailbox"))]
+    {
+      let piece = self.get_piece(square)?;
+      Some((piece, self.colour.get_bit_unchecked(square)))
     }
   }
 
   pub fn get_piece(&self, square: u8) -> Option<PieceType> {
+    #[cfg(feature = "mailbox")]
+    {
+      self.mailbox.get(square as usize)?.map(|(piece, _)| piece)
+    }
+    #[cfg(not(feature = "mailbox"))]
+    {
+      self.scan_piece(square)
+    }
+  }
+
+  /// [`get_piece`](Self::get_piece) by checking each piece bitboard in turn.
+  #[cfg(not(feature = "mailbox"))]
+  fn scan_piece(&self, square: u8) -> Option<PieceType> {
     // Inline checks instead of building an array + iterator to reduce overhead
     if self.pawns.get_bit(square)? {
       return Some(PieceType::Pawn);
@@ -323,6 +724,12 @@ impl GameBoard {
   }
 
   pub fn clear_square(&mut self, square: u8) -> Option<()> {
+    if square >= 64 {
+      return None;
+    }
+    if let Some((piece, white)) = self.piece_at(square) {
+      self.piece_key ^= zobrist::piece_key(piece, white, square);
+    }
     // Clear the bit on every piece bitboard to ensure no stray bits remain
     let _ = self.pawns.unset_bit_unchecked(square);
     let _ = self.knights.unset_bit_unchecked(square);
@@ -334,6 +741,11 @@ impl GameBoard {
     // Clear the colour bit as well
     let _ = self.colour.unset_bit_unchecked(square);
 
+    #[cfg(feature = "mailbox")]
+    {
+      self.mailbox[square as usize] = None;
+    }
+
     Some(())
   }
 
@@ -350,9 +762,97 @@ impl GameBoard {
     };
 
     bitboard.set_bit_unchecked(square);
+    self.piece_key ^= zobrist::piece_key(piece_type, is_white, square);
+    #[cfg(feature = "mailbox")]
+    {
+      self.mailbox[square as usize] = Some((piece_type, is_white));
+    }
     self.colour.update_bit(square, is_white).map(|_f| ())
   }
 
+  /// How many pieces of each type both sides have.
+  pub fn material_signature(&self) -> MaterialSig {
+    MaterialSig::of(self)
+  }
+
+  /// Bishops of the side given by `white` that stand on light squares if `light`, else on dark
+  /// ones.
+  pub fn bishops_on_color(&self, white: bool, light: bool) -> BitBoard {
+    let squares = if light { LIGHT_SQUARES } else { DARK_SQUARES };
+    self.bishops & self.pieces_of(white) & BitBoard::new(squares)
+  }
+
+  /// Whether each side has a single bishop, the two on squares of different colours. Endings
+  /// with such bishops are notoriously drawish, whatever else is on the board.
+  pub fn has_opposite_colored_bishops(&self) -> bool {
+    let white = (self.bishops & self.white_pieces()).count() == 1;
+    let black = (self.bishops & self.black_pieces()).count() == 1;
+    white
+      && black
+      && self.bishops_on_color(true, true).count() != self.bishops_on_color(false, true).count()
+  }
+
+  /// Whether neither side can ever mate, however badly the other plays: bare kings, a single
+  /// knight, or any number of bishops all on squares of one colour.
+  pub fn is_insufficient_material(&self) -> bool {
+    if !(self.pawns | self.rooks | self.queens).is_empty() {
+      return false;
+    }
+    let knights = self.knights.count();
+    let bishops = self.bishops;
+    let one_colour = (bishops & BitBoard::new(LIGHT_SQUARES)).is_empty()
+      || (bishops & BitBoard::new(DARK_SQUARES)).is_empty();
+    (knights == 0 && one_colour) || (knights == 1 && bishops.is_empty())
+  }
+
+  /// Whether the side given by `white` could still mate, for adjudicating a game its opponent
+  /// lost on time: the flag falls to a draw instead when this is `false`.
+  ///
+  /// FIDE asks whether any series of legal moves ends in mate; this answers from the bitboards
+  /// alone, as the main online servers do. A pawn, rook or queen always counts as enough. A lone
+  /// knight is enough only if the opponent has a pawn, knight, bishop or rook to block its own
+  /// king in, and bishops only if they stand on both colours (counting the opponent's) or the
+  /// opponent has a pawn or knight. A bare king never is.
+  pub fn has_mating_material(&self, white: bool) -> bool {
+    let own = self.pieces_of(white);
+    let enemy = self.pieces_of(!white);
+    if !((self.pawns | self.rooks | self.queens) & own).is_empty() {
+      return true;
+    }
+    let knights = self.knights & own;
+    let bishops = self.bishops & own;
+    if !knights.is_empty() {
+      // Two minor pieces can mate; a single knight needs the enemy's help
+      return (knights | bishops).count() > 1 || !(enemy & !self.kings & !self.queens).is_empty();
+    }
+    if !bishops.is_empty() {
+      let one_colour = (self.bishops & BitBoard::new(LIGHT_SQUARES)).is_empty()
+        || (self.bishops & BitBoard::new(DARK_SQUARES)).is_empty();
+      return !one_colour || !((self.pawns | self.knights) & enemy).is_empty();
+    }
+    false
+  }
+
+  /// How many white and black pieces attack each square, as `(white, black)` indexed by square.
+  /// Pieces are counted whether or not the square holds a piece of their own side, so a count
+  /// covers defenders as well as attackers. Built in one pass over the pieces, which is much
+  /// cheaper than asking about each square in turn.
+  pub fn square_control(&self) -> [(u8, u8); 64] {
+    let mut control = [(0u8, 0u8); 64];
+    for from in self.combined() {
+      let white = self.colour.get_bit_unchecked(from);
+      for to in BitBoard::new(attacks_from(self, from)) {
+        let (white_count, black_count) = &mut control[to as usize];
+        if white {
+          *white_count += 1;
+        } else {
+          *black_count += 1;
+        }
+      }
+    }
+    control
+  }
+
   pub fn move_piece(&mut self, piece_move: &PieceMove) -> Option<()> {
     if !self.is_move_legal(piece_move) {
       return None;
@@ -362,6 +862,79 @@ impl GameBoard {
     Some(())
   }
 
+  /// Plays `piece_move` in place if it is legal, returning what [`unmake_move`] needs to take it
+  /// back. A search can then walk the tree on a single board instead of copying it at every
+  /// node. An illegal move leaves the board untouched and fails with the reason
+  /// [`explain_illegal`](Self::explain_illegal) gives.
+  ///
+  /// [`unmake_move`]: Self::unmake_move
+  pub fn make_move(&mut self, piece_move: &PieceMove) -> Result<UndoInfo, IllegalMoveReason> {
+    if !self.is_move_legal(piece_move) {
+      // The two checks agree on every move, so the fallback is never used
+      return Err(
+        self
+          .explain_illegal(piece_move)
+          .unwrap_or(IllegalMoveReason::NullMove),
+      );
+    }
+    let from = piece_move.from_square();
+    let to = piece_move.to_square();
+    let Some(moved) = self.get_piece(from) else {
+      return Err(IllegalMoveReason::NoPiece { square: from });
+    };
+    let castling_rook = if moved == PieceType::King {
+      self.castling_rook(piece_move)
+    } else {
+      None
+    };
+    let captured = match self.get_piece(to) {
+      _ if castling_rook.is_some() => None,
+      Some(piece) => Some((piece, to)),
+      // A pawn moving diagonally onto an empty square takes en passant
+      None if moved == PieceType::Pawn && from % 8 != to % 8 => {
+        Some((PieceType::Pawn, if self.playing { to - 8 } else { to + 8 }))
+      }
+      None => None,
+    };
+    let undo = UndoInfo {
+      piece_move: *piece_move,
+      moved,
+      captured,
+      castling_rook,
+      castling: self.castling,
+      en_passant: self.en_passant,
+    };
+
+    self.apply_move_unchecked(piece_move);
+    self.playing = !self.playing;
+    Ok(undo)
+  }
+
+  /// Takes back the move [`make_move`](Self::make_move) returned `undo` for, which must be the
+  /// last move made on this board that has not been taken back yet.
+  pub fn unmake_move(&mut self, undo: UndoInfo) {
+    self.playing = !self.playing;
+    let white = self.playing;
+    let from = undo.piece_move.from_square();
+    let to = undo.piece_move.to_square();
+
+    if let Some(rook) = undo.castling_rook {
+      let (king_to, rook_to) = castling_destinations(white, rook > from);
+      self.clear_square(king_to);
+      self.clear_square(rook_to);
+      self.set_square(from, PieceType::King, white);
+      self.set_square(rook, PieceType::Rook, white);
+    } else {
+      self.clear_square(to);
+      self.set_square(from, undo.moved, white);
+      if let Some((piece, square)) = undo.captured {
+        self.set_square(square, piece, !white);
+      }
+    }
+    self.castling = undo.castling;
+    self.en_passant = undo.en_passant;
+  }
+
   pub const START_POS: GameBoard = GameBoard {
     pawns: BitBoard::new(0x00FF00000000FF00),
     knights: BitBoard::new(0x4200000000000042),
@@ -370,10 +943,102 @@ impl GameBoard {
     queens: BitBoard::new(0x0800000000000008),
     kings: BitBoard::new(0x1000000000000010),
     colour: BitBoard::new(0x000000000000FFFF), // white pieces on ranks 1 and 2
-    castling: 0b1111,                          // KQkq
-    en_passant: PieceMove::NULL,
+    castling: CastlingRights::STANDARD,
+    en_passant: None,
     playing: true,
-  };
+    piece_key: 0,
+    #[cfg(feature = "mailbox")]
+    mailbox: [None; 64],
+  }
+  .with_mailbox()
+  .with_piece_key();
+
+  /// `self` with the Zobrist key of its pieces computed from the bitboards.
+  const fn with_piece_key(mut self) -> Self {
+    // In PieceType order
+    let boards = [
+      self.pawns.raw(),
+      self.knights.raw(),
+      self.bishops.raw(),
+      self.rooks.raw(),
+      self.queens.raw(),
+      self.kings.raw(),
+    ];
+    self.piece_key = 0;
+    let mut square = 0;
+    while square < 64 {
+      let bit = 1u64 << square;
+      let colour = (self.colour.raw() & bit == 0) as usize;
+      let mut piece = 0;
+      while piece < boards.len() {
+        if boards[piece] & bit != 0 {
+          self.piece_key ^= zobrist::PIECES.0[colour][piece][square];
+          break;
+        }
+        piece += 1;
+      }
+      square += 1;
+    }
+    self
+  }
+
+  /// `self` with the mailbox rebuilt from the bitboards.
+  #[cfg(feature = "mailbox")]
+  const fn with_mailbox(mut self) -> Self {
+    let mut square = 0;
+    while square < 64 {
+      let bit = 1u64 << square;
+      let piece = if self.pawns.raw() & bit != 0 {
+        Some(PieceType::Pawn)
+      } else if self.knights.raw() & bit != 0 {
+        Some(PieceType::Knight)
+      } else if self.bishops.raw() & bit != 0 {
+        Some(PieceType::Bishop)
+      } else if self.rooks.raw() & bit != 0 {
+        Some(PieceType::Rook)
+      } else if self.queens.raw() & bit != 0 {
+        Some(PieceType::Queen)
+      } else if self.kings.raw() & bit != 0 {
+        Some(PieceType::King)
+      } else {
+        None
+      };
+      self.mailbox[square] = match piece {
+        Some(piece) => Some((piece, self.colour.raw() & bit != 0)),
+        None => None,
+      };
+      square += 1;
+    }
+    self
+  }
+
+  #[cfg(not(feature = "mailbox"))]
+  const fn with_mailbox(self) -> Self {
+    self
+  }
+
+  /// Rebuild the state kept alongside the bitboards, the Zobrist key of the pieces and, with the
+  /// `mailbox` feature, the mailbox. Required after any direct edit of the piece or colour
+  /// fields: until then [`zobrist_key`](Self::zobrist_key) is wrong, and repetition detection and
+  /// transposition table lookups go wrong with it.
+  pub fn refresh_derived(&mut self) {
+    *self = self.with_mailbox().with_piece_key();
+  }
+
+  /// The old name of [`refresh_derived`](Self::refresh_derived), from before the board kept a
+  /// Zobrist key.
+  #[deprecated(note = "renamed to `refresh_derived`, which also rebuilds the Zobrist key")]
+  pub fn refresh_mailbox(&mut self) {
+    self.refresh_derived();
+  }
+
+  /// The Zobrist hash of the position, the same as [`zobrist::hash`] gives. The key of the pieces
+  /// is updated as they are placed and removed, by [`make_move`](Self::make_move),
+  /// [`unmake_move`](Self::unmake_move) and every other move, so only the side to move, the
+  /// castling rights and the en passant file are added here.
+  pub fn zobrist_key(&self) -> u64 {
+    self.piece_key ^ zobrist::state_key(self)
+  }
 }
 
 #[cfg(test)]
@@ -411,6 +1076,192 @@ mod tests {
     PieceMove::new_castling(from, to)
   }
 
+  #[test]
+  fn test_square_control() {
+    let control = GameBoard::START_POS.square_control();
+    // e3 is covered by the d2 and f2 pawns, f3 by two pawns and the g1 knight
+    assert_eq!(control[E3 as usize], (2, 0));
+    assert_eq!(control[F3 as usize], (3, 0));
+    assert_eq!(control[F6 as usize], (0, 3));
+    assert_eq!(control[E4 as usize], (0, 0));
+    // Pieces defending each other count too; nothing reaches a1
+    assert_eq!(control[D1 as usize], (1, 0));
+    assert_eq!(control[A1 as usize], (0, 0));
+
+    // Rook d1 and knight c3 attack d5, defended by the e6 pawn
+    let board = board_from_fen("4k3/8/4p3/3p4/8/2N5/8/3RK3 w - - 0 1");
+    let control = board.square_control();
+    assert_eq!(control[D5 as usize], (2, 1));
+    // The d5 pawn stops the rook
+    assert_eq!(control[D6 as usize], (0, 0));
+    assert_eq!(control[D7 as usize], (0, 1));
+  }
+
+  #[test]
+  fn test_square_colours() {
+    assert!(is_dark(A1) && is_light(H1) && is_light(A8) && is_dark(H8));
+    assert!(is_light(D1) && is_dark(E1) && !is_light(64) && !is_dark(64));
+    assert_eq!(LIGHT_SQUARES.count_ones(), 32);
+    assert_eq!(LIGHT_SQUARES & DARK_SQUARES, 0);
+
+    let board = board_from_fen("2b1kb2/8/8/8/8/8/8/2B1KB2 w - - 0 1");
+    assert_eq!(board.bishops_on_color(true, true).first_square(), Some(F1));
+    assert_eq!(board.bishops_on_color(true, false).first_square(), Some(C1));
+    assert_eq!(board.bishops_on_color(false, true).first_square(), Some(C8));
+    assert!(!board.has_opposite_colored_bishops());
+
+    let opposite = board_from_fen("4k3/5b2/8/3p4/3P4/8/8/2B1K3 w - - 0 1");
+    assert!(opposite.has_opposite_colored_bishops());
+    let same = board_from_fen("4k3/4b3/8/3p4/3P4/8/8/2B1K3 w - - 0 1");
+    assert!(!same.has_opposite_colored_bishops());
+  }
+
+  /// Every field, the mailbox included through `piece_at`, so a board only equals what it was
+  /// restored to if nothing at all changed.
+  fn assert_same(a: &GameBoard, b: &GameBoard) {
+    let fields = |board: &GameBoard| {
+      (
+        [
+          board.pawns,
+          board.knights,
+          board.bishops,
+          board.rooks,
+          board.queens,
+          board.kings,
+          board.colour,
+        ],
+        board.castling,
+        board.en_passant,
+        board.playing,
+        board.zobrist_key(),
+      )
+    };
+    assert_eq!(fields(a), fields(b));
+    for square in 0..64 {
+      assert_eq!(a.piece_at(square), b.piece_at(square), "{square}");
+    }
+  }
+
+  /// Leaf count of a walk that makes and unmakes every move on one board, checking that each
+  /// unmake restores the board exactly.
+  fn make_unmake_perft(board: &mut GameBoard, depth: u32) -> u64 {
+    if depth == 0 {
+      return 1;
+    }
+    let (moves, count) = crate::movegen::generate_moves(board);
+    let mut nodes = 0;
+    for piece_move in &moves[..count] {
+      let before = *board;
+      if let Ok(undo) = board.make_move(piece_move) {
+        let mut copied = before;
+        copied.move_piece(piece_move).unwrap();
+        assert_same(board, &copied);
+        nodes += make_unmake_perft(board, depth - 1);
+        board.unmake_move(undo);
+      }
+      assert_same(board, &before);
+    }
+    nodes
+  }
+
+  #[test]
+  fn test_make_unmake_move() {
+    for (fen, depth, nodes) in [
+      // Kiwipete: castling both ways, en passant, promotions and captures of castling rooks
+      (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        2,
+        2039,
+      ),
+      ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3, 2812),
+      (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        2,
+        264,
+      ),
+      // Chess960 castling, with the king landing where its rook stood
+      ("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1", 2, 525),
+    ] {
+      let mut board = board_from_fen(fen);
+      assert_eq!(make_unmake_perft(&mut board, depth), nodes, "{fen}");
+    }
+  }
+
+  #[test]
+  fn test_make_move_rejects_illegal_moves() {
+    let mut board = GameBoard::START_POS;
+    assert_eq!(
+      board.make_move(&simple_move(E2, E5)),
+      Err(IllegalMoveReason::WrongMovement {
+        piece: PieceType::Pawn
+      })
+    );
+    assert_eq!(board, GameBoard::START_POS);
+
+    let undo = board.make_move(&simple_move(E2, E4)).unwrap();
+    assert_eq!(undo.piece_move(), simple_move(E2, E4));
+    assert_eq!(undo.captured(), None);
+    let undo = board.make_move(&simple_move(D7, D5)).unwrap();
+    let capture = board
+      .make_move(&PieceMove::new(E4, D5, true, None))
+      .unwrap();
+    assert_eq!(capture.captured(), Some(PieceType::Pawn));
+    board.unmake_move(capture);
+    board.unmake_move(undo);
+    assert_eq!(board.get_piece(D7), Some(PieceType::Pawn));
+    assert!(!board.playing);
+  }
+
+  #[test]
+  fn test_insufficient_material() {
+    for fen in [
+      "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+      "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+      "4k3/8/8/8/8/8/8/4KB2 w - - 0 1",
+      // Bishops on one colour, whichever side they belong to
+      "4kb2/8/8/8/8/B7/8/2B1K3 w - - 0 1",
+    ] {
+      assert!(board_from_fen(fen).is_insufficient_material(), "{fen}");
+    }
+    for fen in [
+      "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1",
+      "4k3/8/8/8/8/8/8/4KNN1 w - - 0 1",
+      "4kn2/8/8/8/8/8/8/4KB2 w - - 0 1",
+      "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+    ] {
+      assert!(!board_from_fen(fen).is_insufficient_material(), "{fen}");
+    }
+  }
+
+  #[test]
+  fn test_mating_material() {
+    for (fen, white, black) in [
+      ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", false, false),
+      ("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", false, false),
+      ("4k3/8/8/8/8/8/8/4KB2 w - - 0 1", false, false),
+      ("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", true, false),
+      ("4k3/8/8/8/8/8/8/4KNN1 w - - 0 1", true, false),
+      ("4k3/8/8/8/8/8/8/3BKN2 w - - 0 1", true, false),
+      // The knight can mate a king hemmed in by its own pawn or bishop, not by its queen
+      ("4k3/4p3/8/8/8/8/8/4KN2 w - - 0 1", true, true),
+      ("4kb2/8/8/8/8/8/8/4KN2 w - - 0 1", true, true),
+      ("4kq2/8/8/8/8/8/8/4KN2 w - - 0 1", false, true),
+      // Bishops on one colour only mate with an enemy knight or pawn to block
+      ("4kb2/8/8/8/8/B7/8/2B1K3 w - - 0 1", false, false),
+      ("4kn2/8/8/8/8/8/8/4KB2 w - - 0 1", true, true),
+      ("3bk3/8/8/8/8/8/8/4KB2 w - - 0 1", true, true),
+      ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", true, false),
+      ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", true, false),
+    ] {
+      let board = board_from_fen(fen);
+      assert_eq!(board.has_mating_material(true), white, "{fen}");
+      assert_eq!(board.has_mating_material(false), black, "{fen}");
+      if board.is_insufficient_material() {
+        assert!(!white && !black, "{fen}");
+      }
+    }
+  }
+
   // Basic validity tests
   #[test]
   fn test_wrong_color_piece() {
@@ -544,7 +1395,7 @@ mod tests {
   #[test]
   fn test_en_passant_basic() {
     let mut board = board_from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 1");
-    board.en_passant = PieceMove::new(D5, D6, false, None); // Set en passant target
+    board.en_passant = Some(PieceMove::new(D5, D6, false, None)); // Set en passant target
     let en_passant = en_passant_move(E5, D6);
     assert!(board.is_move_legal(&en_passant));
   }
@@ -552,11 +1403,56 @@ mod tests {
   #[test]
   fn test_en_passant_wrong_target() {
     let mut board = board_from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1");
-    board.en_passant = PieceMove::new(D5, C6, false, None); // Wrong en passant target
+    board.en_passant = Some(PieceMove::new(D5, C6, false, None)); // Wrong en passant target
     let en_passant = en_passant_move(E5, D6); // Try to capture to different square
     assert!(!board.is_move_legal(&en_passant));
   }
 
+  #[test]
+  fn test_en_passant_rank_pin_white() {
+    // Both pawns leave the 5th rank, exposing the white king on a5 to the rook on h5
+    let board = board_from_fen("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1");
+    assert!(!board.is_move_legal(&en_passant_move(E5, D6)));
+    // The pawn may still push normally
+    assert!(board.is_move_legal(&simple_move(E5, E6)));
+  }
+
+  #[test]
+  fn test_en_passant_rank_pin_black() {
+    // Mirror image: the black king on h4 is exposed to the white queen on a4
+    let board = board_from_fen("7K/8/8/8/Q2Pp2k/8/8/8 b - d3 0 1");
+    assert!(!board.is_move_legal(&en_passant_move(E4, D3)));
+    assert!(board.is_move_legal(&simple_move(E4, E3)));
+  }
+
+  #[test]
+  fn test_en_passant_rank_pin_adjacent_king() {
+    // King directly next to the capturing pawn, rook on the far side of the captured pawn
+    let board = board_from_fen("8/8/8/KPp4r/8/8/8/7k w - c6 0 1");
+    assert!(!board.is_move_legal(&en_passant_move(B5, C6)));
+
+    let board = board_from_fen("8/8/8/8/R4Ppk/8/8/K7 b - f3 0 1");
+    assert!(!board.is_move_legal(&en_passant_move(G4, F3)));
+  }
+
+  #[test]
+  fn test_en_passant_rank_not_pinned() {
+    // A third piece on the rank still shields the king
+    let board = board_from_fen("8/8/8/K1NpP2r/8/8/8/7k w - d6 0 1");
+    assert!(board.is_move_legal(&en_passant_move(E5, D6)));
+
+    // Slider on the rank belongs to the side to move
+    let board = board_from_fen("8/8/8/K2pP2R/8/8/8/7k w - d6 0 1");
+    assert!(board.is_move_legal(&en_passant_move(E5, D6)));
+
+    // King on another rank is unaffected by the rank slider
+    let board = board_from_fen("K7/8/8/3pP2r/8/8/8/7k w - d6 0 1");
+    assert!(board.is_move_legal(&en_passant_move(E5, D6)));
+
+    let board = board_from_fen("7K/8/8/8/3Pp2k/8/8/q7 b - d3 0 1");
+    assert!(board.is_move_legal(&en_passant_move(E4, D3)));
+  }
+
   // Knight move tests
   #[test]
   fn test_knight_l_shape_moves() {
@@ -589,7 +1485,7 @@ mod tests {
 
   #[test]
   fn test_knight_blocked_by_own_piece() {
-    let board = board_from_fen("8/8/8/2P5/3N4/8/8/8 w - - 0 1");
+    let board = board_from_fen("8/8/2P5/8/3N4/8/8/8 w - - 0 1");
     let blocked_knight = simple_move(D4, C6);
     assert!(!board.is_move_legal(&blocked_knight));
   }
@@ -679,6 +1575,26 @@ mod tests {
     assert!(!board.is_move_legal(&diagonal_move));
   }
 
+  #[test]
+  fn test_kingless_positions() {
+    let board = board_from_fen("8/8/8/8/3R4/8/8/8 w - - 0 1");
+    assert!(board.is_move_legal(&simple_move(D4, D8)));
+    assert_eq!(board.legal_moves_iter().count(), 14);
+    assert!(board.has_legal_move());
+
+    // Only the side with a king has to keep it safe
+    let board = board_from_fen("3k4/8/8/8/3R4/8/8/8 b - - 0 1");
+    assert!(!board.is_move_legal(&simple_move(D8, D7)));
+    assert!(board.is_move_legal(&simple_move(D8, E7)));
+    let board = board_from_fen("3k4/8/8/8/3R4/8/8/8 w - - 0 1");
+    assert!(board.is_move_legal(&simple_move(D4, A4)));
+    assert!(!board.is_move_legal(&simple_move(D4, D8)));
+    assert_eq!(
+      board.explain_illegal(&simple_move(D4, D8)),
+      Some(IllegalMoveReason::CapturesKing)
+    );
+  }
+
   #[test]
   fn test_rook_blocked_path() {
     let board = board_from_fen("8/8/8/8/2PR4/8/8/8 w - - 0 1");
@@ -789,6 +1705,65 @@ mod tests {
     assert!(!board.is_move_legal(&castle_no_rights));
   }
 
+  #[test]
+  fn test_castling_moves_rook() {
+    let mut board = board_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    board.move_piece(&castling_move(E1, G1)).unwrap();
+    assert_eq!(board.get_piece(G1), Some(PieceType::King));
+    assert_eq!(board.get_piece(F1), Some(PieceType::Rook));
+    assert_eq!(board.get_piece(H1), None);
+    assert_eq!(board.castling.bits(), 0b1100);
+
+    board.move_piece(&castling_move(E8, C8)).unwrap();
+    assert_eq!(board.get_piece(C8), Some(PieceType::King));
+    assert_eq!(board.get_piece(D8), Some(PieceType::Rook));
+    assert_eq!(board.get_piece(A8), None);
+    assert_eq!(board.castling.bits(), 0);
+  }
+
+  #[test]
+  fn test_rook_capture_clears_castling_right() {
+    let mut board = board_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    board.move_piece(&capture_move(H1, H8)).unwrap();
+    // White's h1 rook left and black's h8 rook was captured
+    assert_eq!(board.castling.bits(), 0b1010);
+  }
+
+  #[test]
+  fn test_chess960_castling() {
+    // King on d1 with castling rooks on b1 and g1
+    let board = board_from_fen("3k4/8/8/8/8/8/8/1R1K2R1 w KQ - 0 1");
+    assert_eq!(board.castling.rook(true, true), Some(G1));
+    assert_eq!(board.castling.rook(true, false), Some(B1));
+
+    // Kingside is written king-takes-rook: king to g1, rook to f1
+    let mut kingside = board;
+    kingside.move_piece(&castling_move(D1, G1)).unwrap();
+    assert_eq!(kingside.get_piece(G1), Some(PieceType::King));
+    assert_eq!(kingside.get_piece(F1), Some(PieceType::Rook));
+    assert_eq!(kingside.get_piece(D1), None);
+
+    // Queenside: king to c1, rook to d1
+    let mut queenside = board;
+    queenside.move_piece(&castling_move(D1, B1)).unwrap();
+    assert_eq!(queenside.get_piece(C1), Some(PieceType::King));
+    assert_eq!(queenside.get_piece(D1), Some(PieceType::Rook));
+    assert_eq!(queenside.get_piece(B1), None);
+    assert!(!queenside.castling.has(true, true));
+
+    // The classic two-square form only applies to a king on the e-file
+    assert!(!board.is_move_legal(&castling_move(D1, F1)));
+  }
+
+  #[test]
+  fn test_chess960_castling_path_blocked() {
+    // The rook's destination d1 is taken by a bishop
+    let board = board_from_fen("4k3/8/8/8/8/8/8/RK1B4 w Q - 0 1");
+    assert!(!board.is_move_legal(&castling_move(B1, A1)));
+    let board = board_from_fen("4k3/8/8/8/8/8/8/RK6 w Q - 0 1");
+    assert!(board.is_move_legal(&castling_move(B1, A1)));
+  }
+
   // Check escape tests
   #[test]
   fn test_must_escape_check() {
@@ -856,15 +1831,103 @@ mod tests {
   }
 
   // Edge cases
+  #[test]
+  fn test_is_plausible_premove() {
+    // White to move, so every black move here is a premove
+    let board = board_from_fen("r3k2r/pp3ppp/2n5/3pP3/8/8/PPP2PPP/R3K2R w KQkq - 0 1");
+    let premove = |from, to| board.is_plausible_premove(&simple_move(from, to), false);
+    assert!(premove(A7, A5));
+    assert!(premove(A7, A6));
+    // Pawns may premove a capture onto an empty square, but not onto their own piece
+    assert!(premove(F7, E6));
+    assert!(premove(F7, G6));
+    assert!(!premove(F7, G5));
+    assert!(!premove(B7, C6));
+    // Opponent pieces don't block, own pieces do
+    assert!(premove(D5, D4));
+    assert!(!premove(C6, A7));
+    assert!(premove(C6, E5));
+    assert!(!premove(H8, H3));
+    assert!(premove(A8, D8));
+    assert!(!premove(A8, A6));
+    // Castling needs the right and no own piece in the way
+    assert!(board.is_plausible_premove(&PieceMove::new_castling(E8, G8), false));
+    assert!(board.is_plausible_premove(&PieceMove::new_castling(E8, C8), false));
+    // Only the side's own pieces can be premoved, and a pawn on the last rank must promote
+    assert!(!premove(E5, E6));
+    let board = board_from_fen("4k3/8/8/8/8/8/p7/4K3 w - - 0 1");
+    assert!(!board.is_plausible_premove(&simple_move(A2, A1), false));
+    assert!(board.is_plausible_premove(
+      &PieceMove::new(A2, A1, false, Some(PromotionType::Queen)),
+      false
+    ));
+    assert!(board.is_plausible_premove(
+      &PieceMove::new(A2, B1, true, Some(PromotionType::Knight)),
+      false
+    ));
+    // It works for the side to move too, ignoring the opponent's pieces
+    assert!(board.is_plausible_premove(&simple_move(E1, D2), true));
+    assert!(!board.is_plausible_premove(&PieceMove::NULL, true));
+  }
+
+  #[test]
+  fn test_king_square() {
+    let board = GameBoard::START_POS;
+    assert_eq!(board.king_square(true), Some(E1));
+    assert_eq!(board.king_square(false), Some(E8));
+    assert_eq!(board.king_square_unchecked(true), E1);
+    assert_eq!(board.king_square_unchecked(false), E8);
+
+    let mut board = board_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    board.clear_square(E8);
+    assert_eq!(board.king_square(false), None);
+  }
+
+  #[test]
+  fn test_captured_piece() {
+    let game = GameData::from_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+    let board = game.board;
+    assert_eq!(
+      board.captured_piece(&capture_move(A1, A8)),
+      Some(PieceType::Rook)
+    );
+    // En passant takes the pawn beside the empty target square
+    assert_eq!(
+      board.captured_piece(&en_passant_move(E5, D6)),
+      Some(PieceType::Pawn)
+    );
+    assert_eq!(board.captured_piece(&simple_move(E5, E6)), None);
+    // Neither castling nor a move onto a friendly piece takes anything
+    assert_eq!(board.captured_piece(&castling_move(E1, G1)), None);
+    assert_eq!(board.captured_piece(&capture_move(A1, E1)), None);
+
+    // The same for every capture the generator produces
+    let (moves, count) = crate::movegen::generate_moves(&board);
+    for piece_move in &moves[..count] {
+      assert_eq!(
+        board.captured_piece(piece_move).is_some(),
+        piece_move.is_capture(),
+        "{piece_move}"
+      );
+    }
+  }
+
   #[test]
   fn test_null_move_illegal() {
     let board = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
     let null_move = PieceMove::NULL;
     assert!(!board.is_move_legal(&null_move));
+    assert_eq!(
+      board.explain_illegal(&null_move),
+      Some(IllegalMoveReason::NullMove)
+    );
+    // Even with a piece on a1 to "move"
+    let mut game = GameData::START_POS;
+    assert!(game.play_move(&null_move).is_none());
+    assert_eq!(game.history_len(), 0);
   }
 
   #[test]
-  #[should_panic]
   fn test_same_square_move_illegal() {
     let board = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
     let same_square = simple_move(E2, E2);
@@ -899,7 +1962,7 @@ mod tests {
   #[test]
   fn test_en_passant_removes_correct_pawn() {
     let mut board = board_from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 1");
-    board.en_passant = PieceMove::new(D5, D6, false, None); // Set proper en passant target
+    board.en_passant = Some(PieceMove::new(D5, D6, false, None)); // Set proper en passant target
 
     // Before en passant - there should be a black pawn on d5
     assert_eq!(board.get_piece(D5), Some(PieceType::Pawn));
@@ -908,4 +1971,403 @@ mod tests {
     let en_passant = en_passant_move(E5, D6);
     assert!(board.is_move_legal(&en_passant));
   }
+
+  #[test]
+  fn test_arbitrary_moves_never_panic() {
+    let promotions = [
+      None,
+      Some(PromotionType::Queen),
+      Some(PromotionType::Rook),
+      Some(PromotionType::Bishop),
+      Some(PromotionType::Knight),
+    ];
+    let fens = [
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+      "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+      "4k3/8/8/8/8/8/8/4K3 b - - 0 1",
+      // Promoted pieces, beyond what normal material can reach
+      "R6k/8/8/3R4/8/8/8/K6R w - - 0 1",
+      "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1",
+      "Kbnn1kb1/PP1q4/q4q2/2q4q/4q3/1q4q1/3q4/r6r b - - 0 1",
+      "3Q4/1Q4Q1/4Q3/2Q4R/Q4Q2/3Q4/1Q4Rp/1K1BBNNk w - - 0 1",
+      "nnnnk3/nnnn4/8/8/8/8/1PPPP3/BBBBK3 b - - 0 1",
+    ];
+    for fen in fens {
+      let board = board_from_fen(fen);
+      for from in 0..64 {
+        for to in 0..64 {
+          for is_capture in [false, true] {
+            for promotion in promotions {
+              let piece_move = PieceMove::new(from, to, is_capture, promotion);
+              let mut child = board;
+              let legal = board.is_move_legal(&piece_move);
+              assert_eq!(child.move_piece(&piece_move).is_some(), legal);
+              assert_eq!(board.explain_illegal(&piece_move).is_none(), legal);
+            }
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_display_diagram() {
+    let diagram = format!("{}", GameBoard::START_POS);
+    assert_eq!(
+      diagram,
+      "8 r n b q k b n r\n\
+       7 p p p p p p p p\n\
+       6 . . . . . . . .\n\
+       5 . . . . . . . .\n\
+       4 . . . . . . . .\n\
+       3 . . . . . . . .\n\
+       2 P P P P P P P P\n\
+       1 R N B Q K B N R\n  \
+       a b c d e f g h"
+    );
+  }
+
+  #[test]
+  fn test_debug_is_one_line_fen() {
+    assert_eq!(
+      format!("{:?}", GameBoard::START_POS),
+      "GameBoard(rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -; castling rooks: h1 a1 h8 a8)"
+    );
+
+    let board = board_from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    assert_eq!(
+      format!("{board:?}"),
+      "GameBoard(4k3/8/8/3pP3/8/8/8/4K3 w - d6; castling rooks: -; en passant pawn: d5)"
+    );
+  }
+
+  #[test]
+  fn test_piece_char() {
+    let board = GameBoard::START_POS;
+    assert_eq!(board.piece_char(E1), Some('K'));
+    assert_eq!(board.piece_char(D8), Some('q'));
+    assert_eq!(board.piece_char(E4), None);
+    assert_eq!(board.piece_char(64), None);
+  }
+
+  #[test]
+  fn test_pinned_pieces() {
+    // The knight on e2 is pinned by the rook on e8, the bishop on c3 by the queen on a5; the pawn
+    // on f2 has the bishop on h4 behind it but the knight on g3 in front
+    let board = board_from_fen("4r1k1/8/8/q7/7b/2B3n1/4NP2/4K3 w - - 0 1");
+    assert_eq!(board.pinned_pieces(true).raw(), (1 << E2) | (1 << C3));
+    assert_eq!(board.pinned_pieces(false).raw(), 0);
+  }
+
+  #[test]
+  fn test_pinned_needs_single_blocker() {
+    let board = board_from_fen("4r1k1/8/8/8/4P3/8/4N3/4K3 w - - 0 1");
+    assert_eq!(board.pinned_pieces(true).raw(), 0);
+  }
+
+  #[test]
+  fn test_discovered_check_candidates() {
+    // The bishop on d5 blocks our own queen's diagonal from a2 to g8; the knight on e4 blocks
+    // the rook on e1, but the king is not on the e-file
+    let board = board_from_fen("6k1/8/8/3B4/4N3/8/Q7/4R1K1 w - - 0 1");
+    assert_eq!(board.discovered_check_candidates(true).raw(), 1 << D5);
+
+    let board = board_from_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1");
+    assert_eq!(board.discovered_check_candidates(true).raw(), 1 << E4);
+    assert_eq!(board.discovered_check_candidates(false).raw(), 0);
+  }
+
+  #[test]
+  fn test_explain_illegal_basic_rules() {
+    let board = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let explain = |from, to| board.explain_illegal(&simple_move(from, to));
+    assert_eq!(explain(E2, E4), None);
+    assert_eq!(
+      explain(E4, E5),
+      Some(IllegalMoveReason::NoPiece { square: E4 })
+    );
+    assert_eq!(
+      explain(E7, E5),
+      Some(IllegalMoveReason::OpponentsPiece { square: E7 })
+    );
+    assert_eq!(
+      explain(G1, G3),
+      Some(IllegalMoveReason::WrongMovement {
+        piece: PieceType::Knight
+      })
+    );
+    assert_eq!(
+      explain(F1, C4),
+      Some(IllegalMoveReason::Blocked {
+        piece: PieceType::Bishop,
+        blocker: E2
+      })
+    );
+    assert_eq!(
+      explain(G1, E2),
+      Some(IllegalMoveReason::OwnPieceOnTarget { square: E2 })
+    );
+    assert_eq!(
+      explain(E2, E5),
+      Some(IllegalMoveReason::WrongMovement {
+        piece: PieceType::Pawn
+      })
+    );
+    assert_eq!(
+      explain(E2, D3),
+      Some(IllegalMoveReason::PawnCaptureWithoutTarget)
+    );
+    assert_eq!(
+      board.explain_illegal(&promotion_move(E2, E3, PromotionType::Queen)),
+      Some(IllegalMoveReason::UnexpectedPromotion)
+    );
+
+    let board = board_from_fen("7k/P7/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(
+      board.explain_illegal(&simple_move(A7, A8)),
+      Some(IllegalMoveReason::MissingPromotion)
+    );
+  }
+
+  #[test]
+  fn test_explain_illegal_king_safety() {
+    let board = board_from_fen("4k3/8/8/8/8/8/8/R3KN1r w - - 0 1");
+    let reason = board.explain_illegal(&simple_move(F1, E3)).unwrap();
+    assert_eq!(
+      reason,
+      IllegalMoveReason::ExposesKing {
+        attacker: PieceType::Rook,
+        square: H1
+      }
+    );
+    assert_eq!(
+      reason.to_string(),
+      "your king would be in check from the rook on h1"
+    );
+
+    let board = board_from_fen("4k3/8/8/8/8/8/P7/4K2r w - - 0 1");
+    let reason = board.explain_illegal(&simple_move(A2, A3)).unwrap();
+    assert_eq!(
+      reason.to_string(),
+      "your king is in check from the rook on h1"
+    );
+
+    // En passant that empties the rank between the king and a rook
+    let board = board_from_fen("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1");
+    assert_eq!(
+      board.explain_illegal(&en_passant_move(E5, D6)),
+      Some(IllegalMoveReason::ExposesKing {
+        attacker: PieceType::Rook,
+        square: H5
+      })
+    );
+    let board = board_from_fen("8/8/8/K2pP2r/8/8/8/7k w - - 0 1");
+    assert_eq!(
+      board.explain_illegal(&en_passant_move(E5, D6)),
+      Some(IllegalMoveReason::EnPassantUnavailable)
+    );
+  }
+
+  #[test]
+  fn test_explain_illegal_castling() {
+    let board = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(
+      board.explain_illegal(&castling_move(E1, G1)),
+      Some(IllegalMoveReason::CastlingBlocked { blocker: F1 })
+    );
+
+    let board = board_from_fen("4k3/8/8/8/8/8/8/R3K2R w - - 0 1");
+    assert_eq!(
+      board.explain_illegal(&castling_move(E1, G1)),
+      Some(IllegalMoveReason::NoCastlingRight)
+    );
+
+    let board = board_from_fen("4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    assert_eq!(
+      board.explain_illegal(&castling_move(E1, G1)),
+      Some(IllegalMoveReason::CastlingOutOfCheck)
+    );
+
+    let board = board_from_fen("5rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    let reason = board.explain_illegal(&castling_move(E1, G1)).unwrap();
+    assert_eq!(
+      reason,
+      IllegalMoveReason::CastlingThroughCheck { square: F1 }
+    );
+    assert_eq!(
+      reason.message(),
+      "your king can't castle through an attacked square"
+    );
+    assert_eq!(board.explain_illegal(&castling_move(E1, C1)), None);
+  }
+
+  /// What `piece_at` should say, read straight from the bitboards.
+  fn piece_from_bitboards(board: &GameBoard, square: u8) -> Option<(PieceType, bool)> {
+    let pieces = [
+      (board.pawns, PieceType::Pawn),
+      (board.knights, PieceType::Knight),
+      (board.bishops, PieceType::Bishop),
+      (board.rooks, PieceType::Rook),
+      (board.queens, PieceType::Queen),
+      (board.kings, PieceType::King),
+    ];
+    let (_, piece) = pieces
+      .into_iter()
+      .find(|(bitboard, _)| bitboard.get_bit_unchecked(square))?;
+    Some((piece, board.colour.get_bit_unchecked(square)))
+  }
+
+  #[test]
+  fn test_equality_follows_repetition_rules() {
+    use std::collections::HashSet;
+
+    let mut game = GameData::START_POS;
+    for mv in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+      game.play_move(&mv.parse().unwrap()).unwrap();
+    }
+    assert_eq!(game.board, GameBoard::START_POS);
+    let positions: HashSet<GameBoard> = game.replay().map(|(_, _, after)| after.board).collect();
+    assert_eq!(positions.len(), 4);
+    assert!(positions.contains(&GameBoard::START_POS));
+
+    // Nobody can take on e3, so the double push leaves the same position as the plain FEN
+    let mut game = GameData::START_POS;
+    game.play_move(&"e2e4".parse().unwrap()).unwrap();
+    assert!(game.board.en_passant.is_some());
+    assert_eq!(game.board.en_passant_capture_square(), None);
+    let plain = board_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    assert_eq!(game.board, plain);
+
+    // With a pawn on d4 the capture is possible, so the en passant square counts
+    let mut game =
+      GameData::from_fen("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    game.play_move(&"e2e4".parse().unwrap()).unwrap();
+    assert_eq!(game.board.en_passant_capture_square(), Some(E3));
+    let plain = board_from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    assert_ne!(game.board, plain);
+
+    let mut no_castling = GameBoard::START_POS;
+    no_castling.castling = CastlingRights::NONE;
+    assert_ne!(no_castling, GameBoard::START_POS);
+    let mut black_to_move = GameBoard::START_POS;
+    black_to_move.playing = false;
+    assert_ne!(black_to_move, GameBoard::START_POS);
+  }
+
+  #[test]
+  fn test_has_legal_move() {
+    assert!(GameBoard::START_POS.has_legal_move());
+    // Fool's mate
+    let mated = board_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+    assert!(!mated.has_legal_move());
+    let stalemate = board_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+    assert!(!stalemate.has_legal_move());
+    // The king is boxed in, but the pawn can still promote
+    let pawn_move = board_from_fen("7k/5Q2/6K1/8/8/8/p7/8 b - - 0 1");
+    assert!(pawn_move.has_legal_move());
+    assert_eq!(pawn_move.legal_moves_iter().count(), 4);
+  }
+
+  #[test]
+  fn test_piece_at_follows_moves() {
+    let mut board =
+      board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    // Walk a line through castling, captures and promotions, always taking the last legal move
+    for _ in 0..40 {
+      for square in 0..64 {
+        assert_eq!(board.piece_at(square), piece_from_bitboards(&board, square));
+      }
+      let (moves, count) = crate::movegen::generate_moves(&board);
+      let Some(piece_move) = moves[..count].iter().rev().find(|m| board.is_move_legal(m)) else {
+        break;
+      };
+      board.move_piece(piece_move).unwrap();
+    }
+    assert_eq!(board.piece_at(64), None);
+  }
+
+  #[test]
+  fn test_refresh_derived() {
+    let mut board = GameBoard::START_POS;
+    assert_eq!(board.piece_at(E1), Some((PieceType::King, true)));
+    assert_eq!(board.piece_at(D8), Some((PieceType::Queen, false)));
+
+    board.pawns.unset_bit(E2);
+    board.queens.set_bit(E2);
+    board.refresh_derived();
+    assert_eq!(board.get_piece(E2), Some(PieceType::Queen));
+    assert_eq!(board.piece_at(E2), Some((PieceType::Queen, true)));
+
+    // The deprecated name does the same
+    let mut renamed = GameBoard::START_POS;
+    renamed.pawns.unset_bit(E2);
+    renamed.queens.set_bit(E2);
+    #[allow(deprecated)]
+    renamed.refresh_mailbox();
+    assert_eq!(renamed.zobrist_key(), board.zobrist_key());
+    assert_eq!(renamed.piece_at(E2), board.piece_at(E2));
+  }
+
+  #[test]
+  fn test_pieces_of() {
+    let board = board_from_fen("4k3/8/8/3p4/8/8/4P3/4K3 w - - 0 1");
+    assert_eq!(board.white_pieces().raw(), (1 << E1) | (1 << E2));
+    assert_eq!(board.black_pieces().raw(), (1 << E8) | (1 << D5));
+    assert_eq!(board.pieces_of(true).raw(), board.white_pieces().raw());
+    assert_eq!(board.pieces_of(false).raw(), board.black_pieces().raw());
+
+    // The old accessor returns the side opposite to its argument
+    #[allow(deprecated)]
+    let black = board.combined_coloured(true);
+    assert_eq!(black.raw(), board.black_pieces().raw());
+  }
+
+  // The square-by-square walk `is_path_clear` used before the BETWEEN lookup.
+  fn walk_path_clear(board: &GameBoard, from: u8, to: u8) -> bool {
+    let (from_rank, from_file) = ((from / 8) as i8, (from % 8) as i8);
+    let (to_rank, to_file) = ((to / 8) as i8, (to % 8) as i8);
+    let (dr, df) = (
+      (to_rank - from_rank).signum(),
+      (to_file - from_file).signum(),
+    );
+    let (mut r, mut f) = (from_rank + dr, from_file + df);
+    while r != to_rank || f != to_file {
+      if board.combined().get_bit_unchecked((r * 8 + f) as u8) {
+        return false;
+      }
+      r += dr;
+      f += df;
+    }
+    true
+  }
+
+  #[test]
+  fn test_path_clear_matches_walk() {
+    let fens = [
+      "8/8/8/8/8/8/8/8 w - - 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+    for fen in fens {
+      let board = board_from_fen(fen);
+      for from in 0..64u8 {
+        for to in 0..64u8 {
+          let (dr, df) = ((from / 8).abs_diff(to / 8), (from % 8).abs_diff(to % 8));
+          let aligned = from != to && (dr == 0 || df == 0 || dr == df);
+          if aligned {
+            assert_eq!(
+              board.is_path_clear(from, to),
+              walk_path_clear(&board, from, to),
+              "{fen}: {from} -> {to}"
+            );
+          } else {
+            assert!(board.is_path_clear(from, to), "{fen}: {from} -> {to}");
+          }
+        }
+      }
+    }
+  }
 }
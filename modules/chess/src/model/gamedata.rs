@@ -17,9 +17,18 @@
  */
 
 use crate::{
-  errors::FenParseError,
-  model::{gameboard::GameBoard, piecemove::PieceMove},
+  errors::{ChessError, FenParseError, IllegalMoveReason},
+  legal::attack::is_square_attacked,
+  model::{
+    castling::CastlingRights,
+    gameboard::{GameBoard, PieceType},
+    piecemove::{Move16, PieceMove},
+    variant::{CHECKS_TO_WIN, Variant},
+  },
+  san::parse_san,
 };
+#[cfg(feature = "alloc")]
+use crate::model::gameboard::EnPassantFen;
 
 pub const MAX_GAME_MOVES: usize = 1024;
 
@@ -29,6 +38,14 @@ pub struct GameData {
   pub moves: [PieceMove; MAX_GAME_MOVES],
   pub plies: usize,
   pub halfmove_clock: usize,
+  /// Position the recorded history starts from; `moves[0]` is played from here.
+  pub initial_board: GameBoard,
+  pub initial_plies: usize,
+  pub initial_halfmove_clock: usize,
+  pub variant: Variant,
+  /// Checks given by white and by black, counted only in [`Variant::ThreeCheck`].
+  pub checks: [u8; 2],
+  pub initial_checks: [u8; 2],
 }
 
 impl Default for GameData {
@@ -38,6 +55,12 @@ impl Default for GameData {
       moves: [PieceMove::NULL; MAX_GAME_MOVES],
       plies: Default::default(),
       halfmove_clock: Default::default(),
+      initial_board: Default::default(),
+      initial_plies: Default::default(),
+      initial_halfmove_clock: Default::default(),
+      variant: Default::default(),
+      checks: Default::default(),
+      initial_checks: Default::default(),
     }
   }
 }
@@ -51,15 +74,30 @@ impl GameData {
     self.plies >> 1
   }
 
+  /// Parse a FEN. A Three-check count, either lichess's trailing `+W+B` (checks given) or the
+  /// `W+B` field before the clocks (checks remaining), makes the game [`Variant::ThreeCheck`].
   pub fn from_fen(fen: &str) -> Result<Self, FenParseError> {
     let mut parts = fen.split_whitespace();
     let placement = parts.next().ok_or(FenParseError::MalformedFen)?;
     let active_color = parts.next().ok_or(FenParseError::MalformedFen)?;
     let castling = parts.next().ok_or(FenParseError::MalformedFen)?;
     let en_passant = parts.next().ok_or(FenParseError::MalformedFen)?;
-    let halfmove_clock = parts.next().ok_or(FenParseError::MalformedFen)?;
+    let mut halfmove_clock = parts.next().ok_or(FenParseError::MalformedFen)?;
+    let mut checks = None;
+    if halfmove_clock.contains('+') {
+      let [white, black] = parse_checks(halfmove_clock)?;
+      checks = Some([CHECKS_TO_WIN - white, CHECKS_TO_WIN - black]);
+      halfmove_clock = parts.next().ok_or(FenParseError::MalformedFen)?;
+    }
     let fullmove_number = parts.next().ok_or(FenParseError::MalformedFen)?;
 
+    if let Some(given) = parts.next() {
+      let given = given
+        .strip_prefix('+')
+        .filter(|_| checks.is_none())
+        .ok_or(FenParseError::MalformedFen)?;
+      checks = Some(parse_checks(given)?);
+    }
     if parts.next().is_some() {
       return Err(FenParseError::MalformedFen);
     }
@@ -74,7 +112,7 @@ impl GameData {
     for c in placement.chars() {
       match c {
         '1'..='8' => {
-          let empty_squares = c.to_digit(10).unwrap() as usize;
+          let empty_squares = (c as u8 - b'0') as usize;
           i += empty_squares;
           squares += empty_squares;
         }
@@ -88,33 +126,18 @@ impl GameData {
           let is_white = c.is_ascii_uppercase();
           let piece_char_lower = c.to_ascii_lowercase();
 
-          match piece_char_lower {
-            'p' => {
-              board.pawns.set_bit(square_index);
-            }
-            'n' => {
-              board.knights.set_bit(square_index);
-            }
-            'b' => {
-              board.bishops.set_bit(square_index);
-            }
-            'r' => {
-              board.rooks.set_bit(square_index);
-            }
-            'q' => {
-              board.queens.set_bit(square_index);
-            }
-            'k' => {
-              board.kings.set_bit(square_index);
-            }
+          let piece_type = match piece_char_lower {
+            'p' => PieceType::Pawn,
+            'n' => PieceType::Knight,
+            'b' => PieceType::Bishop,
+            'r' => PieceType::Rook,
+            'q' => PieceType::Queen,
+            'k' => PieceType::King,
             _ => return Err(FenParseError::InvalidPieceChar), // Should not be reached with exhaustive match
-          }
-
-          if is_white {
-            board.colour.set_bit(square_index);
-          } else {
-            board.colour.unset_bit(square_index);
-          }
+          };
+          board
+            .set_square(square_index, piece_type, is_white)
+            .ok_or(FenParseError::InvalidPiecePlacement)?;
           i += 1;
           squares += 1;
         }
@@ -152,16 +175,7 @@ impl GameData {
     if castling.len() > 4 {
       return Err(FenParseError::InvalidCastling);
     }
-    for c in castling.chars() {
-      match c {
-        'K' => board.castling |= 0b0001, // White kingside
-        'Q' => board.castling |= 0b0010, // White queenside
-        'k' => board.castling |= 0b0100, // Black kingside
-        'q' => board.castling |= 0b1000, // Black queenside
-        '-' => continue,                 // No castling rights
-        _ => return Err(FenParseError::InvalidCastlingChar),
-      }
-    }
+    board.castling = CastlingRights::from_fen(castling, &board)?;
 
     // 4. En passant target square
     if en_passant.len() > 2 || en_passant.is_empty() {
@@ -264,10 +278,10 @@ impl GameData {
         return Err(FenParseError::InvalidEnPassantContext);
       }
 
-      if board.en_passant != PieceMove::NULL {
+      if board.en_passant.is_some() {
         return Err(FenParseError::InvalidEnPassant);
       }
-      board.en_passant = PieceMove::new(0, square_index, true, None);
+      board.en_passant = Some(PieceMove::new(square_index ^ 8, square_index, false, None));
     }
 
     // 5. Halfmove clock
@@ -289,142 +303,108 @@ impl GameData {
       return Err(FenParseError::InvalidFullmoveNumber);
     }
 
+    let plies = (count - 1) * 2 + if active_color == "b" { 1 } else { 0 };
     Ok(Self {
       board,
       moves: [PieceMove::NULL; MAX_GAME_MOVES],
-      plies: (count - 1) * 2 + if active_color == "b" { 1 } else { 0 },
+      plies,
       halfmove_clock: clock,
+      initial_board: board,
+      initial_plies: plies,
+      initial_halfmove_clock: clock,
+      variant: if checks.is_some() {
+        Variant::ThreeCheck
+      } else {
+        Variant::Standard
+      },
+      checks: checks.unwrap_or_default(),
+      initial_checks: checks.unwrap_or_default(),
     })
   }
 
-  // Add this method to the `impl GameData` block in gamedata.ranks
+  /// Parse one position per line of a dataset, yielding each game or the line number, counting
+  /// from 1 as editors do, and error of a line that failed. Blank lines are skipped but still
+  /// counted.
+  ///
+  /// Lines may be plain FENs or come from the usual dataset formats:
+  ///
+  /// - CSV: only the text before the first comma is read, so `fen,score` rows work.
+  /// - EPD: a line with only the four board fields, or with operations such as `bm Nf3;` in place
+  ///   of the clocks, is read with a halfmove clock of 0 and fullmove number of 1.
+  ///
+  /// EPD lines are completed in one buffer that lives as long as the iterator, so ingesting
+  /// millions of them allocates only once.
   #[cfg(feature = "std")]
-  pub fn to_fen(&self) -> String {
-    let mut fen = String::new();
-
-    // 1. Piece placement
-    // FEN notation starts from rank 8 (index 7) and goes down to rank 1 (index 0)
-    for rank in (0..8).rev() {
-      let mut empty_count = 0;
-      for file in 0..8 {
-        let square = rank * 8 + file;
-        let piece_char = self.get_piece_char(square as u8);
-
-        if let Some(c) = piece_char {
-          if empty_count > 0 {
-            fen.push_str(&empty_count.to_string());
-            empty_count = 0;
+  pub fn from_fen_batch<'a>(
+    lines: impl Iterator<Item = &'a str>,
+  ) -> impl Iterator<Item = Result<GameData, (usize, FenParseError)>> {
+    let mut scratch = std::string::String::new();
+    (1..)
+      .zip(lines)
+      .filter(|(_, line)| !line.trim().is_empty())
+      .map(move |(number, line)| {
+        let fen = line.split(',').next().unwrap_or_default();
+        let mut fields = fen.split_whitespace();
+        let board: [_; 4] = core::array::from_fn(|_| fields.next());
+        // A fifth field that isn't a clock or a check count starts EPD operations
+        let is_epd = fields
+          .next()
+          .is_none_or(|next| !next.starts_with(|c: char| c.is_ascii_digit() || c == '+'));
+        let fen = match board {
+          [
+            Some(placement),
+            Some(colour),
+            Some(castling),
+            Some(en_passant),
+          ] if is_epd => {
+            scratch.clear();
+            for field in [placement, colour, castling, en_passant, "0 1"] {
+              scratch.push_str(field);
+              scratch.push(' ');
+            }
+            scratch.as_str()
           }
-          fen.push(c);
-        } else {
-          empty_count += 1;
-        }
-      }
-
-      // Add any remaining empty squares at end of rank
-      if empty_count > 0 {
-        fen.push_str(&empty_count.to_string());
-      }
-
-      // Add rank separator (unless last rank)
-      if rank > 0 {
-        fen.push('/');
-      }
-    }
-
-    fen.push(' ');
-
-    // 2. Active color
-    fen.push(if self.board.playing { 'w' } else { 'b' });
-    fen.push(' ');
-
-    // 3. Castling availability
-    let mut castling_str = String::new();
-    if self.board.castling & 0b0001 != 0 {
-      castling_str.push('K');
-    }
-    if self.board.castling & 0b0010 != 0 {
-      castling_str.push('Q');
-    }
-    if self.board.castling & 0b0100 != 0 {
-      castling_str.push('k');
-    }
-    if self.board.castling & 0b1000 != 0 {
-      castling_str.push('q');
-    }
-
-    if castling_str.is_empty() {
-      fen.push('-');
-    } else {
-      fen.push_str(&castling_str);
-    }
-    fen.push(' ');
-
-    // 4. En passant target square
-    if self.board.en_passant == PieceMove::NULL {
-      fen.push('-');
-    } else {
-      let sq = self.board.en_passant.to_square();
-      let file = sq % 8;
-      let rank = 1 + (sq / 8);
-      fen.push((b'a' + file) as char);
-      fen.push((b'0' + rank) as char);
-    }
-    fen.push(' ');
+          _ => fen,
+        };
+        GameData::from_fen(fen).map_err(|error| (number, error))
+      })
+  }
 
-    // 5. Halfmove clock
-    fen.push_str(&self.halfmove_clock.to_string());
-    fen.push(' ');
+  /// This game played under `variant`, which FEN cannot express for King of the Hill.
+  pub fn with_variant(mut self, variant: Variant) -> Self {
+    self.variant = variant;
+    self
+  }
 
-    // 6. Fullmove number
-    let fullmove = (self.plies / 2) + 1;
-    fen.push_str(&fullmove.to_string());
+  /// The side, `true` for white, that has won by the variant's own rule: a king on the hill or
+  /// a third check. Checkmate is not included.
+  pub fn variant_winner(&self) -> Option<bool> {
+    self.variant.winner(&self.board, self.checks)
+  }
 
-    fen
+  #[cfg(feature = "alloc")]
+  pub fn to_fen(&self) -> alloc::string::String {
+    self.to_fen_with(EnPassantFen::default())
   }
 
-  // Helper function to get piece character at a square
-  #[cfg(feature = "std")]
-  fn get_piece_char(&self, square: u8) -> Option<char> {
-    if self.board.pawns.get_bit(square).unwrap_or(false) {
-      Some(if self.board.colour.get_bit_unchecked(square) {
-        'P'
-      } else {
-        'p'
-      })
-    } else if self.board.knights.get_bit(square).unwrap_or(false) {
-      Some(if self.board.colour.get_bit_unchecked(square) {
-        'N'
-      } else {
-        'n'
-      })
-    } else if self.board.bishops.get_bit(square).unwrap_or(false) {
-      Some(if self.board.colour.get_bit_unchecked(square) {
-        'B'
-      } else {
-        'b'
-      })
-    } else if self.board.rooks.get_bit(square).unwrap_or(false) {
-      Some(if self.board.colour.get_bit_unchecked(square) {
-        'R'
-      } else {
-        'r'
-      })
-    } else if self.board.queens.get_bit(square).unwrap_or(false) {
-      Some(if self.board.colour.get_bit_unchecked(square) {
-        'Q'
-      } else {
-        'q'
-      })
-    } else if self.board.kings.get_bit(square).unwrap_or(false) {
-      Some(if self.board.colour.get_bit_unchecked(square) {
-        'K'
-      } else {
-        'k'
-      })
-    } else {
-      None
+  /// Like [`to_fen`](Self::to_fen), with `en_passant` choosing when the en passant square is
+  /// written.
+  #[cfg(feature = "alloc")]
+  pub fn to_fen_with(&self, en_passant: EnPassantFen) -> alloc::string::String {
+    use core::fmt::Write;
+
+    let mut fen = alloc::string::String::new();
+    self
+      .board
+      .write_fen_with(&mut fen, en_passant)
+      .expect("Writing to a String cannot fail");
+    let fullmove = (self.plies / 2) + 1;
+    write!(fen, " {} {fullmove}", self.halfmove_clock).expect("Writing to a String cannot fail");
+    if self.variant == Variant::ThreeCheck {
+      let [white, black] = self.checks;
+      write!(fen, " +{white}+{black}").expect("Writing to a String cannot fail");
     }
+    fen
   }
 
   // Helper function to get piece character at a square
@@ -485,7 +465,7 @@ impl GameData {
       for file in 0..8 {
         let sq = (rank * 8 + file) as u8;
         if let Some(c) = match piecetype.as_str() {
-          "ascii" => self.get_piece_char(sq),
+          "ascii" => self.board.piece_char(sq),
           "unicode" => self.get_piece_icon(sq),
           _ => None,
         } {
@@ -510,8 +490,159 @@ impl GameData {
     moves: [PieceMove::NULL; MAX_GAME_MOVES],
     plies: 0,
     halfmove_clock: 0,
+    initial_board: GameBoard::START_POS,
+    initial_plies: 0,
+    initial_halfmove_clock: 0,
+    variant: Variant::Standard,
+    checks: [0; 2],
+    initial_checks: [0; 2],
   };
 
+  /// Number of moves recorded since the initial position.
+  pub fn history_len(&self) -> usize {
+    self.plies.saturating_sub(self.initial_plies)
+  }
+
+  /// Plays `piece_move` if it is legal and records it in the move history, updating the ply
+  /// count and halfmove clock. Returns `None` if the move is illegal or the history is full.
+  pub fn play_move(&mut self, piece_move: &PieceMove) -> Option<()> {
+    let index = self.history_len();
+    if index >= MAX_GAME_MOVES {
+      return None;
+    }
+
+    let resets_clock = piece_move.is_capture()
+      || self.board.get_piece(piece_move.from_square()) == Some(PieceType::Pawn)
+      || self.board.get_piece(piece_move.to_square()).is_some();
+
+    self.board.move_piece(piece_move)?;
+    if self.variant == Variant::ThreeCheck
+      && self
+        .board
+        .king_square(self.board.playing)
+        .is_some_and(|king| is_square_attacked(&self.board, king))
+    {
+      let mover = usize::from(self.board.playing);
+      self.checks[mover] = self.checks[mover].saturating_add(1);
+    }
+    self.moves[index] = *piece_move;
+    self.plies += 1;
+    if resets_clock {
+      self.halfmove_clock = 0;
+    } else {
+      self.halfmove_clock += 1;
+    }
+    Some(())
+  }
+
+  /// The recorded history as [`Move16`]s, for storing a game in two bytes a move.
+  /// [`play_compact`](Self::play_compact) on the initial position plays it back.
+  pub fn compact_moves(&self) -> impl Iterator<Item = Move16> + '_ {
+    self.moves[..self.history_len()]
+      .iter()
+      .map(|&piece_move| Move16::from(piece_move))
+  }
+
+  /// Plays `moves` one after another, restoring each move's flags from the position it is played
+  /// on. Returns `None` at the first illegal or unencodable move, with the moves before it played.
+  pub fn play_compact(&mut self, moves: impl IntoIterator<Item = Move16>) -> Option<()> {
+    for packed in moves {
+      let piece_move = packed.to_move(&self.board)?;
+      self.play_move(&piece_move)?;
+    }
+    Some(())
+  }
+
+  /// Plays the move `san` describes in standard algebraic notation, such as `Nf3`, `exd5`,
+  /// `e8=Q+` or `O-O`.
+  ///
+  /// ```rust
+  /// use lumifox_chess::model::gamedata::GameData;
+  ///
+  /// let mut game = GameData::START_POS;
+  /// for san in ["e4", "e5", "Nf3", "Nc6", "Bb5"] {
+  ///   game.play_san(san)?;
+  /// }
+  /// assert_eq!(game.history_len(), 5);
+  /// # Ok::<(), lumifox_chess::errors::ChessError>(())
+  /// ```
+  pub fn play_san(&mut self, san: &str) -> Result<(), ChessError> {
+    let piece_move = parse_san(&self.board, san)?;
+    self.play_move(&piece_move).ok_or(ChessError::HistoryFull)
+  }
+
+  /// Plays `uci`, a move in UCI notation such as `g1f3` or `e7e8q`. Castling may be written as
+  /// the king's two-square move (`e1g1`) or as the king taking its rook (`e1h1`). An illegal
+  /// move fails with the reason [`GameBoard::explain_illegal`] gives.
+  pub fn play_uci(&mut self, uci: &str) -> Result<(), ChessError> {
+    let parsed = PieceMove::from_uci(uci, &self.board)?;
+    let rook = self.board.castling_rook(&parsed);
+    let found = self.board.legal_moves_iter().find(|candidate| {
+      candidate.from_square() == parsed.from_square()
+        && (candidate.to_square() == parsed.to_square()
+          || rook.is_some() && self.board.castling_rook(candidate) == rook)
+        && candidate.promotion_type() == parsed.promotion_type()
+    });
+    let Some(piece_move) = found else {
+      // `from_uci` made sure a piece of the side to move stands on the from square
+      let piece = self
+        .board
+        .get_piece(parsed.from_square())
+        .unwrap_or(PieceType::Pawn);
+      let reason = self
+        .board
+        .explain_illegal(&parsed)
+        .unwrap_or(IllegalMoveReason::WrongMovement { piece });
+      return Err(ChessError::IllegalMove(reason));
+    };
+    self.play_move(&piece_move).ok_or(ChessError::HistoryFull)
+  }
+
+  /// Returns a copy of this game rewound to the initial position, with an empty history.
+  fn rewound(&self) -> GameData {
+    GameData {
+      board: self.initial_board,
+      moves: [PieceMove::NULL; MAX_GAME_MOVES],
+      plies: self.initial_plies,
+      halfmove_clock: self.initial_halfmove_clock,
+      checks: self.initial_checks,
+      ..*self
+    }
+  }
+
+  /// Replays the recorded history from the initial position and returns the board after `ply`
+  /// moves, so `position_at(0)` is the initial position and `position_at(self.history_len())`
+  /// is the current one. Returns `None` if `ply` is past the end of the history.
+  pub fn position_at(&self, ply: usize) -> Option<GameBoard> {
+    if ply > self.history_len() {
+      return None;
+    }
+    match ply {
+      0 => Some(self.initial_board),
+      _ => self.replay().nth(ply - 1).map(|(_, _, game)| game.board),
+    }
+  }
+
+  /// Iterates over the recorded history, yielding the ply number (as used by
+  /// [`GameData::position_at`]), the move played and the game state after that move.
+  pub fn replay(&self) -> Replay<'_> {
+    Replay {
+      game: self,
+      current: self.rewound(),
+      ply: 0,
+    }
+  }
+
+  /// Like [`GameData::replay`], but yields the FEN of the position after each move.
+  #[cfg(feature = "alloc")]
+  pub fn fen_history(
+    &self,
+  ) -> impl Iterator<Item = (usize, PieceMove, alloc::string::String)> + '_ {
+    self
+      .replay()
+      .map(|(ply, piece_move, game)| (ply, piece_move, game.to_fen()))
+  }
+
   /// Creates a new PieceMove from the given from and to squares, automatically determining
   /// if it's a capture or castling based on the current board state.
   pub fn new_move(&self, from: u8, to: u8) -> PieceMove {
@@ -535,34 +666,61 @@ impl GameData {
 
   /// Checks if the move is a castling move based on the from/to squares and castling rights.
   fn is_castling_move(&self, from: u8, to: u8) -> bool {
-    // Must be moving a king
-    if !self.board.kings.get_bit(from).unwrap_or(false) {
-      return false;
-    }
+    self
+      .board
+      .castling_rook(&PieceMove::new_castling(from, to))
+      .is_some()
+  }
+}
 
-    if self.board.playing {
-      // White to move
-      match (from, to) {
-        (4, 6) => (self.board.castling & 0b0001) != 0, // Kingside: e1 to g1, K right
-        (4, 2) => (self.board.castling & 0b0010) != 0, // Queenside: e1 to c1, Q right
-        _ => false,
-      }
-    } else {
-      // Black to move
-      match (from, to) {
-        (60, 62) => (self.board.castling & 0b0100) != 0, // Kingside: e8 to g8, k right
-        (60, 58) => (self.board.castling & 0b1000) != 0, // Queenside: e8 to c8, q right
-        _ => false,
-      }
+/// Iterator over the moves of a [`GameData`], created by [`GameData::replay`].
+pub struct Replay<'a> {
+  game: &'a GameData,
+  current: GameData,
+  ply: usize,
+}
+
+/// Parse the two counts of a `W+B` Three-check field.
+fn parse_checks(field: &str) -> Result<[u8; 2], FenParseError> {
+  let (white, black) = field
+    .split_once('+')
+    .ok_or(FenParseError::InvalidCheckCount)?;
+  let parse = |count: &str| {
+    count
+      .parse::<u8>()
+      .ok()
+      .filter(|&count| count <= CHECKS_TO_WIN)
+      .ok_or(FenParseError::InvalidCheckCount)
+  };
+  Ok([parse(white)?, parse(black)?])
+}
+
+impl Iterator for Replay<'_> {
+  type Item = (usize, PieceMove, GameData);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.ply >= self.game.history_len() {
+      return None;
+    }
+    let piece_move = self.game.moves[self.ply];
+    // A move that does not replay means the history was edited by hand; stop there
+    if self.current.play_move(&piece_move).is_none() {
+      self.ply = self.game.history_len();
+      return None;
     }
+    self.ply += 1;
+    Some((self.ply, piece_move, self.current))
   }
 }
 
 #[cfg(test)]
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 mod tests {
   use super::*;
-  use crate::errors::FenParseError;
+  use crate::{
+    constants::D7,
+    errors::{FenParseError, MoveParseError, SanError},
+  };
 
   /// Helper function to test FEN round-tripping.
   /// It parses a FEN, generates a new FEN from the result,
@@ -574,6 +732,36 @@ mod tests {
     assert_eq!(fen, new_fen);
   }
 
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_from_fen_batch() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let dataset = [
+      start,
+      "",
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 3 10,0.25",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4; id \"start\";",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w",
+    ];
+    let games: Vec<_> = GameData::from_fen_batch(dataset.into_iter()).collect();
+    assert_eq!(games.len(), 6);
+    assert_eq!(games[0].as_ref().unwrap().to_fen(), start);
+    let kiwipete = games[1].as_ref().unwrap();
+    assert_eq!((kiwipete.halfmove_clock, kiwipete.plies), (3, 18));
+    assert_eq!(games[2].as_ref().unwrap().to_fen(), start);
+    assert_eq!(games[3].as_ref().unwrap().to_fen(), start);
+    assert_eq!(
+      games[4].as_ref().err(),
+      Some(&(6, FenParseError::UnexpectedCharacter))
+    );
+    assert_eq!(
+      games[5].as_ref().err(),
+      Some(&(7, FenParseError::MalformedFen))
+    );
+  }
+
   #[test]
   fn test_fen_roundtrip_startpos() {
     fen_roundtrip_test("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
@@ -612,6 +800,13 @@ mod tests {
     fen_roundtrip_test("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 55");
   }
 
+  #[test]
+  fn test_fen_roundtrip_chess960() {
+    fen_roundtrip_test("bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w KQkq - 0 1");
+    // Inner rooks are named by file
+    fen_roundtrip_test("1r2k1r1/8/8/8/8/8/8/1RR1K1R1 w KCkq - 0 1");
+  }
+
   #[test]
   fn test_fen_roundtrip_only_kings() {
     fen_roundtrip_test("8/k7/8/8/8/8/7K/8 w - - 0 1");
@@ -768,4 +963,230 @@ mod tests {
       GameData::from_fen("rnbqkbnr/ppppp1pp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 2").is_ok()
     );
   }
+
+  fn play(game: &mut GameData, moves: &[&str]) {
+    for mv in moves {
+      let piece_move = mv.parse::<PieceMove>().unwrap();
+      game
+        .play_move(&piece_move)
+        .unwrap_or_else(|| panic!("illegal move {mv}"));
+    }
+  }
+
+  #[test]
+  fn test_position_at_replays_history() {
+    let mut game = GameData::START_POS;
+    play(&mut game, &["e2e4", "e7e5", "g1f3", "b8c6"]);
+    assert_eq!(game.history_len(), 4);
+    assert_eq!(game.plies, 4);
+    assert_eq!(game.halfmove_clock, 2);
+
+    let start = game.position_at(0).unwrap();
+    assert_eq!(
+      start.combined().raw(),
+      GameBoard::START_POS.combined().raw()
+    );
+    assert!(start.playing);
+    let current = game.position_at(4).unwrap();
+    assert_eq!(current.combined().raw(), game.board.combined().raw());
+    assert_eq!(current.knights.raw(), game.board.knights.raw());
+    assert!(game.position_at(5).is_none());
+
+    let after_e4 = game.position_at(1).unwrap();
+    assert_eq!(after_e4.get_piece(28), Some(PieceType::Pawn));
+    assert!(!after_e4.playing);
+  }
+
+  #[test]
+  fn test_history_len_with_edited_plies() {
+    // The fields are public; a ply count set below the initial one means no history, not a panic
+    let mut game = GameData::START_POS;
+    game.initial_plies = 10;
+    game.plies = 4;
+    assert_eq!(game.history_len(), 0);
+    assert_eq!(
+      game.position_at(0).unwrap().combined(),
+      game.board.combined()
+    );
+  }
+
+  #[test]
+  fn test_fen_history_from_fen() {
+    let mut game =
+      GameData::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    play(&mut game, &["d7d5", "e4d5", "d8d5"]);
+
+    let history: Vec<_> = game.fen_history().collect();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].0, 1);
+    assert_eq!(history[0].1, "d7d5".parse().unwrap());
+    assert_eq!(
+      history[0].2,
+      "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+    );
+    assert_eq!(
+      history[2].2,
+      "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3"
+    );
+    assert_eq!(history[2].2, game.to_fen());
+  }
+
+  #[test]
+  fn test_en_passant_field() {
+    // Nothing can take on d6
+    let mut game =
+      GameData::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    play(&mut game, &["d7d5"]);
+    assert_eq!(
+      game.to_fen(),
+      "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+    );
+    assert_eq!(
+      game.to_fen_with(EnPassantFen::Always),
+      "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"
+    );
+
+    // The e5 pawn can, so both conventions write the square, and it parses back
+    let mut game =
+      GameData::from_fen("rnbqkbnr/pppppppp/8/4P3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    play(&mut game, &["d7d5"]);
+    let fen = "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+    assert_eq!(game.to_fen(), fen);
+    assert_eq!(game.to_fen_with(EnPassantFen::Always), fen);
+    assert_eq!(GameData::from_fen(fen).unwrap().to_fen(), fen);
+
+    // A pinned pawn cannot take, so the strict writer leaves the square out
+    let mut game = GameData::from_fen("4k3/3p4/8/K3P2r/8/8/8/8 b - - 0 1").unwrap();
+    play(&mut game, &["d7d5"]);
+    assert_eq!(game.to_fen(), "4k3/8/8/K2pP2r/8/8/8/8 w - - 0 2");
+  }
+
+  #[test]
+  fn test_compact_moves_play_back() {
+    let mut game = GameData::START_POS;
+    for mv in [
+      "e2e4", "d7d5", "e4d5", "g8f6", "f1b5", "c7c6", "d5c6", "b8d7",
+    ] {
+      let parsed = mv.parse::<PieceMove>().unwrap();
+      let piece_move = game.new_move(parsed.from_square(), parsed.to_square());
+      game.play_move(&piece_move).unwrap();
+    }
+    assert!(game.moves[2].is_capture());
+    let packed: Vec<_> = game.compact_moves().collect();
+    assert_eq!(packed.len(), 8);
+
+    let mut replayed = GameData::START_POS;
+    assert!(replayed.play_compact(packed.iter().copied()).is_some());
+    assert_eq!(replayed.to_fen(), game.to_fen());
+    assert_eq!(
+      replayed.moves[..8],
+      game.moves[..8],
+      "capture flags are restored"
+    );
+
+    let mut stopped = GameData::START_POS;
+    let illegal = Move16::from("e2e5".parse::<PieceMove>().unwrap());
+    assert!(stopped.play_compact([packed[0], illegal]).is_none());
+    assert_eq!(stopped.history_len(), 1);
+  }
+
+  #[test]
+  fn test_play_move_rejects_illegal_move() {
+    let mut game = GameData::START_POS;
+    assert!(game.play_move(&"e2e5".parse().unwrap()).is_none());
+    assert_eq!(game.history_len(), 0);
+    assert_eq!(game.replay().count(), 0);
+  }
+
+  #[test]
+  fn test_play_san_and_uci() {
+    let mut game = GameData::START_POS;
+    game.play_san("e4").unwrap();
+    game.play_uci("e7e5").unwrap();
+    game.play_san("Nf3").unwrap();
+    game.play_uci("b8c6").unwrap();
+    game.play_san("Bc4").unwrap();
+    game.play_san("Nf6").unwrap();
+    // Castling in the king-takes-rook form, recorded as the generator writes it
+    game.play_uci("e1h1").unwrap();
+    assert_eq!(game.moves[6].to_string(), "e1g1");
+    assert_eq!(game.history_len(), 7);
+
+    assert_eq!(
+      game.play_san("Ke2"),
+      Err(ChessError::San(SanError::NoMatchingMove))
+    );
+    assert_eq!(
+      game.play_uci("e9e7"),
+      Err(ChessError::Uci(MoveParseError::OutOfBounds))
+    );
+    assert_eq!(
+      game.play_uci("d8d6"),
+      Err(ChessError::IllegalMove(IllegalMoveReason::Blocked {
+        piece: PieceType::Queen,
+        blocker: D7
+      }))
+    );
+    assert_eq!(game.history_len(), 7);
+    assert_eq!(
+      game.play_uci("d8d6").unwrap_err().to_string(),
+      "illegal move: the queen is blocked by the piece on d7"
+    );
+  }
+
+  #[test]
+  fn test_three_check() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let game = GameData::from_fen(&format!("{start} +0+0")).unwrap();
+    assert_eq!(game.variant, Variant::ThreeCheck);
+    assert_eq!(game.to_fen(), format!("{start} +0+0"));
+    assert_eq!(
+      GameData::from_fen(start).unwrap().variant,
+      Variant::Standard
+    );
+
+    // The count before the clocks is of checks remaining
+    let remaining = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1+3 0 2";
+    let game = GameData::from_fen(remaining).unwrap();
+    assert_eq!(game.checks, [2, 0]);
+    assert_eq!(
+      game.to_fen(),
+      "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2 +2+0"
+    );
+
+    // Bb5+ is white's third check
+    let mut game = game;
+    assert_eq!(game.variant_winner(), None);
+    play(&mut game, &["d2d4", "d7d6", "f1b5"]);
+    assert_eq!(game.checks, [3, 0]);
+    assert_eq!(game.variant_winner(), Some(true));
+    assert_eq!(game.rewound().checks, [2, 0]);
+
+    for fen in [
+      format!("{start} +4+0"),
+      format!("{start} +1"),
+      "8/8/8/8/8/8/8/K6k w - - +0+0 0 1".into(),
+    ] {
+      assert!(GameData::from_fen(&fen).is_err(), "{fen}");
+    }
+    assert_eq!(
+      GameData::from_fen(&format!("{start} 0+0")).err(),
+      Some(FenParseError::MalformedFen)
+    );
+  }
+
+  #[test]
+  fn test_king_of_the_hill() {
+    let mut game = GameData::from_fen("8/8/3k4/8/8/8/8/4K3 w - - 0 1")
+      .unwrap()
+      .with_variant(Variant::KingOfTheHill);
+    play(&mut game, &["e1e2", "d6d5"]);
+    assert_eq!(game.variant_winner(), Some(false));
+    // The same moves mean nothing in standard chess
+    let standard = GameData {
+      variant: Variant::Standard,
+      ..game
+    };
+    assert_eq!(standard.variant_winner(), None);
+  }
 }
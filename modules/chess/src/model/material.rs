@@ -0,0 +1,236 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Material signatures: how many of each piece both sides have.
+//!
+//! A [`MaterialSig`] ignores where the pieces stand, which is all that adjudication, tablebase
+//! probing and endgame evaluation need to decide whether a position is theirs to handle. It also
+//! gives the game phase, from the opening's full set of pieces down to bare pawn endings.
+
+use core::{fmt, str::FromStr};
+
+use super::gameboard::{GameBoard, PieceType};
+use crate::errors::MaterialParseError;
+
+/// Piece types in the order [`MaterialSig`] counts them, strongest first as in endgame names.
+const PIECES: [(PieceType, char); 6] = [
+  (PieceType::King, 'K'),
+  (PieceType::Queen, 'Q'),
+  (PieceType::Rook, 'R'),
+  (PieceType::Bishop, 'B'),
+  (PieceType::Knight, 'N'),
+  (PieceType::Pawn, 'P'),
+];
+
+/// [`MaterialSig::phase`] with every piece of the starting position still on the board.
+pub const MAX_PHASE: u8 = 24;
+
+/// Number of pieces of each type for each side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MaterialSig {
+  /// Counts indexed by `PieceType as usize`.
+  white: [u8; 6],
+  black: [u8; 6],
+}
+
+impl MaterialSig {
+  /// Count the pieces on `board`.
+  pub fn of(board: &GameBoard) -> Self {
+    let mut sig = Self::default();
+    for (bitboard, piece) in [
+      (board.pawns, PieceType::Pawn),
+      (board.knights, PieceType::Knight),
+      (board.bishops, PieceType::Bishop),
+      (board.rooks, PieceType::Rook),
+      (board.queens, PieceType::Queen),
+      (board.kings, PieceType::King),
+    ] {
+      sig.white[piece as usize] = (bitboard & board.white_pieces()).count() as u8;
+      sig.black[piece as usize] = (bitboard & board.black_pieces()).count() as u8;
+    }
+    sig
+  }
+
+  /// How many pieces of type `piece` the side given by `white` has.
+  pub fn count(&self, piece: PieceType, white: bool) -> u8 {
+    if white {
+      self.white[piece as usize]
+    } else {
+      self.black[piece as usize]
+    }
+  }
+
+  /// Number of pieces on the board, kings and pawns included.
+  pub fn men(&self) -> u8 {
+    self.white.iter().chain(&self.black).sum()
+  }
+
+  /// Whether the position has few enough pieces, kings included, to be in a `men`-piece
+  /// tablebase.
+  pub fn is_tablebase_position(&self, men: u8) -> bool {
+    self.men() <= men
+  }
+
+  /// Whether the side given by `white` has anything besides its king and pawns.
+  pub fn has_non_pawn_material(&self, white: bool) -> bool {
+    [
+      PieceType::Knight,
+      PieceType::Bishop,
+      PieceType::Rook,
+      PieceType::Queen,
+    ]
+    .into_iter()
+    .any(|piece| self.count(piece, white) > 0)
+  }
+
+  /// Game phase from [`MAX_PHASE`] with all the minor and major pieces of the opening still on
+  /// the board, down to 0 when only kings and pawns are left. Knights and bishops weigh 1, rooks
+  /// 2 and queens 4; extra pieces from promotions are capped at `MAX_PHASE`.
+  pub fn phase(&self) -> u8 {
+    let weights = [
+      (PieceType::Knight, 1),
+      (PieceType::Bishop, 1),
+      (PieceType::Rook, 2),
+      (PieceType::Queen, 4),
+    ];
+    let phase: u32 = weights
+      .into_iter()
+      .map(|(piece, weight)| weight * u32::from(self.count(piece, true) + self.count(piece, false)))
+      .sum();
+    phase.min(u32::from(MAX_PHASE)) as u8
+  }
+
+  /// The signature with the colours swapped.
+  pub fn mirrored(&self) -> Self {
+    Self {
+      white: self.black,
+      black: self.white,
+    }
+  }
+}
+
+/// The usual endgame name, white first: `KQvK`, `KRPvKR`.
+impl fmt::Display for MaterialSig {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (white, separator) in [(true, "v"), (false, "")] {
+      for (piece, letter) in PIECES {
+        for _ in 0..self.count(piece, white) {
+          write!(f, "{letter}")?;
+        }
+      }
+      f.write_str(separator)?;
+    }
+    Ok(())
+  }
+}
+
+/// Parses the [`Display`](fmt::Display) form back, with the letters of each side in any order.
+impl FromStr for MaterialSig {
+  type Err = MaterialParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (white, black) = s
+      .split_once('v')
+      .ok_or(MaterialParseError::MissingSeparator)?;
+    let mut sig = Self::default();
+    for (letters, counts) in [(white, &mut sig.white), (black, &mut sig.black)] {
+      for c in letters.chars() {
+        let (piece, _) =
+          PIECES
+            .into_iter()
+            .find(|&(_, letter)| letter == c)
+            .ok_or(if c == 'v' {
+              MaterialParseError::MissingSeparator
+            } else {
+              MaterialParseError::InvalidPiece(c)
+            })?;
+        counts[piece as usize] = counts[piece as usize].saturating_add(1);
+      }
+    }
+    Ok(sig)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::gamedata::GameData;
+
+  fn sig(fen: &str) -> MaterialSig {
+    GameData::from_fen(fen).unwrap().board.material_signature()
+  }
+
+  #[test]
+  fn test_start_position() {
+    let sig = GameBoard::START_POS.material_signature();
+    assert_eq!(sig.men(), 32);
+    assert_eq!(sig.count(PieceType::Pawn, true), 8);
+    assert_eq!(sig.count(PieceType::Bishop, false), 2);
+    assert_eq!(sig.phase(), MAX_PHASE);
+    assert!(!sig.is_tablebase_position(7));
+    assert_eq!(sig.to_string(), "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP");
+  }
+
+  #[test]
+  fn test_endgames() {
+    let sig = sig("8/8/8/4k3/8/8/3P4/3RK2r w - - 0 1");
+    assert_eq!(sig.to_string(), "KRPvKR");
+    assert_eq!(sig.men(), 5);
+    assert!(sig.is_tablebase_position(5));
+    assert!(!sig.is_tablebase_position(4));
+    assert_eq!(sig.phase(), 4);
+    assert_eq!(sig.mirrored().to_string(), "KRvKRP");
+
+    let pawns = self::sig("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1");
+    assert_eq!(pawns.phase(), 0);
+    assert!(!pawns.has_non_pawn_material(true));
+    assert!(sig.has_non_pawn_material(false));
+  }
+
+  #[test]
+  fn test_promoted_pieces_cap_phase() {
+    let sig = sig("QQQQQQQk/8/8/8/8/8/8/K7 w - - 0 1");
+    assert_eq!(sig.count(PieceType::Queen, true), 7);
+    assert_eq!(sig.phase(), MAX_PHASE);
+  }
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(
+      "KRPvKR".parse(),
+      Ok(sig("8/8/8/4k3/8/8/3P4/3RK2r w - - 0 1"))
+    );
+    assert_eq!(
+      "KPRvKR".parse::<MaterialSig>().unwrap().to_string(),
+      "KRPvKR"
+    );
+    assert_eq!("KvK".parse::<MaterialSig>().unwrap().men(), 2);
+    assert_eq!(
+      "KQK".parse::<MaterialSig>(),
+      Err(MaterialParseError::MissingSeparator)
+    );
+    assert_eq!(
+      "KvKvK".parse::<MaterialSig>(),
+      Err(MaterialParseError::MissingSeparator)
+    );
+    assert_eq!(
+      "KXvK".parse::<MaterialSig>(),
+      Err(MaterialParseError::InvalidPiece('X'))
+    );
+  }
+}
@@ -20,15 +20,31 @@
 //!
 //! This module contains the core data structures used throughout the crate:
 //! - `bitboard` — compact bitboard helpers and masks
+//! - `castling` — castling rights, tracked by rook square (standard chess and Chess960), and
+//!   whether castling is possible right now
+//! - `clock` — remaining time and increments for both sides, and time controls run move by move
+//! - `diagram` — boards read from pasted ASCII or Unicode diagrams
 //! - `gameboard` — the primary GameBoard structure and helpers (startpos, FEN)
 //! - `gamedata` — additional metadata for positions
-//! - `piecemove` — compact move representation used by the move generator
+//! - `material` — piece counts per side, game phase and endgame names
+//! - `piecemove` — compact move representation used by the move generator, and `Move16` for storage
+//! - `snapshot` — whole games saved for adjournment and restored exactly (requires `alloc`)
 //! - `rays` — precomputed directional ray bitboards used by sliding pieces
+//! - `status` — whether a game is over, with forced and claimable draws told apart
+//! - `variant` — King of the Hill and Three-check win conditions
 //!
 //! These types are intentionally low-level and designed for performance.
 
 pub mod bitboard;
+pub mod castling;
+pub mod clock;
+pub mod diagram;
 pub mod gameboard;
 pub mod gamedata;
+pub mod material;
 pub mod piecemove;
 pub mod rays;
+#[cfg(feature = "alloc")]
+pub mod snapshot;
+pub mod status;
+pub mod variant;
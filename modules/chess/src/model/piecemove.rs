@@ -27,11 +27,39 @@ use core::{
   str::FromStr,
 };
 
-use crate::errors::MoveParseError;
+use crate::{
+  errors::MoveParseError,
+  model::gameboard::{GameBoard, PieceType},
+};
+
+/// Parse a square in algebraic notation, such as `e4`.
+pub fn parse_square(s: &str) -> Option<u8> {
+  let &[file, rank] = s.as_bytes() else {
+    return None;
+  };
+  let file = file.wrapping_sub(b'a');
+  let rank = rank.wrapping_sub(b'1');
+  (file < 8 && rank < 8).then_some(rank * 8 + file)
+}
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)] // Added traits for easier use with arrays/debugging
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct PieceMove(u16);
 
+/// Canonical order: by from square, then to square, then promotion (none first, then queen, rook,
+/// bishop, knight), then quiet before capture. This is part of the API and does not depend on
+/// how moves are packed, so sorted move lists stay stable across encoding changes.
+impl Ord for PieceMove {
+  fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+    self.sort_key().cmp(&other.sort_key())
+  }
+}
+
+impl PartialOrd for PieceMove {
+  fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
 impl Debug for PieceMove {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     if *self == PieceMove::NULL {
@@ -110,8 +138,17 @@ impl FromStr for PieceMove {
     let from_square = from_rank_idx * 8 + from_file_idx;
     let to_square = to_rank_idx * 8 + to_file_idx;
 
-    let promotion_type = if s.len() > 4 {
-      match s.chars().nth(4).unwrap().to_ascii_lowercase() {
+    let promotes = s.len() > 4;
+    if promotes && !matches!((from_rank_idx, to_rank_idx), (6, 7) | (1, 0)) {
+      return Err(MoveParseError::InvalidPromotionRank);
+    }
+
+    let promotion_type = if promotes {
+      let promotion = s
+        .chars()
+        .nth(4)
+        .ok_or(MoveParseError::InvalidPromotionPiece)?;
+      match promotion.to_ascii_lowercase() {
         'q' => Some(PromotionType::Queen),
         'r' => Some(PromotionType::Rook),
         'b' => Some(PromotionType::Bishop),
@@ -153,7 +190,42 @@ pub enum PromotionType {
 }
 
 impl PieceMove {
-  pub const NULL: PieceMove = PieceMove(0); // Represents an invalid or null move
+  /// Parse a move in UCI notation and check it against `board`: the side to move must have a
+  /// piece on the from square, only pawns may promote, and a pawn reaching the last rank must.
+  ///
+  /// The returned move carries no capture, castling or en passant flags; match it against the
+  /// generated moves to get those.
+  pub fn from_uci(s: &str, board: &GameBoard) -> Result<Self, MoveParseError> {
+    let piece_move = PieceMove::from_str(s)?;
+    let from = piece_move.from_square();
+    if !board
+      .pieces_of(board.playing)
+      .get_bit(from)
+      .unwrap_or(false)
+    {
+      return Err(MoveParseError::NoPieceOnSquare);
+    }
+
+    let is_pawn = board.get_piece(from) == Some(PieceType::Pawn);
+    let last_rank = if board.playing { 7 } else { 0 };
+    match (is_pawn, piece_move.is_promotion()) {
+      (false, true) => Err(MoveParseError::PromotionByNonPawn),
+      (true, false) if piece_move.to_square() / 8 == last_rank => {
+        Err(MoveParseError::MissingPromotion)
+      }
+      (true, true) if piece_move.to_square() / 8 != last_rank => {
+        Err(MoveParseError::InvalidPromotionRank)
+      }
+      _ => Ok(piece_move),
+    }
+  }
+
+  /// No move: the filler of fixed-size move arrays such as [`GameData::moves`] past the history.
+  /// APIs that may have no move take or return `Option<PieceMove>` instead, and the legality
+  /// checks reject `NULL`, so it is never played.
+  ///
+  /// [`GameData::moves`]: crate::model::gamedata::GameData::moves
+  pub const NULL: PieceMove = PieceMove(0);
 
   /// Creates a new PieceMove.
   ///
@@ -162,19 +234,15 @@ impl PieceMove {
   /// * `to` - The destination square (0-63).
   /// * `is_capture` - True if the move is a capture.
   /// * `promotion_type` - Optional promotion type. If Some, `is_promotion` flag will be set.
+  ///
+  /// Squares are taken modulo 64. Any combination can be built, including a move that starts and
+  /// ends on the same square; such moves are simply never legal.
   pub fn new(from: u8, to: u8, is_capture: bool, promotion_type: Option<PromotionType>) -> Self {
-    debug_assert!(from < 64, "From square must be between 0 and 63");
-    debug_assert!(to < 64, "To square must be between 0 and 63");
-    debug_assert!(
-      from != to,
-      "From and to squares must be different for a valid move"
-    );
-
     let mut move_value: u16 = 0;
 
     // Pack from and to squares
-    move_value |= (from as u16) << FROM_SQUARE_SHIFT; // Bits 0-5
-    move_value |= (to as u16) << TO_SQUARE_SHIFT; // Bits 6-11
+    move_value |= (from as u16 & FROM_SQUARE_MASK) << FROM_SQUARE_SHIFT; // Bits 0-5
+    move_value |= (to as u16 & TO_SQUARE_MASK) << TO_SQUARE_SHIFT; // Bits 6-11
 
     // Pack flags
     if is_capture {
@@ -189,6 +257,22 @@ impl PieceMove {
     PieceMove(move_value)
   }
 
+  /// Key for the canonical order. The raw bits come last, so moves that differ only in bits
+  /// outside the named fields still compare unequal, as `Eq` requires.
+  fn sort_key(&self) -> (u8, u8, u8, bool, u16) {
+    let promotion = match self.promotion_type() {
+      None => 0,
+      Some(promotion) => promotion as u8 + 1,
+    };
+    (
+      self.from_square(),
+      self.to_square(),
+      promotion,
+      self.is_capture(),
+      self.0,
+    )
+  }
+
   /// Creates a new Castling move.
   /// Castling moves are special and do not fit the general capture/promotion scheme.
   /// You might need specific flags for these if they are represented in PieceMove.
@@ -330,3 +414,247 @@ impl Default for PieceMove {
     PieceMove::NULL
   }
 }
+
+/// A move packed for storage: from square in bits 0-5, to square in bits 6-11 and a flag nibble
+/// in bits 12-15, which is 0 for a plain move and 4 + [`PromotionType`] for a promotion.
+///
+/// Unlike [`PieceMove`] it has no capture flag, so the same move always packs to the same bits.
+/// The flag is restored from the position with [`Move16::to_move`], which makes the pair lossless
+/// for the moves the generator produces on that position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move16(u16);
+
+const MOVE16_PROMOTION_FLAG: u16 = 4 << 12;
+
+impl Move16 {
+  pub const NULL: Move16 = Move16(0);
+
+  /// The move with the raw bits `bits`, as returned by [`bits`](Self::bits).
+  pub const fn from_bits(bits: u16) -> Self {
+    Move16(bits)
+  }
+
+  pub const fn bits(self) -> u16 {
+    self.0
+  }
+
+  #[inline]
+  pub const fn from_square(self) -> u8 {
+    (self.0 & 0x3F) as u8
+  }
+
+  #[inline]
+  pub const fn to_square(self) -> u8 {
+    ((self.0 >> 6) & 0x3F) as u8
+  }
+
+  /// The promotion piece, or `None` when the flag nibble is not a promotion.
+  pub const fn promotion_type(self) -> Option<PromotionType> {
+    if self.0 & MOVE16_PROMOTION_FLAG == 0 {
+      return None;
+    }
+    Some(match (self.0 >> 12) & 3 {
+      0 => PromotionType::Queen,
+      1 => PromotionType::Rook,
+      2 => PromotionType::Bishop,
+      _ => PromotionType::Knight,
+    })
+  }
+
+  /// The [`PieceMove`] this move stands for on `board`, with the capture flag set when the side
+  /// to move takes an enemy piece on the to square or captures en passant. Returns `None` for bits
+  /// no move packs to: the same from and to square, such as [`Move16::NULL`], or a flag nibble
+  /// of 1 to 3.
+  pub fn to_move(self, board: &GameBoard) -> Option<PieceMove> {
+    let (from, to) = (self.from_square(), self.to_square());
+    let flags = self.0 >> 12;
+    if from == to || (flags != 0 && flags & 4 == 0) {
+      return None;
+    }
+    let takes_piece = board.combined().get_bit(to).unwrap_or(false)
+      && board.colour.get_bit(to).unwrap_or(false) != board.playing;
+    let en_passant = board
+      .en_passant
+      .is_some_and(|en_passant| en_passant.to_square() == to)
+      && from % 8 != to % 8
+      && board.get_piece(from) == Some(PieceType::Pawn);
+    Some(PieceMove::new(
+      from,
+      to,
+      takes_piece || en_passant,
+      self.promotion_type(),
+    ))
+  }
+}
+
+impl From<PieceMove> for Move16 {
+  fn from(piece_move: PieceMove) -> Self {
+    let flags = match piece_move.promotion_type() {
+      Some(promotion) => MOVE16_PROMOTION_FLAG | (promotion as u16) << 12,
+      None => 0,
+    };
+    Move16(u16::from(piece_move.from_square()) | u16::from(piece_move.to_square()) << 6 | flags)
+  }
+}
+
+impl Display for Move16 {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let piece_move = PieceMove::new(
+      self.from_square(),
+      self.to_square(),
+      false,
+      self.promotion_type(),
+    );
+    Display::fmt(&piece_move, f)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::gamedata::GameData};
+
+  #[test]
+  fn test_parse_square() {
+    assert_eq!(parse_square("a1"), Some(A1));
+    assert_eq!(parse_square("h8"), Some(H8));
+    assert_eq!(parse_square("e4"), Some(E4));
+    assert_eq!(parse_square("i1"), None);
+    assert_eq!(parse_square("a9"), None);
+    assert_eq!(parse_square("e"), None);
+    assert_eq!(parse_square("e44"), None);
+  }
+
+  #[test]
+  fn test_promotion_rank_is_checked_without_board() {
+    assert!("e7e8q".parse::<PieceMove>().is_ok());
+    assert!("e2e1n".parse::<PieceMove>().is_ok());
+    assert_eq!(
+      "e2e4q".parse::<PieceMove>(),
+      Err(MoveParseError::InvalidPromotionRank)
+    );
+    assert_eq!(
+      "e6e8q".parse::<PieceMove>(),
+      Err(MoveParseError::InvalidPromotionRank)
+    );
+    assert_eq!(
+      "e7e8k".parse::<PieceMove>(),
+      Err(MoveParseError::InvalidPromotionPiece)
+    );
+  }
+
+  #[test]
+  fn test_canonical_order() {
+    let quiet = PieceMove::new(E7, E8, false, None);
+    let capture = PieceMove::new(E7, E8, true, None);
+    let queen = PieceMove::new(E7, E8, false, Some(PromotionType::Queen));
+    let knight = PieceMove::new(E7, E8, false, Some(PromotionType::Knight));
+    assert!(PieceMove::new(A2, A3, false, None) < quiet);
+    assert!(PieceMove::new(E7, D8, true, None) < quiet);
+    assert!(quiet < capture);
+    assert!(capture < queen);
+    assert!(queen < knight);
+    assert_eq!(quiet.cmp(&quiet), core::cmp::Ordering::Equal);
+  }
+
+  #[test]
+  fn test_new_masks_squares() {
+    let piece_move = PieceMove::new(64 + E2, 128 + E4, false, None);
+    assert_eq!(piece_move.from_square(), E2);
+    assert_eq!(piece_move.to_square(), E4);
+    assert!(!piece_move.is_capture());
+    assert!(!piece_move.is_promotion());
+  }
+
+  #[test]
+  fn test_parse_never_panics() {
+    let alphabet = "abcdefghijklmnopqrstuvwxyz12345678 -éü\u{1F98A}"
+      .chars()
+      .collect::<Vec<_>>();
+    let board = GameBoard::START_POS;
+    let mut seed: u64 = 0xDEADBEEF;
+    for _ in 0..20_000 {
+      seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+      let len = (seed >> 60) as usize;
+      let input: String = (0..len)
+        .map(|i| alphabet[((seed >> (i * 4)) as usize) % alphabet.len()])
+        .collect();
+      let _ = input.parse::<PieceMove>();
+      let _ = PieceMove::from_uci(&input, &board);
+    }
+  }
+
+  #[test]
+  fn test_from_uci_checks_board() {
+    let board = GameData::from_fen("r3k3/1P6/8/8/8/8/2P3p1/R3K3 w - - 0 1")
+      .unwrap()
+      .board;
+    assert_eq!(
+      PieceMove::from_uci("b7a8q", &board),
+      Ok(PieceMove::new(B7, A8, false, Some(PromotionType::Queen)))
+    );
+    assert_eq!(
+      PieceMove::from_uci("b7b8", &board),
+      Err(MoveParseError::MissingPromotion)
+    );
+    assert_eq!(
+      PieceMove::from_uci("c2c1q", &board),
+      Err(MoveParseError::InvalidPromotionRank)
+    );
+    assert_eq!(
+      PieceMove::from_uci("e1e2", &board),
+      Ok(PieceMove::new(E1, E2, false, None))
+    );
+    // Black's pawn is not ours to move
+    assert_eq!(
+      PieceMove::from_uci("g2g1q", &board),
+      Err(MoveParseError::NoPieceOnSquare)
+    );
+
+    let board = GameData::from_fen("4k3/R7/8/8/8/8/8/4K3 w - - 0 1")
+      .unwrap()
+      .board;
+    assert_eq!(
+      PieceMove::from_uci("a7a8q", &board),
+      Err(MoveParseError::PromotionByNonPawn)
+    );
+  }
+
+  #[test]
+  fn test_move16_round_trips_generated_moves() {
+    let fens = [
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+      "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ];
+    for fen in fens {
+      let board = GameData::from_fen(fen).unwrap().board;
+      let (moves, count) = crate::movegen::generate_moves(&board);
+      for &piece_move in &moves[..count] {
+        let packed = Move16::from(piece_move);
+        assert_eq!(
+          packed.to_move(&board),
+          Some(piece_move),
+          "{fen}: {piece_move:?}"
+        );
+        assert_eq!(Move16::from_bits(packed.bits()), packed);
+        assert_eq!(packed.to_string(), piece_move.to_string());
+      }
+    }
+  }
+
+  #[test]
+  fn test_move16_ignores_capture_flag() {
+    let capture = PieceMove::new(E5, D6, true, None);
+    let quiet = PieceMove::new(E5, D6, false, None);
+    assert_eq!(Move16::from(capture), Move16::from(quiet));
+    let promotion = Move16::from(PieceMove::new(B7, A8, true, Some(PromotionType::Knight)));
+    assert_eq!(promotion.promotion_type(), Some(PromotionType::Knight));
+    assert_eq!(promotion.bits() >> 12, 7);
+    assert_eq!(Move16::NULL.to_move(&GameBoard::START_POS), None);
+    // e2e4 with a reserved flag nibble
+    let reserved = Move16::from_bits(12 | 28 << 6 | 1 << 12);
+    assert_eq!(reserved.to_move(&GameBoard::START_POS), None);
+  }
+}
@@ -16,35 +16,79 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
+//! Attack and geometry tables, built by `const fn` at compile time.
+//!
+//! Every table is a `static` whose initializer runs in the compiler, so nothing is computed or
+//! lazily initialized at runtime, the first lookup costs the same as any other, and the tables
+//! work the same without `std` or an allocator. Each one is wrapped in [`CacheAligned`] so it
+//! starts on a 64-byte boundary: a row of `RAYS` or a `[u64; 8]` slice of the per-square tables
+//! never straddles two cache lines.
+//!
+//! `RAYS` and `BETWEEN` are always present; the rest exist with the `precomputed_rays` feature,
+//! which trades binary size for lookups instead of loops. Tests check each table square by square
+//! against a plain step-by-step walk of the board.
+//!
+//! Memory footprint:
+//!
+//! | Table                                       | Shape             | Size       |
+//! |---------------------------------------------|-------------------|------------|
+//! | `RAYS`                                      | `[[u64; 8]; 64]`  | 4 KiB      |
+//! | `KNIGHT_MOVES`, `KING_MOVES`                | `[u64; 64]`       | 512 B each |
+//! | `PAWN_ATTACK_*`, `PAWN_PUSH_*` (per colour) | `[u64; 64]`       | 512 B each |
+//! | `BETWEEN`                                   | `[[u64; 64]; 64]` | 32 KiB     |
+//! | `LINE`                                      | `[[u64; 64]; 64]` | 32 KiB     |
+//!
+//! Being `static`s rather than `const`s, the tables exist once in the binary instead of being
+//! copied into each function that uses them. The same holds for the Zobrist keys in
+//! [`crate::zobrist`]. Sliding attacks are computed from `RAYS`, or looked up in the magic
+//! bitboard tables of `movegen::magics` with the `magics` feature.
+
+/// A value placed on a 64-byte boundary, the cache line size of the targets we care about.
+///
+/// Dereferences to the wrapped value, so `RAYS[sq][dir]` and `KNIGHT_MOVES.iter()` read the same
+/// as on a bare array.
+#[repr(C, align(64))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheAligned<T>(pub T);
+
+impl<T> core::ops::Deref for CacheAligned<T> {
+  type Target = T;
+
+  #[inline(always)]
+  fn deref(&self) -> &T {
+    &self.0
+  }
+}
+
 pub const DIR_OFFSETS: [i8; 8] = [1, -1, -8, 8, -7, -9, 9, 7];
 
 // Order: E, W, N, S, NE, NW, SE, SW (matches DIR_OFFSETS above)
-pub const RAYS: [[u64; 8]; 64] = build_rays();
+pub static RAYS: CacheAligned<[[u64; 8]; 64]> = CacheAligned(build_rays());
 
 // Precomputed knight moves for every square, built at compile time.
 #[cfg(feature = "precomputed_rays")]
-pub const KNIGHT_MOVES: [u64; 64] = build_knight_moves();
+pub static KNIGHT_MOVES: CacheAligned<[u64; 64]> = CacheAligned(build_knight_moves());
 
 // Precomputed king moves for every square (one-step adjacency)
 #[cfg(feature = "precomputed_rays")]
-pub const KING_MOVES: [u64; 64] = build_king_moves();
+pub static KING_MOVES: CacheAligned<[u64; 64]> = CacheAligned(build_king_moves());
 
 // Pawn attack and push masks. Separate tables per color to keep callers simple.
 // White pawns move "up" (left shift) in existing movegen; black pawns move "down" (right shift).
 #[cfg(feature = "precomputed_rays")]
-pub const PAWN_ATTACK_WHITE: [u64; 64] = build_pawn_attack_white();
+pub static PAWN_ATTACK_WHITE: CacheAligned<[u64; 64]> = CacheAligned(build_pawn_attack_white());
 #[cfg(feature = "precomputed_rays")]
-pub const PAWN_ATTACK_BLACK: [u64; 64] = build_pawn_attack_black();
+pub static PAWN_ATTACK_BLACK: CacheAligned<[u64; 64]> = CacheAligned(build_pawn_attack_black());
 #[cfg(feature = "precomputed_rays")]
-pub const PAWN_PUSH_WHITE: [u64; 64] = build_pawn_push_white();
+pub static PAWN_PUSH_WHITE: CacheAligned<[u64; 64]> = CacheAligned(build_pawn_push_white());
 #[cfg(feature = "precomputed_rays")]
-pub const PAWN_PUSH_BLACK: [u64; 64] = build_pawn_push_black();
+pub static PAWN_PUSH_BLACK: CacheAligned<[u64; 64]> = CacheAligned(build_pawn_push_black());
 
-// Between and line masks for sliding pieces
-#[cfg(feature = "precomputed_rays")]
-pub static BETWEEN: [[u64; 64]; 64] = build_between();
+// Between and line masks for sliding pieces. BETWEEN backs `is_path_clear` in the legality
+// checker, so it exists without `precomputed_rays` too.
+pub static BETWEEN: CacheAligned<[[u64; 64]; 64]> = CacheAligned(build_between());
 #[cfg(feature = "precomputed_rays")]
-pub static LINE: [[u64; 64]; 64] = build_line();
+pub static LINE: CacheAligned<[[u64; 64]; 64]> = CacheAligned(build_line());
 
 // Helper const-fn to build the rays table at compile time.
 const fn build_rays() -> [[u64; 8]; 64] {
@@ -274,9 +318,9 @@ const fn build_pawn_push_black() -> [u64; 64] {
   table
 }
 
-// Build BETWEEN and LINE masks. Use RAYS to detect collinearity; BETWEEN contains squares strictly between
-// from and to (exclusive), LINE contains the full line including endpoints.
-#[cfg(feature = "precomputed_rays")]
+// Build BETWEEN and LINE masks. Use RAYS to detect collinearity; BETWEEN contains squares
+// strictly between from and to (exclusive), LINE contains the segment from `from` to `to`
+// including both endpoints.
 const fn build_between() -> [[u64; 64]; 64] {
   let mut table: [[u64; 64]; 64] = [[0u64; 64]; 64];
   let mut from: usize = 0;
@@ -290,7 +334,7 @@ const fn build_between() -> [[u64; 64]; 64] {
         let mut mask: u64 = 0;
         let mut d: usize = 0;
         while d < 8 {
-          let ray = RAYS[from][d];
+          let ray = RAYS.0[from][d];
           if (ray & (1u64 << (to as u8))) != 0 {
             // Squares between are ray & ~((1<<from) | (1<<to)) trimmed to up-to target
             // Walk from 'from' towards 'to' accumulating squares until we reach 'to'.
@@ -330,12 +374,15 @@ const fn build_line() -> [[u64; 64]; 64] {
       if from == to {
         table[from][to] = 1u64 << (from as u8);
       } else {
-        // if `to` is in some ray from `from`, then line is between[from][to] | endpoints
-        let between_mask = BETWEEN[from][to];
-        if between_mask != 0 {
-          table[from][to] = between_mask | (1u64 << (from as u8)) | (1u64 << (to as u8));
-        } else {
-          table[from][to] = 0;
+        // if `to` is in some ray from `from`, then line is between[from][to] | endpoints.
+        // Adjacent squares are on a line too, with nothing between them.
+        let mut d: usize = 0;
+        while d < 8 {
+          if RAYS.0[from][d] & (1u64 << (to as u8)) != 0 {
+            table[from][to] = BETWEEN.0[from][to] | (1u64 << (from as u8)) | (1u64 << (to as u8));
+            break;
+          }
+          d += 1;
         }
       }
       to += 1;
@@ -348,8 +395,28 @@ const fn build_line() -> [[u64; 64]; 64] {
 #[cfg(test)]
 mod tests {
   use super::*;
+  #[cfg(feature = "precomputed_rays")]
   use crate::constants::*;
 
+  #[test]
+  fn tables_are_cache_aligned() {
+    fn assert_aligned<T>(table: &CacheAligned<T>) {
+      assert_eq!(table as *const _ as usize % 64, 0);
+    }
+    assert_eq!(core::mem::align_of::<CacheAligned<u64>>(), 64);
+    assert_aligned(&RAYS);
+    assert_aligned(&BETWEEN);
+    assert_aligned(&crate::zobrist::PIECES);
+    assert_aligned(&crate::zobrist::EN_PASSANT);
+    #[cfg(feature = "precomputed_rays")]
+    {
+      assert_aligned(&KNIGHT_MOVES);
+      assert_aligned(&KING_MOVES);
+      assert_aligned(&PAWN_ATTACK_WHITE);
+      assert_aligned(&LINE);
+    }
+  }
+
   #[test]
   fn rays_table_nonzero() {
     let mut found = 0;
@@ -361,7 +428,111 @@ mod tests {
     assert!(found > 0);
   }
 
+  // (rank, file) steps in the order of DIR_OFFSETS
+  const STEPS: [(i32, i32); 8] = [
+    (0, 1),
+    (0, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (-1, -1),
+    (1, 1),
+    (1, -1),
+  ];
+
+  // Squares reached from `square` by each of `jumps`, repeating them while `slide` is set
+  fn walk(square: usize, jumps: &[(i32, i32)], slide: bool) -> u64 {
+    let mut mask = 0;
+    for &(dr, df) in jumps {
+      let (mut r, mut f) = (square as i32 / 8 + dr, square as i32 % 8 + df);
+      while (0..8).contains(&r) && (0..8).contains(&f) {
+        mask |= 1u64 << (r * 8 + f);
+        if !slide {
+          break;
+        }
+        r += dr;
+        f += df;
+      }
+    }
+    mask
+  }
+
+  #[test]
+  fn rays_match_board_walk() {
+    for (square, rays) in RAYS.iter().enumerate() {
+      for (d, step) in STEPS.iter().enumerate() {
+        assert_eq!(rays[d], walk(square, &[*step], true), "{square} {d}");
+        // The offsets agree with the steps wherever the ray has a first square
+        if rays[d] != 0 {
+          let next = square as i32 + DIR_OFFSETS[d] as i32;
+          assert_ne!(rays[d] & (1u64 << next), 0);
+        }
+      }
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "precomputed_rays")]
+  fn step_tables_match_board_walk() {
+    let knight = [
+      (-2, -1),
+      (-2, 1),
+      (-1, -2),
+      (-1, 2),
+      (1, -2),
+      (1, 2),
+      (2, -1),
+      (2, 1),
+    ];
+    for square in 0..64 {
+      assert_eq!(KNIGHT_MOVES[square], walk(square, &knight, false));
+      assert_eq!(KING_MOVES[square], walk(square, &STEPS, false));
+      assert_eq!(
+        PAWN_ATTACK_WHITE[square],
+        walk(square, &[(1, -1), (1, 1)], false)
+      );
+      assert_eq!(
+        PAWN_ATTACK_BLACK[square],
+        walk(square, &[(-1, -1), (-1, 1)], false)
+      );
+      assert_eq!(PAWN_PUSH_WHITE[square], walk(square, &[(1, 0)], false));
+      assert_eq!(PAWN_PUSH_BLACK[square], walk(square, &[(-1, 0)], false));
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "precomputed_rays")]
+  fn between_and_line_match_board_walk() {
+    for from in 0..64 {
+      for to in 0..64 {
+        // Step from `from` in each direction, collecting squares until `to` is reached
+        let mut expected = if from == to {
+          (0, 1u64 << from)
+        } else {
+          (0, 0)
+        };
+        for &(dr, df) in &STEPS {
+          let (mut r, mut f) = (from as i32 / 8 + dr, from as i32 % 8 + df);
+          let mut between = 0;
+          while (0..8).contains(&r) && (0..8).contains(&f) {
+            if (r * 8 + f) as usize == to {
+              expected = (between, between | (1u64 << from) | (1u64 << to));
+              break;
+            }
+            between |= 1u64 << (r * 8 + f);
+            r += dr;
+            f += df;
+          }
+        }
+        assert_eq!(BETWEEN[from][to], expected.0, "{from} {to}");
+        assert_eq!(LINE[from][to], expected.1, "{from} {to}");
+        assert_eq!(LINE[from][to], LINE[to][from]);
+      }
+    }
+  }
+
   // Helper to build mask from list of square indices
+  #[cfg(feature = "precomputed_rays")]
   fn mask_from(indices: &[u8]) -> u64 {
     let mut m: u64 = 0;
     for &i in indices {
@@ -0,0 +1,196 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Whole games saved for adjournment and restored exactly.
+//!
+//! [`GameData::snapshot`] stores a game as its initial FEN, the moves played since as
+//! [`Move16`]s and the FEN reached, together with the variant and the players' [`Clock`].
+//! [`GameData::resume`] replays the moves from the initial FEN, so the restored game has the same
+//! history, ply count, halfmove clock and check counts as the saved one, and refuses a snapshot
+//! whose history does not lead to the saved position. With the `serde` feature the snapshot
+//! implements `Serialize` and `Deserialize`; it holds only strings, numbers and lists, so it
+//! reads back the same from any format.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+  errors::SnapshotError,
+  model::{
+    clock::Clock, gameboard::EnPassantFen, gamedata::GameData, piecemove::Move16, variant::Variant,
+  },
+};
+
+/// A game in progress, as saved by [`GameData::snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameSnapshot {
+  /// FEN of the position the history starts from, with its clocks and, in Three-check, the
+  /// checks given so far.
+  pub initial_fen: String,
+  /// Moves played from the initial position, in order.
+  pub moves: Vec<Move16>,
+  /// FEN of the current position. [`GameData::resume`] checks the replayed history against it.
+  pub fen: String,
+  /// The rules the game is played under, which FEN cannot express for King of the Hill.
+  pub variant: Variant,
+  /// The players' remaining time. [`GameData`] keeps no clock, so a snapshot starts untimed and
+  /// the caller fills this in from its own.
+  pub clock: Clock,
+}
+
+impl GameData {
+  /// Saves this game for [`resume`](Self::resume). En passant squares are written even without a
+  /// legal capture, so the restored boards match field for field.
+  pub fn snapshot(&self) -> GameSnapshot {
+    let mut initial = *self;
+    initial.board = self.initial_board;
+    initial.plies = self.initial_plies;
+    initial.halfmove_clock = self.initial_halfmove_clock;
+    initial.checks = self.initial_checks;
+    GameSnapshot {
+      initial_fen: initial.to_fen_with(EnPassantFen::Always),
+      moves: self.compact_moves().collect(),
+      fen: self.to_fen_with(EnPassantFen::Always),
+      variant: self.variant,
+      clock: Clock::default(),
+    }
+  }
+
+  /// Restores a game saved by [`snapshot`](Self::snapshot) by replaying its moves from the
+  /// initial position. The snapshot's `clock` is left for the caller to read.
+  pub fn resume(snapshot: &GameSnapshot) -> Result<GameData, SnapshotError> {
+    let mut game = GameData::from_fen(&snapshot.initial_fen)
+      .map_err(SnapshotError::InvalidFen)?
+      .with_variant(snapshot.variant);
+    for (index, &packed) in snapshot.moves.iter().enumerate() {
+      game
+        .play_compact([packed])
+        .ok_or(SnapshotError::IllegalMove { index })?;
+    }
+    if game.to_fen_with(EnPassantFen::Always) != snapshot.fen {
+      return Err(SnapshotError::PositionMismatch);
+    }
+    Ok(game)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constants::*;
+
+  fn played(fen: &str, moves: &[(u8, u8)]) -> GameData {
+    let mut game = GameData::from_fen(fen).unwrap();
+    for &(from, to) in moves {
+      let piece_move = game.new_move(from, to);
+      game.play_move(&piece_move).unwrap();
+    }
+    game
+  }
+
+  fn assert_same_game(restored: &GameData, game: &GameData) {
+    assert_eq!(restored.board, game.board);
+    assert_eq!(restored.board.en_passant, game.board.en_passant);
+    assert_eq!(restored.initial_board, game.initial_board);
+    assert_eq!(restored.plies, game.plies);
+    assert_eq!(restored.initial_plies, game.initial_plies);
+    assert_eq!(restored.halfmove_clock, game.halfmove_clock);
+    assert_eq!(restored.initial_halfmove_clock, game.initial_halfmove_clock);
+    assert_eq!(restored.variant, game.variant);
+    assert_eq!(restored.checks, game.checks);
+    assert_eq!(restored.initial_checks, game.initial_checks);
+    assert!(restored.compact_moves().eq(game.compact_moves()));
+  }
+
+  #[test]
+  fn test_snapshot_round_trip() {
+    // Ends just after a double step with no pawn to take it, which the default FEN would drop
+    let game = played(
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      &[(G1, F3), (B8, C6), (E2, E4), (C6, D4), (F3, D4), (D7, D5)],
+    );
+    let snapshot = game.snapshot();
+    assert_eq!(snapshot.moves.len(), 6);
+    assert!(snapshot.fen.contains(" d6 "));
+    assert_same_game(&GameData::resume(&snapshot).unwrap(), &game);
+
+    // Three-check counts and a history that starts mid-game
+    let game = played(
+      "4k3/8/8/8/8/8/4R3/4K3 w - - 7 30 +1+0",
+      &[(E2, E7), (E8, D8), (E7, D7), (D8, C8)],
+    );
+    assert_eq!(game.checks, [3, 0]);
+    assert_same_game(&GameData::resume(&game.snapshot()).unwrap(), &game);
+
+    let game =
+      played("4k3/8/8/8/8/8/8/4K3 w - - 0 1", &[(E1, E2)]).with_variant(Variant::KingOfTheHill);
+    let restored = GameData::resume(&game.snapshot()).unwrap();
+    assert_eq!(restored.variant, Variant::KingOfTheHill);
+  }
+
+  #[test]
+  fn test_resume_rejects_bad_snapshots() {
+    let game = played(
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      &[(E2, E4), (E7, E5)],
+    );
+    let snapshot = game.snapshot();
+
+    let mut bad = snapshot.clone();
+    bad.initial_fen = String::from("not a fen");
+    assert!(matches!(
+      GameData::resume(&bad),
+      Err(SnapshotError::InvalidFen(_))
+    ));
+
+    let mut bad = snapshot.clone();
+    bad.moves.push(Move16::from(game.new_move(E4, E6)));
+    assert_eq!(
+      GameData::resume(&bad).err(),
+      Some(SnapshotError::IllegalMove { index: 2 })
+    );
+
+    let mut bad = snapshot;
+    bad.moves.pop();
+    assert_eq!(
+      GameData::resume(&bad).err(),
+      Some(SnapshotError::PositionMismatch)
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_snapshot_serde() {
+    let game = played(
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      &[(D2, D4), (G8, F6)],
+    );
+    let mut snapshot = game.snapshot();
+    snapshot.clock = Clock {
+      white_time: Some(290_000),
+      black_time: Some(287_500),
+      white_increment: 2_000,
+      black_increment: 2_000,
+      moves_to_go: None,
+    };
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let read: GameSnapshot = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, snapshot);
+    assert_same_game(&GameData::resume(&read).unwrap(), &game);
+  }
+}
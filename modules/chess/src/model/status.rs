@@ -0,0 +1,232 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Whether a game is over, and how.
+//!
+//! [`GameData::status`] tells the end of a game from its position and history. Draws come in
+//! two kinds that GUIs and adjudicators treat differently:
+//!
+//! - A [`GameStatus::ForcedDraw`] ends the game at once: stalemate, a dead position, 75 moves
+//!   without a capture or pawn move, or the same position five times.
+//! - A [`GameStatus::ClaimableDraw`] only lets the player to move claim one: 50 moves without a
+//!   capture or pawn move, or the same position three times. Unclaimed, the game goes on, so a
+//!   GUI offers the claim and an engine may keep playing.
+//!
+//! Repetitions are counted over the recorded history since the last capture or pawn move, so
+//! positions before the game's initial FEN are unknown and never count.
+
+use crate::{
+  legal::attack::is_square_attacked,
+  model::{gamedata::GameData, variant::Variant},
+};
+
+/// Why a game is or may be drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DrawReason {
+  /// The side to move has no legal move and is not in check.
+  Stalemate,
+  /// Neither side has the material to mate. Only judged in standard chess, where a bare king
+  /// can still win by the variant rules elsewhere.
+  InsufficientMaterial,
+  /// 50 moves by each side without a capture or pawn move.
+  FiftyMoves,
+  /// The same position for the third time.
+  ThreefoldRepetition,
+  /// 75 moves by each side without a capture or pawn move.
+  SeventyFiveMoves,
+  /// The same position for the fifth time.
+  FivefoldRepetition,
+}
+
+/// Where a game stands, as returned by [`GameData::status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameStatus {
+  /// The game goes on and no draw can be claimed.
+  Ongoing,
+  /// The side to move is mated; `white_wins` names the winner.
+  Checkmate { white_wins: bool },
+  /// The variant's own rule decided the game, such as a king on the hill or a third check.
+  VariantWin { white_wins: bool },
+  /// The game is over as a draw whether or not anyone claims it.
+  ForcedDraw(DrawReason),
+  /// The game goes on, but the side to move may claim a draw.
+  ClaimableDraw(DrawReason),
+}
+
+impl GameStatus {
+  /// Whether the game is over. A claimable draw is not, until it is claimed.
+  pub fn is_over(self) -> bool {
+    !matches!(self, GameStatus::Ongoing | GameStatus::ClaimableDraw(_))
+  }
+}
+
+impl GameData {
+  /// Where the game stands. Checkmate and variant wins come first, then forced draws, then
+  /// draws that can be claimed; see the [module docs](crate::model::status).
+  pub fn status(&self) -> GameStatus {
+    if let Some(white_wins) = self.variant_winner() {
+      return GameStatus::VariantWin { white_wins };
+    }
+    let board = &self.board;
+    if !board.has_legal_move() {
+      let in_check = board
+        .king_square(board.playing)
+        .is_some_and(|king| is_square_attacked(board, king));
+      return if in_check {
+        GameStatus::Checkmate {
+          white_wins: !board.playing,
+        }
+      } else {
+        GameStatus::ForcedDraw(DrawReason::Stalemate)
+      };
+    }
+    if self.variant == Variant::Standard && board.is_insufficient_material() {
+      return GameStatus::ForcedDraw(DrawReason::InsufficientMaterial);
+    }
+
+    let repetitions = self.repetitions();
+    if self.halfmove_clock >= 150 {
+      GameStatus::ForcedDraw(DrawReason::SeventyFiveMoves)
+    } else if repetitions >= 5 {
+      GameStatus::ForcedDraw(DrawReason::FivefoldRepetition)
+    } else if self.halfmove_clock >= 100 {
+      GameStatus::ClaimableDraw(DrawReason::FiftyMoves)
+    } else if repetitions >= 3 {
+      GameStatus::ClaimableDraw(DrawReason::ThreefoldRepetition)
+    } else {
+      GameStatus::Ongoing
+    }
+  }
+
+  /// Whether the side to move may claim a draw by the fifty-move rule or threefold repetition.
+  /// `false` once the game is over, forced draws included.
+  pub fn can_claim_draw(&self) -> bool {
+    matches!(self.status(), GameStatus::ClaimableDraw(_))
+  }
+
+  /// How many times the current position has occurred, this time included, as far as the
+  /// recorded history shows. Positions are the same in the sense of the repetition rules: the
+  /// same pieces, side to move, castling rights and en passant captures.
+  pub fn repetitions(&self) -> usize {
+    let played = self.history_len();
+    // Positions can only repeat since the last capture or pawn move
+    let since = played.saturating_sub(self.halfmove_clock);
+    let earlier = core::iter::once(self.initial_board)
+      .chain(self.replay().map(|(_, _, game)| game.board))
+      .take(played)
+      .skip(since);
+    1 + earlier.filter(|board| *board == self.board).count()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::piecemove::PieceMove;
+
+  fn game(fen: &str) -> GameData {
+    GameData::from_fen(fen).unwrap()
+  }
+
+  fn play(game: &mut GameData, moves: &[&str]) {
+    for text in moves {
+      let typed = PieceMove::from_uci(text, &game.board).unwrap();
+      let piece_move = game
+        .board
+        .legal_moves_iter()
+        .find(|candidate| {
+          candidate.from_square() == typed.from_square()
+            && candidate.to_square() == typed.to_square()
+        })
+        .unwrap();
+      game.play_move(&piece_move).unwrap();
+    }
+  }
+
+  #[test]
+  fn test_decisive_and_dead() {
+    assert_eq!(GameData::START_POS.status(), GameStatus::Ongoing);
+    assert!(!GameData::START_POS.status().is_over());
+    assert_eq!(
+      game("R5k1/5ppp/8/8/8/8/8/K7 b - - 0 1").status(),
+      GameStatus::Checkmate { white_wins: true }
+    );
+    assert_eq!(
+      game("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").status(),
+      GameStatus::ForcedDraw(DrawReason::Stalemate)
+    );
+    assert_eq!(
+      game("7k/8/6K1/8/8/8/8/5B2 b - - 0 1").status(),
+      GameStatus::ForcedDraw(DrawReason::InsufficientMaterial)
+    );
+  }
+
+  #[test]
+  fn test_move_rules() {
+    let fifty = game("7k/8/6K1/8/8/8/8/R7 b - - 100 80");
+    assert_eq!(
+      fifty.status(),
+      GameStatus::ClaimableDraw(DrawReason::FiftyMoves)
+    );
+    assert!(fifty.can_claim_draw());
+    assert!(!fifty.status().is_over());
+
+    let seventy_five = game("7k/8/6K1/8/8/8/8/R7 b - - 150 100");
+    assert_eq!(
+      seventy_five.status(),
+      GameStatus::ForcedDraw(DrawReason::SeventyFiveMoves)
+    );
+    assert!(!seventy_five.can_claim_draw());
+
+    // Mate on the move that reaches 75 moves still wins
+    assert_eq!(
+      game("R5k1/5ppp/8/8/8/8/8/K7 b - - 150 100").status(),
+      GameStatus::Checkmate { white_wins: true }
+    );
+  }
+
+  #[test]
+  fn test_repetitions() {
+    // Starting later in the game must not matter
+    let mut shuffle = game("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 20");
+    let knights = ["g1f3", "g8f6", "f3g1", "f6g8"];
+    assert_eq!(shuffle.repetitions(), 1);
+    play(&mut shuffle, &knights);
+    assert_eq!(shuffle.repetitions(), 2);
+    assert_eq!(shuffle.status(), GameStatus::Ongoing);
+    play(&mut shuffle, &knights);
+    assert_eq!(shuffle.repetitions(), 3);
+    assert_eq!(
+      shuffle.status(),
+      GameStatus::ClaimableDraw(DrawReason::ThreefoldRepetition)
+    );
+    assert!(shuffle.can_claim_draw());
+    play(&mut shuffle, &knights);
+    assert_eq!(shuffle.repetitions(), 4);
+    play(&mut shuffle, &knights);
+    assert_eq!(shuffle.repetitions(), 5);
+    assert_eq!(
+      shuffle.status(),
+      GameStatus::ForcedDraw(DrawReason::FivefoldRepetition)
+    );
+
+    // A pawn move makes the earlier positions unreachable
+    play(&mut shuffle, &["e2e4"]);
+    assert_eq!(shuffle.repetitions(), 1);
+  }
+}
@@ -0,0 +1,62 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Chess variants that keep the standard board and moves but add a way to win.
+//!
+//! - King of the Hill: a king that reaches one of the four centre squares wins at once.
+//! - Three-check: giving check for the third time wins. The checks given so far are kept in
+//!   [`GameData`](super::gamedata::GameData) and written to FEN as a trailing `+W+B` field,
+//!   counting the checks white and black have given, as lichess does.
+//!
+//! Checkmate and the draw rules apply in every variant; [`Variant::winner`] only reports the
+//! extra ways to win.
+
+use super::gameboard::GameBoard;
+use crate::constants::{D4, D5, E4, E5};
+
+/// The four centre squares a king must reach in King of the Hill.
+pub const HILL: u64 = 1 << D4 | 1 << E4 | 1 << D5 | 1 << E5;
+
+/// Checks that win a game of Three-check.
+pub const CHECKS_TO_WIN: u8 = 3;
+
+/// Which rules a game is played under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+  #[default]
+  Standard,
+  KingOfTheHill,
+  ThreeCheck,
+}
+
+impl Variant {
+  /// The side, `true` for white, that has won by this variant's own rule, given the position
+  /// and the checks each side has given as `[white, black]`.
+  pub fn winner(self, board: &GameBoard, checks: [u8; 2]) -> Option<bool> {
+    match self {
+      Variant::Standard => None,
+      Variant::KingOfTheHill => [true, false]
+        .into_iter()
+        .find(|&white| (board.kings & board.pieces_of(white)).raw() & HILL != 0),
+      Variant::ThreeCheck => [true, false]
+        .into_iter()
+        .find(|&white| checks[usize::from(!white)] >= CHECKS_TO_WIN),
+    }
+  }
+}
@@ -16,31 +16,61 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
+#[cfg(not(feature = "magics"))]
 use crate::{
-  constants::{FILE_A, FILE_H}, // Added FILE_A for wrap-around protection
-  model::{bitboard::BitBoard, gameboard::GameBoard, piecemove::PieceMove},
+  constants::{NOT_FILE_A, NOT_FILE_H},
+  movegen::add_move_to_list,
+};
+use crate::{
+  model::{
+    gameboard::{GameBoard, PieceType},
+    piecemove::PieceMove,
+  },
+  movegen::flags::Targets,
+  stats::{self, Event},
 };
 
-pub const MAX_BISHOP_MOVES: usize = 28;
+/// Ten bishops, the two of the starting position and eight promoted pawns, with at most 13 moves
+/// each.
+pub const MAX_BISHOP_MOVES: usize = 10 * 13;
 
+/// Every bishop move, for the tests below.
+#[cfg(test)]
 pub(crate) fn generate_bishop_moves(state: &GameBoard) -> ([PieceMove; MAX_BISHOP_MOVES], usize) {
+  generate_bishop_moves_with(state, Targets::ALL)
+}
+
+/// The bishop moves of the side to move that `targets` allows.
+#[cfg(feature = "magics")]
+pub(crate) fn generate_bishop_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_BISHOP_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::Bishop));
+  crate::movegen::magics::generate_slider_moves(
+    state,
+    state.bishops.raw(),
+    crate::movegen::magics::bishop_attacks,
+    targets,
+  )
+}
+
+/// The bishop moves of the side to move that `targets` allows.
+#[cfg(not(feature = "magics"))]
+pub(crate) fn generate_bishop_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_BISHOP_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::Bishop));
   let mut moves = [PieceMove::NULL; MAX_BISHOP_MOVES];
   let mut count = 0;
 
   let all_occupied =
     state.pawns | state.knights | state.bishops | state.rooks | state.queens | state.kings;
 
-  let (my_bishops, other_pieces): (BitBoard, u64) = if state.playing {
-    (
-      state.bishops & state.colour,
-      (all_occupied & !state.colour).into(),
-    )
-  } else {
-    (
-      state.bishops & !state.colour,
-      (all_occupied & state.colour).into(),
-    )
-  };
+  let my_bishops = state.bishops & state.pieces_of(state.playing);
+  let other_pieces = state.pieces_of(!state.playing).raw() & targets.capture_mask();
+  let quiet_mask = targets.quiet_mask();
 
   // Ray-casting for all 4 diagonal directions
 
@@ -48,7 +78,7 @@ pub(crate) fn generate_bishop_moves(state: &GameBoard) -> ([PieceMove; MAX_BISHO
   let mut ray_attackers: u64 = my_bishops.into();
   for i in 1..8 {
     // We move the bishops up-left, and remove all who warp around to file H.
-    ray_attackers = (ray_attackers << 7) & !FILE_H;
+    ray_attackers = (ray_attackers << 7) & NOT_FILE_H;
 
     // Potential captures are ray attacks that land on an opponent's piece.
     let mut captures = ray_attackers & other_pieces;
@@ -56,8 +86,11 @@ pub(crate) fn generate_bishop_moves(state: &GameBoard) -> ([PieceMove; MAX_BISHO
       let to_board = captures.trailing_zeros() as u8;
       let from_board = to_board - (i * 7);
 
-      moves[count] = PieceMove::new(from_board, to_board, true, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, true, None),
+      );
 
       // Remove this processed capture from the captures bitboard.
       captures &= captures - 1;
@@ -68,13 +101,16 @@ pub(crate) fn generate_bishop_moves(state: &GameBoard) -> ([PieceMove; MAX_BISHO
     ray_attackers &= !blockers;
 
     // Process quiet moves (those that didn't land on a blocker).
-    let mut quiet_moves = ray_attackers;
+    let mut quiet_moves = ray_attackers & quiet_mask;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = to_board - (i * 7);
 
-      moves[count] = PieceMove::new(from_board, to_board, false, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, false, None),
+      );
 
       // Remove this processed move.
       quiet_moves &= quiet_moves - 1;
@@ -89,26 +125,32 @@ pub(crate) fn generate_bishop_moves(state: &GameBoard) -> ([PieceMove; MAX_BISHO
   ray_attackers = my_bishops.into();
   for i in 1..8 {
     // We move the bishops up-right, and remove all who warp around to file A.
-    ray_attackers = (ray_attackers << 9) & !FILE_A;
+    ray_attackers = (ray_attackers << 9) & NOT_FILE_A;
 
     let mut captures = ray_attackers & other_pieces;
     while captures != 0 {
       let to_board = captures.trailing_zeros() as u8;
       let from_board = to_board - (i * 9);
-      moves[count] = PieceMove::new(from_board, to_board, true, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, true, None),
+      );
       captures &= captures - 1;
     }
 
     let blockers = ray_attackers & all_occupied.raw();
     ray_attackers &= !blockers;
 
-    let mut quiet_moves = ray_attackers;
+    let mut quiet_moves = ray_attackers & quiet_mask;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = to_board - (i * 9);
-      moves[count] = PieceMove::new(from_board, to_board, false, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, false, None),
+      );
       quiet_moves &= quiet_moves - 1;
     }
 
@@ -121,26 +163,32 @@ pub(crate) fn generate_bishop_moves(state: &GameBoard) -> ([PieceMove; MAX_BISHO
   ray_attackers = my_bishops.into();
   for i in 1..8 {
     // We move the bishops down-left, and remove all who warp around to file H.
-    ray_attackers = (ray_attackers >> 9) & !FILE_H;
+    ray_attackers = (ray_attackers >> 9) & NOT_FILE_H;
 
     let mut captures = ray_attackers & other_pieces;
     while captures != 0 {
       let to_board = captures.trailing_zeros() as u8;
       let from_board = to_board + (i * 9);
-      moves[count] = PieceMove::new(from_board, to_board, true, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, true, None),
+      );
       captures &= captures - 1;
     }
 
     let blockers = ray_attackers & all_occupied.raw();
     ray_attackers &= !blockers;
 
-    let mut quiet_moves = ray_attackers;
+    let mut quiet_moves = ray_attackers & quiet_mask;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = to_board + (i * 9);
-      moves[count] = PieceMove::new(from_board, to_board, false, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, false, None),
+      );
       quiet_moves &= quiet_moves - 1;
     }
 
@@ -153,26 +201,32 @@ pub(crate) fn generate_bishop_moves(state: &GameBoard) -> ([PieceMove; MAX_BISHO
   ray_attackers = my_bishops.into();
   for i in 1..8 {
     // We move the bishops down-right, and remove all who warp around to file A.
-    ray_attackers = (ray_attackers >> 7) & !FILE_A;
+    ray_attackers = (ray_attackers >> 7) & NOT_FILE_A;
 
     let mut captures = ray_attackers & other_pieces;
     while captures != 0 {
       let to_board = captures.trailing_zeros() as u8;
       let from_board = to_board + (i * 7);
-      moves[count] = PieceMove::new(from_board, to_board, true, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, true, None),
+      );
       captures &= captures - 1;
     }
 
     let blockers = ray_attackers & all_occupied.raw();
     ray_attackers &= !blockers;
 
-    let mut quiet_moves = ray_attackers;
+    let mut quiet_moves = ray_attackers & quiet_mask;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = to_board + (i * 7);
-      moves[count] = PieceMove::new(from_board, to_board, false, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, false, None),
+      );
       quiet_moves &= quiet_moves - 1;
     }
 
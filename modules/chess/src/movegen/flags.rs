@@ -0,0 +1,121 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Which kinds of move a generator should produce.
+
+use core::ops::BitOr;
+
+use crate::model::piecemove::PieceMove;
+
+/// Restrictions on the moves [`generate_moves_with`] and [`generate_legal_moves_with`] produce.
+///
+/// Captures, en passant included, and promotions are the moves that change material and that
+/// quiescence searches; every other move, castling included, is quiet. Each flag leaves some
+/// moves out and flags combine with `|`, so `CAPTURES_ONLY | EVASIONS_ONLY` is the captures that
+/// could answer a check, and [`ALL`](Self::ALL) is the empty set of restrictions.
+///
+/// [`generate_moves_with`]: crate::movegen::generate_moves_with
+/// [`generate_legal_moves_with`]: crate::movegen::generate_legal_moves_with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MoveGenFlags(u8);
+
+const NO_QUIETS: u8 = 1;
+const NO_CAPTURES: u8 = 2;
+const EVASIONS: u8 = 4;
+
+impl MoveGenFlags {
+  /// Every move, as [`generate_moves`](crate::movegen::generate_moves) produces.
+  pub const ALL: Self = Self(0);
+  /// Captures and promotions.
+  pub const CAPTURES_ONLY: Self = Self(NO_QUIETS);
+  /// Moves that neither capture nor promote.
+  pub const QUIETS_ONLY: Self = Self(NO_CAPTURES);
+  /// When in check, king moves and the moves that capture the checker or step between it and
+  /// the king; in double check, king moves alone. Out of check this leaves nothing out.
+  pub const EVASIONS_ONLY: Self = Self(EVASIONS);
+
+  /// Whether every restriction of `other` is in `self`.
+  pub const fn contains(self, other: Self) -> bool {
+    self.0 & other.0 == other.0
+  }
+
+  /// Whether captures and promotions are wanted.
+  pub(crate) const fn captures(self) -> bool {
+    self.0 & NO_CAPTURES == 0
+  }
+
+  /// Whether quiet moves are wanted.
+  pub(crate) const fn quiets(self) -> bool {
+    self.0 & NO_QUIETS == 0
+  }
+
+  /// Whether `piece_move` is of a kind these flags keep. Evasions are not checked here, since
+  /// that needs the board.
+  #[inline]
+  pub fn allows_kind(self, piece_move: &PieceMove) -> bool {
+    let excluded = if piece_move.is_capture() || piece_move.is_promotion() {
+      NO_CAPTURES
+    } else {
+      NO_QUIETS
+    };
+    self.0 & excluded == 0
+  }
+}
+
+impl BitOr for MoveGenFlags {
+  type Output = Self;
+
+  fn bitor(self, other: Self) -> Self {
+    Self(self.0 | other.0)
+  }
+}
+
+/// What a piece generator may produce: captures and promotions, quiet moves, or both, onto the
+/// squares of `squares`. Generators leave everything else out as they go rather than filtering
+/// afterwards; for check evasions `squares` is the checker and the squares between it and the
+/// king.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Targets {
+  pub squares: u64,
+  pub captures: bool,
+  pub quiets: bool,
+}
+
+impl Targets {
+  pub const ALL: Self = Self::new(MoveGenFlags::ALL, !0);
+
+  pub const fn new(flags: MoveGenFlags, squares: u64) -> Self {
+    Self {
+      squares,
+      captures: flags.captures(),
+      quiets: flags.quiets(),
+    }
+  }
+
+  /// The squares a capture or a promotion may land on.
+  #[inline(always)]
+  pub const fn capture_mask(&self) -> u64 {
+    if self.captures { self.squares } else { 0 }
+  }
+
+  /// The squares a quiet move may land on.
+  #[inline(always)]
+  pub const fn quiet_mask(&self) -> u64 {
+    if self.quiets { self.squares } else { 0 }
+  }
+}
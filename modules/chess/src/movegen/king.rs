@@ -18,30 +18,40 @@
 
 use crate::{
   constants::{FILE_A, FILE_H},
-  model::{bitboard::BitBoard, gameboard::GameBoard, piecemove::PieceMove},
-  movegen::add_move_to_list,
+  legal::attack::is_square_attacked,
+  model::{
+    castling::{
+      castling_clearance, castling_destinations, castling_king_path, castling_square_attacked,
+    },
+    gameboard::{GameBoard, PieceType},
+    piecemove::PieceMove,
+  },
+  movegen::{add_move_to_list, flags::Targets},
+  stats::{self, Event},
 };
 
+/// Eight steps. A king that may castle stands on its home square, with at most five steps.
 pub const MAX_KING_MOVES: usize = 8;
 
 pub(crate) fn generate_king_moves(state: &GameBoard) -> ([PieceMove; MAX_KING_MOVES], usize) {
+  generate_king_moves_with(state, Targets::ALL)
+}
+
+/// The king moves of the side to move that `targets` allows. Castling is a quiet move.
+pub(crate) fn generate_king_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_KING_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::King));
   let mut moves = [PieceMove::NULL; MAX_KING_MOVES];
   let mut count = 0;
 
   let all_occupied =
     state.pawns | state.knights | state.bishops | state.rooks | state.queens | state.kings;
 
-  let (my_king, other_pieces): (BitBoard, u64) = if state.playing {
-    (
-      state.kings & state.colour,
-      (all_occupied & !state.colour).into(),
-    )
-  } else {
-    (
-      state.kings & !state.colour,
-      (all_occupied & state.colour).into(),
-    )
-  };
+  let my_king = state.kings & state.pieces_of(state.playing);
+  let other_pieces = state.pieces_of(!state.playing).raw() & targets.capture_mask();
+  let quiet_targets = !all_occupied.raw() & targets.quiet_mask();
 
   // Possible king moves the king may make
   let king_move_data: [(i8, Option<u64>); 8] = [
@@ -76,13 +86,12 @@ pub(crate) fn generate_king_moves(state: &GameBoard) -> ([PieceMove; MAX_KING_MO
       add_move_to_list(
         &mut moves,
         &mut count,
-        MAX_KING_MOVES,
         PieceMove::new(from_board, to_board, true, None),
       );
       attackers &= !(1 << to_board);
     }
 
-    let mut quiet_moves = new_pos.raw() & !all_occupied.raw();
+    let mut quiet_moves = new_pos.raw() & quiet_targets;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = if dir > 0 {
@@ -94,7 +103,6 @@ pub(crate) fn generate_king_moves(state: &GameBoard) -> ([PieceMove; MAX_KING_MO
       add_move_to_list(
         &mut moves,
         &mut count,
-        MAX_KING_MOVES,
         PieceMove::new(from_board, to_board, false, None),
       );
 
@@ -102,95 +110,62 @@ pub(crate) fn generate_king_moves(state: &GameBoard) -> ([PieceMove; MAX_KING_MO
     }
   }
 
-  // Check for castling moves
-  let (queen_side, king_side) = if state.playing {
-    state.casling_right_white()
-  } else {
-    state.casling_right_black()
-  };
-
-  // Get my rooks (same color as the king)
-  let my_rooks = if state.playing {
-    state.rooks & state.colour
-  } else {
-    state.rooks & !state.colour
+  // Check for castling moves, queenside first. Unlike the other moves these are fully legal:
+  // the king may not castle out of, through or into check. Castling is a quiet move
+  if !targets.quiets {
+    return (moves, count);
+  }
+  let Some(king) = state.king_square(state.playing) else {
+    return (moves, count);
   };
+  if is_square_attacked(state, king) {
+    return (moves, count);
+  }
+  let my_rooks = state.rooks & state.pieces_of(state.playing);
 
-  if queen_side {
-    // Queenside castling
-    let (king_pos, rook_pos, empty_squares) = if state.playing {
-      // White queenside: King from E1 to C1, Rook from A1 to D1
-      (
-        crate::constants::E1,
-        crate::constants::A1,
-        (1u64 << crate::constants::B1)
-          | (1u64 << crate::constants::C1)
-          | (1u64 << crate::constants::D1),
-      )
-    } else {
-      // Black queenside: King from E8 to C8, Rook from A8 to D8
-      (
-        crate::constants::E8,
-        crate::constants::A8,
-        (1u64 << crate::constants::B8)
-          | (1u64 << crate::constants::C8)
-          | (1u64 << crate::constants::D8),
-      )
+  for kingside in [false, true] {
+    let Some(rook) = state.castling.rook(state.playing, kingside) else {
+      continue;
     };
-
-    // Check if rook is in correct position and path is clear
-    if my_rooks.get_bit(rook_pos).unwrap_or(false) && (all_occupied.raw() & empty_squares) == 0 {
-      let king_to = if state.playing {
-        crate::constants::C1
-      } else {
-        crate::constants::C8
-      };
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_KING_MOVES,
-        PieceMove::new_castling(king_pos, king_to),
-      );
+    if !my_rooks.get_bit(rook).unwrap_or(false)
+      || all_occupied.raw() & castling_clearance(state.playing, king, rook) != 0
+      || path_attacked(
+        state,
+        castling_king_path(state.playing, king, rook),
+        king,
+        rook,
+      )
+    {
+      continue;
     }
-  }
 
-  if king_side {
-    // Kingside castling
-    let (king_pos, rook_pos, empty_squares) = if state.playing {
-      // White kingside: King from E1 to G1, Rook from H1 to F1
-      (
-        crate::constants::E1,
-        crate::constants::H1,
-        (1u64 << crate::constants::F1) | (1u64 << crate::constants::G1),
-      )
+    // Standard positions use the familiar e1g1 form, anything else the king-takes-rook form
+    let (king_to, _) = castling_destinations(state.playing, kingside);
+    let corner_file = if kingside { 7 } else { 0 };
+    let to = if king % 8 == 4 && rook % 8 == corner_file {
+      king_to
     } else {
-      // Black kingside: King from E8 to G8, Rook from H8 to F8
-      (
-        crate::constants::E8,
-        crate::constants::H8,
-        (1u64 << crate::constants::F8) | (1u64 << crate::constants::G8),
-      )
+      rook
     };
-
-    // Check if rook is in correct position and path is clear
-    if my_rooks.get_bit(rook_pos).unwrap_or(false) && (all_occupied.raw() & empty_squares) == 0 {
-      let king_to = if state.playing {
-        crate::constants::G1
-      } else {
-        crate::constants::G8
-      };
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_KING_MOVES,
-        PieceMove::new_castling(king_pos, king_to),
-      );
+    let castle = PieceMove::new_castling(king, to);
+    if state.castling_rook(&castle) == Some(rook) {
+      add_move_to_list(&mut moves, &mut count, castle);
     }
   }
 
   (moves, count)
 }
 
+fn path_attacked(state: &GameBoard, mut path: u64, king: u8, rook: u8) -> bool {
+  while path != 0 {
+    if castling_square_attacked(state, path.trailing_zeros() as u8, king, rook) {
+      return true;
+    }
+    path &= path - 1;
+  }
+  false
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -556,4 +531,70 @@ mod tests {
       sort_and_compare_moves(expected_moves)
     );
   }
+
+  #[test]
+  fn test_generate_king_moves_chess960_castling() {
+    let board = board_from_fen("4k3/8/8/8/8/8/8/1R1K2R1 w KQ - 0 1");
+    let (moves, count) = generate_king_moves(&board);
+    let generated_moves = moves_to_vec(&moves, count);
+    assert!(generated_moves.contains(&PieceMove::new_castling(D1, B1)));
+    assert!(generated_moves.contains(&PieceMove::new_castling(D1, G1)));
+  }
+
+  #[test]
+  fn test_generate_king_moves_castling_rights_not_swapped() {
+    // Only the kingside right remains
+    let board = board_from_fen("4k3/8/8/8/8/8/8/R3K2R w K - 0 1");
+    let (moves, count) = generate_king_moves(&board);
+    let generated_moves = moves_to_vec(&moves, count);
+    assert!(generated_moves.contains(&PieceMove::new_castling(E1, G1)));
+    assert!(!generated_moves.contains(&PieceMove::new_castling(E1, C1)));
+  }
+
+  #[test]
+  fn test_generate_king_moves_no_castling_out_of_check() {
+    // The rook on e8 gives check
+    let board = board_from_fen("4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    let (moves, count) = generate_king_moves(&board);
+    let generated_moves = moves_to_vec(&moves, count);
+    assert!(!generated_moves.contains(&PieceMove::new_castling(E1, G1)));
+    assert!(!generated_moves.contains(&PieceMove::new_castling(E1, C1)));
+  }
+
+  #[test]
+  fn test_generate_king_moves_no_castling_through_or_into_check() {
+    // f1 is attacked, so kingside castling would pass through check; c1 is attacked, so
+    // queenside castling would land in check. b1 being attacked does not matter.
+    let board = board_from_fen("1rr2rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    let (moves, count) = generate_king_moves(&board);
+    let generated_moves = moves_to_vec(&moves, count);
+    assert!(!generated_moves.contains(&PieceMove::new_castling(E1, G1)));
+    assert!(!generated_moves.contains(&PieceMove::new_castling(E1, C1)));
+
+    let board = board_from_fen("1r4k1/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    let (moves, count) = generate_king_moves(&board);
+    let generated_moves = moves_to_vec(&moves, count);
+    assert!(generated_moves.contains(&PieceMove::new_castling(E1, G1)));
+    assert!(generated_moves.contains(&PieceMove::new_castling(E1, C1)));
+  }
+
+  #[test]
+  fn test_generated_castling_is_legal() {
+    let fens = [
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+      "4k3/8/8/8/8/8/8/1R1K2R1 w KQ - 0 1",
+      "1rr2rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+    ];
+    for fen in fens {
+      let board = board_from_fen(fen);
+      let (moves, count) = generate_king_moves(&board);
+      for castle in moves[..count]
+        .iter()
+        .filter(|m| board.castling_rook(m).is_some())
+      {
+        assert!(board.is_move_legal(castle), "{fen}: {castle:?}");
+      }
+    }
+  }
 }
@@ -18,30 +18,38 @@
 
 use crate::{
   constants::{FILE_A, FILE_B, FILE_G, FILE_H}, // Added FILE_A for wrap-around protection
-  model::{bitboard::BitBoard, gameboard::GameBoard, piecemove::PieceMove},
-  movegen::add_move_to_list,
+  model::{
+    gameboard::{GameBoard, PieceType},
+    piecemove::PieceMove,
+  },
+  movegen::{add_move_to_list, flags::Targets},
+  stats::{self, Event},
 };
 
-pub const MAX_KNIGHT_MOVES: usize = 16;
+/// Ten knights, the two of the starting position and eight promoted pawns, with eight moves each.
+pub const MAX_KNIGHT_MOVES: usize = 10 * 8;
 
+/// Every knight move, for the tests below.
+#[cfg(test)]
 pub(crate) fn generate_knight_moves(state: &GameBoard) -> ([PieceMove; MAX_KNIGHT_MOVES], usize) {
+  generate_knight_moves_with(state, Targets::ALL)
+}
+
+/// The knight moves of the side to move that `targets` allows.
+pub(crate) fn generate_knight_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_KNIGHT_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::Knight));
   let mut moves = [PieceMove::NULL; MAX_KNIGHT_MOVES];
   let mut count = 0;
 
   let all_occupied =
     state.pawns | state.knights | state.bishops | state.rooks | state.queens | state.kings;
 
-  let (my_knights, other_pieces): (BitBoard, u64) = if state.playing {
-    (
-      state.knights & state.colour,
-      (all_occupied & !state.colour).into(),
-    )
-  } else {
-    (
-      state.knights & !state.colour,
-      (all_occupied & state.colour).into(),
-    )
-  };
+  let my_knights = state.knights & state.pieces_of(state.playing);
+  let other_pieces = state.pieces_of(!state.playing).raw() & targets.capture_mask();
+  let quiet_targets = !all_occupied.raw() & targets.quiet_mask();
 
   // Generate moves for each knight
   //
@@ -84,14 +92,13 @@ pub(crate) fn generate_knight_moves(state: &GameBoard) -> ([PieceMove; MAX_KNIGH
       add_move_to_list(
         &mut moves,
         &mut count,
-        MAX_KNIGHT_MOVES,
         PieceMove::new(from_board, to_board, true, None),
       );
 
       attackers &= !(1 << to_board);
     }
 
-    let mut quiet_moves = new_pos.raw() & !all_occupied.raw();
+    let mut quiet_moves = new_pos.raw() & quiet_targets;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = if dir > 0 {
@@ -103,7 +110,6 @@ pub(crate) fn generate_knight_moves(state: &GameBoard) -> ([PieceMove; MAX_KNIGH
       add_move_to_list(
         &mut moves,
         &mut count,
-        MAX_KNIGHT_MOVES,
         PieceMove::new(from_board, to_board, false, None),
       );
 
@@ -0,0 +1,210 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! A lazy iterator over the legal moves of a position.
+//!
+//! [`LegalMoves`] runs the piece generators one at a time, in the order [`generate_moves`] uses,
+//! and checks each move as it is asked for. Callers that stop early, such as "is there any
+//! legal move?", skip the generators and legality checks they never reach.
+//!
+//! Only king moves are played out to see whether they leave the king attacked. Every other move
+//! is checked against the [`Pins`] found once per position: a pinned piece keeps to its pin line
+//! and, in check, a move must capture or block the checker.
+//!
+//! [`generate_moves`]: crate::movegen::generate_moves
+
+use core::iter::FusedIterator;
+
+use crate::{
+  legal::{checker::LegalChecker, pins::Pins},
+  model::{gameboard::GameBoard, piecemove::PieceMove},
+  movegen::{
+    bishop::{self, MAX_BISHOP_MOVES},
+    flags::{MoveGenFlags, Targets},
+    king::{self, MAX_KING_MOVES},
+    knight::{self, MAX_KNIGHT_MOVES},
+    pawn::{self, MAX_PAWN_MOVES},
+    queen::{self, MAX_QUEEN_MOVES},
+    rook::{self, MAX_ROOK_MOVES},
+  },
+  stats::{self, Event},
+};
+
+const fn max(a: usize, b: usize) -> usize {
+  if a > b { a } else { b }
+}
+
+/// Room for the moves of the piece type with the most.
+const MAX_STAGE_MOVES: usize = max(
+  max(MAX_PAWN_MOVES, MAX_QUEEN_MOVES),
+  max(
+    max(MAX_BISHOP_MOVES, MAX_ROOK_MOVES),
+    max(MAX_KNIGHT_MOVES, MAX_KING_MOVES),
+  ),
+);
+
+/// The legal moves of a board, generated one piece type at a time. Created by
+/// [`GameBoard::legal_moves_iter`].
+#[derive(Clone)]
+pub struct LegalMoves<'a> {
+  checker: LegalChecker<'a>,
+  pins: Pins,
+  /// The next piece type to generate: pawns, bishops, knights, rooks, queens, then the king.
+  stage: u8,
+  /// The stage to stop before; the king's stage is skipped when it is 5.
+  end: u8,
+  targets: Targets,
+  moves: [PieceMove; MAX_STAGE_MOVES],
+  len: usize,
+  next: usize,
+}
+
+impl<'a> LegalMoves<'a> {
+  pub fn new(board: &'a GameBoard) -> Self {
+    Self {
+      checker: LegalChecker::new(board),
+      pins: Pins::new(board),
+      stage: 0,
+      end: 6,
+      targets: Targets::ALL,
+      moves: [PieceMove::NULL; MAX_STAGE_MOVES],
+      len: 0,
+      next: 0,
+    }
+  }
+
+  /// Like [`new`](Self::new), only the moves `flags` allows. The generators skip the other
+  /// moves rather than have them filtered out here. Legal moves in check are all evasions, so
+  /// `EVASIONS_ONLY` changes nothing.
+  pub fn with_flags(board: &'a GameBoard, flags: MoveGenFlags) -> Self {
+    Self {
+      targets: Targets::new(flags, !0),
+      ..Self::new(board)
+    }
+  }
+
+  /// Like [`new`](Self::new), without the king's moves.
+  pub(crate) fn without_king(board: &'a GameBoard) -> Self {
+    Self {
+      end: 5,
+      ..Self::new(board)
+    }
+  }
+
+  /// Generate the moves of the next piece type. Returns `false` once every type is done.
+  fn refill(&mut self) -> bool {
+    if self.stage >= self.end {
+      return false;
+    }
+    let board = self.checker.board;
+    let targets = self.targets;
+    match self.stage {
+      0 => self.load(pawn::generate_pawn_moves_with(board, targets)),
+      1 => self.load(bishop::generate_bishop_moves_with(board, targets)),
+      2 => self.load(knight::generate_knight_moves_with(board, targets)),
+      3 => self.load(rook::generate_rook_moves_with(board, targets)),
+      4 => self.load(queen::generate_queen_moves_with(board, targets)),
+      5 => self.load(king::generate_king_moves_with(board, targets)),
+      _ => return false,
+    }
+    self.stage += 1;
+    true
+  }
+
+  fn is_legal(&self, piece_move: &PieceMove) -> bool {
+    if Some(piece_move.from_square()) == self.pins.king() {
+      return self.checker.is_move_legal(piece_move);
+    }
+    let legal = self.pins.allows(self.checker.board, piece_move);
+    if !legal {
+      stats::record(Event::IllegalMove);
+    }
+    legal
+  }
+
+  fn load<const N: usize>(&mut self, (moves, count): ([PieceMove; N], usize)) {
+    self.moves[..count].copy_from_slice(&moves[..count]);
+    self.len = count;
+    self.next = 0;
+  }
+}
+
+impl Iterator for LegalMoves<'_> {
+  type Item = PieceMove;
+
+  fn next(&mut self) -> Option<PieceMove> {
+    loop {
+      while self.next < self.len {
+        let piece_move = self.moves[self.next];
+        self.next += 1;
+        if self.is_legal(&piece_move) {
+          return Some(piece_move);
+        }
+      }
+      if !self.refill() {
+        return None;
+      }
+    }
+  }
+}
+
+impl FusedIterator for LegalMoves<'_> {}
+
+#[cfg(test)]
+mod tests {
+  use crate::{constants::*, model::gamedata::GameData, movegen::generate_moves};
+
+  #[test]
+  fn test_matches_filtered_generator() {
+    for fen in [
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+      "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ] {
+      let board = GameData::from_fen(fen).unwrap().board;
+      let (moves, count) = generate_moves(&board);
+      let expected = moves[..count]
+        .iter()
+        .copied()
+        .filter(|piece_move| board.is_move_legal(piece_move));
+      assert!(board.legal_moves_iter().eq(expected), "{fen}");
+    }
+  }
+
+  #[test]
+  fn test_stops_early() {
+    // Checkmated: every stage runs dry
+    let mated = GameData::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+      .unwrap()
+      .board;
+    let mut moves = mated.legal_moves_iter();
+    assert_eq!(moves.next(), None);
+    assert_eq!(moves.next(), None);
+
+    // Only the king can move, and it comes last
+    let board = GameData::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1")
+      .unwrap()
+      .board;
+    assert_eq!(board.legal_moves_iter().count(), 5);
+    assert_eq!(
+      board.legal_moves_iter().next().map(|m| m.from_square()),
+      Some(E1)
+    );
+  }
+}
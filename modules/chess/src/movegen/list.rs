@@ -0,0 +1,161 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! A fixed-capacity list of moves.
+
+use core::ops::{Deref, DerefMut};
+
+use crate::{
+  model::{gameboard::GameBoard, piecemove::PieceMove},
+  movegen::{MAX_MOVES, add_move_to_list, generate_moves},
+};
+
+/// The moves of one position, stored inline so no allocation is needed.
+#[derive(Clone, Copy)]
+pub struct MoveList {
+  moves: [PieceMove; MAX_MOVES],
+  len: usize,
+}
+
+impl MoveList {
+  pub const fn new() -> Self {
+    Self {
+      moves: [PieceMove::NULL; MAX_MOVES],
+      len: 0,
+    }
+  }
+
+  /// The pseudo-legal moves of `board`, in generator order.
+  pub fn generate(board: &GameBoard) -> Self {
+    let (moves, len) = generate_moves(board);
+    Self { moves, len }
+  }
+
+  /// Append `piece_move`. A full list drops the move, like the generators do.
+  pub fn push(&mut self, piece_move: PieceMove) {
+    add_move_to_list(&mut self.moves, &mut self.len, piece_move);
+  }
+
+  pub fn clear(&mut self) {
+    self.len = 0;
+  }
+
+  /// Sort into the canonical order of [`PieceMove`]'s `Ord`: by from square, then to square,
+  /// then promotion piece, then capture flag. Use this when the order must be reproducible, such
+  /// as in test expectations or files on disk.
+  pub fn sort(&mut self) {
+    self.as_mut_slice().sort_unstable();
+  }
+
+  pub fn as_slice(&self) -> &[PieceMove] {
+    &self.moves[..self.len]
+  }
+
+  pub fn as_mut_slice(&mut self) -> &mut [PieceMove] {
+    &mut self.moves[..self.len]
+  }
+}
+
+impl Default for MoveList {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Deref for MoveList {
+  type Target = [PieceMove];
+
+  fn deref(&self) -> &[PieceMove] {
+    self.as_slice()
+  }
+}
+
+impl DerefMut for MoveList {
+  fn deref_mut(&mut self) -> &mut [PieceMove] {
+    self.as_mut_slice()
+  }
+}
+
+impl core::fmt::Debug for MoveList {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_list().entries(self.as_slice()).finish()
+  }
+}
+
+impl PartialEq for MoveList {
+  fn eq(&self, other: &Self) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl Eq for MoveList {}
+
+impl<'a> IntoIterator for &'a MoveList {
+  type Item = &'a PieceMove;
+  type IntoIter = core::slice::Iter<'a, PieceMove>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.as_slice().iter()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::piecemove::PromotionType};
+
+  #[test]
+  fn test_generate_matches_generate_moves() {
+    let list = MoveList::generate(&GameBoard::START_POS);
+    let (moves, count) = generate_moves(&GameBoard::START_POS);
+    assert_eq!(list.len(), 20);
+    assert_eq!(list.as_slice(), &moves[..count]);
+  }
+
+  #[test]
+  fn test_sort_is_canonical() {
+    let mut list = MoveList::new();
+    list.push(PieceMove::new(E7, E8, false, Some(PromotionType::Knight)));
+    list.push(PieceMove::new(E7, D8, true, Some(PromotionType::Queen)));
+    list.push(PieceMove::new(E7, E8, false, Some(PromotionType::Queen)));
+    list.push(PieceMove::new(E7, E8, false, None));
+    list.push(PieceMove::new(A2, A3, false, None));
+    list.sort();
+    assert_eq!(
+      list.as_slice(),
+      [
+        PieceMove::new(A2, A3, false, None),
+        PieceMove::new(E7, D8, true, Some(PromotionType::Queen)),
+        PieceMove::new(E7, E8, false, None),
+        PieceMove::new(E7, E8, false, Some(PromotionType::Queen)),
+        PieceMove::new(E7, E8, false, Some(PromotionType::Knight)),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_push_and_clear() {
+    let mut list = MoveList::default();
+    assert!(list.is_empty());
+    list.push(PieceMove::new(G1, F3, false, None));
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0], PieceMove::new(G1, F3, false, None));
+    list.clear();
+    assert!(list.is_empty());
+  }
+}
@@ -0,0 +1,351 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Magic bitboard attack tables for the sliding pieces, with the `magics` feature.
+//!
+//! The squares that can block a rook or bishop on a square are its *mask*: the rays from the
+//! square without their last square, since a piece on the edge blocks nothing further. Every
+//! subset of the mask has its own attack set, and the table holds them all, indexed by
+//! multiplying the masked occupancy by the square's magic number and keeping the top bits. On
+//! x86-64 built with BMI2 the index is the masked occupancy packed by `pext` instead, and no
+//! magic is needed.
+//!
+//! The tables are built by `const fn` at compile time like those in [`crate::model::rays`], and
+//! the build fails if a magic maps two subsets with different attacks to one slot. The magics
+//! were found by random search and are fixed, so the tables are the same on every build.
+//!
+//! | Table            | Entries | Size    |
+//! |------------------|---------|---------|
+//! | `ROOK_ATTACKS`   | 102,400 | 800 KiB |
+//! | `BISHOP_ATTACKS` | 5,248   | 41 KiB  |
+
+use crate::{
+  model::{
+    gameboard::GameBoard,
+    piecemove::PieceMove,
+    rays::{CacheAligned, DIR_OFFSETS, RAYS},
+  },
+  movegen::{add_move_to_list, flags::Targets},
+};
+
+/// Entries of the rook table: `2^bits` for each square, summed.
+pub const ROOK_TABLE_SIZE: usize = 102_400;
+/// Entries of the bishop table.
+pub const BISHOP_TABLE_SIZE: usize = 5_248;
+
+#[rustfmt::skip]
+const ROOK_MAGICS: [u64; 64] = [
+  0x0080_0020_4000_8011, 0x6640_0040_1000_2002, 0x4100_0820_0500_4010, 0x0280_0801_1000_812C,
+  0x0080_0400_0800_8002, 0x0180_0600_0400_8001, 0x0200_4100_8408_0200, 0x0A00_0080_4104_0022,
+  0x1820_8014_4000_2080, 0x1021_0020_8108_4000, 0x1090_8010_0020_0080, 0x0202_0008_4200_2010,
+  0x8010_8028_0084_0180, 0x0044_8002_0080_1401, 0x0815_0002_0001_0004, 0x00C2_8024_C080_1100,
+  0x0080_02C0_2000_4000, 0x0011_8100_4002_6B01, 0x8601_0100_2000_1040, 0x0290_0080_1080_0804,
+  0x0089_0100_1008_0006, 0x00C6_0080_8004_0002, 0x4020_1400_1002_2118, 0x0200_0A00_0840_8104,
+  0x0080_0046_4008_2008, 0x1091_1081_0020_4000, 0x0000_2004_8010_0084, 0x4002_1001_0022_0901,
+  0x0610_0801_0010_0500, 0x0243_0009_0002_8400, 0x10A0_0401_0100_0200, 0x0202_0082_0001_0044,
+  0x0040_0082_4380_0029, 0x0090_0840_0440_2002, 0x1890_0020_0080_1082, 0x8009_0008_2100_1002,
+  0x0108_0800_8080_0400, 0x8002_0200_8080_0400, 0x0114_1022_0400_0188, 0x0C84_0084_0200_2041,
+  0x81C2_0080_4102_0020, 0x0840_2000_4000_8080, 0x0040_2005_0441_0010, 0x0000_1000_0800_8080,
+  0x0040_0400_0800_8080, 0x0004_0002_0100_4040, 0x2280_0210_8104_0008, 0x0000_4044_0082_0001,
+  0x0000_8008_4425_0100, 0x00A0_0040_0230_01C0, 0x0200_8020_0010_0080, 0x0011_0510_0108_2100,
+  0x2010_0411_0008_0100, 0x0084_0002_0100_4040, 0x2070_1022_0128_0400, 0x0050_0061_0400_8200,
+  0x4000_1502_0020_4482, 0x1080_8040_0010_2101, 0x0002_0008_1020_4082, 0x0000_2100_08B0_0025,
+  0x0002_0104_1020_4882, 0x040A_0010_0801_8402, 0x0810_9002_0108_0084, 0x9002_0083_0524_0042,
+];
+
+#[rustfmt::skip]
+const BISHOP_MAGICS: [u64; 64] = [
+  0x8404_0434_0044_0300, 0x2028_0124_2400_4840, 0x40C2_0400_4082_A000, 0x0014_A102_00A0_0000,
+  0x4004_0420_4042_0000, 0x0200_8804_4200_4000, 0x4202_00B0_0412_0000, 0x0881_0402_1B01_0806,
+  0x2004_4004_1182_2200, 0x0081_4448_00C1_0208, 0x8000_0942_1E02_0180, 0x5020_A410_4A00_C028,
+  0x0180_2C24_2000_4000, 0x0000_C088_0440_4050, 0x0014_1304_4120_0905, 0x8300_0100_8084_2000,
+  0x1040_1808_0284_0401, 0x0851_2008_0101_0400, 0x0690_20E8_0840_4008, 0x0402_4024_0102_0100,
+  0x0081_0008_2008_3800, 0x4808_8001_0060_0220, 0x8060_5004_8404_2004, 0x100A_0041_0842_0208,
+  0x0082_4020_9004_0821, 0x0108_2500_0801_2800, 0x1880_4040_0401_0200, 0x4120_0800_0100_4008,
+  0x0101_0010_0100_4003, 0x0000_5080_0100_A00C, 0x0308_0106_C603_4340, 0x100C_0080_8040_5450,
+  0xC0B0_0804_8022_0404, 0x0001_1002_6090_8400, 0x0080_8401_10D0_0040, 0x4408_0404_0088_0210,
+  0x0000_4404_0100_4100, 0x8002_0046_0001_00A0, 0x4204_2888_8014_0444, 0x0211_4102_0403_0440,
+  0x0408_0111_10A0_0800, 0x8000_8201_2000_1001, 0x8100_2018_4800_D000, 0x8316_2620_1804_0100,
+  0x9000_0400_9400_0200, 0x0040_2808_8021_0100, 0x0228_8101_1200_3402, 0x0854_0428_4206_004C,
+  0x0402_2D04_2004_0003, 0x0002_1104_2104_0080, 0x0080_0852_0804_0002, 0x0000_04C0_4202_04D0,
+  0x0090_1009_1024_0000, 0xA202_0510_0282_0100, 0x0010_0481_0812_0000, 0x0060_4142_0200_4800,
+  0x8211_8400_4130_0800, 0x4000_4024_2108_0800, 0x0802_B800_C608_0482, 0x0008_0000_9042_0202,
+  0x8024_2800_A202_4400, 0x0C40_1060_0801_1846, 0x8000_0404_1084_0100, 0x4044_100A_004B_0A00,
+];
+
+/// How to find the attacks of one square in its table.
+#[derive(Debug, Clone, Copy)]
+struct Magic {
+  mask: u64,
+  magic: u64,
+  /// 64 minus the number of mask bits.
+  shift: u32,
+  /// Where the square's attacks start in the table.
+  offset: usize,
+}
+
+impl Magic {
+  #[inline(always)]
+  fn index(&self, occupancy: u64) -> usize {
+    #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+    // SAFETY: the cfg above only compiles this for targets that have BMI2
+    let index = unsafe { core::arch::x86_64::_pext_u64(occupancy, self.mask) } as usize;
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+    let index = ((occupancy & self.mask).wrapping_mul(self.magic) >> self.shift) as usize;
+    self.offset + index
+  }
+}
+
+/// Rook directions in [`DIR_OFFSETS`] order.
+const ROOK_DIRECTIONS: [usize; 4] = [0, 1, 2, 3];
+/// Bishop directions in [`DIR_OFFSETS`] order.
+const BISHOP_DIRECTIONS: [usize; 4] = [4, 5, 6, 7];
+
+/// The last square of a non-empty ray, the one on the edge of the board.
+const fn ray_end(ray: u64, direction: usize) -> u64 {
+  if DIR_OFFSETS[direction] > 0 {
+    1 << (63 - ray.leading_zeros())
+  } else {
+    ray & ray.wrapping_neg()
+  }
+}
+
+/// Attacks along `directions` from `square`, each ray cut at its first blocker.
+const fn slow_attacks(square: usize, occupancy: u64, directions: &[usize; 4]) -> u64 {
+  let mut attacks = 0;
+  let mut i = 0;
+  while i < 4 {
+    let direction = directions[i];
+    let ray = RAYS.0[square][direction];
+    let blockers = ray & occupancy;
+    attacks |= if blockers == 0 {
+      ray
+    } else {
+      let blocker = if DIR_OFFSETS[direction] > 0 {
+        blockers.trailing_zeros()
+      } else {
+        63 - blockers.leading_zeros()
+      };
+      ray & !RAYS.0[blocker as usize][direction]
+    };
+    i += 1;
+  }
+  attacks
+}
+
+const fn build_magics(magics: &[u64; 64], directions: &[usize; 4]) -> [Magic; 64] {
+  let mut entries = [Magic {
+    mask: 0,
+    magic: 0,
+    shift: 0,
+    offset: 0,
+  }; 64];
+  let mut offset = 0;
+  let mut square = 0;
+  while square < 64 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < 4 {
+      let ray = RAYS.0[square][directions[i]];
+      if ray != 0 {
+        mask |= ray & !ray_end(ray, directions[i]);
+      }
+      i += 1;
+    }
+    entries[square] = Magic {
+      mask,
+      magic: magics[square],
+      shift: 64 - mask.count_ones(),
+      offset,
+    };
+    offset += 1 << mask.count_ones();
+    square += 1;
+  }
+  entries
+}
+
+/// Fill the table of `entries` with the attacks of every subset of every mask, walking the
+/// subsets with the carry-rippler trick. The walk visits the subsets in the order of their bits
+/// packed under the mask, so the `n`th subset is at slot `n` for `pext`. A slot is never empty
+/// once filled, as every square attacks at least one other, so a slot that is already taken by
+/// other attacks means the magic is bad.
+const fn build_table<const N: usize>(entries: &[Magic; 64], directions: &[usize; 4]) -> [u64; N] {
+  let mut table = [0; N];
+  let mut square = 0;
+  while square < 64 {
+    let entry = &entries[square];
+    let mut subset: u64 = 0;
+    let mut packed = 0;
+    loop {
+      let attacks = slow_attacks(square, subset, directions);
+      let index = if cfg!(all(target_arch = "x86_64", target_feature = "bmi2")) {
+        entry.offset + packed
+      } else {
+        entry.offset + (subset.wrapping_mul(entry.magic) >> entry.shift) as usize
+      };
+      if table[index] != 0 && table[index] != attacks {
+        panic!("magic number maps two occupancies with different attacks to one slot");
+      }
+      table[index] = attacks;
+      packed += 1;
+      subset = subset.wrapping_sub(entry.mask) & entry.mask;
+      if subset == 0 {
+        break;
+      }
+    }
+    square += 1;
+  }
+  table
+}
+
+static ROOK_ENTRIES: CacheAligned<[Magic; 64]> =
+  CacheAligned(build_magics(&ROOK_MAGICS, &ROOK_DIRECTIONS));
+static BISHOP_ENTRIES: CacheAligned<[Magic; 64]> =
+  CacheAligned(build_magics(&BISHOP_MAGICS, &BISHOP_DIRECTIONS));
+
+static ROOK_ATTACKS: CacheAligned<[u64; ROOK_TABLE_SIZE]> =
+  CacheAligned(build_table(&ROOK_ENTRIES.0, &ROOK_DIRECTIONS));
+static BISHOP_ATTACKS: CacheAligned<[u64; BISHOP_TABLE_SIZE]> =
+  CacheAligned(build_table(&BISHOP_ENTRIES.0, &BISHOP_DIRECTIONS));
+
+/// Squares a rook on `square` attacks with `occupancy` as the occupied squares, the first
+/// blocker in each direction included. `square` must be below 64.
+#[inline]
+pub fn rook_attacks(square: u8, occupancy: u64) -> u64 {
+  let entry = &ROOK_ENTRIES[square as usize];
+  ROOK_ATTACKS[entry.index(occupancy)]
+}
+
+/// Like [`rook_attacks`], along the diagonals.
+#[inline]
+pub fn bishop_attacks(square: u8, occupancy: u64) -> u64 {
+  let entry = &BISHOP_ENTRIES[square as usize];
+  BISHOP_ATTACKS[entry.index(occupancy)]
+}
+
+/// The union of [`rook_attacks`] and [`bishop_attacks`].
+#[inline]
+pub fn queen_attacks(square: u8, occupancy: u64) -> u64 {
+  rook_attacks(square, occupancy) | bishop_attacks(square, occupancy)
+}
+
+/// Moves of the side to move's `pieces` that attack with `attacks`: captures of enemy pieces
+/// and quiet moves to empty squares, as far as `targets` allows. The rook, bishop and queen
+/// generators with this feature.
+#[inline]
+pub(crate) fn generate_slider_moves<const N: usize>(
+  state: &GameBoard,
+  pieces: u64,
+  attacks: fn(u8, u64) -> u64,
+  targets: Targets,
+) -> ([PieceMove; N], usize) {
+  let mut moves = [PieceMove::NULL; N];
+  let mut count = 0;
+  let occupancy = state.combined().raw();
+  let own: u64 = state.pieces_of(state.playing).into();
+  let mut pieces = pieces & own;
+  while pieces != 0 {
+    let from = pieces.trailing_zeros() as u8;
+    let reach = attacks(from, occupancy) & !own;
+    let mut captures = reach & occupancy & targets.capture_mask();
+    while captures != 0 {
+      let to = captures.trailing_zeros() as u8;
+      add_move_to_list(&mut moves, &mut count, PieceMove::new(from, to, true, None));
+      captures &= captures - 1;
+    }
+    let mut quiet_moves = reach & !occupancy & targets.quiet_mask();
+    while quiet_moves != 0 {
+      let to = quiet_moves.trailing_zeros() as u8;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from, to, false, None),
+      );
+      quiet_moves &= quiet_moves - 1;
+    }
+    pieces &= pieces - 1;
+  }
+  (moves, count)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constants::*;
+
+  #[test]
+  fn test_table_sizes() {
+    assert_eq!(
+      ROOK_ENTRIES[63].offset + (1 << (64 - ROOK_ENTRIES[63].shift)),
+      ROOK_TABLE_SIZE
+    );
+    assert_eq!(
+      BISHOP_ENTRIES[63].offset + (1 << (64 - BISHOP_ENTRIES[63].shift)),
+      BISHOP_TABLE_SIZE
+    );
+    // Corners have the largest rook masks and the centre the largest bishop masks
+    assert_eq!(ROOK_ENTRIES[A1 as usize].mask.count_ones(), 12);
+    assert_eq!(ROOK_ENTRIES[D4 as usize].mask.count_ones(), 10);
+    assert_eq!(BISHOP_ENTRIES[D4 as usize].mask.count_ones(), 9);
+    assert_eq!(BISHOP_ENTRIES[A1 as usize].mask.count_ones(), 6);
+  }
+
+  #[test]
+  fn test_matches_ray_walk() {
+    // Random occupancies, from sparse to dense, on every square
+    let mut state = 0x006C_756D_6966_6F78u64;
+    let mut random = || {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      state
+    };
+    for _ in 0..200 {
+      let occupancy = match random() % 3 {
+        0 => random() & random() & random(),
+        1 => random() & random(),
+        _ => random(),
+      };
+      for square in 0..64u8 {
+        let index = square as usize;
+        assert_eq!(
+          rook_attacks(square, occupancy),
+          slow_attacks(index, occupancy, &ROOK_DIRECTIONS),
+          "rook on {square}, occupancy {occupancy:#x}"
+        );
+        assert_eq!(
+          bishop_attacks(square, occupancy),
+          slow_attacks(index, occupancy, &BISHOP_DIRECTIONS),
+          "bishop on {square}, occupancy {occupancy:#x}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn test_known_attacks() {
+    // Rook on d4 blocked on d6 and f4: d5, d6, e4, f4 plus the open rays
+    let occupancy = (1 << D6) | (1 << F4) | (1 << D4);
+    let expected = [D5, D6, E4, F4, C4, B4, A4, D3, D2, D1]
+      .iter()
+      .fold(0u64, |acc, &square| acc | (1 << square));
+    assert_eq!(rook_attacks(D4, occupancy), expected);
+    assert_eq!(bishop_attacks(A1, u64::MAX), 1 << B2);
+    assert_eq!(queen_attacks(H8, 0).count_ones(), 21);
+  }
+}
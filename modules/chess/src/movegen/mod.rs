@@ -20,81 +20,406 @@
 //!
 //! This module contains fast, allocation-free move generation for each piece
 //! type and a convenient `generate_moves` entry point that returns a fixed-size
-//! array of `PieceMove` and the number of generated moves.
+//! array of `PieceMove` and the number of generated moves. Those moves are pseudo-legal;
+//! `generate_legal_moves` returns only the legal ones in the same shape, and `LegalMoves` yields
+//! them lazily. `generate_moves_with` and `generate_legal_moves_with` take [`MoveGenFlags`] to
+//! produce only captures, only quiet moves or only check evasions.
 //!
 //! The implementation is optimized for performance and favors stack buffers
 //! and const-sized arrays to remain `no_std` friendly.
 
 use crate::{
+  legal::pins::Pins,
   model::{gameboard::GameBoard, piecemove::PieceMove},
-  movegen::{bishop::MAX_BISHOP_MOVES, knight::MAX_KNIGHT_MOVES, pawn::MAX_PAWN_MOVES},
+  movegen::flags::Targets,
 };
 
 pub mod bishop;
+pub mod flags;
 pub mod king;
 pub mod knight;
+pub mod legal_moves;
+pub mod list;
+#[cfg(feature = "magics")]
+pub mod magics;
 pub mod pawn;
 pub mod queen;
 pub mod rook;
 
-pub const MAX_MOVES: usize = MAX_PAWN_MOVES
-  + MAX_BISHOP_MOVES
-  + MAX_KNIGHT_MOVES
-  + rook::MAX_ROOK_MOVES
-  + queen::MAX_QUEEN_MOVES
-  + king::MAX_KING_MOVES;
-
-/// Helper function to add a move
-#[inline]
-fn add_move_to_list(
-  moves: &mut [PieceMove],
-  count: &mut usize,
-  size: usize,
-  piece_move: PieceMove,
-) {
+pub use flags::MoveGenFlags;
+pub use legal_moves::LegalMoves;
+pub use list::MoveList;
+
+/// Most pseudo-legal moves a position can have: the king has at most 8, and each of the other 15
+/// pieces at most 27, as many as a queen in the middle of an empty board. The per-piece limits
+/// are larger, as one side can have up to ten knights, bishops or rooks or nine queens, but not
+/// all of them at once. Legal positions are known with 218 legal moves.
+pub const MAX_MOVES: usize = king::MAX_KING_MOVES + 15 * 27;
+
+/// Append `piece_move` to `moves` and bump `count`.
+///
+/// Every generator goes through here so a full buffer behaves the same everywhere: debug builds
+/// panic, since it means a `MAX_*` constant is too small, while release builds drop the move
+/// rather than write out of bounds.
+#[inline(always)]
+#[track_caller]
+fn add_move_to_list(moves: &mut [PieceMove], count: &mut usize, piece_move: PieceMove) {
   debug_assert!(
-    *count <= size,
-    "Count {} exceeds size limit of {}",
-    *count,
-    size
+    *count < moves.len(),
+    "Move list overflow: buffer holds {} moves",
+    moves.len()
   );
-  moves[*count] = piece_move;
-  *count += 1;
+  if let Some(slot) = moves.get_mut(*count) {
+    *slot = piece_move;
+    *count += 1;
+  }
 }
 
 pub fn generate_moves(state: &GameBoard) -> ([PieceMove; MAX_MOVES], usize) {
+  generate_for_targets(state, Targets::ALL, Targets::ALL)
+}
+
+/// Like [`generate_moves`], producing only the moves `flags` allows, in the same order. Each
+/// piece generator is told what is wanted and never builds the rest: `CAPTURES_ONLY` skips quiet
+/// moves and castling, `QUIETS_ONLY` skips captures, promotions and en passant, and
+/// `EVASIONS_ONLY` keeps the pieces other than the king to the squares that answer the check,
+/// generating only king moves in double check. The moves are still pseudo-legal.
+pub fn generate_moves_with(
+  state: &GameBoard,
+  flags: MoveGenFlags,
+) -> ([PieceMove; MAX_MOVES], usize) {
+  let king = Targets::new(flags, !0);
+  let others = if flags.contains(MoveGenFlags::EVASIONS_ONLY) {
+    Targets::new(flags, Pins::new(state).check_mask())
+  } else {
+    king
+  };
+  generate_for_targets(state, others, king)
+}
+
+/// The moves `king` allows the king and `others` allows every other piece.
+#[inline(always)]
+fn generate_for_targets(
+  state: &GameBoard,
+  others: Targets,
+  king: Targets,
+) -> ([PieceMove; MAX_MOVES], usize) {
+  let mut moves = [PieceMove::NULL; MAX_MOVES];
+  let mut count = 0;
+  let mut extend = |generated: &[PieceMove]| {
+    for &piece_move in generated {
+      add_move_to_list(&mut moves, &mut count, piece_move);
+    }
+  };
+
+  if others.squares != 0 {
+    let (pawn_moves, pawn_count) = pawn::generate_pawn_moves_with(state, others);
+    extend(&pawn_moves[..pawn_count]);
+    let (bishop_moves, bishop_count) = bishop::generate_bishop_moves_with(state, others);
+    extend(&bishop_moves[..bishop_count]);
+    let (knight_moves, knight_count) = knight::generate_knight_moves_with(state, others);
+    extend(&knight_moves[..knight_count]);
+    let (rook_moves, rook_count) = rook::generate_rook_moves_with(state, others);
+    extend(&rook_moves[..rook_count]);
+    let (queen_moves, queen_count) = queen::generate_queen_moves_with(state, others);
+    extend(&queen_moves[..queen_count]);
+  }
+  let (king_moves, king_count) = king::generate_king_moves_with(state, king);
+  extend(&king_moves[..king_count]);
+
+  (moves, count)
+}
+
+/// Like [`generate_moves`], keeping only the legal moves: pinned pieces stay on their pin lines,
+/// a king in check must be got out of it, and castling out of or through check is left out.
+/// The moves come in the same order, as [`LegalMoves`] yields them.
+pub fn generate_legal_moves(state: &GameBoard) -> ([PieceMove; MAX_MOVES], usize) {
+  let mut moves = [PieceMove::NULL; MAX_MOVES];
+  let mut count = 0;
+  for piece_move in LegalMoves::new(state) {
+    add_move_to_list(&mut moves, &mut count, piece_move);
+  }
+  (moves, count)
+}
+
+/// Like [`generate_legal_moves`], producing only the moves `flags` allows, which the piece
+/// generators skip as in [`generate_moves_with`]. Legal moves in check are all evasions, so
+/// `EVASIONS_ONLY` changes nothing here.
+pub fn generate_legal_moves_with(
+  state: &GameBoard,
+  flags: MoveGenFlags,
+) -> ([PieceMove; MAX_MOVES], usize) {
   let mut moves = [PieceMove::NULL; MAX_MOVES];
   let mut count = 0;
+  for piece_move in LegalMoves::with_flags(state, flags) {
+    add_move_to_list(&mut moves, &mut count, piece_move);
+  }
+  (moves, count)
+}
 
-  let (pawn_moves, pawn_count) = pawn::generate_pawn_moves(state);
-  for &piece_move in pawn_moves.iter().take(pawn_count) {
-    add_move_to_list(&mut moves, &mut count, MAX_MOVES, piece_move);
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::gamedata::GameData};
+
+  /// Leaf nodes `depth` plies below `board`, playing only generated legal moves.
+  fn legal_perft(board: &GameBoard, depth: u32) -> u64 {
+    if depth == 0 {
+      return 1;
+    }
+    let (moves, count) = generate_legal_moves(board);
+    moves[..count]
+      .iter()
+      .map(|piece_move| {
+        let mut child = *board;
+        assert!(child.move_piece(piece_move).is_some(), "{piece_move:?}");
+        legal_perft(&child, depth - 1)
+      })
+      .sum()
   }
 
-  let (bishop_moves, bishop_count) = bishop::generate_bishop_moves(state);
-  for &piece_move in bishop_moves.iter().take(bishop_count) {
-    add_move_to_list(&mut moves, &mut count, MAX_MOVES, piece_move);
+  #[test]
+  fn test_generate_legal_moves_perft() {
+    for (fen, depth, nodes) in [
+      (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        3,
+        8_902,
+      ),
+      (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        2,
+        2_039,
+      ),
+      ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3, 2_812),
+      (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        2,
+        264,
+      ),
+      (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        2,
+        1_486,
+      ),
+    ] {
+      let board = GameData::from_fen(fen).unwrap().board;
+      assert_eq!(legal_perft(&board, depth), nodes, "{fen}");
+    }
   }
 
-  let (knight_moves, knight_count) = knight::generate_knight_moves(state);
-  for &piece_move in knight_moves.iter().take(knight_count) {
-    add_move_to_list(&mut moves, &mut count, MAX_MOVES, piece_move);
+  #[test]
+  fn test_generate_legal_moves_filters() {
+    let legal = |fen: &str| {
+      let board = GameData::from_fen(fen).unwrap().board;
+      let (moves, count) = generate_legal_moves(&board);
+      moves[..count].to_vec()
+    };
+
+    // The e-file bishop is pinned and may not leave it; the king may not castle through f1
+    let moves = legal("4r1k1/8/8/8/8/8/4B3/4K2R w K - 0 1");
+    assert!(moves.iter().all(|m| m.from_square() != E2));
+    let moves = legal("4k3/8/8/8/8/8/5r2/4K2R w K - 0 1");
+    assert!(
+      moves
+        .iter()
+        .all(|m| m.from_square() != E1 || !matches!(m.to_square(), G1 | H1))
+    );
+    // In check, only moves that answer it
+    let moves = legal("4k3/8/8/8/8/8/8/r3K1N1 w - - 0 1");
+    assert!(moves.iter().all(|m| m.from_square() == E1));
+    assert!(!moves.is_empty());
   }
 
-  let (rook_moves, rook_count) = rook::generate_rook_moves(state);
-  for &piece_move in rook_moves.iter().take(rook_count) {
-    add_move_to_list(&mut moves, &mut count, MAX_MOVES, piece_move);
+  fn generated(fen: &str, flags: MoveGenFlags) -> (GameBoard, Vec<PieceMove>) {
+    let board = GameData::from_fen(fen).unwrap().board;
+    let (moves, count) = generate_moves_with(&board, flags);
+    (board, moves[..count].to_vec())
   }
 
-  let (queen_moves, queen_count) = queen::generate_queen_moves(state);
-  for &piece_move in queen_moves.iter().take(queen_count) {
-    add_move_to_list(&mut moves, &mut count, MAX_MOVES, piece_move);
+  #[test]
+  fn test_captures_and_quiets_split_all_moves() {
+    for fen in [
+      "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+      "r3k2r/8/8/8/Pp6/8/8/R3K2R b KQkq a3 0 1",
+      "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ] {
+      let (board, all) = generated(fen, MoveGenFlags::ALL);
+      let (expected, count) = generate_moves(&board);
+      assert_eq!(all, &expected[..count], "{fen}");
+      let (_, captures) = generated(fen, MoveGenFlags::CAPTURES_ONLY);
+      let (_, quiets) = generated(fen, MoveGenFlags::QUIETS_ONLY);
+      let tactical = |m: &PieceMove| m.is_capture() || m.is_promotion();
+      assert!(captures.iter().all(tactical), "{fen}");
+      assert!(!quiets.iter().any(tactical), "{fen}");
+      // Same order as the full list, with nothing lost
+      let filtered: Vec<_> = all.iter().copied().filter(tactical).collect();
+      assert_eq!(captures, filtered, "{fen}");
+      assert_eq!(captures.len() + quiets.len(), all.len(), "{fen}");
+    }
+
+    // En passant is a capture and castling a quiet move
+    let fen = "r3k2r/8/8/8/Pp6/8/8/R3K2R b KQkq a3 0 1";
+    let (_, captures) = generated(fen, MoveGenFlags::CAPTURES_ONLY);
+    assert!(captures.contains(&PieceMove::new_en_passant(B4, A3)));
+    let (_, quiets) = generated(fen, MoveGenFlags::QUIETS_ONLY);
+    assert!(quiets.contains(&PieceMove::new_castling(E8, G8)));
+    assert!(quiets.contains(&PieceMove::new_castling(E8, C8)));
+    assert!(
+      !captures
+        .iter()
+        .any(|m| m.from_square() == E8 && !m.is_capture())
+    );
+    assert!(!quiets.iter().any(PieceMove::is_en_passant));
   }
 
-  let (king_moves, king_count) = king::generate_king_moves(state);
-  for &piece_move in king_moves.iter().take(king_count) {
-    add_move_to_list(&mut moves, &mut count, MAX_MOVES, piece_move);
+  #[test]
+  fn test_evasions() {
+    for fen in [
+      // Rook check that can be blocked or captured
+      "4k3/8/8/8/8/8/1B6/r3K1N1 w - - 0 1",
+      // Pawn check answered by taking the pawn en passant
+      "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+      // Double check: only the king may move
+      "4k3/8/8/8/8/5n2/8/r3K2R w K - 0 1",
+      // Not in check: nothing is left out
+      "r3k2r/8/8/8/Pp6/8/8/R3K2R b KQkq a3 0 1",
+    ] {
+      let (board, evasions) = generated(fen, MoveGenFlags::EVASIONS_ONLY);
+      let (_, all) = generated(fen, MoveGenFlags::ALL);
+      let legal: Vec<_> = all
+        .iter()
+        .copied()
+        .filter(|m| board.is_move_legal(m))
+        .collect();
+      // Every legal move is an evasion, and the evasions differ only by moves that are illegal
+      // for other reasons
+      let legal_evasions: Vec<_> = evasions
+        .iter()
+        .copied()
+        .filter(|m| board.is_move_legal(m))
+        .collect();
+      assert_eq!(legal_evasions, legal, "{fen}");
+      assert!(evasions.iter().all(|m| all.contains(m)), "{fen}");
+    }
+
+    let (_, evasions) = generated(
+      "4k3/8/8/8/8/8/1B6/r3K1N1 w - - 0 1",
+      MoveGenFlags::EVASIONS_ONLY,
+    );
+    assert!(evasions.contains(&PieceMove::new(B2, C1, false, None)));
+    assert!(evasions.iter().all(|m| m.from_square() != G1));
+    let (_, evasions) = generated(
+      "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+      MoveGenFlags::EVASIONS_ONLY,
+    );
+    assert!(evasions.contains(&PieceMove::new_en_passant(E4, D3)));
+    let (_, evasions) = generated(
+      "4k3/8/8/8/8/5n2/8/r3K2R w K - 0 1",
+      MoveGenFlags::EVASIONS_ONLY,
+    );
+    assert!(!evasions.is_empty() && evasions.iter().all(|m| m.from_square() == E1));
+
+    // Only captures among the evasions: the bishop takes the checker
+    let (_, evasions) = generated(
+      "4k3/8/8/8/8/8/1B6/r3K1N1 w - - 0 1",
+      MoveGenFlags::CAPTURES_ONLY | MoveGenFlags::EVASIONS_ONLY,
+    );
+    assert_eq!(evasions, [PieceMove::new(B2, A1, true, None)]);
   }
 
-  (moves, count)
+  #[test]
+  fn test_generate_legal_moves_with() {
+    let board =
+      GameData::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+        .unwrap()
+        .board;
+    let (legal, count) = generate_legal_moves(&board);
+    let (captures, capture_count) = generate_legal_moves_with(&board, MoveGenFlags::CAPTURES_ONLY);
+    let (quiets, quiet_count) = generate_legal_moves_with(&board, MoveGenFlags::QUIETS_ONLY);
+    assert_eq!(capture_count + quiet_count, count);
+    assert!(
+      captures[..capture_count]
+        .iter()
+        .all(|m| m.is_capture() || m.is_promotion())
+    );
+    assert!(
+      quiets[..quiet_count]
+        .iter()
+        .all(|m| legal[..count].contains(m))
+    );
+    assert_eq!(
+      &generate_legal_moves_with(&board, MoveGenFlags::EVASIONS_ONLY).0[..count],
+      &legal[..count]
+    );
+    assert!(MoveGenFlags::EVASIONS_ONLY.contains(MoveGenFlags::ALL));
+    assert!(
+      (MoveGenFlags::CAPTURES_ONLY | MoveGenFlags::EVASIONS_ONLY)
+        .contains(MoveGenFlags::CAPTURES_ONLY)
+    );
+    assert_eq!(MoveGenFlags::default(), MoveGenFlags::ALL);
+
+    // The lazy iterator agrees with filtering every legal move afterwards
+    for flags in [MoveGenFlags::CAPTURES_ONLY, MoveGenFlags::QUIETS_ONLY] {
+      let expected = legal[..count]
+        .iter()
+        .copied()
+        .filter(|m| flags.allows_kind(m));
+      assert!(
+        LegalMoves::with_flags(&board, flags).eq(expected),
+        "{flags:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_promoted_pieces() {
+    for (fen, pseudo_legal, legal) in [
+      // Three rooks; black is in check, so two more pseudo-legal moves take the king
+      ("R6k/8/8/3R4/8/8/8/K6R w - - 0 1", 43, 41),
+      // The known positions with 218 legal moves, the first also mirrored for black
+      (
+        "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1",
+        218,
+        218,
+      ),
+      (
+        "Kbnn1kb1/PP1q4/q4q2/2q4q/4q3/1q4q1/3q4/r6r b - - 0 1",
+        218,
+        218,
+      ),
+      (
+        "3Q4/1Q4Q1/4Q3/2Q4R/Q4Q2/3Q4/1Q4Rp/1K1BBNNk w - - 0 1",
+        218,
+        218,
+      ),
+    ] {
+      let board = GameData::from_fen(fen).unwrap().board;
+      assert_eq!(generate_moves(&board).1, pseudo_legal, "{fen}");
+      assert_eq!(generate_legal_moves(&board).1, legal, "{fen}");
+      assert_eq!(LegalMoves::new(&board).count(), legal, "{fen}");
+    }
+  }
+
+  #[test]
+  fn test_add_move_to_list_fills_buffer() {
+    let mut moves = [PieceMove::NULL; 2];
+    let mut count = 0;
+    add_move_to_list(&mut moves, &mut count, PieceMove::new(0, 1, false, None));
+    add_move_to_list(&mut moves, &mut count, PieceMove::new(0, 2, false, None));
+    assert_eq!(count, 2);
+    assert_eq!(moves[1], PieceMove::new(0, 2, false, None));
+  }
+
+  #[test]
+  #[cfg_attr(debug_assertions, should_panic(expected = "Move list overflow"))]
+  fn test_add_move_to_list_overflow() {
+    let mut moves = [PieceMove::NULL; 1];
+    let mut count = 0;
+    add_move_to_list(&mut moves, &mut count, PieceMove::new(0, 1, false, None));
+    add_move_to_list(&mut moves, &mut count, PieceMove::new(0, 2, false, None));
+    // Release builds saturate instead
+    assert_eq!(count, 1);
+    assert_eq!(moves[0], PieceMove::new(0, 1, false, None));
+  }
 }
@@ -18,287 +18,89 @@
 
 use crate::constants::*;
 use crate::model::{
-  gameboard::GameBoard,
+  gameboard::{GameBoard, PieceType},
   piecemove::{PieceMove, PromotionType},
 };
-use crate::movegen::add_move_to_list;
+use crate::movegen::{add_move_to_list, flags::Targets};
+use crate::stats::{self, Event};
 
-pub const MAX_PAWN_MOVES: usize = 56;
+/// Eight pawns on the seventh rank, each pushing and capturing both ways with four promotions.
+pub const MAX_PAWN_MOVES: usize = 8 * 3 * 4;
 
+/// Every pawn move, for the tests below.
+#[cfg(test)]
 pub(crate) fn generate_pawn_moves(state: &GameBoard) -> ([PieceMove; MAX_PAWN_MOVES], usize) {
+  generate_pawn_moves_with(state, Targets::ALL)
+}
+
+/// The pawn moves of the side to move that `targets` allows. Pushes onto the promotion rank
+/// count as captures.
+pub(crate) fn generate_pawn_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_PAWN_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::Pawn));
   let mut moves = [PieceMove::NULL; MAX_PAWN_MOVES];
   let mut count = 0;
 
-  let all_occupied =
-    state.pawns | state.knights | state.bishops | state.rooks | state.queens | state.kings;
-  let empty_squares = !all_occupied;
-
-  let single_pushes;
-  let double_pushes;
-  let right_captures;
-  let left_captures;
-
-  if state.playing {
-    let white_pawns = state.pawns & state.colour;
-    let opponent_pieces = all_occupied & !state.colour;
-
-    // --- White Pawn Moves ---
-
-    // 1. Single Push: Pawns move one step forward (up the board)
-    single_pushes = (white_pawns << 8) & empty_squares;
-
-    // 2. Double Push: Pawns on their base rank move two steps forward
-    //    - Must start on RANK_2.
-    //    - The square one step ahead must be empty (already checked by `single_pushes`).
-    //    - The square two steps ahead must also be empty.
-    let double_push_starts = single_pushes & RANK_3; // Pawns that successfully moved one step to rank 3
-    double_pushes = (double_push_starts << 8) & empty_squares;
-
-    // 3. Captures
-    right_captures = (white_pawns << 9) & opponent_pieces & !FILE_A; // Capture right, avoiding wrap-around
-    left_captures = (white_pawns << 7) & opponent_pieces & !FILE_H; // Capture left, avoiding wrap-around
-  } else {
-    // Black's turn
-    let black_pawns = state.pawns & !state.colour;
-    let opponent_pieces = all_occupied & state.colour;
-
-    // --- Black Pawn Moves ---
-
-    // 1. Single Push: Pawns move one step forward (down the board)
-    single_pushes = (black_pawns >> 8) & empty_squares;
-
-    // 2. Double Push: Pawns on their base rank move two steps forward
-    //    - Must start on RANK_7.
-    //    - The square one step ahead must be empty.
-    //    - The square two steps ahead must also be empty.
-    let double_push_starts = single_pushes & RANK_6; // Pawns that successfully moved one step to rank 6
-    double_pushes = (double_push_starts >> 8) & empty_squares;
-
-    // 3. Captures
-    right_captures = (black_pawns >> 7) & opponent_pieces & !FILE_A; // Capture right
-    left_captures = (black_pawns >> 9) & opponent_pieces & !FILE_H; // Capture left
-  }
-
-  // 1. Single Pushes
-  let mut tmp_single: u64 = single_pushes.into();
-  while tmp_single != 0 {
-    let to_sq_idx = tmp_single.trailing_zeros() as u8;
-    let to_sq_bb = 1u64 << to_sq_idx; // Bitboard for the 'to' square
-
-    let from_sq_idx = if state.playing {
-      to_sq_idx - 8 // White pawns move up
-    } else {
-      to_sq_idx + 8 // Black pawns move down
-    };
-
-    // Check for promotion
-    let is_promotion_rank =
-      (state.playing && (to_sq_bb & RANK_8) != 0) || (!state.playing && (to_sq_bb & RANK_1) != 0);
-
-    if is_promotion_rank {
-      // Generate 4 promotion moves (Queen, Rook, Bishop, Knight)
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, false, Some(PromotionType::Queen)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, false, Some(PromotionType::Rook)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, false, Some(PromotionType::Bishop)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, false, Some(PromotionType::Knight)),
-      );
-    } else {
-      // Normal single push
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, false, None),
-      );
-    }
-
-    tmp_single &= tmp_single - 1; // Clear the least significant bit
-  }
-
-  // 2. Double Pushes
-  let mut tmp_double: u64 = double_pushes.into();
-  while tmp_double != 0 {
-    let to_sq_idx = tmp_double.trailing_zeros() as u8;
-
-    // Determine the 'from' square based on the direction of the push
-    let from_sq_idx = if state.playing {
-      to_sq_idx - 16
-    } else {
-      to_sq_idx + 16
-    };
-
-    // Double pushes are not captures, and CANNOT be promotions.
-    // The `is_two_square_advance` information isn't directly in PieceMove's packed bits,
-    // but the (from, to) squares uniquely identify it for a pawn.
-    add_move_to_list(
+  let white = state.playing;
+  let pawns = (state.pawns & state.pieces_of(white)).raw();
+  let capture_mask = targets.capture_mask();
+  let quiet_mask = targets.quiet_mask();
+  let opponent_pieces = state.pieces_of(!white).raw() & capture_mask;
+  let empty_squares = !state.combined().raw();
+  let promotion_rank = promotion_rank(white);
+
+  // The step of a push, and of captures towards the h- and a-files, in square indices
+  let (push, east, west) = if white { (8, 9, 7) } else { (-8, -7, -9) };
+
+  // 1. Single Push: Pawns move one step forward
+  let single_pushes = shift(pawns, push) & empty_squares;
+  // 2. Double Push: Pawns that pushed one step off their start rank may push one more, onto an
+  //    empty square
+  let double_pushes =
+    shift(single_pushes & shift(double_push_rank(white), push), push) & empty_squares & quiet_mask;
+  let single_pushes =
+    single_pushes & ((quiet_mask & !promotion_rank) | (capture_mask & promotion_rank));
+  // 3. Captures, avoiding wrap-around between the a- and h-files
+  let east_captures = shift(pawns, east) & opponent_pieces & NOT_FILE_A;
+  let west_captures = shift(pawns, west) & opponent_pieces & NOT_FILE_H;
+
+  let mut add = |targets, offset, capture| {
+    add_pawn_moves(
       &mut moves,
       &mut count,
-      MAX_PAWN_MOVES,
-      PieceMove::new(from_sq_idx, to_sq_idx, false, None),
+      targets,
+      offset,
+      capture,
+      promotion_rank,
     );
-
-    tmp_double &= tmp_double - 1; // Clear the least significant bit
-  }
-
-  // 3. Right Captures
-  let mut tmp_right: u64 = right_captures.into();
-  while tmp_right != 0 {
-    let to_sq_idx = tmp_right.trailing_zeros() as u8;
-    let to_sq_bb = 1u64 << to_sq_idx;
-
-    // Determine the 'from' square based on the direction of the capture
-    let from_sq_idx = if state.playing {
-      to_sq_idx - 9
-    } else {
-      to_sq_idx + 7
-    };
-
-    // Check for promotion (capturing promotion)
-    let is_promotion_rank =
-      (state.playing && (to_sq_bb & RANK_8) != 0) || (!state.playing && (to_sq_bb & RANK_1) != 0);
-
-    if is_promotion_rank {
-      // Generate 4 capturing promotion moves
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, Some(PromotionType::Queen)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, Some(PromotionType::Rook)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, Some(PromotionType::Bishop)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, Some(PromotionType::Knight)),
-      );
-    } else {
-      // Normal capture
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, None),
-      );
-    }
-
-    // Clear the least significant bit
-    tmp_right &= tmp_right - 1;
-  }
-
-  // 4. Left Captures
-  let mut tmp_left: u64 = left_captures.into();
-  while tmp_left != 0 {
-    let to_sq_idx = tmp_left.trailing_zeros() as u8;
-    let to_sq_bb = 1u64 << to_sq_idx;
-
-    // Determine the 'from' square based on the direction of the capture
-    let from_sq_idx = if state.playing {
-      to_sq_idx - 7
-    } else {
-      to_sq_idx + 9
-    };
-
-    // Check for promotion (capturing promotion)
-    let is_promotion_rank =
-      (state.playing && (to_sq_bb & RANK_8) != 0) || (!state.playing && (to_sq_bb & RANK_1) != 0);
-
-    if is_promotion_rank {
-      // Generate 4 capturing promotion moves
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, Some(PromotionType::Queen)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, Some(PromotionType::Rook)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, Some(PromotionType::Bishop)),
-      );
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, Some(PromotionType::Knight)),
-      );
-    } else {
-      // Normal capture
-      add_move_to_list(
-        &mut moves,
-        &mut count,
-        MAX_PAWN_MOVES,
-        PieceMove::new(from_sq_idx, to_sq_idx, true, None),
-      );
-    }
-
-    // Clear the least significant bit
-    tmp_left &= tmp_left - 1;
-  }
-
-  // 5. En Passant captures
-  if state.en_passant != PieceMove::NULL {
-    let ep_target_sq = state.en_passant.to_square();
+  };
+  add(single_pushes, push, false);
+  // Double pushes can never promote
+  add(double_pushes, 2 * push, false);
+  add(east_captures, east, true);
+  add(west_captures, west, true);
+
+  // 4. En passant captures: the capturing pawns stand one capture step behind the target. The
+  //    captured pawn is not on the target, and taking it may be what answers a check
+  if let Some(en_passant) = state.en_passant {
+    let ep_target_sq = en_passant.to_square();
     let ep_target_bb = 1u64 << ep_target_sq;
+    let captured_bb = shift(ep_target_bb, -push);
+    let pawn_attacks =
+      (shift(ep_target_bb, -east) & NOT_FILE_H) | (shift(ep_target_bb, -west) & NOT_FILE_A);
 
-    let pawn_attacks = if state.playing {
-      // White attacks black pawns
-      ((ep_target_bb >> 7) & !FILE_A) | ((ep_target_bb >> 9) & !FILE_H)
-    } else {
-      // Black attacks white pawns
-      ((ep_target_bb << 7) & !FILE_H) | ((ep_target_bb << 9) & !FILE_A)
-    };
-
-    let friendly_pawns: u64 = if state.playing {
-      (state.pawns & state.colour).into()
+    let mut attackers = if (ep_target_bb | captured_bb) & capture_mask != 0 {
+      pawn_attacks & pawns
     } else {
-      (state.pawns & !state.colour).into()
+      0
     };
-
-    let mut attackers = pawn_attacks & friendly_pawns;
     while attackers != 0 {
       let from_sq = attackers.trailing_zeros() as u8;
       add_move_to_list(
         &mut moves,
         &mut count,
-        MAX_PAWN_MOVES,
         PieceMove::new_en_passant(from_sq, ep_target_sq),
       );
       attackers &= attackers - 1;
@@ -308,6 +110,52 @@ pub(crate) fn generate_pawn_moves(state: &GameBoard) -> ([PieceMove; MAX_PAWN_MO
   (moves, count)
 }
 
+/// `bb` moved `offset` squares up the board, or down for a negative offset.
+#[inline(always)]
+fn shift(bb: u64, offset: i8) -> u64 {
+  if offset > 0 {
+    bb << offset
+  } else {
+    bb >> -offset
+  }
+}
+
+/// Add a move to every square of `targets` from the square `offset` behind it, as all four
+/// promotions on `promotion_rank`.
+#[inline(always)]
+fn add_pawn_moves(
+  moves: &mut [PieceMove],
+  count: &mut usize,
+  mut targets: u64,
+  offset: i8,
+  capture: bool,
+  promotion_rank: u64,
+) {
+  while targets != 0 {
+    let to = targets.trailing_zeros() as u8;
+    let from = (to as i8 - offset) as u8;
+
+    if (1u64 << to) & promotion_rank != 0 {
+      for promotion in [
+        PromotionType::Queen,
+        PromotionType::Rook,
+        PromotionType::Bishop,
+        PromotionType::Knight,
+      ] {
+        add_move_to_list(
+          moves,
+          count,
+          PieceMove::new(from, to, capture, Some(promotion)),
+        );
+      }
+    } else {
+      add_move_to_list(moves, count, PieceMove::new(from, to, capture, None));
+    }
+
+    targets &= targets - 1; // Clear the least significant bit
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
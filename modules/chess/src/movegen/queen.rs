@@ -16,31 +16,61 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
+#[cfg(not(feature = "magics"))]
 use crate::{
   constants::{FILE_A, FILE_H},
-  model::{bitboard::BitBoard, gameboard::GameBoard, piecemove::PieceMove},
+  movegen::add_move_to_list,
+};
+use crate::{
+  model::{
+    gameboard::{GameBoard, PieceType},
+    piecemove::PieceMove,
+  },
+  movegen::flags::Targets,
+  stats::{self, Event},
 };
 
-pub const MAX_QUEEN_MOVES: usize = 56; // 28 (rook-like) + 28 (bishop-like) = 56 max
+/// Nine queens, the one of the starting position and eight promoted pawns, with at most 27 moves
+/// each.
+pub const MAX_QUEEN_MOVES: usize = 9 * 27;
 
+/// Every queen move, for the tests below.
+#[cfg(test)]
 pub(crate) fn generate_queen_moves(state: &GameBoard) -> ([PieceMove; MAX_QUEEN_MOVES], usize) {
+  generate_queen_moves_with(state, Targets::ALL)
+}
+
+/// The queen moves of the side to move that `targets` allows.
+#[cfg(feature = "magics")]
+pub(crate) fn generate_queen_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_QUEEN_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::Queen));
+  crate::movegen::magics::generate_slider_moves(
+    state,
+    state.queens.raw(),
+    crate::movegen::magics::queen_attacks,
+    targets,
+  )
+}
+
+/// The queen moves of the side to move that `targets` allows.
+#[cfg(not(feature = "magics"))]
+pub(crate) fn generate_queen_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_QUEEN_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::Queen));
   let mut moves = [PieceMove::NULL; MAX_QUEEN_MOVES];
   let mut count = 0;
 
   let all_occupied =
     state.pawns | state.knights | state.bishops | state.rooks | state.queens | state.kings;
 
-  let (my_queens, other_pieces): (BitBoard, u64) = if state.playing {
-    (
-      state.queens & state.colour,
-      (all_occupied & !state.colour).into(),
-    )
-  } else {
-    (
-      state.queens & !state.colour,
-      (all_occupied & state.colour).into(),
-    )
-  };
+  let my_queens = state.queens & state.pieces_of(state.playing);
+  let other_pieces = state.pieces_of(!state.playing).raw() & targets.capture_mask();
+  let quiet_mask = targets.quiet_mask();
 
   // Queen moves are combination of rook and bishop moves
   // Using a unified approach with direction data: (shift_amount, mask, is_positive_shift)
@@ -81,10 +111,11 @@ pub(crate) fn generate_queen_moves(state: &GameBoard) -> ([PieceMove; MAX_QUEEN_
           to_board + (i * ((-shift) as u8))
         };
 
-        if count < MAX_QUEEN_MOVES {
-          moves[count] = PieceMove::new(from_board, to_board, true, None);
-          count += 1;
-        }
+        add_move_to_list(
+          &mut moves,
+          &mut count,
+          PieceMove::new(from_board, to_board, true, None),
+        );
 
         // Remove this processed capture
         captures &= captures - 1;
@@ -95,7 +126,7 @@ pub(crate) fn generate_queen_moves(state: &GameBoard) -> ([PieceMove; MAX_QUEEN_
       ray_attackers &= !blockers;
 
       // Process quiet moves
-      let mut quiet_moves = ray_attackers;
+      let mut quiet_moves = ray_attackers & quiet_mask;
       while quiet_moves != 0 {
         let to_board = quiet_moves.trailing_zeros() as u8;
         let from_board = if is_positive {
@@ -104,10 +135,11 @@ pub(crate) fn generate_queen_moves(state: &GameBoard) -> ([PieceMove; MAX_QUEEN_
           to_board + (i * ((-shift) as u8))
         };
 
-        if count < MAX_QUEEN_MOVES {
-          moves[count] = PieceMove::new(from_board, to_board, false, None);
-          count += 1;
-        }
+        add_move_to_list(
+          &mut moves,
+          &mut count,
+          PieceMove::new(from_board, to_board, false, None),
+        );
 
         // Remove this processed move
         quiet_moves &= quiet_moves - 1;
@@ -16,31 +16,60 @@
  * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
  */
 
+#[cfg(not(feature = "magics"))]
 use crate::{
-  constants::{FILE_A, FILE_H}, // Added FILE_A for wrap-around protection
-  model::{bitboard::BitBoard, gameboard::GameBoard, piecemove::PieceMove},
+  constants::{NOT_FILE_A, NOT_FILE_H},
+  movegen::add_move_to_list,
+};
+use crate::{
+  model::{
+    gameboard::{GameBoard, PieceType},
+    piecemove::PieceMove,
+  },
+  movegen::flags::Targets,
+  stats::{self, Event},
 };
 
-pub const MAX_ROOK_MOVES: usize = 28;
+/// Ten rooks, the two of the starting position and eight promoted pawns, with 14 moves each.
+pub const MAX_ROOK_MOVES: usize = 10 * 14;
 
+/// Every rook move, for the tests below.
+#[cfg(test)]
 pub(crate) fn generate_rook_moves(state: &GameBoard) -> ([PieceMove; MAX_ROOK_MOVES], usize) {
+  generate_rook_moves_with(state, Targets::ALL)
+}
+
+/// The rook moves of the side to move that `targets` allows.
+#[cfg(feature = "magics")]
+pub(crate) fn generate_rook_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_ROOK_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::Rook));
+  crate::movegen::magics::generate_slider_moves(
+    state,
+    state.rooks.raw(),
+    crate::movegen::magics::rook_attacks,
+    targets,
+  )
+}
+
+/// The rook moves of the side to move that `targets` allows.
+#[cfg(not(feature = "magics"))]
+pub(crate) fn generate_rook_moves_with(
+  state: &GameBoard,
+  targets: Targets,
+) -> ([PieceMove; MAX_ROOK_MOVES], usize) {
+  stats::record(Event::Movegen(PieceType::Rook));
   let mut moves = [PieceMove::NULL; MAX_ROOK_MOVES];
   let mut count = 0;
 
   let all_occupied =
     state.pawns | state.knights | state.bishops | state.rooks | state.queens | state.kings;
 
-  let (my_rooks, other_pieces): (BitBoard, u64) = if state.playing {
-    (
-      state.rooks & state.colour,
-      (all_occupied & !state.colour).into(),
-    )
-  } else {
-    (
-      state.rooks & !state.colour,
-      (all_occupied & state.colour).into(),
-    )
-  };
+  let my_rooks = state.rooks & state.pieces_of(state.playing);
+  let other_pieces = state.pieces_of(!state.playing).raw() & targets.capture_mask();
+  let quiet_mask = targets.quiet_mask();
 
   // Ray-casting for all 4 directions
 
@@ -56,8 +85,11 @@ pub(crate) fn generate_rook_moves(state: &GameBoard) -> ([PieceMove; MAX_ROOK_MO
       let to_board = captures.trailing_zeros() as u8;
       let from_board = to_board - (i * 8);
 
-      moves[count] = PieceMove::new(from_board, to_board, true, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, true, None),
+      );
 
       // Remove this processed capture from the captures bitboard.
       captures &= captures - 1;
@@ -68,13 +100,16 @@ pub(crate) fn generate_rook_moves(state: &GameBoard) -> ([PieceMove; MAX_ROOK_MO
     ray_attackers &= !blockers;
 
     // Process quiet moves (those that didn't land on a blocker).
-    let mut quiet_moves = ray_attackers;
+    let mut quiet_moves = ray_attackers & quiet_mask;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = to_board - (i * 8);
 
-      moves[count] = PieceMove::new(from_board, to_board, false, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, false, None),
+      );
 
       // Remove this processed move.
       quiet_moves &= quiet_moves - 1;
@@ -91,26 +126,32 @@ pub(crate) fn generate_rook_moves(state: &GameBoard) -> ([PieceMove; MAX_ROOK_MO
     // We move the rooks right.
     ray_attackers <<= 1;
     // Remove all who warp around to file A.
-    ray_attackers &= !FILE_A;
+    ray_attackers &= NOT_FILE_A;
 
     let mut captures = ray_attackers & other_pieces;
     while captures != 0 {
       let to_board = captures.trailing_zeros() as u8;
       let from_board = to_board - i;
-      moves[count] = PieceMove::new(from_board, to_board, true, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, true, None),
+      );
       captures &= captures - 1;
     }
 
     let blockers = ray_attackers & all_occupied.raw();
     ray_attackers &= !blockers;
 
-    let mut quiet_moves = ray_attackers;
+    let mut quiet_moves = ray_attackers & quiet_mask;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = to_board - i;
-      moves[count] = PieceMove::new(from_board, to_board, false, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, false, None),
+      );
       quiet_moves &= quiet_moves - 1;
     }
 
@@ -129,20 +170,26 @@ pub(crate) fn generate_rook_moves(state: &GameBoard) -> ([PieceMove; MAX_ROOK_MO
     while captures != 0 {
       let to_board = captures.trailing_zeros() as u8;
       let from_board = to_board + (i * 8);
-      moves[count] = PieceMove::new(from_board, to_board, true, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, true, None),
+      );
       captures &= captures - 1;
     }
 
     let blockers = ray_attackers & all_occupied.raw();
     ray_attackers &= !blockers;
 
-    let mut quiet_moves = ray_attackers;
+    let mut quiet_moves = ray_attackers & quiet_mask;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = to_board + (i * 8);
-      moves[count] = PieceMove::new(from_board, to_board, false, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, false, None),
+      );
       quiet_moves &= quiet_moves - 1;
     }
 
@@ -156,26 +203,32 @@ pub(crate) fn generate_rook_moves(state: &GameBoard) -> ([PieceMove; MAX_ROOK_MO
   for i in 1..8 {
     // We move the rooks left and remove all who warp around to file H.
     ray_attackers >>= 1;
-    ray_attackers &= !FILE_H;
+    ray_attackers &= NOT_FILE_H;
 
     let mut captures = ray_attackers & other_pieces;
     while captures != 0 {
       let to_board = captures.trailing_zeros() as u8;
       let from_board = to_board + i;
-      moves[count] = PieceMove::new(from_board, to_board, true, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, true, None),
+      );
       captures &= captures - 1;
     }
 
     let blockers = ray_attackers & all_occupied.raw();
     ray_attackers &= !blockers;
 
-    let mut quiet_moves = ray_attackers;
+    let mut quiet_moves = ray_attackers & quiet_mask;
     while quiet_moves != 0 {
       let to_board = quiet_moves.trailing_zeros() as u8;
       let from_board = to_board + i;
-      moves[count] = PieceMove::new(from_board, to_board, false, None);
-      count += 1;
+      add_move_to_list(
+        &mut moves,
+        &mut count,
+        PieceMove::new(from_board, to_board, false, None),
+      );
       quiet_moves &= quiet_moves - 1;
     }
 
@@ -489,7 +542,7 @@ mod tests {
     expected_moves.append(&mut d4_moves);
     expected_moves.append(&mut a1_moves);
 
-    assert!(expected_moves.len() == MAX_ROOK_MOVES);
+    assert_eq!(expected_moves.len(), 28);
     assert_eq!(
       sort_and_compare_moves(generated_moves),
       sort_and_compare_moves(expected_moves)
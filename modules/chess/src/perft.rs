@@ -0,0 +1,603 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Perft: counting the leaf nodes of the legal move tree.
+//!
+//! [`perft`] only counts nodes. [`perft_divide`] splits the count by root move, the way engines
+//! print it after `go perft`, so two generators that disagree can be compared move by move down
+//! to the position where they differ. [`perft_detailed`] also breaks the leaf moves down into the
+//! categories of the published perft tables (captures, en passant, castles, promotions, checks,
+//! discovered and double checks, checkmates), which narrows a node count mismatch down to the
+//! kind of move the generator gets wrong.
+//!
+//! [`perft_cached`] counts the same nodes faster by caching subtrees in a table the caller
+//! provides.
+//!
+//! [`perft_rules`] walks a [`GameData`] instead of a bare board and stops wherever the game is
+//! over, so forced draws by repetition, the 75-move rule and insufficient material cut the tree
+//! short. It checks [`GameData::status`] against enumerations that play the game to its end.
+//!
+//! All recurse once per ply. With the `iterative` feature, [`perft_iterative`] walks the tree
+//! without recursion instead, keeping each ply's moves in a [`PerftStack`] that the caller owns
+//! and can place in a `static`, so a microcontroller with a small call stack can run perft as
+//! deep as the stack was sized for.
+
+use core::ops::AddAssign;
+
+#[cfg(feature = "iterative")]
+use crate::movegen::MAX_MOVES;
+use crate::{
+  legal::attack::attacks_from,
+  model::{
+    castling::castling_destinations,
+    gameboard::{GameBoard, PieceType},
+    gamedata::GameData,
+    piecemove::PieceMove,
+    status::GameStatus,
+  },
+  movegen::generate_moves,
+};
+
+/// Leaf counts of a perft run, split by the kind of move that reached each leaf.
+///
+/// A move can fall into several categories; a capture that promotes and gives check counts as a
+/// capture, a promotion and a check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PerftStats {
+  pub nodes: u64,
+  /// Captures, including en passant.
+  pub captures: u64,
+  pub en_passants: u64,
+  pub castles: u64,
+  pub promotions: u64,
+  pub checks: u64,
+  /// Checks given by a piece other than the one that moved.
+  pub discovery_checks: u64,
+  /// Checks given by two pieces at once.
+  pub double_checks: u64,
+  pub checkmates: u64,
+}
+
+impl AddAssign for PerftStats {
+  fn add_assign(&mut self, other: Self) {
+    self.nodes += other.nodes;
+    self.captures += other.captures;
+    self.en_passants += other.en_passants;
+    self.castles += other.castles;
+    self.promotions += other.promotions;
+    self.checks += other.checks;
+    self.discovery_checks += other.discovery_checks;
+    self.double_checks += other.double_checks;
+    self.checkmates += other.checkmates;
+  }
+}
+
+/// Number of leaf nodes `depth` plies below `board`.
+pub fn perft(board: &GameBoard, depth: u32) -> u64 {
+  if depth == 0 {
+    return 1;
+  }
+
+  let (moves, count) = generate_moves(board);
+  let mut nodes = 0;
+  for piece_move in &moves[..count] {
+    let mut child = *board;
+    if child.move_piece(piece_move).is_some() {
+      nodes += if depth == 1 {
+        1
+      } else {
+        perft(&child, depth - 1)
+      };
+    }
+  }
+  nodes
+}
+
+/// The node count [`perft`] gives below each legal root move of `board`, `depth - 1` plies
+/// further down, in generator order. The counts add up to `perft(board, depth)`; at depth 0 there
+/// is nothing to divide and no move is yielded.
+///
+/// ```
+/// use lumifox_chess::{GameBoard, perft::perft_divide};
+///
+/// for (piece_move, nodes) in perft_divide(&GameBoard::START_POS, 2) {
+///   assert_eq!(nodes, 20, "{piece_move}");
+/// }
+/// ```
+pub fn perft_divide(board: &GameBoard, depth: u32) -> impl Iterator<Item = (PieceMove, u64)> + '_ {
+  board
+    .legal_moves_iter()
+    .filter(move |_| depth > 0)
+    .map(move |piece_move| {
+      let mut child = *board;
+      child.apply_move_unchecked(&piece_move);
+      child.playing = !child.playing;
+      (piece_move, perft(&child, depth - 1))
+    })
+}
+
+/// Like [`perft`], remembering the node counts of subtrees in `cache` so transpositions are
+/// counted once. The cache is any slice the caller owns, so its size is up to them: two words an
+/// entry, a Zobrist key mixed with the depth and the count, replaced whenever another subtree
+/// lands on the same slot. An empty slice caches nothing.
+pub fn perft_cached(board: &GameBoard, depth: u32, cache: &mut [u64]) -> u64 {
+  let entries = cache.len() / 2;
+  if depth <= 1 || entries == 0 {
+    return perft(board, depth);
+  }
+
+  let key = board.zobrist_key() ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+  let slot = (key % entries as u64) as usize * 2;
+  if cache[slot] == key {
+    return cache[slot + 1];
+  }
+
+  let (moves, count) = generate_moves(board);
+  let mut nodes = 0;
+  for piece_move in &moves[..count] {
+    let mut child = *board;
+    if child.move_piece(piece_move).is_some() {
+      nodes += perft_cached(&child, depth - 1, cache);
+    }
+  }
+  cache[slot] = key;
+  cache[slot + 1] = nodes;
+  nodes
+}
+
+/// Like [`perft`], with the leaf moves broken down by category.
+pub fn perft_detailed(board: &GameBoard, depth: u32) -> PerftStats {
+  let mut stats = PerftStats::default();
+  if depth == 0 {
+    stats.nodes = 1;
+    return stats;
+  }
+
+  let (moves, count) = generate_moves(board);
+  for piece_move in &moves[..count] {
+    let mut child = *board;
+    if child.move_piece(piece_move).is_none() {
+      continue;
+    }
+    if depth == 1 {
+      stats += leaf_stats(board, piece_move, &child);
+    } else {
+      stats += perft_detailed(&child, depth - 1);
+    }
+  }
+  stats
+}
+
+/// Counts of a [`perft_rules`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RulesPerftStats {
+  /// Positions reached after exactly `depth` plies, leaving out the lines that passed through an
+  /// ended game.
+  pub nodes: u64,
+  /// Games ended by checkmate within `depth` plies, the root included.
+  pub checkmates: u64,
+  /// Games won by the variant's own rule within `depth` plies.
+  pub variant_wins: u64,
+  /// Games ended by a forced draw within `depth` plies: stalemate, insufficient material, the
+  /// 75-move rule or fivefold repetition.
+  pub forced_draws: u64,
+  /// Positions after exactly `depth` plies where a draw could be claimed.
+  pub claimable_draws: u64,
+}
+
+impl AddAssign for RulesPerftStats {
+  fn add_assign(&mut self, other: Self) {
+    self.nodes += other.nodes;
+    self.checkmates += other.checkmates;
+    self.variant_wins += other.variant_wins;
+    self.forced_draws += other.forced_draws;
+    self.claimable_draws += other.claimable_draws;
+  }
+}
+
+/// Like [`perft`], following the rules of the whole game rather than of single moves: a game that
+/// is over, by [`GameData::status`], is a leaf whatever the depth left. The repetition rules look
+/// at the history of `game` as well as the moves played during the count.
+///
+/// Every node replays its history to count repetitions, so this is meant for small positions and
+/// shallow depths.
+pub fn perft_rules(game: &GameData, depth: u32) -> RulesPerftStats {
+  let mut stats = RulesPerftStats::default();
+  let status = game.status();
+  match status {
+    GameStatus::Checkmate { .. } => stats.checkmates = 1,
+    GameStatus::VariantWin { .. } => stats.variant_wins = 1,
+    GameStatus::ForcedDraw(_) => stats.forced_draws = 1,
+    GameStatus::ClaimableDraw(_) if depth == 0 => stats.claimable_draws = 1,
+    GameStatus::ClaimableDraw(_) | GameStatus::Ongoing => {}
+  }
+  if depth == 0 {
+    stats.nodes = 1;
+    return stats;
+  }
+  if status.is_over() {
+    return stats;
+  }
+
+  let (moves, count) = generate_moves(&game.board);
+  for piece_move in &moves[..count] {
+    let mut child = *game;
+    if child.play_move(piece_move).is_some() {
+      stats += perft_rules(&child, depth - 1);
+    }
+  }
+  stats
+}
+
+/// One ply of a [`PerftStack`]: a position, its moves and how many have been tried.
+#[cfg(feature = "iterative")]
+#[derive(Clone, Copy)]
+struct Frame {
+  board: GameBoard,
+  moves: [PieceMove; MAX_MOVES],
+  count: usize,
+  next: usize,
+}
+
+#[cfg(feature = "iterative")]
+impl Frame {
+  const EMPTY: Frame = Frame {
+    board: GameBoard::START_POS,
+    moves: [PieceMove::NULL; MAX_MOVES],
+    count: 0,
+    next: 0,
+  };
+
+  fn load(&mut self, board: GameBoard) {
+    (self.moves, self.count) = generate_moves(&board);
+    self.board = board;
+    self.next = 0;
+  }
+}
+
+/// Storage for [`perft_iterative`], one frame of about 930 bytes per ply, for trees up to
+/// `MAX_PLY` plies deep. `new` is `const`, so the stack can be a `static` rather than live on
+/// the call stack.
+#[cfg(feature = "iterative")]
+pub struct PerftStack<const MAX_PLY: usize> {
+  frames: [Frame; MAX_PLY],
+}
+
+#[cfg(feature = "iterative")]
+impl<const MAX_PLY: usize> PerftStack<MAX_PLY> {
+  pub const fn new() -> Self {
+    Self {
+      frames: [Frame::EMPTY; MAX_PLY],
+    }
+  }
+}
+
+#[cfg(feature = "iterative")]
+impl<const MAX_PLY: usize> Default for PerftStack<MAX_PLY> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// [`perft`] without recursion: the same count, using `stack` for the moves of each ply. Returns
+/// `None` if `depth` is more than the `MAX_PLY` the stack holds.
+#[cfg(feature = "iterative")]
+pub fn perft_iterative<const MAX_PLY: usize>(
+  board: &GameBoard,
+  depth: u32,
+  stack: &mut PerftStack<MAX_PLY>,
+) -> Option<u64> {
+  let depth = depth as usize;
+  if depth == 0 {
+    return Some(1);
+  }
+  if depth > MAX_PLY {
+    return None;
+  }
+
+  stack.frames[0].load(*board);
+  let mut ply = 0;
+  let mut nodes = 0;
+  loop {
+    let frame = &mut stack.frames[ply];
+    if frame.next == frame.count {
+      if ply == 0 {
+        return Some(nodes);
+      }
+      ply -= 1;
+      continue;
+    }
+    let piece_move = frame.moves[frame.next];
+    frame.next += 1;
+    let mut child = frame.board;
+    if child.move_piece(&piece_move).is_none() {
+      continue;
+    }
+    if ply + 1 == depth {
+      nodes += 1;
+    } else {
+      ply += 1;
+      stack.frames[ply].load(child);
+    }
+  }
+}
+
+/// Categorise `piece_move`, played from `board` to reach `child`.
+fn leaf_stats(board: &GameBoard, piece_move: &PieceMove, child: &GameBoard) -> PerftStats {
+  let from = piece_move.from_square();
+  let to = piece_move.to_square();
+  let piece = board.get_piece(from);
+  let castling = piece == Some(PieceType::King) && board.castling_rook(piece_move).is_some();
+  let en_passant =
+    piece == Some(PieceType::Pawn) && from % 8 != to % 8 && board.get_piece(to).is_none();
+  let capture = !castling && (en_passant || board.get_piece(to).is_some());
+
+  let mut stats = PerftStats {
+    nodes: 1,
+    captures: capture as u64,
+    en_passants: en_passant as u64,
+    castles: castling as u64,
+    promotions: piece_move.is_promotion() as u64,
+    ..Default::default()
+  };
+
+  let checkers = checkers(child);
+  if checkers == 0 {
+    return stats;
+  }
+  stats.checks = 1;
+  stats.double_checks = (checkers.count_ones() > 1) as u64;
+
+  // When castling the rook is the piece that moves into position to give check
+  let moved = match board.castling_rook(piece_move) {
+    Some(rook) if castling => 1u64 << castling_destinations(board.playing, rook > from).1,
+    _ => 1u64 << to,
+  };
+  stats.discovery_checks = (checkers & !moved != 0) as u64;
+
+  stats.checkmates = !child.has_legal_move() as u64;
+  stats
+}
+
+/// Pieces giving check to the side to move in `board`.
+fn checkers(board: &GameBoard) -> u64 {
+  let own = board.pieces_of(board.playing).raw();
+  let king = board.kings.raw() & own;
+  if king == 0 {
+    return 0;
+  }
+
+  let mut checkers = 0;
+  let mut enemies = board.combined().raw() & !own;
+  while enemies != 0 {
+    let square = enemies.trailing_zeros() as u8;
+    enemies &= enemies - 1;
+    if attacks_from(board, square) & king != 0 {
+      checkers |= 1 << square;
+    }
+  }
+  checkers
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constants::*;
+
+  const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+  const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn stats(
+    nodes: u64,
+    captures: u64,
+    en_passants: u64,
+    castles: u64,
+    promotions: u64,
+    checks: u64,
+    discovery_checks: u64,
+    double_checks: u64,
+    checkmates: u64,
+  ) -> PerftStats {
+    PerftStats {
+      nodes,
+      captures,
+      en_passants,
+      castles,
+      promotions,
+      checks,
+      discovery_checks,
+      double_checks,
+      checkmates,
+    }
+  }
+
+  #[test]
+  fn test_perft_rules() {
+    // Without ended games on the way, the nodes are plain perft's
+    let start = perft_rules(&GameData::START_POS, 3);
+    assert_eq!(start.nodes, perft(&GameBoard::START_POS, 3));
+    assert_eq!(start.forced_draws + start.checkmates, 0);
+
+    // Kxb2 leaves bare kings at once, and after Ka2 a knight or bishop promotion does too
+    let bare = GameData::from_fen("8/8/8/8/8/8/1p6/K6k w - - 0 1").unwrap();
+    let stats = perft_rules(&bare, 2);
+    assert_eq!(stats.forced_draws, 3);
+    assert_eq!(stats.nodes, 10);
+    assert!(stats.nodes < perft(&bare.board, 2));
+
+    // One move from the 75-move rule, every quiet move ends the game
+    let clock = GameData::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 120").unwrap();
+    let stats = perft_rules(&clock, 1);
+    assert_eq!(stats.forced_draws, stats.nodes);
+    assert_eq!(stats.nodes, perft(&clock.board, 1));
+  }
+
+  #[test]
+  fn test_perft_rules_repetition() {
+    // The position has occurred four times; a king step out and back by each side is the fifth
+    let fen = "4k3/4p3/4P3/8/8/8/8/4K3 w - - 0 1";
+    let mut game = GameData::from_fen(fen).unwrap();
+    for _ in 0..3 {
+      for (from, to) in [(E1, D1), (E8, D8), (D1, E1), (D8, E8)] {
+        game
+          .play_move(&PieceMove::new(from, to, false, None))
+          .unwrap();
+      }
+    }
+    let stats = perft_rules(&game, 4);
+    assert_eq!(stats.nodes, perft(&board_from_fen(fen), 4));
+    // Five squares for the white king and two for the black one, each with one way back
+    assert_eq!(stats.forced_draws, 10);
+  }
+
+  #[test]
+  fn test_perft_startpos() {
+    let board = GameBoard::START_POS;
+    assert_eq!(perft(&board, 0), 1);
+    assert_eq!(perft(&board, 1), 20);
+    assert_eq!(perft(&board, 2), 400);
+    assert_eq!(perft(&board, 3), 8_902);
+  }
+
+  #[test]
+  fn test_perft_positions_4_5_6() {
+    for (fen, counts) in [
+      (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        [6, 264, 9_467],
+      ),
+      (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        [44, 1_486, 62_379],
+      ),
+      (
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        [46, 2_079, 89_890],
+      ),
+    ] {
+      let board = board_from_fen(fen);
+      for (depth, nodes) in (1..).zip(counts) {
+        assert_eq!(perft(&board, depth), nodes, "{fen} at depth {depth}");
+      }
+    }
+  }
+
+  #[test]
+  fn test_perft_divide() {
+    let board = GameBoard::START_POS;
+    let divide: Vec<_> = perft_divide(&board, 3).collect();
+    assert_eq!(divide.len(), 20);
+    assert_eq!(divide.iter().map(|&(_, nodes)| nodes).sum::<u64>(), 8_902);
+    for (uci, nodes) in [("e2e4", 600), ("d2d3", 539), ("b2b4", 421), ("g1f3", 440)] {
+      let piece_move = PieceMove::from_uci(uci, &board).unwrap();
+      assert!(divide.contains(&(piece_move, nodes)), "{uci}");
+    }
+
+    let kiwipete = board_from_fen(KIWIPETE);
+    let nodes: u64 = perft_divide(&kiwipete, 3).map(|(_, nodes)| nodes).sum();
+    assert_eq!(nodes, 97_862);
+    assert_eq!(perft_divide(&kiwipete, 1).count(), 48);
+    assert!(perft_divide(&kiwipete, 1).all(|(_, nodes)| nodes == 1));
+    assert_eq!(perft_divide(&kiwipete, 0).count(), 0);
+  }
+
+  #[test]
+  fn test_perft_detailed_startpos() {
+    let board = GameBoard::START_POS;
+    assert_eq!(perft_detailed(&board, 1), stats(20, 0, 0, 0, 0, 0, 0, 0, 0));
+    assert_eq!(
+      perft_detailed(&board, 3),
+      stats(8_902, 34, 0, 0, 0, 12, 0, 0, 0)
+    );
+    assert_eq!(
+      perft_detailed(&board, 4),
+      stats(197_281, 1_576, 0, 0, 0, 469, 0, 0, 8)
+    );
+  }
+
+  #[test]
+  fn test_perft_detailed_kiwipete() {
+    let board = board_from_fen(KIWIPETE);
+    assert_eq!(perft_detailed(&board, 1), stats(48, 8, 0, 2, 0, 0, 0, 0, 0));
+    assert_eq!(
+      perft_detailed(&board, 2),
+      stats(2_039, 351, 1, 91, 0, 3, 0, 0, 0)
+    );
+    assert_eq!(
+      perft_detailed(&board, 3),
+      stats(97_862, 17_102, 45, 3_162, 0, 993, 0, 0, 1)
+    );
+  }
+
+  #[test]
+  fn test_perft_detailed_position_3() {
+    let board = board_from_fen(POSITION_3);
+    assert_eq!(
+      perft_detailed(&board, 3),
+      stats(2_812, 209, 2, 0, 0, 267, 3, 0, 0)
+    );
+    assert_eq!(
+      perft_detailed(&board, 4),
+      stats(43_238, 3_348, 123, 0, 0, 1_680, 106, 0, 17)
+    );
+  }
+
+  #[cfg(feature = "iterative")]
+  #[test]
+  fn test_perft_iterative_matches_recursive() {
+    let mut stack = PerftStack::<4>::new();
+    for board in [
+      GameBoard::START_POS,
+      board_from_fen(KIWIPETE),
+      board_from_fen(POSITION_3),
+    ] {
+      for depth in 0..=3 {
+        assert_eq!(
+          perft_iterative(&board, depth, &mut stack),
+          Some(perft(&board, depth))
+        );
+      }
+    }
+    assert_eq!(perft_iterative(&GameBoard::START_POS, 5, &mut stack), None);
+  }
+
+  #[test]
+  fn test_perft_cached() {
+    let mut cache = [0u64; 2 * 1024];
+    for (fen, depth) in [(KIWIPETE, 3), (POSITION_3, 4)] {
+      let board = board_from_fen(fen);
+      let expected = perft(&board, depth);
+      assert_eq!(perft_cached(&board, depth, &mut cache), expected);
+      // A second run answers from the cache and agrees
+      assert_eq!(perft_cached(&board, depth, &mut cache), expected);
+    }
+    assert_eq!(perft_cached(&GameBoard::START_POS, 3, &mut []), 8_902);
+    assert_eq!(perft_cached(&GameBoard::START_POS, 4, &mut [0; 3]), 197_281);
+  }
+
+  #[test]
+  fn test_perft_matches_detailed_nodes() {
+    let board = board_from_fen(KIWIPETE);
+    assert_eq!(perft(&board, 2), perft_detailed(&board, 2).nodes);
+  }
+}
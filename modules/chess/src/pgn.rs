@@ -0,0 +1,846 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Portable Game Notation (PGN) games with their tag pairs.
+//!
+//! [`PgnGame`] pairs a [`GameData`] with [`Tags`]: the Seven Tag Roster (Event, Site, Date,
+//! Round, White, Black, Result) as named fields, and any other tags in a map. Parsing a PGN
+//! fills both, starting from the `FEN` tag when there is one; formatting writes the roster, the
+//! other tags in ASCII order and the movetext, with a `FEN` tag for games that do not start from
+//! the initial position. Clock times in [`PgnGame::clocks`] and evaluations in
+//! [`PgnGame::evals`] are written as `[%clk]` and `[%eval]` comments after their moves, and read
+//! back from comments like the ones Lichess exports. Annotations that do not parse are ignored
+//! rather than failing the game. Other comments, NAGs and variations are skipped when reading.
+//!
+//! Files of several games are read with [`read_games`] and written with [`write_games`].
+//! [`PgnWriteOptions`] sets the line width the movetext wraps at and whether the annotation
+//! comments are written. A game only holds its main line, so there are no other comments, NAGs
+//! or variations to write.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::str::FromStr;
+
+use crate::{
+  constants::START_FEN,
+  errors::{MovetextError, PgnError},
+  model::clock::{parse_clk, write_clk},
+  model::gamedata::GameData,
+  san::{read_movetext, write_san},
+};
+
+/// Longest line the writer produces by default, as the PGN export format asks.
+pub const MAX_LINE: usize = 79;
+
+/// The results a movetext can end with. Any other `Result` tag ends the movetext with `*`.
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// How [`PgnGame::write_with`] and [`write_games`] lay out a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PgnWriteOptions {
+  /// Longest movetext line, in characters. A single token longer than this still gets a line of
+  /// its own; `usize::MAX` writes the movetext on one line.
+  pub line_width: usize,
+  /// Write [`PgnGame::clocks`] and [`PgnGame::evals`] as `[%clk]` and `[%eval]` comments.
+  pub annotations: bool,
+}
+
+impl Default for PgnWriteOptions {
+  fn default() -> Self {
+    Self {
+      line_width: MAX_LINE,
+      annotations: true,
+    }
+  }
+}
+
+/// The tag pairs of a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tags {
+  pub event: String,
+  pub site: String,
+  /// `YYYY.MM.DD`, with `??` for unknown parts.
+  pub date: String,
+  pub round: String,
+  pub white: String,
+  pub black: String,
+  /// `1-0`, `0-1`, `1/2-1/2` or `*` for a game in progress.
+  pub result: String,
+  /// Every tag outside the Seven Tag Roster, by name.
+  pub other: BTreeMap<String, String>,
+}
+
+impl Default for Tags {
+  fn default() -> Self {
+    Self {
+      event: "?".to_string(),
+      site: "?".to_string(),
+      date: "????.??.??".to_string(),
+      round: "?".to_string(),
+      white: "?".to_string(),
+      black: "?".to_string(),
+      result: "*".to_string(),
+      other: BTreeMap::new(),
+    }
+  }
+}
+
+impl Tags {
+  /// The value of the tag `name`, from the roster or the other tags.
+  pub fn get(&self, name: &str) -> Option<&str> {
+    match name {
+      "Event" => Some(&self.event),
+      "Site" => Some(&self.site),
+      "Date" => Some(&self.date),
+      "Round" => Some(&self.round),
+      "White" => Some(&self.white),
+      "Black" => Some(&self.black),
+      "Result" => Some(&self.result),
+      _ => self.other.get(name).map(String::as_str),
+    }
+  }
+
+  /// Set the tag `name`, replacing any earlier value.
+  pub fn set(&mut self, name: &str, value: impl Into<String>) {
+    let value = value.into();
+    match name {
+      "Event" => self.event = value,
+      "Site" => self.site = value,
+      "Date" => self.date = value,
+      "Round" => self.round = value,
+      "White" => self.white = value,
+      "Black" => self.black = value,
+      "Result" => self.result = value,
+      _ => {
+        self.other.insert(name.to_string(), value);
+      }
+    }
+  }
+
+  /// Every tag in export order: the roster first, then the rest sorted by name.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+    let roster = [
+      ("Event", self.event.as_str()),
+      ("Site", self.site.as_str()),
+      ("Date", self.date.as_str()),
+      ("Round", self.round.as_str()),
+      ("White", self.white.as_str()),
+      ("Black", self.black.as_str()),
+      ("Result", self.result.as_str()),
+    ];
+    roster.into_iter().chain(
+      self
+        .other
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str())),
+    )
+  }
+}
+
+/// A game with its PGN tags.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+  pub tags: Tags,
+  pub game: GameData,
+  /// The mover's remaining time in milliseconds after each move of the history, if known. See
+  /// [`Clock::record_move`](crate::model::clock::Clock::record_move).
+  pub clocks: Vec<Option<u64>>,
+  /// The evaluation after each move of the history, if known.
+  pub evals: Vec<Option<Eval>>,
+}
+
+impl PgnGame {
+  /// `game` with default tags.
+  pub fn new(game: GameData) -> Self {
+    Self {
+      tags: Tags::default(),
+      game,
+      clocks: Vec::new(),
+      evals: Vec::new(),
+    }
+  }
+
+  /// Take the `[%clk]` and `[%eval]` annotations of `comment`, which follows the last move
+  /// played so far.
+  fn annotate(&mut self, comment: &str) {
+    let Some(index) = self.game.history_len().checked_sub(1) else {
+      return;
+    };
+    if let Some(millis) = command(comment, "clk").and_then(parse_clk) {
+      set_at(&mut self.clocks, index, millis);
+    }
+    if let Some(eval) = command(comment, "eval").and_then(Eval::parse) {
+      set_at(&mut self.evals, index, eval);
+    }
+  }
+}
+
+/// An engine evaluation from a `[%eval]` comment, from white's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eval {
+  /// Advantage in centipawns: `[%eval -0.42]` is `Centipawns(-42)`.
+  Centipawns(i32),
+  /// Mate in this many moves, negative when black mates: `[%eval #-3]` is `Mate(-3)`.
+  Mate(i32),
+}
+
+impl Eval {
+  /// Read the value of an `[%eval]` comment: pawns with up to two decimals, or `#` and the moves
+  /// to mate. A search depth after a comma, as in `0.17,23`, is ignored.
+  pub fn parse(text: &str) -> Option<Self> {
+    let text = text.split(',').next()?.trim();
+    if let Some(moves) = text.strip_prefix('#') {
+      return moves.parse().ok().map(Eval::Mate);
+    }
+
+    let (negative, unsigned) = match text.strip_prefix('-') {
+      Some(rest) => (true, rest),
+      None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (pawns, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if pawns.is_empty() || !digits(pawns) || !digits(fraction) {
+      return None;
+    }
+    let hundredths = fraction
+      .bytes()
+      .chain(core::iter::repeat(b'0'))
+      .take(2)
+      .fold(0, |acc, digit| acc * 10 + i32::from(digit - b'0'));
+    // Apply the sign before adding up, so the most negative value parses too
+    let pawns = pawns.parse::<i32>().ok()?;
+    let centipawns = if negative {
+      pawns.checked_mul(-100)?.checked_sub(hundredths)?
+    } else {
+      pawns.checked_mul(100)?.checked_add(hundredths)?
+    };
+    Some(Eval::Centipawns(centipawns))
+  }
+}
+
+impl fmt::Display for Eval {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match *self {
+      Eval::Centipawns(centipawns) => {
+        let sign = if centipawns < 0 { "-" } else { "" };
+        let abs = centipawns.unsigned_abs();
+        write!(f, "{sign}{}.{:02}", abs / 100, abs % 100)
+      }
+      Eval::Mate(moves) => write!(f, "#{moves}"),
+    }
+  }
+}
+
+impl FromStr for PgnGame {
+  type Err = PgnError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut tags = Tags::default();
+    let mut movetext = String::new();
+    for (line_index, line) in s.lines().enumerate() {
+      let trimmed = line.trim();
+      if movetext.is_empty() && trimmed.starts_with('[') {
+        let (name, value) = parse_tag(trimmed).ok_or(PgnError::InvalidTag { line: line_index })?;
+        tags.set(name, value);
+      } else if !trimmed.starts_with('%') {
+        // Lines starting with % are escaped and ignored
+        movetext.push_str(line);
+        movetext.push('\n');
+      }
+    }
+
+    let game = match tags.other.get("FEN") {
+      Some(fen) => GameData::from_fen(fen).map_err(PgnError::InvalidFen)?,
+      None => GameData::START_POS,
+    };
+    let mut pgn = Self {
+      tags,
+      ..Self::new(game)
+    };
+    for (part, comment) in split_comments(&movetext) {
+      let played = pgn.game.history_len();
+      read_movetext(&mut pgn.game, &strip_annotations(part)).map_err(|error| {
+        PgnError::Movetext(MovetextError {
+          index: played + error.index,
+          ..error
+        })
+      })?;
+      if let Some(comment) = comment {
+        pgn.annotate(comment);
+      }
+    }
+    Ok(pgn)
+  }
+}
+
+impl fmt::Display for PgnGame {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.write_with(f, &PgnWriteOptions::default())
+  }
+}
+
+impl PgnGame {
+  /// Write the game as [`Display`](fmt::Display) does, laid out by `options`: the tags, a blank
+  /// line, then the movetext ending with the result.
+  pub fn write_with<W: Write>(&self, out: &mut W, options: &PgnWriteOptions) -> fmt::Result {
+    let initial_fen = GameData {
+      board: self.game.initial_board,
+      plies: self.game.initial_plies,
+      halfmove_clock: self.game.initial_halfmove_clock,
+      ..self.game
+    }
+    .to_fen();
+    let setup = initial_fen != START_FEN;
+
+    for (name, value) in self.tags.iter() {
+      if setup && matches!(name, "SetUp" | "FEN") {
+        continue;
+      }
+      write_tag(out, name, value)?;
+    }
+    if setup {
+      write_tag(out, "SetUp", "1")?;
+      write_tag(out, "FEN", &initial_fen)?;
+    }
+    out.write_char('\n')?;
+
+    let result = RESULTS
+      .into_iter()
+      .find(|&result| result == self.tags.result)
+      .unwrap_or("*");
+    let mut width = 0;
+    for token in self
+      .movetext_tokens(options.annotations)?
+      .iter()
+      .map(String::as_str)
+      .chain([result])
+    {
+      if width > 0 && width + 1 + token.len() > options.line_width {
+        out.write_char('\n')?;
+        width = 0;
+      } else if width > 0 {
+        out.write_char(' ')?;
+        width += 1;
+      }
+      out.write_str(token)?;
+      width += token.len();
+    }
+    out.write_char('\n')
+  }
+
+  /// The movetext as words to wrap: move numbers, SAN moves and annotation comments. A black move
+  /// gets its own `N...` number when it opens the game or follows a comment.
+  fn movetext_tokens(&self, annotations: bool) -> Result<Vec<String>, fmt::Error> {
+    let mut tokens = Vec::new();
+    let mut board = self.game.initial_board;
+    let mut commented = false;
+    for (index, piece_move) in self.game.moves[..self.game.history_len()]
+      .iter()
+      .enumerate()
+    {
+      let fullmove = (self.game.initial_plies + index) / 2 + 1;
+      if board.playing {
+        tokens.push(format!("{fullmove}."));
+      } else if index == 0 || commented {
+        tokens.push(format!("{fullmove}..."));
+      }
+      let mut san = String::new();
+      write_san(&board, piece_move, &mut san)?;
+      tokens.push(san);
+
+      let mut comment = Vec::new();
+      if let Some(&Some(eval)) = self.evals.get(index).filter(|_| annotations) {
+        comment.push(format!("[%eval {eval}]"));
+      }
+      if let Some(&Some(millis)) = self.clocks.get(index).filter(|_| annotations) {
+        let mut clk = "[%clk ".to_string();
+        write_clk(millis, &mut clk)?;
+        clk.push(']');
+        comment.push(clk);
+      }
+      commented = !comment.is_empty();
+      if commented {
+        // One token, so no line starts with `[`, which readers take for a tag
+        tokens.push(format!("{{ {} }}", comment.join(" ")));
+      }
+      // A move that does not replay means the history was edited by hand; stop there
+      if board.move_piece(piece_move).is_none() {
+        break;
+      }
+    }
+    Ok(tokens)
+  }
+}
+
+/// Split `[Name "value"]` into its name and unescaped value.
+fn parse_tag(line: &str) -> Option<(&str, String)> {
+  let inner = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+  let (name, rest) = inner.split_once(char::is_whitespace)?;
+  let quoted = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+  let mut value = String::with_capacity(quoted.len());
+  let mut chars = quoted.chars();
+  while let Some(c) = chars.next() {
+    value.push(if c == '\\' { chars.next()? } else { c });
+  }
+  Some((name, value))
+}
+
+fn write_tag<W: Write>(out: &mut W, name: &str, value: &str) -> fmt::Result {
+  write!(out, "[{name} \"")?;
+  for c in value.chars() {
+    if matches!(c, '"' | '\\') {
+      out.write_char('\\')?;
+    }
+    // A tag pair has to stay on one line
+    out.write_char(if c.is_control() { ' ' } else { c })?;
+  }
+  out.write_str("\"]\n")
+}
+
+/// Read every game of `text`, one or more PGN games one after another, in order.
+pub fn read_games(text: &str) -> impl Iterator<Item = Result<PgnGame, PgnError>> + '_ {
+  split_games(text).map(str::parse)
+}
+
+/// Write `games` one after another, each laid out by `options` and separated by a blank line,
+/// so [`read_games`] reads them back.
+pub fn write_games<'a, W: Write>(
+  out: &mut W,
+  games: impl IntoIterator<Item = &'a PgnGame>,
+  options: &PgnWriteOptions,
+) -> fmt::Result {
+  for (index, game) in games.into_iter().enumerate() {
+    if index > 0 {
+      out.write_char('\n')?;
+    }
+    game.write_with(out, options)?;
+  }
+  Ok(())
+}
+
+/// Split a file of PGN games at each tag section that follows movetext.
+fn split_games(text: &str) -> impl Iterator<Item = &str> {
+  let mut starts = Vec::new();
+  let mut in_movetext = false;
+  let mut offset = 0;
+  for line in text.split_inclusive('\n') {
+    let trimmed = line.trim();
+    if trimmed.starts_with('[') && (in_movetext || starts.is_empty()) {
+      starts.push(offset);
+      in_movetext = false;
+    } else if !trimmed.is_empty() && !trimmed.starts_with('[') {
+      in_movetext = true;
+    }
+    offset += line.len();
+  }
+  if starts.is_empty() && !text.trim().is_empty() {
+    starts.push(0);
+  }
+  let ends: Vec<_> = starts.iter().skip(1).copied().chain([text.len()]).collect();
+  starts
+    .into_iter()
+    .zip(ends)
+    .map(move |(start, end)| &text[start..end])
+}
+
+/// Split movetext at its top-level `{}` comments into each stretch of movetext with the comment
+/// that ends it, if any. Comments inside variations and `;` comments stay in the movetext, for
+/// [`strip_annotations`] to drop. An unclosed comment runs to the end.
+fn split_comments(movetext: &str) -> Vec<(&str, Option<&str>)> {
+  let mut parts = Vec::new();
+  let mut start = 0;
+  let mut depth = 0usize;
+  let mut chars = movetext.char_indices();
+  while let Some((index, c)) = chars.next() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth = depth.saturating_sub(1),
+      ';' => {
+        for (_, c) in chars.by_ref() {
+          if c == '\n' {
+            break;
+          }
+        }
+      }
+      '{' => {
+        let close = chars
+          .by_ref()
+          .find(|&(_, c)| c == '}')
+          .map_or(movetext.len(), |(close, _)| close);
+        if depth == 0 {
+          parts.push((&movetext[start..index], Some(&movetext[index + 1..close])));
+          start = (close + 1).min(movetext.len());
+        }
+      }
+      _ => {}
+    }
+  }
+  parts.push((&movetext[start..], None));
+  parts
+}
+
+/// The value of the `[%name value]` command in `comment`, if it has one.
+fn command<'a>(comment: &'a str, name: &str) -> Option<&'a str> {
+  let mut rest = comment;
+  while let Some(open) = rest.find("[%") {
+    rest = &rest[open + 2..];
+    let close = rest.find(']')?;
+    if let Some((command, value)) = rest[..close].trim().split_once(char::is_whitespace)
+      && command == name
+    {
+      return Some(value.trim());
+    }
+    rest = &rest[close + 1..];
+  }
+  None
+}
+
+/// Set `list[index]`, growing the list with `None`s as needed.
+fn set_at<T>(list: &mut Vec<Option<T>>, index: usize, value: T) {
+  if list.len() <= index {
+    list.resize_with(index + 1, || None);
+  }
+  list[index] = Some(value);
+}
+
+/// Drop `{}` and `;` comments, `$` NAGs and `()` variations from movetext.
+fn strip_annotations(movetext: &str) -> String {
+  let mut out = String::with_capacity(movetext.len());
+  let mut depth = 0usize;
+  let mut chars = movetext.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '{' => {
+        for c in chars.by_ref() {
+          if c == '}' {
+            break;
+          }
+        }
+        out.push(' ');
+      }
+      ';' => {
+        for c in chars.by_ref() {
+          if c == '\n' {
+            break;
+          }
+        }
+        out.push(' ');
+      }
+      '(' => depth += 1,
+      ')' => {
+        depth = depth.saturating_sub(1);
+        out.push(' ');
+      }
+      '$' => {
+        // A NAG runs to the next whitespace
+        for c in chars.by_ref() {
+          if c.is_whitespace() {
+            break;
+          }
+        }
+        out.push(' ');
+      }
+      _ if depth == 0 => out.push(c),
+      _ => {}
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::errors::{MovetextError, SanError};
+  use crate::model::clock::Clock;
+
+  const OPERA_PGN: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+[ECO "C41"]
+
+1. e4 e5 2. Nf3 d6 {Philidor's Defence} 3. d4 Bg4 $6 4. dxe5 Bxf3 5. Qxf3 dxe5
+6. Bc4 Nf6 7. Qb3 Qe7 8. Nc3 (8. Qxb7 Qb4+ 9. Qxb4 Bxb4+) 8... c6 9. Bg5 b5
+10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6
+15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+
+  #[test]
+  fn test_reads_tags_and_moves() {
+    let pgn: PgnGame = OPERA_PGN.parse().unwrap();
+    assert_eq!(pgn.tags.event, "Paris");
+    assert_eq!(pgn.tags.white, "Paul Morphy");
+    assert_eq!(pgn.tags.black, "Duke Karl / Count Isouard");
+    assert_eq!(pgn.tags.result, "1-0");
+    assert_eq!(pgn.tags.get("ECO"), Some("C41"));
+    assert_eq!(pgn.tags.get("Annotator"), None);
+    assert_eq!(pgn.game.history_len(), 33);
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let pgn: PgnGame = OPERA_PGN.parse().unwrap();
+    let written = pgn.to_string();
+    assert!(written.starts_with("[Event \"Paris\"]\n[Site \"Paris FRA\"]\n"));
+    assert!(written.contains("[Result \"1-0\"]\n[ECO \"C41\"]\n\n1. e4 e5"));
+    assert!(written.ends_with("17. Rd8# 1-0\n"));
+    assert!(written.lines().all(|line| line.len() <= MAX_LINE));
+
+    let again: PgnGame = written.parse().unwrap();
+    assert_eq!(again.tags, pgn.tags);
+    assert_eq!(again.game.to_fen(), pgn.game.to_fen());
+  }
+
+  #[test]
+  fn test_setup_position() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 3 40";
+    let mut pgn = PgnGame::new(GameData::from_fen(fen).unwrap());
+    pgn.tags.set("White", "Alice \"Ace\" \\o/");
+    read_movetext(&mut pgn.game, "Kd7").unwrap();
+
+    let written = pgn.to_string();
+    assert!(written.contains("[White \"Alice \\\"Ace\\\" \\\\o/\"]\n"));
+    assert!(written.contains(&format!("[SetUp \"1\"]\n[FEN \"{fen}\"]\n")));
+    assert!(written.contains("40... Kd7 *"));
+    assert_eq!(written.lines().last(), Some("40... Kd7 *"));
+
+    let again: PgnGame = written.parse().unwrap();
+    assert_eq!(again.tags.white, "Alice \"Ace\" \\o/");
+    assert_eq!(again.game.history_len(), 1);
+    assert_eq!(again.game.to_fen(), pgn.game.to_fen());
+  }
+
+  #[test]
+  fn test_defaults_and_errors() {
+    let written = PgnGame::new(GameData::START_POS).to_string();
+    assert_eq!(
+      written,
+      "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"?\"]\n\
+       [Black \"?\"]\n[Result \"*\"]\n\n*\n"
+    );
+
+    assert_eq!(
+      "[Event Paris]\n\n1. e4".parse::<PgnGame>().unwrap_err(),
+      PgnError::InvalidTag { line: 0 }
+    );
+    assert_eq!(
+      "1. e4 e4".parse::<PgnGame>().unwrap_err(),
+      PgnError::Movetext(MovetextError {
+        index: 1,
+        error: SanError::NoMatchingMove
+      })
+    );
+  }
+
+  #[test]
+  fn test_writes_clock_comments() {
+    let mut clock = Clock {
+      white_time: Some(180_000),
+      black_time: Some(180_000),
+      white_increment: 2_000,
+      black_increment: 2_000,
+      moves_to_go: None,
+    };
+    let mut pgn = PgnGame::new(GameData::START_POS);
+    read_movetext(&mut pgn.game, "e4 e5 Nf3").unwrap();
+    for (ply, elapsed) in [1_000, 4_500, 12_000].into_iter().enumerate() {
+      pgn.clocks.push(clock.record_move(ply % 2 == 0, elapsed));
+    }
+
+    let written = pgn.to_string();
+    let movetext = written.split("\n\n").nth(1).unwrap().replace('\n', " ");
+    assert_eq!(
+      movetext,
+      "1. e4 { [%clk 0:03:01] } 1... e5 { [%clk 0:02:57.5] } 2. Nf3 { [%clk 0:02:51] } * "
+    );
+    // The clocks are read back
+    let again: PgnGame = written.parse().unwrap();
+    assert_eq!(again.game.history_len(), 3);
+    assert_eq!(again.clocks, pgn.clocks);
+  }
+
+  #[test]
+  fn test_reads_lichess_annotations() {
+    let text = "[Event \"Rated blitz game\"]\n\n\
+      1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... c5 { [%eval 0.19] [%clk 0:02:58.3] } \
+      2. Nf3 { [%clk 0:02:59] } 2... d6 { A comment (with parens) } \
+      3. d4 { [%eval -1.5,24] [%clk 0:02:5x] } (3. Bb5+ { [%eval 9.99] }) \
+      3... cxd4 { [%eval #-3] } 1-0";
+    let pgn: PgnGame = text.parse().unwrap();
+    assert_eq!(pgn.game.history_len(), 6);
+    assert_eq!(pgn.clocks, [Some(180_000), Some(178_300), Some(179_000)]);
+    assert_eq!(
+      pgn.evals,
+      [
+        Some(Eval::Centipawns(17)),
+        Some(Eval::Centipawns(19)),
+        None,
+        None,
+        Some(Eval::Centipawns(-150)),
+        Some(Eval::Mate(-3)),
+      ]
+    );
+
+    // Both are written back in one comment
+    let written = pgn.to_string();
+    let movetext = written.replace('\n', " ");
+    assert!(movetext.contains("1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... c5"));
+    assert!(movetext.contains("3. d4 { [%eval -1.50] } 3... cxd4 { [%eval #-3] } *"));
+    let again: PgnGame = written.parse().unwrap();
+    assert_eq!(again.evals, pgn.evals);
+    assert_eq!(again.clocks, pgn.clocks);
+  }
+
+  #[test]
+  fn test_eval_parse() {
+    assert_eq!(Eval::parse("0.17"), Some(Eval::Centipawns(17)));
+    assert_eq!(Eval::parse("-0.42"), Some(Eval::Centipawns(-42)));
+    assert_eq!(Eval::parse("+3"), Some(Eval::Centipawns(300)));
+    assert_eq!(Eval::parse("1.234"), Some(Eval::Centipawns(123)));
+    assert_eq!(Eval::parse("#5"), Some(Eval::Mate(5)));
+    assert_eq!(Eval::parse("21474836.47"), Some(Eval::Centipawns(i32::MAX)));
+    assert_eq!(
+      Eval::parse("-21474836.48"),
+      Some(Eval::Centipawns(i32::MIN))
+    );
+    for bad in [
+      "",
+      "-",
+      ".5",
+      "1.x",
+      "#",
+      "#x",
+      "pawn",
+      "21474836.48",
+      "21474836.99",
+      "-21474836.49",
+      "99999999999",
+    ] {
+      assert_eq!(Eval::parse(bad), None, "{bad}");
+    }
+    assert_eq!(Eval::Centipawns(-5).to_string(), "-0.05");
+    assert_eq!(Eval::Mate(2).to_string(), "#2");
+  }
+
+  #[test]
+  fn test_movetext_error_index_spans_comments() {
+    assert_eq!(
+      "1. e4 { [%clk 0:03:00] } e5 2. Ke3"
+        .parse::<PgnGame>()
+        .unwrap_err(),
+      PgnError::Movetext(MovetextError {
+        index: 2,
+        error: SanError::NoMatchingMove
+      })
+    );
+  }
+
+  #[test]
+  fn test_write_options() {
+    let mut pgn: PgnGame = OPERA_PGN.parse().unwrap();
+    pgn.evals = vec![Some(Eval::Centipawns(30))];
+
+    let mut narrow = String::new();
+    let options = PgnWriteOptions {
+      line_width: 20,
+      annotations: false,
+    };
+    pgn.write_with(&mut narrow, &options).unwrap();
+    let movetext = narrow.split("\n\n").nth(1).unwrap();
+    assert!(movetext.lines().all(|line| line.len() <= 20));
+    assert!(movetext.starts_with("1. e4 e5 2. Nf3 d6\n"));
+    assert!(!narrow.contains("%eval"));
+
+    let mut wide = String::new();
+    let options = PgnWriteOptions {
+      line_width: usize::MAX,
+      ..PgnWriteOptions::default()
+    };
+    pgn.write_with(&mut wide, &options).unwrap();
+    let movetext = wide.split("\n\n").nth(1).unwrap();
+    assert_eq!(movetext.lines().count(), 1);
+    assert!(movetext.starts_with("1. e4 { [%eval 0.30] } 1... e5"));
+    assert_eq!(pgn.to_string(), {
+      let mut text = String::new();
+      pgn
+        .write_with(&mut text, &PgnWriteOptions::default())
+        .unwrap();
+      text
+    });
+
+    // Comments are never split, so a wrapped line cannot start with `[` and read as a tag
+    pgn.evals = vec![Some(Eval::Centipawns(-5)); pgn.game.history_len()];
+    let written = pgn.to_string();
+    assert!(
+      written
+        .split("\n\n")
+        .nth(1)
+        .unwrap()
+        .lines()
+        .all(|line| !line.starts_with('['))
+    );
+    let again: PgnGame = written.parse().unwrap();
+    assert_eq!(again.evals, pgn.evals);
+  }
+
+  #[test]
+  fn test_result_token_and_tag_escapes() {
+    let mut pgn = PgnGame::new(GameData::START_POS);
+    read_movetext(&mut pgn.game, "f3 e5 g4 Qh4#").unwrap();
+    pgn.tags.result = "0-1".to_string();
+    pgn.tags.event = "The \"Fool's\" mate \\ blitz\nround".to_string();
+    let written = pgn.to_string();
+    assert!(written.contains("[Event \"The \\\"Fool's\\\" mate \\\\ blitz round\"]\n"));
+    assert!(written.ends_with("2. g4 Qh4# 0-1\n"));
+
+    // Anything but a result ends the movetext as a game in progress
+    pgn.tags.result = "black won".to_string();
+    assert!(pgn.to_string().ends_with("2. g4 Qh4# *\n"));
+  }
+
+  #[test]
+  fn test_multiple_games() {
+    let opera: PgnGame = OPERA_PGN.parse().unwrap();
+    let mut fools = PgnGame::new(GameData::START_POS);
+    read_movetext(&mut fools.game, "f3 e5 g4 Qh4#").unwrap();
+    fools.tags.result = "0-1".to_string();
+    fools.clocks = vec![Some(60_000)];
+
+    let mut text = String::new();
+    write_games(&mut text, [&opera, &fools], &PgnWriteOptions::default()).unwrap();
+    assert!(text.contains("17. Rd8# 1-0\n\n[Event \"?\"]\n"));
+
+    let games: Vec<_> = read_games(&text).collect::<Result<_, _>>().unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].tags, opera.tags);
+    assert_eq!(games[0].game.to_fen(), opera.game.to_fen());
+    assert_eq!(games[1].tags, fools.tags);
+    assert_eq!(games[1].game.history_len(), 4);
+    assert_eq!(games[1].clocks, fools.clocks);
+
+    let broken = text.replace("Qh4#", "Qh5#");
+    assert!(read_games(&broken).nth(1).unwrap().is_err());
+  }
+}
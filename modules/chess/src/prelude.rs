@@ -0,0 +1,60 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! The types most programs need, in one import.
+//!
+//! ```rust
+//! use lumifox_chess::prelude::*;
+//!
+//! let game = GameData::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")?;
+//! let (moves, count) = generate_moves(&game.board);
+//! assert_eq!(count, 20);
+//! assert!(moves[..count].contains(&PieceMove::new(52, 36, false, None)));
+//! # Ok::<(), FenParseError>(())
+//! ```
+//!
+//! Squares are plain `u8` indices from a1 = 0 to h8 = 63 (named in [`constants`](crate::constants)),
+//! and sides are `bool`s with `true` for white, so neither needs a type of its own.
+
+pub use crate::{
+  errors::{
+    ChessError, DiagramParseError, FenParseError, IllegalMoveReason, MaterialParseError,
+    MoveParseError, SanError,
+  },
+  legal::attack::{is_square_attacked, is_square_attacked_excluding},
+  model::{
+    bitboard::BitBoard,
+    castling::{CastleStatus, CastlingRights},
+    diagram::DiagramOptions,
+    gameboard::{GameBoard, PieceType, UndoInfo},
+    gamedata::GameData,
+    material::MaterialSig,
+    piecemove::{PieceMove, PromotionType, parse_square},
+    status::{DrawReason, GameStatus},
+  },
+  movegen::{
+    MoveGenFlags, MoveList, generate_legal_moves, generate_legal_moves_with, generate_moves,
+    generate_moves_with,
+  },
+};
+
+#[cfg(feature = "alloc")]
+pub use crate::{
+  errors::{MovetextError, PgnError},
+  pgn::PgnGame,
+};
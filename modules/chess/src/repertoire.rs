@@ -0,0 +1,377 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Opening repertoires built from PGN games, for preparation and practice.
+//!
+//! A [`Repertoire`] stores every position reached by the games added to it, with the moves played
+//! from each. Positions are keyed by their FEN without the clocks, so lines that transpose into
+//! each other share their continuations and the tree of lines becomes a graph. The key includes
+//! the en passant square as [`GameBoard::write_fen`] writes it. Ask it for the
+//! [`moves`](Repertoire::moves) of a position, whether a move is [`Verdict::Book`] with
+//! [`check`](Repertoire::check), where a played game first left it with
+//! [`deviation`](Repertoire::deviation), or write it back out as PGN with
+//! [`to_pgn`](Repertoire::to_pgn). Requires the `alloc` feature.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{
+  errors::PgnError,
+  model::{
+    gameboard::GameBoard,
+    gamedata::{GameData, MAX_GAME_MOVES},
+    piecemove::{Move16, PieceMove},
+  },
+  pgn::{PgnGame, PgnWriteOptions, read_games, write_games},
+};
+
+/// A position of the repertoire and the moves played from it, in the order they were added.
+/// Moves are kept packed and get their flags back from the position when read.
+#[derive(Debug, Clone, Default)]
+struct Node {
+  moves: Vec<(Move16, usize)>,
+}
+
+/// Positions and moves collected from games. See the [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct Repertoire {
+  nodes: Vec<Node>,
+  index: BTreeMap<String, usize>,
+  /// The distinct positions games started from, with the node of each.
+  roots: Vec<(GameData, usize)>,
+}
+
+/// How a move relates to the repertoire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+  /// The move is one of the repertoire moves of the position.
+  Book,
+  /// The repertoire has moves for the position, but not this one.
+  Deviation { expected: Vec<PieceMove> },
+  /// The repertoire has nothing to say about the position.
+  OutOfBook,
+}
+
+/// Where a game first left the repertoire with a move it has an answer for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deviation {
+  /// Moves played before the deviation, so `game.position_at(ply)` is where it happened.
+  pub ply: usize,
+  pub played: PieceMove,
+  /// The repertoire moves of that position.
+  pub expected: Vec<PieceMove>,
+}
+
+impl Repertoire {
+  /// An empty repertoire.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Every position the repertoire knows.
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+
+  /// Add the moves of `game`, from its initial position.
+  pub fn add_game(&mut self, game: &GameData) {
+    let start = initial(game);
+    let mut node = self.node_for(&start.board);
+    if !self.roots.iter().any(|&(_, root)| root == node) {
+      self.roots.push((start, node));
+    }
+    for (_, piece_move, after) in game.replay() {
+      let next = self.node_for(&after.board);
+      let packed = Move16::from(piece_move);
+      let moves = &mut self.nodes[node].moves;
+      if !moves.iter().any(|&(known, _)| known == packed) {
+        moves.push((packed, next));
+      }
+      node = next;
+    }
+  }
+
+  /// Add every game of `text`, one or more PGN games one after another. Nothing is added if any
+  /// game fails to parse.
+  pub fn add_pgn(&mut self, text: &str) -> Result<usize, PgnError> {
+    let games = read_games(text).collect::<Result<Vec<_>, _>>()?;
+    for pgn in &games {
+      self.add_game(&pgn.game);
+    }
+    Ok(games.len())
+  }
+
+  /// The repertoire moves of `board`, empty if the position is not in the repertoire.
+  pub fn moves(&self, board: &GameBoard) -> Vec<PieceMove> {
+    self
+      .index
+      .get(&key(board))
+      .map(|&node| {
+        self.nodes[node]
+          .moves
+          .iter()
+          .filter_map(|&(packed, _)| packed.to_move(board))
+          .collect()
+      })
+      .unwrap_or_default()
+  }
+
+  /// Whether playing `piece_move` on `board` follows the repertoire. Moves are compared by their
+  /// squares and promotion, so moves parsed from UCI without capture flags match too.
+  pub fn check(&self, board: &GameBoard, piece_move: &PieceMove) -> Verdict {
+    let expected = self.moves(board);
+    if expected.is_empty() {
+      Verdict::OutOfBook
+    } else if expected.iter().any(|known| same_move(known, piece_move)) {
+      Verdict::Book
+    } else {
+      Verdict::Deviation { expected }
+    }
+  }
+
+  /// The first move of `game` by the side given by `white` that is not in the repertoire. The
+  /// opponent may play anything; the game is followed until either side reaches a position the
+  /// repertoire does not know.
+  pub fn deviation(&self, game: &GameData, white: bool) -> Option<Deviation> {
+    let mut board = game.initial_board;
+    for (ply, piece_move) in game.moves[..game.history_len()].iter().enumerate() {
+      match self.check(&board, piece_move) {
+        Verdict::OutOfBook => return None,
+        Verdict::Deviation { expected } if board.playing == white => {
+          return Some(Deviation {
+            ply,
+            played: *piece_move,
+            expected,
+          });
+        }
+        _ => {}
+      }
+      board.move_piece(piece_move)?;
+    }
+    None
+  }
+
+  /// Every line of the repertoire as a game, from a starting position to a position with no
+  /// repertoire moves. A line that transposes back into a position already on it ends there.
+  pub fn lines(&self) -> Vec<GameData> {
+    let mut lines = Vec::new();
+    for (start, root) in &self.roots {
+      let mut path = Vec::new();
+      self.collect_lines(*start, *root, &mut path, &mut lines);
+    }
+    lines
+  }
+
+  /// [`lines`](Self::lines) as PGN games with default tags, separated by blank lines.
+  pub fn to_pgn(&self) -> String {
+    let games: Vec<_> = self.lines().into_iter().map(PgnGame::new).collect();
+    let mut text = String::new();
+    write_games(&mut text, &games, &PgnWriteOptions::default())
+      .expect("Writing to a String cannot fail");
+    text
+  }
+
+  fn collect_lines(
+    &self,
+    game: GameData,
+    node: usize,
+    path: &mut Vec<usize>,
+    lines: &mut Vec<GameData>,
+  ) {
+    let ends_here = self.nodes[node].moves.is_empty()
+      || path.contains(&node)
+      || game.history_len() >= MAX_GAME_MOVES;
+    if ends_here {
+      lines.push(game);
+      return;
+    }
+    path.push(node);
+    for &(packed, next) in &self.nodes[node].moves {
+      let Some(piece_move) = packed.to_move(&game.board) else {
+        continue;
+      };
+      let mut after = game;
+      if after.play_move(&piece_move).is_some() {
+        self.collect_lines(after, next, path, lines);
+      }
+    }
+    path.pop();
+  }
+
+  fn node_for(&mut self, board: &GameBoard) -> usize {
+    let next = self.nodes.len();
+    let node = *self.index.entry(key(board)).or_insert(next);
+    if node == next {
+      self.nodes.push(Node::default());
+    }
+    node
+  }
+}
+
+/// `game` rewound to its initial position.
+fn initial(game: &GameData) -> GameData {
+  let mut start = GameData {
+    board: game.initial_board,
+    plies: game.initial_plies,
+    halfmove_clock: game.initial_halfmove_clock,
+    ..*game
+  };
+  start.moves[..game.history_len()].fill(PieceMove::NULL);
+  start
+}
+
+/// The FEN of `board` without the clocks.
+fn key(board: &GameBoard) -> String {
+  let mut fen = String::new();
+  board
+    .write_fen(&mut fen)
+    .expect("Writing to a String cannot fail");
+  fen
+}
+
+fn same_move(a: &PieceMove, b: &PieceMove) -> bool {
+  Move16::from(*a) == Move16::from(*b)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constants::*;
+  use crate::san::read_movetext;
+
+  const REPERTOIRE: &str = "[Event \"Open Sicilian\"]
+
+1. e4 c5 2. Nf3 d6 3. d4 *
+
+[Event \"Alapin\"]
+
+1. e4 c5 2. c3 d5 *
+
+[Event \"French\"]
+
+1. e4 e6 2. d4 d5 3. Nc3 *
+";
+
+  fn game(movetext: &str) -> GameData {
+    let mut game = GameData::START_POS;
+    read_movetext(&mut game, movetext).unwrap();
+    game
+  }
+
+  #[test]
+  fn test_moves_by_position() {
+    let mut repertoire = Repertoire::new();
+    assert_eq!(repertoire.add_pgn(REPERTOIRE), Ok(3));
+    assert_eq!(
+      repertoire.moves(&GameBoard::START_POS),
+      [PieceMove::new(E2, E4, false, None)]
+    );
+    let after_c5 = game("e4 c5").board;
+    let moves = repertoire.moves(&after_c5);
+    assert_eq!(moves.len(), 2);
+    assert!(same_move(&moves[0], &PieceMove::simple(G1, F3)));
+    assert!(same_move(&moves[1], &PieceMove::simple(C2, C3)));
+    assert!(repertoire.moves(&game("d4").board).is_empty());
+
+    // Adding a line again changes nothing
+    let known = repertoire.len();
+    repertoire.add_game(&game("e4 c5 Nf3"));
+    assert_eq!(repertoire.len(), known);
+  }
+
+  #[test]
+  fn test_transpositions_share_moves() {
+    let mut repertoire = Repertoire::new();
+    repertoire.add_game(&game("d4 d5 Nf3 Nf6 c4"));
+    // 1. Nf3 d5 2. d4 Nf6 reaches the same position by another order
+    let board = game("Nf3 d5 d4 Nf6").board;
+    assert_eq!(
+      repertoire.check(&board, &PieceMove::simple(C2, C4)),
+      Verdict::Book
+    );
+  }
+
+  #[test]
+  fn test_check_and_deviation() {
+    let mut repertoire = Repertoire::new();
+    repertoire.add_pgn(REPERTOIRE).unwrap();
+    let after_c5 = game("e4 c5").board;
+    assert_eq!(
+      repertoire.check(&after_c5, &PieceMove::simple(C2, C3)),
+      Verdict::Book
+    );
+    assert!(matches!(
+      repertoire.check(&after_c5, &PieceMove::simple(B1, C3)),
+      Verdict::Deviation { expected } if expected.len() == 2
+    ));
+
+    // White played 2. Nc3 instead of the repertoire moves
+    let played = game("e4 c5 Nc3 Nc6");
+    let deviation = repertoire.deviation(&played, true).unwrap();
+    assert_eq!(deviation.ply, 2);
+    assert!(same_move(&deviation.played, &PieceMove::simple(B1, C3)));
+    // Black's repertoire is not checked for white's mistakes, and black's own deviation is found
+    assert_eq!(repertoire.deviation(&played, false), None);
+    let deviation = repertoire.deviation(&game("e4 c5 Nf3 Nc6"), false).unwrap();
+    assert_eq!(deviation.ply, 3);
+    // Leaving the repertoire after it ends is not a deviation
+    assert_eq!(
+      repertoire.deviation(&game("e4 c5 Nf3 d6 d4 cxd4"), true),
+      None
+    );
+  }
+
+  #[test]
+  fn test_export_round_trip() {
+    let mut repertoire = Repertoire::new();
+    repertoire.add_pgn(REPERTOIRE).unwrap();
+    let lines = repertoire.lines();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines.iter().map(GameData::history_len).max(), Some(5));
+
+    let text = repertoire.to_pgn();
+    assert_eq!(text.matches("[Event \"?\"]").count(), 3);
+    let mut again = Repertoire::new();
+    assert_eq!(again.add_pgn(&text), Ok(3));
+    assert_eq!(again.len(), repertoire.len());
+    assert_eq!(again.to_pgn(), text);
+  }
+
+  #[test]
+  fn test_cycles_end_lines() {
+    let mut repertoire = Repertoire::new();
+    repertoire.add_game(&game("Nf3 Nf6 Ng1 Ng8 e4"));
+    let lines = repertoire.lines();
+    // The knights return to the start, whose moves are already on the line
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.history_len() <= 5));
+  }
+
+  #[test]
+  fn test_bad_pgn_adds_nothing() {
+    let mut repertoire = Repertoire::new();
+    let text = "[Event \"ok\"]\n\n1. e4 *\n\n[Event \"bad\"]\n\n1. e5 *\n";
+    assert!(repertoire.add_pgn(text).is_err());
+    assert!(repertoire.is_empty());
+  }
+}
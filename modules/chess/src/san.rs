@@ -0,0 +1,517 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Standard algebraic notation (SAN), for single moves and whole games.
+//!
+//! [`write_san`] and [`parse_san`] convert one move, given the position it is played from.
+//! Writing names the moving piece's file, rank or both only when another legal move of the same
+//! piece type reaches the same square, and appends `+` or `#`. Parsing accepts what people and
+//! other programs actually write: `0-0` for `O-O`, a promotion piece with or without `=`, and
+//! trailing `+`, `#`, `!` and `?` marks, which are ignored.
+//!
+//! [`write_movetext`] and [`read_movetext`] do the same for the history of a [`GameData`], as
+//! numbered movetext like `1. e4 e5 2. Nf3`.
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use crate::{
+  errors::{MovetextError, SanError},
+  legal::attack::is_square_attacked,
+  model::{
+    gameboard::{GameBoard, PieceType},
+    gamedata::GameData,
+    piecemove::{PieceMove, PromotionType},
+  },
+  movegen::{MAX_MOVES, generate_moves},
+};
+
+/// Write `piece_move`, which must be legal in `board`, in SAN.
+pub fn write_san<W: Write>(board: &GameBoard, piece_move: &PieceMove, out: &mut W) -> fmt::Result {
+  let from = piece_move.from_square();
+  let to = piece_move.to_square();
+  let Some(piece) = board.get_piece(from) else {
+    return Err(fmt::Error);
+  };
+
+  if piece == PieceType::King
+    && let Some(rook) = board.castling_rook(piece_move)
+  {
+    out.write_str(if rook > from { "O-O" } else { "O-O-O" })?;
+  } else {
+    let capture = board.get_piece(to).is_some() || (piece == PieceType::Pawn && from % 8 != to % 8);
+    if piece == PieceType::Pawn {
+      if capture {
+        out.write_char(file_char(from))?;
+      }
+    } else {
+      out.write_char(piece_letter(piece))?;
+      write_disambiguation(board, piece, from, to, out)?;
+    }
+    if capture {
+      out.write_char('x')?;
+    }
+    write!(out, "{}{}", file_char(to), rank_char(to))?;
+    if let Some(promotion) = piece_move.promotion_type() {
+      write!(out, "={}", promotion_letter(promotion))?;
+    }
+  }
+
+  let mut child = *board;
+  if child.move_piece(piece_move).is_none() {
+    return Err(fmt::Error);
+  }
+  if child
+    .king_square(child.playing)
+    .is_some_and(|king| is_square_attacked(&child, king))
+  {
+    out.write_char(if child.has_legal_move() { '+' } else { '#' })?;
+  }
+  Ok(())
+}
+
+/// `piece_move` in SAN as a `String`.
+#[cfg(feature = "alloc")]
+pub fn to_san(board: &GameBoard, piece_move: &PieceMove) -> Result<String, fmt::Error> {
+  let mut san = String::new();
+  write_san(board, piece_move, &mut san)?;
+  Ok(san)
+}
+
+/// Find the legal move of `board` that `san` describes.
+pub fn parse_san(board: &GameBoard, san: &str) -> Result<PieceMove, SanError> {
+  let san = san.trim_end_matches(['+', '#', '!', '?']);
+  let (legal, count) = legal_moves(board);
+  let legal = &legal[..count];
+
+  let castling = match san {
+    "O-O" | "0-0" => Some(true),
+    "O-O-O" | "0-0-0" => Some(false),
+    _ => None,
+  };
+  if let Some(kingside) = castling {
+    let mut found = legal.iter().filter(|piece_move| {
+      board
+        .castling_rook(piece_move)
+        .is_some_and(|rook| (rook > piece_move.from_square()) == kingside)
+        && board.get_piece(piece_move.from_square()) == Some(PieceType::King)
+    });
+    return found.next().copied().ok_or(SanError::NoMatchingMove);
+  }
+
+  let pattern = SanPattern::parse(san).ok_or(SanError::InvalidSyntax)?;
+  let mut found = None;
+  for piece_move in legal {
+    if !pattern.matches(board, piece_move) {
+      continue;
+    }
+    if found.is_some() {
+      return Err(SanError::AmbiguousMove);
+    }
+    found = Some(*piece_move);
+  }
+  found.ok_or(SanError::NoMatchingMove)
+}
+
+/// Write the moves of `game` as numbered movetext, starting from its initial position. A game
+/// that starts with black to move opens with `1...`-style numbering.
+pub fn write_movetext<W: Write>(game: &GameData, out: &mut W) -> fmt::Result {
+  let mut board = game.initial_board;
+  for (index, piece_move) in game.moves[..game.history_len()].iter().enumerate() {
+    if index > 0 {
+      out.write_char(' ')?;
+    }
+    let fullmove = (game.initial_plies + index) / 2 + 1;
+    if board.playing {
+      write!(out, "{fullmove}. ")?;
+    } else if index == 0 {
+      write!(out, "{fullmove}... ")?;
+    }
+    write_san(&board, piece_move, out)?;
+    // A move that does not replay means the history was edited by hand; stop there
+    if board.move_piece(piece_move).is_none() {
+      break;
+    }
+  }
+  Ok(())
+}
+
+/// The moves of `game` as movetext in a `String`.
+#[cfg(feature = "alloc")]
+pub fn to_movetext(game: &GameData) -> String {
+  let mut text = String::new();
+  write_movetext(game, &mut text).expect("Writing to a String cannot fail");
+  text
+}
+
+/// Play the SAN moves of `text` on `game`. Move numbers (`12.`, `12...`) and a result token
+/// (`1-0`, `0-1`, `1/2-1/2`, `*`) are skipped. On error the moves before the bad one stay
+/// played.
+pub fn read_movetext(game: &mut GameData, text: &str) -> Result<(), MovetextError> {
+  let mut index = 0;
+  for token in text.split_whitespace() {
+    let token = strip_move_number(token);
+    if token.is_empty() || matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+      continue;
+    }
+    let piece_move =
+      parse_san(&game.board, token).map_err(|error| MovetextError { index, error })?;
+    game.play_move(&piece_move).ok_or(MovetextError {
+      index,
+      error: SanError::HistoryFull,
+    })?;
+    index += 1;
+  }
+  Ok(())
+}
+
+/// The parts of a non-castling SAN move.
+struct SanPattern {
+  piece: PieceType,
+  from_file: Option<u8>,
+  from_rank: Option<u8>,
+  to: u8,
+  promotion: Option<PromotionType>,
+}
+
+impl SanPattern {
+  fn parse(san: &str) -> Option<Self> {
+    let mut bytes = san.as_bytes();
+    let piece = match bytes.first()? {
+      b'N' => PieceType::Knight,
+      b'B' => PieceType::Bishop,
+      b'R' => PieceType::Rook,
+      b'Q' => PieceType::Queen,
+      b'K' => PieceType::King,
+      _ => PieceType::Pawn,
+    };
+    if piece != PieceType::Pawn {
+      bytes = &bytes[1..];
+    }
+
+    // Promotion, with or without the `=`
+    let mut promotion = None;
+    if let [rest @ .., last] = bytes
+      && let Some(promo) = promotion_from_letter(*last)
+    {
+      promotion = Some(promo);
+      bytes = rest.strip_suffix(b"=").unwrap_or(rest);
+    }
+    if promotion.is_some() && piece != PieceType::Pawn {
+      return None;
+    }
+
+    let [rest @ .., file, rank] = bytes else {
+      return None;
+    };
+    let to = square(*file, *rank)?;
+    let rest = rest.strip_suffix(b"x").unwrap_or(rest);
+    let (from_file, from_rank) = match rest {
+      [] => (None, None),
+      [b @ b'a'..=b'h'] => (Some(b - b'a'), None),
+      [b @ b'1'..=b'8'] => (None, Some(b - b'1')),
+      [file, rank] => {
+        let from = square(*file, *rank)?;
+        (Some(from % 8), Some(from / 8))
+      }
+      _ => return None,
+    };
+    // A pawn move names its file only when capturing
+    if piece == PieceType::Pawn && from_rank.is_some() {
+      return None;
+    }
+
+    Some(Self {
+      piece,
+      from_file,
+      from_rank,
+      to,
+      promotion,
+    })
+  }
+
+  fn matches(&self, board: &GameBoard, piece_move: &PieceMove) -> bool {
+    let from = piece_move.from_square();
+    board.get_piece(from) == Some(self.piece)
+      && piece_move.to_square() == self.to
+      && self.from_file.is_none_or(|file| from % 8 == file)
+      && self.from_rank.is_none_or(|rank| from / 8 == rank)
+      && piece_move.promotion_type() == self.promotion
+      && !(self.piece == PieceType::King && board.castling_rook(piece_move).is_some())
+  }
+}
+
+/// Name enough of `from` to tell the move apart from other legal moves of the same piece type to
+/// `to`: the file if that is unique, else the rank if that is, else both.
+fn write_disambiguation<W: Write>(
+  board: &GameBoard,
+  piece: PieceType,
+  from: u8,
+  to: u8,
+  out: &mut W,
+) -> fmt::Result {
+  let (legal, count) = legal_moves(board);
+  let (mut any, mut same_file, mut same_rank) = (false, false, false);
+  for other in legal[..count]
+    .iter()
+    .filter(|m| m.to_square() == to)
+    .map(|m| m.from_square())
+  {
+    if other == from || board.get_piece(other) != Some(piece) {
+      continue;
+    }
+    any = true;
+    same_file |= other % 8 == from % 8;
+    same_rank |= other / 8 == from / 8;
+  }
+  if !any {
+    return Ok(());
+  }
+  if !same_file {
+    out.write_char(file_char(from))
+  } else if !same_rank {
+    out.write_char(rank_char(from))
+  } else {
+    write!(out, "{}{}", file_char(from), rank_char(from))
+  }
+}
+
+/// The legal moves of `board`.
+fn legal_moves(board: &GameBoard) -> ([PieceMove; MAX_MOVES], usize) {
+  let (mut moves, count) = generate_moves(board);
+  let mut legal = 0;
+  for i in 0..count {
+    let mut child = *board;
+    if child.move_piece(&moves[i]).is_some() {
+      moves[legal] = moves[i];
+      legal += 1;
+    }
+  }
+  (moves, legal)
+}
+
+/// `token` without a leading move number such as `12.` or `12...`.
+fn strip_move_number(token: &str) -> &str {
+  let rest = token.trim_start_matches(|c: char| c.is_ascii_digit());
+  if rest.len() < token.len() && rest.starts_with('.') {
+    rest.trim_start_matches('.')
+  } else {
+    token
+  }
+}
+
+fn square(file: u8, rank: u8) -> Option<u8> {
+  match (file, rank) {
+    (b'a'..=b'h', b'1'..=b'8') => Some((rank - b'1') * 8 + (file - b'a')),
+    _ => None,
+  }
+}
+
+fn file_char(square: u8) -> char {
+  (b'a' + square % 8) as char
+}
+
+fn rank_char(square: u8) -> char {
+  (b'1' + square / 8) as char
+}
+
+fn piece_letter(piece: PieceType) -> char {
+  match piece {
+    PieceType::Pawn => 'P',
+    PieceType::Knight => 'N',
+    PieceType::Bishop => 'B',
+    PieceType::Rook => 'R',
+    PieceType::Queen => 'Q',
+    PieceType::King => 'K',
+  }
+}
+
+fn promotion_letter(promotion: PromotionType) -> char {
+  match promotion {
+    PromotionType::Queen => 'Q',
+    PromotionType::Rook => 'R',
+    PromotionType::Bishop => 'B',
+    PromotionType::Knight => 'N',
+  }
+}
+
+fn promotion_from_letter(letter: u8) -> Option<PromotionType> {
+  match letter {
+    b'Q' => Some(PromotionType::Queen),
+    b'R' => Some(PromotionType::Rook),
+    b'B' => Some(PromotionType::Bishop),
+    b'N' => Some(PromotionType::Knight),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constants::*;
+
+  const OPERA_GAME: &str = "1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 \
+    7. Qb3 Qe7 8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 \
+    14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8#";
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  fn san(board: &GameBoard, from: u8, to: u8) -> String {
+    san_of(board, &PieceMove::new(from, to, false, None))
+  }
+
+  fn san_of(board: &GameBoard, piece_move: &PieceMove) -> String {
+    let mut out = String::new();
+    write_san(board, piece_move, &mut out).unwrap();
+    out
+  }
+
+  /// Every legal move of `board` survives SAN and back.
+  fn assert_round_trips(board: &GameBoard) {
+    let (legal, count) = legal_moves(board);
+    for piece_move in &legal[..count] {
+      let text = san_of(board, piece_move);
+      let parsed = parse_san(board, &text).unwrap();
+      assert_eq!(
+        (
+          parsed.from_square(),
+          parsed.to_square(),
+          parsed.promotion_type()
+        ),
+        (
+          piece_move.from_square(),
+          piece_move.to_square(),
+          piece_move.promotion_type()
+        ),
+        "{text}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_game_round_trip() {
+    let mut game =
+      GameData::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    read_movetext(&mut game, &format!("{OPERA_GAME} 1-0")).unwrap();
+    assert_eq!(game.history_len(), 33);
+
+    let mut text = String::new();
+    write_movetext(&game, &mut text).unwrap();
+    assert_eq!(
+      text,
+      OPERA_GAME.split_whitespace().collect::<Vec<_>>().join(" ")
+    );
+
+    for ply in 0..game.history_len() {
+      assert_round_trips(&game.position_at(ply).unwrap());
+    }
+  }
+
+  #[test]
+  fn test_movetext_from_black() {
+    let mut game =
+      GameData::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    read_movetext(&mut game, "1... c5 2.Nf3 d6").unwrap();
+    let mut text = String::new();
+    write_movetext(&game, &mut text).unwrap();
+    assert_eq!(text, "1... c5 2. Nf3 d6");
+  }
+
+  #[test]
+  fn test_disambiguation() {
+    // Knights on the same file need the rank, on the same rank the file
+    let board = board_from_fen("4k3/8/8/6N1/8/8/8/4K1N1 w - - 0 1");
+    assert_eq!(san(&board, G1, F3), "N1f3");
+    assert_eq!(san(&board, G5, F3), "N5f3");
+    assert_eq!(san(&board, G1, E2), "Ne2");
+    assert_round_trips(&board);
+
+    let board = board_from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+    assert_eq!(san(&board, B1, D2), "Nbd2");
+    assert_eq!(san(&board, F1, D2), "Nfd2");
+    assert_round_trips(&board);
+
+    // Three queens reaching b2 need the full square for the corner one
+    let board = board_from_fen("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1");
+    assert_eq!(san(&board, A1, B2), "Qa1b2");
+    assert_eq!(san(&board, A3, B2), "Q3b2");
+    assert_eq!(san(&board, C1, B2), "Qcb2");
+    assert_eq!(parse_san(&board, "Qb2"), Err(SanError::AmbiguousMove));
+    assert_round_trips(&board);
+
+    // A pinned knight can't reach e2, so the other one needs no disambiguation
+    let board = board_from_fen("4k3/8/8/4b3/8/2N5/8/K5N1 w - - 0 1");
+    assert_eq!(san(&board, G1, E2), "Ne2");
+    assert_eq!(parse_san(&board, "Ne2").unwrap().from_square(), G1);
+  }
+
+  #[test]
+  fn test_special_moves() {
+    let board = board_from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1");
+    assert_eq!(san_of(&board, &PieceMove::new_castling(E1, G1)), "O-O");
+    assert_eq!(san_of(&board, &PieceMove::new_castling(E1, C1)), "O-O-O");
+    assert_eq!(san_of(&board, &PieceMove::new_en_passant(E5, D6)), "exd6");
+    assert_eq!(
+      san_of(
+        &board,
+        &PieceMove::new(B7, A8, true, Some(PromotionType::Queen))
+      ),
+      "bxa8=Q+"
+    );
+    assert_eq!(
+      san_of(
+        &board,
+        &PieceMove::new(B7, B8, false, Some(PromotionType::Knight))
+      ),
+      "b8=N"
+    );
+    assert_round_trips(&board);
+
+    assert_eq!(parse_san(&board, "0-0").unwrap().to_square(), G1);
+    assert_eq!(
+      parse_san(&board, "bxa8Q+").unwrap().promotion_type(),
+      Some(PromotionType::Queen)
+    );
+    assert_eq!(parse_san(&board, "b8"), Err(SanError::NoMatchingMove));
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    let board = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(parse_san(&board, ""), Err(SanError::InvalidSyntax));
+    assert_eq!(parse_san(&board, "Nz3"), Err(SanError::InvalidSyntax));
+    assert_eq!(parse_san(&board, "e2e4"), Err(SanError::InvalidSyntax));
+    assert_eq!(parse_san(&board, "Nf4"), Err(SanError::NoMatchingMove));
+    assert_eq!(parse_san(&board, "O-O"), Err(SanError::NoMatchingMove));
+    assert_eq!(parse_san(&board, "Nf3!?").unwrap().from_square(), G1);
+
+    let mut game =
+      GameData::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    assert_eq!(
+      read_movetext(&mut game, "1. e4 e5 2. Ke3"),
+      Err(MovetextError {
+        index: 2,
+        error: SanError::NoMatchingMove
+      })
+    );
+    assert_eq!(game.history_len(), 2);
+  }
+}
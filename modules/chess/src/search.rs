@@ -0,0 +1,304 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! A fixed-depth alpha-beta search that does not recurse, for targets with little stack.
+//!
+//! [`search_iterative`] walks the tree the way [`perft_iterative`](crate::perft::perft_iterative)
+//! does, keeping each ply's moves and bounds in a [`SearchStack`] the caller owns and can place
+//! in a `static`. The evaluation is the caller's: this is the tree walk of a search, without the
+//! move ordering, quiescence or transposition table of the engine's, which recurses and needs
+//! `std`.
+
+use crate::{
+  legal::pins::Pins,
+  model::{gameboard::GameBoard, piecemove::PieceMove},
+  movegen::{MAX_MOVES, generate_moves},
+};
+
+/// The score of checkmating at the root; a mate `n` plies away scores `MATE - n`.
+pub const MATE: i32 = 30_000;
+
+/// What [`search_iterative`] found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+  /// The best legal move at the root, `None` when there is none or the depth was 0.
+  pub best_move: Option<PieceMove>,
+  /// The score of the root for the side to move.
+  pub score: i32,
+  /// Legal moves played during the search.
+  pub nodes: u64,
+}
+
+/// One ply of a [`SearchStack`]: a position, its moves, how many have been tried, and the
+/// alpha-beta window and best score so far.
+#[derive(Clone, Copy)]
+struct Frame {
+  board: GameBoard,
+  moves: [PieceMove; MAX_MOVES],
+  count: usize,
+  next: usize,
+  alpha: i32,
+  beta: i32,
+  best: i32,
+  best_move: Option<PieceMove>,
+  legal: usize,
+}
+
+impl Frame {
+  const EMPTY: Frame = Frame {
+    board: GameBoard::START_POS,
+    moves: [PieceMove::NULL; MAX_MOVES],
+    count: 0,
+    next: 0,
+    alpha: 0,
+    beta: 0,
+    best: 0,
+    best_move: None,
+    legal: 0,
+  };
+
+  fn load(&mut self, board: GameBoard, alpha: i32, beta: i32) {
+    (self.moves, self.count) = generate_moves(&board);
+    self.board = board;
+    self.next = 0;
+    self.alpha = alpha;
+    self.beta = beta;
+    self.best = -MATE - 1;
+    self.best_move = None;
+    self.legal = 0;
+  }
+
+  /// Takes the score of `piece_move`, cutting the remaining moves off once it refutes the
+  /// window.
+  fn score(&mut self, piece_move: PieceMove, score: i32) {
+    if score > self.best {
+      self.best = score;
+      self.best_move = Some(piece_move);
+    }
+    self.alpha = self.alpha.max(score);
+    if self.alpha >= self.beta {
+      self.next = self.count;
+    }
+  }
+
+  /// The score of the finished frame `ply` plies from the root.
+  fn result(&self, ply: usize) -> i32 {
+    if self.legal > 0 {
+      self.best
+    } else {
+      no_move_score(&self.board, ply)
+    }
+  }
+}
+
+/// Storage for [`search_iterative`], one frame of about 950 bytes per ply, for searches up to
+/// `MAX_PLY` plies deep. `new` is `const`, so the stack can be a `static` rather than live on
+/// the call stack.
+pub struct SearchStack<const MAX_PLY: usize> {
+  frames: [Frame; MAX_PLY],
+}
+
+impl<const MAX_PLY: usize> SearchStack<MAX_PLY> {
+  pub const fn new() -> Self {
+    Self {
+      frames: [Frame::EMPTY; MAX_PLY],
+    }
+  }
+}
+
+impl<const MAX_PLY: usize> Default for SearchStack<MAX_PLY> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Searches `board` `depth` plies deep with alpha-beta, without recursion, using `stack` for
+/// the moves of each ply. `evaluate` scores the positions at the horizon for their side to move,
+/// and should stay within `MATE` either way; checkmate and stalemate are scored here, so mates
+/// within `depth` plies are always found. Returns `None` if `depth` is more than the `MAX_PLY`
+/// the stack holds.
+pub fn search_iterative<const MAX_PLY: usize>(
+  board: &GameBoard,
+  depth: u32,
+  stack: &mut SearchStack<MAX_PLY>,
+  mut evaluate: impl FnMut(&GameBoard) -> i32,
+) -> Option<SearchResult> {
+  let depth = depth as usize;
+  if depth > MAX_PLY {
+    return None;
+  }
+  if depth == 0 {
+    return Some(SearchResult {
+      best_move: None,
+      score: evaluate(board),
+      nodes: 0,
+    });
+  }
+
+  stack.frames[0].load(*board, -MATE - 1, MATE + 1);
+  let mut ply = 0;
+  let mut nodes = 0;
+  loop {
+    let frame = &mut stack.frames[ply];
+    if frame.next == frame.count {
+      let score = frame.result(ply);
+      if ply == 0 {
+        return Some(SearchResult {
+          best_move: frame.best_move,
+          score,
+          nodes,
+        });
+      }
+      ply -= 1;
+      let parent = &mut stack.frames[ply];
+      let piece_move = parent.moves[parent.next - 1];
+      parent.score(piece_move, -score);
+      continue;
+    }
+
+    let piece_move = frame.moves[frame.next];
+    frame.next += 1;
+    let mut child = frame.board;
+    if child.move_piece(&piece_move).is_none() {
+      continue;
+    }
+    frame.legal += 1;
+    nodes += 1;
+
+    if ply + 1 == depth {
+      let score = if child.has_legal_move() {
+        evaluate(&child)
+      } else {
+        no_move_score(&child, ply + 1)
+      };
+      frame.score(piece_move, -score);
+    } else {
+      let (alpha, beta) = (-frame.beta, -frame.alpha);
+      ply += 1;
+      stack.frames[ply].load(child, alpha, beta);
+    }
+  }
+}
+
+/// The score of `board` for its side to move when it has no legal move, `ply` plies from the
+/// root: mated or stalemated.
+fn no_move_score(board: &GameBoard, ply: usize) -> i32 {
+  if Pins::new(board).checkers().raw() != 0 {
+    -MATE + ply as i32
+  } else {
+    0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{constants::PIECE_VALUES, model::gamedata::GameData};
+
+  const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  /// Material for the side to move, in centipawns.
+  fn material(board: &GameBoard) -> i32 {
+    let side = |white| {
+      let own = board.pieces_of(white);
+      [
+        board.pawns,
+        board.knights,
+        board.bishops,
+        board.rooks,
+        board.queens,
+      ]
+      .into_iter()
+      .zip(PIECE_VALUES)
+      .map(|(pieces, value)| (pieces & own).count() as i32 * value)
+      .sum::<i32>()
+    };
+    side(board.playing) - side(!board.playing)
+  }
+
+  /// Plain recursive negamax without pruning, which alpha-beta must agree with.
+  fn negamax(board: &GameBoard, depth: usize, ply: usize) -> i32 {
+    if !board.has_legal_move() {
+      return no_move_score(board, ply);
+    }
+    if depth == 0 {
+      return material(board);
+    }
+    let (moves, count) = generate_moves(board);
+    let mut best = None;
+    for piece_move in &moves[..count] {
+      let mut child = *board;
+      if child.move_piece(piece_move).is_some() {
+        let score = -negamax(&child, depth - 1, ply + 1);
+        best = Some(best.map_or(score, |best: i32| best.max(score)));
+      }
+    }
+    best.unwrap_or_else(|| no_move_score(board, ply))
+  }
+
+  #[test]
+  fn test_search_matches_negamax() {
+    let mut stack = SearchStack::<3>::new();
+    for fen in [
+      "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+      KIWIPETE,
+      "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ] {
+      let board = board_from_fen(fen);
+      for depth in 0..=3 {
+        let result = search_iterative(&board, depth, &mut stack, material).unwrap();
+        assert_eq!(
+          result.score,
+          negamax(&board, depth as usize, 0),
+          "{fen} at depth {depth}"
+        );
+        assert_eq!(result.best_move.is_some(), depth > 0);
+      }
+    }
+    assert_eq!(
+      search_iterative(&GameBoard::START_POS, 4, &mut stack, material),
+      None
+    );
+  }
+
+  #[test]
+  fn test_search_finds_mates() {
+    let mut stack = SearchStack::<3>::new();
+
+    // Back rank mate in one, seen at depth 1
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let result = search_iterative(&board, 1, &mut stack, material).unwrap();
+    assert_eq!(result.score, MATE - 1);
+    assert_eq!(result.best_move, PieceMove::from_uci("a1a8", &board).ok());
+
+    // Mated and stalemated at the root
+    let mated = board_from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+    let result = search_iterative(&mated, 2, &mut stack, material).unwrap();
+    assert_eq!(
+      (result.best_move, result.score, result.nodes),
+      (None, -MATE, 0)
+    );
+    let stalemate = board_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+    let result = search_iterative(&stalemate, 2, &mut stack, material).unwrap();
+    assert_eq!((result.best_move, result.score), (None, 0));
+  }
+}
@@ -0,0 +1,140 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Instrumentation counters, enabled by the `stats` feature.
+//!
+//! With the feature, the crate counts how often each piece's move generator runs, how many
+//! attack queries ([`is_square_attacked`](crate::legal::attack::is_square_attacked)) are made
+//! and how many moves [`LegalChecker::is_move_legal`](crate::legal::checker::LegalChecker)
+//! rejects. Counters are process-wide relaxed atomics, so they cost one uncontended increment
+//! each and work without `std`; read them with [`snapshot`] and clear them with [`reset`].
+//! Without the feature the recording calls compile to nothing.
+
+#[cfg(feature = "stats")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::model::gameboard::PieceType;
+
+/// Something the counters track.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "stats"), allow(dead_code))]
+pub(crate) enum Event {
+  /// One call of the move generator for a piece type.
+  Movegen(PieceType),
+  /// One call of `is_square_attacked`.
+  AttackQuery,
+  /// One move rejected by `LegalChecker::is_move_legal`.
+  IllegalMove,
+}
+
+#[cfg(feature = "stats")]
+impl Event {
+  fn index(self) -> usize {
+    match self {
+      Event::Movegen(piece) => piece as usize,
+      Event::AttackQuery => 6,
+      Event::IllegalMove => 7,
+    }
+  }
+}
+
+#[cfg(feature = "stats")]
+static COUNTERS: [AtomicUsize; 8] = [const { AtomicUsize::new(0) }; 8];
+
+/// Count `event`.
+#[inline(always)]
+pub(crate) fn record(event: Event) {
+  #[cfg(feature = "stats")]
+  COUNTERS[event.index()].fetch_add(1, Ordering::Relaxed);
+  #[cfg(not(feature = "stats"))]
+  let _ = event;
+}
+
+/// The counters at one moment.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+  /// Move generator calls, indexed by [`PieceType`] (`PieceType::Pawn as usize` and so on).
+  pub movegen: [usize; 6],
+  /// Calls of `is_square_attacked`.
+  pub attack_queries: usize,
+  /// Moves rejected by `LegalChecker::is_move_legal`.
+  pub illegal_moves: usize,
+}
+
+#[cfg(feature = "stats")]
+impl Stats {
+  /// Move generator calls for `piece`.
+  pub fn movegen_calls(&self, piece: PieceType) -> usize {
+    self.movegen[piece as usize]
+  }
+
+  /// Move generator calls for every piece type together.
+  pub fn total_movegen_calls(&self) -> usize {
+    self.movegen.iter().sum()
+  }
+}
+
+/// Read every counter. Counters are read one at a time, so a snapshot taken while other threads
+/// generate moves may mix slightly different moments.
+#[cfg(feature = "stats")]
+pub fn snapshot() -> Stats {
+  let read = |index: usize| COUNTERS[index].load(Ordering::Relaxed);
+  Stats {
+    movegen: core::array::from_fn(read),
+    attack_queries: read(6),
+    illegal_moves: read(7),
+  }
+}
+
+/// Set every counter back to zero.
+#[cfg(feature = "stats")]
+pub fn reset() {
+  for counter in &COUNTERS {
+    counter.store(0, Ordering::Relaxed);
+  }
+}
+
+#[cfg(test)]
+#[cfg(feature = "stats")]
+mod tests {
+  use super::*;
+  use crate::constants::*;
+  use crate::legal::attack::is_square_attacked;
+  use crate::model::{gameboard::GameBoard, piecemove::PieceMove};
+  use crate::movegen::generate_moves;
+
+  // Other tests run in parallel and also bump the counters, so only lower bounds are checked
+  #[test]
+  fn test_counters_record_calls() {
+    let before = snapshot();
+    let board = GameBoard::START_POS;
+    generate_moves(&board);
+    generate_moves(&board);
+    is_square_attacked(&board, E4);
+    assert!(!board.is_move_legal(&PieceMove::new(E2, E5, false, None)));
+
+    let after = snapshot();
+    for piece in [PieceType::Pawn, PieceType::Knight, PieceType::King] {
+      assert!(after.movegen_calls(piece) >= before.movegen_calls(piece) + 2);
+    }
+    assert!(after.total_movegen_calls() >= before.total_movegen_calls() + 12);
+    assert!(after.attack_queries > before.attack_queries);
+    assert!(after.illegal_moves > before.illegal_moves);
+  }
+}
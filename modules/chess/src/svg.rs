@@ -0,0 +1,239 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Board diagrams as SVG.
+//!
+//! [`write_svg`] draws a position into any [`fmt::Write`], and [`to_svg`] (with `alloc`) returns
+//! it as a `String`, for documentation, web tools and server-side diagrams. Pieces are the solid
+//! Unicode chess glyphs, so no artwork is embedded; every element carries CSS classes
+//! (`square light`, `piece white knight`, `highlight`, `arrow`, `coordinate`) for a page to
+//! restyle, and [`SvgOptions::style`] adds a default stylesheet so the image also stands alone.
+
+use core::fmt::{self, Write};
+
+use crate::{
+  constants::is_light,
+  model::{
+    bitboard::BitBoard,
+    gameboard::{GameBoard, PieceType},
+  },
+};
+
+/// Side of one square, in SVG user units.
+pub const SQUARE_SIZE: u32 = 45;
+
+/// Used when [`SvgOptions::style`] is set.
+const DEFAULT_STYLE: &str = ".light{fill:#f0d9b5}.dark{fill:#b58863}\
+.piece{font-size:38px;text-anchor:middle;dominant-baseline:central}\
+.piece.white{fill:#fff;stroke:#000;stroke-width:1px}.piece.black{fill:#000}\
+.highlight{fill:#ff0;opacity:.4}.arrow{stroke:#15781b;stroke-width:8px;opacity:.8}\
+.coordinate{font-size:10px}";
+
+/// What to draw besides the pieces.
+#[derive(Debug, Clone, Copy)]
+pub struct SvgOptions<'a> {
+  /// Draw black's side at the bottom.
+  pub flipped: bool,
+  /// Label the files and ranks along the edges.
+  pub coordinates: bool,
+  /// Squares to shade, such as the last move or the squares a piece attacks.
+  pub highlights: BitBoard,
+  /// Arrows as `(from, to)` squares.
+  pub arrows: &'a [(u8, u8)],
+  /// Embed a default stylesheet. Turn it off when the page styles the classes itself.
+  pub style: bool,
+}
+
+impl Default for SvgOptions<'_> {
+  fn default() -> Self {
+    Self {
+      flipped: false,
+      coordinates: true,
+      highlights: BitBoard::EMPTY,
+      arrows: &[],
+      style: true,
+    }
+  }
+}
+
+/// Draw `board` as an SVG image.
+pub fn write_svg<W: Write>(board: &GameBoard, options: &SvgOptions, out: &mut W) -> fmt::Result {
+  let size = SQUARE_SIZE * 8;
+  write!(
+    out,
+    r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {size} {size}" width="{size}" height="{size}">"#
+  )?;
+  if options.style {
+    write!(out, "<style>{DEFAULT_STYLE}</style>")?;
+  }
+  if !options.arrows.is_empty() {
+    out.write_str(
+      r#"<defs><marker id="arrowhead" viewBox="0 0 4 4" refX="2" refY="2" markerWidth="2" markerHeight="2" orient="auto"><path d="M0,0L4,2L0,4z" class="arrowhead"/></marker></defs>"#,
+    )?;
+  }
+
+  for square in 0..64 {
+    let (x, y) = corner(square, options.flipped);
+    let shade = if is_light(square) { "light" } else { "dark" };
+    write!(
+      out,
+      r#"<rect x="{x}" y="{y}" width="{SQUARE_SIZE}" height="{SQUARE_SIZE}" class="square {shade}"/>"#
+    )?;
+  }
+  for square in options.highlights {
+    let (x, y) = corner(square, options.flipped);
+    write!(
+      out,
+      r#"<rect x="{x}" y="{y}" width="{SQUARE_SIZE}" height="{SQUARE_SIZE}" class="highlight"/>"#
+    )?;
+  }
+  if options.coordinates {
+    write_coordinates(options.flipped, out)?;
+  }
+
+  for square in board.combined() {
+    let Some((piece, white)) = board.piece_at(square) else {
+      continue;
+    };
+    let (x, y) = centre(square, options.flipped);
+    let (name, glyph) = match piece {
+      PieceType::Pawn => ("pawn", '♟'),
+      PieceType::Knight => ("knight", '♞'),
+      PieceType::Bishop => ("bishop", '♝'),
+      PieceType::Rook => ("rook", '♜'),
+      PieceType::Queen => ("queen", '♛'),
+      PieceType::King => ("king", '♚'),
+    };
+    let colour = if white { "white" } else { "black" };
+    write!(
+      out,
+      r#"<text x="{x}" y="{y}" class="piece {colour} {name}">{glyph}</text>"#
+    )?;
+  }
+
+  for &(from, to) in options
+    .arrows
+    .iter()
+    .filter(|&&(from, to)| from < 64 && to < 64)
+  {
+    let (x1, y1) = centre(from, options.flipped);
+    let (x2, y2) = centre(to, options.flipped);
+    write!(
+      out,
+      r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" class="arrow" marker-end="url(#arrowhead)"/>"#
+    )?;
+  }
+  out.write_str("</svg>")
+}
+
+/// [`write_svg`] into a new `String`.
+#[cfg(feature = "alloc")]
+pub fn to_svg(board: &GameBoard, options: &SvgOptions) -> alloc::string::String {
+  let mut svg = alloc::string::String::new();
+  write_svg(board, options, &mut svg).expect("Writing to a String cannot fail");
+  svg
+}
+
+/// Top-left corner of `square` in the image.
+fn corner(square: u8, flipped: bool) -> (u32, u32) {
+  let (file, rank) = (u32::from(square % 8), u32::from(square / 8));
+  let (column, row) = if flipped {
+    (7 - file, rank)
+  } else {
+    (file, 7 - rank)
+  };
+  (column * SQUARE_SIZE, row * SQUARE_SIZE)
+}
+
+fn centre(square: u8, flipped: bool) -> (u32, u32) {
+  let (x, y) = corner(square, flipped);
+  (x + SQUARE_SIZE / 2, y + SQUARE_SIZE / 2)
+}
+
+/// File letters along the bottom edge and rank numbers along the left, inside the squares.
+fn write_coordinates<W: Write>(flipped: bool, out: &mut W) -> fmt::Result {
+  for index in 0..8u8 {
+    let bottom = if flipped { 63 - index } else { index };
+    let (x, y) = corner(bottom, flipped);
+    let file = char::from(b'a' + bottom % 8);
+    write!(
+      out,
+      r#"<text x="{}" y="{}" class="coordinate">{file}</text>"#,
+      x + SQUARE_SIZE - 8,
+      y + SQUARE_SIZE - 3
+    )?;
+
+    let left = if flipped { 63 - index * 8 } else { index * 8 };
+    let (x, y) = corner(left, flipped);
+    let rank = left / 8 + 1;
+    write!(
+      out,
+      r#"<text x="{}" y="{}" class="coordinate">{rank}</text>"#,
+      x + 2,
+      y + 10
+    )?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::gamedata::GameData};
+
+  #[test]
+  fn test_start_position() {
+    let svg = to_svg(&GameBoard::START_POS, &SvgOptions::default());
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>"));
+    assert_eq!(svg.matches("class=\"square ").count(), 64);
+    assert_eq!(svg.matches("class=\"square light\"").count(), 32);
+    assert_eq!(svg.matches("class=\"piece ").count(), 32);
+    assert_eq!(svg.matches("class=\"piece white pawn\"").count(), 8);
+    assert_eq!(svg.matches("class=\"coordinate\"").count(), 16);
+    assert!(svg.contains("<style>"));
+    // a1 is dark and sits bottom left
+    assert!(svg.contains(r#"<rect x="0" y="315" width="45" height="45" class="square dark"/>"#));
+    assert!(svg.contains(r#"<text x="22" y="337" class="piece white rook">♜</text>"#));
+  }
+
+  #[test]
+  fn test_options() {
+    let board = GameData::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1")
+      .unwrap()
+      .board;
+    let options = SvgOptions {
+      flipped: true,
+      coordinates: false,
+      highlights: BitBoard::new(1 << E1 | 1 << E2),
+      arrows: &[(E1, E2), (E8, 64)],
+      style: false,
+    };
+    let svg = to_svg(&board, &options);
+    assert!(!svg.contains("<style>"));
+    assert!(!svg.contains("coordinate"));
+    assert_eq!(svg.matches("class=\"highlight\"").count(), 2);
+    // Off-board arrows are dropped
+    assert_eq!(svg.matches("class=\"arrow\"").count(), 1);
+    assert!(svg.contains("marker id=\"arrowhead\""));
+    // Flipped, e1 is near the top
+    assert!(svg.contains(r#"<text x="157" y="22" class="piece white king">♚</text>"#));
+    assert!(svg.contains(r#"<line x1="157" y1="22" x2="157" y2="67""#));
+  }
+}
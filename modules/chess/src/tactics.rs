@@ -0,0 +1,390 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Detection of basic tactical motifs.
+//!
+//! [`find_motifs`] scans a position for the patterns one side can exploit: knight forks, pins,
+//! skewers and hanging pieces. Findings are structured, so puzzle generators, teaching tools and
+//! evaluation terms can use them without parsing text. Motifs describe the position as it
+//! stands; nothing is searched, so a "hanging" piece may still be tactically defended.
+//!
+//! [`solve_mate`] is the one exception: an exhaustive search for a forced checkmate, for
+//! validating puzzles and checking the engine's mate scores against a proof.
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::{
+  legal::attack::attacks_from,
+  model::{
+    bitboard::BitBoard,
+    gameboard::{GameBoard, PieceType},
+    rays::{DIR_OFFSETS, RAYS},
+  },
+};
+#[cfg(feature = "alloc")]
+use crate::{legal::attack::is_square_attacked, model::piecemove::PieceMove};
+
+/// A tactical pattern, from the point of view of the side that can exploit it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motif {
+  /// A knight on `knight` attacks two or more enemy pieces of knight value or more, including the
+  /// king.
+  KnightFork { knight: u8, targets: u64 },
+  /// The enemy piece on `pinned` cannot move off the line from `pinner` without exposing the more
+  /// valuable piece on `target`. The pin is absolute when `target` is the king, since then moving
+  /// is illegal rather than merely bad.
+  Pin {
+    pinner: u8,
+    pinned: u8,
+    target: u8,
+    absolute: bool,
+  },
+  /// The enemy piece on `front` is attacked by `attacker` and, once it moves, the less valuable
+  /// piece on `behind` is exposed.
+  Skewer { attacker: u8, front: u8, behind: u8 },
+  /// The enemy piece on `square` is attacked and not defended at all.
+  Hanging { square: u8 },
+}
+
+/// Report every motif that `white`'s side can exploit in `board` to `visit`, grouped by kind:
+/// forks, then pins and skewers, then hanging pieces.
+pub fn find_motifs(board: &GameBoard, white: bool, mut visit: impl FnMut(Motif)) {
+  let own = board.pieces_of(white).raw();
+  let enemy = board.pieces_of(!white).raw();
+
+  // Knight forks
+  let mut knights = board.knights.raw() & own;
+  let valuable = enemy & !board.pawns.raw();
+  while knights != 0 {
+    let knight = knights.trailing_zeros() as u8;
+    knights &= knights - 1;
+    let targets = attacks_from(board, knight) & valuable;
+    if targets.count_ones() >= 2 {
+      visit(Motif::KnightFork { knight, targets });
+    }
+  }
+
+  // Pins and skewers: look along each line from our sliders for two enemy pieces in a row
+  let mut sliders = (board.bishops | board.rooks | board.queens).raw() & own;
+  while sliders != 0 {
+    let slider = sliders.trailing_zeros() as u8;
+    sliders &= sliders - 1;
+    let directions = match board.get_piece(slider) {
+      Some(PieceType::Rook) => 0..4,
+      Some(PieceType::Bishop) => 4..8,
+      _ => 0..8,
+    };
+    for idx in directions {
+      let Some(front) = first_piece(board, slider, idx) else {
+        continue;
+      };
+      let Some(behind) = first_piece(board, front, idx) else {
+        continue;
+      };
+      if !is_set(enemy, front) || !is_set(enemy, behind) {
+        continue;
+      }
+      let front_value = value(board, front);
+      let behind_value = value(board, behind);
+      if behind_value > front_value {
+        visit(Motif::Pin {
+          pinner: slider,
+          pinned: front,
+          target: behind,
+          absolute: board.get_piece(behind) == Some(PieceType::King),
+        });
+      } else if front_value > behind_value {
+        visit(Motif::Skewer {
+          attacker: slider,
+          front,
+          behind,
+        });
+      }
+    }
+  }
+
+  // Hanging pieces
+  let attacked = attacked_by(board, own);
+  let defended = attacked_by(board, enemy);
+  let mut hanging = enemy & attacked & !defended & !board.kings.raw();
+  while hanging != 0 {
+    let square = hanging.trailing_zeros() as u8;
+    hanging &= hanging - 1;
+    visit(Motif::Hanging { square });
+  }
+}
+
+/// [`find_motifs`] collected into a `Vec`.
+#[cfg(feature = "alloc")]
+pub fn motifs(board: &GameBoard, white: bool) -> Vec<Motif> {
+  let mut found = Vec::new();
+  find_motifs(board, white, |motif| found.push(motif));
+  found
+}
+
+/// The shortest forced checkmate for the side to move in `board` within `max_plies` plies, as the
+/// moves of a main line ending with the mate, or `None` if there is none.
+///
+/// Every reply of the defender is tried, so a returned line is a proof that the mate is forced;
+/// along it the defender plays the reply that holds out longest. Shorter mates are searched
+/// first, and positions already decided at a depth are remembered by their
+/// [`zobrist`](crate::zobrist) hash, so a transposition is only searched once. Draws by repetition
+/// or the fifty-move rule are not considered. The search is exponential in `max_plies`; puzzles
+/// up to mate in three or four are quick.
+#[cfg(feature = "alloc")]
+pub fn solve_mate(board: &GameBoard, max_plies: u8) -> Option<Vec<PieceMove>> {
+  let mut solver = MateSolver::default();
+  let mut plies = (1..=max_plies)
+    .step_by(2)
+    .find(|&plies| solver.mates(board, plies))?;
+
+  let mut line = Vec::new();
+  let mut board = *board;
+  loop {
+    let (mating, child) = board.legal_moves_iter().find_map(|piece_move| {
+      let mut child = board;
+      child.move_piece(&piece_move)?;
+      solver.forced(&child, plies).then_some((piece_move, child))
+    })?;
+    line.push(mating);
+    if !child.has_legal_move() {
+      return Some(line);
+    }
+
+    // The reply after which the mate takes longest
+    let (reply, next, rest) = child
+      .legal_moves_iter()
+      .filter_map(|reply| {
+        let mut next = child;
+        next.move_piece(&reply)?;
+        let rest = (1..=plies - 2)
+          .step_by(2)
+          .find(|&rest| solver.mates(&next, rest))?;
+        Some((reply, next, rest))
+      })
+      .max_by_key(|&(_, _, rest)| rest)?;
+    line.push(reply);
+    board = next;
+    plies = rest;
+  }
+}
+
+/// The proof search behind [`solve_mate`], with its table of decided positions.
+#[cfg(feature = "alloc")]
+#[derive(Default)]
+struct MateSolver {
+  /// Whether the side to move mates within the plies, by position hash and plies.
+  decided: BTreeMap<(u64, u8), bool>,
+}
+
+#[cfg(feature = "alloc")]
+impl MateSolver {
+  /// Whether the side to move in `board` mates within `plies` plies against any defence.
+  fn mates(&mut self, board: &GameBoard, plies: u8) -> bool {
+    let key = (board.zobrist_key(), plies);
+    if let Some(&known) = self.decided.get(&key) {
+      return known;
+    }
+    let found = board.legal_moves_iter().any(|piece_move| {
+      let mut child = *board;
+      child.move_piece(&piece_move).is_some() && self.forced(&child, plies)
+    });
+    self.decided.insert(key, found);
+    found
+  }
+
+  /// Whether the defender to move in `child`, just after the attacker's move, is mated within
+  /// the rest of `plies`, that move included.
+  fn forced(&mut self, child: &GameBoard, plies: u8) -> bool {
+    if !child.has_legal_move() {
+      return child
+        .king_square(child.playing)
+        .is_some_and(|king| is_square_attacked(child, king));
+    }
+    plies >= 3
+      && child.legal_moves_iter().all(|reply| {
+        let mut next = *child;
+        next.move_piece(&reply).is_some() && self.mates(&next, plies - 2)
+      })
+  }
+}
+
+fn is_set(bits: u64, square: u8) -> bool {
+  BitBoard::new(bits).get_bit(square).unwrap_or(false)
+}
+
+/// The first occupied square from `square` along direction `idx`, not counting `square` itself.
+fn first_piece(board: &GameBoard, square: u8, idx: usize) -> Option<u8> {
+  let blockers = RAYS[square as usize][idx] & board.combined().raw();
+  match blockers {
+    0 => None,
+    _ if DIR_OFFSETS[idx] > 0 => Some(blockers.trailing_zeros() as u8),
+    _ => Some(63 - blockers.leading_zeros() as u8),
+  }
+}
+
+/// Squares attacked by any of `pieces`.
+fn attacked_by(board: &GameBoard, mut pieces: u64) -> u64 {
+  let mut attacked = 0;
+  while pieces != 0 {
+    let square = pieces.trailing_zeros() as u8;
+    pieces &= pieces - 1;
+    attacked |= attacks_from(board, square);
+  }
+  attacked
+}
+
+/// Rough material value used to tell pins from skewers; the king outranks everything.
+fn value(board: &GameBoard, square: u8) -> u32 {
+  match board.get_piece(square) {
+    Some(PieceType::Pawn) => 1,
+    Some(PieceType::Knight | PieceType::Bishop) => 3,
+    Some(PieceType::Rook) => 5,
+    Some(PieceType::Queen) => 9,
+    Some(PieceType::King) => 100,
+    None => 0,
+  }
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::gamedata::GameData};
+
+  fn board_from_fen(fen: &str) -> GameBoard {
+    GameData::from_fen(fen).unwrap().board
+  }
+
+  fn bits(squares: &[u8]) -> u64 {
+    squares.iter().fold(0, |acc, &sq| acc | (1 << sq))
+  }
+
+  #[test]
+  fn test_knight_fork() {
+    // Nc7 forks the king on e8 and the rook on a8; the queen on d8 is out of its reach
+    let board = board_from_fen("r2qk3/2N5/8/8/8/8/8/4K3 w - - 0 1");
+    let found = motifs(&board, true);
+    assert!(found.contains(&Motif::KnightFork {
+      knight: C7,
+      targets: bits(&[A8, E8]),
+    }));
+
+    // Two pawns are not a fork, though both are hanging
+    let board = board_from_fen("4k3/8/3p1p2/8/4N3/8/8/4K3 w - - 0 1");
+    let found = motifs(&board, true);
+    assert!(!found.iter().any(|m| matches!(m, Motif::KnightFork { .. })));
+    assert!(found.contains(&Motif::Hanging { square: D6 }));
+  }
+
+  #[test]
+  fn test_pins() {
+    // The rook on e1 pins the knight on e5 to the king. No white piece lines up with the knight
+    // on c6, so it is not pinned
+    let board = board_from_fen("r3k3/8/2n5/4n3/8/8/8/B3R1K1 w - - 0 1");
+    let found = motifs(&board, true);
+    assert!(found.contains(&Motif::Pin {
+      pinner: E1,
+      pinned: E5,
+      target: E8,
+      absolute: true,
+    }));
+    assert!(
+      !found
+        .iter()
+        .any(|m| matches!(m, Motif::Pin { pinned: C6, .. }))
+    );
+
+    // The bishop on d1 pins the knight on c2 to the queen on a4, a relative pin: the knight may
+    // still legally move
+    let board = board_from_fen("4k3/8/8/8/q7/8/2n5/3BK3 w - - 0 1");
+    let found = motifs(&board, true);
+    assert!(found.contains(&Motif::Pin {
+      pinner: D1,
+      pinned: C2,
+      target: A4,
+      absolute: false,
+    }));
+  }
+
+  #[test]
+  fn test_skewer() {
+    // The bishop checks the king, which must step aside and give up the rook
+    let board = board_from_fen("8/8/5r2/4k3/8/8/1B6/6K1 w - - 0 1");
+    let found = motifs(&board, true);
+    assert!(found.contains(&Motif::Skewer {
+      attacker: B2,
+      front: E5,
+      behind: F6,
+    }));
+  }
+
+  #[test]
+  fn test_hanging() {
+    // The knight on d5 is attacked by the pawn and undefended; the rook on h8 is defended
+    let board = board_from_fen("6kr/7p/8/3n4/4P3/8/8/4K2R w - - 0 1");
+    let found = motifs(&board, true);
+    assert!(found.contains(&Motif::Hanging { square: D5 }));
+    assert!(!found.contains(&Motif::Hanging { square: H8 }));
+    assert!(!found.contains(&Motif::Hanging { square: G8 }));
+  }
+
+  #[test]
+  fn test_sides_are_separate() {
+    let board = board_from_fen("r2qk3/2N5/8/8/8/8/8/4K3 w - - 0 1");
+    assert!(
+      !motifs(&board, false)
+        .iter()
+        .any(|m| matches!(m, Motif::KnightFork { .. }))
+    );
+  }
+
+  #[test]
+  fn test_solve_mate() {
+    // Back rank mate in one
+    let board = board_from_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1");
+    let line = solve_mate(&board, 5).unwrap();
+    assert_eq!(line, [PieceMove::new(D1, D8, false, None)]);
+
+    // Morphy's mate in two: 1. Ra6 bxa6 2. b7#, or 1... Bc7/Bd6/... 2. Rxa7#
+    let board = board_from_fen("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1");
+    assert_eq!(solve_mate(&board, 2), None);
+    let line = solve_mate(&board, 3).unwrap();
+    assert_eq!(line.len(), 3);
+    assert_eq!(line[0], PieceMove::new(A1, A6, false, None));
+    let mut end = board;
+    for piece_move in &line {
+      end.move_piece(piece_move).unwrap();
+    }
+    assert!(!end.has_legal_move());
+    assert!(is_square_attacked(&end, A8));
+  }
+
+  #[test]
+  fn test_solve_mate_needs_a_mate() {
+    // Stalemated or already mated: no move to play
+    let board = board_from_fen("k7/8/1Q6/8/8/8/8/7K b - - 0 1");
+    assert_eq!(solve_mate(&board, 3), None);
+    let board = board_from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1");
+    assert_eq!(solve_mate(&board, 3), None);
+    // Bare kings
+    let board = board_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(solve_mate(&board, 5), None);
+  }
+}
@@ -0,0 +1,246 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! Zobrist hashing of positions.
+//!
+//! The keys are fixed: they are drawn at compile time from SplitMix64 seeded with [`SEED`], in
+//! this order:
+//!
+//! 1. [`PIECES`]: white then black, each piece type in [`PieceType`] order (pawn to king), each
+//!    square from a1 to h8;
+//! 2. [`CASTLING`]: the `K`, `Q`, `k` and `q` rights;
+//! 3. [`EN_PASSANT`]: the en passant file, a to h;
+//! 4. [`SIDE`]: black to move.
+//!
+//! The seed, the generator and the order are part of the API. Hashes written to disk, such as
+//! opening book or hash table entries, stay valid across versions of the crate; changing any key
+//! is a breaking change, and the tests pin a few of them.
+//!
+//! Boards keep the key of their pieces up to date as they move, so
+//! [`GameBoard::zobrist_key`] is cheap; [`hash`] computes the same hash from scratch. Code that
+//! edits a board's piece fields directly must call [`GameBoard::refresh_derived`] before asking
+//! for its key.
+//!
+//! Both agree with [`GameBoard`]'s `Eq`: an en passant square only counts when the side to
+//! move can capture onto it, the rule the FEN writer follows by default
+//! ([`EnPassantFen::Legal`](crate::model::gameboard::EnPassantFen::Legal)), and castling rights
+//! count by side and wing, not by rook square.
+
+use crate::model::{
+  gameboard::{GameBoard, PieceType},
+  rays::CacheAligned,
+};
+
+/// Seed of the key generator: "lumifox" in ASCII.
+pub const SEED: u64 = 0x006C_756D_6966_6F78;
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+  let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+  let mut z = state;
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+  (state, z ^ (z >> 31))
+}
+
+/// Number of keys: 768 piece keys, 4 castling keys, 8 en passant keys and the side key.
+const KEY_COUNT: usize = 2 * 6 * 64 + 4 + 8 + 1;
+
+const KEYS: [u64; KEY_COUNT] = {
+  let mut keys = [0; KEY_COUNT];
+  let mut state = SEED;
+  let mut i = 0;
+  while i < KEY_COUNT {
+    let (next, key) = splitmix64(state);
+    state = next;
+    keys[i] = key;
+    i += 1;
+  }
+  keys
+};
+
+/// Key of a piece on a square, indexed by colour (0 white, 1 black), [`PieceType`] and square.
+pub static PIECES: CacheAligned<[[[u64; 64]; 6]; 2]> = CacheAligned({
+  let mut pieces = [[[0; 64]; 6]; 2];
+  let mut i = 0;
+  while i < 2 * 6 * 64 {
+    pieces[i / 384][(i / 64) % 6][i % 64] = KEYS[i];
+    i += 1;
+  }
+  pieces
+});
+
+/// Key of each castling right, in `KQkq` order (the bit order of
+/// [`CastlingRights::bits`](crate::model::castling::CastlingRights::bits)).
+pub static CASTLING: CacheAligned<[u64; 4]> =
+  CacheAligned([KEYS[768], KEYS[769], KEYS[770], KEYS[771]]);
+
+/// Key of the en passant file, a to h.
+pub static EN_PASSANT: CacheAligned<[u64; 8]> = CacheAligned({
+  let mut files = [0; 8];
+  let mut i = 0;
+  while i < 8 {
+    files[i] = KEYS[772 + i];
+    i += 1;
+  }
+  files
+});
+
+/// Key toggled when black is to move.
+pub const SIDE: u64 = KEYS[780];
+
+/// The key of `piece` of the given colour on `square`.
+#[inline]
+pub const fn piece_key(piece: PieceType, white: bool, square: u8) -> u64 {
+  PIECES.0[!white as usize][piece as usize][square as usize % 64]
+}
+
+/// The Zobrist hash of `board`, computed from scratch. [`GameBoard::zobrist_key`] gives the same
+/// hash without visiting every piece.
+pub fn hash(board: &GameBoard) -> u64 {
+  let mut key = state_key(board);
+  for square in board.combined() {
+    if let Some((piece, white)) = board.piece_at(square) {
+      key ^= piece_key(piece, white, square);
+    }
+  }
+  key
+}
+
+/// The part of the hash that is not about pieces: castling rights, en passant file and side to
+/// move.
+pub(crate) fn state_key(board: &GameBoard) -> u64 {
+  let mut key = 0;
+  let rights = board.castling.bits();
+  for (index, right) in CASTLING.iter().enumerate() {
+    if rights & (1 << index) != 0 {
+      key ^= right;
+    }
+  }
+  if let Some(target) = board.en_passant_capture_square() {
+    key ^= EN_PASSANT[usize::from(target % 8)];
+  }
+  if !board.playing {
+    key ^= SIDE;
+  }
+  key
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{constants::*, model::gamedata::GameData};
+
+  #[test]
+  fn test_keys_are_stable() {
+    // Pinned: changing these breaks every stored hash
+    assert_eq!(PIECES[0][0][0], 0x01AE_5452_029C_ABC0);
+    assert_eq!(CASTLING[0], 0x8A69_96C4_7178_ED52);
+    assert_eq!(EN_PASSANT[7], 0xE329_B39B_816E_F877);
+    assert_eq!(SIDE, 0x9B5E_8B60_210C_6F20);
+    assert_eq!(hash(&GameBoard::START_POS), 0xA294_68DE_432D_D727);
+  }
+
+  #[test]
+  fn test_keys_are_distinct() {
+    let mut keys = KEYS.to_vec();
+    keys.sort_unstable();
+    keys.dedup();
+    assert_eq!(keys.len(), KEY_COUNT);
+    assert!(!keys.contains(&0));
+  }
+
+  #[test]
+  fn test_hash_follows_equality() {
+    let mut game = GameData::START_POS;
+    let mut hashes = vec![hash(&game.board)];
+    for mv in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+      game.play_move(&mv.parse().unwrap()).unwrap();
+      hashes.push(hash(&game.board));
+    }
+    assert_eq!(hashes[4], hashes[0]);
+    assert_ne!(hashes[1], hashes[0]);
+    assert_ne!(hashes[2], hashes[1]);
+
+    // An en passant square nobody can use does not change the hash
+    let mut game = GameData::START_POS;
+    game.play_move(&"e2e4".parse().unwrap()).unwrap();
+    let plain =
+      GameData::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_eq!(hash(&game.board), hash(&plain.board));
+
+    let with_capture =
+      GameData::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    let without =
+      GameData::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_eq!(
+      hash(&with_capture.board) ^ hash(&without.board),
+      EN_PASSANT[4]
+    );
+    assert_eq!(
+      piece_key(PieceType::Pawn, true, E4),
+      PIECES[0][0][E4 as usize]
+    );
+  }
+
+  /// Walk the tree below `board` with make/unmake, checking the kept key against a fresh hash.
+  fn check_incremental(board: &mut GameBoard, depth: u32) {
+    assert_eq!(board.zobrist_key(), hash(board), "{board}");
+    if depth == 0 {
+      return;
+    }
+    let key = board.zobrist_key();
+    let moves: Vec<_> = board.legal_moves_iter().collect();
+    for piece_move in moves {
+      let undo = board.make_move(&piece_move).unwrap();
+      check_incremental(board, depth - 1);
+      board.unmake_move(undo);
+      assert_eq!(board.zobrist_key(), key);
+    }
+  }
+
+  #[test]
+  fn test_incremental_key() {
+    assert_eq!(
+      GameBoard::START_POS.zobrist_key(),
+      hash(&GameBoard::START_POS)
+    );
+    assert_eq!(GameBoard::new().zobrist_key(), 0);
+    for (fen, depth) in [
+      (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        2,
+      ),
+      ("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", 2),
+      (
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        2,
+      ),
+      ("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1", 2),
+    ] {
+      let mut board = GameData::from_fen(fen).unwrap().board;
+      check_incremental(&mut board, depth);
+    }
+
+    // Edits behind the board's back are picked up by a refresh
+    let mut board = GameBoard::START_POS;
+    board.pawns.unset_bit(E2);
+    board.queens.set_bit(E2);
+    board.refresh_derived();
+    assert_eq!(board.zobrist_key(), hash(&board));
+  }
+}
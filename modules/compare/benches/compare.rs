@@ -0,0 +1,97 @@
+//! Legal move generation and perft against the `shakmaty` and `chess` crates on the same
+//! positions. Needs a nightly toolchain:
+//!
+//! ```sh
+//! cargo +nightly bench --package lumifox_compare
+//! ```
+//!
+//! Each bench checks its node count against the others' before timing, so the numbers compare
+//! the same work.
+
+#![feature(test)]
+
+extern crate test;
+
+use std::str::FromStr;
+
+use test::Bencher;
+
+use lumifox_chess::{model::gamedata::GameData, movegen::generate_moves, perft::perft};
+use shakmaty::{CastlingMode, Chess, Position, fen::Fen};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const KIWIPETE_MOVES: usize = 48;
+const KIWIPETE_PERFT_3: u64 = 97_862;
+
+fn lumifox(fen: &str) -> GameData {
+  GameData::from_fen(fen).unwrap()
+}
+
+fn shakmaty(fen: &str) -> Chess {
+  Fen::from_str(fen)
+    .unwrap()
+    .into_position(CastlingMode::Standard)
+    .unwrap()
+}
+
+fn chess(fen: &str) -> chess::Board {
+  chess::Board::from_str(fen).unwrap()
+}
+
+fn lumifox_legal_moves(game: &GameData) -> usize {
+  let (moves, count) = generate_moves(&game.board);
+  moves[..count]
+    .iter()
+    .filter(|piece_move| game.board.is_move_legal(piece_move))
+    .count()
+}
+
+#[bench]
+fn bench_movegen_lumifox(b: &mut Bencher) {
+  let game = lumifox(KIWIPETE);
+  assert_eq!(lumifox_legal_moves(&game), KIWIPETE_MOVES);
+  b.iter(|| test::black_box(lumifox_legal_moves(test::black_box(&game))));
+}
+
+#[bench]
+fn bench_movegen_shakmaty(b: &mut Bencher) {
+  let position = shakmaty(KIWIPETE);
+  assert_eq!(position.legal_moves().len(), KIWIPETE_MOVES);
+  b.iter(|| test::black_box(test::black_box(&position).legal_moves().len()));
+}
+
+#[bench]
+fn bench_movegen_chess(b: &mut Bencher) {
+  let board = chess(KIWIPETE);
+  assert_eq!(chess::MoveGen::new_legal(&board).len(), KIWIPETE_MOVES);
+  b.iter(|| test::black_box(chess::MoveGen::new_legal(test::black_box(&board)).len()));
+}
+
+#[bench]
+fn bench_perft_lumifox(b: &mut Bencher) {
+  let game = lumifox(KIWIPETE);
+  assert_eq!(perft(&game.board, 3), KIWIPETE_PERFT_3);
+  b.iter(|| test::black_box(perft(test::black_box(&game.board), 3)));
+}
+
+#[bench]
+fn bench_perft_shakmaty(b: &mut Bencher) {
+  let position = shakmaty(KIWIPETE);
+  assert_eq!(shakmaty::perft(&position, 3), KIWIPETE_PERFT_3);
+  b.iter(|| test::black_box(shakmaty::perft(test::black_box(&position), 3)));
+}
+
+#[bench]
+fn bench_perft_chess(b: &mut Bencher) {
+  let board = chess(KIWIPETE);
+  assert_eq!(
+    chess::MoveGen::movegen_perft_test(&board, 3) as u64,
+    KIWIPETE_PERFT_3
+  );
+  b.iter(|| {
+    test::black_box(chess::MoveGen::movegen_perft_test(
+      test::black_box(&board),
+      3,
+    ))
+  });
+}
@@ -0,0 +1,178 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! A toy engine made only from the public APIs of the workspace's library crates.
+//!
+//! [`ToyEngine`] answers UCI commands parsed by `lumifox_uci` and searches the moves
+//! `lumifox_chess` generates with a plain material negamax. It is not meant to play well: the
+//! tests in `tests/` drive it with positions written with the `lumifox_chess_proc` macros, so a
+//! change in any of the three crates that breaks how they fit together fails here even when each
+//! crate's own tests pass.
+
+use lumifox_chess::{
+  constants::piece_value,
+  legal::attack::is_square_attacked,
+  model::{
+    gameboard::GameBoard,
+    gamedata::GameData,
+    piecemove::PieceMove,
+  },
+};
+use lumifox_uci::{EngineToGuiCommand, GuiToEngineCommand, PositionType};
+
+/// Depth searched when `go` gives none.
+pub const DEFAULT_DEPTH: u32 = 2;
+
+/// Score of being checkmated at the root; mates further away score closer to zero.
+pub const MATE_SCORE: i32 = 100_000;
+
+/// Holds the game the GUI set up and answers one command at a time.
+#[derive(Debug, Clone)]
+pub struct ToyEngine {
+  game: GameData,
+}
+
+impl Default for ToyEngine {
+  fn default() -> Self {
+    Self {
+      game: GameData::START_POS,
+    }
+  }
+}
+
+impl ToyEngine {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The game the next search starts from.
+  pub fn game(&self) -> &GameData {
+    &self.game
+  }
+
+  /// Parse `line` as a GUI command and handle it. Lines that do not parse are ignored, as the
+  /// protocol asks, and get no reply.
+  pub fn handle_line(&mut self, line: &str) -> Vec<EngineToGuiCommand> {
+    match line.parse() {
+      Ok(command) => self.handle(command),
+      Err(_) => Vec::new(),
+    }
+  }
+
+  /// The replies to `command`.
+  pub fn handle(&mut self, command: GuiToEngineCommand) -> Vec<EngineToGuiCommand> {
+    match command {
+      GuiToEngineCommand::Uci => vec![
+        EngineToGuiCommand::Id {
+          name: Some("Lumifox toy engine".to_string()),
+          author: Some("Clifton Toaster Reid".to_string()),
+        },
+        EngineToGuiCommand::UciOk,
+      ],
+      GuiToEngineCommand::IsReady => vec![EngineToGuiCommand::ReadyOk],
+      GuiToEngineCommand::UciNewGame => {
+        self.game = GameData::START_POS;
+        Vec::new()
+      }
+      GuiToEngineCommand::Position { position, .. } => {
+        self.set_position(*position);
+        Vec::new()
+      }
+      GuiToEngineCommand::Go(params) => {
+        let depth = params.depth.unwrap_or(DEFAULT_DEPTH).max(1);
+        // With no legal move the GUI still expects a bestmove, the null move
+        let bestmove =
+          best_move(&self.game.board, depth).map_or(PieceMove::NULL, |(bestmove, _)| bestmove);
+        vec![EngineToGuiCommand::BestMove {
+          bestmove,
+          ponder: None,
+        }]
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  fn set_position(&mut self, position: PositionType) {
+    let (mut game, moves) = match position {
+      PositionType::StartPos { moves } => (GameData::START_POS, moves),
+      PositionType::Fen { gamedata, moves } => (*gamedata, moves),
+    };
+    for piece_move in moves {
+      // UCI moves carry no flags, so play the generated move with the same squares
+      let Some(legal) = game.board.legal_moves_iter().find(|legal| {
+        legal.from_square() == piece_move.from_square()
+          && legal.to_square() == piece_move.to_square()
+          && legal.promotion_type() == piece_move.promotion_type()
+      }) else {
+        break;
+      };
+      if game.play_move(&legal).is_none() {
+        break;
+      }
+    }
+    self.game = game;
+  }
+}
+
+/// The best move of `board` searched `depth` plies deep, with its score for the side to move, or
+/// `None` if there is no legal move.
+pub fn best_move(board: &GameBoard, depth: u32) -> Option<(PieceMove, i32)> {
+  let mut best: Option<(PieceMove, i32)> = None;
+  for piece_move in board.legal_moves_iter() {
+    let mut child = *board;
+    child.move_piece(&piece_move)?;
+    let score = -negamax(&child, depth - 1, 1);
+    if best.is_none_or(|(_, best_score)| score > best_score) {
+      best = Some((piece_move, score));
+    }
+  }
+  best
+}
+
+fn negamax(board: &GameBoard, depth: u32, ply: i32) -> i32 {
+  if !board.has_legal_move() {
+    let in_check = board
+      .king_square(board.playing)
+      .is_some_and(|king| is_square_attacked(board, king));
+    return if in_check { ply - MATE_SCORE } else { 0 };
+  }
+  if depth == 0 {
+    return material(board);
+  }
+  let mut best = -MATE_SCORE;
+  for piece_move in board.legal_moves_iter() {
+    let mut child = *board;
+    if child.move_piece(&piece_move).is_some() {
+      best = best.max(-negamax(&child, depth - 1, ply + 1));
+    }
+  }
+  best
+}
+
+/// Material balance in centipawns for the side to move.
+fn material(board: &GameBoard) -> i32 {
+  board
+    .combined()
+    .into_iter()
+    .filter_map(|square| board.piece_at(square))
+    .map(|(piece, white)| {
+      let sign = if white == board.playing { 1 } else { -1 };
+      sign * piece_value(piece)
+    })
+    .sum()
+}
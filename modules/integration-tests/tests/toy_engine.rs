@@ -0,0 +1,109 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! The toy engine driven over UCI with positions from the macro crate.
+
+use lumifox_chess::model::gamedata::GameData;
+use lumifox_chess_proc::{fen, move_list, position, san, sq};
+use lumifox_integration_tests::{MATE_SCORE, ToyEngine, best_move};
+use lumifox_uci::EngineToGuiCommand;
+
+fn bestmove(replies: &[EngineToGuiCommand]) -> Option<String> {
+  replies.iter().find_map(|reply| match reply {
+    EngineToGuiCommand::BestMove { bestmove, .. } => Some(bestmove.to_string()),
+    _ => None,
+  })
+}
+
+#[test]
+fn test_handshake() {
+  let mut engine = ToyEngine::new();
+  let replies = engine.handle_line("uci");
+  assert_eq!(replies.last(), Some(&EngineToGuiCommand::UciOk));
+  assert_eq!(
+    replies
+      .iter()
+      .map(ToString::to_string)
+      .collect::<String>()
+      .lines()
+      .last(),
+    Some("uciok")
+  );
+  assert_eq!(engine.handle_line("isready"), [EngineToGuiCommand::ReadyOk]);
+  assert!(engine.handle_line("not a command").is_empty());
+}
+
+#[test]
+fn test_position_matches_macros() {
+  let mut engine = ToyEngine::new();
+  engine.handle_line("position startpos moves e2e4 e7e5 g1f3");
+  let expected = position! {
+    "rnbqkbnr"
+    "pppp.ppp"
+    "........"
+    "....p..."
+    "....P..."
+    ".....N.."
+    "PPPP.PPP"
+    "RNBQKB.R"
+    ; to_move: Black
+    ; castling: "KQkq"
+  };
+  assert_eq!(engine.game().board, expected.board);
+  assert_eq!(
+    engine.game().board,
+    fen!("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2").board
+  );
+
+  // The moves the GUI sent are the ones the macros spell
+  let played = &engine.game().moves[..engine.game().history_len()];
+  let expected = move_list!["e2e4", "e7e5", "g1f3"];
+  assert_eq!(played.len(), expected.len());
+  for (played, expected) in played.iter().zip(&expected) {
+    assert_eq!(played.from_square(), expected.from_square());
+    assert_eq!(played.to_square(), expected.to_square());
+  }
+  assert_eq!(played[2].to_square(), sq!("f3"));
+}
+
+#[test]
+fn test_finds_mate_in_one() {
+  let game: GameData = fen!("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+  let (piece_move, score) = best_move(&game.board, 2).unwrap();
+  assert_eq!(piece_move, san!("e1e8"));
+  assert_eq!(score, MATE_SCORE - 1);
+
+  let mut engine = ToyEngine::new();
+  engine.handle_line("position fen 6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1");
+  let replies = engine.handle_line("go depth 2");
+  assert_eq!(bestmove(&replies).as_deref(), Some("e1e8"));
+}
+
+#[test]
+fn test_takes_free_material() {
+  let mut engine = ToyEngine::new();
+  engine.handle_line("position fen 4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+  let replies = engine.handle_line("go depth 1");
+  assert_eq!(bestmove(&replies).as_deref(), Some("d2d5"));
+
+  // Checkmated: no legal move, so the null move
+  engine.handle_line("position fen R5k1/5ppp/8/8/8/8/8/K7 b - - 0 1");
+  let replies = engine.handle_line("go depth 1");
+  let sent: Vec<String> = replies.iter().map(ToString::to_string).collect();
+  assert_eq!(sent, ["bestmove 0000\n"]);
+}
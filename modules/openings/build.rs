@@ -1,8 +1,8 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
@@ -54,41 +54,73 @@ fn parse_pgn_moves(pgn: &str) -> Vec<String> {
     .collect()
 }
 
+/// The TSV files named by `LUMIFOX_ECO_PATH`: the file itself, or every `.tsv` file of the
+/// directory in name order.
+fn local_tsvs(path: &Path) -> Vec<PathBuf> {
+  if !path.is_dir() {
+    return vec![path.to_path_buf()];
+  }
+  let mut files: Vec<PathBuf> = fs::read_dir(path)
+    .expect("Failed to read LUMIFOX_ECO_PATH")
+    .map(|entry| entry.expect("Failed to read LUMIFOX_ECO_PATH").path())
+    .filter(|file| file.extension().is_some_and(|ext| ext == "tsv"))
+    .collect();
+  files.sort();
+  files
+}
+
+/// The Lichess TSVs, downloaded into `out_dir` on the first build and read from there after.
+fn lichess_tsvs(out_dir: &Path) -> Vec<String> {
+  ['a', 'b', 'c', 'd', 'e']
+    .into_iter()
+    .map(|letter| {
+      let url =
+        format!("https://github.com/lichess-org/chess-openings/raw/refs/heads/master/{letter}.tsv");
+
+      // Save downloaded TSVs into OUT_DIR so they live alongside generated openings.rs
+      let cache_file = out_dir.join(format!("{letter}.tsv"));
+      if cache_file.exists() {
+        println!("cargo:debug=Using cached {}", cache_file.display());
+        fs::read_to_string(&cache_file).expect("Failed to read cached TSV file")
+      } else {
+        println!("cargo:info=Downloading {url}");
+        let response = reqwest::blocking::get(&url).expect("Failed to download TSV file");
+        let text = response.text().expect("Failed to read response as text");
+        fs::write(&cache_file, &text).expect("Failed to write cache file");
+        text
+      }
+    })
+    .collect()
+}
+
 fn main() {
   println!("cargo:rerun-if-changed=build.rs");
+  println!("cargo:rerun-if-env-changed=LUMIFOX_ECO_PATH");
 
   let out_dir = env::var("OUT_DIR").unwrap();
   let dest_path = Path::new(&out_dir).join("openings.rs");
 
-  // Download and parse all TSV files
-  let mut openings = HashMap::new();
-
-  for letter in ['a', 'b', 'c', 'd', 'e'] {
-    let url =
-      format!("https://github.com/lichess-org/chess-openings/raw/refs/heads/master/{letter}.tsv");
-
-    // Save downloaded TSVs into OUT_DIR so they live alongside generated openings.rs
-    let cache_dir = Path::new(&out_dir);
-    let cache_file = cache_dir.join(format!("{letter}.tsv"));
-
-    let content = if cache_file.exists() {
-      println!("cargo:debug=Using cached {}", cache_file.display());
-      fs::read_to_string(&cache_file).expect("Failed to read cached TSV file")
-    } else {
-      println!("cargo:info=Downloading {url}");
-      let response = reqwest::blocking::get(&url).expect("Failed to download TSV file");
-      let text = response.text().expect("Failed to read response as text");
-      fs::write(&cache_file, &text).expect("Failed to write cache file");
-      text
-    };
-
+  let contents: Vec<String> = match env::var_os("LUMIFOX_ECO_PATH") {
+    Some(path) => local_tsvs(Path::new(&path))
+      .iter()
+      .map(|file| {
+        println!("cargo:rerun-if-changed={}", file.display());
+        fs::read_to_string(file).expect("Failed to read TSV file")
+      })
+      .collect(),
+    None => lichess_tsvs(Path::new(&out_dir)),
+  };
+
+  // Keyed by upper-case name for case-insensitive lookup, and sorted so every build generates
+  // the same code
+  let mut openings = BTreeMap::new();
+  for content in &contents {
     let mut reader = csv::ReaderBuilder::new()
       .delimiter(b'\t')
       .from_reader(content.as_bytes());
 
     for result in reader.deserialize() {
       let opening: Opening = result.expect("Failed to parse TSV row");
-      // Use uppercase name as key for case-insensitive lookup
       openings.insert(opening.name.to_uppercase(), opening);
     }
   }
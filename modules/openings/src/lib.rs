@@ -0,0 +1,45 @@
+/*
+ * A high-performance chess library licensed under the LGPLv3.
+ * Copyright (C) 2025 Clifton Toaster Reid
+ *
+ * This library is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public License
+ * along with this library. If not, see <https://opensource.org/license/lgpl-3-0>.
+ */
+
+//! # Lumifox opening database
+//!
+//! Every named opening of the ECO classification as [`Opening`]s in [`OPENINGS`], keyed by
+//! upper-case name. The table is generated by the build script from tab-separated files with
+//! `eco`, `name` and `pgn` columns, the format of the Lichess
+//! [chess-openings](https://github.com/lichess-org/chess-openings) database:
+//!
+//! - By default the build downloads `a.tsv` to `e.tsv` from that repository once and caches
+//!   them in the build directory.
+//! - With `LUMIFOX_ECO_PATH` set, it reads that file instead, or every `.tsv` file of that
+//!   directory in name order, and never touches the network. Use it for offline builds and
+//!   custom databases.
+//!
+//! When two rows share a name the later one wins. `lumifox_chess_proc` builds its `opening!`
+//! macros on this crate; depend on it directly to look openings up at runtime without the
+//! macros.
+//!
+//! ```rust
+//! use lumifox_openings::OPENINGS;
+//!
+//! if let Some(sicilian) = OPENINGS.get("SICILIAN DEFENSE") {
+//!   println!("{} {}", sicilian.eco, sicilian.pgn);
+//! }
+//! ```
+
+// Include the generated openings data
+include!(concat!(env!("OUT_DIR"), "/openings.rs"));
@@ -106,7 +106,7 @@ fn main() {
   println!("\n=== Example Test Usage ===");
 
   // This is how you'd use move_list! in tests
-  let generated_moves = vec![san!("e2e4"), san!("d7d5"), san!("e4xd5")];
+  let generated_moves = [san!("e2e4"), san!("d7d5"), san!("e4xd5")];
   let expected_moves = move_list!["e2e4", "d7d5", "e4xd5"];
 
   println!(
@@ -28,10 +28,12 @@
 //! ## Literals
 //! - `sq!()` - Square notation to indices
 //! - `bitboard!()` - Create bitboards from square lists
-//! - `san!()` - UCI move notation parsing
+//! - `san!()` - UCI move notation parsing, rejecting malformed moves at compile time
 //! - `move_list!()` - Create move lists for testing
 //!
 //! ## Opening Database
+//! Enabled by the default `openings` feature, which pulls in the `lumifox_openings` crate. Set
+//! `LUMIFOX_ECO_PATH` to a TSV file or directory to build it offline.
 //! - `opening!()` - Look up chess openings by name (case-insensitive, PGN parsed into SAN move strings)
 //! - `opening_list!()` - Get all available opening names
 //! - `opening_search!()` - Search openings by partial name match
@@ -39,7 +41,7 @@
 //! ## Example Usage
 //!
 //! ```rust
-//! use lumifox_chess_proc::{fen, sq, bitboard, san, move_list, position, opening, opening_search};
+//! use lumifox_chess_proc::{fen, sq, bitboard, san, move_list, position};
 //! use lumifox_chess::model::gamedata::GameData;
 //!
 //! // Parse starting position with FEN
@@ -70,6 +72,13 @@
 //!
 //! // Create move lists for testing
 //! let expected_moves = move_list!["e2e4", "d7d5", "e4xd5"];
+//! ```
+//!
+//! With the `openings` feature:
+//!
+//! ```rust
+//! # #[cfg(feature = "openings")] {
+//! use lumifox_chess_proc::{opening, opening_search};
 //!
 //! // Look up chess openings (case-insensitive, with parsed SAN moves)
 //! let sicilian = opening!("Sicilian Defense");
@@ -79,6 +88,7 @@
 //! // Search for openings
 //! let all_sicilian = opening_search!("Sicilian");
 //! println!("Found {} Sicilian variations", all_sicilian.len());
+//! # }
 //! ```
 
 pub mod macros;
@@ -80,74 +80,102 @@ macro_rules! bitboard {
     }};
 }
 
-/// Helper const function to parse UCI-style move notation
-pub const fn parse_uci_move(uci: &str) -> (u8, u8, bool, Option<u8>) {
-  let bytes = uci.as_bytes();
-  if bytes.len() < 4 {
-    panic!("UCI move must be at least 4 characters");
+/// Square index of the file and rank bytes at `at` and `at + 1`, panicking with `message` unless
+/// they name a square.
+const fn square_at(bytes: &[u8], at: usize, message: &str) -> u8 {
+  if bytes.len() < at + 2 {
+    panic!("{}", message);
   }
+  let (file, rank) = (bytes[at], bytes[at + 1]);
+  if file < b'a' || file > b'h' || rank < b'1' || rank > b'8' {
+    panic!("{}", message);
+  }
+  (rank - b'1') * 8 + (file - b'a')
+}
 
-  // Determine whether this is capture notation (e4xd5) or regular UCI (e2e4)
-  let is_capture;
-  let from: u8;
-  let to: u8;
-  let promotion_idx: usize;
-
-  if bytes.len() >= 5 && bytes[2] == b'x' {
-    // Capture notation: e4xd5
-    let from_file = bytes[0];
-    let from_rank = bytes[1];
-    let to_file = bytes[3];
-    let to_rank = bytes[4];
+/// Helper const function to parse UCI-style move notation.
+///
+/// A move is the from square, an optional `-` (quiet move) or `x` (capture), the to square and an
+/// optional promotion piece, which may follow an `=`: `e2e4`, `e2-e4`, `e4xd5`, `e7e8q`,
+/// `d7xc8=N`. Only `x` marks a capture. A promotion must take a pawn from the 7th rank to the 8th
+/// or from the 2nd to the 1st, straight ahead or, capturing, one file across.
+pub const fn parse_uci_move(uci: &str) -> (u8, u8, bool, Option<u8>) {
+  let bytes = uci.as_bytes();
+  let from = square_at(
+    bytes,
+    0,
+    "Invalid from square: a move starts with a square like e2",
+  );
+  let separator = if bytes.len() > 2 { bytes[2] } else { 0 };
+  let (is_capture, to_at) = match separator {
+    b'x' => (true, 3),
+    b'-' => (false, 3),
+    _ => (false, 2),
+  };
+  let to = square_at(
+    bytes,
+    to_at,
+    "Invalid to square: expected a square like e4 after the from square",
+  );
 
-    if from_file < b'a' || from_file > b'h' || from_rank < b'1' || from_rank > b'8' {
-      panic!("Invalid from square in capture notation");
+  let mut promotion_at = to_at + 2;
+  if bytes.len() > promotion_at && bytes[promotion_at] == b'=' {
+    promotion_at += 1;
+    if bytes.len() == promotion_at {
+      panic!("Missing promotion piece after '='");
     }
-    if to_file < b'a' || to_file > b'h' || to_rank < b'1' || to_rank > b'8' {
-      panic!("Invalid to square in capture notation");
+  }
+  let promotion = if bytes.len() > promotion_at {
+    let piece = match bytes[promotion_at] {
+      b'q' | b'Q' => 0,
+      b'r' | b'R' => 1,
+      b'b' | b'B' => 2,
+      b'n' | b'N' => 3,
+      _ => panic!("Invalid promotion piece: expected one of q, r, b or n"),
+    };
+    if bytes.len() > promotion_at + 1 {
+      panic!("Unexpected characters after the promotion piece");
     }
-
-    from = (from_rank - b'1') * 8 + (from_file - b'a');
-    to = (to_rank - b'1') * 8 + (to_file - b'a');
-    is_capture = true;
-    promotion_idx = 5;
+    Some(piece)
   } else {
-    // Regular UCI notation: e2e4
-    let from_file = bytes[0];
-    let from_rank = bytes[1];
-    let to_file = bytes[2];
-    let to_rank = bytes[3];
+    None
+  };
 
-    if from_file < b'a' || from_file > b'h' || from_rank < b'1' || from_rank > b'8' {
-      panic!("Invalid from square");
+  if promotion.is_some() {
+    let (from_rank, to_rank) = (from / 8, to / 8);
+    if !(from_rank == 6 && to_rank == 7 || from_rank == 1 && to_rank == 0) {
+      panic!(
+        "Invalid promotion: a pawn promotes from the 7th rank to the 8th or the 2nd to the 1st"
+      );
     }
-    if to_file < b'a' || to_file > b'h' || to_rank < b'1' || to_rank > b'8' {
-      panic!("Invalid to square");
+    let files_apart = (from % 8).abs_diff(to % 8);
+    if files_apart > 1 || is_capture && files_apart != 1 {
+      panic!("Invalid promotion: a pawn moves straight ahead or captures one file across");
     }
-
-    from = (from_rank - b'1') * 8 + (from_file - b'a');
-    to = (to_rank - b'1') * 8 + (to_file - b'a');
-    is_capture = false;
-    promotion_idx = 4;
   }
 
-  // Parse promotion if present
-  let promotion = if bytes.len() > promotion_idx {
-    match bytes[promotion_idx] {
-      b'q' | b'Q' => Some(0), // Queen
-      b'r' | b'R' => Some(1), // Rook
-      b'b' | b'B' => Some(2), // Bishop
-      b'n' | b'N' => Some(3), // Knight
-      _ => panic!("Invalid promotion piece"),
-    }
-  } else {
-    None
-  };
-
   (from, to, is_capture, promotion)
 }
+
+/// Like [`parse_uci_move`], for a move of the side given by `white`: a promotion must reach that
+/// side's last rank, so `e2e1q` is rejected for white and `e7e8q` for black.
+pub const fn parse_uci_move_for(uci: &str, white: bool) -> (u8, u8, bool, Option<u8>) {
+  let parsed = parse_uci_move(uci);
+  if parsed.3.is_some() && (parsed.1 / 8 == 7) != white {
+    if white {
+      panic!("Invalid promotion for white: white pawns promote on the 8th rank");
+    } else {
+      panic!("Invalid promotion for black: black pawns promote on the 1st rank");
+    }
+  }
+  parsed
+}
+
 /// Compile-time UCI-style move literal: e.g. san!("e2e4"), optional promotion like "e7e8q"
 ///
+/// Moves are read by [`parse_uci_move`], and a malformed one fails to compile. Adding
+/// `; to_move: White` or `; to_move: Black` also checks that a promotion is that side's.
+///
 /// # Examples
 ///
 /// ```rust
@@ -157,18 +185,40 @@ pub const fn parse_uci_move(uci: &str) -> (u8, u8, bool, Option<u8>) {
 /// let king_pawn = san!("e2e4");
 /// let promotion = san!("e7e8q");
 /// let knight_move = san!("g1f3");
+/// let capture = san!("e4xd5");
+/// let black_promotion = san!("b2xa1=N"; to_move: Black);
+/// ```
+///
+/// A promotion onto the wrong side's last rank does not compile:
+///
+/// ```compile_fail
+/// use lumifox_chess_proc::san;
+///
+/// let promotion = san!("e2e1q"; to_move: White);
 /// ```
 #[macro_export]
 macro_rules! san {
   ($uci:literal) => {{
     const PARSED: (u8, u8, bool, Option<u8>) = $crate::macros::literals::parse_uci_move($uci);
-    let promotion = match PARSED.3 {
+    $crate::san!(@build PARSED)
+  }};
+  ($uci:literal; to_move: $to_move:ident) => {{
+    const PARSED: (u8, u8, bool, Option<u8>) =
+      $crate::macros::literals::parse_uci_move_for($uci, $crate::san!(@white $to_move));
+    $crate::san!(@build PARSED)
+  }};
+
+  (@white White) => { true };
+  (@white Black) => { false };
+
+  (@build $parsed:ident) => {{
+    let promotion = match $parsed.3 {
       Some(0) => Some(lumifox_chess::model::piecemove::PromotionType::Queen),
       Some(1) => Some(lumifox_chess::model::piecemove::PromotionType::Rook),
       Some(2) => Some(lumifox_chess::model::piecemove::PromotionType::Bishop),
       Some(3) => Some(lumifox_chess::model::piecemove::PromotionType::Knight),
       _ => None,
     };
-    lumifox_chess::model::piecemove::PieceMove::new(PARSED.0, PARSED.1, PARSED.2, promotion)
+    lumifox_chess::model::piecemove::PieceMove::new($parsed.0, $parsed.1, $parsed.2, promotion)
   }};
 }
@@ -22,9 +22,10 @@
 //! - FEN string parsing and validation
 //! - Square, bitboard, and move notation literals
 //! - Position creation and move list utilities
-//! - Chess opening lookup and search
+//! - Chess opening lookup and search (the `openings` feature)
 
 pub mod fen;
 pub mod literals;
+#[cfg(feature = "openings")]
 pub mod openings;
 pub mod positions;
@@ -17,9 +17,11 @@
  */
 
 //! Chess opening macros for compile-time opening lookup.
+//!
+//! The database itself lives in [`lumifox_openings`]; see its docs for pointing the build at
+//! local TSV files with `LUMIFOX_ECO_PATH` instead of downloading them.
 
-// Include the generated openings data
-include!(concat!(env!("OUT_DIR"), "/openings.rs"));
+pub use lumifox_openings::{OPENINGS, Opening};
 
 /// Macro to look up chess openings by name at compile time.
 /// The lookup is case-insensitive, so "Sicilian Defense", "sicilian defense",
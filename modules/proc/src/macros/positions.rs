@@ -55,10 +55,12 @@ pub fn compress_rank_runtime(rank: &str) -> String {
 
 /// Create a list of moves from various notation formats
 ///
-/// Supports multiple formats:
+/// Each move is a [`san!`](crate::san) literal, so these formats are supported:
 /// - UCI notation: "e2e4", "e7e8q" (with promotion)
-/// - Algebraic with capture: "e4xd5", "Nxf7"
-/// - Simple format: "e4-d5" (quiet move), "e4xd5" (capture)
+/// - With a separator: "e2-e4" (quiet move), "e4xd5" (capture), "d7xc8=N"
+///
+/// Pieces are not named, since a list has no board to find them on. Ending the list with
+/// `; to_move: White` or `; to_move: Black` checks every promotion against that side.
 ///
 /// # Examples
 ///
@@ -76,6 +78,9 @@ pub fn compress_rank_runtime(rank: &str) -> String {
 ///
 /// // Clean syntax for test assertions
 /// let knight_moves = move_list!["g1f3", "b8c6", "f3e5"];
+///
+/// // The promotions of one side
+/// let black_promotions = move_list!["a2a1q", "a2xb1=R"; to_move: Black];
 /// ```
 #[macro_export]
 macro_rules! move_list {
@@ -86,12 +91,20 @@ macro_rules! move_list {
       ),*
     ]
   }};
+  [$($move_str:literal),* $(,)?; to_move: $to_move:ident] => {{
+    vec![
+      $(
+        $crate::san!($move_str; to_move: $to_move)
+      ),*
+    ]
+  }};
 }
 
 /// Create a position using visual board representation
 ///
 /// Much more readable than FEN for test cases and examples.
 /// Supports piece placement with standard symbols.
+/// `GameBoard::from_diagram` reads the same layout at runtime.
 ///
 /// # Examples
 ///
@@ -174,8 +187,10 @@ macro_rules! position {
       fullmove_number
     );
 
-    lumifox_chess::model::gamedata::GameData::from_fen(&fen)
-      .unwrap_or_else(|e| panic!("Invalid position: {:?}", e))
+    let mut game = lumifox_chess::model::gamedata::GameData::from_fen(&fen)
+      .unwrap_or_else(|e| panic!("Invalid position: {:?}", e));
+    position!(@default_castling game $($castling)?);
+    game
   }};
 
   // Helper rules for defaults
@@ -184,7 +199,17 @@ macro_rules! position {
   (@to_move) => { "w" };
 
   (@castling $castling:literal) => { $castling };
-  (@castling) => { "KQkq" };
+  (@castling) => { "-" };
+
+  // Without a castling field, every standard right the pieces still allow. The history is
+  // replayed from the initial board, so it needs the rights as well.
+  (@default_castling $game:ident $castling:literal) => {};
+  (@default_castling $game:ident) => {
+    let castling = lumifox_chess::model::castling::CastlingRights::STANDARD
+      .restrict_to(&$game.board);
+    $game.board.castling = castling;
+    $game.initial_board.castling = castling;
+  };
 
   (@en_passant Some($square:literal)) => { $square };
   (@en_passant None) => { "-" };
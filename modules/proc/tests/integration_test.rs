@@ -18,8 +18,15 @@
 
 #[cfg(test)]
 mod tests {
-  use lumifox_chess::model::gamedata::GameData;
-  use lumifox_chess_proc::fen;
+  use lumifox_chess::model::{
+    gamedata::GameData,
+    piecemove::{PieceMove, PromotionType},
+  };
+  use lumifox_chess_proc::{
+    fen,
+    macros::literals::{parse_uci_move, parse_uci_move_for},
+    move_list, position, san,
+  };
 
   #[test]
   fn test_fen_macro_starting_position() {
@@ -28,7 +35,7 @@ mod tests {
     assert!(start_pos.board.playing); // White to move
     assert_eq!(start_pos.plies, 0);
     assert_eq!(start_pos.halfmove_clock, 0);
-    assert_eq!(start_pos.board.castling, 0b1111); // All castling rights
+    assert_eq!(start_pos.board.castling.bits(), 0b1111); // All castling rights
   }
 
   #[test]
@@ -46,11 +53,11 @@ mod tests {
     let en_passant: GameData = fen!("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2");
 
     assert!(en_passant.board.playing); // White to move
-    assert_ne!(
-      en_passant.board.en_passant,
-      lumifox_chess::model::piecemove::PieceMove::NULL
-    );
-    assert_eq!(en_passant.board.en_passant.to_square(), 43); // d6 = 43
+    let target = en_passant
+      .board
+      .en_passant
+      .map(|en_passant| en_passant.to_square());
+    assert_eq!(target, Some(43)); // d6 = 43
   }
 
   #[test]
@@ -59,7 +66,7 @@ mod tests {
       fen!("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b - - 1 1");
 
     assert!(!no_castling.board.playing); // Black to move
-    assert_eq!(no_castling.board.castling, 0); // No castling rights
+    assert_eq!(no_castling.board.castling.bits(), 0); // No castling rights
     assert_eq!(no_castling.halfmove_clock, 1);
   }
 
@@ -68,8 +75,78 @@ mod tests {
     let endgame: GameData = fen!("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 55");
 
     assert!(endgame.board.playing); // White to move
-    assert_eq!(endgame.board.castling, 0); // No castling rights
+    assert_eq!(endgame.board.castling.bits(), 0); // No castling rights
     assert_eq!(endgame.halfmove_clock, 0);
     assert_eq!(endgame.plies, 108);
   }
+
+  #[test]
+  fn test_position_macro_default_castling() {
+    let mut game = position! {
+      "rnbqkbnr"
+      "pppppppp"
+      "........"
+      "........"
+      "........"
+      "........"
+      "PPPPPPPP"
+      "RNBQKBNR"
+    };
+    assert_eq!(game.initial_board.castling, game.board.castling);
+    for uci in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1"] {
+      game.play_uci(uci).unwrap();
+    }
+
+    // Castling is replayed from the initial board like every other move
+    assert_eq!(game.replay().count(), 7);
+    assert_eq!(game.position_at(7), Some(game.board));
+    assert_eq!(game.initial_board.castling.bits(), 0b1111);
+  }
+
+  #[test]
+  fn test_move_literal_notations() {
+    // e2 = 12, e4 = 28, d5 = 35
+    assert_eq!(san!("e2e4"), PieceMove::new(12, 28, false, None));
+    assert_eq!(san!("e2-e4"), san!("e2e4"));
+    assert_eq!(san!("e4xd5"), PieceMove::new(28, 35, true, None));
+
+    // d7 = 51, c8 = 58, a2 = 8, a1 = 0
+    let knight = Some(PromotionType::Knight);
+    assert_eq!(san!("d7xc8=N"), PieceMove::new(51, 58, true, knight));
+    assert_eq!(san!("d7c8n"), PieceMove::new(51, 58, false, knight));
+    assert_eq!(
+      move_list!["a2a1q", "a2-a1=r"; to_move: Black],
+      vec![
+        PieceMove::new(8, 0, false, Some(PromotionType::Queen)),
+        PieceMove::new(8, 0, false, Some(PromotionType::Rook)),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_malformed_move_literals() {
+    // The macros evaluate these at compile time; called directly they panic with the same message
+    for (uci, message) in [
+      ("Nxf7", "Invalid from square"),
+      ("e2", "Invalid to square"),
+      ("e2+e4", "Invalid to square"),
+      ("e7e8k", "Invalid promotion piece"),
+      ("e7e8qq", "Unexpected characters"),
+      ("e7e8=", "Missing promotion piece"),
+      ("e6e7q", "a pawn promotes from the 7th rank"),
+      ("e7c8q", "straight ahead or captures"),
+      ("e7xe8q", "straight ahead or captures"),
+    ] {
+      let panic = std::panic::catch_unwind(|| parse_uci_move(uci)).unwrap_err();
+      let text = match panic.downcast_ref::<&str>() {
+        Some(text) => text.to_string(),
+        None => panic.downcast_ref::<String>().cloned().unwrap_or_default(),
+      };
+      assert!(text.contains(message), "{uci}: {text}");
+    }
+
+    assert!(std::panic::catch_unwind(|| parse_uci_move_for("e2e1q", true)).is_err());
+    assert!(std::panic::catch_unwind(|| parse_uci_move_for("e7e8q", false)).is_err());
+    assert_eq!(parse_uci_move_for("e2e4", false), parse_uci_move("e2e4"));
+  }
 }
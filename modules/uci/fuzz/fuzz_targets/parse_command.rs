@@ -0,0 +1,70 @@
+/*
+ * This file is dual-licensed under the terms of the GNU Lesser General Public License,
+ * Version 3 or later, and the BSD 3-Clause License.
+ *
+ * You are free to use this software under the terms of either licence.
+ * See the `LICENCE-LGPL-3.0-or-later.md` and `LICENCE-BSD-3-Clause.md`
+ * files in this repository for the full text of each licence.
+ *
+ * If the files have not been provided, you can find the full text of the licences at:
+ * LGPL-3.0-or-later: https://opensource.org/license/lgpl-3-0
+ * BSD-3-Clause: https://opensource.org/license/bsd-3-clause
+ *
+ * Copyright (C) 2025 Clifton Toaster Reid
+ */
+
+//! Feed arbitrary GUI input through the command parser and the position resolver. Any panic is a
+//! bug, and the parsed command must never hold more than the input could describe.
+//!
+//! Run with `cargo fuzz run parse_command -- -rss_limit_mb=64` from `modules/uci`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lumifox_chess::{model::gamedata::GameData, movegen::generate_moves};
+use lumifox_uci::{GuiToEngineCommand, PositionType};
+
+fuzz_target!(|data: &[u8]| {
+  // GUIs send text, but a broken pipe can send anything
+  let line = String::from_utf8_lossy(data);
+  let Ok(command) = line.parse::<GuiToEngineCommand>() else {
+    return;
+  };
+  let tokens = line.split_whitespace().count();
+
+  match command {
+    GuiToEngineCommand::Position { position, moves } => {
+      assert!(moves.len() < tokens);
+      let (mut game, listed) = match *position {
+        PositionType::StartPos { moves } => (GameData::START_POS, moves),
+        PositionType::Fen { gamedata, moves } => (*gamedata, moves),
+      };
+      assert_eq!(listed, moves);
+
+      // Replay the moves the way the engine does, stopping at the first one that is not legal
+      for piece_move in moves {
+        let (generated, count) = generate_moves(&game.board);
+        let Some(played) = generated[..count].iter().find(|candidate| {
+          candidate.from_square() == piece_move.from_square()
+            && candidate.to_square() == piece_move.to_square()
+            && candidate.promotion_type() == piece_move.promotion_type()
+        }) else {
+          break;
+        };
+        if game.play_move(played).is_none() {
+          break;
+        }
+      }
+    }
+    GuiToEngineCommand::Go(params) => {
+      if let Some(moves) = params.searchmoves {
+        assert!(moves.len() < tokens);
+      }
+      assert!(params.depth != Some(0) && params.nodes != Some(0) && params.mate != Some(0));
+    }
+    GuiToEngineCommand::SetOption { name, value } => {
+      assert!(name.len() + value.map_or(0, |value| value.len()) <= line.len());
+    }
+    _ => {}
+  }
+});
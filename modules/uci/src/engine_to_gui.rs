@@ -32,7 +32,8 @@ pub enum EngineToGuiCommand {
   /// Engine is ready to accept new commands
   ReadyOk,
 
-  /// Best move found
+  /// Best move found. [`PieceMove::NULL`] is sent as `0000`, the UCI null move, for a position
+  /// with no legal move.
   BestMove {
     bestmove: PieceMove,
     ponder: Option<PieceMove>,
@@ -191,7 +192,11 @@ fn fmt_id(name: &Option<String>, author: &Option<String>) -> String {
 }
 
 fn fmt_bestmove(bestmove: &PieceMove, ponder: &Option<PieceMove>) -> String {
-  let mut string = format!("bestmove {bestmove}");
+  let mut string = if *bestmove == PieceMove::NULL {
+    "bestmove 0000".to_string()
+  } else {
+    format!("bestmove {bestmove}")
+  };
   if let Some(ponder) = ponder {
     string.push_str(&format!(" ponder {ponder}"));
   }
@@ -24,9 +24,34 @@ pub enum UciError {
   #[error("Parser error: {0}")]
   Parser(String),
 
-  // Use Debug formatting since MoveParseError does not implement Display.
-  #[error("Invalid piece move: {0:?}")]
+  #[error("Invalid piece move: {0}")]
   InvalidPieceMove(MoveParseError),
+
+  #[error("Invalid promotion {mv}: {reason}")]
+  InvalidPromotion { mv: String, reason: MoveParseError },
+
+  #[error("Pawn move {mv} reaches the last rank without a promotion piece")]
+  MissingPromotion { mv: String },
+
+  /// A `go` parameter was given without a value, or with one that is not a number.
+  #[error("Invalid value for go {name}: {value:?}")]
+  InvalidGoValue { name: String, value: Option<String> },
+}
+
+impl UciError {
+  /// Wrap an error from parsing the move `mv`, singling out promotion mistakes.
+  pub fn from_move(mv: &str, error: MoveParseError) -> Self {
+    match error {
+      MoveParseError::InvalidPromotionRank
+      | MoveParseError::InvalidPromotionPiece
+      | MoveParseError::PromotionByNonPawn => UciError::InvalidPromotion {
+        mv: mv.to_string(),
+        reason: error,
+      },
+      MoveParseError::MissingPromotion => UciError::MissingPromotion { mv: mv.to_string() },
+      _ => UciError::InvalidPieceMove(error),
+    }
+  }
 }
 
 // Convenience conversion so `?` works with functions that return UciError.
@@ -0,0 +1,193 @@
+/*
+ * This file is dual-licensed under the terms of the GNU Lesser General Public License,
+ * Version 3 or later, and the BSD 3-Clause License.
+ *
+ * You are free to use this software under the terms of either licence.
+ * See the `LICENCE-LGPL-3.0-or-later.md` and `LICENCE-BSD-3-Clause.md`
+ * files in this repository for the full text of each licence.
+ *
+ * If the files have not been provided, you can find the full text of the licences at:
+ * LGPL-3.0-or-later: https://opensource.org/license/lgpl-3-0
+ * BSD-3-Clause: https://opensource.org/license/bsd-3-clause
+ *
+ * Copyright (C) 2025 Clifton Toaster Reid
+ */
+
+//! Parameters of the `go` command.
+//!
+//! [`GoParams`] collects everything `go` can carry, already sanitised, so every engine built on
+//! this crate reads the same values:
+//! - negative times and increments, which some GUIs send once a side has flagged, become 0;
+//! - `depth`, `nodes`, `movestogo` and `movetime` of 0 are raised to 1, since a search needs at
+//!   least one ply, node, move or millisecond to produce a move;
+//! - `mate 0` asks for nothing and is dropped;
+//! - a parameter without a value, or with one that is not a number, rejects the command with
+//!   [`UciError::InvalidGoValue`].
+//!
+//! Parameters that are not given keep their [`Default`]: no limits, no increments, not pondering
+//! and not infinite.
+
+use lumifox_chess::model::{clock::Clock, piecemove::PieceMove};
+use std::str::FromStr;
+
+use crate::error::UciError;
+
+/// Everything a `go` command asks for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoParams {
+  /// Restrict search to these moves only
+  pub searchmoves: Option<Vec<PieceMove>>,
+  /// Start searching in pondering mode
+  pub ponder: bool,
+  /// Remaining time and increments from `wtime`, `btime`, `winc`, `binc` and `movestogo`
+  pub clock: Clock,
+  /// Search x plies only; at least 1
+  pub depth: Option<u32>,
+  /// Search x nodes only; at least 1
+  pub nodes: Option<u64>,
+  /// Search for a mate in x moves; at least 1
+  pub mate: Option<u32>,
+  /// Search exactly x milliseconds; at least 1
+  pub movetime: Option<u64>,
+  /// Search until the "stop" command
+  pub infinite: bool,
+}
+
+impl GoParams {
+  /// Whether the search has anything to stop it besides a `stop` command: a depth, node, mate or
+  /// time limit, or a clock.
+  pub fn is_limited(&self) -> bool {
+    !self.infinite
+      && (self.depth.is_some()
+        || self.nodes.is_some()
+        || self.mate.is_some()
+        || self.movetime.is_some()
+        || self.clock.is_timed())
+  }
+
+  /// Parse the words following `go`. Unknown words are skipped.
+  pub fn parse(args: &[&str]) -> Result<Self, UciError> {
+    let mut params = GoParams::default();
+    let mut idx = 0;
+
+    while idx < args.len() {
+      let name = args[idx];
+      idx += 1;
+      match name {
+        "searchmoves" => {
+          let mut moves = Vec::new();
+          // The list ends at the first token that is not a move, usually the next parameter
+          while let Some(Ok(mv)) = args.get(idx).map(|arg| PieceMove::from_str(arg)) {
+            moves.push(mv);
+            idx += 1;
+          }
+          params.searchmoves = Some(moves);
+          continue;
+        }
+        "ponder" => {
+          params.ponder = true;
+          continue;
+        }
+        "infinite" => {
+          params.infinite = true;
+          continue;
+        }
+        _ => {}
+      }
+
+      let value = args.get(idx).copied();
+      let parsed = match name {
+        "wtime" => time(name, value).map(|time| params.clock.white_time = Some(time)),
+        "btime" => time(name, value).map(|time| params.clock.black_time = Some(time)),
+        "winc" => time(name, value).map(|inc| params.clock.white_increment = inc),
+        "binc" => time(name, value).map(|inc| params.clock.black_increment = inc),
+        "movestogo" => positive(name, value).map(|moves| params.clock.moves_to_go = Some(moves)),
+        "depth" => positive(name, value).map(|depth| params.depth = Some(depth)),
+        "nodes" => positive(name, value).map(|nodes| params.nodes = Some(nodes)),
+        "movetime" => positive(name, value).map(|time| params.movetime = Some(time)),
+        "mate" => {
+          number::<u32>(name, value).map(|moves| params.mate = (moves > 0).then_some(moves))
+        }
+        // Skip parameters this parser does not know rather than dropping the whole command
+        _ => continue,
+      };
+      parsed?;
+      idx += 1;
+    }
+
+    Ok(params)
+  }
+}
+
+fn number<T: FromStr>(name: &str, value: Option<&str>) -> Result<T, UciError> {
+  value
+    .and_then(|value| value.parse().ok())
+    .ok_or_else(|| UciError::InvalidGoValue {
+      name: name.to_string(),
+      value: value.map(str::to_string),
+    })
+}
+
+/// A time or increment in milliseconds, with negative values read as 0.
+fn time(name: &str, value: Option<&str>) -> Result<u64, UciError> {
+  number::<i64>(name, value).map(|time| time.max(0) as u64)
+}
+
+/// A count or limit that must be at least 1.
+fn positive<T: FromStr + Ord + From<u8>>(name: &str, value: Option<&str>) -> Result<T, UciError> {
+  number::<T>(name, value).map(|value| value.max(T::from(1)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(line: &str) -> Result<GoParams, UciError> {
+    GoParams::parse(&line.split_whitespace().collect::<Vec<_>>())
+  }
+
+  #[test]
+  fn test_defaults() {
+    let params = parse("").unwrap();
+    assert_eq!(params, GoParams::default());
+    assert!(!params.is_limited());
+    assert!(parse("depth 5").unwrap().is_limited());
+    assert!(parse("wtime 1000").unwrap().is_limited());
+    assert!(!parse("depth 5 infinite").unwrap().is_limited());
+  }
+
+  #[test]
+  fn test_clamps_nonsense() {
+    let params =
+      parse("wtime -1500 btime 0 winc -10 depth 0 nodes 0 movestogo 0 movetime 0 mate 0").unwrap();
+    assert_eq!(params.clock.white_time, Some(0));
+    assert_eq!(params.clock.black_time, Some(0));
+    assert_eq!(params.clock.white_increment, 0);
+    assert_eq!(params.clock.moves_to_go, Some(1));
+    assert_eq!(params.depth, Some(1));
+    assert_eq!(params.nodes, Some(1));
+    assert_eq!(params.movetime, Some(1));
+    assert_eq!(params.mate, None);
+  }
+
+  #[test]
+  fn test_rejects_missing_and_bad_values() {
+    for line in [
+      "movetime",
+      "depth infinite",
+      "wtime abc",
+      "nodes -5",
+      "mate 1.5",
+    ] {
+      assert!(
+        matches!(parse(line), Err(UciError::InvalidGoValue { .. })),
+        "{line}"
+      );
+    }
+    let Err(UciError::InvalidGoValue { name, value }) = parse("depth 3 movetime") else {
+      panic!("expected an error");
+    };
+    assert_eq!(name, "movetime");
+    assert_eq!(value, None);
+  }
+}
@@ -13,9 +13,12 @@
  * Copyright (C) 2025 Clifton Toaster Reid
  */
 
-use lumifox_chess::model::{gamedata::GameData, piecemove::PieceMove};
+use lumifox_chess::{
+  model::{gameboard::GameBoard, gamedata::GameData, piecemove::PieceMove},
+  movegen::generate_moves,
+};
 
-use crate::error::UciError;
+use crate::{error::UciError, go::GoParams};
 use std::str::FromStr;
 
 /// Commands sent from the GUI to the engine
@@ -52,32 +55,7 @@ pub enum GuiToEngineCommand {
   },
 
   /// Start calculating on the current position
-  Go {
-    /// Restrict search to these moves only
-    searchmoves: Option<Vec<PieceMove>>,
-    /// Start searching in pondering mode
-    ponder: bool,
-    /// White has x milliseconds left on the clock
-    wtime: Option<u64>,
-    /// Black has x milliseconds left on the clock
-    btime: Option<u64>,
-    /// White increment per move in milliseconds
-    winc: Option<u64>,
-    /// Black increment per move in milliseconds
-    binc: Option<u64>,
-    /// Moves to the next time control
-    movestogo: Option<u32>,
-    /// Search x plies only
-    depth: Option<u32>,
-    /// Search x nodes only
-    nodes: Option<u64>,
-    /// Search for a mate in x moves
-    mate: Option<u32>,
-    /// Search exactly x milliseconds
-    movetime: Option<u64>,
-    /// Search until the "stop" command
-    infinite: bool,
-  },
+  Go(GoParams),
 
   /// Stop calculating as soon as possible
   Stop,
@@ -111,6 +89,11 @@ impl FromStr for GuiToEngineCommand {
       return Err(UciError::Parser("Empty command".to_string()));
     }
 
+    // The protocol asks engines to skip unknown words, so "joho debug on" is "debug on"
+    let Some(args) = skip_to(&args, COMMANDS) else {
+      return Err(UciError::Parser("Unrecognized command".to_string()));
+    };
+
     match args[0] {
       "uci" => Ok(GuiToEngineCommand::Uci),
       "debug" => parse_debug(&args[1..]),
@@ -119,26 +102,42 @@ impl FromStr for GuiToEngineCommand {
       "register" => parse_register(&args[1..]),
       "ucinewgame" => Ok(GuiToEngineCommand::UciNewGame),
       "position" => parse_position(&args[1..]),
-      "go" => parse_go(&args[1..]),
+      "go" => GoParams::parse(&args[1..]).map(GuiToEngineCommand::Go),
       "stop" => Ok(GuiToEngineCommand::Stop),
       "ponderhit" => Ok(GuiToEngineCommand::PonderHit),
       "quit" => Ok(GuiToEngineCommand::Quit),
-      _ => Err(UciError::Parser("Unrecognized command".to_string())),
+      _ => unreachable!("skip_to only stops at known commands"),
     }
   }
 }
 
+/// Every command the GUI may send, used to find where a line starts.
+const COMMANDS: &[&str] = &[
+  "uci",
+  "debug",
+  "isready",
+  "setoption",
+  "register",
+  "ucinewgame",
+  "position",
+  "go",
+  "stop",
+  "ponderhit",
+  "quit",
+];
+
 // Helper functions for parsing individual commands
 
+/// Drop the unknown words in front of the first token that is one of `words`.
+fn skip_to<'a, 'b>(args: &'a [&'b str], words: &[&str]) -> Option<&'a [&'b str]> {
+  let start = args.iter().position(|arg| words.contains(arg))?;
+  Some(&args[start..])
+}
+
 fn parse_debug(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
-  if args.len() != 1 {
-    return Err(UciError::Parser(
-      "Invalid number of arguments for debug command".to_string(),
-    ));
-  }
-  match args[0] {
-    "on" => Ok(GuiToEngineCommand::Debug { on: true }),
-    "off" => Ok(GuiToEngineCommand::Debug { on: false }),
+  match skip_to(args, &["on", "off"]).map(|args| args[0]) {
+    Some("on") => Ok(GuiToEngineCommand::Debug { on: true }),
+    Some("off") => Ok(GuiToEngineCommand::Debug { on: false }),
     _ => Err(UciError::Parser(
       "Invalid argument for debug command".to_string(),
     )),
@@ -146,7 +145,8 @@ fn parse_debug(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
 }
 
 fn parse_setoption(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
-  if args.len() < 2 || args[0] != "name" {
+  let args = skip_to(args, &["name"]).unwrap_or_default();
+  if args.len() < 2 {
     return Err(UciError::Parser(
       "Invalid setoption command format".to_string(),
     ));
@@ -173,11 +173,11 @@ fn parse_setoption(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
 }
 
 fn parse_register(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
-  if args.is_empty() {
+  let Some(args) = skip_to(args, &["later", "name", "code"]) else {
     return Err(UciError::Parser(
-      "Invalid number of arguments for register command".to_string(),
+      "Invalid argument for register command".to_string(),
     ));
-  }
+  };
   let word = args[0];
   let rest = args[1..].join(" ");
 
@@ -192,35 +192,27 @@ fn parse_register(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
       name: Some(rest),
       code: None,
     }),
-    "code" => Ok(GuiToEngineCommand::Register {
+    _ => Ok(GuiToEngineCommand::Register {
       later: false,
       name: None,
       code: Some(rest),
     }),
-    _ => Err(UciError::Parser(
-      "Invalid argument for register command".to_string(),
-    )),
   }
 }
 
 fn parse_position(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
-  if args.is_empty() {
+  let Some(args) = skip_to(args, &["startpos", "fen"]) else {
     return Err(UciError::Parser(
-      "Invalid number of arguments for position command".to_string(),
+      "Invalid position command, expected 'startpos' or 'fen'".to_string(),
     ));
-  }
+  };
 
   let mut idx = 0;
   let mut moves: Vec<PieceMove> = Vec::new();
 
   if args[idx] == "startpos" {
-    idx += 1;
-    if idx < args.len() && args[idx] == "moves" {
-      idx += 1;
-      while idx < args.len() {
-        moves.push(PieceMove::from_str(args[idx])?);
-        idx += 1;
-      }
+    if let Some(rest) = skip_to(&args[idx + 1..], &["moves"]) {
+      moves = parse_moves(&rest[1..], GameBoard::START_POS)?;
     }
     let pos_type = PositionType::StartPos {
       moves: moves.clone(),
@@ -246,18 +238,13 @@ fn parse_position(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
     }
 
     let fen = fen_parts.join(" ");
-
-    if idx < args.len() && args[idx] == "moves" {
-      idx += 1;
-      while idx < args.len() {
-        moves.push(PieceMove::from_str(args[idx])?);
-        idx += 1;
-      }
-    }
-
     let gamedata =
       GameData::from_fen(&fen).map_err(|e| UciError::Parser(format!("Invalid FEN: {e:?}")))?;
 
+    if let Some(rest) = skip_to(&args[idx..], &["moves"]) {
+      moves = parse_moves(&rest[1..], gamedata.board)?;
+    }
+
     let pos_type = PositionType::Fen {
       gamedata: Box::new(gamedata),
       moves: moves.clone(),
@@ -268,190 +255,142 @@ fn parse_position(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
     });
   }
 
-  Err(UciError::Parser(
-    "Invalid position command, expected 'startpos' or 'fen'".to_string(),
-  ))
+  unreachable!("skip_to only stops at 'startpos' or 'fen'")
 }
 
-fn parse_go(args: &[&str]) -> Result<GuiToEngineCommand, UciError> {
-  let mut idx = 0;
-  let mut searchmoves: Option<Vec<PieceMove>> = None;
-  let mut ponder = false;
-  let mut wtime: Option<u64> = None;
-  let mut btime: Option<u64> = None;
-  let mut winc: Option<u64> = None;
-  let mut binc: Option<u64> = None;
-  let mut movestogo: Option<u32> = None;
-  let mut depth: Option<u32> = None;
-  let mut nodes: Option<u64> = None;
-  let mut mate: Option<u32> = None;
-  let mut movetime: Option<u64> = None;
-  let mut infinite = false;
-
-  while idx < args.len() {
-    match args[idx] {
-      "searchmoves" => {
-        idx += 1;
-        let mut moves = Vec::new();
-        while idx < args.len() {
-          let kw = args[idx];
-          if kw == "ponder"
-            || kw == "wtime"
-            || kw == "btime"
-            || kw == "winc"
-            || kw == "binc"
-            || kw == "movestogo"
-            || kw == "depth"
-            || kw == "nodes"
-            || kw == "mate"
-            || kw == "movetime"
-            || kw == "infinite"
-          {
-            break;
-          }
-          moves.push(PieceMove::from_str(kw)?);
-          idx += 1;
-        }
-        searchmoves = Some(moves);
-        continue;
-      }
-      "ponder" => {
-        ponder = true;
-        idx += 1;
-      }
-      "wtime" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for wtime".to_string()));
-        }
-        wtime = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid wtime value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "btime" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for btime".to_string()));
-        }
-        btime = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid btime value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "winc" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for winc".to_string()));
-        }
-        winc = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid winc value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "binc" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for binc".to_string()));
-        }
-        binc = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid binc value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "movestogo" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for movestogo".to_string()));
-        }
-        movestogo = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid movestogo value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "depth" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for depth".to_string()));
-        }
-        depth = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid depth value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "nodes" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for nodes".to_string()));
-        }
-        nodes = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid nodes value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "mate" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for mate".to_string()));
-        }
-        mate = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid mate value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "movetime" => {
-        idx += 1;
-        if idx >= args.len() {
-          return Err(UciError::Parser("Missing value for movetime".to_string()));
-        }
-        movetime = Some(
-          args[idx]
-            .parse()
-            .map_err(|_| UciError::Parser("Invalid movetime value".to_string()))?,
-        );
-        idx += 1;
-      }
-      "infinite" => {
-        infinite = true;
-        idx += 1;
-      }
-      _ => {
-        return Err(UciError::Parser(format!(
-          "Unrecognized token in go command: {}",
-          args[idx]
-        )));
-      }
+/// Parse the move list of a `position` command, playing the moves out from `board` so that
+/// promotions can be checked against the pieces actually moving. Once a move does not match any
+/// generated move the position is unknown, and later moves are only checked on their own.
+fn parse_moves(args: &[&str], mut board: GameBoard) -> Result<Vec<PieceMove>, UciError> {
+  let mut known = true;
+  let mut moves = Vec::with_capacity(args.len());
+  for &arg in args {
+    let parsed = if known {
+      PieceMove::from_uci(arg, &board)
+    } else {
+      PieceMove::from_str(arg)
+    }
+    .map_err(|e| UciError::from_move(arg, e))?;
+
+    if known {
+      let (generated, count) = generate_moves(&board);
+      let played = generated[..count].iter().find(|candidate| {
+        candidate.from_square() == parsed.from_square()
+          && candidate.to_square() == parsed.to_square()
+          && candidate.promotion_type() == parsed.promotion_type()
+      });
+      known = played.is_some_and(|played| board.move_piece(played).is_some());
     }
+    moves.push(parsed);
+  }
+  Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lumifox_chess::model::clock::Clock;
+
+  fn parse(line: &str) -> GuiToEngineCommand {
+    line.parse().unwrap()
+  }
+
+  #[test]
+  fn test_skips_junk_before_command() {
+    // The example from the UCI specification
+    assert!(matches!(
+      parse("joho debug on"),
+      GuiToEngineCommand::Debug { on: true }
+    ));
+    assert!(matches!(
+      parse("1 2 3 isready"),
+      GuiToEngineCommand::IsReady
+    ));
+    assert!(matches!(parse("  \t uci  "), GuiToEngineCommand::Uci));
+  }
+
+  #[test]
+  fn test_unknown_command_is_an_error() {
+    assert!("joho".parse::<GuiToEngineCommand>().is_err());
+    assert!("".parse::<GuiToEngineCommand>().is_err());
+    assert!("debug maybe".parse::<GuiToEngineCommand>().is_err());
+  }
+
+  #[test]
+  fn test_go_skips_unknown_parameters() {
+    let GuiToEngineCommand::Go(params) = parse("go joho depth 6 wtime 1000 hiya infinite") else {
+      panic!("expected go");
+    };
+    assert_eq!(params.depth, Some(6));
+    assert_eq!(params.clock.white_time, Some(1000));
+    assert!(params.infinite);
+  }
+
+  #[test]
+  fn test_searchmoves_end_at_first_non_move() {
+    let GuiToEngineCommand::Go(params) = parse("go searchmoves e2e4 d2d4 joho depth 3") else {
+      panic!("expected go");
+    };
+    let moves: Vec<String> = params
+      .searchmoves
+      .unwrap()
+      .iter()
+      .map(|m| m.to_string())
+      .collect();
+    assert_eq!(moves, ["e2e4", "d2d4"]);
+    assert_eq!(params.depth, Some(3));
+  }
+
+  #[test]
+  fn test_position_skips_junk() {
+    let GuiToEngineCommand::Position { position, moves } =
+      parse("position joho startpos hiya moves e2e4 e7e5")
+    else {
+      panic!("expected position");
+    };
+    assert!(matches!(*position, PositionType::StartPos { .. }));
+    assert_eq!(moves.len(), 2);
+
+    let GuiToEngineCommand::Position { position, moves } =
+      parse("position fen 8/8/8/8/8/8/8/K6k w - - 0 1 joho moves a1a2")
+    else {
+      panic!("expected position");
+    };
+    assert!(matches!(*position, PositionType::Fen { .. }));
+    assert_eq!(moves.len(), 1);
   }
 
-  Ok(GuiToEngineCommand::Go {
-    searchmoves,
-    ponder,
-    wtime,
-    btime,
-    winc,
-    binc,
-    movestogo,
-    depth,
-    nodes,
-    mate,
-    movetime,
-    infinite,
-  })
+  #[test]
+  fn test_setoption_and_register_skip_junk() {
+    let GuiToEngineCommand::SetOption { name, value } = parse("setoption joho name Hash value 32")
+    else {
+      panic!("expected setoption");
+    };
+    assert_eq!(name, "Hash");
+    assert_eq!(value.as_deref(), Some("32"));
+
+    assert!(matches!(
+      parse("register joho later"),
+      GuiToEngineCommand::Register { later: true, .. }
+    ));
+  }
+
+  #[test]
+  fn test_go_fills_clock() {
+    let GuiToEngineCommand::Go(params) =
+      parse("go wtime 300000 btime 290000 winc 2000 binc 1000 movestogo 20")
+    else {
+      panic!("expected go");
+    };
+    assert_eq!(
+      params.clock,
+      Clock {
+        white_time: Some(300_000),
+        black_time: Some(290_000),
+        white_increment: 2_000,
+        black_increment: 1_000,
+        moves_to_go: Some(20),
+      }
+    );
+  }
 }
@@ -16,6 +16,7 @@
 // Module declarations
 pub mod engine_to_gui;
 pub mod error;
+pub mod go;
 pub mod gui_to_engine;
 
 // Re-exports for convenience
@@ -23,4 +24,5 @@ pub use engine_to_gui::{
   EngineToGuiCommand, InfoType, OptionType, ProtectionStatus, RegistrationStatus, ScoreBound,
   ScoreType,
 };
+pub use go::GoParams;
 pub use gui_to_engine::{GuiToEngineCommand, PositionType};